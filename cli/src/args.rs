@@ -69,6 +69,21 @@ pub enum Task {
     HelpMessage(String),
     OpenShell(ClientConfig),
     ExecOnce(ClientConfig, String),
+    /// run an import. no client config means that this is a dry run
+    Import(Option<ClientConfig>, Import),
+}
+
+#[derive(Debug)]
+pub enum Import {
+    Rdb {
+        rdb_path: String,
+        mapping_path: String,
+    },
+    Csv {
+        csv_path: String,
+        schema_path: String,
+        model: String,
+    },
 }
 
 enum TaskInner {
@@ -90,6 +105,24 @@ pub fn parse() -> CliResult<Task> {
         TaskInner::HelpMsg(msg) => return Ok(Task::HelpMessage(msg)),
         TaskInner::OpenShell(args) => args,
     };
+    let import = parse_import(&mut args)?;
+    let dry_run = match args.remove("--dry-run").as_deref() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(_) => return Err(CliError::ArgsErr("invalid value for --dry-run".into())),
+    };
+    match import {
+        Some(import) if dry_run => {
+            // we don't need to connect for a dry run
+            return Ok(Task::Import(None, import));
+        }
+        None if dry_run => {
+            return Err(CliError::ArgsErr(
+                "--dry-run can only be used with --import-rdb or --import-csv".into(),
+            ))
+        }
+        _ => {}
+    }
     let endpoint = match args.remove("--endpoint") {
         None => ClientConfigKind::Tcp("127.0.0.1".into(), 2003),
         Some(ep) => {
@@ -160,15 +193,67 @@ pub fn parse() -> CliResult<Task> {
     let eval = args.remove("--eval");
     if args.is_empty() {
         let client = ClientConfig::new(endpoint, username, password);
-        match eval {
-            Some(query) => Ok(Task::ExecOnce(client, query)),
-            None => Ok(Task::OpenShell(client)),
+        match (eval, import) {
+            (Some(_), Some(_)) => Err(CliError::ArgsErr(
+                "--eval can't be used with an import".into(),
+            )),
+            (Some(query), None) => Ok(Task::ExecOnce(client, query)),
+            (None, Some(import)) => Ok(Task::Import(Some(client), import)),
+            (None, None) => Ok(Task::OpenShell(client)),
         }
     } else {
         Err(CliError::ArgsErr(format!("found unknown arguments")))
     }
 }
 
+fn parse_import(args: &mut HashMap<String, String>) -> CliResult<Option<Import>> {
+    let rdb = match (args.remove("--import-rdb"), args.remove("--mapping")) {
+        (Some(rdb_path), Some(mapping_path)) => Some(Import::Rdb {
+            rdb_path,
+            mapping_path,
+        }),
+        (None, None) => None,
+        (Some(_), None) => {
+            return Err(CliError::ArgsErr(
+                "must provide a mapping file when importing a RDB file".into(),
+            ))
+        }
+        (None, Some(_)) => {
+            return Err(CliError::ArgsErr(
+                "--mapping can only be used with --import-rdb".into(),
+            ))
+        }
+    };
+    let csv = match (
+        args.remove("--import-csv"),
+        args.remove("--schema"),
+        args.remove("--model"),
+    ) {
+        (Some(csv_path), Some(schema_path), Some(model)) => Some(Import::Csv {
+            csv_path,
+            schema_path,
+            model,
+        }),
+        (None, None, None) => None,
+        (Some(_), _, _) => {
+            return Err(CliError::ArgsErr(
+                "must provide --schema and --model when importing a CSV file".into(),
+            ))
+        }
+        _ => {
+            return Err(CliError::ArgsErr(
+                "--schema and --model can only be used with --import-csv".into(),
+            ))
+        }
+    };
+    match (rdb, csv) {
+        (Some(_), Some(_)) => Err(CliError::ArgsErr(
+            "can only run one import at a time".into(),
+        )),
+        (rdb, csv) => Ok(rdb.or(csv)),
+    }
+}
+
 fn read_password(prompt: &str) -> Result<String, std::io::Error> {
     print!("{prompt}");
     io::stdout().flush()?;
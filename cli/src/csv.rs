@@ -0,0 +1,943 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    CSV import
+    ---
+    takes a relational schema (the `CREATE TABLE` subset of what `pg_dump --schema-only` or `mysqldump --no-data`
+    generate) and a CSV export with a header row. we generate the model from the table and then insert every row.
+    the entire file is validated before we touch the server, so a bad row doesn't leave a half imported model. the
+    file is streamed twice (once to validate and once to insert) instead of being loaded into memory
+*/
+
+use {
+    crate::{
+        error::{CliError, CliResult},
+        query::{IsConnection, Item},
+    },
+    skytable::Query,
+    std::{
+        fs::{self, File},
+        io::{BufRead, BufReader},
+    },
+};
+
+/// print progress after these many rows
+const PROGRESS_EVERY: usize = 1000;
+/// the number of bad values that we print before giving up
+const MAX_REPORTED_ERRORS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnKind {
+    Bool,
+    UInt,
+    SInt,
+    Float,
+    String,
+    Binary,
+}
+
+#[derive(Debug)]
+struct Column {
+    name: String,
+    kind: ColumnKind,
+    ty: &'static str,
+    nullable: bool,
+    primary: bool,
+}
+
+#[derive(Debug)]
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+}
+
+/// A converted CSV field. Nulls and booleans are sent inline
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Param(Item),
+}
+
+impl Value {
+    fn placeholder(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(true) => "true",
+            Self::Bool(false) => "false",
+            Self::Param(_) => "?",
+        }
+    }
+}
+
+pub struct CsvImport {
+    csv_path: String,
+    space: String,
+    model: String,
+    create_model: String,
+    table: Table,
+    rows: usize,
+}
+
+impl CsvImport {
+    pub fn load(csv_path: &str, schema_path: &str, model: &str) -> CliResult<Self> {
+        let Some((space, model)) = model.split_once('.') else {
+            return Err(err("the model must be fully qualified (`space.model`)"));
+        };
+        let mut tables = parse_schema(&fs::read_to_string(schema_path)?)?;
+        // prefer the table with the same name as the model
+        let table = match tables
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(model))
+        {
+            Some(i) => tables.swap_remove(i),
+            None if tables.len() == 1 => tables.remove(0),
+            None => return Err(err(format!("no table named `{model}` in the schema"))),
+        };
+        let create_model = create_model(space, model, &table);
+        let rows = validate_csv(BufReader::new(File::open(csv_path)?), &table)?;
+        Ok(Self {
+            csv_path: csv_path.into(),
+            space: space.into(),
+            model: model.into(),
+            create_model,
+            table,
+            rows,
+        })
+    }
+    /// Run the import. If no connection is given, this is a dry run and we only print what we would do
+    pub fn run<C: IsConnection>(&self, con: Option<&mut C>) -> CliResult<()> {
+        let create_space = format!("create space if not exists {}", self.space);
+        let Some(con) = con else {
+            println!("{create_space}");
+            println!("{}", self.create_model);
+            println!(
+                "would insert {} row(s) into {}.{}",
+                self.rows, self.space, self.model
+            );
+            return Ok(());
+        };
+        con.execute_query(Query::new(&create_space))?;
+        con.execute_query(Query::new(&self.create_model))?;
+        let mut rows = RowReader::new(BufReader::new(File::open(&self.csv_path)?), &self.table)?;
+        let mut inserted = 0;
+        while let Some((line, row)) = rows.next_row()? {
+            // every row was checked when we loaded the file, so this only fails if it was changed since
+            let row = row.map_err(|e| err(format!("line {line}: {}", e.join("; "))))?;
+            let fields: Vec<&str> = row.iter().map(Value::placeholder).collect();
+            let mut q = Query::new(&format!(
+                "insert into {}.{}({})",
+                self.space,
+                self.model,
+                fields.join(", ")
+            ));
+            row.iter().for_each(|v| {
+                if let Value::Param(p) = v {
+                    q.push_param(p.clone());
+                }
+            });
+            con.execute_query(q)?;
+            inserted += 1;
+            if inserted % PROGRESS_EVERY == 0 {
+                println!("inserted {inserted} of {} row(s)", self.rows);
+            }
+        }
+        println!(
+            "imported {inserted} row(s) into {}.{}",
+            self.space, self.model
+        );
+        Ok(())
+    }
+}
+
+fn err(e: impl ToString) -> CliError {
+    CliError::ImportError(e.to_string())
+}
+
+/*
+    schema
+*/
+
+/// The characters of `sql` (with their offsets) that aren't inside a quoted string or identifier. Quotes are
+/// escaped by doubling them, so `'it''s'` is simply read as two strings
+fn unquoted(sql: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    sql.char_indices().filter(move |&(_, c)| match quote {
+        Some(q) => {
+            if c == q {
+                quote = None;
+            }
+            false
+        }
+        None if matches!(c, '\'' | '"' | '`') => {
+            quote = Some(c);
+            false
+        }
+        None => true,
+    })
+}
+
+/// Remove `--` comments (but not a `--` inside a quoted default)
+fn strip_comments(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote = None;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '\'' | '"' | '`') => quote = Some(c),
+            None if (c == '-') & (chars.peek() == Some(&'-')) => {
+                // skip to the end of the line (which we keep)
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            None => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn parse_schema(src: &str) -> CliResult<Vec<Table>> {
+    let src = strip_comments(src);
+    let lower = src.to_ascii_lowercase();
+    let mut tables = vec![];
+    let mut cursor = 0;
+    while let Some(start) = lower[cursor..].find("create table") {
+        let start = cursor + start + "create table".len();
+        let Some(open) = src[start..].find('(').map(|i| start + i) else {
+            return Err(err("bad CREATE TABLE statement"));
+        };
+        // find the matching paren
+        let mut depth = 0;
+        let mut close = None;
+        for (i, c) in unquoted(&src[open..]) {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let Some(close) = close else {
+            return Err(err("unterminated CREATE TABLE statement"));
+        };
+        let mut name = src[start..open].trim();
+        if name.to_ascii_lowercase().starts_with("if not exists") {
+            name = name["if not exists".len()..].trim();
+        }
+        // drop the schema (`public.users`) and any quotes
+        let name = unquote(name.rsplit('.').next().unwrap());
+        tables.push(parse_table(name, &src[open + 1..close])?);
+        cursor = close;
+    }
+    if tables.is_empty() {
+        return Err(err("no CREATE TABLE statements in the schema"));
+    }
+    Ok(tables)
+}
+
+fn parse_table(name: &str, body: &str) -> CliResult<Table> {
+    let mut columns = vec![];
+    let mut primary_key = None;
+    for item in split_top_level(body) {
+        let item = item.trim();
+        if item.is_empty() {
+            continue;
+        }
+        let lower = item.to_ascii_lowercase();
+        if let Some(pk) = lower.find("primary key") {
+            if lower.starts_with("primary key") || lower.starts_with("constraint") {
+                // table constraint: `[CONSTRAINT name] PRIMARY KEY (col)`
+                let cols = &item[pk + "primary key".len()..];
+                let cols = cols.trim().trim_start_matches('(').trim_end_matches(')');
+                if cols.contains(',') {
+                    return Err(err(format!(
+                        "table `{name}` has a composite primary key, which is not supported"
+                    )));
+                }
+                primary_key = Some(unquote(cols.trim()).to_owned());
+                continue;
+            }
+        }
+        let first_word = lower.split(|c: char| c.is_whitespace() | (c == '(')).next();
+        if matches!(
+            first_word,
+            Some("constraint" | "unique" | "foreign" | "check" | "key" | "index" | "exclude")
+        ) {
+            // we don't have any of these
+            continue;
+        }
+        let mut parts = item.split_ascii_whitespace();
+        let (Some(col_name), Some(sql_ty)) = (parts.next(), parts.next()) else {
+            return Err(err(format!("bad column definition `{item}`")));
+        };
+        let rest = parts.collect::<Vec<_>>().join(" ").to_ascii_lowercase();
+        let (kind, ty) = map_type(sql_ty, &rest)?;
+        let primary = rest.contains("primary key");
+        columns.push(Column {
+            name: unquote(col_name).to_owned(),
+            kind,
+            ty,
+            nullable: !(primary | rest.contains("not null")),
+            primary,
+        });
+    }
+    if let Some(pk) = primary_key {
+        match columns
+            .iter_mut()
+            .find(|c| c.name.eq_ignore_ascii_case(&pk))
+        {
+            Some(col) => {
+                col.primary = true;
+                col.nullable = false;
+            }
+            None => return Err(err(format!("unknown primary key column `{pk}`"))),
+        }
+    }
+    match columns.iter().filter(|c| c.primary).count() {
+        1 => Ok(Table {
+            name: name.into(),
+            columns,
+        }),
+        0 => Err(err(format!("table `{name}` has no primary key"))),
+        _ => Err(err(format!("table `{name}` has more than one primary key"))),
+    }
+}
+
+/// Split on commas that aren't nested inside parentheses
+fn split_top_level(body: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in unquoted(body) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&body[start..]);
+    items
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'))
+}
+
+/// Map a SQL type to a Skytable type. `rest` is the remainder of the column definition (lowercased)
+fn map_type(sql_ty: &str, rest: &str) -> CliResult<(ColumnKind, &'static str)> {
+    let sql_ty = sql_ty.to_ascii_lowercase();
+    let base = sql_ty.split('(').next().unwrap();
+    let unsigned = rest.contains("unsigned");
+    let int = |signed: &'static str, unsigned_ty: &'static str| {
+        if unsigned {
+            (ColumnKind::UInt, unsigned_ty)
+        } else {
+            (ColumnKind::SInt, signed)
+        }
+    };
+    let mapped = match base {
+        "bool" | "boolean" => (ColumnKind::Bool, "bool"),
+        "tinyint" => int("sint8", "uint8"),
+        "smallint" | "int2" | "smallserial" => int("sint16", "uint16"),
+        "int" | "integer" | "int4" | "mediumint" | "serial" => int("sint32", "uint32"),
+        "bigint" | "int8" | "bigserial" => int("sint64", "uint64"),
+        "real" | "float4" => (ColumnKind::Float, "float32"),
+        "float" | "float8" | "double" => (ColumnKind::Float, "float64"),
+        "numeric" | "decimal" | "dec" | "fixed" | "money" => {
+            return Err(err(format!(
+                "column type `{sql_ty}` can't be stored exactly. change it to a float or text type in the \
+                 schema if that is acceptable"
+            )))
+        }
+        "bytea" | "blob" | "tinyblob" | "mediumblob" | "longblob" | "binary" | "varbinary" => {
+            (ColumnKind::Binary, "binary")
+        }
+        "text" | "varchar" | "char" | "character" | "tinytext" | "mediumtext" | "longtext"
+        | "uuid" | "date" | "time" | "timestamp" | "timestamptz" | "datetime" | "json"
+        | "jsonb" | "enum" | "citext" | "inet" => (ColumnKind::String, "string"),
+        _ => return Err(err(format!("unsupported column type `{sql_ty}`"))),
+    };
+    Ok(mapped)
+}
+
+fn create_model(space: &str, model: &str, table: &Table) -> String {
+    let fields: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| {
+            let prefix = if c.primary {
+                "primary "
+            } else if c.nullable {
+                "null "
+            } else {
+                ""
+            };
+            format!("{prefix}{}: {}", c.name, c.ty)
+        })
+        .collect();
+    format!("create model {space}.{model}({})", fields.join(", "))
+}
+
+/*
+    CSV
+*/
+
+/// The fields of a CSV record, with a flag for every field that tells if it was quoted
+type Record = Vec<(String, bool)>;
+/// A converted row, or all the problems with it
+type Row = Result<Vec<Value>, Vec<String>>;
+
+/// Reads RFC 4180 CSV one record at a time
+struct CsvReader<R> {
+    src: R,
+    line: String,
+    /// the number of lines read so far
+    lines: usize,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    fn new(src: R) -> Self {
+        Self {
+            src,
+            line: String::new(),
+            lines: 0,
+        }
+    }
+    /// Returns the next record and the line that it starts on
+    fn next_record(&mut self) -> CliResult<Option<(usize, Record)>> {
+        let start = self.lines + 1;
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        loop {
+            self.line.clear();
+            if self.src.read_line(&mut self.line)? == 0 {
+                if in_quotes {
+                    return Err(err("unterminated quoted field in CSV"));
+                }
+                if field.is_empty() & record.is_empty() & !quoted {
+                    return Ok(None);
+                }
+                // no newline at the end of the file
+                record.push((field, quoted));
+                return Ok(Some((start, record)));
+            }
+            self.lines += 1;
+            let mut chars = self.line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if in_quotes => {
+                        if chars.peek() == Some(&'"') {
+                            // escaped quote
+                            chars.next();
+                            field.push('"');
+                        } else {
+                            in_quotes = false;
+                        }
+                    }
+                    '"' if field.is_empty() && !quoted => {
+                        in_quotes = true;
+                        quoted = true;
+                    }
+                    ',' if !in_quotes => {
+                        record.push((core::mem::take(&mut field), quoted));
+                        quoted = false;
+                    }
+                    '\r' if !in_quotes => {}
+                    '\n' if !in_quotes => {
+                        record.push((field, quoted));
+                        return Ok(Some((start, record)));
+                    }
+                    c => field.push(c),
+                }
+            }
+        }
+    }
+}
+
+/// Reads the rows of a CSV file (with a header row) for a table
+struct RowReader<'a, R> {
+    csv: CsvReader<R>,
+    table: &'a Table,
+    header_len: usize,
+    /// the position of every column in the CSV
+    positions: Vec<Option<usize>>,
+}
+
+impl<'a, R: BufRead> RowReader<'a, R> {
+    fn new(src: R, table: &'a Table) -> CliResult<Self> {
+        let mut csv = CsvReader::new(src);
+        let Some((_, header)) = csv.next_record()? else {
+            return Err(err("the CSV file is empty"));
+        };
+        let mut positions = vec![];
+        for col in table.columns.iter() {
+            let position = header
+                .iter()
+                .position(|(name, _)| name.trim().eq_ignore_ascii_case(&col.name));
+            if position.is_none() && !col.nullable {
+                return Err(err(format!(
+                    "the CSV file has no values for the non-null column `{}`",
+                    col.name
+                )));
+            }
+            positions.push(position);
+        }
+        Ok(Self {
+            csv,
+            table,
+            header_len: header.len(),
+            positions,
+        })
+    }
+    /// Returns the next row and the line that it starts on. If any of the values are bad, we get all the problems
+    /// with the row instead
+    fn next_row(&mut self) -> CliResult<Option<(usize, Row)>> {
+        loop {
+            let Some((line, record)) = self.csv.next_record()? else {
+                return Ok(None);
+            };
+            if (record.len() == 1) & (record[0] == (String::new(), false)) {
+                // blank line
+                continue;
+            }
+            if record.len() != self.header_len {
+                let e = format!(
+                    "expected {} fields but found {}",
+                    self.header_len,
+                    record.len()
+                );
+                return Ok(Some((line, Err(vec![e]))));
+            }
+            let mut row = vec![];
+            let mut errors = vec![];
+            for (col, position) in self.table.columns.iter().zip(self.positions.iter()) {
+                let value = match position {
+                    Some(p) => convert(col, &record[*p].0, record[*p].1),
+                    None => Ok(Value::Null),
+                };
+                match value {
+                    Ok(v) => row.push(v),
+                    Err(e) => errors.push(e),
+                }
+            }
+            let row = if errors.is_empty() {
+                Ok(row)
+            } else {
+                Err(errors)
+            };
+            return Ok(Some((line, row)));
+        }
+    }
+}
+
+/// Check every row in the CSV and return the number of rows
+fn validate_csv(src: impl BufRead, table: &Table) -> CliResult<usize> {
+    let mut rows = RowReader::new(src, table)?;
+    let mut count = 0;
+    let mut errors = 0;
+    while let Some((line, row)) = rows.next_row()? {
+        match row {
+            Ok(_) => count += 1,
+            Err(row_errors) => {
+                for e in row_errors {
+                    if errors < MAX_REPORTED_ERRORS {
+                        eprintln!("[skysh error]: line {line}: {e}");
+                    }
+                    errors += 1;
+                }
+            }
+        }
+    }
+    if errors == 0 {
+        Ok(count)
+    } else {
+        Err(err(format!("found {errors} bad value(s) in the CSV file")))
+    }
+}
+
+/// Convert a CSV field. An empty unquoted field (`COPY ... CSV`) or `\N` (`mysqldump`) is a null
+fn convert(col: &Column, value: &str, quoted: bool) -> Result<Value, String> {
+    let is_null = (value.is_empty() & !quoted) | (value == "\\N");
+    match (is_null, col.nullable, col.kind) {
+        (true, true, _) => return Ok(Value::Null),
+        (true, false, ColumnKind::String) if value.is_empty() => {
+            return Ok(Value::Param(Item::String(String::new())))
+        }
+        (true, false, _) => return Err(format!("null value for non-null column `{}`", col.name)),
+        (false, _, _) => {}
+    }
+    let bad = || format!("bad value `{value}` for column `{}`", col.name);
+    let item = match col.kind {
+        ColumnKind::Bool => match value.to_ascii_lowercase().as_str() {
+            "t" | "true" | "1" | "y" | "yes" => return Ok(Value::Bool(true)),
+            "f" | "false" | "0" | "n" | "no" => return Ok(Value::Bool(false)),
+            _ => return Err(bad()),
+        },
+        ColumnKind::UInt => Item::UInt(value.trim().parse().map_err(|_| bad())?),
+        ColumnKind::SInt => Item::SInt(value.trim().parse().map_err(|_| bad())?),
+        ColumnKind::Float => Item::Float(value.trim().parse().map_err(|_| bad())?),
+        ColumnKind::String => Item::String(value.to_owned()),
+        ColumnKind::Binary => match value.strip_prefix("\\x") {
+            // postgres' hex format
+            Some(hex) => Item::Bin(decode_hex(hex).ok_or_else(bad)?),
+            None => Item::Bin(value.as_bytes().to_vec()),
+        },
+    };
+    Ok(Value::Param(item))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(src: &str) -> CliResult<Vec<Record>> {
+        let mut csv = CsvReader::new(src.as_bytes());
+        let mut records = vec![];
+        while let Some((_, record)) = csv.next_record()? {
+            records.push(record);
+        }
+        Ok(records)
+    }
+    fn fields(fields: &[(&str, bool)]) -> Record {
+        fields.iter().map(|(f, q)| (f.to_string(), *q)).collect()
+    }
+    fn table(schema: &str) -> Table {
+        let mut tables = parse_schema(schema).unwrap();
+        assert_eq!(tables.len(), 1);
+        tables.remove(0)
+    }
+    fn columns(table: &Table) -> Vec<(&str, &str, bool, bool)> {
+        table
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.ty, c.nullable, c.primary))
+            .collect()
+    }
+    fn rows(csv: &str, table: &Table) -> Vec<(usize, Row)> {
+        let mut reader = RowReader::new(csv.as_bytes(), table).unwrap();
+        let mut rows = vec![];
+        while let Some(row) = reader.next_row().unwrap() {
+            rows.push(row);
+        }
+        rows
+    }
+
+    /*
+        CSV
+    */
+
+    #[test]
+    fn csv_plain() {
+        assert_eq!(
+            records("a,b,c\n1,,3\n").unwrap(),
+            [
+                fields(&[("a", false), ("b", false), ("c", false)]),
+                fields(&[("1", false), ("", false), ("3", false)]),
+            ]
+        );
+        // no newline at the end
+        assert_eq!(
+            records("a,b\n1,2").unwrap(),
+            [
+                fields(&[("a", false), ("b", false)]),
+                fields(&[("1", false), ("2", false)])
+            ]
+        );
+        assert!(records("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(
+            records("\"a,b\",\"\",c\n\"multi\nline\",\"x\"\n").unwrap(),
+            [
+                fields(&[("a,b", true), ("", true), ("c", false)]),
+                fields(&[("multi\nline", true), ("x", true)]),
+            ]
+        );
+        // a quote in the middle of an unquoted field is just a character
+        assert_eq!(records("ab\"c\n").unwrap(), [fields(&[("ab\"c", false)])]);
+        assert!(records("a,\"b\n").is_err());
+    }
+
+    #[test]
+    fn csv_escaped_quotes() {
+        assert_eq!(
+            records("\"say \"\"hi\"\"\",\"\"\"\"\n").unwrap(),
+            [fields(&[("say \"hi\"", true), ("\"", true)])]
+        );
+    }
+
+    #[test]
+    fn csv_crlf() {
+        assert_eq!(
+            records("a,b\r\n1,\"x\r\ny\"\r\n").unwrap(),
+            [
+                fields(&[("a", false), ("b", false)]),
+                fields(&[("1", false), ("x\r\ny", true)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_record_lines() {
+        let mut csv = CsvReader::new(&b"h\n\"a\nb\"\nc\n"[..]);
+        let lines: Vec<usize> = core::iter::from_fn(|| csv.next_record().unwrap())
+            .map(|(line, _)| line)
+            .collect();
+        assert_eq!(lines, [1, 2, 4]);
+    }
+
+    /*
+        schema
+    */
+
+    #[test]
+    fn schema_postgres() {
+        let schema = "
+            -- pg_dump output
+            CREATE TABLE public.\"users\" (
+                id bigint NOT NULL, -- the user's id
+                name character varying(64) NOT NULL DEFAULT 'it''s -- not a comment',
+                email text,
+                active boolean DEFAULT true,
+                score double precision,
+                avatar bytea,
+                CONSTRAINT users_pkey PRIMARY KEY (id),
+                CONSTRAINT users_email_key UNIQUE (email),
+                CONSTRAINT users_name_check CHECK (name <> ',' AND name <> ')')
+            );
+        ";
+        let table = table(schema);
+        assert_eq!(table.name, "users");
+        assert_eq!(
+            columns(&table),
+            [
+                ("id", "sint64", false, true),
+                ("name", "string", false, false),
+                ("email", "string", true, false),
+                ("active", "bool", true, false),
+                ("score", "float64", true, false),
+                ("avatar", "binary", true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_mysql() {
+        let schema = "
+            CREATE TABLE IF NOT EXISTS `orders` (
+              `id` int unsigned NOT NULL AUTO_INCREMENT,
+              `user_id` bigint NOT NULL,
+              `note` varchar(255) DEFAULT '(none), see --help',
+              PRIMARY KEY (`id`),
+              KEY `user_id` (`user_id`),
+              FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+            ) ENGINE=InnoDB;
+        ";
+        let table = table(schema);
+        assert_eq!(table.name, "orders");
+        assert_eq!(
+            columns(&table),
+            [
+                ("id", "uint32", false, true),
+                ("user_id", "sint64", false, false),
+                ("note", "string", true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_inline_primary_key() {
+        let tables = parse_schema(
+            "create table a (id text primary key, x int);\ncreate table b (k int primary key);",
+        )
+        .unwrap();
+        assert_eq!(
+            tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(
+            columns(&tables[0]),
+            [("id", "string", false, true), ("x", "sint32", true, false)]
+        );
+    }
+
+    #[test]
+    fn schema_bad() {
+        for schema in [
+            "",
+            "-- create table t (id int primary key)",
+            "create table t (id int primary key",
+            "create table t (id int, x int)",
+            "create table t (id int primary key, x int primary key)",
+            "create table t (id int, x int, primary key (id, x))",
+            "create table t (id int, primary key (nope))",
+            "create table t (id int primary key, x)",
+        ] {
+            assert!(parse_schema(schema).is_err(), "{schema}");
+        }
+    }
+
+    #[test]
+    fn type_mapping() {
+        for (sql_ty, rest, ty) in [
+            ("BOOLEAN", "", "bool"),
+            ("tinyint(1)", "", "sint8"),
+            ("tinyint", "unsigned", "uint8"),
+            ("smallserial", "", "sint16"),
+            ("INTEGER", "not null", "sint32"),
+            ("int", "unsigned not null", "uint32"),
+            ("bigserial", "", "sint64"),
+            ("bigint", "unsigned", "uint64"),
+            ("real", "", "float32"),
+            ("double", "", "float64"),
+            ("longblob", "", "binary"),
+            ("varchar(10)", "", "string"),
+            ("timestamptz", "", "string"),
+            ("jsonb", "", "string"),
+        ] {
+            assert_eq!(map_type(sql_ty, rest).unwrap().1, ty, "{sql_ty} {rest}");
+        }
+    }
+
+    #[test]
+    fn type_mapping_rejects_inexact_and_unknown() {
+        for sql_ty in ["numeric(10,2)", "DECIMAL", "money", "geometry", "int[]"] {
+            assert!(map_type(sql_ty, "").is_err(), "{sql_ty}");
+        }
+    }
+
+    /*
+        rows
+    */
+
+    #[test]
+    fn rows_convert() {
+        let table = table(
+            "create table t (id int primary key, name text not null, ok bool, data bytea, score real)",
+        );
+        let csv = "ID,name,ok,data,score\n\
+            1,\"sayan\",t,\\x6869,1.5\n\
+            \n\
+            2,,\\N,,\n\
+            3,\"\",no,raw,-2\n";
+        assert_eq!(
+            rows(csv, &table),
+            [
+                (
+                    2,
+                    Ok(vec![
+                        Value::Param(Item::SInt(1)),
+                        Value::Param(Item::String("sayan".into())),
+                        Value::Bool(true),
+                        Value::Param(Item::Bin(b"hi".to_vec())),
+                        Value::Param(Item::Float(1.5)),
+                    ])
+                ),
+                (
+                    4,
+                    Ok(vec![
+                        Value::Param(Item::SInt(2)),
+                        // an empty string for a non-null string column
+                        Value::Param(Item::String(String::new())),
+                        Value::Null,
+                        Value::Null,
+                        Value::Null,
+                    ])
+                ),
+                (
+                    5,
+                    Ok(vec![
+                        Value::Param(Item::SInt(3)),
+                        Value::Param(Item::String(String::new())),
+                        Value::Bool(false),
+                        Value::Param(Item::Bin(b"raw".to_vec())),
+                        Value::Param(Item::Float(-2.0)),
+                    ])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_missing_columns() {
+        let table = table("create table t (id int primary key, note text)");
+        // nullable columns can be left out
+        assert_eq!(
+            rows("id\n1\n", &table),
+            [(2, Ok(vec![Value::Param(Item::SInt(1)), Value::Null]))]
+        );
+        assert!(RowReader::new(&b"note\nx\n"[..], &table).is_err());
+        assert!(RowReader::new(&b""[..], &table).is_err());
+    }
+
+    #[test]
+    fn rows_bad() {
+        let table = table("create table t (id int primary key, ok bool not null)");
+        let csv = "id,ok\n1,maybe\nx,\n3\n4,true\n";
+        let rows = rows(csv, &table);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].1.as_ref().unwrap_err().len(), 1);
+        assert_eq!(rows[1].1.as_ref().unwrap_err().len(), 2);
+        assert_eq!(
+            rows[2].1,
+            Err(vec!["expected 2 fields but found 1".to_owned()])
+        );
+        assert!(rows[3].1.is_ok());
+        // 4 bad values, so nothing gets imported
+        assert!(validate_csv(csv.as_bytes(), &table).is_err());
+        assert_eq!(
+            validate_csv(&b"id,ok\n1,true\n\n2,f"[..], &table).unwrap(),
+            2
+        );
+    }
+}
@@ -32,12 +32,15 @@ macro_rules! fatal {
 }
 
 mod args;
+mod csv;
 mod error;
+mod migrate;
 mod query;
+mod rdb;
 mod repl;
 mod resp;
 
-use args::Task;
+use args::{Import, Task};
 
 fn main() {
     match run() {
@@ -60,6 +63,43 @@ fn run() -> error::CliResult<()> {
             )??;
             resp::format_response(resp, false, false);
         }
+        Task::Import(
+            cfg,
+            Import::Rdb {
+                rdb_path,
+                mapping_path,
+            },
+        ) => {
+            let mapping = migrate::Mapping::load(&mapping_path)?;
+            match cfg {
+                None => migrate::import_rdb::<skytable::Connection>(None, &rdb_path, &mapping)?,
+                Some(cfg) => query::connect(
+                    cfg,
+                    false,
+                    |mut c| migrate::import_rdb(Some(&mut c), &rdb_path, &mapping),
+                    |mut c| migrate::import_rdb(Some(&mut c), &rdb_path, &mapping),
+                )?,
+            }
+        }
+        Task::Import(
+            cfg,
+            Import::Csv {
+                csv_path,
+                schema_path,
+                model,
+            },
+        ) => {
+            let import = csv::CsvImport::load(&csv_path, &schema_path, &model)?;
+            match cfg {
+                None => import.run::<skytable::Connection>(None)?,
+                Some(cfg) => query::connect(
+                    cfg,
+                    false,
+                    |mut c| import.run(Some(&mut c)),
+                    |mut c| import.run(Some(&mut c)),
+                )?,
+            }
+        }
     }
     Ok(())
 }
@@ -0,0 +1,272 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    redis migration
+    ---
+    the mapping file has one rule per line (`#` starts a comment):
+
+    ```
+    <kind> <key pattern> <space>.<model> [<primary key field>]
+    ```
+
+    - `string`/`binary`: string keys go into a model with a primary key and a string (or binary) value field,
+    as `(key, value)`
+    - `list`: list keys go into a model with a primary key and a `list { type: string }` field, as
+    `(key, [values])`
+    - `hash`: hash keys become a row. the key goes into the given primary key field and every hash field goes into
+    the field of the same name
+
+    the pattern either matches a key exactly or, if it ends with `*`, matches by prefix. the first matching rule wins.
+    the models must already exist
+*/
+
+use {
+    crate::{
+        error::{CliError, CliResult},
+        query::IsConnection,
+        rdb::{RdbReader, RdbValue},
+    },
+    skytable::Query,
+    std::{collections::BTreeMap, fs},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum RuleKind {
+    String,
+    Binary,
+    List,
+    Hash,
+}
+
+#[derive(Debug)]
+struct Rule {
+    kind: RuleKind,
+    pattern: String,
+    model: String,
+    pk: Option<String>,
+}
+
+impl Rule {
+    fn matches(&self, key: &[u8]) -> bool {
+        match self.pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix.as_bytes()),
+            None => key == self.pattern.as_bytes(),
+        }
+    }
+    fn accepts(&self, value: &RdbValue) -> bool {
+        matches!(
+            (self.kind, value),
+            (RuleKind::String | RuleKind::Binary, RdbValue::String(_))
+                | (RuleKind::List, RdbValue::List(_))
+                | (RuleKind::Hash, RdbValue::Hash(_))
+        )
+    }
+}
+
+pub struct Mapping {
+    rules: Vec<Rule>,
+}
+
+impl Mapping {
+    pub fn load(path: &str) -> CliResult<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+    fn parse(src: &str) -> CliResult<Self> {
+        let mut rules = vec![];
+        for (i, line) in src.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad_rule = |e: &str| CliError::ImportError(format!("mapping line {}: {e}", i + 1));
+            let parts: Vec<&str> = line.split_ascii_whitespace().collect();
+            let kind = match parts[0] {
+                "string" => RuleKind::String,
+                "binary" => RuleKind::Binary,
+                "list" => RuleKind::List,
+                "hash" => RuleKind::Hash,
+                kind => return Err(bad_rule(&format!("unknown kind `{kind}`"))),
+            };
+            let pk = match (kind, parts.len()) {
+                (RuleKind::Hash, 4) => Some(parts[3].to_owned()),
+                (RuleKind::Hash, _) => {
+                    return Err(bad_rule(
+                        "expected `hash <pattern> <space>.<model> <primary key field>`",
+                    ))
+                }
+                (_, 3) => None,
+                (_, _) => return Err(bad_rule("expected `<kind> <pattern> <space>.<model>`")),
+            };
+            if parts[2].split('.').count() != 2 {
+                return Err(bad_rule(
+                    "the model must be fully qualified (`space.model`)",
+                ));
+            }
+            rules.push(Rule {
+                kind,
+                pattern: parts[1].to_owned(),
+                model: parts[2].to_owned(),
+                pk,
+            });
+        }
+        if rules.is_empty() {
+            return Err(CliError::ImportError("the mapping has no rules".into()));
+        }
+        Ok(Self { rules })
+    }
+    fn find(&self, key: &[u8]) -> Option<(usize, &Rule)> {
+        self.rules.iter().enumerate().find(|(_, r)| r.matches(key))
+    }
+}
+
+#[derive(Default)]
+struct Report {
+    keys: u64,
+    /// keys imported by each rule
+    imported: Vec<u64>,
+    unmatched: BTreeMap<&'static str, u64>,
+    type_mismatch: u64,
+    not_utf8: u64,
+    expiry_dropped: u64,
+    failed: u64,
+}
+
+impl Report {
+    fn print(&self, mapping: &Mapping, dry_run: bool) {
+        let verb = if dry_run { "would import" } else { "imported" };
+        println!("read {} key(s)", self.keys);
+        for (rule, imported) in mapping.rules.iter().zip(self.imported.iter()) {
+            println!(
+                "  {:?} `{}` -> {}: {verb} {imported} key(s)",
+                rule.kind, rule.pattern, rule.model
+            );
+        }
+        for (ty, count) in self.unmatched.iter() {
+            println!("  skipped {count} {ty} key(s) (no matching rule)");
+        }
+        if self.type_mismatch != 0 {
+            println!(
+                "  skipped {} key(s) (the rule does not accept the key's type)",
+                self.type_mismatch
+            );
+        }
+        if self.not_utf8 != 0 {
+            println!("  skipped {} key(s) (not valid UTF-8)", self.not_utf8);
+        }
+        if self.expiry_dropped != 0 {
+            println!(
+                "  note: {} key(s) had an expiry, which was not carried over",
+                self.expiry_dropped
+            );
+        }
+        if self.failed != 0 {
+            println!("  failed to import {} key(s)", self.failed);
+        }
+    }
+}
+
+/// Import a RDB dump. If no connection is given, this is a dry run and we only print a report
+pub fn import_rdb<C: IsConnection>(
+    con: Option<&mut C>,
+    rdb_path: &str,
+    mapping: &Mapping,
+) -> CliResult<()> {
+    let dry_run = con.is_none();
+    let mut con = con;
+    let mut rdb = RdbReader::open(rdb_path)?;
+    let mut report = Report {
+        imported: vec![0; mapping.rules.len()],
+        ..Default::default()
+    };
+    while let Some(entry) = rdb.next_entry()? {
+        report.keys += 1;
+        let Some((rule_id, rule)) = mapping.find(&entry.key) else {
+            *report.unmatched.entry(entry.value.type_name()).or_default() += 1;
+            continue;
+        };
+        if !rule.accepts(&entry.value) {
+            report.type_mismatch += 1;
+            continue;
+        }
+        let Some(q) = prepare_insert(rule, entry.key, entry.value) else {
+            report.not_utf8 += 1;
+            continue;
+        };
+        if let Some(con) = con.as_mut() {
+            if let Err(e) = con.execute_query(q) {
+                eprintln!("[skysh error]: failed to import key. {e}");
+                report.failed += 1;
+                continue;
+            }
+        }
+        report.imported[rule_id] += 1;
+        report.expiry_dropped += entry.expires as u64;
+    }
+    report.print(mapping, dry_run);
+    Ok(())
+}
+
+fn prepare_insert(rule: &Rule, key: Vec<u8>, value: RdbValue) -> Option<Query> {
+    let key = String::from_utf8(key).ok()?;
+    let q = match value {
+        RdbValue::String(v) => {
+            let mut q = Query::new(&format!("insert into {}(?, ?)", rule.model));
+            q.push_param(key);
+            if rule.kind == RuleKind::Binary {
+                q.push_param(v.as_slice());
+            } else {
+                q.push_param(String::from_utf8(v).ok()?);
+            }
+            q
+        }
+        RdbValue::List(items) => {
+            let params = vec!["?"; items.len()].join(", ");
+            let mut q = Query::new(&format!("insert into {}(?, [{params}])", rule.model));
+            q.push_param(key);
+            for item in items {
+                q.push_param(String::from_utf8(item).ok()?);
+            }
+            q
+        }
+        RdbValue::Hash(pairs) => {
+            let mut fields = format!("{}: ?", rule.pk.as_ref().unwrap());
+            let mut values = vec![];
+            for (field, value) in pairs {
+                fields.push_str(&format!(", {}: ?", String::from_utf8(field).ok()?));
+                values.push(String::from_utf8(value).ok()?);
+            }
+            let mut q = Query::new(&format!("insert into {} {{ {fields} }}", rule.model));
+            q.push_param(key);
+            for value in values {
+                q.push_param(value);
+            }
+            q
+        }
+        RdbValue::Unsupported(_) => return None,
+    };
+    Some(q)
+}
@@ -81,8 +81,8 @@ impl IsConnection for ConnectionTls {
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum Item {
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item {
     UInt(u64),
     SInt(i64),
     Float(f64),
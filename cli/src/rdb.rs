@@ -0,0 +1,1054 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    redis RDB reader
+    ---
+    this only understands the parts of the format that we need to migrate strings, lists and hashes (including
+    their compact ziplist/listpack encodings). everything else is skipped (when possible) and reported as
+    unsupported. lengths come from the file, so we never allocate a buffer for a length up front: a corrupt length
+    runs into the end of the file (and fails) instead
+*/
+
+use {
+    crate::error::{CliError, CliResult},
+    std::{
+        fs::File,
+        io::{BufReader, Read},
+    },
+};
+
+const MAGIC: &[u8] = b"REDIS";
+
+/*
+    opcodes
+*/
+
+const OP_FUNCTION2: u8 = 0xF5;
+/// functions from the 7.0 release candidates (redis itself refuses to load these)
+const OP_FUNCTION_PRE_GA: u8 = 0xF6;
+const OP_MODULE_AUX: u8 = 0xF7;
+const OP_IDLE: u8 = 0xF8;
+const OP_FREQ: u8 = 0xF9;
+const OP_AUX: u8 = 0xFA;
+const OP_RESIZEDB: u8 = 0xFB;
+const OP_EXPIRETIME_MS: u8 = 0xFC;
+const OP_EXPIRETIME: u8 = 0xFD;
+const OP_SELECTDB: u8 = 0xFE;
+const OP_EOF: u8 = 0xFF;
+
+/*
+    value types
+*/
+
+const TY_STRING: u8 = 0;
+const TY_LIST: u8 = 1;
+const TY_SET: u8 = 2;
+const TY_ZSET: u8 = 3;
+const TY_HASH: u8 = 4;
+const TY_ZSET_2: u8 = 5;
+const TY_HASH_ZIPMAP: u8 = 9;
+const TY_LIST_ZIPLIST: u8 = 10;
+const TY_SET_INTSET: u8 = 11;
+const TY_ZSET_ZIPLIST: u8 = 12;
+const TY_HASH_ZIPLIST: u8 = 13;
+const TY_LIST_QUICKLIST: u8 = 14;
+const TY_HASH_LISTPACK: u8 = 16;
+const TY_ZSET_LISTPACK: u8 = 17;
+const TY_LIST_QUICKLIST_2: u8 = 18;
+const TY_SET_LISTPACK: u8 = 20;
+
+/// quicklist node containers
+const QUICKLIST_NODE_PLAIN: u64 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum RdbValue {
+    String(Vec<u8>),
+    List(Vec<Vec<u8>>),
+    Hash(Vec<(Vec<u8>, Vec<u8>)>),
+    /// A value that we skipped over (the type name)
+    Unsupported(&'static str),
+}
+
+impl RdbValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::List(_) => "list",
+            Self::Hash(_) => "hash",
+            Self::Unsupported(ty) => ty,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RdbEntry {
+    pub db: u64,
+    pub key: Vec<u8>,
+    pub value: RdbValue,
+    /// true if the key had an expiry set (which we can't carry over)
+    pub expires: bool,
+}
+
+pub struct RdbReader<R> {
+    r: R,
+    db: u64,
+    done: bool,
+}
+
+impl RdbReader<BufReader<File>> {
+    pub fn open(path: &str) -> CliResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> RdbReader<R> {
+    pub fn new(mut r: R) -> CliResult<Self> {
+        let mut header = [0u8; 9];
+        r.read_exact(&mut header)?;
+        if &header[..5] != MAGIC || !header[5..].iter().all(u8::is_ascii_digit) {
+            return Err(err("not a RDB file"));
+        }
+        Ok(Self {
+            r,
+            db: 0,
+            done: false,
+        })
+    }
+    /// Returns the next key in the dump or [`None`] if we've reached the end
+    pub fn next_entry(&mut self) -> CliResult<Option<RdbEntry>> {
+        let mut expires = false;
+        while !self.done {
+            let op = self.byte()?;
+            match op {
+                OP_EOF => self.done = true,
+                OP_SELECTDB => self.db = self.length()?,
+                OP_RESIZEDB => {
+                    self.length()?;
+                    self.length()?;
+                }
+                OP_AUX => {
+                    self.string()?;
+                    self.string()?;
+                }
+                OP_EXPIRETIME => {
+                    self.bytes(4)?;
+                    expires = true;
+                }
+                OP_EXPIRETIME_MS => {
+                    self.bytes(8)?;
+                    expires = true;
+                }
+                OP_FREQ => {
+                    self.byte()?;
+                }
+                OP_IDLE => {
+                    self.length()?;
+                }
+                OP_FUNCTION2 => {
+                    self.string()?;
+                }
+                OP_FUNCTION_PRE_GA => {
+                    return Err(err(
+                        "functions from redis 7.0 release candidates are not supported",
+                    ))
+                }
+                OP_MODULE_AUX => return Err(err("module data is not supported")),
+                ty => {
+                    let key = self.string()?;
+                    let value = self.value(ty)?;
+                    return Ok(Some(RdbEntry {
+                        db: self.db,
+                        key,
+                        value,
+                        expires,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+    fn value(&mut self, ty: u8) -> CliResult<RdbValue> {
+        let value = match ty {
+            TY_STRING => RdbValue::String(self.string()?),
+            TY_LIST => {
+                let len = self.length()?;
+                RdbValue::List(self.strings(len)?)
+            }
+            TY_HASH => {
+                let len = self.length()?;
+                let mut pairs = vec![];
+                for _ in 0..len {
+                    pairs.push((self.string()?, self.string()?));
+                }
+                RdbValue::Hash(pairs)
+            }
+            TY_LIST_ZIPLIST => RdbValue::List(decode_ziplist(&self.string()?)?),
+            TY_HASH_ZIPLIST => RdbValue::Hash(into_pairs(decode_ziplist(&self.string()?)?)?),
+            TY_HASH_LISTPACK => RdbValue::Hash(into_pairs(decode_listpack(&self.string()?)?)?),
+            TY_LIST_QUICKLIST => {
+                let len = self.length()?;
+                let mut list = vec![];
+                for _ in 0..len {
+                    list.extend(decode_ziplist(&self.string()?)?);
+                }
+                RdbValue::List(list)
+            }
+            TY_LIST_QUICKLIST_2 => {
+                let len = self.length()?;
+                let mut list = vec![];
+                for _ in 0..len {
+                    let container = self.length()?;
+                    let node = self.string()?;
+                    if container == QUICKLIST_NODE_PLAIN {
+                        list.push(node);
+                    } else {
+                        list.extend(decode_listpack(&node)?);
+                    }
+                }
+                RdbValue::List(list)
+            }
+            /*
+                everything below is skipped
+            */
+            TY_SET => {
+                let len = self.length()?;
+                self.strings(len)?;
+                RdbValue::Unsupported("set")
+            }
+            TY_ZSET => {
+                let len = self.length()?;
+                for _ in 0..len {
+                    self.string()?;
+                    // the score is a string prefixed with a one byte length (or a special value)
+                    let score_len = self.byte()?;
+                    if score_len < 253 {
+                        self.bytes(score_len as usize)?;
+                    }
+                }
+                RdbValue::Unsupported("zset")
+            }
+            TY_ZSET_2 => {
+                let len = self.length()?;
+                for _ in 0..len {
+                    self.string()?;
+                    self.bytes(8)?;
+                }
+                RdbValue::Unsupported("zset")
+            }
+            TY_SET_INTSET | TY_SET_LISTPACK => {
+                self.string()?;
+                RdbValue::Unsupported("set")
+            }
+            TY_ZSET_ZIPLIST | TY_ZSET_LISTPACK => {
+                self.string()?;
+                RdbValue::Unsupported("zset")
+            }
+            TY_HASH_ZIPMAP => {
+                self.string()?;
+                RdbValue::Unsupported("zipmap")
+            }
+            ty => {
+                // modules, streams and the like. we have no idea how long these are, so we can't go on
+                return Err(err(format!("unsupported value type {ty}")));
+            }
+        };
+        Ok(value)
+    }
+    /*
+        primitives
+    */
+    fn byte(&mut self) -> CliResult<u8> {
+        let mut b = [0u8; 1];
+        self.r.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+    fn bytes(&mut self, len: usize) -> CliResult<Vec<u8>> {
+        // the buffer only grows as far as the file goes
+        let mut buf = vec![];
+        (&mut self.r).take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() != len {
+            return Err(err("unexpected end of file"));
+        }
+        Ok(buf)
+    }
+    /// Read a length. Special (integer or compressed) string encodings are returned as errors
+    fn length(&mut self) -> CliResult<u64> {
+        match self.length_or_encoding()? {
+            Length::Len(l) => Ok(l),
+            Length::Encoded(_) => Err(err("expected a length")),
+        }
+    }
+    fn length_or_encoding(&mut self) -> CliResult<Length> {
+        let first = self.byte()?;
+        let len = match first >> 6 {
+            0b00 => (first & 0x3F) as u64,
+            0b01 => (((first & 0x3F) as u64) << 8) | self.byte()? as u64,
+            0b10 => match first {
+                0x80 => {
+                    let b = self.bytes(4)?;
+                    u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64
+                }
+                0x81 => {
+                    let b = self.bytes(8)?;
+                    u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+                }
+                _ => return Err(err("bad length encoding")),
+            },
+            _ => return Ok(Length::Encoded(first & 0x3F)),
+        };
+        Ok(Length::Len(len))
+    }
+    fn string(&mut self) -> CliResult<Vec<u8>> {
+        match self.length_or_encoding()? {
+            Length::Len(len) => self.bytes(len as usize),
+            Length::Encoded(0) => Ok((self.byte()? as i8).to_string().into_bytes()),
+            Length::Encoded(1) => {
+                let b = self.bytes(2)?;
+                Ok(i16::from_le_bytes([b[0], b[1]]).to_string().into_bytes())
+            }
+            Length::Encoded(2) => {
+                let b = self.bytes(4)?;
+                Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]])
+                    .to_string()
+                    .into_bytes())
+            }
+            Length::Encoded(3) => {
+                let compressed_len = self.length()?;
+                let len = self.length()?;
+                let compressed = self.bytes(compressed_len as usize)?;
+                lzf_decompress(&compressed, len as usize)
+            }
+            Length::Encoded(_) => Err(err("bad string encoding")),
+        }
+    }
+    fn strings(&mut self, len: u64) -> CliResult<Vec<Vec<u8>>> {
+        let mut strings = vec![];
+        for _ in 0..len {
+            strings.push(self.string()?);
+        }
+        Ok(strings)
+    }
+}
+
+enum Length {
+    Len(u64),
+    Encoded(u8),
+}
+
+fn err(e: impl ToString) -> CliError {
+    CliError::ImportError(format!("bad RDB file. {}", e.to_string()))
+}
+
+fn into_pairs(items: Vec<Vec<u8>>) -> CliResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    if !items.len().is_multiple_of(2) {
+        return Err(err("odd number of hash items"));
+    }
+    let mut items = items.into_iter();
+    let mut pairs = vec![];
+    while let (Some(k), Some(v)) = (items.next(), items.next()) {
+        pairs.push((k, v));
+    }
+    Ok(pairs)
+}
+
+/*
+    compact encodings
+*/
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+    i: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(buf: &'a [u8], i: usize) -> Self {
+        Self { buf, i }
+    }
+    fn take(&mut self, n: usize) -> CliResult<&'a [u8]> {
+        if self.i + n > self.buf.len() {
+            return Err(err("truncated encoding"));
+        }
+        let r = &self.buf[self.i..self.i + n];
+        self.i += n;
+        Ok(r)
+    }
+    fn byte(&mut self) -> CliResult<u8> {
+        self.take(1).map(|b| b[0])
+    }
+    /// Read a little endian signed integer of `n` bytes
+    fn int_le(&mut self, n: usize) -> CliResult<i64> {
+        let mut buf = [0u8; 8];
+        buf[..n].copy_from_slice(self.take(n)?);
+        // sign extend
+        let shift = 64 - (n as u32 * 8);
+        Ok((i64::from_le_bytes(buf) << shift) >> shift)
+    }
+}
+
+fn decode_ziplist(buf: &[u8]) -> CliResult<Vec<Vec<u8>>> {
+    // [zlbytes: u32][zltail: u32][zllen: u16][entries][0xFF]
+    let mut c = Cursor::new(buf, 10);
+    let mut items = vec![];
+    loop {
+        let prevlen = c.byte()?;
+        if prevlen == 0xFF {
+            break;
+        }
+        if prevlen == 0xFE {
+            c.take(4)?;
+        }
+        let enc = c.byte()?;
+        let item = match enc >> 6 {
+            0b00 => c.take((enc & 0x3F) as usize)?.to_vec(),
+            0b01 => {
+                let len = (((enc & 0x3F) as usize) << 8) | c.byte()? as usize;
+                c.take(len)?.to_vec()
+            }
+            0b10 => {
+                let b = c.take(4)?;
+                c.take(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)?
+                    .to_vec()
+            }
+            _ => {
+                let int = match enc {
+                    0xC0 => c.int_le(2)?,
+                    0xD0 => c.int_le(4)?,
+                    0xE0 => c.int_le(8)?,
+                    0xF0 => c.int_le(3)?,
+                    0xFE => c.int_le(1)?,
+                    0xF1..=0xFD => (enc & 0x0F) as i64 - 1,
+                    _ => return Err(err("bad ziplist encoding")),
+                };
+                int.to_string().into_bytes()
+            }
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
+
+fn decode_listpack(buf: &[u8]) -> CliResult<Vec<Vec<u8>>> {
+    // [total bytes: u32][elements: u16][entries][0xFF]
+    let mut c = Cursor::new(buf, 6);
+    let mut items = vec![];
+    loop {
+        let start = c.i;
+        let enc = c.byte()?;
+        if enc == 0xFF {
+            break;
+        }
+        let item = if enc & 0x80 == 0 {
+            // 7 bit uint
+            (enc as i64).to_string().into_bytes()
+        } else if enc & 0xC0 == 0x80 {
+            c.take((enc & 0x3F) as usize)?.to_vec()
+        } else if enc & 0xE0 == 0xC0 {
+            // 13 bit signed int
+            let v = (((enc & 0x1F) as i64) << 8) | c.byte()? as i64;
+            let v = if v >= 1 << 12 { v - (1 << 13) } else { v };
+            v.to_string().into_bytes()
+        } else if enc & 0xF0 == 0xE0 {
+            let len = (((enc & 0x0F) as usize) << 8) | c.byte()? as usize;
+            c.take(len)?.to_vec()
+        } else {
+            match enc {
+                0xF0 => {
+                    let b = c.take(4)?;
+                    c.take(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)?
+                        .to_vec()
+                }
+                0xF1 => c.int_le(2)?.to_string().into_bytes(),
+                0xF2 => c.int_le(3)?.to_string().into_bytes(),
+                0xF3 => c.int_le(4)?.to_string().into_bytes(),
+                0xF4 => c.int_le(8)?.to_string().into_bytes(),
+                _ => return Err(err("bad listpack encoding")),
+            }
+        };
+        // skip the backlen
+        let entry_len = c.i - start;
+        let backlen = match entry_len {
+            0..=127 => 1,
+            128..=16383 => 2,
+            16384..=2097151 => 3,
+            2097152..=268435455 => 4,
+            _ => 5,
+        };
+        c.take(backlen)?;
+        items.push(item);
+    }
+    Ok(items)
+}
+
+fn lzf_decompress(input: &[u8], len: usize) -> CliResult<Vec<u8>> {
+    let mut c = Cursor::new(input, 0);
+    // don't trust the length for the allocation (LZF rarely gets past 2x anyway)
+    let mut out = Vec::with_capacity(len.min(input.len() * 2));
+    while c.i < input.len() {
+        if out.len() > len {
+            return Err(err("bad LZF length"));
+        }
+        let ctrl = c.byte()? as usize;
+        if ctrl < 32 {
+            // literal run
+            out.extend_from_slice(c.take(ctrl + 1)?);
+        } else {
+            // back reference
+            let mut run = ctrl >> 5;
+            if run == 7 {
+                run += c.byte()? as usize;
+            }
+            let back = ((ctrl & 0x1F) << 8) + c.byte()? as usize + 1;
+            if back > out.len() {
+                return Err(err("bad LZF back reference"));
+            }
+            let from = out.len() - back;
+            for i in 0..run + 2 {
+                out.push(out[from + i]);
+            }
+        }
+    }
+    if out.len() != len {
+        return Err(err("bad LZF length"));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+        fixtures
+    */
+
+    fn dump(body: &[&[u8]]) -> Vec<u8> {
+        let mut dump = b"REDIS0011".to_vec();
+        body.iter().for_each(|part| dump.extend_from_slice(part));
+        dump.push(OP_EOF);
+        // checksum (never read)
+        dump.extend_from_slice(&[0; 8]);
+        dump
+    }
+    fn len(len: usize) -> Vec<u8> {
+        match len {
+            0..=0x3F => vec![len as u8],
+            0x40..=0x3FFF => vec![0x40 | (len >> 8) as u8, len as u8],
+            _ => [&[0x80][..], &(len as u32).to_be_bytes()].concat(),
+        }
+    }
+    fn s(s: &[u8]) -> Vec<u8> {
+        [len(s.len()), s.to_vec()].concat()
+    }
+    fn entry(ty: u8, key: &[u8], value: &[&[u8]]) -> Vec<u8> {
+        let mut entry = vec![ty];
+        entry.extend(s(key));
+        value.iter().for_each(|part| entry.extend_from_slice(part));
+        entry
+    }
+    fn read_all(dump: &[u8]) -> CliResult<Vec<RdbEntry>> {
+        let mut rdb = RdbReader::new(dump)?;
+        let mut entries = vec![];
+        while let Some(entry) = rdb.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+    fn read_one(dump: &[u8]) -> RdbValue {
+        let mut entries = read_all(dump).unwrap();
+        assert_eq!(entries.len(), 1);
+        entries.remove(0).value
+    }
+    fn strs(items: &[&str]) -> Vec<Vec<u8>> {
+        items.iter().map(|item| item.as_bytes().to_vec()).collect()
+    }
+    fn assert_bad(dump: &[u8]) {
+        assert!(read_all(dump).is_err(), "{dump:?}");
+    }
+
+    /// `[0: abc] [1000] [4] [-1] [-8388607] [i32::MIN] [0x0102030405060708] [xyz] [hi] [z]`
+    const ZIPLIST: &[u8] = b"\0\0\0\0\0\0\0\0\0\0\
+        \x00\x03abc\
+        \x05\xC0\xE8\x03\
+        \x04\xF5\
+        \x02\xFE\xFF\
+        \x03\xF0\x01\x00\x80\
+        \x05\xD0\x00\x00\x00\x80\
+        \x06\xE0\x08\x07\x06\x05\x04\x03\x02\x01\
+        \x0A\x40\x03xyz\
+        \x05\x80\x00\x00\x00\x02hi\
+        \xFE\x07\x00\x00\x00\x01z\
+        \xFF";
+    const ZIPLIST_ITEMS: &[&str] = &[
+        "abc",
+        "1000",
+        "4",
+        "-1",
+        "-8388607",
+        "-2147483648",
+        "72623859790382856",
+        "xyz",
+        "hi",
+        "z",
+    ];
+    /// `[5] [abc] [-1] [256] [hi] [x] [-1000] [-8388608] [1000000000] [-1]`
+    const LISTPACK: &[u8] = b"\0\0\0\0\0\0\
+        \x05\x01\
+        \x83abc\x04\
+        \xDF\xFF\x02\
+        \xC1\x00\x02\
+        \xE0\x02hi\x04\
+        \xF0\x01\x00\x00\x00x\x06\
+        \xF1\x18\xFC\x03\
+        \xF2\x00\x00\x80\x04\
+        \xF3\x00\xCA\x9A\x3B\x05\
+        \xF4\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x09\
+        \xFF";
+    const LISTPACK_ITEMS: &[&str] = &[
+        "5",
+        "abc",
+        "-1",
+        "256",
+        "hi",
+        "x",
+        "-1000",
+        "-8388608",
+        "1000000000",
+        "-1",
+    ];
+    /// `aaaaaaaaaa` (a literal and a back reference with an extended run)
+    const LZF_RUN: &[u8] = b"\x00a\xE0\x00\x00";
+    /// `abcabcabc`
+    const LZF_REPEAT: &[u8] = b"\x02abc\x80\x02";
+
+    /*
+        strings
+    */
+
+    #[test]
+    fn string_raw() {
+        let long = vec![b'x'; 300];
+        let huge = vec![b'y'; 0x4000];
+        for value in [&b""[..], b"hello", &long, &huge] {
+            assert_eq!(
+                read_one(&dump(&[&entry(TY_STRING, b"k", &[&s(value)])])),
+                RdbValue::String(value.to_vec())
+            );
+        }
+        // a 64-bit length
+        assert_eq!(
+            read_one(&dump(&[&entry(
+                TY_STRING,
+                b"k",
+                &[b"\x81\0\0\0\0\0\0\0\x02hi"]
+            )])),
+            RdbValue::String(b"hi".to_vec())
+        );
+    }
+
+    #[test]
+    fn string_int() {
+        for (encoded, value) in [
+            (&b"\xC0\x85"[..], "-123"),
+            (b"\xC1\x39\x30", "12345"),
+            (b"\xC2\x00\xCA\x9A\x3B", "1000000000"),
+        ] {
+            assert_eq!(
+                read_one(&dump(&[&entry(TY_STRING, b"k", &[encoded])])),
+                RdbValue::String(value.into())
+            );
+        }
+    }
+
+    #[test]
+    fn string_lzf() {
+        for (compressed, value) in [(LZF_RUN, "aaaaaaaaaa"), (LZF_REPEAT, "abcabcabc")] {
+            let encoded = [
+                &[0xC3][..],
+                &len(compressed.len()),
+                &len(value.len()),
+                compressed,
+            ]
+            .concat();
+            assert_eq!(
+                read_one(&dump(&[&entry(TY_STRING, b"k", &[&encoded])])),
+                RdbValue::String(value.into())
+            );
+        }
+    }
+
+    /*
+        lists and hashes
+    */
+
+    #[test]
+    fn list() {
+        assert_eq!(
+            read_one(&dump(&[&entry(
+                TY_LIST,
+                b"k",
+                &[&len(2), &s(b"a"), &s(b"b")]
+            )])),
+            RdbValue::List(strs(&["a", "b"]))
+        );
+    }
+
+    #[test]
+    fn list_ziplist() {
+        assert_eq!(
+            read_one(&dump(&[&entry(TY_LIST_ZIPLIST, b"k", &[&s(ZIPLIST)])])),
+            RdbValue::List(strs(ZIPLIST_ITEMS))
+        );
+    }
+
+    #[test]
+    fn list_quicklist() {
+        let nodes = [len(2), s(ZIPLIST), s(b"\0\0\0\0\0\0\0\0\0\0\x00\x01q\xFF")].concat();
+        let mut items = strs(ZIPLIST_ITEMS);
+        items.push(b"q".to_vec());
+        assert_eq!(
+            read_one(&dump(&[&entry(TY_LIST_QUICKLIST, b"k", &[&nodes])])),
+            RdbValue::List(items)
+        );
+    }
+
+    #[test]
+    fn list_quicklist_2() {
+        // a packed node and a plain node
+        let nodes = [len(2), len(2), s(LISTPACK), len(1), s(b"plain")].concat();
+        let mut items = strs(LISTPACK_ITEMS);
+        items.push(b"plain".to_vec());
+        assert_eq!(
+            read_one(&dump(&[&entry(TY_LIST_QUICKLIST_2, b"k", &[&nodes])])),
+            RdbValue::List(items)
+        );
+    }
+
+    #[test]
+    fn listpack_long_entry() {
+        // 202 bytes, so the backlen takes two bytes
+        let mut listpack = b"\0\0\0\0\0\0\xE0\xC8".to_vec();
+        listpack.extend([b'x'; 200]);
+        listpack.extend(b"\x01\x4A\xFF");
+        assert_eq!(
+            read_one(&dump(&[&entry(
+                TY_LIST_QUICKLIST_2,
+                b"k",
+                &[&len(1), &len(2), &s(&listpack)]
+            )])),
+            RdbValue::List(vec![vec![b'x'; 200]])
+        );
+    }
+
+    #[test]
+    fn hash() {
+        assert_eq!(
+            read_one(&dump(&[&entry(
+                TY_HASH,
+                b"k",
+                &[&len(1), &s(b"f"), &s(b"v")]
+            )])),
+            RdbValue::Hash(vec![(b"f".to_vec(), b"v".to_vec())])
+        );
+    }
+
+    #[test]
+    fn hash_ziplist() {
+        let ziplist = b"\0\0\0\0\0\0\0\0\0\0\x00\x02k1\x04\x02v1\xFF";
+        assert_eq!(
+            read_one(&dump(&[&entry(TY_HASH_ZIPLIST, b"k", &[&s(ziplist)])])),
+            RdbValue::Hash(vec![(b"k1".to_vec(), b"v1".to_vec())])
+        );
+    }
+
+    #[test]
+    fn hash_listpack() {
+        let items = strs(LISTPACK_ITEMS);
+        let pairs = items
+            .chunks(2)
+            .map(|kv| (kv[0].clone(), kv[1].clone()))
+            .collect();
+        assert_eq!(
+            read_one(&dump(&[&entry(TY_HASH_LISTPACK, b"k", &[&s(LISTPACK)])])),
+            RdbValue::Hash(pairs)
+        );
+    }
+
+    /*
+        skipped values and opcodes
+    */
+
+    #[test]
+    fn skip_unsupported() {
+        let skipped = [
+            entry(TY_SET, b"set", &[&len(2), &s(b"a"), &s(b"b")]),
+            // a regular score, then +inf
+            entry(
+                TY_ZSET,
+                b"zset",
+                &[&len(2), &s(b"a"), b"\x031.5", &s(b"b"), b"\xFE"],
+            ),
+            entry(TY_ZSET_2, b"zset2", &[&len(1), &s(b"a"), &[0; 8]]),
+            entry(
+                TY_SET_INTSET,
+                b"intset",
+                &[&s(b"\x02\0\0\0\x01\0\0\0\x01\0")],
+            ),
+            entry(TY_SET_LISTPACK, b"setlp", &[&s(LISTPACK)]),
+            entry(TY_ZSET_ZIPLIST, b"zsetzl", &[&s(ZIPLIST)]),
+            entry(TY_ZSET_LISTPACK, b"zsetlp", &[&s(LISTPACK)]),
+            entry(TY_HASH_ZIPMAP, b"zipmap", &[&s(b"\x00\xFF")]),
+        ];
+        let last = entry(TY_STRING, b"last", &[&s(b"value")]);
+        let mut body: Vec<&[u8]> = skipped.iter().map(Vec::as_slice).collect();
+        body.push(&last);
+        let entries = read_all(&dump(&body)).unwrap();
+        let types: Vec<_> = entries.iter().map(|e| e.value.type_name()).collect();
+        assert_eq!(
+            types,
+            ["set", "zset", "zset", "set", "set", "zset", "zset", "zipmap", "string"]
+        );
+        assert_eq!(entries[8].key, b"last");
+        assert_eq!(entries[8].value, RdbValue::String(b"value".to_vec()));
+    }
+
+    #[test]
+    fn opcodes() {
+        let aux = [&[OP_AUX][..], &s(b"redis-ver"), &s(b"7.2.0")].concat();
+        let function = [&[OP_FUNCTION2][..], &s(b"#!lua name=lib")].concat();
+        let select = [OP_SELECTDB, 3];
+        let resize = [OP_RESIZEDB, 2, 1];
+        let expiring = [
+            &[OP_EXPIRETIME_MS][..],
+            &[0; 8],
+            &[OP_FREQ, 5],
+            &entry(TY_STRING, b"a", &[&s(b"1")]),
+        ]
+        .concat();
+        let expiring_secs = [&[OP_EXPIRETIME][..], &[0; 4], &[OP_IDLE, 9]].concat();
+        let b = entry(TY_STRING, b"b", &[&s(b"2")]);
+        let c = entry(TY_STRING, b"c", &[&s(b"3")]);
+        let entries = read_all(&dump(&[
+            &aux,
+            &function,
+            &select,
+            &resize,
+            &expiring,
+            &expiring_secs,
+            &b,
+            &c,
+        ]))
+        .unwrap();
+        assert_eq!(
+            entries,
+            [
+                RdbEntry {
+                    db: 3,
+                    key: b"a".to_vec(),
+                    value: RdbValue::String(b"1".to_vec()),
+                    expires: true
+                },
+                RdbEntry {
+                    db: 3,
+                    key: b"b".to_vec(),
+                    value: RdbValue::String(b"2".to_vec()),
+                    expires: true
+                },
+                RdbEntry {
+                    db: 3,
+                    key: b"c".to_vec(),
+                    value: RdbValue::String(b"3".to_vec()),
+                    expires: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_dump() {
+        assert!(read_all(&dump(&[])).unwrap().is_empty());
+    }
+
+    /*
+        corrupt and truncated dumps
+    */
+
+    #[test]
+    fn bad_header() {
+        assert_bad(b"");
+        assert_bad(b"REDIS00");
+        assert_bad(b"RADIS0011\xFF");
+        assert_bad(b"REDISabcd\xFF");
+    }
+
+    #[test]
+    fn unsupported_opcodes_and_types() {
+        assert_bad(&dump(&[&[OP_FUNCTION_PRE_GA], &s(b"lib")]));
+        assert_bad(&dump(&[&[OP_MODULE_AUX], &len(1)]));
+        // module and stream values
+        assert_bad(&dump(&[&entry(7, b"k", &[&len(1)])]));
+        assert_bad(&dump(&[&entry(15, b"k", &[&len(1)])]));
+    }
+
+    #[test]
+    fn truncated() {
+        let lzf = [&[0xC3][..], &len(LZF_RUN.len()), &len(10), LZF_RUN].concat();
+        let full = dump(&[
+            &[OP_AUX],
+            &s(b"redis-ver"),
+            &s(b"7.2.0"),
+            &[OP_SELECTDB, 1],
+            &entry(TY_STRING, b"lzf", &[&lzf]),
+            &entry(TY_STRING, b"int", &[b"\xC2\x00\xCA\x9A\x3B"]),
+            &entry(TY_LIST_ZIPLIST, b"zl", &[&s(ZIPLIST)]),
+            &entry(TY_HASH_LISTPACK, b"lp", &[&s(LISTPACK)]),
+            &entry(TY_ZSET_2, b"zset", &[&len(1), &s(b"a"), &[0; 8]]),
+        ]);
+        assert_eq!(read_all(&full).unwrap().len(), 5);
+        // every prefix that ends before the EOF opcode is an error
+        let eof = full.len() - 9;
+        for end in 0..eof {
+            assert_bad(&full[..end]);
+        }
+    }
+
+    #[test]
+    fn huge_lengths() {
+        // lengths far beyond the end of the file must not be allocated
+        assert_bad(&dump(&[&entry(
+            TY_STRING,
+            b"k",
+            &[b"\x80\xFF\xFF\xFF\xFF"],
+        )]));
+        assert_bad(&dump(&[&entry(
+            TY_STRING,
+            b"k",
+            &[b"\x81\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF"],
+        )]));
+        // LZF with an enormous uncompressed length
+        assert_bad(&dump(&[&entry(
+            TY_STRING,
+            b"k",
+            &[b"\xC3\x05\x81\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF", LZF_RUN],
+        )]));
+        assert_bad(&dump(&[&entry(
+            TY_LIST,
+            b"k",
+            &[b"\x81\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF"],
+        )]));
+    }
+
+    #[test]
+    fn corrupt_lzf() {
+        let lzf = |compressed: &[u8], uncompressed: usize| {
+            dump(&[&entry(
+                TY_STRING,
+                b"k",
+                &[
+                    &[0xC3],
+                    &len(compressed.len()),
+                    &len(uncompressed),
+                    compressed,
+                ],
+            )])
+        };
+        // wrong uncompressed length
+        assert_bad(&lzf(LZF_RUN, 9));
+        assert_bad(&lzf(LZF_RUN, 11));
+        // a back reference before the start
+        assert_bad(&lzf(b"\x20\x05", 3));
+        assert_bad(&lzf(b"\x00a\x20\x05", 4));
+        // a literal run that goes past the end
+        assert_bad(&lzf(b"\x05ab", 6));
+    }
+
+    #[test]
+    fn corrupt_compact_encodings() {
+        // no terminator
+        assert_bad(&dump(&[&entry(
+            TY_LIST_ZIPLIST,
+            b"k",
+            &[&s(&ZIPLIST[..ZIPLIST.len() - 1])],
+        )]));
+        assert_bad(&dump(&[&entry(
+            TY_HASH_LISTPACK,
+            b"k",
+            &[&s(&LISTPACK[..LISTPACK.len() - 1])],
+        )]));
+        // shorter than the header
+        assert_bad(&dump(&[&entry(TY_LIST_ZIPLIST, b"k", &[&s(b"\0\0")])]));
+        assert_bad(&dump(&[&entry(TY_HASH_LISTPACK, b"k", &[&s(b"")])]));
+        // bad entry encodings
+        assert_bad(&dump(&[&entry(
+            TY_LIST_ZIPLIST,
+            b"k",
+            &[&s(b"\0\0\0\0\0\0\0\0\0\0\x00\xC8\xFF")],
+        )]));
+        assert_bad(&dump(&[&entry(
+            TY_HASH_LISTPACK,
+            b"k",
+            &[&s(b"\0\0\0\0\0\0\xF5\x01\xFF")],
+        )]));
+        // a string that runs past the end
+        assert_bad(&dump(&[&entry(
+            TY_LIST_ZIPLIST,
+            b"k",
+            &[&s(b"\0\0\0\0\0\0\0\0\0\0\x00\x09ab\xFF")],
+        )]));
+        // odd number of hash items
+        assert_bad(&dump(&[&entry(
+            TY_HASH_ZIPLIST,
+            b"k",
+            &[&s(b"\0\0\0\0\0\0\0\0\0\0\x00\x01a\x03\x01b\x03\x01c\xFF")],
+        )]));
+        assert_bad(&dump(&[&entry(
+            TY_HASH_LISTPACK,
+            b"k",
+            &[&s(b"\0\0\0\0\0\0\x01\x01\xFF")],
+        )]));
+    }
+
+    #[test]
+    fn corrupt_bytes() {
+        // flipping any byte must give us an error (or a different dump), but never a panic
+        let full = dump(&[
+            &entry(TY_LIST_ZIPLIST, b"zl", &[&s(ZIPLIST)]),
+            &entry(TY_HASH_LISTPACK, b"lp", &[&s(LISTPACK)]),
+            &entry(
+                TY_STRING,
+                b"lzf",
+                &[&[0xC3], &len(LZF_REPEAT.len()), &len(9), LZF_REPEAT],
+            ),
+        ]);
+        for i in 0..full.len() {
+            for b in [0x00, 0x3F, 0x40, 0x7F, 0x80, 0x81, 0xC3, 0xF0, 0xFE, 0xFF] {
+                let mut corrupt = full.clone();
+                corrupt[i] = b;
+                let _ = read_all(&corrupt);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/*
+ * skytable.h: C API for the embedded Skytable engine
+ *
+ * Build the library with `cargo build --release -p skyd --lib` and link against
+ * `libskyd`. See `src/ffi.rs` for the implementation.
+ *
+ * This file is a part of Skytable
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#ifndef SKYTABLE_H
+#define SKYTABLE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* status codes (returned by every function that can fail) */
+#define SKY_OK 0
+/* a null pointer, invalid UTF-8 or an invalid parameter was passed */
+#define SKY_ERR_ARGUMENT 1
+/* the database couldn't be opened (see the logs) */
+#define SKY_ERR_OPEN 2
+/* the query failed; see sky_last_error */
+#define SKY_ERR_QUERY 3
+/* the row, column or element doesn't exist */
+#define SKY_ERR_RANGE 4
+/* the engine panicked (see the logs); the database should be closed. Functions
+ * that don't return a status code return 0 instead */
+#define SKY_ERR_PANIC 5
+
+/* value types */
+#define SKY_TYPE_NULL 0
+#define SKY_TYPE_BOOL 1
+#define SKY_TYPE_UINT 2
+#define SKY_TYPE_SINT 3
+#define SKY_TYPE_FLOAT 4
+#define SKY_TYPE_BINARY 5
+#define SKY_TYPE_STRING 6
+#define SKY_TYPE_LIST 7
+#define SKY_TYPE_DICT 8
+
+/*
+ * A value. Only the fields for the value's type are set. Binary and string
+ * values are in `ptr` and `len` (strings are UTF-8 and NOT NUL terminated).
+ * Lists and dicts are read with sky_value_list_get and sky_value_dict_get.
+ * Lists and dicts can't be passed as query parameters.
+ */
+typedef struct sky_value {
+    uint32_t ty;
+    bool b;
+    uint64_t u;
+    int64_t i;
+    double f;
+    const uint8_t *ptr;
+    size_t len;
+} sky_value;
+
+typedef struct SkyDb sky_db;
+typedef struct SkyResult sky_result;
+
+/*
+ * database
+ */
+
+/* Open the database in the directory at `path` (only one database can be open
+ * per process). `options` are server options like
+ * `--auth-root-password=...` */
+int32_t sky_open(const char *path, const char *const *options,
+                 size_t options_len, sky_db **out);
+/* Open a database that is kept in memory instead of on disk. Nothing is
+ * written to disk, but the data stays around until the process exits */
+int32_t sky_open_in_memory(const char *const *options, size_t options_len,
+                           sky_db **out);
+/* Run a query. On success, the result is written to `out` and must be freed
+ * with sky_result_free */
+int32_t sky_execute(sky_db *db, const char *query, const sky_value *params,
+                    size_t params_len, sky_result **out);
+/* The error code of the last failed query (the same code that the server
+ * sends to clients), or 0 if the last query succeeded */
+uint16_t sky_last_error(const sky_db *db);
+/* Flush all data and close the database */
+void sky_close(sky_db *db);
+
+/*
+ * results
+ *
+ * A query that returns a single value returns one row with one column, and
+ * one that returns nothing returns no rows. Values are only valid until the
+ * result is freed.
+ */
+
+size_t sky_result_rows(const sky_result *res);
+size_t sky_result_columns(const sky_result *res, size_t row);
+int32_t sky_result_get(const sky_result *res, size_t row, size_t column,
+                       sky_value *out);
+int32_t sky_value_list_get(const sky_value *list, size_t index,
+                           sky_value *out);
+/* the key is written to `key_out` as a string */
+int32_t sky_value_dict_get(const sky_value *dict, size_t index,
+                           sky_value *key_out, sky_value *value_out);
+void sky_result_free(sky_result *res);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SKYTABLE_H */
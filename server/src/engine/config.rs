@@ -37,6 +37,8 @@ use {
 
 pub type ParsedRawArgs = std::collections::HashMap<String, Vec<String>>;
 pub const ROOT_PASSWORD_MIN_LEN: usize = 16;
+/// Journal preallocation extents must be aligned to this size
+pub const JOURNAL_PREALLOC_ALIGN: u64 = 4096;
 
 #[derive(Debug, PartialEq)]
 pub struct ModifyGuard<T> {
@@ -75,9 +77,13 @@ impl<T> core::ops::DerefMut for ModifyGuard<T> {
 /// The final configuration that can be used to start up all services
 pub struct Configuration {
     pub endpoints: ConfigEndpoint,
+    /// the endpoints that we listen on besides the main TCP and TLS ones
+    pub additional_endpoints: Vec<ConfigEndpointAdditional>,
     pub mode: ConfigMode,
     pub system: ConfigSystem,
     pub auth: ConfigAuth,
+    pub logging: ConfigLogging,
+    pub ddl: ConfigDdl,
 }
 
 impl Configuration {
@@ -90,9 +96,12 @@ impl Configuration {
     ) -> Self {
         Self {
             endpoints,
+            additional_endpoints: vec![],
             mode,
             system,
             auth,
+            logging: ConfigLogging::default(),
+            ddl: ConfigDdl::default(),
         }
     }
     const DEFAULT_HOST: &'static str = "127.0.0.1";
@@ -102,10 +111,14 @@ impl Configuration {
             endpoints: ConfigEndpoint::Insecure(ConfigEndpointTcp {
                 host: Self::DEFAULT_HOST.to_owned(),
                 port: Self::DEFAULT_PORT_TCP,
+                proxy_protocol: false,
             }),
+            additional_endpoints: vec![],
             mode: ConfigMode::Dev,
             system: ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
-            auth: ConfigAuth::new(auth.plugin, auth.root_pass),
+            auth: ConfigAuth::new_with_kdf(auth.plugin, auth.root_pass, auth.kdf),
+            logging: ConfigLogging::default(),
+            ddl: ConfigDdl::default(),
         }
     }
 }
@@ -125,12 +138,25 @@ pub enum ConfigEndpoint {
 pub struct ConfigEndpointTcp {
     host: String,
     port: u16,
+    /// if set, every connection must start with a PROXY v2 header
+    proxy_protocol: bool,
 }
 
 impl ConfigEndpointTcp {
     #[cfg(test)]
     pub fn new(host: String, port: u16) -> Self {
-        Self { host, port }
+        Self {
+            host,
+            port,
+            proxy_protocol: false,
+        }
+    }
+    #[cfg(test)]
+    pub fn with_proxy_protocol(self) -> Self {
+        Self {
+            proxy_protocol: true,
+            ..self
+        }
     }
     pub fn host(&self) -> &str {
         self.host.as_ref()
@@ -138,6 +164,19 @@ impl ConfigEndpointTcp {
     pub fn port(&self) -> u16 {
         self.port
     }
+    pub fn proxy_protocol(&self) -> bool {
+        self.proxy_protocol
+    }
+}
+
+impl fmt::Display for ConfigEndpointTcp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -147,6 +186,7 @@ pub struct ConfigEndpointTls {
     cert: String,
     private_key: String,
     pkey_pass: String,
+    files: TlsFiles,
 }
 
 impl ConfigEndpointTls {
@@ -156,12 +196,14 @@ impl ConfigEndpointTls {
         cert: String,
         private_key: String,
         pkey_pass: String,
+        files: TlsFiles,
     ) -> Self {
         Self {
             tcp,
             cert,
             private_key,
             pkey_pass,
+            files,
         }
     }
     pub fn tcp(&self) -> &ConfigEndpointTcp {
@@ -176,6 +218,76 @@ impl ConfigEndpointTls {
     pub fn pkey_pass(&self) -> &str {
         self.pkey_pass.as_ref()
     }
+    pub fn files(&self) -> &TlsFiles {
+        &self.files
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// An endpoint that is listened on besides the main TCP and TLS endpoints
+pub enum ConfigEndpointAdditional {
+    Tcp(ConfigEndpointTcp),
+    /// uses the certificate of the main TLS endpoint
+    Tls(ConfigEndpointTcp),
+    Uds(ConfigEndpointUds),
+}
+
+impl fmt::Display for ConfigEndpointAdditional {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(tcp) => write!(f, "tcp@{tcp}"),
+            Self::Tls(tcp) => write!(f, "tls@{tcp}"),
+            Self::Uds(uds) => write!(f, "uds@{}", uds.path),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// Unix domain socket endpoint configuration
+pub struct ConfigEndpointUds {
+    path: String,
+    /// if unset, clients don't need a password (the permissions of the socket decide who can connect)
+    auth: bool,
+}
+
+impl ConfigEndpointUds {
+    #[cfg(test)]
+    pub fn new(path: String, auth: bool) -> Self {
+        Self { path, auth }
+    }
+    pub fn path(&self) -> &str {
+        self.path.as_ref()
+    }
+    pub fn auth(&self) -> bool {
+        self.auth
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+/// Paths to the files that the TLS configuration was loaded from (so that we can reload it)
+pub struct TlsFiles {
+    cert: String,
+    private_key: String,
+    pkey_pass: String,
+}
+
+impl TlsFiles {
+    pub fn new(cert: &str, private_key: &str, pkey_pass: &str) -> Self {
+        Self {
+            cert: cert.into(),
+            private_key: private_key.into(),
+            pkey_pass: pkey_pass.into(),
+        }
+    }
+    pub fn cert(&self) -> &str {
+        self.cert.as_ref()
+    }
+    pub fn private_key(&self) -> &str {
+        self.private_key.as_ref()
+    }
+    pub fn pkey_pass(&self) -> &str {
+        self.pkey_pass.as_ref()
+    }
 }
 
 /*
@@ -202,16 +314,109 @@ pub enum ConfigMode {
 pub struct ConfigSystem {
     /// time window in seconds for the reliability system to kick-in automatically
     pub reliability_system_window: u64,
+    /// idle time in seconds after which the OS starts sending TCP keepalive probes (if enabled)
+    pub tcp_keepalive: Option<u64>,
+    /// size in bytes of the extents used to preallocate space for journals (if enabled)
+    pub journal_prealloc: Option<u64>,
+    /// skip the check that refuses to start on data written by a newer, incompatible version
+    pub force_downgrade_check_off: bool,
+    /// only write the changed fields for updates in data batches
+    pub delta_batches: bool,
+    /// the maximum transient memory (in bytes) that a single query can use
+    pub query_memory_limit: Option<u64>,
+    /// the maximum transient memory (in bytes) that all running queries can use together
+    pub query_memory_global_limit: Option<u64>,
+    /// the maximum number of write queries that a user (other than root) can run every second
+    pub user_write_ops_limit: Option<u64>,
+    /// the maximum number of bytes that a user (other than root) can send with write queries every second
+    pub user_write_bytes_limit: Option<u64>,
+    /// the maximum encoded size (in bytes) of a single row
+    pub row_size_limit: Option<u64>,
+    /// the maximum size (in bytes) of a single string or binary value
+    pub field_size_limit: Option<u64>,
+    /// the OTLP/HTTP collector that statement traces are exported to (if enabled)
+    pub otlp_endpoint: Option<String>,
 }
 
 impl ConfigSystem {
     pub fn new(reliability_system_window: u64) -> Self {
         Self {
             reliability_system_window,
+            tcp_keepalive: None,
+            journal_prealloc: None,
+            force_downgrade_check_off: false,
+            delta_batches: false,
+            query_memory_limit: None,
+            query_memory_global_limit: None,
+            user_write_ops_limit: None,
+            user_write_bytes_limit: None,
+            row_size_limit: None,
+            field_size_limit: None,
+            otlp_endpoint: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn with_tcp_keepalive(self, tcp_keepalive: u64) -> Self {
+        Self {
+            tcp_keepalive: Some(tcp_keepalive),
+            ..self
+        }
+    }
+    #[cfg(test)]
+    pub fn with_journal_prealloc(self, journal_prealloc: u64) -> Self {
+        Self {
+            journal_prealloc: Some(journal_prealloc),
+            ..self
         }
     }
 }
 
+/*
+    config logging
+*/
+
+#[derive(Debug, PartialEq)]
+/// Logging configuration
+pub struct ConfigLogging {
+    /// the file that logs are written to (if unset, logs go to stderr)
+    pub file: Option<String>,
+    /// size in bytes after which the log file is rotated (if enabled)
+    pub max_size: Option<u64>,
+    /// time in seconds after which the log file is rotated (if enabled)
+    pub rotate_interval: Option<u64>,
+    /// gzip rotated log files
+    pub compress: bool,
+    /// the number of rotated log files to keep
+    pub retain: u64,
+}
+
+impl ConfigLogging {
+    pub const DEFAULT_RETAIN: u64 = 7;
+}
+
+impl Default for ConfigLogging {
+    fn default() -> Self {
+        Self {
+            file: None,
+            max_size: None,
+            rotate_interval: None,
+            compress: true,
+            retain: Self::DEFAULT_RETAIN,
+        }
+    }
+}
+
+/*
+    config ddl
+*/
+
+#[derive(Debug, PartialEq, Default)]
+/// DDL configuration
+pub struct ConfigDdl {
+    /// time in seconds that dropped models are kept in the trash for (if enabled)
+    pub trash_retention: Option<u64>,
+}
+
 /*
     config auth
 */
@@ -226,11 +431,75 @@ pub enum AuthDriver {
 pub struct ConfigAuth {
     pub plugin: AuthDriver,
     pub root_key: String,
+    pub kdf: ConfigKdf,
 }
 
 impl ConfigAuth {
+    #[cfg(test)]
     pub fn new(plugin: AuthDriver, root_key: String) -> Self {
-        Self { plugin, root_key }
+        Self::new_with_kdf(plugin, root_key, ConfigKdf::default())
+    }
+    pub fn new_with_kdf(plugin: AuthDriver, root_key: String, kdf: ConfigKdf) -> Self {
+        Self {
+            plugin,
+            root_key,
+            kdf,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Clone, Copy)]
+#[serde(tag = "algorithm")]
+/// The KDF used to hash passwords. Hashes created with a different KDF (or different parameters)
+/// are upgraded the next time the user logs in
+pub enum ConfigKdf {
+    /// bcrypt (the scheme used by older versions)
+    #[serde(rename = "bcrypt")]
+    Bcrypt,
+    /// argon2id
+    #[serde(rename = "argon2id")]
+    Argon2id {
+        /// memory cost in KiB
+        #[serde(default = "ConfigKdf::default_argon2_memory")]
+        memory: u32,
+        /// number of passes
+        #[serde(default = "ConfigKdf::default_argon2_iterations")]
+        iterations: u32,
+        /// degree of parallelism
+        #[serde(default = "ConfigKdf::default_argon2_parallelism")]
+        parallelism: u32,
+    },
+}
+
+impl ConfigKdf {
+    fn default_argon2_memory() -> u32 {
+        19 * 1024
+    }
+    fn default_argon2_iterations() -> u32 {
+        2
+    }
+    fn default_argon2_parallelism() -> u32 {
+        1
+    }
+    fn is_valid(&self) -> bool {
+        match *self {
+            Self::Bcrypt => true,
+            Self::Argon2id {
+                memory,
+                iterations,
+                parallelism,
+            } => argon2::Params::new(memory, iterations, parallelism, None).is_ok(),
+        }
+    }
+}
+
+impl Default for ConfigKdf {
+    fn default() -> Self {
+        Self::Argon2id {
+            memory: Self::default_argon2_memory(),
+            iterations: Self::default_argon2_iterations(),
+            parallelism: Self::default_argon2_parallelism(),
+        }
     }
 }
 
@@ -244,6 +513,8 @@ pub struct DecodedConfiguration {
     system: Option<DecodedSystemConfig>,
     endpoints: Option<DecodedEPConfig>,
     auth: Option<DecodedAuth>,
+    logging: Option<DecodedLoggingConfig>,
+    ddl: Option<DecodedDdlConfig>,
 }
 
 impl Default for DecodedConfiguration {
@@ -252,6 +523,8 @@ impl Default for DecodedConfiguration {
             system: Default::default(),
             endpoints: Default::default(),
             auth: None,
+            logging: None,
+            ddl: None,
         }
     }
 }
@@ -260,13 +533,42 @@ impl Default for DecodedConfiguration {
 pub struct DecodedAuth {
     plugin: AuthDriver,
     root_pass: String,
+    #[serde(default)]
+    kdf: ConfigKdf,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Default)]
 /// Decoded system configuration
 pub struct DecodedSystemConfig {
     mode: Option<ConfigMode>,
     rs_window: Option<u64>,
+    tcp_keepalive: Option<u64>,
+    journal_prealloc: Option<u64>,
+    force_downgrade_check_off: Option<bool>,
+    delta_batches: Option<bool>,
+    query_memory_limit: Option<u64>,
+    query_memory_global_limit: Option<u64>,
+    user_write_ops_limit: Option<u64>,
+    user_write_bytes_limit: Option<u64>,
+    row_size_limit: Option<u64>,
+    field_size_limit: Option<u64>,
+    otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Default)]
+/// Decoded logging configuration
+pub struct DecodedLoggingConfig {
+    file: Option<String>,
+    max_size: Option<u64>,
+    rotate_interval: Option<u64>,
+    compress: Option<bool>,
+    retain: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Default)]
+/// Decoded DDL configuration
+pub struct DecodedDdlConfig {
+    trash_retention: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -274,6 +576,9 @@ pub struct DecodedSystemConfig {
 pub struct DecodedEPConfig {
     secure: Option<DecodedEPSecureConfig>,
     insecure: Option<DecodedEPInsecureConfig>,
+    /// in the same form as on the command line (see [`parse_additional_endpoint`])
+    #[serde(default)]
+    additional: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -284,6 +589,10 @@ pub struct DecodedEPSecureConfig {
     cert: String,
     private_key: String,
     pkey_passphrase: String,
+    #[serde(default)]
+    proxy_protocol: bool,
+    #[serde(skip)]
+    files: TlsFiles,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -291,6 +600,8 @@ pub struct DecodedEPSecureConfig {
 pub struct DecodedEPInsecureConfig {
     host: String,
     port: u16,
+    #[serde(default)]
+    proxy_protocol: bool,
 }
 
 impl DecodedEPInsecureConfig {
@@ -298,6 +609,7 @@ impl DecodedEPInsecureConfig {
         Self {
             host: host.to_owned(),
             port,
+            proxy_protocol: false,
         }
     }
 }
@@ -386,6 +698,17 @@ pub(super) trait ConfigurationSource {
     const KEY_ENDPOINTS: &'static str;
     const KEY_RUN_MODE: &'static str;
     const KEY_SERVICE_WINDOW: &'static str;
+    const KEY_TCP_KEEPALIVE: &'static str;
+    const KEY_JOURNAL_PREALLOC: &'static str;
+    const KEY_FORCE_DOWNGRADE_CHECK_OFF: &'static str;
+    const KEY_DELTA_BATCHES: &'static str;
+    const KEY_QUERY_MEMORY_LIMIT: &'static str;
+    const KEY_QUERY_MEMORY_GLOBAL_LIMIT: &'static str;
+    const KEY_USER_WRITE_OPS_LIMIT: &'static str;
+    const KEY_USER_WRITE_BYTES_LIMIT: &'static str;
+    const KEY_ROW_SIZE_LIMIT: &'static str;
+    const KEY_FIELD_SIZE_LIMIT: &'static str;
+    const KEY_OTLP_ENDPOINT: &'static str;
     const SOURCE: ConfigSource;
     /// Formats an error `Invalid value for {key}`
     fn err_invalid_value_for(key: &str) -> ConfigError {
@@ -428,14 +751,61 @@ enum ConnectionProtocol {
     Tls,
 }
 
+/// Parse an endpoint that is listened on besides the main ones: `tcp@host:port`, `tls@host:port` or `uds@path`. A UDS
+/// endpoint can be followed by `?auth=off` to let its clients in without a password
+fn parse_additional_endpoint(
+    source: ConfigSource,
+    s: &str,
+) -> RuntimeResult<ConfigEndpointAdditional> {
+    if let Some(spec) = s.strip_prefix("uds@") {
+        let (path, auth) = match spec.split_once('?') {
+            None => (spec, true),
+            Some((path, "auth=on")) => (path, true),
+            Some((path, "auth=off")) => (path, false),
+            Some(_) => {
+                return Err(ConfigError::with_src(
+                    source,
+                    ConfigErrorKind::ErrorString(
+                        "invalid option for UDS endpoint. should be `auth=on` or `auth=off`".into(),
+                    ),
+                )
+                .into())
+            }
+        };
+        if path.is_empty() {
+            return Err(ConfigError::with_src(
+                source,
+                ConfigErrorKind::ErrorString("missing path for UDS endpoint".into()),
+            )
+            .into());
+        }
+        return Ok(ConfigEndpointAdditional::Uds(ConfigEndpointUds {
+            path: path.into(),
+            auth,
+        }));
+    }
+    let (protocol, host, port) = parse_endpoint(source, s)?;
+    let tcp = ConfigEndpointTcp {
+        host: host.into(),
+        port,
+        proxy_protocol: false,
+    };
+    Ok(match protocol {
+        ConnectionProtocol::Tcp => ConfigEndpointAdditional::Tcp(tcp),
+        ConnectionProtocol::Tls => ConfigEndpointAdditional::Tls(tcp),
+    })
+}
+
 /// Parse an endpoint (`protocol@host:port`)
 fn parse_endpoint(source: ConfigSource, s: &str) -> RuntimeResult<(ConnectionProtocol, &str, u16)> {
     let err = || {
         Err(ConfigError::with_src(
             source,
-            ConfigErrorKind::ErrorString(format!(
-                "invalid endpoint syntax. should be `protocol@hostname:port`"
-            )),
+            ConfigErrorKind::ErrorString(
+                "invalid endpoint syntax. should be `protocol@hostname:port` (IPv6 hosts must be enclosed in `[]`) or \
+                `uds@path`"
+                    .into(),
+            ),
         )
         .into())
     };
@@ -444,11 +814,15 @@ fn parse_endpoint(source: ConfigSource, s: &str) -> RuntimeResult<(ConnectionPro
         return err();
     }
     let [protocol, hostport] = [x[0], x[1]];
-    let hostport = hostport.split(":").collect::<Vec<&str>>();
-    if hostport.len() != 2 {
+    let Some((host, port)) = hostport.rsplit_once(":") else {
         return err();
-    }
-    let [host, port] = [hostport[0], hostport[1]];
+    };
+    let host = match strip_ipv6_brackets(host) {
+        Some(v6) => v6,
+        // an unbracketed IPv6 address is ambiguous with the port
+        None if host.contains(":") => return err(),
+        None => host,
+    };
     let Ok(port) = port.parse::<u16>() else {
         return err();
     };
@@ -460,6 +834,11 @@ fn parse_endpoint(source: ConfigSource, s: &str) -> RuntimeResult<(ConnectionPro
     Ok((protocol, host, port))
 }
 
+/// Returns the address if the host is a bracketed IPv6 address (`[::1]`)
+fn strip_ipv6_brackets(host: &str) -> Option<&str> {
+    host.strip_prefix("[").and_then(|h| h.strip_suffix("]"))
+}
+
 /// Decode a TLS endpoint (read in cert and private key)
 fn decode_tls_ep(
     cert_path: &str,
@@ -478,6 +857,8 @@ fn decode_tls_ep(
         cert: tls_cert,
         private_key: tls_key,
         pkey_passphrase: tls_priv_key_passphrase,
+        proxy_protocol: false,
+        files: TlsFiles::new(cert_path, key_path, pkey_pass),
     })
 }
 
@@ -547,24 +928,29 @@ fn arg_decode_auth<CS: ConfigurationSource>(
     config.auth = Some(DecodedAuth {
         plugin: auth_plugin,
         root_pass: root_key.remove(0),
+        kdf: ConfigKdf::default(),
     });
     Ok(())
 }
 
-/// Decode the endpoints (`protocol@host:port`)
+/// Decode the endpoints (`protocol@host:port` or `uds@path`). The first TCP and the first TLS endpoint are the main
+/// ones, and any others are additional endpoints
 fn arg_decode_endpoints<CS: ConfigurationSource>(
     args: &mut ParsedRawArgs,
     config: &mut ModifyGuard<DecodedConfiguration>,
 ) -> RuntimeResult<()> {
     let mut insecure = None;
     let mut secure = None;
+    let mut additional = vec![];
     let Some(endpoints) = args.remove(CS::KEY_ENDPOINTS) else {
         return Ok(());
     };
-    if endpoints.len() > 2 {
-        return Err(CS::err_too_many_values_for(CS::KEY_ENDPOINTS).into());
-    }
     for ep in endpoints {
+        if ep.starts_with("uds@") {
+            // validated along with the rest of the configuration
+            additional.push(ep);
+            continue;
+        }
         let (proto, host, port) = parse_endpoint(CS::SOURCE, &ep)?;
         match proto {
             ConnectionProtocol::Tcp if insecure.is_none() => {
@@ -573,18 +959,14 @@ fn arg_decode_endpoints<CS: ConfigurationSource>(
             ConnectionProtocol::Tls if secure.is_none() => {
                 secure = Some(arg_decode_tls_endpoint::<CS>(args, host, port)?);
             }
-            _ => {
-                return Err(CS::custom_err(format!(
-                    "duplicate endpoints specified in `{}`",
-                    CS::KEY_ENDPOINTS
-                ))
-                .into());
-            }
+            _ => additional.push(ep),
         }
     }
-    if insecure.is_some() | secure.is_some() {
-        config.endpoints = Some(DecodedEPConfig { secure, insecure });
-    }
+    config.endpoints = Some(DecodedEPConfig {
+        secure,
+        insecure,
+        additional,
+    });
     Ok(())
 }
 
@@ -601,15 +983,7 @@ fn arg_decode_mode<CS: ConfigurationSource>(
         "prod" => ConfigMode::Prod,
         _ => return Err(CS::err_invalid_value_for(CS::KEY_RUN_MODE).into()),
     };
-    match config.system.as_mut() {
-        Some(s) => s.mode = Some(mode),
-        None => {
-            config.system = Some(DecodedSystemConfig {
-                mode: Some(mode),
-                rs_window: None,
-            })
-        }
-    }
+    config.system.get_or_insert_with(Default::default).mode = Some(mode);
     Ok(())
 }
 
@@ -620,20 +994,205 @@ fn arg_decode_rs_window<CS: ConfigurationSource>(
 ) -> RuntimeResult<()> {
     argck_duplicate_values::<CS>(&mode, CS::KEY_SERVICE_WINDOW)?;
     match mode[0].parse::<u64>() {
-        Ok(n) => match config.system.as_mut() {
-            Some(sys) => sys.rs_window = Some(n),
-            None => {
-                config.system = Some(DecodedSystemConfig {
-                    mode: None,
-                    rs_window: Some(n),
-                })
-            }
-        },
+        Ok(n) => config.system.get_or_insert_with(Default::default).rs_window = Some(n),
         Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_SERVICE_WINDOW).into()),
     }
     Ok(())
 }
 
+/// Decode the TCP keepalive idle time
+fn arg_decode_tcp_keepalive<CS: ConfigurationSource>(
+    keepalive: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(keepalive, CS::KEY_TCP_KEEPALIVE)?;
+    match keepalive[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .tcp_keepalive = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_TCP_KEEPALIVE).into()),
+    }
+    Ok(())
+}
+
+/// Decode the journal preallocation extent size
+fn arg_decode_journal_prealloc<CS: ConfigurationSource>(
+    prealloc: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(prealloc, CS::KEY_JOURNAL_PREALLOC)?;
+    match prealloc[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .journal_prealloc = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_JOURNAL_PREALLOC).into()),
+    }
+    Ok(())
+}
+
+/// Decode the switch that disables the downgrade check
+fn arg_decode_force_downgrade_check_off<CS: ConfigurationSource>(
+    switch: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(switch, CS::KEY_FORCE_DOWNGRADE_CHECK_OFF)?;
+    match switch[0].parse::<bool>() {
+        Ok(b) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .force_downgrade_check_off = Some(b)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_FORCE_DOWNGRADE_CHECK_OFF).into()),
+    }
+    Ok(())
+}
+
+/// Decode the switch that enables delta encoded data batches
+fn arg_decode_delta_batches<CS: ConfigurationSource>(
+    switch: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(switch, CS::KEY_DELTA_BATCHES)?;
+    match switch[0].parse::<bool>() {
+        Ok(b) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .delta_batches = Some(b)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_DELTA_BATCHES).into()),
+    }
+    Ok(())
+}
+
+/// Decode the per-query memory limit
+fn arg_decode_query_memory_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(limit, CS::KEY_QUERY_MEMORY_LIMIT)?;
+    match limit[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .query_memory_limit = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_QUERY_MEMORY_LIMIT).into()),
+    }
+    Ok(())
+}
+
+/// Decode the global memory limit for queries
+fn arg_decode_query_memory_global_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(limit, CS::KEY_QUERY_MEMORY_GLOBAL_LIMIT)?;
+    match limit[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .query_memory_global_limit = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_QUERY_MEMORY_GLOBAL_LIMIT).into()),
+    }
+    Ok(())
+}
+
+/// Decode the per-user write query limit
+fn arg_decode_user_write_ops_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(limit, CS::KEY_USER_WRITE_OPS_LIMIT)?;
+    match limit[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .user_write_ops_limit = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_USER_WRITE_OPS_LIMIT).into()),
+    }
+    Ok(())
+}
+
+/// Decode the per-user write bytes limit
+fn arg_decode_user_write_bytes_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(limit, CS::KEY_USER_WRITE_BYTES_LIMIT)?;
+    match limit[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .user_write_bytes_limit = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_USER_WRITE_BYTES_LIMIT).into()),
+    }
+    Ok(())
+}
+
+/// Decode the row size limit
+fn arg_decode_row_size_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(limit, CS::KEY_ROW_SIZE_LIMIT)?;
+    match limit[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .row_size_limit = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_ROW_SIZE_LIMIT).into()),
+    }
+    Ok(())
+}
+
+/// Decode the field size limit
+fn arg_decode_field_size_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(limit, CS::KEY_FIELD_SIZE_LIMIT)?;
+    match limit[0].parse::<u64>() {
+        Ok(n) => {
+            config
+                .system
+                .get_or_insert_with(Default::default)
+                .field_size_limit = Some(n)
+        }
+        Err(_) => return Err(CS::err_invalid_value_for(CS::KEY_FIELD_SIZE_LIMIT).into()),
+    }
+    Ok(())
+}
+
+/// Decode the OTLP endpoint
+fn arg_decode_otlp_endpoint<CS: ConfigurationSource>(
+    endpoint: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(endpoint, CS::KEY_OTLP_ENDPOINT)?;
+    config
+        .system
+        .get_or_insert_with(Default::default)
+        .otlp_endpoint = Some(endpoint[0].clone());
+    Ok(())
+}
+
 /*
     CLI args process
 */
@@ -667,6 +1226,7 @@ impl<T> CLIConfigParseReturn<T> {
 /// Parse CLI args:
 /// - `--{option} {value}`
 /// - `--{option}={value}`
+/// - `--{switch}` (same as `--{switch}=true`)
 pub fn parse_cli_args<'a, T: 'a + AsRef<str>>(
     src: impl Iterator<Item = T>,
 ) -> RuntimeResult<CLIConfigParseReturn<ParsedRawArgs>> {
@@ -687,22 +1247,28 @@ pub fn parse_cli_args<'a, T: 'a + AsRef<str>>(
             )
             .into());
         }
+        if CSCommandLine::SWITCHES.contains(&arg) {
+            // a switch doesn't take a value
+            cli_args
+                .entry(arg.to_string())
+                .or_default()
+                .push("true".into());
+            continue;
+        }
         // x=1
         let arg_key;
         let arg_val;
-        let splits_arg_and_value = arg.split("=").collect::<Vec<&str>>();
-        if (splits_arg_and_value.len() == 2) & (arg.len() >= 5) {
-            // --{n}={x}; good
-            arg_key = splits_arg_and_value[0];
-            arg_val = splits_arg_and_value[1].to_string();
-        } else if splits_arg_and_value.len() != 1 {
-            // that's an invalid argument since the split is either `x=y` or `x` and we don't have any args
-            // with special characters
-            return Err(ConfigError::with_src(
-                ConfigSource::Cli,
-                ConfigErrorKind::ErrorString(format!("incorrectly formatted argument `{arg}`")),
-            )
-            .into());
+        if let Some((key, value)) = arg.split_once('=') {
+            if (key.len() < 3) | value.is_empty() {
+                return Err(ConfigError::with_src(
+                    ConfigSource::Cli,
+                    ConfigErrorKind::ErrorString(format!("incorrectly formatted argument `{arg}`")),
+                )
+                .into());
+            }
+            // --{n}={x}; good (the value itself may contain a `=`)
+            arg_key = key;
+            arg_val = value.to_string();
         } else {
             let Some(value) = args_iter.next() else {
                 return Err(ConfigError::with_src(
@@ -737,12 +1303,23 @@ pub fn parse_cli_args<'a, T: 'a + AsRef<str>>(
 
 /// Parse environment variables
 pub fn parse_env_args() -> RuntimeResult<Option<ParsedRawArgs>> {
-    const KEYS: [&str; 8] = [
+    const KEYS: [&str; 19] = [
         CSEnvArgs::KEY_AUTH_DRIVER,
         CSEnvArgs::KEY_AUTH_ROOT_PASSWORD,
+        CSEnvArgs::KEY_DELTA_BATCHES,
         CSEnvArgs::KEY_ENDPOINTS,
+        CSEnvArgs::KEY_FIELD_SIZE_LIMIT,
+        CSEnvArgs::KEY_FORCE_DOWNGRADE_CHECK_OFF,
+        CSEnvArgs::KEY_JOURNAL_PREALLOC,
+        CSEnvArgs::KEY_OTLP_ENDPOINT,
+        CSEnvArgs::KEY_QUERY_MEMORY_GLOBAL_LIMIT,
+        CSEnvArgs::KEY_QUERY_MEMORY_LIMIT,
+        CSEnvArgs::KEY_ROW_SIZE_LIMIT,
         CSEnvArgs::KEY_RUN_MODE,
+        CSEnvArgs::KEY_USER_WRITE_BYTES_LIMIT,
+        CSEnvArgs::KEY_USER_WRITE_OPS_LIMIT,
         CSEnvArgs::KEY_SERVICE_WINDOW,
+        CSEnvArgs::KEY_TCP_KEEPALIVE,
         CSEnvArgs::KEY_TLS_CERT,
         CSEnvArgs::KEY_TLS_KEY,
         CSEnvArgs::KEY_TLS_PKEY_PASS,
@@ -772,6 +1349,702 @@ pub fn parse_env_args() -> RuntimeResult<Option<ParsedRawArgs>> {
     }
 }
 
+/*
+    config key overrides
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The type (and accepted values) of a configuration file key
+pub(super) enum ConfigKeyKind {
+    /// any string
+    String,
+    /// one of the given strings
+    Choice(&'static [&'static str]),
+    /// a string with at least the given number of characters
+    Secret(usize),
+    /// a path to an existing file
+    Path,
+    /// an integer in `[min, max]` that is a multiple of `align`
+    Integer { min: u64, max: u64, align: u64 },
+    /// a boolean
+    Boolean,
+    /// a duration (see [`parse_duration`])
+    Duration,
+    /// a list of endpoints (see [`parse_additional_endpoint`])
+    Endpoints,
+}
+
+impl ConfigKeyKind {
+    const fn int(min: u64, max: u64) -> Self {
+        Self::Integer { min, max, align: 1 }
+    }
+    /// Describe the accepted values
+    fn accepts(&self) -> String {
+        match *self {
+            Self::String => "a string".into(),
+            Self::Choice(choices) => format!(
+                "one of {}",
+                choices
+                    .iter()
+                    .map(|c| format!("`{c}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Secret(min_len) => format!("a string with at least {min_len} characters"),
+            Self::Path => "a path to an existing file".into(),
+            Self::Integer { min, max, align } => {
+                let mut ret = if max == u64::MAX {
+                    format!("an integer >= {min}")
+                } else {
+                    format!("an integer between {min} and {max}")
+                };
+                if align > 1 {
+                    ret.push_str(&format!(" that is a multiple of {align}"));
+                }
+                ret
+            }
+            Self::Boolean => "`true` or `false`".into(),
+            Self::Duration => "a duration such as `90s`, `30m`, `24h` or `7d`".into(),
+            Self::Endpoints => {
+                "a list of endpoints such as `tcp@[::1]:2003`, `tls@0.0.0.0:2004` or `uds@/run/skyd.sock`".into()
+            }
+        }
+    }
+    /// Convert a raw override value into a value of this type. The value is not validated
+    fn parse_raw(&self, raw: String) -> Option<serde_yaml::Value> {
+        use serde_yaml::Value;
+        match self {
+            Self::String | Self::Choice(_) | Self::Secret(_) | Self::Path | Self::Duration => {
+                Some(Value::String(raw))
+            }
+            Self::Integer { .. } => raw.parse::<u64>().ok().map(|n| Value::Number(n.into())),
+            Self::Boolean => raw.parse::<bool>().ok().map(Value::Bool),
+            Self::Endpoints => Some(Value::Sequence(
+                raw.split(',').map(|ep| Value::String(ep.into())).collect(),
+            )),
+        }
+    }
+    /// Check if the value is acceptable for this type
+    fn check(&self, v: &serde_yaml::Value) -> bool {
+        match *self {
+            Self::String => v.is_string(),
+            Self::Choice(choices) => v.as_str().is_some_and(|v| choices.contains(&v)),
+            Self::Secret(min_len) => v.as_str().is_some_and(|v| v.len() >= min_len),
+            Self::Path => v
+                .as_str()
+                .is_some_and(|v| std::path::Path::new(v).is_file()),
+            Self::Integer { min, max, align } => v
+                .as_u64()
+                .is_some_and(|v| (v >= min) & (v <= max) & (v % align == 0)),
+            Self::Boolean => v.is_bool(),
+            Self::Duration => v.as_str().and_then(parse_duration).is_some(),
+            Self::Endpoints => v.as_sequence().is_some_and(|eps| {
+                eps.iter().all(|ep| {
+                    ep.as_str()
+                        .is_some_and(|ep| parse_additional_endpoint(ConfigSource::File, ep).is_ok())
+                })
+            }),
+        }
+    }
+}
+
+/// Parse a duration with a unit (`s`, `m`, `h` or `d`) like `24h` into seconds
+pub(super) fn parse_duration(raw: &str) -> Option<u64> {
+    let unit = match raw.as_bytes().last()? {
+        b's' => 1,
+        b'm' => 60,
+        b'h' => 60 * 60,
+        b'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    raw[..raw.len() - 1].parse::<u64>().ok()?.checked_mul(unit)
+}
+
+#[derive(Debug)]
+/// A key in the configuration file
+pub(super) struct ConfigKey {
+    pub key: &'static str,
+    pub kind: ConfigKeyKind,
+    /// must be set if its section is present
+    pub required: bool,
+    /// the value written to the default configuration file (if unset, the key is commented out)
+    pub default: Option<&'static str>,
+    pub description: &'static str,
+}
+
+impl ConfigKey {
+    const fn new(
+        key: &'static str,
+        kind: ConfigKeyKind,
+        required: bool,
+        default: Option<&'static str>,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            key,
+            kind,
+            required,
+            default,
+            description,
+        }
+    }
+    fn get(key: &str) -> Option<&'static Self> {
+        CONFIG_FILE_KEYS.iter().find(|k| k.key == key)
+    }
+    /// the section that this key belongs to
+    fn section(&self) -> &'static str {
+        self.key.rsplit_once('.').map_or("", |(section, _)| section)
+    }
+}
+
+/// Every key in the configuration file. Each of these can be overridden with `--{key}={value}` on the command line
+/// or with an environment variable (see [`config_key_env_var`])
+pub(super) static CONFIG_FILE_KEYS: [ConfigKey; 35] = [
+    ConfigKey::new(
+        "system.mode",
+        ConfigKeyKind::Choice(&["dev", "prod"]),
+        false,
+        Some("dev"),
+        "the mode the server runs in",
+    ),
+    ConfigKey::new(
+        "system.rs_window",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        Some("300"),
+        "time window in seconds for the reliability system to kick-in automatically",
+    ),
+    ConfigKey::new(
+        "system.tcp_keepalive",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "idle time in seconds after which the OS starts sending TCP keepalive probes (disabled if unset)",
+    ),
+    ConfigKey::new(
+        "system.journal_prealloc",
+        ConfigKeyKind::Integer {
+            min: JOURNAL_PREALLOC_ALIGN,
+            max: u64::MAX,
+            align: JOURNAL_PREALLOC_ALIGN,
+        },
+        false,
+        None,
+        "size in bytes of the extents used to preallocate space for journals (Linux only, disabled if unset)",
+    ),
+    ConfigKey::new(
+        "system.force_downgrade_check_off",
+        ConfigKeyKind::Boolean,
+        false,
+        None,
+        "start even if the data directory was last written by a newer, incompatible version (experts only!)",
+    ),
+    ConfigKey::new(
+        "system.delta_batches",
+        ConfigKeyKind::Boolean,
+        false,
+        None,
+        "only write the fields that changed for updates to data files (for wide models with small updates)",
+    ),
+    ConfigKey::new(
+        "system.query_memory_limit",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the maximum transient memory in bytes (for example, for result sets) that a single query can use (unlimited if unset)",
+    ),
+    ConfigKey::new(
+        "system.query_memory_global_limit",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the maximum transient memory in bytes that all running queries can use together (unlimited if unset)",
+    ),
+    ConfigKey::new(
+        "system.user_write_ops_limit",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the maximum number of write queries that a user (other than root) can run every second (unlimited if unset)",
+    ),
+    ConfigKey::new(
+        "system.user_write_bytes_limit",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the maximum number of bytes that a user (other than root) can send with write queries every second (unlimited if unset)",
+    ),
+    ConfigKey::new(
+        "system.row_size_limit",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the maximum encoded size in bytes of a single row that can be inserted or updated (unlimited if unset)",
+    ),
+    ConfigKey::new(
+        "system.field_size_limit",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the maximum size in bytes of a single string or binary value that can be inserted or updated (unlimited if unset)",
+    ),
+    ConfigKey::new(
+        "system.otlp_endpoint",
+        ConfigKeyKind::String,
+        false,
+        None,
+        "the OTLP/HTTP collector (`http://host:port`) that statement traces are exported to (disabled if unset)",
+    ),
+    ConfigKey::new(
+        "auth.plugin",
+        ConfigKeyKind::Choice(&["pwd"]),
+        true,
+        Some("pwd"),
+        "the authentication plugin",
+    ),
+    ConfigKey::new(
+        "auth.root_pass",
+        ConfigKeyKind::Secret(ROOT_PASSWORD_MIN_LEN),
+        true,
+        None,
+        "the password for the root account",
+    ),
+    ConfigKey::new(
+        "auth.kdf.algorithm",
+        ConfigKeyKind::Choice(&["argon2id", "bcrypt"]),
+        true,
+        Some("argon2id"),
+        "the password hashing algorithm. existing hashes are upgraded when users log in",
+    ),
+    ConfigKey::new(
+        "auth.kdf.memory",
+        ConfigKeyKind::int(8, u32::MAX as u64),
+        false,
+        Some("19456"),
+        "argon2id memory cost in KiB",
+    ),
+    ConfigKey::new(
+        "auth.kdf.iterations",
+        ConfigKeyKind::int(1, u32::MAX as u64),
+        false,
+        Some("2"),
+        "argon2id number of passes",
+    ),
+    ConfigKey::new(
+        "auth.kdf.parallelism",
+        ConfigKeyKind::int(1, 0xFFFFFF),
+        false,
+        Some("1"),
+        "argon2id degree of parallelism",
+    ),
+    ConfigKey::new(
+        "endpoints.secure.host",
+        ConfigKeyKind::String,
+        true,
+        None,
+        "the host for the TLS endpoint",
+    ),
+    ConfigKey::new(
+        "endpoints.secure.port",
+        ConfigKeyKind::int(1, u16::MAX as u64),
+        true,
+        None,
+        "the port for the TLS endpoint",
+    ),
+    ConfigKey::new(
+        "endpoints.secure.cert",
+        ConfigKeyKind::Path,
+        true,
+        None,
+        "path to the TLS certificate",
+    ),
+    ConfigKey::new(
+        "endpoints.secure.private_key",
+        ConfigKeyKind::Path,
+        true,
+        None,
+        "path to the TLS private key",
+    ),
+    ConfigKey::new(
+        "endpoints.secure.pkey_passphrase",
+        ConfigKeyKind::Path,
+        true,
+        None,
+        "path to a file containing the passphrase for the TLS private key",
+    ),
+    ConfigKey::new(
+        "endpoints.secure.proxy_protocol",
+        ConfigKeyKind::Boolean,
+        false,
+        None,
+        "expect PROXY protocol v2 headers on the TLS endpoint (disabled if unset)",
+    ),
+    ConfigKey::new(
+        "endpoints.insecure.host",
+        ConfigKeyKind::String,
+        true,
+        Some("127.0.0.1"),
+        "the host for the TCP endpoint",
+    ),
+    ConfigKey::new(
+        "endpoints.insecure.port",
+        ConfigKeyKind::int(1, u16::MAX as u64),
+        true,
+        Some("2003"),
+        "the port for the TCP endpoint",
+    ),
+    ConfigKey::new(
+        "endpoints.insecure.proxy_protocol",
+        ConfigKeyKind::Boolean,
+        false,
+        None,
+        "expect PROXY protocol v2 headers on the TCP endpoint (disabled if unset)",
+    ),
+    ConfigKey::new(
+        "endpoints.additional",
+        ConfigKeyKind::Endpoints,
+        false,
+        None,
+        "more endpoints to listen on. `tls` endpoints use the certificate of the TLS endpoint, and `uds` endpoints can \
+        let clients in without a password with `?auth=off` (as in `uds@/run/skyd.sock?auth=off`)",
+    ),
+    ConfigKey::new(
+        "logging.file",
+        ConfigKeyKind::String,
+        false,
+        None,
+        "the file that logs are written to (logs go to stderr if unset)",
+    ),
+    ConfigKey::new(
+        "logging.max_size",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "size in bytes after which the log file is rotated (disabled if unset)",
+    ),
+    ConfigKey::new(
+        "logging.rotate_interval",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "time in seconds after which the log file is rotated (disabled if unset)",
+    ),
+    ConfigKey::new(
+        "logging.compress",
+        ConfigKeyKind::Boolean,
+        false,
+        None,
+        "gzip rotated log files (enabled if unset)",
+    ),
+    ConfigKey::new(
+        "logging.retain",
+        ConfigKeyKind::int(1, u64::MAX),
+        false,
+        None,
+        "the number of rotated log files to keep (7 if unset)",
+    ),
+    ConfigKey::new(
+        "ddl.trash_retention",
+        ConfigKeyKind::Duration,
+        false,
+        None,
+        "how long dropped models are kept in the trash before they're purged (deleted right away if unset)",
+    ),
+];
+
+/// Returns the environment variable that overrides the given configuration file key. For example, `system.rs_window`
+/// becomes `SKYDB_SYSTEM__RS_WINDOW`
+pub(super) fn config_key_env_var(key: &str) -> String {
+    format!("SKYDB_{}", key.to_ascii_uppercase().replace('.', "__"))
+}
+
+/// A list of `(key, value)` overrides for configuration file keys
+type ConfigOverrides = Vec<(&'static str, String)>;
+
+/// Parse overrides from environment variables
+fn parse_env_overrides() -> RuntimeResult<ConfigOverrides> {
+    let mut overrides = vec![];
+    for ConfigKey { key, .. } in CONFIG_FILE_KEYS.iter() {
+        let var = config_key_env_var(key);
+        match get_var_from_store(&var) {
+            Ok(v) => overrides.push((*key, v)),
+            Err(std::env::VarError::NotPresent) => {}
+            Err(std::env::VarError::NotUnicode(_)) => {
+                return Err(CSEnvArgs::err_invalid_value_for(&var).into())
+            }
+        }
+    }
+    Ok(overrides)
+}
+
+/// Remove all the `--{key}={value}` overrides from the parsed CLI args
+fn extract_cli_overrides(args: &mut ParsedRawArgs) -> RuntimeResult<ConfigOverrides> {
+    let mut overrides = vec![];
+    let override_keys: Vec<String> = args
+        .keys()
+        .filter(|arg| arg.contains('.'))
+        .cloned()
+        .collect();
+    for arg in override_keys {
+        let Some(ConfigKey { key, .. }) = ConfigKey::get(&arg[2..]) else {
+            return Err(
+                CSCommandLine::custom_err(format!("unknown configuration key `{arg}`")).into(),
+            );
+        };
+        let values = args.remove(&arg).unwrap();
+        if values.len() != 1 {
+            return Err(CSCommandLine::err_too_many_values_for(&arg).into());
+        }
+        overrides.push((*key, values.into_iter().next().unwrap()));
+    }
+    Ok(overrides)
+}
+
+/// Apply the overrides to the given configuration tree
+fn apply_overrides<CS: ConfigurationSource>(
+    config: &mut serde_yaml::Value,
+    overrides: ConfigOverrides,
+) -> RuntimeResult<()> {
+    use serde_yaml::{Mapping, Value};
+    for (key, raw_value) in overrides {
+        let Some(value) = ConfigKey::get(key).unwrap().kind.parse_raw(raw_value) else {
+            return Err(CS::err_invalid_value_for(key).into());
+        };
+        let mut node = &mut *config;
+        for part in key.split('.') {
+            if !node.is_mapping() {
+                *node = Value::Mapping(Mapping::new());
+            }
+            let map = node.as_mapping_mut().unwrap();
+            if !map.contains_key(part) {
+                map.insert(part.into(), Value::Null);
+            }
+            node = map.get_mut(part).unwrap();
+        }
+        *node = value;
+    }
+    Ok(())
+}
+
+/*
+    config subcommands
+*/
+
+#[derive(Debug, PartialEq)]
+/// A problem found while validating a configuration file
+pub struct ConfigIssue {
+    /// the line (if known)
+    line: Option<usize>,
+    message: String,
+}
+
+impl ConfigIssue {
+    fn new(line: Option<usize>, message: String) -> Self {
+        Self { line, message }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Find the line on which each key (or section) is defined in the configuration file
+fn config_key_lines(src: &str) -> HashMap<String, usize> {
+    let mut ret = HashMap::new();
+    // (indent, key)
+    let mut stack: Vec<(usize, &str)> = vec![];
+    for (line_no, line) in src.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() | trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let Some((key, _)) = trimmed.split_once(':') else {
+            continue;
+        };
+        while stack.last().is_some_and(|(i, _)| *i >= indent) {
+            stack.pop();
+        }
+        stack.push((indent, key.trim()));
+        let path = stack.iter().map(|(_, k)| *k).collect::<Vec<_>>().join(".");
+        ret.entry(path).or_insert(line_no + 1);
+    }
+    ret
+}
+
+/// A short description of a value for error messages
+fn config_value_repr(v: &serde_yaml::Value) -> String {
+    use serde_yaml::Value;
+    match v {
+        Value::Null => "null".into(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("\"{s}\""),
+        Value::Sequence(_) => "a list".into(),
+        Value::Mapping(_) => "a section".into(),
+        Value::Tagged(_) => "a tagged value".into(),
+    }
+}
+
+/// Validate the given configuration file, returning every problem found
+pub fn validate_config_file(src: &str) -> Vec<ConfigIssue> {
+    use serde_yaml::Value;
+    let tree: Value = match serde_yaml::from_str(src) {
+        Ok(tree) => tree,
+        Err(e) => {
+            return vec![ConfigIssue::new(
+                e.location().map(|l| l.line()),
+                format!("invalid YAML: {e}"),
+            )]
+        }
+    };
+    let lines = config_key_lines(src);
+    let line_of = |key: &str| lines.get(key).copied();
+    let mut issues = vec![];
+    // (path, value)
+    let mut stack = vec![(String::new(), &tree)];
+    let mut present = vec![];
+    while let Some((section, node)) = stack.pop() {
+        let map = match node {
+            Value::Mapping(map) => map,
+            Value::Null => continue,
+            v => {
+                let message = match section.as_str() {
+                    "" => "the configuration must be a set of `key: value` pairs".into(),
+                    section => format!(
+                        "`{section}` must be a section but found {}",
+                        config_value_repr(v)
+                    ),
+                };
+                issues.push(ConfigIssue::new(line_of(&section), message));
+                continue;
+            }
+        };
+        for (key, value) in map {
+            let Some(key) = key.as_str() else {
+                issues.push(ConfigIssue::new(
+                    line_of(&section),
+                    format!("found a non-string key in `{section}`"),
+                ));
+                continue;
+            };
+            let path = match section.as_str() {
+                "" => key.to_owned(),
+                section => format!("{section}.{key}"),
+            };
+            present.push(path.clone());
+            if let Some(cfg_key) = ConfigKey::get(&path) {
+                if !cfg_key.kind.check(value) {
+                    issues.push(ConfigIssue::new(
+                        line_of(&path),
+                        format!(
+                            "invalid value {} for `{path}`. expected {}",
+                            config_value_repr(value),
+                            cfg_key.kind.accepts()
+                        ),
+                    ));
+                }
+            } else if CONFIG_FILE_KEYS
+                .iter()
+                .any(|k| k.key.starts_with(&path) && k.key[path.len()..].starts_with('.'))
+            {
+                stack.push((path, value));
+            } else {
+                issues.push(ConfigIssue::new(
+                    line_of(&path),
+                    format!("unknown key `{path}`"),
+                ));
+            }
+        }
+    }
+    // look for missing keys
+    if !present.iter().any(|k| k == "auth") {
+        issues.push(ConfigIssue::new(
+            None,
+            "missing required section `auth`".into(),
+        ));
+    }
+    for key in CONFIG_FILE_KEYS.iter().filter(|k| k.required) {
+        let section = key.section();
+        if present.iter().any(|k| k == section) && !present.iter().any(|k| k == key.key) {
+            issues.push(ConfigIssue::new(
+                line_of(section),
+                format!(
+                    "missing required key `{}` ({}). expected {}",
+                    key.key,
+                    key.description,
+                    key.kind.accepts()
+                ),
+            ));
+        }
+    }
+    issues.sort_by_key(|issue| issue.line);
+    if issues.is_empty() {
+        // anything else that the decoder doesn't like
+        if let Err(e) = serde_yaml::from_value::<DecodedConfiguration>(tree) {
+            issues.push(ConfigIssue::new(None, format!("{e}")));
+        }
+    }
+    issues
+}
+
+/// Generate the default configuration file, with every key documented
+pub fn default_config_file() -> String {
+    let mut ret = String::from(
+        "# Skytable configuration file (generated by `skyd config defaults`)\n\
+        #\n\
+        # every key can also be set with an environment variable (`system.mode` -> `SKYDB_SYSTEM__MODE`)\n\
+        # or a CLI option (`--system.mode=prod`). environment variables override this file and CLI options\n\
+        # override both. keys that are commented out aren't set by default\n",
+    );
+    let mut current_section: Vec<&str> = vec![];
+    for key in CONFIG_FILE_KEYS.iter() {
+        let parts: Vec<&str> = key.key.split('.').collect();
+        let (leaf, section) = parts.split_last().unwrap();
+        let common = current_section
+            .iter()
+            .zip(section.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        for depth in common..section.len() {
+            let section_path = section[..=depth].join(".");
+            let has_defaults = CONFIG_FILE_KEYS.iter().any(|k| {
+                k.default.is_some()
+                    && k.key.starts_with(&section_path)
+                    && k.key[section_path.len()..].starts_with('.')
+            });
+            if depth == 0 {
+                ret.push('\n');
+            }
+            let comment = if has_defaults { "" } else { "# " };
+            ret.push_str(&format!(
+                "{:indent$}{comment}{}:\n",
+                "",
+                section[depth],
+                indent = depth * 2
+            ));
+        }
+        current_section = section.to_vec();
+        let indent = section.len() * 2;
+        ret.push_str(&format!("{:indent$}# {}\n", "", key.description));
+        ret.push_str(&format!(
+            "{:indent$}# accepts: {}\n",
+            "",
+            key.kind.accepts()
+        ));
+        match key.default {
+            Some(default) => ret.push_str(&format!("{:indent$}{leaf}: {default}\n", "")),
+            None => ret.push_str(&format!("{:indent$}# {leaf}:\n", "")),
+        }
+    }
+    ret
+}
+
 /*
     apply config changes
 */
@@ -805,6 +2078,58 @@ fn apply_config_changes<CS: ConfigurationSource>(
             key: CS::KEY_SERVICE_WINDOW,
             f: arg_decode_rs_window::<CS>,
         },
+        // tcp keepalive
+        DecodeKind::Simple {
+            key: CS::KEY_TCP_KEEPALIVE,
+            f: arg_decode_tcp_keepalive::<CS>,
+        },
+        // journal preallocation
+        DecodeKind::Simple {
+            key: CS::KEY_JOURNAL_PREALLOC,
+            f: arg_decode_journal_prealloc::<CS>,
+        },
+        // downgrade check
+        DecodeKind::Simple {
+            key: CS::KEY_FORCE_DOWNGRADE_CHECK_OFF,
+            f: arg_decode_force_downgrade_check_off::<CS>,
+        },
+        // delta batches
+        DecodeKind::Simple {
+            key: CS::KEY_DELTA_BATCHES,
+            f: arg_decode_delta_batches::<CS>,
+        },
+        // query memory limits
+        DecodeKind::Simple {
+            key: CS::KEY_QUERY_MEMORY_LIMIT,
+            f: arg_decode_query_memory_limit::<CS>,
+        },
+        DecodeKind::Simple {
+            key: CS::KEY_QUERY_MEMORY_GLOBAL_LIMIT,
+            f: arg_decode_query_memory_global_limit::<CS>,
+        },
+        // user write quotas
+        DecodeKind::Simple {
+            key: CS::KEY_USER_WRITE_OPS_LIMIT,
+            f: arg_decode_user_write_ops_limit::<CS>,
+        },
+        DecodeKind::Simple {
+            key: CS::KEY_USER_WRITE_BYTES_LIMIT,
+            f: arg_decode_user_write_bytes_limit::<CS>,
+        },
+        // size limits
+        DecodeKind::Simple {
+            key: CS::KEY_ROW_SIZE_LIMIT,
+            f: arg_decode_row_size_limit::<CS>,
+        },
+        DecodeKind::Simple {
+            key: CS::KEY_FIELD_SIZE_LIMIT,
+            f: arg_decode_field_size_limit::<CS>,
+        },
+        // tracing
+        DecodeKind::Simple {
+            key: CS::KEY_OTLP_ENDPOINT,
+            f: arg_decode_otlp_endpoint::<CS>,
+        },
         // endpoints
         DecodeKind::Complex {
             f: arg_decode_endpoints::<CS>,
@@ -840,6 +2165,9 @@ fn apply_config_changes<CS: ConfigurationSource>(
 pub struct CSCommandLine;
 impl CSCommandLine {
     const ARG_CONFIG_FILE: &'static str = "--config";
+    /// options that don't take a value
+    const SWITCHES: [&'static str; 2] =
+        [Self::KEY_FORCE_DOWNGRADE_CHECK_OFF, Self::KEY_DELTA_BATCHES];
 }
 impl ConfigurationSource for CSCommandLine {
     const KEY_AUTH_DRIVER: &'static str = "--auth-plugin";
@@ -850,6 +2178,17 @@ impl ConfigurationSource for CSCommandLine {
     const KEY_ENDPOINTS: &'static str = "--endpoint";
     const KEY_RUN_MODE: &'static str = "--mode";
     const KEY_SERVICE_WINDOW: &'static str = "--service-window";
+    const KEY_TCP_KEEPALIVE: &'static str = "--tcp-keepalive";
+    const KEY_JOURNAL_PREALLOC: &'static str = "--journal-prealloc";
+    const KEY_FORCE_DOWNGRADE_CHECK_OFF: &'static str = "--force-downgrade-check-off";
+    const KEY_DELTA_BATCHES: &'static str = "--delta-batches";
+    const KEY_QUERY_MEMORY_LIMIT: &'static str = "--query-memory-limit";
+    const KEY_QUERY_MEMORY_GLOBAL_LIMIT: &'static str = "--query-memory-global-limit";
+    const KEY_USER_WRITE_OPS_LIMIT: &'static str = "--user-write-ops-limit";
+    const KEY_USER_WRITE_BYTES_LIMIT: &'static str = "--user-write-bytes-limit";
+    const KEY_ROW_SIZE_LIMIT: &'static str = "--row-size-limit";
+    const KEY_FIELD_SIZE_LIMIT: &'static str = "--field-size-limit";
+    const KEY_OTLP_ENDPOINT: &'static str = "--otlp-endpoint";
     const SOURCE: ConfigSource = ConfigSource::Cli;
 }
 
@@ -863,19 +2202,41 @@ impl ConfigurationSource for CSEnvArgs {
     const KEY_ENDPOINTS: &'static str = "SKYDB_ENDPOINTS";
     const KEY_RUN_MODE: &'static str = "SKYDB_RUN_MODE";
     const KEY_SERVICE_WINDOW: &'static str = "SKYDB_SERVICE_WINDOW";
+    const KEY_TCP_KEEPALIVE: &'static str = "SKYDB_TCP_KEEPALIVE";
+    const KEY_JOURNAL_PREALLOC: &'static str = "SKYDB_JOURNAL_PREALLOC";
+    const KEY_FORCE_DOWNGRADE_CHECK_OFF: &'static str = "SKYDB_FORCE_DOWNGRADE_CHECK_OFF";
+    const KEY_DELTA_BATCHES: &'static str = "SKYDB_DELTA_BATCHES";
+    const KEY_QUERY_MEMORY_LIMIT: &'static str = "SKYDB_QUERY_MEMORY_LIMIT";
+    const KEY_QUERY_MEMORY_GLOBAL_LIMIT: &'static str = "SKYDB_QUERY_MEMORY_GLOBAL_LIMIT";
+    const KEY_USER_WRITE_OPS_LIMIT: &'static str = "SKYDB_USER_WRITE_OPS_LIMIT";
+    const KEY_USER_WRITE_BYTES_LIMIT: &'static str = "SKYDB_USER_WRITE_BYTES_LIMIT";
+    const KEY_ROW_SIZE_LIMIT: &'static str = "SKYDB_ROW_SIZE_LIMIT";
+    const KEY_FIELD_SIZE_LIMIT: &'static str = "SKYDB_FIELD_SIZE_LIMIT";
+    const KEY_OTLP_ENDPOINT: &'static str = "SKYDB_OTLP_ENDPOINT";
     const SOURCE: ConfigSource = ConfigSource::Env;
 }
 
 pub struct CSConfigFile;
 impl ConfigurationSource for CSConfigFile {
     const KEY_AUTH_DRIVER: &'static str = "auth.plugin";
-    const KEY_AUTH_ROOT_PASSWORD: &'static str = "auth.root_password";
+    const KEY_AUTH_ROOT_PASSWORD: &'static str = "auth.root_pass";
     const KEY_TLS_CERT: &'static str = "endpoints.secure.cert";
-    const KEY_TLS_KEY: &'static str = "endpoints.secure.key";
+    const KEY_TLS_KEY: &'static str = "endpoints.secure.private_key";
     const KEY_TLS_PKEY_PASS: &'static str = "endpoints.secure.pkey_passphrase";
     const KEY_ENDPOINTS: &'static str = "endpoints";
     const KEY_RUN_MODE: &'static str = "system.mode";
-    const KEY_SERVICE_WINDOW: &'static str = "system.service_window";
+    const KEY_SERVICE_WINDOW: &'static str = "system.rs_window";
+    const KEY_TCP_KEEPALIVE: &'static str = "system.tcp_keepalive";
+    const KEY_JOURNAL_PREALLOC: &'static str = "system.journal_prealloc";
+    const KEY_FORCE_DOWNGRADE_CHECK_OFF: &'static str = "system.force_downgrade_check_off";
+    const KEY_DELTA_BATCHES: &'static str = "system.delta_batches";
+    const KEY_QUERY_MEMORY_LIMIT: &'static str = "system.query_memory_limit";
+    const KEY_QUERY_MEMORY_GLOBAL_LIMIT: &'static str = "system.query_memory_global_limit";
+    const KEY_USER_WRITE_OPS_LIMIT: &'static str = "system.user_write_ops_limit";
+    const KEY_USER_WRITE_BYTES_LIMIT: &'static str = "system.user_write_bytes_limit";
+    const KEY_ROW_SIZE_LIMIT: &'static str = "system.row_size_limit";
+    const KEY_FIELD_SIZE_LIMIT: &'static str = "system.field_size_limit";
+    const KEY_OTLP_ENDPOINT: &'static str = "system.otlp_endpoint";
     const SOURCE: ConfigSource = ConfigSource::File;
 }
 
@@ -903,6 +2264,8 @@ fn validate_configuration<CS: ConfigurationSource>(
         system,
         endpoints,
         auth,
+        logging,
+        ddl,
     }: DecodedConfiguration,
 ) -> RuntimeResult<Configuration> {
     let Some(auth) = auth else {
@@ -917,28 +2280,45 @@ fn validate_configuration<CS: ConfigurationSource>(
     };
     // initialize our default configuration
     let mut config = Configuration::default_dev_mode(auth);
+    let mut additional_endpoints = vec![];
     // mutate
     if_some!(
         system => |system: DecodedSystemConfig| {
             if_some!(system.mode => |mode| config.mode = mode);
             if_some!(system.rs_window => |window| config.system.reliability_system_window = window);
+            if_some!(system.tcp_keepalive => |keepalive| config.system.tcp_keepalive = Some(keepalive));
+            if_some!(system.journal_prealloc => |prealloc| config.system.journal_prealloc = Some(prealloc));
+            if_some!(system.force_downgrade_check_off => |off| config.system.force_downgrade_check_off = off);
+            if_some!(system.delta_batches => |delta| config.system.delta_batches = delta);
+            if_some!(system.query_memory_limit => |limit| config.system.query_memory_limit = Some(limit));
+            if_some!(system.query_memory_global_limit => |limit| config.system.query_memory_global_limit = Some(limit));
+            if_some!(system.user_write_ops_limit => |limit| config.system.user_write_ops_limit = Some(limit));
+            if_some!(system.user_write_bytes_limit => |limit| config.system.user_write_bytes_limit = Some(limit));
+            if_some!(system.row_size_limit => |limit| config.system.row_size_limit = Some(limit));
+            if_some!(system.field_size_limit => |limit| config.system.field_size_limit = Some(limit));
+            if_some!(system.otlp_endpoint => |endpoint| config.system.otlp_endpoint = Some(endpoint));
         }
     );
     if_some!(
         endpoints => |ep: DecodedEPConfig| {
+            additional_endpoints = ep.additional;
             let has_insecure = ep.insecure.is_some();
+            // the config file may use the bracketed IPv6 form as well
+            let host = |h: String| strip_ipv6_brackets(&h).map(ToOwned::to_owned).unwrap_or(h);
             if_some!(ep.insecure => |insecure: DecodedEPInsecureConfig| {
-                config.endpoints = ConfigEndpoint::Insecure(ConfigEndpointTcp { host: insecure.host, port: insecure.port });
+                config.endpoints = ConfigEndpoint::Insecure(ConfigEndpointTcp { host: host(insecure.host), port: insecure.port, proxy_protocol: insecure.proxy_protocol });
             });
             if_some!(ep.secure => |secure: DecodedEPSecureConfig| {
                 let secure_ep = ConfigEndpointTls {
                     tcp: ConfigEndpointTcp {
-                        host: secure.host,
+                        host: host(secure.host),
                         port: secure.port,
+                        proxy_protocol: secure.proxy_protocol,
                     },
                     cert: secure.cert,
                     private_key: secure.private_key,
                     pkey_pass: secure.pkey_passphrase,
+                    files: secure.files,
                 };
                 match &config.endpoints {
                     ConfigEndpoint::Insecure(is) => if has_insecure {
@@ -953,16 +2333,92 @@ fn validate_configuration<CS: ConfigurationSource>(
             })
         }
     );
+    let main_endpoints = match &config.endpoints {
+        ConfigEndpoint::Insecure(tcp) => vec![format!("tcp@{tcp}")],
+        ConfigEndpoint::Secure(tls) => vec![format!("tls@{}", tls.tcp())],
+        ConfigEndpoint::Multi(tcp, tls) => vec![format!("tcp@{tcp}"), format!("tls@{}", tls.tcp())],
+    };
+    for ep in additional_endpoints {
+        let ep = parse_additional_endpoint(CS::SOURCE, &ep)?;
+        let name = ep.to_string();
+        err_if!(
+            if main_endpoints.contains(&name) || config.additional_endpoints.iter().any(|other| other.to_string() == name) => CS::custom_err(
+                format!("duplicate endpoint `{name}` in `{}`", CS::KEY_ENDPOINTS),
+            ).into(),
+            if matches!(ep, ConfigEndpointAdditional::Tls(_)) && matches!(config.endpoints, ConfigEndpoint::Insecure(_)) => CS::custom_err(
+                format!("`{name}` needs a TLS endpoint to take the certificate from"),
+            ).into(),
+            if matches!(ep, ConfigEndpointAdditional::Uds(_)) && cfg!(not(unix)) => CS::custom_err(
+                format!("`{name}`: UDS endpoints are only supported on Unix-like systems"),
+            ).into(),
+        );
+        config.additional_endpoints.push(ep);
+    }
+    if_some!(
+        logging => |logging: DecodedLoggingConfig| {
+            if_some!(logging.file => |file| config.logging.file = Some(file));
+            if_some!(logging.max_size => |size| config.logging.max_size = Some(size));
+            if_some!(logging.rotate_interval => |interval| config.logging.rotate_interval = Some(interval));
+            if_some!(logging.compress => |compress| config.logging.compress = compress);
+            if_some!(logging.retain => |retain| config.logging.retain = retain);
+        }
+    );
+    if_some!(
+        ddl => |ddl: DecodedDdlConfig| {
+            // an invalid duration is caught below
+            if_some!(ddl.trash_retention => |retention: String| config.ddl.trash_retention = Some(parse_duration(&retention).unwrap_or(0)));
+        }
+    );
     // now check a few things
     err_if!(
         if config.system.reliability_system_window == 0 => ConfigError::with_src(
             CS::SOURCE,
             ConfigErrorKind::ErrorString("invalid value for service window. must be nonzero".into()),
         ).into(),
+        if config.system.tcp_keepalive == Some(0) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for TCP keepalive. must be nonzero".into()),
+        ).into(),
+        if config.system.journal_prealloc.is_some_and(|size| size == 0 || size % JOURNAL_PREALLOC_ALIGN != 0) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString(format!("invalid value for journal preallocation. must be a nonzero multiple of {JOURNAL_PREALLOC_ALIGN}")),
+        ).into(),
+        if config.system.query_memory_limit == Some(0) || config.system.query_memory_global_limit == Some(0) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for query memory limit. must be nonzero".into()),
+        ).into(),
+        if config.system.user_write_ops_limit == Some(0) || config.system.user_write_bytes_limit == Some(0) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for user write limit. must be nonzero".into()),
+        ).into(),
+        if config.system.row_size_limit == Some(0) || config.system.field_size_limit == Some(0) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for size limit. must be nonzero".into()),
+        ).into(),
+        if config.system.otlp_endpoint.as_deref().is_some_and(|ep| !ep.starts_with("http://")) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for OTLP endpoint. must be an `http://` URL".into()),
+        ).into(),
+        if config.logging.max_size == Some(0) || config.logging.rotate_interval == Some(0) || config.logging.retain == 0 => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for log rotation. must be nonzero".into()),
+        ).into(),
+        if config.logging.file.is_none() && (config.logging.max_size.is_some() || config.logging.rotate_interval.is_some()) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("log rotation needs a log file (`logging.file`)".into()),
+        ).into(),
+        if config.ddl.trash_retention == Some(0) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid value for trash retention. must be a nonzero duration such as `24h`".into()),
+        ).into(),
         if config.auth.root_key.len() < ROOT_PASSWORD_MIN_LEN => ConfigError::with_src(
             CS::SOURCE,
             ConfigErrorKind::ErrorString("the root password must have at least 16 characters".into()),
         ).into(),
+        if !config.auth.kdf.is_valid() => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString("invalid KDF parameters".into()),
+        ).into(),
     );
     Ok(config)
 }
@@ -977,14 +2433,14 @@ pub enum ConfigReturn {
     /// Don't need to do anything. We've output a message and we're good to exit
     HelpMessage(String),
     /// A configuration that we have fully validated was provided
-    Config(Configuration),
+    Config(Box<Configuration>),
 }
 
 impl ConfigReturn {
     #[cfg(test)]
     pub fn into_config(self) -> Configuration {
         match self {
-            Self::Config(c) => c,
+            Self::Config(c) => *c,
             _ => panic!(),
         }
     }
@@ -995,7 +2451,7 @@ pub(super) fn apply_and_validate<CS: ConfigurationSource>(
     mut args: ParsedRawArgs,
 ) -> RuntimeResult<ConfigReturn> {
     let cfg = apply_config_changes::<CS>(&mut args)?;
-    validate_configuration::<CS>(cfg.val).map(ConfigReturn::Config)
+    validate_configuration::<CS>(cfg.val).map(|cfg| ConfigReturn::Config(Box::new(cfg)))
 }
 
 /*
@@ -1087,12 +2543,22 @@ fn get_cli_from_store() -> Vec<String> {
 /// - CLI args
 /// - ENV variables
 /// - Config file (if any)
+///
+/// The CLI options and environment variables that predate the configuration file (like `--mode` or `SKYDB_RUN_MODE`)
+/// can't be combined with anything else. Otherwise, every configuration file key can be set (in increasing order of
+/// precedence) in the configuration file, with a `SKYDB_*` environment variable or with a `--{key}={value}` CLI option
 pub fn check_configuration() -> RuntimeResult<ConfigReturn> {
+    check_configuration_from(get_cli_from_store())
+}
+
+/// Same as [`check_configuration`], but the CLI args (including the program name) are provided by the caller
+pub fn check_configuration_from(cli: Vec<String>) -> RuntimeResult<ConfigReturn> {
     // read in our environment variables
     let env_args = parse_env_args()?;
+    let env_overrides = parse_env_overrides()?;
     // read in our CLI args (since that can tell us whether we need a configuration file)
-    let read_cli_args = parse_cli_args(get_cli_from_store().into_iter())?;
-    let cli_args = match read_cli_args {
+    let read_cli_args = parse_cli_args(cli.into_iter())?;
+    let mut cli_args = match read_cli_args {
         CLIConfigParseReturn::Default => {
             // no options were provided in the CLI
             None
@@ -1107,55 +2573,64 @@ pub fn check_configuration() -> RuntimeResult<ConfigReturn> {
         }
         CLIConfigParseReturn::YieldedConfig(cfg) => Some(cfg),
     };
+    let cli_overrides = match cli_args.as_mut() {
+        Some(args) => extract_cli_overrides(args)?,
+        None => vec![],
+    };
+    let cli_args = cli_args.filter(|args| !args.is_empty());
+    let has_cfg_file = cli_args
+        .as_ref()
+        .is_some_and(|args| args.contains_key(CSCommandLine::ARG_CONFIG_FILE));
+    if has_cfg_file | !env_overrides.is_empty() | !cli_overrides.is_empty() {
+        return check_config_file(cli_args, env_args, env_overrides, cli_overrides);
+    }
     match cli_args {
         Some(cfg_from_cli) => {
-            // we have some CLI args
-            match cfg_from_cli.get(CSCommandLine::ARG_CONFIG_FILE) {
-                Some(cfg_file) => return check_config_file(&cfg_from_cli, &env_args, cfg_file),
-                None => {
-                    // no config file; check if there is a conflict with environment args
-                    if env_args.is_some() {
-                        // as we feared
-                        return Err(ConfigError::with_src(
-                            ConfigSource::Cli,
-                            ConfigErrorKind::Conflict,
-                        )
-                        .into());
-                    }
-                    return apply_and_validate::<CSCommandLine>(cfg_from_cli);
-                }
+            // we have some CLI args; check if there is a conflict with environment args
+            if env_args.is_some() {
+                // as we feared
+                return Err(
+                    ConfigError::with_src(ConfigSource::Cli, ConfigErrorKind::Conflict).into(),
+                );
             }
+            apply_and_validate::<CSCommandLine>(cfg_from_cli)
         }
         None => {
             // no CLI args; but do we have anything from env?
             match env_args {
-                Some(args) => {
-                    return apply_and_validate::<CSEnvArgs>(args);
-                }
+                Some(args) => apply_and_validate::<CSEnvArgs>(args),
                 None => {
                     // no env args or cli args; we're running on default
-                    return Err(ConfigError::new(ConfigErrorKind::ErrorString(
+                    Err(ConfigError::new(ConfigErrorKind::ErrorString(
                         "no configuration provided".into(),
                     ))
-                    .into());
+                    .into())
                 }
             }
         }
     }
 }
 
-/// Check the configuration file
+/// Check the configuration file (if any) along with the key overrides from the environment and the CLI
 fn check_config_file(
-    cfg_from_cli: &ParsedRawArgs,
-    env_args: &Option<ParsedRawArgs>,
-    cfg_file: &Vec<String>,
+    cfg_from_cli: Option<ParsedRawArgs>,
+    env_args: Option<ParsedRawArgs>,
+    env_overrides: ConfigOverrides,
+    cli_overrides: ConfigOverrides,
 ) -> RuntimeResult<ConfigReturn> {
-    if cfg_from_cli.len() == 1 && env_args.is_none() {
-        // yes, we only have the config file
-        argck_duplicate_values::<CSCommandLine>(&cfg_file, CSCommandLine::ARG_CONFIG_FILE)?;
-        // read the config file
-        let file = get_file_from_store(&cfg_file[0])?;
-        let mut config_from_file: DecodedConfiguration =
+    let cfg_file = cfg_from_cli
+        .as_ref()
+        .and_then(|args| args.get(CSCommandLine::ARG_CONFIG_FILE));
+    let cli_arg_count = cfg_from_cli.as_ref().map_or(0, |args| args.len());
+    if (cli_arg_count != cfg_file.is_some() as usize) | env_args.is_some() {
+        // so there are more configuration options + a config file? (and maybe even env?)
+        return Err(ConfigError::with_src(ConfigSource::Cli, ConfigErrorKind::Conflict).into());
+    }
+    let mut config_tree = match cfg_file {
+        Some(cfg_file) => {
+            argck_duplicate_values::<CSCommandLine>(cfg_file, CSCommandLine::ARG_CONFIG_FILE)?;
+            // read the config file
+            let file = get_file_from_store(&cfg_file[0])?;
             serde_yaml::from_str(&file).map_err(|e| {
                 ConfigError::with_src(
                     ConfigSource::File,
@@ -1163,27 +2638,46 @@ fn check_config_file(
                         "failed to parse YAML config file with error: `{e}`"
                     )),
                 )
-            })?;
-        // read in the TLS certs (if any)
-        match config_from_file.endpoints.as_mut() {
-            Some(ep) => match ep.secure.as_mut() {
-                Some(secure_ep) => {
-                    super::fractal::context::set_dmsg("loading TLS configuration from disk");
-                    let cert = fs::read_to_string(&secure_ep.cert)?;
-                    let private_key = fs::read_to_string(&secure_ep.private_key)?;
-                    let private_key_passphrase = fs::read_to_string(&secure_ep.pkey_passphrase)?;
-                    secure_ep.cert = cert;
-                    secure_ep.private_key = private_key;
-                    secure_ep.pkey_passphrase = private_key_passphrase;
-                }
-                None => {}
-            },
-            None => {}
+            })?
         }
-        // done here
-        return validate_configuration::<CSConfigFile>(config_from_file).map(ConfigReturn::Config);
-    } else {
-        // so there are more configuration options + a config file? (and maybe even env?)
-        return Err(ConfigError::with_src(ConfigSource::Cli, ConfigErrorKind::Conflict).into());
+        None => serde_yaml::Value::Null,
+    };
+    // env overrides the file, and the CLI overrides everything
+    apply_overrides::<CSEnvArgs>(&mut config_tree, env_overrides)?;
+    apply_overrides::<CSCommandLine>(&mut config_tree, cli_overrides)?;
+    if config_tree.is_null() {
+        // an empty configuration file
+        config_tree = serde_yaml::Value::Mapping(Default::default());
+    }
+    let mut config_from_file: DecodedConfiguration =
+        serde_yaml::from_value(config_tree).map_err(|e| {
+            ConfigError::with_src(
+                ConfigSource::File,
+                ConfigErrorKind::ErrorString(format!(
+                    "failed to parse configuration with error: `{e}`"
+                )),
+            )
+        })?;
+    // read in the TLS certs (if any)
+    if let Some(secure_ep) = config_from_file
+        .endpoints
+        .as_mut()
+        .and_then(|ep| ep.secure.as_mut())
+    {
+        super::fractal::context::set_dmsg("loading TLS configuration from disk");
+        secure_ep.files = TlsFiles::new(
+            &secure_ep.cert,
+            &secure_ep.private_key,
+            &secure_ep.pkey_passphrase,
+        );
+        let cert = fs::read_to_string(&secure_ep.cert)?;
+        let private_key = fs::read_to_string(&secure_ep.private_key)?;
+        let private_key_passphrase = fs::read_to_string(&secure_ep.pkey_passphrase)?;
+        secure_ep.cert = cert;
+        secure_ep.private_key = private_key;
+        secure_ep.pkey_passphrase = private_key_passphrase;
     }
+    // done here
+    validate_configuration::<CSConfigFile>(config_from_file)
+        .map(|cfg| ConfigReturn::Config(Box::new(cfg)))
 }
@@ -0,0 +1,199 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    statement cancellation
+    ---
+    on connections that use the `QueryTimeAck` exchange mode, every statement is registered here and its ID is sent to
+    the client (in an ack frame) before the statement is run. the client (or root) can then abort the statement from
+    another connection with `sysctl cancel query <id>`. just like jobs, cancellation is cooperative: it is only
+    observed by scans, which check for it every few rows and then fail with `QExecQueryCancelled`.
+
+    statement timeouts (`set statement_timeout` and `with timeout`) use the same checks: a statement with a timeout
+    gets a deadline on its flag and a scan that runs past it fails with `QExecQueryTimeout`
+*/
+
+use {
+    crate::engine::error::{QueryError, QueryResult},
+    parking_lot::{const_mutex, Mutex},
+    std::{
+        cell::RefCell,
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        time::Instant,
+    },
+};
+
+/// Scans check for cancellation once every these many rows
+pub const CANCEL_CHECK_INTERVAL: usize = 256;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// The running statements (and the users that are running them)
+static RUNNING: Mutex<BTreeMap<u64, (Box<str>, CancelFlag)>> = const_mutex(BTreeMap::new());
+
+thread_local! {
+    /// The cancellation flag of the statement that is running on this thread
+    static CURRENT: RefCell<Option<CancelFlag>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Default)]
+/// Set when a statement is asked to stop (or runs past its deadline)
+pub struct CancelFlag {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl PartialEq for CancelFlag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled) & (self.deadline == other.deadline)
+    }
+}
+
+impl CancelFlag {
+    /// Returns the same flag, but one that also stops the statement at `deadline`
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+    /// Returns an error if the statement was cancelled or if it ran past its deadline
+    pub fn check(&self) -> QueryResult<()> {
+        if self.is_cancelled() {
+            Err(QueryError::QExecQueryCancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Err(QueryError::QExecQueryTimeout)
+        } else {
+            Ok(())
+        }
+    }
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release)
+    }
+}
+
+/// Run `f` on this thread with `flag` as the current statement's cancellation flag
+pub fn scope<T>(flag: Option<CancelFlag>, f: impl FnOnce() -> T) -> T {
+    let prev = CURRENT.with(|c| c.replace(flag));
+    let ret = f();
+    CURRENT.with(|c| *c.borrow_mut() = prev);
+    ret
+}
+
+/// Returns the cancellation flag of the statement running on this thread (if it can be cancelled)
+pub fn current() -> Option<CancelFlag> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+#[derive(Debug)]
+/// A statement that can be cancelled. It is unregistered when this is dropped
+pub struct RunningStatement {
+    id: u64,
+    flag: CancelFlag,
+}
+
+impl RunningStatement {
+    pub fn register(user: &str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::AcqRel);
+        let flag = CancelFlag::default();
+        RUNNING.lock().insert(id, (user.into(), flag.clone()));
+        Self { id, flag }
+    }
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn flag(&self) -> &CancelFlag {
+        &self.flag
+    }
+}
+
+impl Drop for RunningStatement {
+    fn drop(&mut self) {
+        let _ = RUNNING.lock().remove(&self.id);
+    }
+}
+
+/// Cancel the statement with the given ID on behalf of `user`. Only root can cancel another user's statements
+pub fn cancel(id: u64, user: &str, root: bool) -> QueryResult<()> {
+    match RUNNING.lock().get(&id) {
+        Some((owner, flag)) if root || &**owner == user => {
+            flag.cancel();
+            Ok(())
+        }
+        Some(_) => Err(QueryError::SysPermissionDenied),
+        None => Err(QueryError::QExecObjectNotFound),
+    }
+}
+
+#[test]
+fn cancel_statement() {
+    let stmt = RunningStatement::register("sayan");
+    assert_eq!(
+        cancel(stmt.id(), "notsayan", false),
+        Err(QueryError::SysPermissionDenied)
+    );
+    assert!(!stmt.flag().is_cancelled());
+    cancel(stmt.id(), "sayan", false).unwrap();
+    assert_eq!(stmt.flag().check(), Err(QueryError::QExecQueryCancelled));
+    // the flag is visible to scans on this thread
+    scope(Some(stmt.flag().clone()), || {
+        assert_eq!(
+            current().unwrap().check(),
+            Err(QueryError::QExecQueryCancelled)
+        )
+    });
+    assert!(current().is_none());
+    let id = stmt.id();
+    drop(stmt);
+    assert_eq!(
+        cancel(id, "root", true),
+        Err(QueryError::QExecObjectNotFound)
+    );
+}
+
+#[test]
+fn statement_deadline() {
+    let flag = CancelFlag::default();
+    assert_eq!(flag.check(), Ok(()));
+    let expired = flag.clone().with_deadline(Instant::now());
+    assert_eq!(expired.check(), Err(QueryError::QExecQueryTimeout));
+    let later = flag
+        .clone()
+        .with_deadline(Instant::now() + std::time::Duration::from_secs(60));
+    assert_eq!(later.check(), Ok(()));
+    // a cancellation wins over the deadline
+    flag.cancel();
+    assert_eq!(expired.check(), Err(QueryError::QExecQueryCancelled));
+}
@@ -25,27 +25,105 @@
 */
 
 use crate::engine::{
-    data::{tag::TagClass, DictEntryGeneric},
+    core::{
+        cancel,
+        import::ImportSpec,
+        model::{ColumnarCache, ModelData},
+        quota,
+        system_db::SystemDatabase,
+        EntityIDRef,
+    },
+    data::{tag::TagClass, DictEntryGeneric, DictGeneric},
     error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
-    net::protocol::ClientLocalState,
-    ql::dcl::{SysctlCommand, UserDecl, UserDel},
+    fractal::{GenericTask, GlobalInstanceLike, JobKind, ModelUniqueID, Task},
+    net::{
+        self,
+        notice::{self, Notice, NoticeKind},
+        protocol::{ClientLocalState, Response, ResponseType},
+    },
+    ql::dcl::{ImportDecl, SysctlCommand, UserDecl, UserDel},
+    sync::atm::cpin,
 };
 
 const KEY_PASSWORD: &str = "password";
+const KEY_RATE: &str = "rate";
+const KEY_WRITE_OPS_LIMIT: &str = "write_ops_limit";
+const KEY_WRITE_BYTES_LIMIT: &str = "write_bytes_limit";
 
 pub fn exec<G: GlobalInstanceLike>(
-    g: G,
+    g: &G,
     current_user: &ClientLocalState,
     cmd: SysctlCommand,
-) -> QueryResult<()> {
+) -> QueryResult<Response> {
     if cmd.needs_root() & !current_user.is_root() {
         return Err(QueryError::SysPermissionDenied);
     }
     match cmd {
-        SysctlCommand::CreateUser(new) => create_user(&g, new),
-        SysctlCommand::DropUser(drop) => drop_user(&g, current_user, drop),
-        SysctlCommand::AlterUser(usermod) => alter_user(&g, current_user, usermod),
+        SysctlCommand::CreateUser(new) => create_user(g, new),
+        SysctlCommand::DropUser(drop) => drop_user(g, current_user, drop),
+        SysctlCommand::AlterUser(usermod) => alter_user(g, usermod),
+        SysctlCommand::ReloadTls => {
+            // the TLS listener picks this up and swaps in the new certificate
+            net::tls::request_reload();
+            Ok(())
+        }
+        SysctlCommand::IssueToken => {
+            let Some(token) = g
+                .state()
+                .namespace()
+                .sys_db()
+                .issue_token(current_user.username())
+            else {
+                // the user was dropped after they logged in
+                return Err(QueryError::SysAuthError);
+            };
+            return Ok(Response::Serialized {
+                ty: ResponseType::String,
+                size: token.len(),
+                data: token.into_bytes(),
+            });
+        }
+        SysctlCommand::RevokeToken(token) => {
+            let revoked = g
+                .state()
+                .namespace()
+                .sys_db()
+                .revoke_token(current_user.username(), token.as_bytes());
+            if revoked {
+                Ok(())
+            } else {
+                Err(QueryError::SysAuthError)
+            }
+        }
+        SysctlCommand::SetReadOnly(read_only) => {
+            g.state().namespace().sys_db().set_read_only(g, read_only)?;
+            notice::post(Notice::new(
+                NoticeKind::ModeChanged,
+                if read_only {
+                    "the server is now read-only"
+                } else {
+                    "the server is now accepting writes"
+                },
+            ));
+            Ok(())
+        }
+        SysctlCommand::PostNotice(message) => {
+            notice::post(Notice::new(NoticeKind::Message, message));
+            Ok(())
+        }
+        SysctlCommand::CompactModel(entity) => {
+            return compact_model(g, entity).map(job_response);
+        }
+        SysctlCommand::ImportSpace(import) => {
+            return import_space(g, import).map(job_response);
+        }
+        SysctlCommand::CancelJob(id) => g.jobs().cancel(id),
+        SysctlCommand::CancelQuery(id) => {
+            cancel::cancel(id, current_user.username(), current_user.is_root())
+        }
+        SysctlCommand::CacheModel(entity) => cache_model(g, entity),
+        SysctlCommand::UncacheModel(entity) => uncache_model(g, entity),
+        SysctlCommand::UndropModel(entity) => ModelData::transactional_exec_undrop(g, entity),
         SysctlCommand::ReportStatus => {
             if g.health().status_okay() {
                 Ok(())
@@ -54,23 +132,62 @@ pub fn exec<G: GlobalInstanceLike>(
             }
         }
     }
+    .map(|_| Response::Empty)
 }
 
-fn alter_user(
-    global: &impl GlobalInstanceLike,
-    cstate: &ClientLocalState,
-    user: UserDecl,
-) -> QueryResult<()> {
-    if cstate.is_root() {
-        // the root password can only be changed by shutting down the server
+fn alter_user(global: &impl GlobalInstanceLike, mut user: UserDecl) -> QueryResult<()> {
+    if user.username() == SystemDatabase::ROOT_ACCOUNT {
+        // the root password can only be changed by shutting down the server (and root has no write limits)
         return Err(QueryError::SysAuthError);
     }
-    let (username, password) = get_user_data(user)?;
-    global
-        .state()
-        .namespace()
-        .sys_db()
-        .alter_user(global, &username, &password)
+    let password = match user.options_mut().remove(KEY_PASSWORD) {
+        Some(DictEntryGeneric::Data(d)) if d.kind() == TagClass::Str => Some(unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            d.into_str().unwrap_unchecked()
+        }),
+        None => None,
+        Some(_) => return Err(QueryError::QExecDdlInvalidProperties),
+    };
+    let ops_limit = take_write_limit(user.options_mut(), KEY_WRITE_OPS_LIMIT)?;
+    let bytes_limit = take_write_limit(user.options_mut(), KEY_WRITE_BYTES_LIMIT)?;
+    let change_limits = ops_limit.is_some() | bytes_limit.is_some();
+    if !user.options().is_empty() | (password.is_none() & !change_limits) {
+        // invalid properties (or nothing to change)
+        return Err(QueryError::QExecDdlInvalidProperties);
+    }
+    let sys_db = global.state().namespace().sys_db();
+    if change_limits {
+        sys_db.alter_user_limits(global, user.username(), |limits| {
+            if let Some(ops) = ops_limit {
+                limits.set_ops(ops);
+            }
+            if let Some(bytes) = bytes_limit {
+                limits.set_bytes(bytes);
+            }
+        })?;
+    }
+    if let Some(password) = password {
+        sys_db.alter_user(global, user.username(), &password)?;
+        notice::post(Notice::for_user(
+            NoticeKind::AccountChanged,
+            user.username(),
+            "your password was changed",
+        ));
+    }
+    Ok(())
+}
+
+/// Take a write limit out of the options of `sysctl alter user`. This returns `None` if the limit isn't being
+/// changed and `Some(None)` if it is `null` (that is, the user goes back to the server-wide limit)
+fn take_write_limit(options: &mut DictGeneric, key: &str) -> QueryResult<Option<Option<u64>>> {
+    match options.remove(key) {
+        Some(DictEntryGeneric::Data(d)) if d.kind() == TagClass::UnsignedInt => {
+            Ok(Some(Some(d.uint())))
+        }
+        Some(DictEntryGeneric::Data(d)) if d.is_null() => Ok(Some(None)),
+        None => Ok(None),
+        Some(_) => Err(QueryError::QExecDdlInvalidProperties),
+    }
 }
 
 fn create_user(global: &impl GlobalInstanceLike, user: UserDecl) -> QueryResult<()> {
@@ -109,5 +226,99 @@ fn drop_user(
         .state()
         .namespace()
         .sys_db()
-        .drop_user(global, user_del.username())
+        .drop_user(global, user_del.username())?;
+    quota::forget_user(user_del.username());
+    notice::post(Notice::for_user(
+        NoticeKind::AccountChanged,
+        user_del.username(),
+        "your account was removed",
+    ));
+    Ok(())
+}
+
+/// Compaction can take a while, so it runs as a background job. Returns the job ID
+fn compact_model(global: &impl GlobalInstanceLike, entity: EntityIDRef) -> QueryResult<u64> {
+    let models = global.state().namespace().idx_models().read();
+    let Some(model) = models.get(&entity) else {
+        return Err(QueryError::QExecObjectNotFound);
+    };
+    let job = global
+        .jobs()
+        .submit(JobKind::CompactModel(ModelUniqueID::new(
+            entity.space(),
+            entity.entity(),
+            model.data().get_uuid(),
+        )));
+    let job_id = job.id();
+    drop(models);
+    global.taskmgr_post_standard_priority(Task::new(GenericTask::RunJob(job)));
+    Ok(job_id)
+}
+
+/// Build the columnar scan cache for the model. Fails if the model has no numeric fields
+fn cache_model(global: &impl GlobalInstanceLike, entity: EntityIDRef) -> QueryResult<()> {
+    global.state().namespace().with_model(entity, |mdl| {
+        // lock order: index latch, then the cache (see the notes on the cache)
+        let _idx_latch = mdl.primary_index().acquire_exclusive();
+        let mut cache = mdl.columnar_cache().write();
+        let g = cpin();
+        match ColumnarCache::build(mdl, &g) {
+            Some(new) => {
+                info!(
+                    "cached {} row(s) of {}.{} in columnar form",
+                    new.len(),
+                    entity.space(),
+                    entity.entity()
+                );
+                *cache = Some(new);
+                Ok(())
+            }
+            None => Err(QueryError::QExecDdlInvalidProperties),
+        }
+    })
+}
+
+fn uncache_model(global: &impl GlobalInstanceLike, entity: EntityIDRef) -> QueryResult<()> {
+    global.state().namespace().with_model(entity, |mdl| {
+        *mdl.columnar_cache().write() = None;
+        Ok(())
+    })
+}
+
+fn import_space(global: &impl GlobalInstanceLike, mut import: ImportDecl) -> QueryResult<u64> {
+    let rate = match import.options_mut().remove(KEY_RATE) {
+        Some(DictEntryGeneric::Data(d)) if d.kind() == TagClass::UnsignedInt && d.uint() != 0 => {
+            Some(d.uint())
+        }
+        None => None,
+        Some(_) => return Err(QueryError::QExecDdlInvalidProperties),
+    };
+    if !import.options().is_empty() {
+        return Err(QueryError::QExecDdlInvalidProperties);
+    }
+    if global
+        .state()
+        .namespace()
+        .idx()
+        .read()
+        .contains_key(import.space())
+    {
+        return Err(QueryError::QExecDdlObjectAlreadyExists);
+    }
+    let Some(spec) = ImportSpec::from_url(import.url(), import.space(), rate) else {
+        return Err(QueryError::QExecDdlInvalidProperties);
+    };
+    let job = global.jobs().submit(JobKind::ImportSpace(spec));
+    let job_id = job.id();
+    global.taskmgr_post_standard_priority(Task::new(GenericTask::RunJob(job)));
+    Ok(job_id)
+}
+
+fn job_response(job_id: u64) -> Response {
+    // NB: a uint is framed as its value followed by a LF, which is exactly how the size is framed
+    Response::Serialized {
+        ty: ResponseType::UInt64,
+        size: job_id as usize,
+        data: vec![],
+    }
 }
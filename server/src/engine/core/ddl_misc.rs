@@ -25,9 +25,10 @@
 */
 
 use crate::engine::{
+    core::model::{watermark, Backpressure},
     error::{QueryError, QueryResult},
     fractal::GlobalInstanceLike,
-    net::protocol::{ClientLocalState, Response, ResponseType},
+    net::protocol::{resp::DictWriter, ClientLocalState, Response, ResponseType},
     ql::ddl::Inspect,
 };
 
@@ -36,72 +37,148 @@ pub fn inspect(
     c: &ClientLocalState,
     stmt: Inspect,
 ) -> QueryResult<Response> {
-    let ret = match stmt {
+    let mut ret = DictWriter::new(c.dict_format());
+    match stmt {
         Inspect::Global => {
             // collect spaces
             let spaces = g.state().namespace().idx().read();
-            let mut spaces_iter = spaces.iter().peekable();
-            let mut ret = format!("{{\"spaces\":[");
-            while let Some((space, _)) = spaces_iter.next() {
-                ret.push('"');
-                ret.push_str(&space);
-                ret.push('"');
-                if spaces_iter.peek().is_some() {
-                    ret.push(',');
-                }
-            }
+            ret.put_str_list("spaces", spaces.keys().map(|space| space.as_ref()));
+            drop(spaces);
             if c.is_root() {
                 // iff the user is root, show information about other users. if not, just show models and settings
-                ret.push_str("],\"users\":[");
-                drop(spaces_iter);
-                drop(spaces);
-                // collect users
                 let users = g.state().namespace().sys_db().users().read();
-                let mut users_iter = users.iter().peekable();
-                while let Some((user, _)) = users_iter.next() {
-                    ret.push('"');
-                    ret.push_str(&user);
-                    ret.push('"');
-                    if users_iter.peek().is_some() {
-                        ret.push(',');
-                    }
-                }
+                ret.put_str_list("users", users.keys().map(|user| user.as_ref()));
             }
-            ret.push_str("],\"settings\":{}}");
-            ret
+            ret.put_dict("settings", ret.nested());
         }
         Inspect::Model(m) => match g.state().namespace().idx_models().read().get(&m) {
             Some(m) => {
                 let m = m.data();
-                format!(
-                    "{{\"decl\":\"{}\",\"rows\":{},\"properties\":{{}}}}",
-                    m.describe(),
-                    m.primary_index().count()
-                )
+                let unflushed = m.delta_state().unflushed();
+                ret.put_str("decl", m.describe());
+                ret.put_uint("rows", m.primary_index().count() as u64);
+                ret.put_uint("unflushed", unflushed as u64);
+                ret.put_str(
+                    "backpressure",
+                    Backpressure::compute(unflushed, g.get_max_delta_size()).as_str(),
+                );
+                let mut properties = ret.nested();
+                properties.put_bool("strict", m.is_strict());
+                properties.put_str("pk_index", m.primary_index().kind().name_str());
+                properties.put_uint_or_null("ttl", m.ttl());
+                properties.put_bool("append_only", m.is_append_only());
+                ret.put_dict("properties", properties);
+                let mut indexes = ret.nested();
+                for index in m.secondary_indexes().read().iter() {
+                    indexes.put_str(index.name(), index.field());
+                }
+                ret.put_dict("indexes", indexes);
+            }
+            None => return Err(QueryError::QExecObjectNotFound),
+        },
+        Inspect::ModelFingerprint(m) => match g.state().namespace().idx_models().read().get(&m) {
+            // NB: a uint is framed as its value followed by a LF, which is exactly how the size is framed
+            Some(m) => {
+                return Ok(Response::Serialized {
+                    ty: ResponseType::UInt64,
+                    size: m.data().fingerprint() as usize,
+                    data: vec![],
+                })
             }
             None => return Err(QueryError::QExecObjectNotFound),
         },
         Inspect::Space(s) => match g.state().namespace().idx().read().get(s.as_str()) {
             Some(s) => {
-                let mut ret = format!("{{\"models\":[");
-                let mut models_iter = s.models().iter().peekable();
-                while let Some(mdl) = models_iter.next() {
-                    ret.push('\"');
-                    ret.push_str(&mdl);
-                    ret.push('\"');
-                    if models_iter.peek().is_some() {
-                        ret.push(',');
-                    }
-                }
-                ret.push_str("]}}");
-                ret
+                ret.put_str_list("models", s.models().iter().map(|mdl| mdl.as_ref()));
+                ret.put_str_or_null("storage_path", s.storage_path());
             }
             None => return Err(QueryError::QExecObjectNotFound),
         },
-    };
-    Ok(Response::Serialized {
-        ty: ResponseType::String,
-        size: ret.len(),
-        data: ret.into_bytes(),
-    })
+        Inspect::Tasks => {
+            // a task's statement runs as root, so only root gets to see it
+            if !c.is_root() {
+                return Err(QueryError::SysPermissionDenied);
+            }
+            let tasks = g.state().namespace().tasks().tasks().read();
+            let tasks: Vec<_> = tasks
+                .iter()
+                .map(|(name, task)| {
+                    let mut info = ret.nested();
+                    info.put_str("name", name);
+                    info.put_str("schedule", task.schedule().src());
+                    info.put_str("statement", &String::from_utf8_lossy(task.body().query()));
+                    info.put_bool("enabled", task.is_enabled());
+                    info
+                })
+                .collect();
+            ret.put_dict_list("tasks", tasks.into_iter());
+        }
+        Inspect::TaskRuns => {
+            if !c.is_root() {
+                return Err(QueryError::SysPermissionDenied);
+            }
+            let runs = g.state().namespace().tasks().runs().lock();
+            let runs: Vec<_> = runs
+                .iter()
+                .map(|run| {
+                    let mut info = ret.nested();
+                    info.put_str("task", run.task());
+                    info.put_str("due", &run.due().to_rfc3339());
+                    info.put_uint_or_null("run", run.id());
+                    info.put_str("status", run.status());
+                    info.put_uint_or_null(
+                        "duration_ms",
+                        run.elapsed().map(|elapsed| elapsed.as_millis() as u64),
+                    );
+                    info.put_uint_or_null("error", run.error().map(|e| e.value_qword()));
+                    info
+                })
+                .collect();
+            ret.put_dict_list("runs", runs.into_iter());
+        }
+        Inspect::Jobs => {
+            let jobs: Vec<_> = g
+                .jobs()
+                .list()
+                .iter()
+                .map(|job| {
+                    let (done, total) = job.progress();
+                    let mut info = ret.nested();
+                    info.put_uint("id", job.id());
+                    info.put_str("job", &job.kind().to_string());
+                    info.put_str("status", job.status().as_str());
+                    info.put_uint_list("progress", [done, total].into_iter());
+                    info
+                })
+                .collect();
+            ret.put_dict_list("jobs", jobs.into_iter());
+        }
+        Inspect::Watermarks => {
+            // every watermark that advanced before this tick is in the listing below
+            let clock = watermark::tick();
+            let models = g.state().namespace().idx_models().read();
+            let mut watermarks: Vec<_> = models
+                .iter()
+                .map(|(id, model)| {
+                    let (committed, committed_clock) =
+                        model.data().delta_state().committed_watermark();
+                    let mut watermark = ret.nested();
+                    watermark.put_uint_or_null("committed", committed);
+                    watermark.put_uint_or_null(
+                        "clock",
+                        Some(committed_clock).filter(|clock| *clock != 0),
+                    );
+                    (format!("{}.{}", id.space(), id.entity()), watermark)
+                })
+                .collect();
+            watermarks.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut wm_models = ret.nested();
+            for (model, watermark) in watermarks {
+                wm_models.put_dict(&model, watermark);
+            }
+            ret.put_uint("clock", clock);
+            ret.put_dict("models", wm_models);
+        }
+    }
+    Ok(ret.into_response())
 }
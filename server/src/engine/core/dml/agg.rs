@@ -0,0 +1,391 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    aggregates
+    ---
+    an aggregate select goes over every row that its where clause matches (or every row of the model if it doesn't have
+    one) and folds them into a single row. like in SQL, every aggregate other than `count(*)` skips nulls and `sum`,
+    `avg`, `min` and `max` are null if there weren't any values to aggregate.
+
+    - `count`: `uint64`
+    - `sum`: `uint64`, `sint64` or `float64` depending on the field. an overflow fails with a lossy cast error
+    - `avg`: `float64`
+    - `min` and `max`: the type of the field
+
+    with a `group by`, the matched rows are put into groups (in a hash map) by the values of the grouped fields and
+    every group is folded into its own row, which has the values of the grouped fields and the aggregates of the group
+    (in the order of the select's columns). nulls form a group of their own. the rows are returned in no particular
+    order, and a select with no matching rows has no groups (and hence no rows)
+*/
+
+use {
+    super::{
+        scan::{ScanBatch, ScanFilter},
+        sel::{self, RowIteratorAll},
+    },
+    crate::engine::{
+        core::{
+            cancel::{self, CANCEL_CHECK_INTERVAL},
+            index::{DcFieldIndex, PrimaryIndexKey, RowData},
+            model::ModelData,
+            query_mem::{self, QueryMemory},
+            trace,
+        },
+        data::{
+            cell::{Datacell, VirtualDatacell},
+            tag::{DataTag, TagClass},
+        },
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::STIndex,
+        ql::dml::{
+            expr::{Aggregate, AggregateFn},
+            sel::SelectStatement,
+        },
+        sync,
+    },
+    std::{
+        cmp::Ordering,
+        collections::{hash_map::Entry, HashMap},
+        mem,
+    },
+};
+
+/// The running value of an aggregate
+#[derive(Clone)]
+enum Fold {
+    Count(u64),
+    SumUInt(Option<u64>),
+    SumSInt(Option<i64>),
+    SumFloat(Option<f64>),
+    Avg(f64, u64),
+    Min(Option<Datacell>),
+    Max(Option<Datacell>),
+}
+
+impl Fold {
+    fn push(&mut self, dc: &Datacell) -> QueryResult<()> {
+        match self {
+            Self::Count(c) => *c += 1,
+            Self::SumUInt(sum) => {
+                *sum = Some(
+                    sum.unwrap_or(0)
+                        .checked_add(dc.uint())
+                        .ok_or(QueryError::QExecDmlLossyCast)?,
+                )
+            }
+            Self::SumSInt(sum) => {
+                *sum = Some(
+                    sum.unwrap_or(0)
+                        .checked_add(dc.sint())
+                        .ok_or(QueryError::QExecDmlLossyCast)?,
+                )
+            }
+            Self::SumFloat(sum) => *sum = Some(sum.unwrap_or(0.0) + dc.float()),
+            Self::Avg(sum, c) => {
+                *sum += match dc.kind() {
+                    TagClass::UnsignedInt => dc.uint() as f64,
+                    TagClass::SignedInt => dc.sint() as f64,
+                    _ => dc.float(),
+                };
+                *c += 1;
+            }
+            Self::Min(min) => {
+                if min
+                    .as_ref()
+                    .is_none_or(|min| sel::cmp_values(dc, min) == Ordering::Less)
+                {
+                    *min = Some(dc.clone());
+                }
+            }
+            Self::Max(max) => {
+                if max
+                    .as_ref()
+                    .is_none_or(|max| sel::cmp_values(dc, max) == Ordering::Greater)
+                {
+                    *max = Some(dc.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+    fn finish(self) -> Datacell {
+        match self {
+            Self::Count(c) => Datacell::new_uint_default(c),
+            Self::SumUInt(sum) => sum.map_or_else(Datacell::null, Datacell::new_uint_default),
+            Self::SumSInt(sum) => sum.map_or_else(Datacell::null, Datacell::new_sint_default),
+            Self::SumFloat(sum) => sum.map_or_else(Datacell::null, Datacell::new_float_default),
+            Self::Avg(_, 0) => Datacell::null(),
+            Self::Avg(sum, c) => Datacell::new_float_default(sum / c as f64),
+            Self::Min(v) | Self::Max(v) => v.unwrap_or_else(Datacell::null),
+        }
+    }
+}
+
+/// An aggregate of a select, along with its running value
+#[derive(Clone)]
+struct Accumulator<'a> {
+    /// `None` for `count(*)`
+    field: Option<&'a str>,
+    fold: Fold,
+}
+
+impl<'a> Accumulator<'a> {
+    fn new(mdl: &ModelData, agg: Aggregate<'a>) -> QueryResult<Self> {
+        let Some(field) = agg.field() else {
+            return Ok(Self {
+                field: None,
+                fold: Fold::Count(0),
+            });
+        };
+        let Some(field_info) = mdl.fields().st_get(field.as_str()) else {
+            return Err(QueryError::QExecUnknownField);
+        };
+        let class = field_info.layers()[0].tag().tag_class();
+        let numeric = matches!(
+            class,
+            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float
+        );
+        let fold = match (agg.func(), class) {
+            (AggregateFn::Count, _) => Fold::Count(0),
+            (AggregateFn::Sum, TagClass::UnsignedInt) => Fold::SumUInt(None),
+            (AggregateFn::Sum, TagClass::SignedInt) => Fold::SumSInt(None),
+            (AggregateFn::Sum, TagClass::Float) => Fold::SumFloat(None),
+            (AggregateFn::Avg, _) if numeric => Fold::Avg(0.0, 0),
+            (AggregateFn::Min, class) if class < TagClass::List => Fold::Min(None),
+            (AggregateFn::Max, class) if class < TagClass::List => Fold::Max(None),
+            _ => return Err(QueryError::QExecDmlValidationError),
+        };
+        Ok(Self {
+            field: Some(field.as_str()),
+            fold,
+        })
+    }
+    fn push(&mut self, mdl: &ModelData, pkdc: &Datacell, fields: &DcFieldIndex) -> QueryResult<()> {
+        let dc = match self.field {
+            Some(field) => sel::read_field(mdl, pkdc, field, fields)?,
+            // count(*) counts every row
+            None => return self.fold.push(pkdc),
+        };
+        if dc.is_null() {
+            return Ok(());
+        }
+        self.fold.push(dc)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+/// The value of a grouped field, as a hashable key
+enum GroupValue {
+    Null,
+    Bool(bool),
+    UInt(u64),
+    SInt(i64),
+    Float(u64),
+    Bytes(Box<[u8]>),
+}
+
+impl GroupValue {
+    fn from_dc(dc: &Datacell) -> QueryResult<Self> {
+        if dc.is_null() {
+            return Ok(Self::Null);
+        }
+        dc.try_bool()
+            .map(Self::Bool)
+            .or_else(|| dc.try_uint().map(Self::UInt))
+            .or_else(|| dc.try_sint().map(Self::SInt))
+            .or_else(|| dc.try_float().map(|f| Self::Float(f.to_bits())))
+            .or_else(|| dc.try_str().map(|s| Self::Bytes(s.as_bytes().into())))
+            .or_else(|| dc.try_bin().map(|b| Self::Bytes(b.into())))
+            .ok_or(QueryError::QExecDmlValidationError)
+    }
+    /// The number of bytes that this holds on the heap
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Bytes(b) => b.len(),
+            _ => 0,
+        }
+    }
+}
+
+/// A column of the result
+enum Column {
+    /// the n-th grouped field
+    Group(usize),
+    /// the n-th aggregate
+    Aggregate(usize),
+}
+
+/// The values of the grouped fields of a group, along with the aggregates of its rows
+struct Group<'a> {
+    keys: Vec<Datacell>,
+    accumulators: Vec<Accumulator<'a>>,
+}
+
+impl<'a> Group<'a> {
+    fn finish(self, columns: &[Column]) -> Vec<Datacell> {
+        let aggregates: Vec<_> = self
+            .accumulators
+            .into_iter()
+            .map(|acc| acc.fold.finish())
+            .collect();
+        columns
+            .iter()
+            .map(|column| match *column {
+                Column::Group(i) => self.keys[i].clone(),
+                Column::Aggregate(i) => aggregates[i].clone(),
+            })
+            .collect()
+    }
+}
+
+/// Evaluate the aggregates of the select, calling `f` with the cells of every resulting row (there is exactly one
+/// row if the select isn't grouped). The groups are charged to `mem`. Returns the number of rows
+pub fn aggregate<F>(
+    global: &impl GlobalInstanceLike,
+    select: SelectStatement,
+    mem: &QueryMemory,
+    mut f: F,
+) -> QueryResult<usize>
+where
+    F: FnMut(&[Datacell]) -> QueryResult<()>,
+{
+    global
+        .state()
+        .namespace()
+        .with_model(select.entity(), |mdl| {
+            for field in select.group_by() {
+                let Some(field_info) = mdl.fields().st_get(field.as_str()) else {
+                    return Err(QueryError::QExecUnknownField);
+                };
+                if field_info.layers()[0].tag().tag_class() >= TagClass::List {
+                    return Err(QueryError::QExecDmlValidationError);
+                }
+            }
+            let mut columns = Vec::with_capacity(select.fields().len());
+            let mut accumulators = vec![];
+            for expr in select.fields() {
+                match (expr.as_aggregate(), expr.as_field()) {
+                    (Some(agg), _) => {
+                        columns.push(Column::Aggregate(accumulators.len()));
+                        accumulators.push(Accumulator::new(mdl, agg)?);
+                    }
+                    (None, Some(field)) => {
+                        match select.group_by().iter().position(|group| *group == field) {
+                            Some(i) => columns.push(Column::Group(i)),
+                            None => return Err(QueryError::QExecDmlValidationError),
+                        }
+                    }
+                    (None, None) => return Err(QueryError::QExecDmlValidationError),
+                }
+            }
+            let mut groups: HashMap<Vec<GroupValue>, Group> = HashMap::new();
+            let mut charge = mem.working_buffer();
+            // what the groups hold outside of the map itself
+            let mut groups_heap = 0;
+            let mut fold_row = |key: &PrimaryIndexKey, data: &RowData| -> QueryResult<()> {
+                let pkdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+                let mut group_key = Vec::with_capacity(select.group_by().len());
+                for field in select.group_by() {
+                    let dc = sel::read_field(mdl, &pkdc, field.as_str(), data.fields())?;
+                    group_key.push(GroupValue::from_dc(dc)?);
+                }
+                let mut new_group_heap = 0;
+                let group = match groups.entry(group_key) {
+                    Entry::Occupied(group) => group.into_mut(),
+                    Entry::Vacant(group) => {
+                        // the grouped values are held twice: once in the key and once as cells
+                        new_group_heap = query_mem::vec_size(group.key())
+                            + group.key().len() * mem::size_of::<Datacell>()
+                            + group.key().iter().map(|v| 2 * v.heap_size()).sum::<usize>()
+                            + accumulators.len() * mem::size_of::<Accumulator>();
+                        let keys = select
+                            .group_by()
+                            .iter()
+                            .map(|field| {
+                                sel::read_field(mdl, &pkdc, field.as_str(), data.fields()).cloned()
+                            })
+                            .collect::<QueryResult<_>>()?;
+                        group.insert(Group {
+                            keys,
+                            accumulators: accumulators.clone(),
+                        })
+                    }
+                };
+                for acc in group.accumulators.iter_mut() {
+                    acc.push(mdl, &pkdc, data.fields())?;
+                }
+                if new_group_heap != 0 {
+                    groups_heap += new_group_heap;
+                    charge.charge_upto(
+                        groups.capacity() * mem::size_of::<(Vec<GroupValue>, Group)>()
+                            + groups_heap,
+                    )?;
+                }
+                Ok(())
+            };
+            let g = sync::atm::cpin();
+            // an aggregate over a large model can be aborted with `sysctl cancel query`
+            let cancel = cancel::current();
+            let check_cancelled = || cancel.as_ref().map_or(Ok(()), |flag| flag.check());
+            if select.clauses().clauses().is_empty() {
+                for (scanned, (key, data)) in RowIteratorAll::new(&g, mdl, usize::MAX).enumerate() {
+                    if scanned % CANCEL_CHECK_INTERVAL == 0 {
+                        check_cancelled()?;
+                    }
+                    fold_row(key, &data)?;
+                }
+            } else {
+                let filter = trace::span("plan", || ScanFilter::compile(mdl, select.clauses()))?;
+                let (_latch, mut rows) = sel::scan_rows(&g, mdl, &filter);
+                let mut batch = ScanBatch::new();
+                while batch.fill(&mut rows) {
+                    check_cancelled()?;
+                    for (key, data) in batch.apply(&filter) {
+                        fold_row(key, data)?;
+                    }
+                }
+            }
+            // without a group by, every row is folded into the one (and only) group even if nothing matched
+            if select.group_by().is_empty() && groups.is_empty() {
+                groups.insert(
+                    vec![],
+                    Group {
+                        keys: vec![],
+                        accumulators,
+                    },
+                );
+            }
+            let mut rows = 0;
+            for group in groups.into_values() {
+                check_cancelled()?;
+                f(&group.finish(&columns))?;
+                rows += 1;
+            }
+            Ok(rows)
+        })
+}
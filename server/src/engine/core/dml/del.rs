@@ -28,7 +28,6 @@ use crate::engine::{
     core::{self, dml::QueryExecMeta, model::delta::DataDeltaKind},
     error::{QueryError, QueryResult},
     fractal::GlobalInstanceLike,
-    idx::MTIndex,
     net::protocol::Response,
     ql::dml::del::DeleteStatement,
     sync,
@@ -43,17 +42,43 @@ pub fn delete_resp(
 
 pub fn delete(global: &impl GlobalInstanceLike, mut delete: DeleteStatement) -> QueryResult<()> {
     core::with_model_for_data_update(global, delete.entity(), |model| {
+        model.check_rows_mutable()?;
         let g = sync::atm::cpin();
         let delta_state = model.delta_state();
-        let _idx_latch = model.primary_index().acquire_cd();
+        // with unique fields, no update can claim values for the row after we released them (see `model::unique`)
+        let unique = model.has_unique_fields();
+        let _idx_latch = (!unique).then(|| model.primary_index().acquire_cd());
+        let _idx_latch_x = unique.then(|| model.primary_index().acquire_exclusive());
+        let key = model.resolve_where(delete.clauses_mut())?;
+        let row_lock = if !delete.clauses_mut().clauses().is_empty() || model.ttl().is_some() {
+            // we have more clauses to check (or the row might have expired), so look at the row first
+            let Some(row) = model.primary_index().select(key.clone(), &g) else {
+                return Err(QueryError::QExecDmlRowNotFound);
+            };
+            drop(row.resolve_schema_deltas_and_freeze(delta_state));
+            // hold the row lock until the row is gone so that an update can't change it after we checked it
+            let row_data = row.d_data().write();
+            if model.expiry().is_expired(&row_data)
+                || !model.where_residual_matches(delete.clauses_mut(), row_data.fields())?
+            {
+                return Err(QueryError::QExecDmlRowNotFound);
+            }
+            Some(row_data)
+        } else {
+            None
+        };
         // create new version
         let new_version = delta_state.create_new_data_delta_version();
         match model
             .primary_index()
             .__raw_index()
-            .mt_delete_return_entry(&model.resolve_where(delete.clauses_mut())?, &g)
+            .mt_delete_return_entry(&key, &g)
         {
             Some(row) => {
+                drop(row_lock);
+                model.columnar_cache_remove(row.d_key());
+                model.secondary_indexes_remove(row.d_key());
+                model.unique_release(row.d_key());
                 let dp = delta_state.append_new_data_delta_with(
                     DataDeltaKind::Delete,
                     row.clone(),
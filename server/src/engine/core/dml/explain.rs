@@ -0,0 +1,268 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    explain
+    ---
+    `explain` plans a select the same way that the select itself would (see `sel::scan_rows`) but stops before reading
+    any rows. the plan is returned as a row of four cells:
+    - the access path (`point lookup`, `multi get`, `index scan`, `cache scan`, `prefix scan` or `full scan`)
+    - the secondary index that is used (or null)
+    - the estimated number of rows that the select returns. this is an upper bound: it is exact for point lookups and
+    index scans but for any other scan it assumes that every row passes the filter
+    - the filters that are pushed down into the access path (like `age > ?`), sorted by field
+*/
+
+use {
+    super::{scan::ScanFilter, sel},
+    crate::engine::{
+        core::{index::PrimaryIndexKind, model::ModelData},
+        data::{
+            cell::Datacell,
+            tag::{DataTag, TagClass},
+        },
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::STIndex,
+        net::protocol::{Response, ResponseType},
+        ql::{
+            ast::explain::Explain,
+            dml::sel::{SelectAllStatement, SelectStatement},
+        },
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How a select reads the rows of the model
+pub enum AccessPath {
+    /// a single key is looked up in the primary index
+    PointLookup,
+    /// a list of keys is looked up in the primary index
+    MultiGet,
+    /// the keys are looked up in a secondary index
+    IndexScan,
+    /// the filter runs on the columnar cache
+    CacheScan,
+    /// only the keys under a prefix are read from the (radix) primary index
+    PrefixScan,
+    /// every row is read
+    FullScan,
+}
+
+impl AccessPath {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PointLookup => "point lookup",
+            Self::MultiGet => "multi get",
+            Self::IndexScan => "index scan",
+            Self::CacheScan => "cache scan",
+            Self::PrefixScan => "prefix scan",
+            Self::FullScan => "full scan",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// The plan of a select
+pub struct Plan {
+    access: AccessPath,
+    index: Option<Box<str>>,
+    estimated_rows: u64,
+    filters: Vec<String>,
+}
+
+impl Plan {
+    fn new(
+        access: AccessPath,
+        index: Option<Box<str>>,
+        estimated_rows: u64,
+        mut filters: Vec<String>,
+    ) -> Self {
+        filters.sort();
+        Self {
+            access,
+            index,
+            estimated_rows,
+            filters,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_test(
+        access: AccessPath,
+        index: Option<&str>,
+        estimated_rows: u64,
+        filters: &[&str],
+    ) -> Self {
+        Self::new(
+            access,
+            index.map(Into::into),
+            estimated_rows,
+            filters.iter().map(|filter| filter.to_string()).collect(),
+        )
+    }
+    fn into_response(self) -> Response {
+        let mut data = vec![];
+        sel::encode_cell(&mut data, &Datacell::new_str(self.access.name().into()));
+        match self.index {
+            Some(index) => sel::encode_cell(&mut data, &Datacell::new_str(index)),
+            None => sel::encode_cell(&mut data, &Datacell::null()),
+        }
+        sel::encode_cell(&mut data, &Datacell::new_uint_default(self.estimated_rows));
+        let filters = self
+            .filters
+            .into_iter()
+            .map(|filter| Datacell::new_str(filter.into_boxed_str()))
+            .collect();
+        sel::encode_cell(&mut data, &Datacell::new_list(filters));
+        Response::Serialized {
+            ty: ResponseType::Row,
+            size: 4,
+            data,
+        }
+    }
+}
+
+pub fn explain_resp(global: &impl GlobalInstanceLike, explain: Explain) -> QueryResult<Response> {
+    self::explain(global, explain).map(Plan::into_response)
+}
+
+/// Plan the select without running it
+pub fn explain(global: &impl GlobalInstanceLike, explain: Explain) -> QueryResult<Plan> {
+    match explain {
+        Explain::Select(select) => explain_select(global, select),
+        Explain::SelectAll(select) => explain_select_all(global, select),
+    }
+}
+
+fn explain_select(
+    global: &impl GlobalInstanceLike,
+    mut select: SelectStatement,
+) -> QueryResult<Plan> {
+    global
+        .state()
+        .namespace()
+        .with_model(select.entity(), |mdl| {
+            if let Some(multi) = select.take_multi_get() {
+                if multi.field().as_str() != mdl.p_key() {
+                    return Err(QueryError::QExecDmlWhereHasUnindexedColumn);
+                }
+                let bounds = select.bounds();
+                let keys = (multi.into_keys().len() as u64)
+                    .saturating_sub(bounds.offset())
+                    .min(bounds.limit());
+                let filter = format!("{} in ?", mdl.p_key());
+                return Ok(Plan::new(AccessPath::MultiGet, None, keys, vec![filter]));
+            }
+            if select.is_aggregate() {
+                let grouped = !select.group_by().is_empty();
+                let mut plan = if select.clauses().clauses().is_empty() {
+                    plan_scan(mdl, None)
+                } else {
+                    let filter = ScanFilter::compile(mdl, select.clauses())?;
+                    plan_scan(mdl, Some(&filter))
+                };
+                // an aggregate folds all the rows into a single row unless it's grouped
+                if !grouped {
+                    plan.estimated_rows = 1;
+                }
+                return Ok(plan);
+            }
+            mdl.resolve_where(select.clauses_mut())?;
+            let mut filters = vec![format!("{} = ?", mdl.p_key())];
+            for (field_id, clause) in select.clauses().clauses() {
+                if !mdl.fields().st_contains(field_id.as_str()) {
+                    return Err(QueryError::QExecUnknownField);
+                }
+                match clause.null_test() {
+                    Some(true) => filters.push(format!("{} is null", field_id.as_str())),
+                    Some(false) => filters.push(format!("{} is not null", field_id.as_str())),
+                    None if clause.is_cmp_null() => {
+                        filters.push(format!("{} compared with null", field_id.as_str()))
+                    }
+                    None => return Err(QueryError::QExecDmlWhereHasUnindexedColumn),
+                }
+            }
+            Ok(Plan::new(AccessPath::PointLookup, None, 1, filters))
+        })
+}
+
+fn explain_select_all(
+    global: &impl GlobalInstanceLike,
+    select: SelectAllStatement,
+) -> QueryResult<Plan> {
+    global.state().namespace().with_model(select.entity, |mdl| {
+        if let Some(order_by) = select.order_by {
+            match mdl.fields().st_get(order_by.field().as_str()) {
+                Some(field) if field.layers()[0].tag().tag_class() < TagClass::List => {}
+                Some(_) => return Err(QueryError::QExecDmlValidationError),
+                None => return Err(QueryError::QExecUnknownField),
+            }
+        }
+        let mut plan = match select.clause {
+            Some(ref clause) => {
+                let filter = ScanFilter::compile(mdl, clause)?;
+                plan_scan(mdl, Some(&filter))
+            }
+            None => plan_scan(mdl, None),
+        };
+        plan.estimated_rows = plan
+            .estimated_rows
+            .saturating_sub(select.offset)
+            .min(select.limit);
+        Ok(plan)
+    })
+}
+
+/// Pick the access path that [`sel::scan_rows`] would use for the filter
+fn plan_scan(mdl: &ModelData, filter: Option<&ScanFilter>) -> Plan {
+    let rows = mdl.primary_index().count() as u64;
+    let Some(filter) = filter else {
+        return Plan::new(AccessPath::FullScan, None, rows, vec![]);
+    };
+    let filters = filter.describe();
+    if let Some((index, value)) = filter.indexed_lookup(&mdl.secondary_indexes().read()) {
+        let matched = index.lookup(&value).count() as u64;
+        return Plan::new(
+            AccessPath::IndexScan,
+            Some(index.name().into()),
+            matched,
+            filters,
+        );
+    }
+    let cached = mdl
+        .columnar_cache()
+        .read()
+        .as_ref()
+        .is_some_and(|cache| filter.is_cached(cache));
+    if cached {
+        Plan::new(AccessPath::CacheScan, None, rows, filters)
+    } else if filter.pk_prefix().is_some() & (mdl.primary_index().kind() == PrimaryIndexKind::Radix)
+    {
+        Plan::new(AccessPath::PrefixScan, None, rows, filters)
+    } else {
+        Plan::new(AccessPath::FullScan, None, rows, filters)
+    }
+}
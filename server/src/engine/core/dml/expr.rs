@@ -0,0 +1,555 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    crate::engine::{
+        core::model::Layer,
+        data::{
+            cell::Datacell,
+            lit::Lit,
+            tag::{DataTag, FloatSpec, FullTag, SIntSpec, TagClass, UIntSpec},
+        },
+        error::{QueryError, QueryResult},
+        ql::{
+            dml::expr::{BinaryOp, Expr},
+            lex::Ident,
+        },
+    },
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+/*
+    Scalar function evaluation
+    ---
+    NULL handling follows SQL: unless a function is explicitly meant to deal with nulls (like `coalesce`), a null
+    argument makes the result null without the function ever being called
+*/
+
+type ScalarFn = fn(Vec<Datacell>) -> QueryResult<Datacell>;
+
+struct ScalarFunction {
+    name: &'static str,
+    /// if set, a null argument produces a null result
+    null_propagating: bool,
+    f: ScalarFn,
+}
+
+impl ScalarFunction {
+    const fn new(name: &'static str, null_propagating: bool, f: ScalarFn) -> Self {
+        Self {
+            name,
+            null_propagating,
+            f,
+        }
+    }
+}
+
+static SCALAR_FUNCTIONS: [ScalarFunction; 11] = [
+    // null handling
+    ScalarFunction::new("coalesce", false, sfn_coalesce),
+    ScalarFunction::new("ifnull", false, sfn_ifnull),
+    // strings
+    ScalarFunction::new("lower", true, sfn_lower),
+    ScalarFunction::new("upper", true, sfn_upper),
+    ScalarFunction::new("length", true, sfn_length),
+    ScalarFunction::new("substr", true, sfn_substr),
+    ScalarFunction::new("concat", true, sfn_concat),
+    // numbers
+    ScalarFunction::new("abs", true, sfn_abs),
+    ScalarFunction::new("round", true, sfn_round),
+    // generators
+    ScalarFunction::new("now", false, sfn_now),
+    ScalarFunction::new("uuid", false, sfn_uuid),
+];
+
+fn ldfunc(name: &str) -> Option<&'static ScalarFunction> {
+    SCALAR_FUNCTIONS
+        .iter()
+        .find(|sfn| sfn.name.eq_ignore_ascii_case(name))
+}
+
+/// Evaluate the expression, using `fetch` to load the value of a field
+pub fn eval(
+    expr: &Expr,
+    fetch: &mut impl FnMut(&str) -> QueryResult<Datacell>,
+) -> QueryResult<Datacell> {
+    match expr {
+        Expr::Field(field) => fetch(field.as_str()),
+        Expr::Value(v) => Ok(v.clone()),
+        Expr::Call(func, args) => {
+            let Some(sfn) = ldfunc(func.as_str()) else {
+                return Err(QueryError::QExecUnknownFunction);
+            };
+            let mut evaluated = Vec::with_capacity(args.len());
+            for arg in args {
+                evaluated.push(eval(arg, fetch)?);
+            }
+            if sfn.null_propagating & evaluated.iter().any(Datacell::is_null) {
+                return Ok(Datacell::null());
+            }
+            (sfn.f)(evaluated)
+        }
+        Expr::Cast(expr, ty) => {
+            let Some(layer) = Layer::get_layer(ty.as_str()) else {
+                return Err(QueryError::QExecDmlValidationError);
+            };
+            cast(eval(expr, fetch)?, layer.tag())
+        }
+        Expr::Binary(op, lhs, rhs) => arith(*op, eval(lhs, fetch)?, eval(rhs, fetch)?),
+        // aggregates go over a set of rows (see `dml::agg`)
+        Expr::Aggregate(_) => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+/*
+    arithmetic
+    ---
+    - a null operand makes the result null
+    - two unsigned integers give an unsigned integer, two integers (at least one of them signed) give a signed integer
+    and anything with a float gives a float. the result is always the widest type of its kind
+    - an overflow fails with a lossy cast error and a division by zero fails with a validation error
+*/
+
+fn arith(op: BinaryOp, lhs: Datacell, rhs: Datacell) -> QueryResult<Datacell> {
+    if lhs.is_null() | rhs.is_null() {
+        return Ok(Datacell::null());
+    }
+    let div = op == BinaryOp::Div;
+    match (lhs.kind(), rhs.kind()) {
+        (TagClass::UnsignedInt, TagClass::UnsignedInt) => {
+            let (a, b) = (lhs.uint(), rhs.uint());
+            if div & (b == 0) {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            let ret = match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+            };
+            ret.map(Datacell::new_uint_default)
+                .ok_or(QueryError::QExecDmlLossyCast)
+        }
+        (
+            TagClass::UnsignedInt | TagClass::SignedInt,
+            TagClass::UnsignedInt | TagClass::SignedInt,
+        ) => {
+            let (a, b) = (cast_to_sint(&lhs)?, cast_to_sint(&rhs)?);
+            if div & (b == 0) {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            let ret = match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+            };
+            ret.map(Datacell::new_sint_default)
+                .ok_or(QueryError::QExecDmlLossyCast)
+        }
+        (
+            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float,
+            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float,
+        ) => {
+            let (a, b) = (cast_to_float(&lhs)?, cast_to_float(&rhs)?);
+            if div & (b == 0.0) {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            let ret = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+            };
+            if ret.is_finite() {
+                Ok(Datacell::new_float_default(ret))
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+/*
+    null handling
+*/
+
+fn sfn_coalesce(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    if args.is_empty() {
+        return Err(QueryError::QExecDmlValidationError);
+    }
+    Ok(args
+        .into_iter()
+        .find(Datacell::is_init)
+        .unwrap_or_else(Datacell::null))
+}
+
+fn sfn_ifnull(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let [value, fallback] = args_exact::<2>(args)?;
+    if value.is_null() {
+        Ok(fallback)
+    } else {
+        Ok(value)
+    }
+}
+
+fn args_exact<const N: usize>(args: Vec<Datacell>) -> QueryResult<[Datacell; N]> {
+    args.try_into()
+        .map_err(|_| QueryError::QExecDmlValidationError)
+}
+
+fn arg_uint(dc: &Datacell) -> QueryResult<u64> {
+    dc.try_uint().ok_or(QueryError::QExecDmlValidationError)
+}
+
+fn arg_str(dc: &Datacell) -> QueryResult<&str> {
+    dc.try_str().ok_or(QueryError::QExecDmlValidationError)
+}
+
+/*
+    strings
+*/
+
+fn sfn_lower(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let [s] = args_exact::<1>(args)?;
+    Ok(Datacell::new_str(
+        arg_str(&s)?.to_lowercase().into_boxed_str(),
+    ))
+}
+
+fn sfn_upper(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let [s] = args_exact::<1>(args)?;
+    Ok(Datacell::new_str(
+        arg_str(&s)?.to_uppercase().into_boxed_str(),
+    ))
+}
+
+fn sfn_length(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let [v] = args_exact::<1>(args)?;
+    let len = match v.kind() {
+        TagClass::Str => v.str().chars().count(),
+        TagClass::Bin => v.bin().len(),
+        TagClass::List => v.list().read().len(),
+        TagClass::Map => v.map().read().len(),
+        _ => return Err(QueryError::QExecDmlValidationError),
+    };
+    Ok(Datacell::new_uint_default(len as u64))
+}
+
+/// `substr(s, start[, len])`, where `start` is 1-based and both `start` and `len` count characters
+fn sfn_substr(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let (s, start, len) = match args.len() {
+        2 => {
+            let [s, start] = args_exact::<2>(args)?;
+            (s, arg_uint(&start)?, None)
+        }
+        3 => {
+            let [s, start, len] = args_exact::<3>(args)?;
+            (s, arg_uint(&start)?, Some(arg_uint(&len)? as usize))
+        }
+        _ => return Err(QueryError::QExecDmlValidationError),
+    };
+    if start == 0 {
+        return Err(QueryError::QExecDmlValidationError);
+    }
+    let chars = arg_str(&s)?.chars().skip(start as usize - 1);
+    let ret: String = match len {
+        Some(len) => chars.take(len).collect(),
+        None => chars.collect(),
+    };
+    Ok(Datacell::new_str(ret.into_boxed_str()))
+}
+
+fn sfn_concat(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let mut ret = String::new();
+    for arg in args {
+        ret.push_str(&cast_to_str(&arg)?);
+    }
+    Ok(Datacell::new_str(ret.into_boxed_str()))
+}
+
+/*
+    numbers
+*/
+
+fn sfn_abs(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let [v] = args_exact::<1>(args)?;
+    match v.kind() {
+        TagClass::UnsignedInt => Ok(v),
+        TagClass::SignedInt => {
+            let spec = unsafe {
+                // UNSAFE(@ohsayan): verified tag class
+                SIntSpec::from_full(v.tag())
+            };
+            match v.sint().checked_abs() {
+                Some(abs) if spec.check(abs) => Ok(Datacell::new_sint(abs, spec)),
+                _ => Err(QueryError::QExecDmlLossyCast),
+            }
+        }
+        TagClass::Float => {
+            let spec = unsafe {
+                // UNSAFE(@ohsayan): verified tag class
+                FloatSpec::from_full(v.tag())
+            };
+            Ok(Datacell::new_float(v.float().abs(), spec))
+        }
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+/// `round(f[, digits])`
+fn sfn_round(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    let (v, digits) = match args.len() {
+        1 => {
+            let [v] = args_exact::<1>(args)?;
+            (v, 0)
+        }
+        2 => {
+            let [v, digits] = args_exact::<2>(args)?;
+            (v, arg_uint(&digits)?)
+        }
+        _ => return Err(QueryError::QExecDmlValidationError),
+    };
+    match v.kind() {
+        TagClass::UnsignedInt | TagClass::SignedInt => Ok(v),
+        TagClass::Float if digits <= f64::DIGITS as u64 => {
+            let spec = unsafe {
+                // UNSAFE(@ohsayan): verified tag class
+                FloatSpec::from_full(v.tag())
+            };
+            let scale = 10f64.powi(digits as i32);
+            Ok(Datacell::new_float(
+                (v.float() * scale).round() / scale,
+                spec,
+            ))
+        }
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+/*
+    generators
+*/
+
+fn sfn_now(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    args_exact::<0>(args)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| QueryError::SysServerError)?;
+    Ok(Datacell::new_uint_default(now.as_secs()))
+}
+
+fn sfn_uuid(args: Vec<Datacell>) -> QueryResult<Datacell> {
+    args_exact::<0>(args)?;
+    Ok(Datacell::new_str(
+        uuid::Uuid::new_v4().to_string().into_boxed_str(),
+    ))
+}
+
+/*
+    cast
+    ---
+    - a null always casts to a null
+    - a conversion that would lose information (out of range, fractional part, ...) fails with a lossy cast error
+    - a conversion that doesn't make sense (like a string that isn't a number into an integer) fails with a validation error
+*/
+
+fn cast(dc: Datacell, target: FullTag) -> QueryResult<Datacell> {
+    if dc.is_null() | (dc.tag() == target) {
+        return Ok(dc);
+    }
+    match target.tag_class() {
+        TagClass::Bool => cast_to_bool(&dc).map(Datacell::new_bool),
+        TagClass::UnsignedInt => {
+            let spec = unsafe {
+                // UNSAFE(@ohsayan): we just verified the tag class
+                UIntSpec::from_full(target)
+            };
+            let v = cast_to_uint(&dc)?;
+            if spec.check(v) {
+                Ok(Datacell::new_uint(v, spec))
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::SignedInt => {
+            let spec = unsafe {
+                // UNSAFE(@ohsayan): we just verified the tag class
+                SIntSpec::from_full(target)
+            };
+            let v = cast_to_sint(&dc)?;
+            if spec.check(v) {
+                Ok(Datacell::new_sint(v, spec))
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::Float => {
+            let spec = unsafe {
+                // UNSAFE(@ohsayan): we just verified the tag class
+                FloatSpec::from_full(target)
+            };
+            let v = cast_to_float(&dc)?;
+            if spec.check(v) {
+                Ok(Datacell::new_float(v, spec))
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::Bin => match dc.kind() {
+            TagClass::Str => Ok(Datacell::new_bin(dc.str().as_bytes().into())),
+            _ => Err(QueryError::QExecDmlValidationError),
+        },
+        TagClass::Str => cast_to_str(&dc).map(|s| Datacell::new_str(s.into_boxed_str())),
+        TagClass::List | TagClass::Map => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+/// Cast a literal (in a where clause or an assignment) to the type with the given name. A literal can only hold a
+/// 64-bit value, so a cast to a narrower type only checks that the value fits
+pub fn cast_lit<'a>(lit: Lit<'a>, ty: Ident) -> QueryResult<Lit<'a>> {
+    let Some(layer) = Layer::get_layer(ty.as_str()) else {
+        return Err(QueryError::QExecDmlValidationError);
+    };
+    let dc = cast(Datacell::from(lit), layer.tag())?;
+    match dc.kind() {
+        TagClass::Bool => Ok(Lit::new_bool(dc.bool())),
+        TagClass::UnsignedInt => Ok(Lit::new_uint(dc.uint())),
+        TagClass::SignedInt => Ok(Lit::new_sint(dc.sint())),
+        TagClass::Float => Ok(Lit::new_float(dc.float())),
+        TagClass::Str => Ok(Lit::new_string(dc.str().to_owned())),
+        // a literal can't own binary data
+        TagClass::Bin | TagClass::List | TagClass::Map => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+fn cast_to_bool(dc: &Datacell) -> QueryResult<bool> {
+    match dc.kind() {
+        TagClass::Bool => Ok(dc.bool()),
+        TagClass::UnsignedInt => match dc.uint() {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(QueryError::QExecDmlLossyCast),
+        },
+        TagClass::SignedInt => match dc.sint() {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(QueryError::QExecDmlLossyCast),
+        },
+        TagClass::Str => match dc.str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(QueryError::QExecDmlValidationError),
+        },
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+fn cast_to_uint(dc: &Datacell) -> QueryResult<u64> {
+    match dc.kind() {
+        TagClass::Bool => Ok(dc.bool() as u64),
+        TagClass::UnsignedInt => Ok(dc.uint()),
+        TagClass::SignedInt => u64::try_from(dc.sint()).map_err(|_| QueryError::QExecDmlLossyCast),
+        TagClass::Float => {
+            let f = dc.float();
+            if (f.fract() == 0.0) & (f >= 0.0) & (f < u64::MAX as f64) {
+                Ok(f as u64)
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::Str => dc
+            .str()
+            .parse()
+            .map_err(|_| QueryError::QExecDmlValidationError),
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+fn cast_to_sint(dc: &Datacell) -> QueryResult<i64> {
+    match dc.kind() {
+        TagClass::Bool => Ok(dc.bool() as i64),
+        TagClass::UnsignedInt => {
+            i64::try_from(dc.uint()).map_err(|_| QueryError::QExecDmlLossyCast)
+        }
+        TagClass::SignedInt => Ok(dc.sint()),
+        TagClass::Float => {
+            let f = dc.float();
+            if (f.fract() == 0.0) & (f >= i64::MIN as f64) & (f < i64::MAX as f64) {
+                Ok(f as i64)
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::Str => dc
+            .str()
+            .parse()
+            .map_err(|_| QueryError::QExecDmlValidationError),
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+fn cast_to_float(dc: &Datacell) -> QueryResult<f64> {
+    match dc.kind() {
+        TagClass::UnsignedInt => {
+            let u = dc.uint();
+            if (u as f64) as u64 == u {
+                Ok(u as f64)
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::SignedInt => {
+            let s = dc.sint();
+            if (s as f64) as i64 == s {
+                Ok(s as f64)
+            } else {
+                Err(QueryError::QExecDmlLossyCast)
+            }
+        }
+        TagClass::Float => Ok(dc.float()),
+        TagClass::Str => dc
+            .str()
+            .parse()
+            .map_err(|_| QueryError::QExecDmlValidationError),
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
+fn cast_to_str(dc: &Datacell) -> QueryResult<String> {
+    match dc.kind() {
+        TagClass::Bool => Ok(dc.bool().to_string()),
+        TagClass::UnsignedInt => Ok(dc.uint().to_string()),
+        TagClass::SignedInt => Ok(dc.sint().to_string()),
+        TagClass::Float => Ok(dc.float().to_string()),
+        TagClass::Bin => {
+            String::from_utf8(dc.bin().to_vec()).map_err(|_| QueryError::QExecDmlValidationError)
+        }
+        TagClass::Str => Ok(dc.str().to_owned()),
+        TagClass::List | TagClass::Map => Err(QueryError::QExecDmlValidationError),
+    }
+}
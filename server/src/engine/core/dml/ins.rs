@@ -24,19 +24,24 @@
  *
 */
 
-use crate::engine::{
-    core::{
-        self,
-        dml::QueryExecMeta,
-        index::{DcFieldIndex, PrimaryIndexKey, Row},
-        model::{delta::DataDeltaKind, ModelData},
+use {
+    crate::engine::{
+        core::{
+            self,
+            dml::QueryExecMeta,
+            index::{DcFieldIndex, PrimaryIndexKey, Row, RowData},
+            model::{delta::DataDeltaKind, Expiry, ModelData, EXTRA_FIELD},
+        },
+        data::{cell::Datacell, tag::TagClass},
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::{IndexBaseSpec, STIndex, STIndexExt, STIndexSeq},
+        net::protocol::Response,
+        ql::dml::ins::{InsertData, InsertStatement},
+        sync::atm::{cpin, Guard},
     },
-    error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
-    idx::{IndexBaseSpec, MTIndex, STIndex, STIndexExt, STIndexSeq},
-    net::protocol::Response,
-    ql::dml::ins::{InsertData, InsertStatement},
-    sync::atm::cpin,
+    parking_lot::RwLockWriteGuard,
+    std::{collections::HashSet, mem},
 };
 
 pub fn insert_resp(
@@ -48,6 +53,10 @@ pub fn insert_resp(
 
 pub fn insert(global: &impl GlobalInstanceLike, insert: InsertStatement) -> QueryResult<()> {
     core::with_model_for_data_update(global, insert.entity(), |mdl| {
+        // the values of unique fields can only be claimed with the latch held exclusively (see `model::unique`)
+        if (insert.row_count() != 1) | mdl.has_unique_fields() {
+            return insert_multi(mdl, insert);
+        }
         let (pk, data) = prepare_insert(mdl, insert.data())?;
         let _idx_latch = mdl.primary_index().acquire_cd();
         let g = cpin();
@@ -56,37 +65,208 @@ pub fn insert(global: &impl GlobalInstanceLike, insert: InsertStatement) -> Quer
         let new_version = ds.create_new_data_delta_version();
         let row = Row::new(pk, data, ds.schema_current_version(), new_version);
         if mdl.primary_index().__raw_index().mt_insert(row.clone(), &g) {
+            mdl.columnar_cache_upsert(&row);
+            mdl.secondary_indexes_upsert(&row);
             // append delta for new version
             let dp = ds.append_new_data_delta_with(DataDeltaKind::Insert, row, new_version, &g);
             Ok(QueryExecMeta::new(dp))
         } else {
-            Err(QueryError::QExecDmlDuplicate)
+            // the key is taken, but that's fine if the row that has it expired
+            let data = mem::take(row.d_data().write().fields_mut());
+            match replace_expired(mdl, mdl.expiry(), row.d_key(), data, &g) {
+                Some(dp) => Ok(QueryExecMeta::new(dp)),
+                None => Err(QueryError::QExecDmlDuplicate),
+            }
+        }
+    })
+}
+
+pub fn upsert_resp(
+    global: &impl GlobalInstanceLike,
+    upsert: InsertStatement,
+) -> QueryResult<Response> {
+    self::upsert(global, upsert).map(|_| Response::Empty)
+}
+
+/// Insert the given row(s), replacing any row that already has the same primary key
+pub fn upsert(global: &impl GlobalInstanceLike, upsert: InsertStatement) -> QueryResult<()> {
+    core::with_model_for_data_update(global, upsert.entity(), |mdl| {
+        // an upsert may replace a row
+        mdl.check_rows_mutable()?;
+        let mut rows = Vec::with_capacity(upsert.row_count());
+        for data in upsert.rows() {
+            rows.push(prepare_insert(mdl, data)?);
+        }
+        // with unique fields, no other write can change the claims until we're done (see `model::unique`)
+        let unique = mdl.has_unique_fields();
+        let _idx_latch = (!unique).then(|| mdl.primary_index().acquire_cd());
+        let _idx_latch_x = unique.then(|| mdl.primary_index().acquire_exclusive());
+        mdl.unique_claim(rows.iter().map(|(pk, data)| (pk, data)))?;
+        // every row is valid, so nothing below can fail
+        let g = cpin();
+        let mut dp = 0;
+        for (pk, data) in rows {
+            dp = upsert_row(mdl, pk, data, &g);
         }
+        Ok(QueryExecMeta::new(dp))
     })
 }
 
+fn upsert_row(
+    mdl: &ModelData,
+    mut pk: PrimaryIndexKey,
+    mut data: DcFieldIndex,
+    g: &Guard,
+) -> usize {
+    let ds = mdl.delta_state();
+    loop {
+        if let Some(row) = mdl.primary_index().__raw_index().mt_get_element(&pk, g) {
+            // replace the row in place (holding the row lock orders us with any concurrent update)
+            let row_data_wl = row.d_data().write();
+            return replace_row(mdl, row, row_data_wl, data, g);
+        }
+        let new_version = ds.create_new_data_delta_version();
+        let row = Row::new(pk, data, ds.schema_current_version(), new_version);
+        if mdl.primary_index().__raw_index().mt_insert(row.clone(), g) {
+            mdl.columnar_cache_upsert(&row);
+            mdl.secondary_indexes_upsert(&row);
+            return ds.append_new_data_delta_with(DataDeltaKind::Insert, row, new_version, g);
+        }
+        // someone inserted this key right after we looked, so take our data back and replace their row instead
+        pk = row.d_key().clone();
+        data = mem::take(row.d_data().write().fields_mut());
+    }
+}
+
+/// Replace all the data of the (locked) row. Returns the size of the delta queue
+fn replace_row(
+    mdl: &ModelData,
+    row: &Row,
+    mut row_data_wl: RwLockWriteGuard<RowData>,
+    data: DcFieldIndex,
+    g: &Guard,
+) -> usize {
+    let ds = mdl.delta_state();
+    let new_version = ds.create_new_data_delta_version();
+    row_data_wl.replace_fields(data, ds.schema_current_version());
+    row_data_wl.set_txn_revised(new_version);
+    let dp = ds.append_new_data_delta_with(DataDeltaKind::Update, row.clone(), new_version, g);
+    drop(row_data_wl);
+    mdl.columnar_cache_upsert(row);
+    mdl.secondary_indexes_upsert(row);
+    dp
+}
+
+/// Replace the row that has the key with the given data, but only if it expired. Returns the size of the delta queue
+/// or `None` if the row is still live
+fn replace_expired(
+    mdl: &ModelData,
+    expiry: Expiry,
+    pk: &PrimaryIndexKey,
+    data: DcFieldIndex,
+    g: &Guard,
+) -> Option<usize> {
+    let row = mdl.primary_index().__raw_index().mt_get_element(pk, g)?;
+    let row_data_wl = row.d_data().write();
+    if !expiry.is_expired(&row_data_wl) {
+        return None;
+    }
+    Some(replace_row(mdl, row, row_data_wl, data, g))
+}
+
+/// Insert every row or none of them
+fn insert_multi(mdl: &ModelData, insert: InsertStatement) -> QueryResult<QueryExecMeta> {
+    let mut rows = Vec::with_capacity(insert.row_count());
+    for data in insert.rows() {
+        rows.push(prepare_insert(mdl, data)?);
+    }
+    /*
+        no other insert or delete can run while we hold the latch exclusively, so if none of the keys are present
+        right now (and no key is repeated), every insert below will go through. a key that belongs to an expired row
+        is as good as absent since the row is replaced (and an update can't make it live again). the same goes for the
+        values of unique fields, which are claimed up front
+    */
+    let _idx_latch = mdl.primary_index().acquire_exclusive();
+    let g = cpin();
+    let expiry = mdl.expiry();
+    let mut keys = HashSet::with_capacity(rows.len());
+    for (pk, _) in rows.iter() {
+        let taken = mdl
+            .primary_index()
+            .__raw_index()
+            .mt_get_element(pk, &g)
+            .is_some_and(|row| !expiry.is_expired(&row.d_data().read()));
+        if !keys.insert(pk) || taken {
+            return Err(QueryError::QExecDmlDuplicate);
+        }
+    }
+    drop(keys);
+    mdl.unique_claim(rows.iter().map(|(pk, data)| (pk, data)))?;
+    let ds = mdl.delta_state();
+    let mut dp = 0;
+    for (pk, data) in rows {
+        let new_version = ds.create_new_data_delta_version();
+        let row = Row::new(pk, data, ds.schema_current_version(), new_version);
+        if mdl.primary_index().__raw_index().mt_insert(row.clone(), &g) {
+            mdl.columnar_cache_upsert(&row);
+            mdl.secondary_indexes_upsert(&row);
+            dp = ds.append_new_data_delta_with(DataDeltaKind::Insert, row, new_version, &g);
+        } else {
+            // the row that has this key expired
+            let data = mem::take(row.d_data().write().fields_mut());
+            let replaced = replace_expired(mdl, expiry, row.d_key(), data, &g);
+            debug_assert!(replaced.is_some());
+            dp = replaced.unwrap_or(dp);
+        }
+    }
+    Ok(QueryExecMeta::new(dp))
+}
+
 // TODO(@ohsayan): optimize null case
 fn prepare_insert(
     model: &ModelData,
     insert: InsertData,
 ) -> QueryResult<(PrimaryIndexKey, DcFieldIndex)> {
     let fields = model.fields();
-    let mut okay = fields.len() == insert.column_count();
+    let strict = model.is_strict();
+    // the hidden column of a non-strict model can be left out (it can only be given to put back the output of a select)
+    let declared = fields.len() - (!strict as usize);
+    let mut okay = match &insert {
+        InsertData::Ordered(_) => {
+            (declared == insert.column_count())
+                | (!strict & (insert.column_count() == fields.len()))
+        }
+        // the fields that were left out are checked once we have the rest
+        InsertData::Map(_) => true,
+    };
+    let mut null_violation = false;
     let mut prepared_data = DcFieldIndex::idx_init_cap(fields.len());
+    let mut extra = vec![];
+    let mut given_extra = None;
     match insert {
         InsertData::Ordered(tuple) => {
+            let with_extra = tuple.len() == fields.len();
             let mut fields = fields.stseq_ord_kv();
             let mut tuple = tuple.into_iter();
             while (tuple.len() != 0) & okay {
                 let mut data;
                 let field;
                 unsafe {
-                    // UNSAFE(@ohsayan): safe because of invariant
-                    data = tuple.next().unwrap_unchecked();
                     // UNSAFE(@ohsayan): safe because of flag
                     field = fields.next().unwrap_unchecked();
                 }
                 let (field_id, field) = field;
+                if field_id.as_str() == EXTRA_FIELD {
+                    if with_extra {
+                        given_extra = tuple.next();
+                    }
+                    continue;
+                }
+                unsafe {
+                    // UNSAFE(@ohsayan): safe because of invariant
+                    data = tuple.next().unwrap_unchecked();
+                }
+                null_violation |= data.is_null() & !field.is_nullable();
                 okay &= field.vt_data_fpath(&mut data);
                 okay &= prepared_data.st_insert(
                     unsafe {
@@ -98,7 +278,6 @@ fn prepare_insert(
             }
         }
         InsertData::Map(map) => {
-            let mut inserted = 0;
             let mut map = map.into_iter();
             while (map.len() != 0) & okay {
                 let (field_id, mut data) = unsafe {
@@ -108,11 +287,18 @@ fn prepare_insert(
                 let (spec_field_name, spec_field) =
                     match fields.stext_get_key_value(field_id.as_str()) {
                         Some(f) => f,
+                        None if !strict => {
+                            // an ad-hoc field
+                            okay &= is_extra_value(&data);
+                            extra.push((field_id.as_str().to_owned().into_boxed_str(), data));
+                            continue;
+                        }
                         None => {
                             okay = false;
                             break;
                         }
                     };
+                null_violation |= data.is_null() & !spec_field.is_nullable();
                 okay &= spec_field.vt_data_fpath(&mut data);
                 prepared_data.st_insert(
                     unsafe {
@@ -121,14 +307,61 @@ fn prepare_insert(
                     },
                     data,
                 );
-                inserted += 1;
             }
-            okay &= inserted == fields.len();
+            // a field that was left out is null (so it has to be nullable)
+            for (field_id, field) in fields.stseq_ord_kv() {
+                if !okay {
+                    break;
+                }
+                if (field_id.as_str() == EXTRA_FIELD) | prepared_data.st_contains(field_id.as_str())
+                {
+                    continue;
+                }
+                null_violation |= !field.is_nullable();
+                prepared_data.st_insert(
+                    unsafe {
+                        // UNSAFE(@ohsayan): as long as model lives, we're good
+                        field_id.clone()
+                    },
+                    Datacell::null(),
+                );
+            }
         }
     }
+    if !strict {
+        let (extra_field, _) = fields.stext_get_key_value(EXTRA_FIELD).unwrap();
+        let extra = match given_extra {
+            Some(given) => {
+                okay &= is_extra_cell(model, &given);
+                given
+            }
+            None if extra.is_empty() => Datacell::null(),
+            None => {
+                // keep the order stable
+                extra.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                Datacell::new_list(
+                    extra
+                        .into_iter()
+                        .flat_map(|(k, v)| [Datacell::new_str(k), v])
+                        .collect(),
+                )
+            }
+        };
+        prepared_data.st_insert(
+            unsafe {
+                // UNSAFE(@ohsayan): as long as model lives, we're good
+                extra_field.clone()
+            },
+            extra,
+        );
+    }
     let primary_key = prepared_data.remove(model.p_key());
     okay &= primary_key.is_some();
+    if null_violation {
+        return Err(QueryError::QExecDmlNullViolation);
+    }
     if okay {
+        core::row_size::check_cells(primary_key.iter().chain(prepared_data.st_iter_value()))?;
         let primary_key = unsafe {
             // UNSAFE(@ohsayan): okay check above
             PrimaryIndexKey::new_from_dc(primary_key.unwrap_unchecked())
@@ -138,3 +371,30 @@ fn prepare_insert(
         Err(QueryError::QExecDmlValidationError)
     }
 }
+
+/// Check if the cell can be the hidden column of a non-strict model: either null or a list of alternating keys and
+/// values, where every key is a string that isn't the name of a declared field (and isn't repeated) and every value
+/// passes [`is_extra_value`]
+fn is_extra_cell(model: &ModelData, dc: &Datacell) -> bool {
+    if dc.is_null() {
+        return true;
+    }
+    if dc.kind() != TagClass::List {
+        return false;
+    }
+    let list = dc.list().read();
+    let mut keys = HashSet::new();
+    list.len().is_multiple_of(2)
+        && list.chunks(2).all(|kv| {
+            (kv[0].kind() == TagClass::Str)
+                && !model.fields().st_contains(kv[0].str())
+                && keys.insert(kv[0].str())
+                && is_extra_value(&kv[1])
+        })
+}
+
+/// Check if the value can be stored in an ad-hoc field: it has to be null or a single value (lists would be stored
+/// without any of the checks that a declared field gets, so they aren't allowed)
+fn is_extra_value(dc: &Datacell) -> bool {
+    dc.is_null() | (dc.kind() != TagClass::List)
+}
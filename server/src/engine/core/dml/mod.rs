@@ -24,32 +24,42 @@
  *
 */
 
+mod agg;
 mod del;
+mod explain;
+mod expr;
 mod ins;
+mod scan;
 mod sel;
 mod upd;
 
 use crate::{
     engine::{
-        core::model::ModelData,
+        core::{index::DcFieldIndex, model::ModelData},
         data::{lit::Lit, tag::DataTag},
         error::{QueryError, QueryResult},
+        idx::STIndex,
         ql::dml::WhereClause,
     },
     util::compiler,
 };
 
+pub(in crate::engine::core) use sel::encode_cell;
+pub(in crate::engine) use expr::cast_lit;
 #[cfg(test)]
 pub use {
+    agg::aggregate,
     del::delete,
-    ins::insert,
-    sel::{select_all, select_custom},
+    explain::{explain, AccessPath, Plan},
+    ins::{insert, upsert},
+    sel::{select_all, select_custom, select_multi},
     upd::{collect_trace_path as update_flow_trace, update},
 };
 pub use {
     del::delete_resp,
-    ins::insert_resp,
-    sel::{select_all_resp, select_resp},
+    explain::explain_resp,
+    ins::{insert_resp, upsert_resp},
+    sel::{select_all_resp, select_resp, ResultFormat},
     upd::update_resp,
 };
 
@@ -65,9 +75,36 @@ impl ModelData {
             {
                 Ok(clause.rhs())
             }
+            // the key is never null, so comparing it with a null never matches a row
+            Some(clause) if clause.is_cmp_null() => {
+                compiler::cold_rerr(QueryError::QExecDmlRowNotFound)
+            }
             _ => compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
         }
     }
+    /// Check if the row satisfies the clauses that are left behind after the key has been resolved
+    /// using [`Self::resolve_where`]
+    ///
+    /// Only null tests (and comparisons with null, which never match) can be evaluated on non-key fields (since we
+    /// don't have any secondary indexes)
+    pub(self) fn where_residual_matches(
+        &self,
+        where_clause: &WhereClause,
+        fields: &DcFieldIndex,
+    ) -> QueryResult<bool> {
+        let mut okay = true;
+        for (field_id, clause) in where_clause.clauses() {
+            let Some(dc) = fields.st_get(field_id.as_str()) else {
+                return compiler::cold_rerr(QueryError::QExecUnknownField);
+            };
+            match clause.null_test() {
+                Some(expect_null) => okay &= dc.is_null() == expect_null,
+                None if clause.is_cmp_null() => okay = false,
+                None => return compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
+            }
+        }
+        Ok(okay)
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,549 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    vectorized scan filters
+    ---
+    a scan with a where clause reads rows in batches. for every comparison, the values of the column are copied out of
+    the batch into a contiguous scratch buffer and then compared in one go, a fixed number of lanes at a time and
+    without branches, so that the compiler can use SIMD instructions for the comparisons. the masks for every clause
+    are and-ed together and only the rows that pass are sent back. nulls never pass a comparison: comparing with a null
+    is unknown (not true), whether the null is in the row (`x > 1` on a null `x`) or in the query (`x = null`)
+
+    if the model has a columnar cache, the comparisons run on the cached columns instead and only the rows that pass
+    are read. these rows are filtered once more as they are read since they could have changed in the meantime
+
+    string and binary primary keys are compared byte by byte, and can also be matched by prefix (`STARTS WITH`). if
+    the model uses the radix index, a prefix match only reads the rows under that prefix instead of every row. a range
+    of keys is read the same way (under the prefix that both ends share) and the scan stops at the end of the range
+
+    string and binary fields that have a secondary index can be compared too. if the filter tests an indexed field for
+    equality, the index is used to find the rows and only those are read (and filtered like the cached ones)
+*/
+
+use {
+    crate::engine::{
+        core::{
+            index::{PrimaryIndexKey, RowData},
+            model::{
+                columnar::{ColumnValues, ColumnarCache},
+                secondary::IndexedValue,
+                ModelData, SecondaryIndex,
+            },
+        },
+        data::{
+            cell::Datacell,
+            lit::Lit,
+            tag::{DataTag, TagClass},
+        },
+        error::{QueryError, QueryResult},
+        idx::STIndex,
+        ql::dml::{RelationalExpr, WhereClause},
+    },
+    parking_lot::RwLockReadGuard,
+};
+
+/// The number of rows read into a batch
+pub const SCAN_BATCH_SIZE: usize = 1024;
+/// The number of values compared in a single step
+const LANES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CmpOp {
+    fn from_opc(opc: u8) -> Self {
+        match opc {
+            RelationalExpr::OP_EQ => Self::Eq,
+            RelationalExpr::OP_NE => Self::Ne,
+            RelationalExpr::OP_GT => Self::Gt,
+            RelationalExpr::OP_GE => Self::Ge,
+            RelationalExpr::OP_LT => Self::Lt,
+            RelationalExpr::OP_LE => Self::Le,
+            _ => unreachable!(),
+        }
+    }
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Predicate<'a> {
+    UInt(CmpOp, u64),
+    SInt(CmpOp, i64),
+    Float(CmpOp, f64),
+    /// A comparison on a string or binary primary key (or indexed field)
+    Bytes(CmpOp, &'a [u8]),
+    /// `STARTS WITH` on a string or binary primary key
+    Prefix(&'a [u8]),
+    /// `IS NULL` (true) or `IS NOT NULL` (false)
+    Null(bool),
+    /// A comparison with null, which no row passes
+    Unknown,
+}
+
+#[derive(Debug, PartialEq)]
+struct ColumnFilter<'a> {
+    field: &'a str,
+    pk: bool,
+    predicate: Predicate<'a>,
+}
+
+#[derive(Debug, PartialEq)]
+/// A where clause compiled against a model for a scan
+pub struct ScanFilter<'a> {
+    columns: Vec<ColumnFilter<'a>>,
+}
+
+impl<'a> ScanFilter<'a> {
+    /// Compile the where clause. Only null tests, comparisons on numeric fields and comparisons on the primary key (or
+    /// an indexed field) are supported
+    pub fn compile(mdl: &'a ModelData, clause: &'a WhereClause<'a>) -> QueryResult<Self> {
+        let mut columns = Vec::with_capacity(clause.clauses().len());
+        let indexes = mdl.secondary_indexes().read();
+        for (field_id, expr) in clause.clauses() {
+            let field = field_id.as_str();
+            let Some(field_info) = mdl.fields().st_get(field) else {
+                return Err(QueryError::QExecUnknownField);
+            };
+            let is_pk = field == mdl.p_key();
+            let indexed = indexes.iter().any(|index| index.field() == field);
+            let predicate = match expr.null_test() {
+                Some(expect_null) => Predicate::Null(expect_null),
+                None if expr.is_cmp_null() => Predicate::Unknown,
+                None if expr.opc() == RelationalExpr::OP_STARTS_WITH => {
+                    let rhs = expr.rhs_ref();
+                    let prefix = match field_info.layers()[0].tag().tag_class() {
+                        TagClass::Str if is_pk => rhs.try_str().map(str::as_bytes),
+                        TagClass::Bin if is_pk => rhs.try_bin(),
+                        _ => return Err(QueryError::QExecDmlWhereHasUnindexedColumn),
+                    };
+                    match prefix {
+                        Some(prefix) => Predicate::Prefix(prefix),
+                        None => return Err(QueryError::QExecDmlValidationError),
+                    }
+                }
+                None => Self::compile_cmp(
+                    field_info.layers()[0].tag().tag_class(),
+                    is_pk | indexed,
+                    expr.opc(),
+                    expr.rhs_ref(),
+                )?,
+            };
+            columns.push(ColumnFilter {
+                field,
+                pk: is_pk,
+                predicate,
+            });
+            // the other end of a range
+            if let Some((opc, rhs)) = expr.bound() {
+                columns.push(ColumnFilter {
+                    field,
+                    pk: is_pk,
+                    predicate: Self::compile_cmp(
+                        field_info.layers()[0].tag().tag_class(),
+                        is_pk | indexed,
+                        opc,
+                        rhs,
+                    )?,
+                });
+            }
+        }
+        Ok(Self { columns })
+    }
+    fn compile_cmp(
+        class: TagClass,
+        pk_or_indexed: bool,
+        opc: u8,
+        rhs: &'a Lit<'a>,
+    ) -> QueryResult<Predicate<'a>> {
+        let op = CmpOp::from_opc(opc);
+        let predicate = match class {
+            TagClass::UnsignedInt => rhs.try_uint().map(|u| Predicate::UInt(op, u)),
+            TagClass::SignedInt => rhs
+                .try_sint()
+                .or_else(|| rhs.try_uint().and_then(|u| i64::try_from(u).ok()))
+                .map(|s| Predicate::SInt(op, s)),
+            TagClass::Float => rhs
+                .try_float()
+                .or_else(|| rhs.try_uint().map(|u| u as f64))
+                .or_else(|| rhs.try_sint().map(|s| s as f64))
+                .map(|f| Predicate::Float(op, f)),
+            TagClass::Str if pk_or_indexed => {
+                rhs.try_str().map(|s| Predicate::Bytes(op, s.as_bytes()))
+            }
+            TagClass::Bin if pk_or_indexed => rhs.try_bin().map(|b| Predicate::Bytes(op, b)),
+            _ => return Err(QueryError::QExecDmlWhereHasUnindexedColumn),
+        };
+        predicate.ok_or(QueryError::QExecDmlValidationError)
+    }
+    /// Returns the prefix that the key of every row passing the filter starts with (if any). Every key in a range of
+    /// string or binary keys starts with whatever the two ends of the range have in common
+    pub fn pk_prefix(&self) -> Option<&'a [u8]> {
+        let (mut lower, mut upper) = (None, None);
+        for column in self.columns.iter().filter(|column| column.pk) {
+            match column.predicate {
+                Predicate::Prefix(prefix) | Predicate::Bytes(CmpOp::Eq, prefix) => {
+                    return Some(prefix)
+                }
+                Predicate::Bytes(CmpOp::Gt | CmpOp::Ge, key) => lower = Some(key),
+                Predicate::Bytes(CmpOp::Lt | CmpOp::Le, key) => upper = Some(key),
+                _ => {}
+            }
+        }
+        let (lower, upper) = (lower?, upper?);
+        let common = lower
+            .iter()
+            .zip(upper.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        (common != 0).then(|| &lower[..common])
+    }
+    /// Returns the largest string or binary key that a row passing the filter can have (and whether a row with that
+    /// very key can pass)
+    pub fn pk_upper_bound(&self) -> Option<(&'a [u8], bool)> {
+        self.columns
+            .iter()
+            .filter(|column| column.pk)
+            .find_map(|column| match column.predicate {
+                Predicate::Bytes(CmpOp::Lt, key) => Some((key, false)),
+                Predicate::Bytes(CmpOp::Le | CmpOp::Eq, key) => Some((key, true)),
+                _ => None,
+            })
+    }
+    /// Look up the rows that pass an equality test on an indexed field and return their keys. Returns `None` if no
+    /// such test is in the filter
+    pub fn eval_indexed(&self, indexes: &[SecondaryIndex]) -> Option<Vec<PrimaryIndexKey>> {
+        self.indexed_lookup(indexes)
+            .map(|(index, value)| index.lookup(&value).cloned().collect())
+    }
+    /// Returns the index (and the value to look up in it) for the first equality test on an indexed field
+    pub fn indexed_lookup<'i>(
+        &self,
+        indexes: &'i [SecondaryIndex],
+    ) -> Option<(&'i SecondaryIndex, IndexedValue)> {
+        self.columns.iter().find_map(|column| {
+            if column.pk {
+                return None;
+            }
+            let value = match column.predicate {
+                Predicate::UInt(CmpOp::Eq, rhs) => IndexedValue::UInt(rhs),
+                Predicate::SInt(CmpOp::Eq, rhs) => IndexedValue::SInt(rhs),
+                Predicate::Bytes(CmpOp::Eq, rhs) => IndexedValue::Bytes(rhs.into()),
+                _ => return None,
+            };
+            let index = indexes.iter().find(|index| index.field() == column.field)?;
+            Some((index, value))
+        })
+    }
+    /// Returns true if [`Self::eval_cached`] can run the filter on the cache (every column is cached)
+    pub fn is_cached(&self, cache: &ColumnarCache) -> bool {
+        self.columns.iter().all(|column| {
+            let Some(cached) = cache.column(column.field) else {
+                return false;
+            };
+            matches!(
+                (column.predicate, cached.values()),
+                (Predicate::Null(_) | Predicate::Unknown, _)
+                    | (Predicate::UInt(..), ColumnValues::UInt(_))
+                    | (Predicate::SInt(..), ColumnValues::SInt(_))
+                    | (Predicate::Float(..), ColumnValues::Float(_))
+            )
+        })
+    }
+    /// Describe every test of the filter (like `age > ?`)
+    pub fn describe(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|column| match column.predicate {
+                Predicate::UInt(op, _)
+                | Predicate::SInt(op, _)
+                | Predicate::Float(op, _)
+                | Predicate::Bytes(op, _) => format!("{} {} ?", column.field, op.as_str()),
+                Predicate::Prefix(_) => format!("{} starts with ?", column.field),
+                Predicate::Null(true) => format!("{} is null", column.field),
+                Predicate::Null(false) => format!("{} is not null", column.field),
+                Predicate::Unknown => format!("{} compared with null", column.field),
+            })
+            .collect()
+    }
+    /// Run the filter on the columnar cache and return the keys of the rows that passed. Returns `None` if a column
+    /// isn't in the cache
+    pub fn eval_cached(&self, cache: &ColumnarCache) -> Option<Vec<PrimaryIndexKey>> {
+        let mut mask = vec![1u8; cache.len()];
+        for column in self.columns.iter() {
+            let cached = cache.column(column.field)?;
+            let valid = cached.valid();
+            match (column.predicate, cached.values()) {
+                (Predicate::Null(expect_null), _) => {
+                    cmp_lanes(valid, &mut mask, |v| (v == 0) == expect_null)
+                }
+                (Predicate::Unknown, _) => mask.fill(0),
+                (Predicate::UInt(op, rhs), ColumnValues::UInt(col)) => {
+                    cmp_lanes(valid, &mut mask, |v| v != 0);
+                    cmp_column(op, col, rhs, &mut mask);
+                }
+                (Predicate::SInt(op, rhs), ColumnValues::SInt(col)) => {
+                    cmp_lanes(valid, &mut mask, |v| v != 0);
+                    cmp_column(op, col, rhs, &mut mask);
+                }
+                (Predicate::Float(op, rhs), ColumnValues::Float(col)) => {
+                    cmp_lanes(valid, &mut mask, |v| v != 0);
+                    cmp_column(op, col, rhs, &mut mask);
+                }
+                _ => return None,
+            }
+        }
+        Some(
+            cache
+                .keys()
+                .iter()
+                .zip(mask)
+                .filter(|(_, m)| *m != 0)
+                .map(|(key, _)| key.clone())
+                .collect(),
+        )
+    }
+}
+
+pub type ScanRow<'g> = (&'g PrimaryIndexKey, RwLockReadGuard<'g, RowData>);
+
+/// A batch of rows read by a scan along with the scratch space used to filter them
+pub struct ScanBatch<'g> {
+    rows: Vec<ScanRow<'g>>,
+    mask: Vec<u8>,
+    col_uint: Vec<u64>,
+    col_sint: Vec<i64>,
+    col_float: Vec<f64>,
+}
+
+impl<'g> ScanBatch<'g> {
+    pub fn new() -> Self {
+        Self {
+            rows: Vec::with_capacity(SCAN_BATCH_SIZE),
+            mask: Vec::with_capacity(SCAN_BATCH_SIZE),
+            col_uint: Vec::new(),
+            col_sint: Vec::new(),
+            col_float: Vec::new(),
+        }
+    }
+    /// Read the next batch of rows, returning false if there are none left
+    pub fn fill(&mut self, rows: &mut impl Iterator<Item = ScanRow<'g>>) -> bool {
+        self.rows.clear();
+        self.rows.extend(rows.take(SCAN_BATCH_SIZE));
+        !self.rows.is_empty()
+    }
+    /// Apply the filter to the batch and return the rows that passed
+    pub fn apply(
+        &mut self,
+        filter: &ScanFilter,
+    ) -> impl Iterator<Item = (&'g PrimaryIndexKey, &RowData)> + '_ {
+        self.eval(filter);
+        self.rows
+            .iter()
+            .zip(self.mask.iter())
+            .filter(|(_, m)| **m != 0)
+            .map(|((key, row), _)| (*key, &**row))
+    }
+    /// Apply the filter to the batch and move the rows that passed out of the batch
+    pub fn drain(&mut self, filter: &ScanFilter) -> impl Iterator<Item = ScanRow<'g>> + '_ {
+        self.eval(filter);
+        self.rows
+            .drain(..)
+            .zip(self.mask.iter())
+            .filter(|(_, m)| **m != 0)
+            .map(|(row, _)| row)
+    }
+    /// Compute the mask of the rows that pass the filter
+    fn eval(&mut self, filter: &ScanFilter) {
+        self.mask.clear();
+        self.mask.resize(self.rows.len(), 1);
+        for column in filter.columns.iter() {
+            match column.predicate {
+                Predicate::Null(expect_null) => {
+                    for (m, (_, row)) in self.mask.iter_mut().zip(self.rows.iter()) {
+                        // the primary key can never be null
+                        let is_null = (!column.pk) && Self::cell(row, column.field).is_null();
+                        *m &= (is_null == expect_null) as u8;
+                    }
+                }
+                Predicate::Unknown => self.mask.fill(0),
+                Predicate::UInt(op, rhs) => {
+                    Self::gather(
+                        &self.rows,
+                        &mut self.mask,
+                        &mut self.col_uint,
+                        column,
+                        |pk| pk.uint(),
+                        |dc| dc.try_uint(),
+                    );
+                    cmp_column(op, &self.col_uint, rhs, &mut self.mask);
+                }
+                Predicate::SInt(op, rhs) => {
+                    Self::gather(
+                        &self.rows,
+                        &mut self.mask,
+                        &mut self.col_sint,
+                        column,
+                        |pk| pk.sint(),
+                        |dc| dc.try_sint(),
+                    );
+                    cmp_column(op, &self.col_sint, rhs, &mut self.mask);
+                }
+                Predicate::Float(op, rhs) => {
+                    Self::gather(
+                        &self.rows,
+                        &mut self.mask,
+                        &mut self.col_float,
+                        column,
+                        |_| None,
+                        |dc| dc.try_float(),
+                    );
+                    cmp_column(op, &self.col_float, rhs, &mut self.mask);
+                }
+                Predicate::Bytes(op, rhs) => {
+                    // these can't be copied into a column, so we just compare them in place
+                    for (m, (key, row)) in self.mask.iter_mut().zip(self.rows.iter()) {
+                        let lhs = if column.pk {
+                            Self::pk_bytes(key)
+                        } else {
+                            Self::dc_bytes(Self::cell(row, column.field))
+                        };
+                        *m &= lhs.is_some_and(|lhs| cmp_one(op, lhs, rhs)) as u8;
+                    }
+                }
+                Predicate::Prefix(prefix) => {
+                    for (m, (key, _)) in self.mask.iter_mut().zip(self.rows.iter()) {
+                        *m &= Self::pk_bytes(key).is_some_and(|key| key.starts_with(prefix)) as u8;
+                    }
+                }
+            }
+        }
+    }
+    fn cell<'r>(row: &'r RowData, field: &str) -> &'r Datacell {
+        row.fields().st_get(field).unwrap()
+    }
+    fn pk_bytes(key: &PrimaryIndexKey) -> Option<&[u8]> {
+        key.str().map(str::as_bytes).or_else(|| key.bin())
+    }
+    fn dc_bytes(dc: &Datacell) -> Option<&[u8]> {
+        dc.try_str().map(str::as_bytes).or_else(|| dc.try_bin())
+    }
+    /// Copy the values of a column into the scratch buffer. Nulls are knocked out of the mask right away
+    fn gather<T: Copy + Default>(
+        rows: &[ScanRow<'g>],
+        mask: &mut [u8],
+        col: &mut Vec<T>,
+        column: &ColumnFilter,
+        from_pk: impl Fn(&PrimaryIndexKey) -> Option<T>,
+        from_dc: impl Fn(&Datacell) -> Option<T>,
+    ) {
+        col.clear();
+        col.extend(rows.iter().zip(mask.iter_mut()).map(|((key, row), m)| {
+            let value = if column.pk {
+                from_pk(key)
+            } else {
+                from_dc(Self::cell(row, column.field))
+            };
+            *m &= value.is_some() as u8;
+            value.unwrap_or_default()
+        }));
+    }
+}
+
+#[inline(always)]
+fn cmp_column<T: Copy + PartialOrd>(op: CmpOp, col: &[T], rhs: T, mask: &mut [u8]) {
+    // pick the comparison once so that every loop below is specialized for its operator
+    match op {
+        CmpOp::Eq => cmp_lanes(col, mask, |v| v == rhs),
+        CmpOp::Ne => cmp_lanes(col, mask, |v| v != rhs),
+        CmpOp::Gt => cmp_lanes(col, mask, |v| v > rhs),
+        CmpOp::Ge => cmp_lanes(col, mask, |v| v >= rhs),
+        CmpOp::Lt => cmp_lanes(col, mask, |v| v < rhs),
+        CmpOp::Le => cmp_lanes(col, mask, |v| v <= rhs),
+    }
+}
+
+#[inline(always)]
+fn cmp_one<T: PartialOrd + ?Sized>(op: CmpOp, lhs: &T, rhs: &T) -> bool {
+    match op {
+        CmpOp::Eq => lhs == rhs,
+        CmpOp::Ne => lhs != rhs,
+        CmpOp::Gt => lhs > rhs,
+        CmpOp::Ge => lhs >= rhs,
+        CmpOp::Lt => lhs < rhs,
+        CmpOp::Le => lhs <= rhs,
+    }
+}
+
+#[inline(always)]
+fn cmp_lanes<T: Copy>(col: &[T], mask: &mut [u8], f: impl Fn(T) -> bool) {
+    let mut col_chunks = col.chunks_exact(LANES);
+    let mut mask_chunks = mask.chunks_exact_mut(LANES);
+    for (values, m) in (&mut col_chunks).zip(&mut mask_chunks) {
+        for lane in 0..LANES {
+            m[lane] &= f(values[lane]) as u8;
+        }
+    }
+    for (value, m) in col_chunks
+        .remainder()
+        .iter()
+        .zip(mask_chunks.into_remainder())
+    {
+        *m &= f(*value) as u8;
+    }
+}
+
+#[test]
+fn cmp_lanes_remainder() {
+    let col: Vec<u64> = (0..40).collect();
+    let mut mask = vec![1u8; col.len()];
+    cmp_column(CmpOp::Ge, &col, 10, &mut mask);
+    cmp_column(CmpOp::Lt, &col, 35, &mut mask);
+    assert_eq!(
+        mask.iter()
+            .enumerate()
+            .filter(|(_, m)| **m != 0)
+            .map(|(i, _)| i as u64)
+            .collect::<Vec<_>>(),
+        (10..35).collect::<Vec<_>>()
+    );
+}
@@ -24,122 +24,700 @@
  *
 */
 
-use crate::engine::{
-    core::{
-        index::{
-            DcFieldIndex, IndexLatchHandleExclusive, PrimaryIndexKey, Row, RowData, RowDataLck,
+use {
+    crate::engine::{
+        core::{
+            cancel::{self, CANCEL_CHECK_INTERVAL},
+            dml::{
+                agg, expr,
+                scan::{ScanBatch, ScanFilter, ScanRow},
+            },
+            index::{
+                DcFieldIndex, IndexLatchHandleExclusive, PrimaryIndexKey, PrimaryIndexKind, Row,
+                RowData, RowIter,
+            },
+            model::{Expiry, ModelData},
+            query_mem::{self, QueryMemory},
+            scratch, trace, EntityIDRef,
         },
-        model::ModelData,
-    },
-    data::{
-        cell::{Datacell, VirtualDatacell},
-        tag::{DataTag, TagClass},
+        data::{
+            cell::{Datacell, VirtualDatacell},
+            tag::{DataTag, TagClass, TagSelector},
+        },
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::{STIndex, STIndexSeq},
+        mem::IntegerRepr,
+        net::protocol::{Response, ResponseType},
+        ql::dml::sel::{MultiGet, OrderBy, SelectAllStatement, SelectStatement},
+        sync,
     },
-    error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
-    idx::{IndexMTRaw, MTIndexExt, STIndex, STIndexSeq},
-    mem::IntegerRepr,
-    net::protocol::{Response, ResponseType},
-    ql::dml::sel::{SelectAllStatement, SelectStatement},
-    sync,
+    core::cmp,
 };
 
 pub fn select_resp(
+    global: &impl GlobalInstanceLike,
+    mut select: SelectStatement,
+    fmt: ResultFormat,
+) -> QueryResult<Response> {
+    let meta = if fmt.meta {
+        let columns: Vec<_> = select
+            .fields()
+            .iter()
+            .zip(select.aliases())
+            .map(|(expr, alias)| {
+                let field = expr.as_field().map(|field| field.as_str());
+                (
+                    alias.map_or(field.unwrap_or_default(), |alias| alias.as_str()),
+                    field,
+                )
+            })
+            .collect();
+        Some(result_meta(
+            global,
+            select.entity(),
+            select.is_wildcard(),
+            &columns,
+        )?)
+    } else {
+        None
+    };
+    let resp = if select.is_aggregate() {
+        select_aggregate_resp(global, select)?
+    } else if let Some(multi) = select.take_multi_get() {
+        select_multi_resp(global, select, multi)?
+    } else {
+        let mut data = scratch::response_buffer();
+        let mut i = 0usize;
+        self::select_custom(global, select, |item| {
+            encode_cell(&mut data, item);
+            i += 1;
+        })?;
+        Response::Serialized {
+            ty: ResponseType::Row,
+            size: i,
+            data,
+        }
+    };
+    Ok(with_meta(meta, resp))
+}
+
+/// Returns a scalar for a single aggregate and a row for several aggregates, or a row for every group if the select
+/// is grouped
+fn select_aggregate_resp(
     global: &impl GlobalInstanceLike,
     select: SelectStatement,
 ) -> QueryResult<Response> {
-    let mut data = vec![];
-    let mut i = 0usize;
-    self::select_custom(global, select, |item| {
-        encode_cell(&mut data, item);
-        i += 1;
+    let grouped = !select.group_by().is_empty();
+    let col_c = select.fields().len();
+    let mut data = scratch::response_buffer();
+    let mem = QueryMemory::new();
+    let rows = agg::aggregate(global, select, &mem, |row| {
+        if grouped {
+            IntegerRepr::scoped(row.len() as u64, |repr| data.extend(repr));
+            data.push(b'\n');
+        }
+        for cell in row {
+            encode_cell(&mut data, cell);
+        }
+        mem.charge_upto(data.capacity())
     })?;
-    Ok(Response::Serialized {
-        ty: ResponseType::Row,
-        size: i,
-        data,
-    })
+    if grouped {
+        Ok(Response::SerializedCharged {
+            ty: ResponseType::MultiRow,
+            size: rows,
+            data,
+            mem,
+        })
+    } else if col_c == 1 {
+        Ok(Response::Cell(data))
+    } else {
+        Ok(Response::Serialized {
+            ty: ResponseType::Row,
+            size: col_c,
+            data,
+        })
+    }
 }
 
-pub fn select_all_resp(
+/// Returns a list with one element for each key (in the same order as the keys). The element is null if the key
+/// wasn't found and a list with the fields of the row otherwise
+fn select_multi_resp(
     global: &impl GlobalInstanceLike,
-    select: SelectAllStatement,
+    select: SelectStatement,
+    multi: MultiGet,
 ) -> QueryResult<Response> {
-    let mut ret_buf = Vec::new();
-    let i = self::select_all(
+    let mut ret_buf = scratch::response_buffer();
+    let mem = QueryMemory::new();
+    let i = self::select_multi(
         global,
         select,
+        multi,
+        &mem,
         &mut ret_buf,
-        |buf, _, col_c| {
-            IntegerRepr::scoped(col_c as u64, |repr| buf.extend(repr));
-            buf.push(b'\n');
+        |buf, col_c| match col_c {
+            Some(col_c) => {
+                buf.push(ResponseType::List.value_u8());
+                IntegerRepr::scoped(col_c as u64, |repr| buf.extend(repr));
+                buf.push(b'\n');
+            }
+            None => buf.push(ResponseType::Null.value_u8()),
+        },
+        |buf, data| {
+            encode_cell(buf, data);
+            mem.charge_upto(buf.capacity())
         },
-        |buf, data, _| encode_cell(buf, data),
     )?;
-    Ok(Response::Serialized {
-        ty: ResponseType::MultiRow,
+    Ok(Response::SerializedCharged {
+        ty: ResponseType::List,
         size: i,
         data: ret_buf,
+        mem,
     })
 }
 
+pub fn select_all_resp(
+    global: &impl GlobalInstanceLike,
+    select: SelectAllStatement,
+    fmt: ResultFormat,
+) -> QueryResult<Response> {
+    let meta = if fmt.meta {
+        let columns: Vec<_> = select
+            .fields
+            .iter()
+            .map(|field| (field.as_str(), Some(field.as_str())))
+            .collect();
+        Some(result_meta(
+            global,
+            select.entity,
+            select.wildcard,
+            &columns,
+        )?)
+    } else {
+        None
+    };
+    let mem = QueryMemory::new();
+    let resp = if fmt.packed {
+        let mut columns = PackedColumns::new();
+        let i = self::select_all(
+            global,
+            select,
+            &mem,
+            &mut columns,
+            |columns, _, col_c| columns.init(col_c),
+            |columns, data, _| {
+                columns.push(data);
+                mem.charge_upto(columns.capacity())
+            },
+        )?;
+        Response::SerializedCharged {
+            ty: ResponseType::PackedRows,
+            size: i,
+            data: columns.finish(),
+            mem,
+        }
+    } else {
+        let mut ret_buf = scratch::response_buffer();
+        let i = self::select_all(
+            global,
+            select,
+            &mem,
+            &mut ret_buf,
+            |buf, _, col_c| {
+                IntegerRepr::scoped(col_c as u64, |repr| buf.extend(repr));
+                buf.push(b'\n');
+            },
+            |buf, data, _| {
+                encode_cell(buf, data);
+                mem.charge_upto(buf.capacity())
+            },
+        )?;
+        Response::SerializedCharged {
+            ty: ResponseType::MultiRow,
+            size: i,
+            data: ret_buf,
+            mem,
+        }
+    };
+    Ok(with_meta(meta, resp))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// How a session wants its select results to be encoded
+pub struct ResultFormat {
+    /// precede the results with a metadata frame (`set result_metadata = true`)
+    pub meta: bool,
+    /// send the numeric columns of `select all` results as packed arrays (`set packed_columns = true`)
+    pub packed: bool,
+}
+
+/*
+    packed columns
+    ---
+    if a session turns on packed columns (`set packed_columns = true`), `select all` returns its rows column by
+    column: `[0x18][row count]\n[column count]\n` followed by an encoding byte and the values of every column. a column
+    whose values are all (non-null) `uint64`s or `float64`s is sent as an array of 8-byte little endian values
+    (encoding 1 and 2 respectively) while any other column is sent as regular cells (encoding 0)
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PackedKind {
+    Empty,
+    Cells,
+    UInt,
+    Float,
+}
+
+struct PackedColumn {
+    kind: PackedKind,
+    data: Vec<u8>,
+}
+
+impl PackedColumn {
+    fn push(&mut self, item: &Datacell) {
+        let kind = match item.tag().tag_selector() {
+            _ if item.is_null() => PackedKind::Cells,
+            TagSelector::UInt64 => PackedKind::UInt,
+            TagSelector::Float64 => PackedKind::Float,
+            _ => PackedKind::Cells,
+        };
+        if self.kind == PackedKind::Empty {
+            self.kind = kind;
+        } else if (self.kind != kind) & (self.kind != PackedKind::Cells) {
+            self.unpack();
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            match self.kind {
+                PackedKind::UInt => self.data.extend(item.read_uint().to_le_bytes()),
+                PackedKind::Float => self.data.extend(item.read_float().to_le_bytes()),
+                PackedKind::Cells => encode_cell(&mut self.data, item),
+                PackedKind::Empty => unreachable!(),
+            }
+        }
+    }
+    /// The column isn't homogeneous after all, so switch to regular cells
+    fn unpack(&mut self) {
+        let packed = core::mem::take(&mut self.data);
+        for value in packed.chunks_exact(sizeof!(u64)) {
+            let value = u64::from_le_bytes(value.try_into().unwrap());
+            match self.kind {
+                PackedKind::UInt => {
+                    self.data.push(TagSelector::UInt64.value_u8() + 1);
+                    IntegerRepr::scoped(value, |repr| self.data.extend(repr));
+                }
+                PackedKind::Float => {
+                    self.data.push(TagSelector::Float64.value_u8() + 1);
+                    self.data
+                        .extend(f64::from_bits(value).to_string().as_bytes());
+                }
+                PackedKind::Empty | PackedKind::Cells => unreachable!(),
+            }
+            self.data.push(b'\n');
+        }
+        self.kind = PackedKind::Cells;
+    }
+}
+
+/// Buffers the cells of a result column by column
+struct PackedColumns {
+    columns: Vec<PackedColumn>,
+    next: usize,
+}
+
+impl PackedColumns {
+    fn new() -> Self {
+        Self {
+            columns: vec![],
+            next: 0,
+        }
+    }
+    fn init(&mut self, col_c: usize) {
+        self.columns = (0..col_c)
+            .map(|_| PackedColumn {
+                kind: PackedKind::Empty,
+                data: vec![],
+            })
+            .collect();
+    }
+    fn push(&mut self, item: &Datacell) {
+        self.columns[self.next].push(item);
+        self.next = (self.next + 1) % self.columns.len();
+    }
+    fn capacity(&self) -> usize {
+        self.columns.iter().map(|col| col.data.capacity()).sum()
+    }
+    fn finish(self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(self.capacity() + self.columns.len() + 4);
+        IntegerRepr::scoped(self.columns.len() as u64, |repr| ret.extend(repr));
+        ret.push(b'\n');
+        for column in self.columns {
+            ret.push(match column.kind {
+                PackedKind::Empty | PackedKind::Cells => 0,
+                PackedKind::UInt => 1,
+                PackedKind::Float => 2,
+            });
+            ret.extend(column.data);
+        }
+        ret
+    }
+}
+
+/*
+    result metadata
+    ---
+    if a session turns on result metadata (`set result_metadata = true`), select responses are preceded by a frame
+    that describes the columns: `[0x17][column count]\n` followed by `[name length]\n[name][type][nullable]` for
+    every column. the name is the column's alias if it has one (`<expr> as <alias>`) and otherwise its field. the type
+    is the code that the column's cells use (see `encode_cell`), except for computed columns which use
+    `META_TYPE_COMPUTED` since their type is only known once they're evaluated (and have no name unless they're aliased)
+*/
+
+const META_TYPE_COMPUTED: u8 = 0xFF;
+
+/// Encode the metadata frame for the given columns, each as `(name, field)` (the field is `None` for a computed
+/// column)
+fn result_meta(
+    global: &impl GlobalInstanceLike,
+    entity: EntityIDRef,
+    wildcard: bool,
+    columns: &[(&str, Option<&str>)],
+) -> QueryResult<Vec<u8>> {
+    global.state().namespace().with_model(entity, |mdl| {
+        let mut meta = vec![ResponseType::Meta.value_u8()];
+        let col_c = if wildcard {
+            mdl.fields().len()
+        } else {
+            columns.len()
+        };
+        IntegerRepr::scoped(col_c as u64, |repr| meta.extend(repr));
+        meta.push(b'\n');
+        let mut column = |name: &str, field: Option<&str>| -> QueryResult<()> {
+            let (ty, nullable) = match field {
+                Some(field) => {
+                    let field = mdl
+                        .fields()
+                        .st_get(field)
+                        .ok_or(QueryError::QExecUnknownField)?;
+                    (
+                        field.layers()[0].tag().tag_selector().value_u8() + 1,
+                        field.is_nullable(),
+                    )
+                }
+                None => (META_TYPE_COMPUTED, true),
+            };
+            IntegerRepr::scoped(name.len() as u64, |repr| meta.extend(repr));
+            meta.push(b'\n');
+            meta.extend(name.as_bytes());
+            meta.push(ty);
+            meta.push(nullable as u8);
+            Ok(())
+        };
+        if wildcard {
+            for key in mdl.fields().stseq_ord_key() {
+                column(key.as_ref(), Some(key.as_ref()))?;
+            }
+        } else {
+            for (name, field) in columns {
+                column(name, *field)?;
+            }
+        }
+        Ok(meta)
+    })
+}
+
+fn with_meta(meta: Option<Vec<u8>>, resp: Response) -> Response {
+    match meta {
+        Some(meta) => Response::WithMeta {
+            meta,
+            resp: Box::new(resp),
+        },
+        None => resp,
+    }
+}
+
 pub fn select_all<Fm, F, T>(
     global: &impl GlobalInstanceLike,
     select: SelectAllStatement,
+    mem: &QueryMemory,
     serialize_target: &mut T,
     mut f_mdl: Fm,
     mut f: F,
 ) -> QueryResult<usize>
 where
     Fm: FnMut(&mut T, &ModelData, usize),
-    F: FnMut(&mut T, &Datacell, usize),
+    F: FnMut(&mut T, &Datacell, usize) -> QueryResult<()>,
 {
     global.state().namespace().with_model(select.entity, |mdl| {
         let g = sync::atm::cpin();
-        let mut i = 0;
-        if select.wildcard {
-            f_mdl(serialize_target, mdl, mdl.fields().len());
-            for (key, data) in RowIteratorAll::new(&g, mdl, select.limit as usize) {
-                let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+        // schema check
+        if !select.wildcard
+            && (select.fields.len() > mdl.fields().len()
+                || select
+                    .fields
+                    .iter()
+                    .any(|f| !mdl.fields().st_contains(f.as_str())))
+        {
+            return Err(QueryError::QExecUnknownField);
+        }
+        if let Some(order_by) = select.order_by {
+            check_order_by(mdl, order_by)?;
+        }
+        let filter = match select.clause {
+            Some(ref clause) => Some(trace::span("plan", || ScanFilter::compile(mdl, clause))?),
+            None => None,
+        };
+        let col_c = if select.wildcard {
+            mdl.fields().len()
+        } else {
+            select.fields.len()
+        };
+        f_mdl(serialize_target, mdl, col_c);
+        let mut emit = |key: &PrimaryIndexKey, data: &RowData| -> QueryResult<()> {
+            let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+            if select.wildcard {
                 for key in mdl.fields().stseq_ord_key() {
                     let r = if key.as_str() == mdl.p_key() {
                         &*vdc
                     } else {
                         data.fields().get(key).unwrap()
                     };
-                    f(serialize_target, r, mdl.fields().len());
+                    f(serialize_target, r, col_c)?;
                 }
-                i += 1;
-            }
-        } else {
-            // schema check
-            if select.fields.len() > mdl.fields().len()
-                || select
-                    .fields
-                    .iter()
-                    .any(|f| !mdl.fields().st_contains(f.as_str()))
-            {
-                return Err(QueryError::QExecUnknownField);
-            }
-            f_mdl(serialize_target, mdl, select.fields.len());
-            for (key, data) in RowIteratorAll::new(&g, mdl, select.limit as usize) {
-                let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+            } else {
                 for key in select.fields.iter() {
                     let r = if key.as_str() == mdl.p_key() {
                         &*vdc
                     } else {
                         data.fields().st_get(key.as_str()).unwrap()
                     };
-                    f(serialize_target, r, select.fields.len());
+                    f(serialize_target, r, col_c)?;
+                }
+            }
+            Ok(())
+        };
+        let limit = select.limit as usize;
+        // the rows that we skip (for the offset) are scanned but not emitted
+        let mut to_skip = select.offset as usize;
+        let mut i = 0;
+        // a runaway scan can be aborted with `sysctl cancel query`
+        let cancel = cancel::current();
+        let check_cancelled = || cancel.as_ref().map_or(Ok(()), |flag| flag.check());
+        if let Some(order_by) = select.order_by {
+            // we can't tell which rows come first without reading all of them
+            let mut rows = Vec::new();
+            let mut charge = mem.working_buffer();
+            match filter {
+                None => {
+                    for (scanned, row) in RowIteratorAll::new(&g, mdl, usize::MAX).enumerate() {
+                        if scanned.is_multiple_of(CANCEL_CHECK_INTERVAL) {
+                            check_cancelled()?;
+                        }
+                        rows.push(row);
+                        charge.charge_upto(query_mem::vec_size(&rows))?;
+                    }
+                }
+                Some(filter) => {
+                    let (_latch, mut source) = scan_rows(&g, mdl, &filter);
+                    let mut batch = ScanBatch::new();
+                    while batch.fill(&mut source) {
+                        check_cancelled()?;
+                        rows.extend(batch.drain(&filter));
+                        charge.charge_upto(query_mem::vec_size(&rows))?;
+                    }
                 }
+            }
+            check_cancelled()?;
+            trace::span("sort", || {
+                rows.sort_by(|(ka, a), (kb, b)| cmp_rows(mdl, order_by, (*ka, &**a), (*kb, &**b)))
+            });
+            for (key, data) in rows.iter().skip(to_skip).take(limit) {
+                emit(key, data)?;
                 i += 1;
             }
+            return Ok(i);
+        }
+        match filter {
+            None => {
+                for (scanned, (key, data)) in
+                    RowIteratorAll::new(&g, mdl, limit.saturating_add(to_skip)).enumerate()
+                {
+                    if scanned.is_multiple_of(CANCEL_CHECK_INTERVAL) {
+                        check_cancelled()?;
+                    }
+                    if to_skip != 0 {
+                        to_skip -= 1;
+                        continue;
+                    }
+                    emit(key, &data)?;
+                    i += 1;
+                }
+            }
+            Some(filter) => {
+                // the limit applies to the rows that pass the filter, so we may have to go over all the rows
+                let (_latch, mut rows) = scan_rows(&g, mdl, &filter);
+                let mut batch = ScanBatch::new();
+                while (i < limit) && batch.fill(&mut rows) {
+                    // filtering can go over a lot of rows without emitting any, so we check once every batch
+                    check_cancelled()?;
+                    let mut matched = batch.apply(&filter);
+                    while to_skip != 0 && matched.next().is_some() {
+                        to_skip -= 1;
+                    }
+                    for (key, data) in matched.take(limit - i) {
+                        emit(key, data)?;
+                        i += 1;
+                    }
+                }
+            }
         }
         Ok(i)
     })
 }
 
-fn encode_cell(resp: &mut Vec<u8>, item: &Datacell) {
+/// The rows that a filtered scan has to go over. If a secondary index or the columnar cache can evaluate the filter,
+/// these are just the rows that matched (and the returned latch must be held for as long as they're read). If the
+/// filter matches the key by prefix (or by range) and the index keeps its keys in order, these are just the rows under
+/// that prefix (up to the end of the range)
+pub(super) fn scan_rows<'g>(
+    g: &'g sync::atm::Guard,
+    mdl: &'g ModelData,
+    filter: &ScanFilter<'g>,
+) -> (
+    Option<IndexLatchHandleExclusive<'g>>,
+    Box<dyn Iterator<Item = ScanRow<'g>> + 'g>,
+) {
+    let matched = filter
+        .eval_indexed(&mdl.secondary_indexes().read())
+        .or_else(|| {
+            mdl.columnar_cache()
+                .read()
+                .as_ref()
+                .and_then(|cache| filter.eval_cached(cache))
+        });
+    match matched {
+        Some(keys) => {
+            let idx = mdl.primary_index();
+            let latch = idx.acquire_exclusive();
+            let expiry = mdl.expiry();
+            let rows = keys
+                .into_iter()
+                .filter_map(move |key| idx.__raw_index().mt_get_element(&key, g))
+                .map(move |row| {
+                    (
+                        row.d_key(),
+                        row.resolve_schema_deltas_and_freeze(mdl.delta_state()),
+                    )
+                })
+                .filter(move |(_, data)| !expiry.is_expired(data));
+            (Some(latch), Box::new(rows))
+        }
+        None => {
+            let rows = filter
+                .pk_prefix()
+                .and_then(|prefix| RowIteratorAll::new_prefix(g, mdl, prefix))
+                .unwrap_or_else(|| RowIteratorAll::new(g, mdl, usize::MAX));
+            match filter.pk_upper_bound() {
+                // the keys come in order, so none of the keys after the end of the range can pass
+                Some((upper, inclusive))
+                    if mdl.primary_index().kind() == PrimaryIndexKind::Radix =>
+                {
+                    let rows = rows.take_while(move |(key, _)| {
+                        let key = key.str().map(str::as_bytes).or_else(|| key.bin());
+                        key.is_some_and(|key| (key < upper) | (inclusive & (key == upper)))
+                    });
+                    (None, Box::new(rows))
+                }
+                _ => (None, Box::new(rows)),
+            }
+        }
+    }
+}
+
+/*
+    ordering
+    ---
+    rows are ordered by the value of a single (non-list, non-map) field. nulls (and the keys of a multi-get that weren't found)
+    go after every other value in ascending order and before them in descending order. only the radix index keeps its
+    keys in order (and rows are usually ordered by some other field anyway), so the rows are always sorted after
+    they're read
+*/
+
+/// Check that the rows of the model can be ordered by the field
+fn check_order_by(mdl: &ModelData, order_by: OrderBy) -> QueryResult<()> {
+    match mdl.fields().st_get(order_by.field().as_str()) {
+        Some(field) if field.layers()[0].tag().tag_class() < TagClass::List => Ok(()),
+        Some(_) => Err(QueryError::QExecDmlValidationError),
+        None => Err(QueryError::QExecUnknownField),
+    }
+}
+
+/// Compare two cells of the same field (`None` is a missing row)
+fn cmp_cells(order_by: OrderBy, a: Option<&Datacell>, b: Option<&Datacell>) -> cmp::Ordering {
+    let a = a.filter(|dc| !dc.is_null());
+    let b = b.filter(|dc| !dc.is_null());
+    let ord = match (a, b) {
+        (None, None) => cmp::Ordering::Equal,
+        (None, Some(_)) => cmp::Ordering::Greater,
+        (Some(_), None) => cmp::Ordering::Less,
+        (Some(a), Some(b)) => cmp_values(a, b),
+    };
+    if order_by.is_desc() {
+        ord.reverse()
+    } else {
+        ord
+    }
+}
+
+/// Compare two (non-null, non-list, non-map) cells of the same field
+pub(super) fn cmp_values(a: &Datacell, b: &Datacell) -> cmp::Ordering {
+    unsafe {
+        // UNSAFE(@ohsayan): both cells belong to the same field so they have the same class (+tagck)
+        match a.tag().tag_class() {
+            TagClass::Bool => a.read_bool().cmp(&b.read_bool()),
+            TagClass::UnsignedInt => a.read_uint().cmp(&b.read_uint()),
+            TagClass::SignedInt => a.read_sint().cmp(&b.read_sint()),
+            TagClass::Float => a.read_float().total_cmp(&b.read_float()),
+            TagClass::Bin | TagClass::Str => a.read_bin().cmp(b.read_bin()),
+            TagClass::List | TagClass::Map => unreachable!(),
+        }
+    }
+}
+
+/// Compare two rows by the field that they're ordered by
+fn cmp_rows(
+    mdl: &ModelData,
+    order_by: OrderBy,
+    (ka, a): (&PrimaryIndexKey, &RowData),
+    (kb, b): (&PrimaryIndexKey, &RowData),
+) -> cmp::Ordering {
+    let field = order_by.field().as_str();
+    if field == mdl.p_key() {
+        cmp_cells(
+            order_by,
+            Some(&*VirtualDatacell::new_pk(ka, mdl.p_tag())),
+            Some(&*VirtualDatacell::new_pk(kb, mdl.p_tag())),
+        )
+    } else {
+        cmp_cells(order_by, a.fields().st_get(field), b.fields().st_get(field))
+    }
+}
+
+/// Read the cell that the row is ordered by
+fn sort_cell(mdl: &ModelData, order_by: OrderBy, row: &Row) -> Datacell {
+    let field = order_by.field().as_str();
+    if field == mdl.p_key() {
+        (*VirtualDatacell::new_pk(row.d_key(), mdl.p_tag())).clone()
+    } else {
+        row.resolve_schema_deltas_and_freeze(mdl.delta_state())
+            .fields()
+            .st_get(field)
+            .unwrap()
+            .clone()
+    }
+}
+
+pub(in crate::engine::core) fn encode_cell(resp: &mut Vec<u8>, item: &Datacell) {
     resp.push((item.tag().tag_selector().value_u8() + 1) * (item.is_init() as u8));
     if item.is_null() {
         return;
@@ -168,11 +746,65 @@ fn encode_cell(resp: &mut Vec<u8>, item: &Datacell) {
                 }
                 return;
             }
+            TagClass::Map => {
+                // [entry count]\n then [key len]\n[key][value] for every entry
+                let map = item.read_map().read();
+                IntegerRepr::scoped(map.len() as u64, |b| resp.extend(b));
+                resp.push(b'\n');
+                for (key, value) in map.iter() {
+                    IntegerRepr::scoped(key.len() as u64, |b| resp.extend(b));
+                    resp.push(b'\n');
+                    resp.extend(key.as_bytes());
+                    encode_cell(resp, value);
+                }
+                return;
+            }
         }
     }
     resp.push(b'\n');
 }
 
+pub(super) fn read_field<'a>(
+    mdl: &ModelData,
+    pkdc: &'a Datacell,
+    key: &str,
+    fields: &'a DcFieldIndex,
+) -> QueryResult<&'a Datacell> {
+    match fields.st_get(key) {
+        Some(dc) => Ok(dc),
+        None if key == mdl.p_key() => Ok(pkdc),
+        None => Err(QueryError::QExecUnknownField),
+    }
+}
+
+/// Read the selected fields (or expressions) from the row
+fn read_row<F>(
+    mdl: &ModelData,
+    select: &SelectStatement,
+    pkdc: &Datacell,
+    fields: &DcFieldIndex,
+    cellfn: &mut F,
+) -> QueryResult<()>
+where
+    F: FnMut(&Datacell) -> QueryResult<()>,
+{
+    if select.is_wildcard() {
+        for key in mdl.fields().stseq_ord_key() {
+            cellfn(read_field(mdl, pkdc, key.as_ref(), fields)?)?;
+        }
+    } else {
+        for expr in select.fields() {
+            match expr.as_field() {
+                Some(key) => cellfn(read_field(mdl, pkdc, key.as_str(), fields)?)?,
+                None => cellfn(&expr::eval(expr, &mut |key| {
+                    read_field(mdl, pkdc, key, fields).cloned()
+                })?)?,
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn select_custom<F>(
     global: &impl GlobalInstanceLike,
     mut select: SelectStatement,
@@ -181,6 +813,13 @@ pub fn select_custom<F>(
 where
     F: FnMut(&Datacell),
 {
+    if select.is_aggregate() {
+        return agg::aggregate(global, select, &QueryMemory::new(), |row| {
+            row.iter().for_each(&mut cellfn);
+            Ok(())
+        })
+        .map(|_| ());
+    }
     global
         .state()
         .namespace()
@@ -188,26 +827,18 @@ where
             let target_key = mdl.resolve_where(select.clauses_mut())?;
             let pkdc = VirtualDatacell::new(target_key.clone(), mdl.p_tag().tag_unique());
             let g = sync::atm::cpin();
-            let mut read_field = |key, fields: &DcFieldIndex| {
-                match fields.st_get(key) {
-                    Some(dc) => cellfn(dc),
-                    None if key == mdl.p_key() => cellfn(&pkdc),
-                    None => return Err(QueryError::QExecUnknownField),
-                }
-                Ok(())
-            };
             match mdl.primary_index().select(target_key.clone(), &g) {
                 Some(row) => {
                     let r = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
-                    if select.is_wildcard() {
-                        for key in mdl.fields().stseq_ord_key() {
-                            read_field(key.as_ref(), r.fields())?;
-                        }
-                    } else {
-                        for key in select.into_fields() {
-                            read_field(key.as_str(), r.fields())?;
-                        }
+                    if mdl.expiry().is_expired(&r)
+                        || !mdl.where_residual_matches(select.clauses_mut(), r.fields())?
+                    {
+                        return Err(QueryError::QExecDmlRowNotFound);
                     }
+                    read_row(mdl, &select, &pkdc, r.fields(), &mut |dc| {
+                        cellfn(dc);
+                        Ok(())
+                    })?;
                 }
                 None => return Err(QueryError::QExecDmlRowNotFound),
             }
@@ -215,16 +846,105 @@ where
         })
 }
 
-struct RowIteratorAll<'g> {
+/// Look up all the keys (within the bounds of the statement) in one go and then read the rows in the order of the keys
+/// (or the order that the statement asks for). `f_row` is called before the fields of each row are read, with the
+/// number of fields in the row or `None` if the key wasn't found. Returns the number of keys
+pub fn select_multi<T, Fr, F>(
+    global: &impl GlobalInstanceLike,
+    select: SelectStatement,
+    multi: MultiGet,
+    mem: &QueryMemory,
+    serialize_target: &mut T,
+    mut f_row: Fr,
+    mut f: F,
+) -> QueryResult<usize>
+where
+    Fr: FnMut(&mut T, Option<usize>),
+    F: FnMut(&mut T, &Datacell) -> QueryResult<()>,
+{
+    global
+        .state()
+        .namespace()
+        .with_model(select.entity(), |mdl| {
+            if multi.field().as_str() != mdl.p_key() {
+                return Err(QueryError::QExecDmlWhereHasUnindexedColumn);
+            }
+            let keys = multi.into_keys();
+            if keys
+                .iter()
+                .any(|key| key.kind().tag_unique() != mdl.p_tag().tag_unique())
+            {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            let col_c = if select.is_wildcard() {
+                mdl.fields().len()
+            } else {
+                select.fields().len()
+            };
+            let g = sync::atm::cpin();
+            let bounds = select.bounds();
+            // an expired row is as good as a missing one
+            let expiry = mdl.expiry();
+            let lookup = |key| {
+                mdl.primary_index()
+                    .select(key, &g)
+                    .filter(|row| !expiry.is_expired(&row.d_data().read()))
+            };
+            let rows: Vec<Option<&Row>> = match select.order_by() {
+                Some(order_by) => {
+                    check_order_by(mdl, order_by)?;
+                    // the bounds apply to the ordered rows, so all the keys have to be looked up
+                    let mut rows: Vec<(Option<Datacell>, Option<&Row>)> = keys
+                        .into_iter()
+                        .map(|key| {
+                            let row = lookup(key);
+                            (row.map(|row| sort_cell(mdl, order_by, row)), row)
+                        })
+                        .collect();
+                    let mut charge = mem.working_buffer();
+                    charge.charge_upto(query_mem::vec_size(&rows))?;
+                    rows.sort_by(|(a, _), (b, _)| cmp_cells(order_by, a.as_ref(), b.as_ref()));
+                    rows.into_iter()
+                        .skip(bounds.offset() as usize)
+                        .take(bounds.limit() as usize)
+                        .map(|(_, row)| row)
+                        .collect()
+                }
+                None => keys
+                    .into_iter()
+                    .skip(bounds.offset() as usize)
+                    .take(bounds.limit() as usize)
+                    .map(lookup)
+                    .collect(),
+            };
+            let key_c = rows.len();
+            for row in rows {
+                let Some(row) = row else {
+                    f_row(serialize_target, None);
+                    continue;
+                };
+                f_row(serialize_target, Some(col_c));
+                let pkdc = VirtualDatacell::new_pk(row.d_key(), mdl.p_tag());
+                let r = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+                read_row(mdl, &select, &pkdc, r.fields(), &mut |dc| {
+                    f(serialize_target, dc)
+                })?;
+            }
+            Ok(key_c)
+        })
+}
+
+pub(super) struct RowIteratorAll<'g> {
     _g: &'g sync::atm::Guard,
     mdl: &'g ModelData,
-    iter: <IndexMTRaw<Row> as MTIndexExt<Row, PrimaryIndexKey, RowDataLck>>::IterEntry<'g, 'g, 'g>,
+    iter: RowIter<'g>,
     _latch: IndexLatchHandleExclusive<'g>,
     limit: usize,
+    expiry: Expiry,
 }
 
 impl<'g> RowIteratorAll<'g> {
-    fn new(g: &'g sync::atm::Guard, mdl: &'g ModelData, limit: usize) -> Self {
+    pub(super) fn new(g: &'g sync::atm::Guard, mdl: &'g ModelData, limit: usize) -> Self {
         let idx = mdl.primary_index();
         let latch = idx.acquire_exclusive();
         Self {
@@ -233,8 +953,23 @@ impl<'g> RowIteratorAll<'g> {
             iter: idx.__raw_index().mt_iter_entry(g),
             _latch: latch,
             limit,
+            expiry: mdl.expiry(),
         }
     }
+    /// Only go over the rows whose keys start with `prefix`. Returns `None` if the index can't find them without going
+    /// over every row
+    fn new_prefix(g: &'g sync::atm::Guard, mdl: &'g ModelData, prefix: &[u8]) -> Option<Self> {
+        let idx = mdl.primary_index();
+        let latch = idx.acquire_exclusive();
+        Some(Self {
+            _g: g,
+            mdl,
+            iter: idx.select_prefix(prefix, g)?,
+            _latch: latch,
+            limit: usize::MAX,
+            expiry: mdl.expiry(),
+        })
+    }
     fn _next(
         &mut self,
     ) -> Option<(
@@ -244,13 +979,15 @@ impl<'g> RowIteratorAll<'g> {
         if self.limit == 0 {
             return None;
         }
-        self.limit -= 1;
-        self.iter.next().map(|row| {
-            (
-                row.d_key(),
-                row.resolve_schema_deltas_and_freeze(self.mdl.delta_state()),
-            )
-        })
+        loop {
+            let row = self.iter.next()?;
+            let data = row.resolve_schema_deltas_and_freeze(self.mdl.delta_state());
+            // skip expired rows (they don't count towards the limit)
+            if !self.expiry.is_expired(&data) {
+                self.limit -= 1;
+                return Some((row.d_key(), data));
+            }
+        }
     }
 }
 
@@ -28,7 +28,12 @@ use {
     crate::{
         engine::{
             core::{
-                self, dml::QueryExecMeta, model::delta::DataDeltaKind,
+                self,
+                dml::QueryExecMeta,
+                model::{
+                    delta::{self, DataDelta, DataDeltaKind},
+                    Field, ListBounds,
+                },
                 query_meta::AssignmentOperator,
             },
             data::{
@@ -38,14 +43,14 @@ use {
             },
             error::{QueryError, QueryResult},
             fractal::GlobalInstanceLike,
-            idx::STIndex,
+            idx::{STIndex, STIndexSeq},
             net::protocol::Response,
             ql::dml::upd::{AssignmentExpression, UpdateStatement},
             sync,
         },
         util::compiler::{self, TaggedEnum},
     },
-    std::mem,
+    std::{collections::BTreeMap, mem},
 };
 
 #[inline(always)]
@@ -226,6 +231,13 @@ static OPERATOR: [unsafe fn(&Datacell, Lit) -> (bool, Datacell); {
     dc_op_fail,
     dc_op_fail,
     dc_op_fail,
+    // list
+    // -- pad: 5
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
 ];
 
 #[inline(always)]
@@ -233,6 +245,79 @@ const fn opc(opr: TagClass, ope: AssignmentOperator) -> usize {
     (AssignmentOperator::VARIANT_COUNT * opr.value_word()) + ope.value_word()
 }
 
+/// Apply the operator to the element at `index` of a list, in the same way as it would be applied to a field
+fn list_element_op(
+    bounds: ListBounds,
+    list: &mut [Datacell],
+    index: u64,
+    rhs: Lit,
+    operator_fn: AssignmentOperator,
+) -> QueryResult<()> {
+    let Some(element) = usize::try_from(index)
+        .ok()
+        .and_then(|index| list.get_mut(index))
+    else {
+        return Err(QueryError::QExecDmlValidationError);
+    };
+    let tag = element.tag().tag_class();
+    if (tag != rhs.kind().tag_class()) | (tag >= TagClass::List) | element.is_null() {
+        return Err(QueryError::QExecDmlValidationError);
+    }
+    let (okay, new) = unsafe {
+        // UNSAFE(@ohsayan): matched tags
+        OPERATOR[opc(tag, operator_fn)](element, rhs)
+    };
+    if okay & bounds.element_fits(&new) {
+        *element = new;
+        Ok(())
+    } else {
+        Err(QueryError::QExecDmlValidationError)
+    }
+}
+
+/// Apply the operator to the value at `key` of a map. Only an assignment can add a new key
+fn map_key_op(
+    field: &Field,
+    map: &mut BTreeMap<Box<str>, Datacell>,
+    key: &str,
+    rhs: Lit,
+    operator_fn: AssignmentOperator,
+) -> QueryResult<()> {
+    let (okay, mut new) = match map.get(key) {
+        Some(value) => {
+            let tag = value.tag().tag_class();
+            if (tag != rhs.kind().tag_class()) | (tag >= TagClass::List) {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            unsafe {
+                // UNSAFE(@ohsayan): matched tags
+                OPERATOR[opc(tag, operator_fn)](value, rhs)
+            }
+        }
+        None => (operator_fn == AssignmentOperator::Assign, rhs.into()),
+    };
+    if okay & field.vt_nested(&mut new) {
+        map.insert(key.into(), new);
+        Ok(())
+    } else {
+        Err(QueryError::QExecDmlValidationError)
+    }
+}
+
+/// Pop `count` elements off the end of a list
+fn list_pop(list: &mut Vec<Datacell>, count: u64) -> QueryResult<()> {
+    match usize::try_from(count)
+        .ok()
+        .and_then(|count| list.len().checked_sub(count))
+    {
+        Some(len) => {
+            list.truncate(len);
+            Ok(())
+        }
+        None => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
 #[cfg(test)]
 local! {
     pub(super) static ROUTE_TRACE: Vec<&'static str> = Vec::new();
@@ -258,7 +343,17 @@ pub fn update_resp(
 }
 
 pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) -> QueryResult<()> {
+    #[cfg(test)]
+    {
+        // only trace the last update
+        local_mut!(ROUTE_TRACE, |rtrace| rtrace.clear())
+    }
     core::with_model_for_data_update(global, update.entity(), |mdl| {
+        mdl.check_rows_mutable()?;
+        // with unique fields, an update can't run alongside an insert or a delete (see `model::unique`)
+        let _idx_latch = mdl
+            .has_unique_fields()
+            .then(|| mdl.primary_index().acquire_cd());
         let mut ret = Ok(QueryExecMeta::zero());
         // prepare row fetch
         let key = mdl.resolve_where(update.clauses_mut())?;
@@ -269,12 +364,19 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
         };
         // lock row
         let mut row_data_wl = row.d_data().write();
+        if mdl.expiry().is_expired(&row_data_wl) {
+            return Err(QueryError::QExecDmlRowNotFound);
+        }
+        if !mdl.where_residual_matches(update.clauses_mut(), row_data_wl.fields())? {
+            return Err(QueryError::QExecDmlRowNotFound);
+        }
         // create new version
         let ds = mdl.delta_state();
         let new_version = ds.create_new_data_delta_version();
         // process changes
         let mut rollback_now = false;
         let mut rollback_data = Vec::with_capacity(update.expressions().len());
+        let mut changed_fields = Vec::with_capacity(update.expressions().len());
         let mut assn_expressions = update.into_expressions().into_iter();
         /*
             FIXME(@ohsayan): where's my usual magic? I'll do it once we have the SE stabilized
@@ -283,8 +385,10 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
         while (assn_expressions.len() != 0) & (!rollback_now) {
             let AssignmentExpression {
                 lhs,
+                index,
                 rhs,
                 operator_fn,
+                ..
             } = unsafe {
                 // UNSAFE(@ohsayan): pre-loop cond
                 assn_expressions.next().unwrap_unchecked()
@@ -298,6 +402,7 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
                 (Some(fdef), Some(fdata)) => {
                     field_definition = fdef;
                     field_data = fdata;
+                    changed_fields.push(lhs.as_str());
                 }
                 _ => {
                     input_trace("fieldnotfound");
@@ -306,6 +411,91 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
                     break;
                 }
             }
+            let field_class = field_definition.layers()[0].tag().tag_class();
+            let Some(rhs) = rhs else {
+                // `x = null`, or arithmetic with a null (`x += null`), which is always null
+                if index.is_some() {
+                    input_trace("null;element");
+                    rollback_now = true;
+                    ret = Err(QueryError::QExecDmlValidationError);
+                    break;
+                }
+                if (operator_fn != AssignmentOperator::Assign) & (field_class >= TagClass::List) {
+                    // that's a push or a remove, not arithmetic
+                    input_trace("null;collection");
+                    rollback_now = true;
+                    ret = Err(QueryError::QExecDmlValidationError);
+                    break;
+                }
+                if !field_definition.is_nullable() {
+                    input_trace("null;notnullable");
+                    rollback_now = true;
+                    ret = Err(QueryError::QExecDmlNullViolation);
+                    break;
+                }
+                rollback_data.push((lhs.as_str(), mem::replace(field_data, Datacell::null())));
+                input_trace("null");
+                continue;
+            };
+            let is_list = (field_class == TagClass::List) & field_data.is_init();
+            let is_map = (field_class == TagClass::Map) & field_data.is_init();
+            if let Some(index) = index {
+                match index.kind().tag_class() {
+                    // an element of a list
+                    TagClass::UnsignedInt if is_list => {
+                        rollback_data.push((lhs.as_str(), field_data.clone()));
+                        let r = unsafe {
+                            // UNSAFE(@ohsayan): +tagck
+                            list_element_op(
+                                field_definition.layers()[0].bounds(),
+                                &mut field_data.read_list().write(),
+                                index.uint(),
+                                rhs,
+                                operator_fn,
+                            )
+                        };
+                        if let Err(e) = r {
+                            input_trace("list;element;invalid");
+                            rollback_now = true;
+                            ret = Err(e);
+                            break;
+                        }
+                        input_trace("list;element");
+                    }
+                    // a key of a map
+                    TagClass::Str if is_map => {
+                        rollback_data.push((lhs.as_str(), field_data.clone()));
+                        let r = unsafe {
+                            // UNSAFE(@ohsayan): +tagck
+                            map_key_op(
+                                field_definition,
+                                &mut field_data.read_map().write(),
+                                index.str(),
+                                rhs,
+                                operator_fn,
+                            )
+                        };
+                        if let Err(e) = r {
+                            input_trace("map;key;invalid");
+                            rollback_now = true;
+                            ret = Err(e);
+                            break;
+                        }
+                        input_trace("map;key");
+                    }
+                    _ => {
+                        input_trace(if field_class == TagClass::Map {
+                            "map;key;badtag"
+                        } else {
+                            "list;element;badtag"
+                        });
+                        rollback_now = true;
+                        ret = Err(QueryError::QExecDmlValidationError);
+                        break;
+                    }
+                }
+                continue;
+            }
             match (
                 field_definition.layers()[0].tag().tag_class(),
                 rhs.kind().tag_class(),
@@ -326,14 +516,32 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
                     rollback_data.push((lhs.as_str(), mem::replace(field_data, rhs.into())));
                     input_trace("sametag;orignull");
                 }
-                (TagClass::List, tag_b) if operator_fn == AssignmentOperator::AddAssign => {
+                (tag_a, tag_b)
+                    if (tag_a == tag_b) & (tag_a < TagClass::List) & field_data.is_null() =>
+                {
+                    // arithmetic on a null is still null, so the field is left as it is
+                    input_trace("sametag;orignull;propagated");
+                }
+                (TagClass::List, tag_b)
+                    if is_list & (operator_fn == AssignmentOperator::AddAssign) =>
+                {
                     if field_definition.layers()[1].tag().tag_class() == tag_b {
                         unsafe {
                             // UNSAFE(@ohsayan): matched tags
                             let mut list = field_data.read_list().write();
+                            let element: Datacell = rhs.into();
+                            if !field_definition.layers()[0]
+                                .bounds()
+                                .check_push(list.len(), &element)
+                            {
+                                input_trace("list;outofbounds");
+                                rollback_now = true;
+                                ret = Err(QueryError::QExecDmlValidationError);
+                                break;
+                            }
                             if list.try_reserve(1).is_ok() {
                                 input_trace("list;sametag");
-                                list.push(rhs.into());
+                                list.push(element);
                             } else {
                                 rollback_now = true;
                                 ret = Err(QueryError::SysOutOfMemory);
@@ -347,6 +555,38 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
                         break;
                     }
                 }
+                (TagClass::List, TagClass::UnsignedInt)
+                    if is_list & (operator_fn == AssignmentOperator::SubAssign) =>
+                {
+                    rollback_data.push((lhs.as_str(), field_data.clone()));
+                    let r = unsafe {
+                        // UNSAFE(@ohsayan): +tagck
+                        list_pop(&mut field_data.read_list().write(), rhs.uint())
+                    };
+                    if let Err(e) = r {
+                        input_trace("list;pop;outofbounds");
+                        rollback_now = true;
+                        ret = Err(e);
+                        break;
+                    }
+                    input_trace("list;pop");
+                }
+                (TagClass::Map, TagClass::Str)
+                    if is_map & (operator_fn == AssignmentOperator::SubAssign) =>
+                {
+                    rollback_data.push((lhs.as_str(), field_data.clone()));
+                    let removed = unsafe {
+                        // UNSAFE(@ohsayan): +tagck
+                        field_data.read_map().write().remove(rhs.str())
+                    };
+                    if removed.is_none() {
+                        input_trace("map;remove;notfound");
+                        rollback_now = true;
+                        ret = Err(QueryError::QExecDmlValidationError);
+                        break;
+                    }
+                    input_trace("map;remove");
+                }
                 _ => {
                     input_trace("unknown_reason;exitmainloop");
                     ret = Err(QueryError::QExecDmlValidationError);
@@ -355,20 +595,63 @@ pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) ->
                 }
             }
         }
+        if !rollback_now {
+            if let Err(e) = core::row_size::check_row(row.d_key(), row_data_wl.fields()) {
+                input_trace("rowsize;toolarge");
+                rollback_now = true;
+                ret = Err(e);
+            }
+        }
+        // nothing can fail after this, so claim the (new) values of the unique fields last
+        if !rollback_now {
+            if let Err(e) = mdl.unique_claim([(row.d_key(), row_data_wl.fields())]) {
+                input_trace("unique;violation");
+                rollback_now = true;
+                ret = Err(e);
+            }
+        }
         if compiler::unlikely(rollback_now) {
             input_trace("rollback");
+            // a field can be changed more than once so restore the oldest copy last
             rollback_data
                 .into_iter()
+                .rev()
                 .for_each(|(field_id, restored_data)| {
                     row_data_wl.fields_mut().st_update(field_id, restored_data);
                 });
         } else {
             // update revised tag
             row_data_wl.set_txn_revised(new_version);
+            row_data_wl.touch();
+            // track the changed fields (on top of whatever else was changed since the row was last written out)
+            let positions = mdl
+                .fields()
+                .stseq_ord_key()
+                .filter(|field| field.as_str() != mdl.p_key())
+                .enumerate()
+                .filter(|(_, field)| changed_fields.contains(&field.as_str()))
+                .map(|(position, _)| position)
+                .collect();
+            let dirty = row_data_wl.mark_dirty(ds.schema_current_version(), positions);
             // publish delta
-            let dp =
-                ds.append_new_data_delta_with(DataDeltaKind::Update, row.clone(), new_version, &g);
-            ret = Ok(QueryExecMeta::new(dp))
+            let dp = match dirty {
+                // only the changed fields will be written out
+                Some(changed) if delta::delta_batches() => ds.append_new_data_delta(
+                    DataDelta::new_partial_update(new_version, row.clone(), changed),
+                    &g,
+                ),
+                _ => ds.append_new_data_delta_with(
+                    DataDeltaKind::Update,
+                    row.clone(),
+                    new_version,
+                    &g,
+                ),
+            };
+            ret = Ok(QueryExecMeta::new(dp));
+            // the cache reads the row again, so let go of it first
+            drop(row_data_wl);
+            mdl.columnar_cache_upsert(row);
+            mdl.secondary_indexes_upsert(row);
         }
         ret
     })
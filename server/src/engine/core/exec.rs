@@ -24,16 +24,29 @@
  *
 */
 
-use crate::engine::{
-    core::{ddl_misc, dml, model::ModelData, space::Space},
-    error::{QueryError, QueryResult},
-    fractal::{Global, GlobalInstanceLike},
-    net::protocol::{ClientLocalState, Response, ResponseType, SQuery},
-    ql::{
-        ast::{traits::ASTNode, InplaceData, State},
-        ddl::Use,
-        lex::KeywordStmt,
+use {
+    crate::engine::{
+        core::{
+            cancel::{self, CancelFlag},
+            ddl_misc, dml,
+            model::{Backpressure, ModelData},
+            quota, scratch,
+            space::{DropSpaceResult, Space},
+            task,
+            trace::{self, Phase, TraceHandle},
+        },
+        error::{QueryError, QueryResult},
+        fractal::{compute, Global, GlobalInstanceLike},
+        net::protocol::{ClientLocalState, Response, ResponseType, RetryInfo, SQuery},
+        ql::{
+            ast::{traits::ASTNode, InplaceData, State},
+            ddl::Use,
+            dml::sel::SelectAllStatement,
+            lex::{Keyword, KeywordStmt, SecureLexer, Token},
+            session::{self, SetSession},
+        },
     },
+    std::time::{Duration, Instant},
 };
 
 /*
@@ -46,19 +59,170 @@ pub async fn dispatch_to_executor<'a>(
     cstate: &mut ClientLocalState,
     query: SQuery<'a>,
 ) -> QueryResult<Response> {
-    let tokens =
-        crate::engine::ql::lex::SecureLexer::new_with_segments(query.query(), query.params())
-            .lex()?;
-    let mut state = State::new_inplace(&tokens);
+    let trace = cstate.trace().cloned();
+    let parse = trace.as_ref().map(|trace| trace.phase("parse"));
+    // the tokens go into the session's token vector, which we take back once the statement is done with them
+    let tokens = match SecureLexer::new_with_segments_in(
+        query.query(),
+        query.params(),
+        cstate.scratch_mut().take_tokens(),
+    )
+    .lex()
+    {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            // we don't know what the statement is, but we know that it didn't run
+            cstate.set_retry(RetryInfo::new(false, true));
+            return Err(e);
+        }
+    };
+    let side_effect_free = is_side_effect_free(&tokens);
+    let ret = dispatch_tokens(global, cstate, &query, &tokens, trace, parse).await;
+    cstate.set_retry(RetryInfo::new(side_effect_free, is_not_applied(&ret)));
+    cstate.scratch_mut().recycle_tokens(tokens);
+    ret
+}
+
+/// Returns true if the statement can't change any data. Statements that only change the session (like `use` or `set`)
+/// are side-effect-free too, since running them again leaves the session in the same state
+fn is_side_effect_free(tokens: &[Token]) -> bool {
+    match tokens.first() {
+        // sysctl can change users and settings
+        Some(Token::Keyword(Keyword::Statement(stmt))) => {
+            !(stmt.is_write() | (*stmt == KeywordStmt::Sysctl))
+        }
+        Some(token) => !Token![truncate].eq(token),
+        None => true,
+    }
+}
+
+/// Returns true if the statement failed before it could change anything
+fn is_not_applied(ret: &QueryResult<Response>) -> bool {
+    match ret {
+        Err(e) | Ok(Response::ErrorAt { error: e, .. }) => !e.is_indeterminate(),
+        Ok(_) => false,
+    }
+}
+
+async fn dispatch_tokens<'a>(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    query: &SQuery<'a>,
+    tokens: &[Token<'a>],
+    trace: Option<TraceHandle>,
+    parse: Option<Phase>,
+) -> QueryResult<Response> {
+    let (tokens, timeout) = session::split_timeout_clause(tokens)?;
+    let mut state = State::new_inplace(tokens);
+    state.set_lenient(!cstate.strict_parsing());
+    match dispatch_state(global, cstate, query, &mut state, timeout, trace, parse).await {
+        // point the client at the first of the trailing tokens (if it knows how to read that)
+        Err(QueryError::QLUnexpectedTrailingTokens) if cstate.reports_error_positions() => {
+            Ok(Response::ErrorAt {
+                error: QueryError::QLUnexpectedTrailingTokens,
+                position: state.cursor(),
+            })
+        }
+        r => r,
+    }
+}
+
+async fn dispatch_state<'a>(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    query: &SQuery<'a>,
+    state: &mut State<'_, InplaceData>,
+    timeout: Option<Duration>,
+    trace: Option<TraceHandle>,
+    parse: Option<Phase>,
+) -> QueryResult<Response> {
+    if state.not_exhausted() && state.cursor_eq(Token![set]) && timeout.is_none() {
+        state.cursor_ahead();
+        return cstate_set(cstate, state);
+    }
     state.set_space_maybe(unsafe {
         // UNSAFE(@ohsayan): exclusively used within this scope
-        core::mem::transmute(cstate.get_cs())
+        core::mem::transmute::<Option<&str>, Option<&str>>(cstate.get_cs())
     });
+    if state.not_exhausted() && state.cursor_eq(Token![truncate]) {
+        // not a statement keyword (it's only valid as `truncate model`)
+        drop(parse);
+        if let Some(trace) = trace.as_ref() {
+            trace.set_operation("truncate");
+        }
+        return run_truncate(global, cstate, state).await;
+    }
+    if state.has_remaining(2)
+        && matches!(state.read(), Token![create] | Token![alter] | Token![drop])
+        && state.offset_current_r(1).ident_eq("task")
+    {
+        // `task` isn't a keyword either, and creating a task needs the raw query
+        drop(parse);
+        if let Some(trace) = trace.as_ref() {
+            trace.set_operation("task");
+        }
+        return run_task_ddl(global, cstate, query, state).await;
+    }
+    if state.not_exhausted() && state.read().ident_eq("explain") {
+        // not a statement keyword either (it's only valid as `explain select ...`)
+        drop(parse);
+        if let Some(trace) = trace.as_ref() {
+            trace.set_operation("explain");
+        }
+        return run_explain(global, state);
+    }
     let stmt = state.try_statement()?;
+    drop(parse);
+    if let Some(trace) = trace.as_ref() {
+        trace.set_operation(stmt.as_str());
+    }
+    // the statement's own timeout wins over the session's
+    let cancel = match timeout
+        .or(cstate.statement_timeout())
+        .filter(|t| !t.is_zero())
+    {
+        Some(timeout) => Some(
+            cstate
+                .cancel_flag()
+                .cloned()
+                .unwrap_or_default()
+                .with_deadline(Instant::now() + timeout),
+        ),
+        None => cstate.cancel_flag().cloned(),
+    };
+    if stmt.is_write()
+        && (global.health().is_read_only() || global.state().namespace().sys_db().is_read_only())
+    {
+        // either storage has failed (only reads are served until it recovers) or an admin has
+        // put the server in read-only mode using `sysctl set read_only = true`
+        return Err(QueryError::SysReadOnly);
+    }
+    if stmt.is_write() && !cstate.is_root() {
+        let limits = global
+            .state()
+            .namespace()
+            .sys_db()
+            .write_limits(cstate.username());
+        quota::charge_write(cstate.username(), limits, query.payload().len())?;
+    }
+    let _execute = trace.as_ref().map(|trace| trace.phase("execute"));
     if stmt.is_blocking() {
         run_blocking_stmt(global, cstate, state, stmt).await
+    } else if (stmt == KeywordStmt::Select) & state.cursor_rounded_eq(Token![all]) {
+        run_select_all(global, cstate, query, cancel, trace, state).await
     } else {
-        run_nb(global, cstate, state, stmt)
+        let buf = cstate.scratch_mut().take_response_buffer();
+        let (r, unused) = scratch::scope(buf, || {
+            trace::scope(trace, || run_nb(global, cstate, state, stmt))
+        });
+        if let Some(buf) = unused {
+            cstate.scratch_mut().recycle_response_buffer(buf);
+        }
+        if let Some(delay) = Backpressure::take_deferred() {
+            // the flusher is falling behind so hold back the response to slow this client down
+            tokio::time::sleep(delay).await;
+        }
+        r
     }
 }
 
@@ -104,7 +268,7 @@ fn translate_ddl_result(x: Option<bool>) -> Response {
 async fn run_blocking_stmt(
     global: &Global,
     cstate: &mut ClientLocalState,
-    mut state: State<'_, InplaceData>,
+    state: &mut State<'_, InplaceData>,
     stmt: KeywordStmt,
 ) -> Result<Response, QueryError> {
     if !(cstate.is_root() | (stmt == KeywordStmt::Sysctl)) {
@@ -131,13 +295,15 @@ async fn run_blocking_stmt(
     let a_m = (alter & Token![model].eq(a) & last_id) as u8 * 5;
     let d_s = (drop & Token![space].eq(a) & (last_id | last_allow | last_if)) as u8 * 6;
     let d_m = (drop & Token![model].eq(a) & (last_id | last_allow | last_if)) as u8 * 7;
-    let fc = sysctl as u8 | c_s | c_m | a_s | a_m | d_s | d_m;
+    let c_i = (create & Token![index].eq(a) & (last_id | last_if)) as u8 * 8;
+    let d_i = (drop & Token![index].eq(a) & (last_id | last_if)) as u8 * 9;
+    let fc = sysctl as u8 | c_s | c_m | a_s | a_m | d_s | d_m | c_i | d_i;
     state.cursor_ahead_if(!sysctl);
     static BLK_EXEC: [fn(
         Global,
         &ClientLocalState,
         &mut State<'static, InplaceData>,
-    ) -> QueryResult<Response>; 8] = [
+    ) -> QueryResult<Response>; 10] = [
         |_, _, _| Err(QueryError::QLUnknownStatement),
         blocking_exec_sysctl,
         |g, _, t| {
@@ -162,7 +328,14 @@ async fn run_blocking_stmt(
                 Response::Empty
             })
         },
-        |g, _, t| _callgs_map(&g, t, Space::transactional_exec_drop, translate_ddl_result),
+        |g, _, t| {
+            _callgs_map(
+                &g,
+                t,
+                Space::transactional_exec_drop,
+                DropSpaceResult::into_response,
+            )
+        },
         |g, _, t| {
             _callgs_map(
                 &g,
@@ -171,28 +344,208 @@ async fn run_blocking_stmt(
                 translate_ddl_result,
             )
         },
+        |g, _, t| {
+            _callgs_map(
+                &g,
+                t,
+                ModelData::transactional_exec_create_index,
+                translate_ddl_result,
+            )
+        },
+        |g, _, t| {
+            _callgs_map(
+                &g,
+                t,
+                ModelData::transactional_exec_drop_index,
+                translate_ddl_result,
+            )
+        },
     ];
     let r = unsafe {
         // UNSAFE(@ohsayan): the only await is within this block
         let c_glob = global.clone();
         let static_cstate: &'static ClientLocalState = core::mem::transmute(cstate);
-        let static_state: &'static mut State<'static, InplaceData> =
-            core::mem::transmute(&mut state);
+        let static_state: &'static mut State<'static, InplaceData> = core::mem::transmute(state);
+        tokio::task::spawn_blocking(move || {
+            trace::scope(static_cstate.trace().cloned(), || {
+                BLK_EXEC[fc as usize](c_glob, static_cstate, static_state)
+            })
+        })
+        .await
+    };
+    r.unwrap()
+}
+
+async fn run_truncate(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    state: &mut State<'_, InplaceData>,
+) -> QueryResult<Response> {
+    if !cstate.is_root() {
+        return Err(QueryError::SysPermissionDenied);
+    }
+    if global.health().is_read_only() || global.state().namespace().sys_db().is_read_only() {
+        return Err(QueryError::SysReadOnly);
+    }
+    state.cursor_ahead();
+    if !((state.remaining() >= 2) && state.cursor_eq(Token![model])) {
+        return Err(QueryError::QLInvalidSyntax);
+    }
+    state.cursor_ahead();
+    // like any other DDL query, the model must be fully specified
+    state.unset_space();
+    let r = unsafe {
+        // UNSAFE(@ohsayan): the only await is within this block
+        let c_glob = global.clone();
+        let static_cstate: &'static ClientLocalState = core::mem::transmute(cstate);
+        let static_state: &'static mut State<'static, InplaceData> = core::mem::transmute(state);
+        tokio::task::spawn_blocking(move || {
+            trace::scope(static_cstate.trace().cloned(), || {
+                _callgs_map(
+                    &c_glob,
+                    static_state,
+                    ModelData::transactional_exec_truncate,
+                    |_| Response::Empty,
+                )
+            })
+        })
+        .await
+    };
+    r.unwrap()
+}
+
+async fn run_task_ddl(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    query: &SQuery<'_>,
+    state: &mut State<'_, InplaceData>,
+) -> QueryResult<Response> {
+    // tasks run as root, so only root can manage them
+    if !cstate.is_root() {
+        return Err(QueryError::SysPermissionDenied);
+    }
+    if global.health().is_read_only() || global.state().namespace().sys_db().is_read_only() {
+        return Err(QueryError::SysReadOnly);
+    }
+    let stmt = state.fw_read();
+    state.cursor_ahead();
+    let body = match stmt {
+        Token![create] => Some(task::TaskBody::from_create(query)?),
+        _ => None,
+    };
+    let alter = Token![alter].eq(stmt);
+    let r = unsafe {
+        // UNSAFE(@ohsayan): the only await is within this block
+        let c_glob = global.clone();
+        let static_cstate: &'static ClientLocalState = core::mem::transmute(cstate);
+        let static_state: &'static mut State<'static, InplaceData> = core::mem::transmute(state);
         tokio::task::spawn_blocking(move || {
-            BLK_EXEC[fc as usize](c_glob, static_cstate, static_state)
+            trace::scope(static_cstate.trace().cloned(), || match body {
+                Some(body) => _callgs_map(
+                    &c_glob,
+                    static_state,
+                    |g, stmt| task::create_task(g, stmt, body),
+                    |_| Response::Empty,
+                ),
+                None if alter => {
+                    _callgs_map(&c_glob, static_state, task::alter_task, |_| Response::Empty)
+                }
+                None => _callgs_map(&c_glob, static_state, task::drop_task, |_| Response::Empty),
+            })
         })
         .await
     };
     r.unwrap()
 }
 
+fn run_explain(global: &Global, state: &mut State<'_, InplaceData>) -> QueryResult<Response> {
+    // nothing is read (or written), so there's no need to check for read-only mode
+    state.cursor_ahead();
+    let state = unsafe {
+        // UNSAFE(@ohsayan): this is a lifetime issue with the token handle
+        core::mem::transmute::<&mut State<'_, InplaceData>, &mut State<'_, InplaceData>>(state)
+    };
+    _callgs(global, state, dml::explain_resp)
+}
+
+/// Scans that can return at least these many rows are run on the compute pool
+const COMPUTE_OFFLOAD_MIN_ROWS: u64 = 1024;
+
+async fn run_select_all(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    query: &SQuery<'_>,
+    mut cancel: Option<CancelFlag>,
+    mut trace: Option<TraceHandle>,
+    state: &mut State<'_, InplaceData>,
+) -> QueryResult<Response> {
+    state.cursor_ahead();
+    let start = state.cursor();
+    let fmt = cstate.result_format();
+    let mut buf = Some(cstate.scratch_mut().take_response_buffer());
+    let inline = {
+        let select = SelectAllStatement::parse_from_state_hardened(state)?;
+        // the offset is scanned over too, and ordering the rows means reading all of them
+        (select.order_by.is_none()
+            & (select.limit.saturating_add(select.offset) < COMPUTE_OFFLOAD_MIN_ROWS))
+            .then(|| {
+                scratch::scope(buf.take().unwrap(), || {
+                    trace::scope(trace.take(), || {
+                        cancel::scope(cancel.take(), || dml::select_all_resp(global, select, fmt))
+                    })
+                })
+            })
+    };
+    let (r, unused) = match inline {
+        Some(r) => r,
+        None => {
+            /*
+                the statement borrows from the connection's query buffer, and the compute pool may still be running
+                the task after this future has been dropped. so the task gets its own copy of the query and parses the
+                statement again (which costs nothing next to the scan)
+            */
+            let c_glob = global.clone();
+            let payload = query.payload().to_vec();
+            let q_window = query.q_window();
+            let space: Option<Box<str>> = cstate.get_cs().map(Into::into);
+            let lenient = !cstate.strict_parsing();
+            let buf = buf.take().unwrap();
+            compute::offload(move || {
+                let query = SQuery::new(&payload, q_window);
+                let tokens = SecureLexer::new_with_segments(query.query(), query.params()).lex()?;
+                let (tokens, _) = session::split_timeout_clause(&tokens)?;
+                let mut state = State::new_inplace(tokens);
+                state.set_lenient(lenient);
+                state.set_space_maybe(unsafe {
+                    // UNSAFE(@ohsayan): the space is owned by this task and outlives the state
+                    core::mem::transmute::<Option<&str>, Option<&str>>(space.as_deref())
+                });
+                state.cursor_ahead_by(start);
+                let select = SelectAllStatement::parse_from_state_hardened(&mut state)?;
+                Ok(scratch::scope(buf, || {
+                    trace::scope(trace, || {
+                        cancel::scope(cancel, || dml::select_all_resp(&c_glob, select, fmt))
+                    })
+                }))
+            })
+            .await
+            // the buffer is lost if the task couldn't run, but the connection will just allocate a new one
+            .unwrap_or_else(|e| (Err(e), None))
+        }
+    };
+    if let Some(buf) = unused {
+        cstate.scratch_mut().recycle_response_buffer(buf);
+    }
+    r
+}
+
 fn blocking_exec_sysctl(
     g: Global,
     cstate: &ClientLocalState,
     state: &mut State<'static, InplaceData>,
 ) -> QueryResult<Response> {
     let r = ASTNode::parse_from_state_hardened(state)?;
-    super::dcl::exec(g, cstate, r).map(|_| Response::Empty)
+    super::dcl::exec(&g, cstate, r)
 }
 
 /*
@@ -239,10 +592,25 @@ fn cstate_use(
     Ok(Response::Empty)
 }
 
+fn cstate_set(
+    cstate: &mut ClientLocalState,
+    state: &mut State<'_, InplaceData>,
+) -> QueryResult<Response> {
+    match SetSession::parse_from_state_hardened(state)? {
+        SetSession::StatementTimeout(timeout) => cstate.set_statement_timeout(timeout),
+        SetSession::ResultMetadata(enabled) => cstate.result_format_mut().meta = enabled,
+        SetSession::PackedColumns(enabled) => cstate.result_format_mut().packed = enabled,
+        SetSession::TraceParent(parent) => cstate.set_traceparent(parent),
+        SetSession::StrictParsing(strict) => cstate.set_strict_parsing(strict),
+        SetSession::RetryInfo(enabled) => cstate.set_retry_info(enabled),
+    }
+    Ok(Response::Empty)
+}
+
 fn run_nb(
     global: &Global,
     cstate: &mut ClientLocalState,
-    mut state: State<'_, InplaceData>,
+    state: &mut State<'_, InplaceData>,
     stmt: KeywordStmt,
 ) -> QueryResult<Response> {
     let stmt_c = stmt.value_u8() - KeywordStmt::Use.value_u8();
@@ -250,25 +618,32 @@ fn run_nb(
         &Global,
         &mut ClientLocalState,
         &mut State<'static, InplaceData>,
-    ) -> QueryResult<Response>; 9] = [
+    ) -> QueryResult<Response>; 10] = [
         cstate_use, // use
         |g, c, s| _callgcs(g, c, s, ddl_misc::inspect),
         |_, _, _| Err(QueryError::QLUnknownStatement), // describe
         |g, _, s| _callgs(g, s, dml::insert_resp),
-        |g, _, s| _callgs(g, s, dml::select_resp),
+        |g, c, s| {
+            let fmt = c.result_format();
+            _callgs(g, s, |g, select| dml::select_resp(g, select, fmt))
+        },
         |g, _, s| _callgs(g, s, dml::update_resp),
         |g, _, s| _callgs(g, s, dml::delete_resp),
         |_, _, _| Err(QueryError::QLUnknownStatement), // exists
-        |g, _, s| _callgs(g, s, dml::select_all_resp),
+        |g, _, s| _callgs(g, s, dml::upsert_resp),
+        |g, c, s| {
+            let fmt = c.result_format();
+            _callgs(g, s, |g, select| dml::select_all_resp(g, select, fmt))
+        },
     ];
     {
         let n_offset_adjust = (stmt == KeywordStmt::Select) & state.cursor_rounded_eq(Token![all]);
         state.cursor_ahead_if(n_offset_adjust);
-        let corrected_offset = (n_offset_adjust as u8 * 8) | (stmt_c * (!n_offset_adjust as u8));
-        let mut state = unsafe {
+        let corrected_offset = (n_offset_adjust as u8 * 9) | (stmt_c * (!n_offset_adjust as u8));
+        let state = unsafe {
             // UNSAFE(@ohsayan): this is a lifetime issue with the token handle
-            core::mem::transmute(state)
+            core::mem::transmute::<&mut State<'_, InplaceData>, &mut State<'_, InplaceData>>(state)
         };
-        F[corrected_offset as usize](global, cstate, &mut state)
+        F[corrected_offset as usize](global, cstate, state)
     }
 }
@@ -33,7 +33,7 @@ use {
             lit::Lit,
             tag::{DataTag, TagUnique},
         },
-        idx::meta::Comparable,
+        idx::{meta::Comparable, mtart::RadixKey},
         mem::{self, DwordNN, DwordQN, SpecialPaddedWord, WordIO, ZERO_BLOCK},
     },
     core::{
@@ -52,7 +52,12 @@ pub struct PrimaryIndexKey {
 impl Clone for PrimaryIndexKey {
     fn clone(&self) -> Self {
         match self.tag {
-            TagUnique::SignedInt | TagUnique::UnsignedInt => {
+            TagUnique::SignedInt | TagUnique::UnsignedInt => Self {
+                tag: self.tag,
+                data: unsafe { core::mem::transmute_copy(&self.data) },
+            },
+            TagUnique::Bin | TagUnique::Str => {
+                // the key owns its block (see the dtor), so the clone needs a block of its own
                 let (qw, nw) = self.data.dwordqn_load_qw_nw();
                 unsafe {
                     let slice = slice::from_raw_parts(nw as *const u8, qw as _);
@@ -63,10 +68,6 @@ impl Clone for PrimaryIndexKey {
                     }
                 }
             }
-            TagUnique::Bin | TagUnique::Str => Self {
-                tag: self.tag,
-                data: unsafe { core::mem::transmute_copy(&self.data) },
-            },
             _ => unreachable!(),
         }
     }
@@ -263,6 +264,19 @@ impl<'a> Comparable<PrimaryIndexKey> for Lit<'a> {
     }
 }
 
+// NB: integer keys have no bytes, which is why only models with string or binary keys can use the radix index
+impl RadixKey for PrimaryIndexKey {
+    fn radix_key(&self) -> &[u8] {
+        self.virtual_block()
+    }
+}
+
+impl<'a> RadixKey for Lit<'a> {
+    fn radix_key(&self) -> &[u8] {
+        self.__vdata()
+    }
+}
+
 impl fmt::Debug for PrimaryIndexKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut dbg_struct = f.debug_struct("PrimaryIndexKey");
@@ -395,3 +409,22 @@ fn empty_slice() {
     assert_eq!(pk2, pk2_);
     drop((pk2, pk2_));
 }
+
+#[test]
+fn clone_owns_block() {
+    let data = [
+        Datacell::from(100),
+        Datacell::from(-100),
+        Datacell::from("hello"),
+        Datacell::from("hello".as_bytes()),
+        Datacell::from(""),
+    ];
+    for datum in data {
+        let pk = PrimaryIndexKey::try_from_dc(datum).unwrap();
+        let pk_ = pk.clone();
+        assert_eq!(pk, pk_);
+        drop(pk);
+        // the clone must still be readable after the original is gone
+        assert_eq!(format!("{pk_:?}"), format!("{:?}", pk_.clone()));
+    }
+}
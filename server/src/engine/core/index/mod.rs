@@ -29,7 +29,11 @@ mod row;
 
 use crate::engine::{
     data::lit::Lit,
-    idx::{IndexBaseSpec, IndexMTRaw, MTIndex},
+    idx::{
+        meta::Comparable,
+        mtart::{self, RadixKey},
+        IndexBaseSpec, IndexMTArt, IndexMTRaw, MTIndex, MTIndexExt,
+    },
     sync::atm::Guard,
 };
 
@@ -40,19 +44,65 @@ pub use {
 
 pub type RowDataLck = parking_lot::RwLock<RowData>;
 
+/// The structure that holds the rows of a model. It is picked when the model is created (`with { pk_index: "radix" }`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PrimaryIndexKind {
+    /// A concurrent hash table. This is the default
+    Hash = 0,
+    /// A radix tree that stores a prefix shared by many keys only once and keeps the rows in key order. Only for
+    /// string and binary keys
+    Radix = 1,
+}
+
+impl PrimaryIndexKind {
+    pub const fn name_str(&self) -> &'static str {
+        match self {
+            Self::Hash => "hash",
+            Self::Radix => "radix",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hash" => Some(Self::Hash),
+            "radix" => Some(Self::Radix),
+            _ => None,
+        }
+    }
+    pub const fn value_u8(&self) -> u8 {
+        *self as u8
+    }
+    pub const fn try_from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            0 => Some(Self::Hash),
+            1 => Some(Self::Radix),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PrimaryIndex {
-    data: IndexMTRaw<row::Row>,
+    data: RawPrimaryIndex,
     latch: IndexLatch,
 }
 
 impl PrimaryIndex {
-    pub fn new_empty() -> Self {
+    pub fn new_empty(kind: PrimaryIndexKind) -> Self {
         Self {
-            data: IndexMTRaw::idx_init(),
+            data: match kind {
+                PrimaryIndexKind::Hash => RawPrimaryIndex::Hash(IndexMTRaw::idx_init()),
+                PrimaryIndexKind::Radix => RawPrimaryIndex::Radix(IndexMTArt::idx_init()),
+            },
             latch: IndexLatch::new(),
         }
     }
+    pub fn kind(&self) -> PrimaryIndexKind {
+        match self.data {
+            RawPrimaryIndex::Hash(_) => PrimaryIndexKind::Hash,
+            RawPrimaryIndex::Radix(_) => PrimaryIndexKind::Radix,
+        }
+    }
     pub fn acquire_cd(&self) -> IndexLatchHandleShared {
         self.latch.gl_handle_shared()
     }
@@ -62,7 +112,15 @@ impl PrimaryIndex {
     pub fn select<'a, 'v, 't: 'v, 'g: 't>(&'t self, key: Lit<'a>, g: &'g Guard) -> Option<&'v Row> {
         self.data.mt_get_element(&key, g)
     }
-    pub fn __raw_index(&self) -> &IndexMTRaw<row::Row> {
+    /// Returns the rows whose keys start with `prefix`, in key order. Returns `None` if the index doesn't keep its
+    /// keys in order
+    pub fn select_prefix<'g>(&'g self, prefix: &[u8], g: &'g Guard) -> Option<RowIter<'g>> {
+        match self.data {
+            RawPrimaryIndex::Hash(_) => None,
+            RawPrimaryIndex::Radix(ref idx) => Some(RowIter::Radix(idx.mt_iter_prefix(prefix, g))),
+        }
+    }
+    pub fn __raw_index(&self) -> &RawPrimaryIndex {
         &self.data
     }
     pub fn count(&self) -> usize {
@@ -70,6 +128,124 @@ impl PrimaryIndex {
     }
 }
 
+/// The index holding the rows, for either [`PrimaryIndexKind`]
+#[derive(Debug)]
+pub enum RawPrimaryIndex {
+    Hash(IndexMTRaw<Row>),
+    Radix(IndexMTArt<Row>),
+}
+
+impl RawPrimaryIndex {
+    pub fn mt_len(&self) -> usize {
+        match self {
+            Self::Hash(idx) => idx.mt_len(),
+            Self::Radix(idx) => idx.mt_len(),
+        }
+    }
+    pub fn mt_clear(&self, g: &Guard) {
+        match self {
+            Self::Hash(idx) => idx.mt_clear(g),
+            Self::Radix(idx) => idx.mt_clear(g),
+        }
+    }
+    pub fn mt_insert(&self, row: Row, g: &Guard) -> bool {
+        match self {
+            Self::Hash(idx) => idx.mt_insert(row, g),
+            Self::Radix(idx) => idx.mt_insert(row, g),
+        }
+    }
+    pub fn mt_get<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v RowDataLck>
+    where
+        Q: ?Sized + RadixKey + Comparable<PrimaryIndexKey>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        match self {
+            Self::Hash(idx) => idx.mt_get(key, g),
+            Self::Radix(idx) => idx.mt_get(key, g),
+        }
+    }
+    pub fn mt_get_element<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v Row>
+    where
+        Q: ?Sized + RadixKey + Comparable<PrimaryIndexKey>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        match self {
+            Self::Hash(idx) => idx.mt_get_element(key, g),
+            Self::Radix(idx) => idx.mt_get_element(key, g),
+        }
+    }
+    pub fn mt_delete<Q>(&self, key: &Q, g: &Guard) -> bool
+    where
+        Q: ?Sized + RadixKey + Comparable<PrimaryIndexKey>,
+    {
+        match self {
+            Self::Hash(idx) => idx.mt_delete(key, g),
+            Self::Radix(idx) => idx.mt_delete(key, g),
+        }
+    }
+    pub fn mt_delete_return<'t, 'g, 'v, Q>(
+        &'t self,
+        key: &Q,
+        g: &'g Guard,
+    ) -> Option<&'v RowDataLck>
+    where
+        Q: ?Sized + RadixKey + Comparable<PrimaryIndexKey>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        match self {
+            Self::Hash(idx) => idx.mt_delete_return(key, g),
+            Self::Radix(idx) => idx.mt_delete_return(key, g),
+        }
+    }
+    pub fn mt_delete_return_entry<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v Row>
+    where
+        Q: ?Sized + RadixKey + Comparable<PrimaryIndexKey>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        match self {
+            Self::Hash(idx) => idx.mt_delete_return_entry(key, g),
+            Self::Radix(idx) => idx.mt_delete_return_entry(key, g),
+        }
+    }
+    pub fn mt_iter_entry<'g>(&'g self, g: &'g Guard) -> RowIter<'g> {
+        match self {
+            Self::Hash(idx) => RowIter::Hash(Box::new(idx.mt_iter_entry(g))),
+            Self::Radix(idx) => RowIter::Radix(idx.mt_iter_entry(g)),
+        }
+    }
+    pub fn mt_iter_kv<'g>(
+        &'g self,
+        g: &'g Guard,
+    ) -> impl Iterator<Item = (&'g PrimaryIndexKey, &'g RowDataLck)> {
+        self.mt_iter_entry(g).map(|row| (row.d_key(), row.d_data()))
+    }
+}
+
+type HashRowIter<'g> =
+    <IndexMTRaw<Row> as MTIndexExt<Row, PrimaryIndexKey, RowDataLck>>::IterEntry<'g, 'g, 'g>;
+
+/// An iterator over the rows of a [`RawPrimaryIndex`]. The order is meaningless for the hash index, and the key order
+/// for the radix index
+pub enum RowIter<'g> {
+    // boxed, since the hash index iterator keeps its whole traversal stack inline
+    Hash(Box<HashRowIter<'g>>),
+    Radix(mtart::IterEntry<'g, 'g, 'g, Row>),
+}
+
+impl<'g> Iterator for RowIter<'g> {
+    type Item = &'g Row;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Hash(it) => it.next(),
+            Self::Radix(it) => it.next(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct IndexLatchHandleShared<'t>(parking_lot::RwLockReadGuard<'t, ()>);
 #[derive(Debug)]
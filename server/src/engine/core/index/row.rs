@@ -28,13 +28,13 @@ use {
     super::key::PrimaryIndexKey,
     crate::{
         engine::{
-            core::model::{DeltaState, DeltaVersion, SchemaDeltaKind},
+            core::model::{delta::ChangedFields, DeltaState, DeltaVersion, SchemaDeltaKind},
             data::cell::Datacell,
             idx::{meta::hash::HasherNativeFx, mtchm::meta::TreeElement, IndexST, STIndex},
             mem::RawStr,
             sync::smart::RawRC,
         },
-        util::compiler,
+        util::{compiler, os},
     },
     parking_lot::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard},
     std::mem::ManuallyDrop,
@@ -53,6 +53,20 @@ pub struct RowData {
     fields: DcFieldIndex,
     txn_revised_data: DeltaVersion,
     txn_revised_schema_version: DeltaVersion,
+    dirty: DirtyFields,
+    /// when the row was last written (in seconds since the epoch)
+    written_at: u64,
+}
+
+/// The fields of a row that were changed since the row was last written to disk
+#[derive(Debug, PartialEq)]
+enum DirtyFields {
+    /// nothing changed since the last write
+    Clean,
+    /// only these fields changed
+    Fields(ChangedFields),
+    /// the whole row has to be written out (either it's a new row or the schema changed in between)
+    All,
 }
 
 impl RowData {
@@ -68,6 +82,54 @@ impl RowData {
     pub fn get_txn_revised(&self) -> DeltaVersion {
         self.txn_revised_data
     }
+    /// When the row was last written (in seconds since the epoch)
+    pub fn written_at(&self) -> u64 {
+        self.written_at
+    }
+    pub fn set_written_at(&mut self, written_at: u64) {
+        self.written_at = written_at;
+    }
+    /// Mark the row as written right now
+    pub fn touch(&mut self) {
+        self.written_at = (os::get_epoch_time() / 1_000_000_000) as u64;
+    }
+    /// Mark the fields at the given positions (as of `schema_version`) as dirty. Returns every field that changed
+    /// since the row was last written out, or `None` if the full row needs to be written out
+    pub fn mark_dirty(
+        &mut self,
+        schema_version: DeltaVersion,
+        positions: Vec<usize>,
+    ) -> Option<ChangedFields> {
+        match &mut self.dirty {
+            DirtyFields::Clean => {
+                self.dirty = DirtyFields::Fields(ChangedFields::new(schema_version, positions))
+            }
+            DirtyFields::Fields(changed) if changed.schema_version() == schema_version => {
+                changed.extend(positions)
+            }
+            // the positions that we have are for an older schema, so we can't merge them
+            DirtyFields::Fields(_) => self.dirty = DirtyFields::All,
+            DirtyFields::All => {}
+        }
+        match &self.dirty {
+            DirtyFields::Fields(changed) => Some(changed.clone()),
+            _ => None,
+        }
+    }
+    /// Replace every field in this row with the given data (which must be for `schema_version`). The whole row will
+    /// have to be written out
+    pub fn replace_fields(&mut self, fields: DcFieldIndex, schema_version: DeltaVersion) {
+        self.fields = fields;
+        self.txn_revised_schema_version = schema_version;
+        self.dirty = DirtyFields::All;
+        self.touch();
+    }
+    /// Mark the row as clean if the given version was the last one to change it (i.e it was the one written out)
+    pub fn mark_clean(&mut self, version: DeltaVersion) {
+        if self.txn_revised_data == version {
+            self.dirty = DirtyFields::Clean;
+        }
+    }
 }
 
 impl TreeElement for Row {
@@ -100,13 +162,38 @@ impl Row {
         schema_version: DeltaVersion,
         txn_revised_data: DeltaVersion,
     ) -> Self {
-        Self::new_restored(pk, data, schema_version, txn_revised_data)
+        Self::new_with_dirty(
+            pk,
+            data,
+            schema_version,
+            txn_revised_data,
+            DirtyFields::All,
+            (os::get_epoch_time() / 1_000_000_000) as u64,
+        )
     }
     pub fn new_restored(
         pk: PrimaryIndexKey,
         data: DcFieldIndex,
         schema_version: DeltaVersion,
         txn_revised_data: DeltaVersion,
+        written_at: u64,
+    ) -> Self {
+        Self::new_with_dirty(
+            pk,
+            data,
+            schema_version,
+            txn_revised_data,
+            DirtyFields::Clean,
+            written_at,
+        )
+    }
+    fn new_with_dirty(
+        pk: PrimaryIndexKey,
+        data: DcFieldIndex,
+        schema_version: DeltaVersion,
+        txn_revised_data: DeltaVersion,
+        dirty: DirtyFields,
+        written_at: u64,
     ) -> Self {
         Self {
             __pk: ManuallyDrop::new(pk),
@@ -116,6 +203,8 @@ impl Row {
                     fields: data,
                     txn_revised_schema_version: schema_version,
                     txn_revised_data,
+                    dirty,
+                    written_at,
                 }))
             },
         }
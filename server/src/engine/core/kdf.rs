@@ -0,0 +1,172 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    password hashing
+    ---
+    Hashes are self-describing: argon2 hashes are stored as PHC strings (`$argon2id$...`) while
+    anything else is a (compact) bcrypt hash created by older versions. So we can always verify a
+    password irrespective of the KDF that is currently configured, and then upgrade the hash if it
+    doesn't match the configured KDF.
+*/
+
+use {
+    crate::engine::config::ConfigKdf,
+    argon2::{
+        password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString},
+        Algorithm, Argon2, Params, PasswordVerifier, Version,
+    },
+    std::sync::OnceLock,
+};
+
+const ARGON2_PREFIX: &[u8] = b"$argon2";
+
+static KDF: OnceLock<ConfigKdf> = OnceLock::new();
+
+/// Set the KDF used for new password hashes. Only the first call has any effect
+pub fn set_kdf(kdf: ConfigKdf) {
+    let _ = KDF.set(kdf);
+}
+
+fn kdf() -> ConfigKdf {
+    KDF.get().copied().unwrap_or_default()
+}
+
+fn argon2(memory: u32, iterations: u32, parallelism: u32) -> Argon2<'static> {
+    // params were validated when loading the configuration
+    let params = Params::new(memory, iterations, parallelism, None).unwrap();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// Hash the password using the configured KDF
+pub fn hash(password: impl AsRef<[u8]>) -> Box<[u8]> {
+    hash_with(kdf(), password.as_ref())
+}
+
+fn hash_with(kdf: ConfigKdf, password: &[u8]) -> Box<[u8]> {
+    match kdf {
+        ConfigKdf::Bcrypt => rcrypt::hash(password, rcrypt::DEFAULT_COST)
+            .unwrap()
+            .into_boxed_slice(),
+        ConfigKdf::Argon2id {
+            memory,
+            iterations,
+            parallelism,
+        } => {
+            let salt = SaltString::generate(&mut OsRng);
+            argon2(memory, iterations, parallelism)
+                .hash_password(password, &salt)
+                .unwrap()
+                .to_string()
+                .into_bytes()
+                .into_boxed_slice()
+        }
+    }
+}
+
+/// Verify the password against the hash (created with any supported KDF)
+pub fn verify(password: &[u8], hash: &[u8]) -> bool {
+    if hash.starts_with(ARGON2_PREFIX) {
+        let Some(hash) = core::str::from_utf8(hash)
+            .ok()
+            .and_then(|h| PasswordHash::new(h).ok())
+        else {
+            return false;
+        };
+        // the params are read from the hash itself
+        Argon2::default().verify_password(password, &hash).is_ok()
+    } else {
+        rcrypt::verify(password, hash).unwrap_or(false)
+    }
+}
+
+/// Returns true if the hash was not created by the configured KDF (or with the configured parameters)
+pub fn needs_rehash(hash: &[u8]) -> bool {
+    needs_rehash_with(kdf(), hash)
+}
+
+fn needs_rehash_with(kdf: ConfigKdf, hash: &[u8]) -> bool {
+    let is_argon2 = hash.starts_with(ARGON2_PREFIX);
+    match kdf {
+        ConfigKdf::Bcrypt => is_argon2,
+        ConfigKdf::Argon2id {
+            memory,
+            iterations,
+            parallelism,
+        } => {
+            if !is_argon2 {
+                return true;
+            }
+            let params = core::str::from_utf8(hash)
+                .ok()
+                .and_then(|h| PasswordHash::new(h).ok())
+                .and_then(|h| Params::try_from(&h).ok());
+            match params {
+                Some(p) => {
+                    (p.m_cost(), p.t_cost(), p.p_cost()) != (memory, iterations, parallelism)
+                }
+                None => true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARGON2_SMALL: ConfigKdf = ConfigKdf::Argon2id {
+        memory: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn hash_verify() {
+        for kdf in [ConfigKdf::Bcrypt, ARGON2_SMALL] {
+            let h = hash_with(kdf, b"password12345678");
+            assert!(verify(b"password12345678", &h));
+            assert!(!verify(b"password12345679", &h));
+            assert!(!needs_rehash_with(kdf, &h));
+        }
+    }
+    #[test]
+    fn rehash_on_kdf_change() {
+        let bcrypt = hash_with(ConfigKdf::Bcrypt, b"password12345678");
+        let argon = hash_with(ARGON2_SMALL, b"password12345678");
+        assert!(needs_rehash_with(ARGON2_SMALL, &bcrypt));
+        assert!(needs_rehash_with(ConfigKdf::Bcrypt, &argon));
+        // same KDF, different params
+        assert!(needs_rehash_with(
+            ConfigKdf::Argon2id {
+                memory: 128,
+                iterations: 1,
+                parallelism: 1
+            },
+            &argon
+        ));
+    }
+}
@@ -24,15 +24,25 @@
  *
 */
 
+pub(in crate::engine) mod cancel;
 pub(in crate::engine) mod dcl;
 mod ddl_misc;
 pub(in crate::engine) mod dml;
 pub(in crate::engine) mod exec;
+pub(in crate::engine) mod import;
 pub(in crate::engine) mod index;
+pub(in crate::engine) mod kdf;
 pub(in crate::engine) mod model;
+pub(in crate::engine) mod query_mem;
 pub(in crate::engine) mod query_meta;
+pub(in crate::engine) mod quota;
+pub(in crate::engine) mod row_size;
+pub(in crate::engine) mod scratch;
 pub(in crate::engine) mod space;
 pub(in crate::engine) mod system_db;
+pub(in crate::engine) mod task;
+pub(in crate::engine) mod token;
+pub(in crate::engine) mod trace;
 // util
 mod util;
 // test
@@ -88,6 +98,7 @@ pub struct GNSData {
     idx_mdl: RWLIdx<EntityID, Model>,
     idx: RWLIdx<Box<str>, Space>,
     sys_db: system_db::SystemDatabase,
+    tasks: task::TaskRegistry,
 }
 
 impl GNSData {
@@ -96,6 +107,7 @@ impl GNSData {
             idx_mdl: RWLIdx::default(),
             idx: RWLIdx::default(),
             sys_db: system_db::SystemDatabase::empty(),
+            tasks: task::TaskRegistry::new(),
         }
     }
     pub fn ddl_with_all_mut<T>(
@@ -169,6 +181,9 @@ impl GNSData {
     pub fn sys_db(&self) -> &system_db::SystemDatabase {
         &self.sys_db
     }
+    pub fn tasks(&self) -> &task::TaskRegistry {
+        &self.tasks
+    }
 }
 
 pub(self) fn with_model_for_data_update<'a, F>(
@@ -184,6 +199,16 @@ where
         return Err(QueryError::QExecObjectNotFound);
     };
     if compiler::likely(model.driver().status().is_healthy()) {
+        match model::Backpressure::compute(
+            model.data().delta_state().unflushed(),
+            global.get_max_delta_size(),
+        ) {
+            model::Backpressure::None => {}
+            model::Backpressure::Delay(delay) => model::Backpressure::defer(delay),
+            model::Backpressure::Reject => {
+                return compiler::cold_call(|| Err(QueryError::SysWriteBackpressure))
+            }
+        }
         let r = f(model.data())?;
         model::DeltaState::guard_delta_overflow(
             global,
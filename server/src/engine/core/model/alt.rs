@@ -210,9 +210,8 @@ impl<'a> AlterPlan<'a> {
         while (zipped_layers.len() != 0) & okay {
             let ((LayerSpec { ty, props }, current_layer), new_layer) =
                 zipped_layers.next().unwrap();
-            // actually parse the new layer
-            okay &= props.is_empty();
-            let Some(new_parsed_layer) = Layer::get_layer(&ty) else {
+            // actually parse the new layer (the bounds of a list are replaced by the ones in the new definition)
+            let Some(new_parsed_layer) = Layer::parse(&ty, props) else {
                 return Err(QueryError::QExecDdlInvalidTypeDefinition);
             };
             match (
@@ -233,8 +232,11 @@ impl<'a> AlterPlan<'a> {
                     return Err(QueryError::QExecDdlInvalidTypeDefinition);
                 }
             }
+            // new bounds only apply to later writes, so the existing lists are left as they are
+            deltasize += (new_parsed_layer.bounds != current_layer.bounds) as usize;
             *new_layer = new_parsed_layer;
         }
+        okay &= Field::bounds_okay(new_field.layers());
         *super_nlck &= no_lock;
         *super_okay &= okay;
         if okay {
@@ -288,17 +290,44 @@ impl ModelData {
                             });
                     }
                     AlterAction::Remove(removed) => {
+                        // the indexes on the removed fields are dropped along with them
+                        let dropped_indexes: Vec<Box<str>> = model
+                            .secondary_indexes()
+                            .read()
+                            .iter()
+                            .filter(|index| {
+                                removed.iter().any(|field| field.as_str() == index.field())
+                            })
+                            .map(|index| index.name().into())
+                            .collect();
                         // prepare txn
-                        let txn = gns::model::AlterModelRemoveTxn::new(
-                            ModelIDRef::new_ref(&space_name, space, &model_name, model),
-                            &removed,
-                        );
+                        let model_id = ModelIDRef::new_ref(space_name, space, model_name, model);
+                        let txn = gns::model::AlterModelRemoveTxn::new(model_id, &removed);
                         // commit txn
-                        global.state().gns_driver().driver_context(
-                            global,
-                            |drv| drv.commit_event(txn),
-                            || {},
-                        )?;
+                        if dropped_indexes.is_empty() {
+                            global.state().gns_driver().driver_context(
+                                global,
+                                |drv| drv.commit_event(txn),
+                                || {},
+                            )?;
+                        } else {
+                            // a crash must not leave an index on a field that no longer exists (or the other way
+                            // around), so the index drops and the field removal go into a single record
+                            let mut batch = gns::BatchEvents::new();
+                            for index in dropped_indexes.iter() {
+                                batch.push(gns::model::DropIndexTxn::new(model_id, index));
+                            }
+                            batch.push(txn);
+                            let txn = gns::BatchTxn::new(&batch);
+                            global.state().gns_driver().driver_context(
+                                global,
+                                |drv| drv.commit_event(txn),
+                                || {},
+                            )?;
+                        }
+                        for index in dropped_indexes.iter() {
+                            model.__raw_drop_index(index);
+                        }
                         let mut mutator = model.model_mutator();
                         removed.iter().for_each(|field_id| {
                             mutator.remove_field(field_id.as_str());
@@ -322,6 +351,8 @@ impl ModelData {
                         });
                     }
                 }
+                model.columnar_cache_rebuild();
+                model.secondary_indexes_rebuild();
                 Ok(())
             })
     }
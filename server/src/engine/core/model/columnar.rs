@@ -0,0 +1,239 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    columnar scan cache
+    ---
+    an opt-in, in-memory copy of the numeric fields of a model, laid out as one contiguous array per field. scans with
+    filters can run their comparisons on these arrays instead of going through every row. every insert, update and
+    delete on the model keeps the cache up to date, so this makes writes a little more expensive.
+
+    to stay out of deadlocks, locks are always taken in this order: the index latch, the cache lock and then the row
+    lock. DML updates the cache after it is done with the row and reads the row again while holding the cache lock,
+    so the cache always ends up with the latest value for a row no matter in which order concurrent writers get to it.
+
+    the cache is not persisted and has to be enabled again after a restart
+*/
+
+use {
+    super::ModelData,
+    crate::engine::{
+        core::index::{PrimaryIndexKey, Row, RowData},
+        data::{
+            cell::Datacell,
+            tag::{DataTag, TagClass},
+        },
+        idx::{STIndex, STIndexSeq},
+        sync::atm::Guard,
+    },
+    std::collections::HashMap,
+};
+
+#[derive(Debug)]
+/// The values of a cached column
+pub enum ColumnValues {
+    UInt(Vec<u64>),
+    SInt(Vec<i64>),
+    Float(Vec<f64>),
+}
+
+impl ColumnValues {
+    fn push(&mut self, dc: Option<&Datacell>, pk: Option<&PrimaryIndexKey>) -> bool {
+        match self {
+            Self::UInt(v) => Self::_push(
+                v,
+                dc.and_then(Datacell::try_uint)
+                    .or(pk.and_then(|pk| pk.uint())),
+            ),
+            Self::SInt(v) => Self::_push(
+                v,
+                dc.and_then(Datacell::try_sint)
+                    .or(pk.and_then(|pk| pk.sint())),
+            ),
+            Self::Float(v) => Self::_push(v, dc.and_then(Datacell::try_float)),
+        }
+    }
+    fn set(&mut self, slot: usize, dc: Option<&Datacell>, pk: Option<&PrimaryIndexKey>) -> bool {
+        match self {
+            Self::UInt(v) => Self::_set(
+                v,
+                slot,
+                dc.and_then(Datacell::try_uint)
+                    .or(pk.and_then(|pk| pk.uint())),
+            ),
+            Self::SInt(v) => Self::_set(
+                v,
+                slot,
+                dc.and_then(Datacell::try_sint)
+                    .or(pk.and_then(|pk| pk.sint())),
+            ),
+            Self::Float(v) => Self::_set(v, slot, dc.and_then(Datacell::try_float)),
+        }
+    }
+    fn swap_remove(&mut self, slot: usize) {
+        match self {
+            Self::UInt(v) => {
+                v.swap_remove(slot);
+            }
+            Self::SInt(v) => {
+                v.swap_remove(slot);
+            }
+            Self::Float(v) => {
+                v.swap_remove(slot);
+            }
+        }
+    }
+    fn _push<T: Default>(v: &mut Vec<T>, value: Option<T>) -> bool {
+        let valid = value.is_some();
+        v.push(value.unwrap_or_default());
+        valid
+    }
+    fn _set<T: Default>(v: &mut [T], slot: usize, value: Option<T>) -> bool {
+        let valid = value.is_some();
+        v[slot] = value.unwrap_or_default();
+        valid
+    }
+}
+
+#[derive(Debug)]
+/// A cached column. A null value has its validity byte set to zero
+pub struct CachedColumn {
+    field: Box<str>,
+    pk: bool,
+    values: ColumnValues,
+    valid: Vec<u8>,
+}
+
+impl CachedColumn {
+    pub fn values(&self) -> &ColumnValues {
+        &self.values
+    }
+    pub fn valid(&self) -> &[u8] {
+        &self.valid
+    }
+    /// The cell for this column (the primary key isn't in the row data, so this is `None` for it)
+    fn cell<'a>(&self, row: &'a RowData) -> Option<&'a Datacell> {
+        if self.pk {
+            None
+        } else {
+            row.fields().st_get(self.field.as_ref())
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A columnar copy of the numeric fields of a model
+pub struct ColumnarCache {
+    slots: HashMap<PrimaryIndexKey, usize>,
+    keys: Vec<PrimaryIndexKey>,
+    columns: Vec<CachedColumn>,
+}
+
+impl ColumnarCache {
+    /// Build the cache from the rows in the model. The caller must hold the index latch exclusively. Returns `None` if
+    /// the model doesn't have any numeric fields
+    pub fn build(mdl: &ModelData, g: &Guard) -> Option<Self> {
+        let columns: Vec<CachedColumn> = mdl
+            .fields()
+            .stseq_ord_kv()
+            .filter(|(_, field)| field.layers().len() == 1)
+            .filter_map(|(field_id, field)| {
+                let values = match field.layers()[0].tag().tag_class() {
+                    TagClass::UnsignedInt => ColumnValues::UInt(vec![]),
+                    TagClass::SignedInt => ColumnValues::SInt(vec![]),
+                    TagClass::Float => ColumnValues::Float(vec![]),
+                    _ => return None,
+                };
+                Some(CachedColumn {
+                    field: field_id.as_str().into(),
+                    pk: field_id.as_str() == mdl.p_key(),
+                    values,
+                    valid: vec![],
+                })
+            })
+            .collect();
+        if columns.is_empty() {
+            return None;
+        }
+        let mut slf = Self {
+            slots: HashMap::with_capacity(mdl.primary_index().count()),
+            keys: Vec::with_capacity(mdl.primary_index().count()),
+            columns,
+        };
+        for row in mdl.primary_index().__raw_index().mt_iter_entry(g) {
+            slf.upsert(mdl, row);
+        }
+        Some(slf)
+    }
+    /// The number of cached rows
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn keys(&self) -> &[PrimaryIndexKey] {
+        &self.keys
+    }
+    pub fn column(&self, field: &str) -> Option<&CachedColumn> {
+        self.columns.iter().find(|col| col.field.as_ref() == field)
+    }
+    /// Add or refresh a row (this reads the row, so the caller must not be holding the row lock)
+    pub fn upsert(&mut self, mdl: &ModelData, row: &Row) {
+        let data = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+        let key = row.d_key();
+        match self.slots.get(key) {
+            Some(&slot) => {
+                for column in self.columns.iter_mut() {
+                    let (dc, pk) = (column.cell(&data), column.pk.then_some(key));
+                    let valid = column.values.set(slot, dc, pk);
+                    column.valid[slot] = valid as u8;
+                }
+            }
+            None => {
+                self.slots.insert(key.clone(), self.keys.len());
+                self.keys.push(key.clone());
+                for column in self.columns.iter_mut() {
+                    let (dc, pk) = (column.cell(&data), column.pk.then_some(key));
+                    let valid = column.values.push(dc, pk);
+                    column.valid.push(valid as u8);
+                }
+            }
+        }
+    }
+    /// Remove a row (if it was cached)
+    pub fn remove(&mut self, key: &PrimaryIndexKey) {
+        let Some(slot) = self.slots.remove(key) else {
+            return;
+        };
+        self.keys.swap_remove(slot);
+        for column in self.columns.iter_mut() {
+            column.values.swap_remove(slot);
+            column.valid.swap_remove(slot);
+        }
+        // the last row now lives in the slot that was freed up
+        if let Some(moved) = self.keys.get(slot) {
+            *self.slots.get_mut(moved).unwrap() = slot;
+        }
+    }
+}
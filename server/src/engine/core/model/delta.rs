@@ -25,7 +25,7 @@
 */
 
 use {
-    super::ModelData,
+    super::{watermark, ModelData},
     crate::engine::{
         core::{dml::QueryExecMeta, index::Row},
         fractal::{FractalToken, GlobalInstanceLike},
@@ -34,11 +34,89 @@ use {
         sync::queue::Queue,
     },
     std::{
+        cell::Cell,
         collections::btree_map::{BTreeMap, Range},
-        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+        mem,
+        sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        time::Duration,
     },
 };
 
+/// If set, update deltas keep track of the fields that they changed so that only those are written to disk
+static DELTA_BATCHES: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable delta encoded (partial) updates in data batches
+pub fn set_delta_batches(enabled: bool) {
+    DELTA_BATCHES.store(enabled, Ordering::Release)
+}
+
+pub fn delta_batches() -> bool {
+    DELTA_BATCHES.load(Ordering::Acquire)
+}
+
+/*
+    write backpressure
+    ---
+    if the flusher can't keep up, deltas keep piling up in memory. once a model has more than `BACKPRESSURE_DELAY_FACTOR`
+    times the flush threshold of unflushed deltas, writes to it are slowed down (the longer the queue, the longer the delay)
+    and after `BACKPRESSURE_REJECT_FACTOR` times the flush threshold they are rejected with `SysWriteBackpressure` which
+    the client can retry
+*/
+
+/// Writes are delayed once the unflushed deltas for a model exceed these many times the flush threshold
+const BACKPRESSURE_DELAY_FACTOR: usize = 2;
+/// Writes are rejected once the unflushed deltas for a model exceed these many times the flush threshold
+const BACKPRESSURE_REJECT_FACTOR: usize = 8;
+/// The longest we will delay a single write for
+const BACKPRESSURE_MAX_DELAY: Duration = Duration::from_millis(100);
+
+thread_local! {
+    /// The delay for the write that was just executed on this thread
+    static BACKPRESSURE_DELAY: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The backpressure applied on writes to a model
+pub enum Backpressure {
+    /// the flusher is keeping up
+    None,
+    /// the flusher is falling behind, so slow down writes
+    Delay(Duration),
+    /// the flusher is way behind, so reject writes
+    Reject,
+}
+
+impl Backpressure {
+    pub fn compute(unflushed: usize, flush_threshold: usize) -> Self {
+        let delay_mark = flush_threshold.saturating_mul(BACKPRESSURE_DELAY_FACTOR);
+        let reject_mark = flush_threshold.saturating_mul(BACKPRESSURE_REJECT_FACTOR);
+        if unflushed <= delay_mark {
+            Self::None
+        } else if unflushed >= reject_mark {
+            Self::Reject
+        } else {
+            // scale up the delay as we get closer to the reject mark
+            let over = (unflushed - delay_mark) as f64 / (reject_mark - delay_mark) as f64;
+            Self::Delay(BACKPRESSURE_MAX_DELAY.mul_f64(over))
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Delay(_) => "delay",
+            Self::Reject => "reject",
+        }
+    }
+    /// Record a delay for the write that is running on this thread
+    pub fn defer(delay: Duration) {
+        BACKPRESSURE_DELAY.with(|d| d.set(Some(delay)))
+    }
+    /// Take the delay (if any) recorded for the last write on this thread
+    pub fn take_deferred() -> Option<Duration> {
+        BACKPRESSURE_DELAY.with(|d| d.take())
+    }
+}
+
 #[derive(Debug)]
 /// A delta state for the model
 pub struct DeltaState {
@@ -49,6 +127,18 @@ pub struct DeltaState {
     data_current_version: AtomicU64,
     data_deltas: Queue<DataDelta>,
     data_deltas_size: AtomicUsize,
+    // number of data deltas that are yet to be written to disk
+    data_unflushed: AtomicUsize,
+    // number of data events (live or dead) in the data file
+    data_persisted_events: AtomicUsize,
+    // data deltas older than this version were discarded by a truncate
+    data_truncated_at: AtomicU64,
+    // one more than the version of the latest data delta written to the data file (zero if none)
+    data_written_version: AtomicU64,
+    // same as above, but only once the data file was synced
+    data_committed_version: AtomicU64,
+    // the (global) clock when the committed version last advanced
+    data_committed_clock: AtomicU64,
 }
 
 impl DeltaState {
@@ -60,6 +150,12 @@ impl DeltaState {
             data_current_version: AtomicU64::new(0),
             data_deltas: Queue::new(),
             data_deltas_size: AtomicUsize::new(0),
+            data_unflushed: AtomicUsize::new(0),
+            data_persisted_events: AtomicUsize::new(0),
+            data_truncated_at: AtomicU64::new(0),
+            data_written_version: AtomicU64::new(0),
+            data_committed_version: AtomicU64::new(0),
+            data_committed_clock: AtomicU64::new(0),
         }
     }
     pub fn __set_delta_version(&self, version: DeltaVersion) {
@@ -94,6 +190,7 @@ impl DeltaState {
     }
     pub fn append_new_data_delta(&self, delta: DataDelta, g: &Guard) -> usize {
         self.data_deltas.blocking_enqueue(delta, g);
+        self.data_unflushed.fetch_add(1, Ordering::Release);
         self.data_deltas_size.fetch_add(1, Ordering::Release) + 1
     }
     pub fn create_new_data_delta_version(&self) -> DeltaVersion {
@@ -106,7 +203,27 @@ impl DeltaState {
         self.data_current_version.fetch_add(1, Ordering::AcqRel)
     }
     pub fn __data_delta_dequeue(&self, g: &Guard) -> Option<DataDelta> {
-        self.data_deltas.blocking_try_dequeue(g)
+        let delta = self.data_deltas.blocking_try_dequeue(g);
+        if delta.is_some() {
+            self.data_unflushed.fetch_sub(1, Ordering::Release);
+        }
+        delta
+    }
+    /// Returns the number of data deltas that are yet to be written to disk
+    pub fn unflushed(&self) -> usize {
+        self.data_unflushed.load(Ordering::Acquire)
+    }
+    /// Discard every data delta created so far. The deltas stay in the queue (since the flusher might have already
+    /// observed them) but they are skipped when written out
+    ///
+    /// NB: the caller must ensure that no new deltas are being created meanwhile
+    pub fn truncate_data_deltas(&self) {
+        let version = self.__data_delta_step();
+        self.data_truncated_at.store(version, Ordering::Release)
+    }
+    /// Returns true if this delta was discarded by a truncate
+    pub fn is_truncated(&self, delta: &DataDelta) -> bool {
+        delta.data_version().value_u64() < self.data_truncated_at.load(Ordering::Acquire)
     }
 }
 
@@ -147,6 +264,48 @@ impl DeltaState {
     pub fn __fractal_take_full_from_data_delta(&self, _token: FractalToken) -> usize {
         self.data_deltas_size.swap(0, Ordering::AcqRel)
     }
+    /// Returns the number of data events in the data file (including dead ones)
+    pub fn persisted_events(&self) -> usize {
+        self.data_persisted_events.load(Ordering::Acquire)
+    }
+    pub fn __record_persisted_events(&self, count: usize) {
+        self.data_persisted_events
+            .fetch_add(count, Ordering::AcqRel);
+    }
+    pub fn __reset_persisted_events(&self, count: usize) {
+        self.data_persisted_events.store(count, Ordering::Release)
+    }
+    /// Record that the data delta with this version was written to the data file (but it might not be synced yet)
+    pub fn __record_written(&self, version: DeltaVersion) {
+        self.data_written_version
+            .fetch_max(version.value_u64() + 1, Ordering::AcqRel);
+    }
+    /// Record that a data delta with this version was restored from the data file (so it is committed)
+    pub fn __record_restored(&self, version: DeltaVersion) {
+        self.__record_written(version);
+        self.data_committed_version
+            .fetch_max(version.value_u64() + 1, Ordering::AcqRel);
+    }
+    /// Everything written to the data file so far was synced, so advance the committed watermark
+    ///
+    /// NB: the caller must hold the batch driver's lock
+    pub fn __commit_written(&self) {
+        let written = self.data_written_version.load(Ordering::Acquire);
+        if written > self.data_committed_version.load(Ordering::Acquire) {
+            self.data_committed_clock
+                .store(watermark::tick(), Ordering::Release);
+            self.data_committed_version
+                .store(written, Ordering::Release);
+        }
+    }
+    /// Returns the version of the latest data delta that is durably on disk (if any) along with the clock when it was
+    /// committed (zero if it was restored from disk)
+    pub fn committed_watermark(&self) -> (Option<u64>, u64) {
+        // the clock is stored before the version, so we never see a newer version with an older clock
+        let version = self.data_committed_version.load(Ordering::Acquire);
+        let clock = self.data_committed_clock.load(Ordering::Acquire);
+        (version.checked_sub(1), clock)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -208,6 +367,7 @@ pub struct DataDelta {
     data_version: DeltaVersion,
     row: Row,
     change: DataDeltaKind,
+    changed_fields: Option<ChangedFields>,
 }
 
 impl DataDelta {
@@ -216,8 +376,26 @@ impl DataDelta {
             data_version,
             row,
             change,
+            changed_fields: None,
+        }
+    }
+    /// An update that only changed the given fields
+    pub const fn new_partial_update(
+        data_version: DeltaVersion,
+        row: Row,
+        changed_fields: ChangedFields,
+    ) -> Self {
+        Self {
+            data_version,
+            row,
+            change: DataDeltaKind::Update,
+            changed_fields: Some(changed_fields),
         }
     }
+    /// Returns the fields changed by this update, if they were tracked
+    pub fn changed_fields(&self) -> Option<&ChangedFields> {
+        self.changed_fields.as_ref()
+    }
     pub fn data_version(&self) -> DeltaVersion {
         self.data_version
     }
@@ -229,6 +407,40 @@ impl DataDelta {
     }
 }
 
+/// The fields changed by an update, as positions in the model's field order (skipping the primary key)
+///
+/// NB: the positions are only valid for the schema version that they were recorded at
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedFields {
+    schema_version: DeltaVersion,
+    fields: Box<[usize]>,
+}
+
+impl ChangedFields {
+    pub fn new(schema_version: DeltaVersion, mut fields: Vec<usize>) -> Self {
+        fields.sort_unstable();
+        fields.dedup();
+        Self {
+            schema_version,
+            fields: fields.into_boxed_slice(),
+        }
+    }
+    /// Add more fields (that were recorded at the same schema version)
+    pub fn extend(&mut self, fields: Vec<usize>) {
+        let mut merged = mem::take(&mut self.fields).into_vec();
+        merged.extend(fields);
+        merged.sort_unstable();
+        merged.dedup();
+        self.fields = merged.into_boxed_slice();
+    }
+    pub fn schema_version(&self) -> DeltaVersion {
+        self.schema_version
+    }
+    pub fn fields(&self) -> &[usize] {
+        &self.fields
+    }
+}
+
 #[derive(Debug, Clone, Copy, sky_macros::EnumMethods, PartialEq)]
 #[repr(u8)]
 pub enum DataDeltaKind {
@@ -236,3 +448,19 @@ pub enum DataDeltaKind {
     Insert = 1,
     Update = 2,
 }
+
+#[test]
+fn backpressure_marks() {
+    assert_eq!(Backpressure::compute(0, 100), Backpressure::None);
+    assert_eq!(Backpressure::compute(200, 100), Backpressure::None);
+    assert_eq!(
+        Backpressure::compute(500, 100),
+        Backpressure::Delay(BACKPRESSURE_MAX_DELAY / 2)
+    );
+    assert_eq!(Backpressure::compute(800, 100), Backpressure::Reject);
+    // the test global never flushes on its own
+    assert_eq!(
+        Backpressure::compute(usize::MAX, usize::MAX),
+        Backpressure::None
+    );
+}
@@ -0,0 +1,188 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+/*
+    snapshot masking
+    ---
+    a snapshot can be anonymized with a masking profile so that a copy of production data can be handed out safely:
+    `create model a.b as snapshot of a.c with { mask: { email: 'hash', name: 'token', age: 'zero' } }`. the values
+    are masked as they're copied, so the masked values never hit the disk:
+    - `hash`: a string or binary is replaced with its HMAC (SHA256; hex encoded for strings). the key is generated for
+    every snapshot, so equal values remain equal within a snapshot but can't be matched against another one
+    - `token`: a string is replaced with `token_<n>`, where `n` is assigned in the order that distinct values are seen
+    - `null`: the value is replaced with a null (the field must be nullable)
+    - `zero`: the value is replaced with the zero value of its type (`false`, `0`, `''` and so on)
+    since masked values must still be unique, only `hash` and `token` can be used on the primary key
+*/
+
+use {
+    super::{Field, ModelData},
+    crate::engine::{
+        core::token::hex,
+        data::{
+            cell::Datacell,
+            dict::{DictEntryGeneric, DictGeneric},
+            tag::{DataTag, TagClass},
+        },
+        error::{QueryError, QueryResult},
+        idx::STIndex,
+    },
+    openssl::{hash::MessageDigest, pkey::PKey, rand, sign::Signer},
+    std::collections::{BTreeMap, HashMap},
+};
+
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MaskKind {
+    Hash,
+    Token,
+    Null,
+    Zero,
+}
+
+impl MaskKind {
+    fn new(kind: &str, field: &Field, is_pk: bool) -> Option<Self> {
+        let class = field.layers()[0].tag().tag_class();
+        let (kind, okay) = match kind {
+            "hash" => (Self::Hash, matches!(class, TagClass::Str | TagClass::Bin)),
+            "token" => (Self::Token, class == TagClass::Str),
+            "null" => (Self::Null, field.is_nullable() & !is_pk),
+            "zero" => (Self::Zero, !is_pk),
+            _ => return None,
+        };
+        if okay {
+            Some(kind)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FieldMask {
+    kind: MaskKind,
+    tokens: HashMap<Box<str>, u64>,
+}
+
+/// The masking profile of a snapshot (see the module docs)
+pub struct MaskProfile {
+    fields: HashMap<Box<str>, FieldMask>,
+    key: [u8; KEY_LEN],
+}
+
+impl core::fmt::Debug for MaskProfile {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // don't leak the key
+        f.debug_struct("MaskProfile")
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MaskProfile {
+    /// Build a masking profile for the given model from the snapshot's properties
+    pub fn new(model: &ModelData, mut props: DictGeneric) -> QueryResult<Self> {
+        let mask = props.remove("mask");
+        if !props.is_empty() {
+            return Err(QueryError::QExecDdlInvalidProperties);
+        }
+        let mask = match mask {
+            Some(DictEntryGeneric::Map(mask)) => mask,
+            None => DictGeneric::new(),
+            Some(DictEntryGeneric::Data(_)) => return Err(QueryError::QExecDdlInvalidProperties),
+        };
+        let mut fields = HashMap::with_capacity(mask.len());
+        for (field_name, kind) in mask {
+            let field = model
+                .fields()
+                .st_get(field_name.as_ref())
+                .ok_or(QueryError::QExecUnknownField)?;
+            let kind = match kind {
+                DictEntryGeneric::Data(kind) => kind
+                    .try_str()
+                    .and_then(|kind| MaskKind::new(kind, field, model.is_pk(&field_name))),
+                DictEntryGeneric::Map(_) => None,
+            }
+            .ok_or(QueryError::QExecDdlInvalidProperties)?;
+            fields.insert(
+                field_name,
+                FieldMask {
+                    kind,
+                    tokens: HashMap::new(),
+                },
+            );
+        }
+        let mut key = [0u8; KEY_LEN];
+        rand::rand_bytes(&mut key).unwrap();
+        Ok(Self { fields, key })
+    }
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+    /// Mask a value of the given field (if the field is masked)
+    pub fn apply(&mut self, field_name: &str, dc: Datacell) -> Datacell {
+        let Some(mask) = self.fields.get_mut(field_name) else {
+            return dc;
+        };
+        if dc.is_null() {
+            return dc;
+        }
+        match mask.kind {
+            MaskKind::Hash => {
+                let key = PKey::hmac(&self.key).unwrap();
+                let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+                signer
+                    .update(unsafe {
+                        // UNSAFE(@ohsayan): the profile only allows hashing strings and binaries
+                        dc.read_bin()
+                    })
+                    .unwrap();
+                let digest = signer.sign_to_vec().unwrap();
+                match dc.kind() {
+                    TagClass::Str => Datacell::new_str(hex(&digest).into_boxed_str()),
+                    _ => Datacell::new_bin(digest.into_boxed_slice()),
+                }
+            }
+            MaskKind::Token => {
+                let next = mask.tokens.len() as u64;
+                let token = *mask.tokens.entry(dc.str().into()).or_insert(next);
+                Datacell::new_str(format!("token_{token}").into_boxed_str())
+            }
+            MaskKind::Null => Datacell::null(),
+            MaskKind::Zero => match dc.kind() {
+                TagClass::Bool => Datacell::new_bool(false),
+                TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float => unsafe {
+                    // UNSAFE(@ohsayan): the tag is the same and a zeroed qword is a zero in all these classes
+                    Datacell::new_qw(0, dc.tag())
+                },
+                TagClass::Bin => Datacell::new_bin(Box::new([])),
+                TagClass::Str => Datacell::new_str("".into()),
+                TagClass::List => Datacell::new_list(vec![]),
+                TagClass::Map => Datacell::new_map(BTreeMap::new()),
+            },
+        }
+    }
+}
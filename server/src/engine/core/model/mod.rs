@@ -25,35 +25,61 @@
 */
 
 pub(super) mod alt;
+pub(in crate::engine) mod columnar;
 pub(in crate::engine) mod delta;
+mod mask;
+pub(in crate::engine) mod secondary;
+pub(in crate::engine) mod trash;
+pub(in crate::engine) mod ttl;
+pub(in crate::engine) mod unique;
+pub(in crate::engine) mod watermark;
 
 use {
-    super::index::PrimaryIndex,
+    super::{
+        dml::QueryExecMeta,
+        index::{DcFieldIndex, PrimaryIndex, PrimaryIndexKey, PrimaryIndexKind, Row},
+    },
     crate::engine::{
         data::{
-            cell::Datacell,
-            tag::{DataTag, FloatSpec, FullTag, SIntSpec, TagClass, TagSelector, UIntSpec},
+            cell::{Datacell, VirtualDatacell},
+            dict::{DictEntryGeneric, DictGeneric},
+            tag::{
+                DataTag, FloatSpec, FullTag, SIntSpec, TagClass, TagSelector, TagUnique, UIntSpec,
+            },
             uuid::Uuid,
         },
         error::{QueryError, QueryResult},
-        fractal::{FractalModelDriver, GenericTask, GlobalInstanceLike, Task},
+        fractal::{FractalModelDriver, GenericTask, GlobalInstanceLike, ModelUniqueIDRef, Task},
         idx::{self, IndexBaseSpec, IndexSTSeqCns, STIndex, STIndexSeq},
         mem::{RawStr, VInline},
         ql::ddl::{
             crt::CreateModel,
-            drop::DropModel,
+            drop::{DropModel, TruncateModel},
             syn::{FieldSpec, LayerSpec},
         },
+        storage::safe_interfaces::SCrc64,
+        sync::atm::cpin,
         txn::{gns, ModelIDRef, SpaceIDRef},
     },
+    parking_lot::RwLock,
     std::collections::hash_map::{Entry, HashMap},
 };
 
-pub(in crate::engine::core) use self::delta::{DeltaState, DeltaVersion, SchemaDeltaKind};
+pub(in crate::engine::core) use self::delta::{
+    Backpressure, DeltaState, DeltaVersion, SchemaDeltaKind,
+};
+pub(in crate::engine::core) use self::{
+    columnar::ColumnarCache, secondary::SecondaryIndex, ttl::Expiry, unique::UniqueIndex,
+};
 
+use self::{delta::DataDeltaKind, mask::MaskProfile};
 use super::util::{EntityID, EntityIDRef};
 type Fields = IndexSTSeqCns<RawStr, Field>;
 
+/// The hidden column of a non-strict model (`with { strict: false }`) that holds the ad-hoc fields of every row as a
+/// list of alternating keys and values. It isn't a valid identifier, so it can never clash with a declared field
+pub(in crate::engine) const EXTRA_FIELD: &str = "#extra";
+
 #[derive(Debug)]
 pub struct Model {
     data: ModelData,
@@ -88,6 +114,11 @@ pub struct ModelData {
     delta: DeltaState,
     private: ModelPrivate,
     decl: String,
+    columnar: RwLock<Option<ColumnarCache>>,
+    secondary: RwLock<Vec<SecondaryIndex>>,
+    unique: RwLock<Vec<UniqueIndex>>,
+    ttl: Option<u64>,
+    append_only: bool,
 }
 
 #[cfg(test)]
@@ -97,6 +128,9 @@ impl PartialEq for ModelData {
             && self.p_key == m.p_key
             && self.p_tag == m.p_tag
             && self.fields == m.fields
+            && self.data.kind() == m.data.kind()
+            && self.ttl == m.ttl
+            && self.append_only == m.append_only
     }
 }
 
@@ -132,6 +166,85 @@ impl ModelData {
     pub fn fields(&self) -> &Fields {
         &self.fields
     }
+    /// Returns false if rows can have ad-hoc fields (besides the declared ones)
+    pub fn is_strict(&self) -> bool {
+        !self.fields.st_contains(EXTRA_FIELD)
+    }
+    /// Returns true if rows can only be inserted (and never updated or deleted)
+    pub fn is_append_only(&self) -> bool {
+        self.append_only
+    }
+    /// Fails if this model is append-only. Anything that updates or deletes rows (and not just the DML statements)
+    /// must check this first. Only `truncate model` (which needs root) can remove the rows of such a model
+    pub fn check_rows_mutable(&self) -> QueryResult<()> {
+        if self.append_only {
+            return Err(QueryError::QExecDmlAppendOnly);
+        }
+        Ok(())
+    }
+    /// The columnar scan cache for this model, if it was enabled
+    pub fn columnar_cache(&self) -> &RwLock<Option<ColumnarCache>> {
+        &self.columnar
+    }
+    /// Update the columnar cache (if enabled) after a row was inserted or updated
+    pub fn columnar_cache_upsert(&self, row: &Row) {
+        if self.columnar.read().is_none() {
+            // don't make writes to every other model wait on the write lock
+            return;
+        }
+        if let Some(cache) = self.columnar.write().as_mut() {
+            cache.upsert(self, row)
+        }
+    }
+    /// Update the columnar cache (if enabled) after a row was deleted
+    pub fn columnar_cache_remove(&self, key: &PrimaryIndexKey) {
+        if self.columnar.read().is_none() {
+            return;
+        }
+        if let Some(cache) = self.columnar.write().as_mut() {
+            cache.remove(key)
+        }
+    }
+    /// Rebuild the columnar cache (if enabled), for example after the schema changed
+    fn columnar_cache_rebuild(&mut self) {
+        if self.columnar.get_mut().is_some() {
+            let g = crate::engine::sync::atm::cpin();
+            let cache = ColumnarCache::build(self, &g);
+            *self.columnar.get_mut() = cache;
+        }
+    }
+    /// The secondary indexes on this model
+    pub fn secondary_indexes(&self) -> &RwLock<Vec<SecondaryIndex>> {
+        &self.secondary
+    }
+    /// Update the secondary indexes (if any) after a row was inserted or updated
+    pub fn secondary_indexes_upsert(&self, row: &Row) {
+        if self.secondary.read().is_empty() {
+            return;
+        }
+        for index in self.secondary.write().iter_mut() {
+            index.upsert(self, row)
+        }
+    }
+    /// Update the secondary indexes (if any) after a row was deleted
+    pub fn secondary_indexes_remove(&self, key: &PrimaryIndexKey) {
+        if self.secondary.read().is_empty() {
+            return;
+        }
+        for index in self.secondary.write().iter_mut() {
+            index.remove(key)
+        }
+    }
+    /// Rebuild the secondary indexes and the unique fields (if any), for example after the rows were loaded from disk
+    pub fn secondary_indexes_rebuild(&mut self) {
+        let g = crate::engine::sync::atm::cpin();
+        let mut indexes = std::mem::take(self.secondary.get_mut());
+        for index in indexes.iter_mut() {
+            index.build(self, &g);
+        }
+        *self.secondary.get_mut() = indexes;
+        self.unique_rebuild(&g);
+    }
     pub fn model_mutator<'a>(&'a mut self) -> ModelMutator<'a> {
         ModelMutator { model: self }
     }
@@ -141,9 +254,21 @@ impl ModelData {
     pub fn describe(&self) -> &str {
         &self.decl
     }
+    /// A stable hash of the model's schema (as in [`Self::describe`]), so that clients can cheaply detect if the
+    /// schema changed
+    pub fn fingerprint(&self) -> u64 {
+        let mut crc = SCrc64::new();
+        crc.update(self.decl.as_bytes());
+        crc.update(&[self.is_strict() as u8]);
+        crc.finish()
+    }
     fn redescribe(&self) -> String {
         let mut ret = format!("{{");
-        let mut it = self.fields().stseq_ord_kv().peekable();
+        let mut it = self
+            .fields()
+            .stseq_ord_kv()
+            .filter(|(field_name, _)| field_name.as_str() != EXTRA_FIELD)
+            .peekable();
         while let Some((field_name, field_decl)) = it.next() {
             // legend: * -> primary, ! -> not null, ? -> null
             if self.is_pk(&field_name) {
@@ -155,18 +280,20 @@ impl ModelData {
             }
             ret.push_str(&field_name);
             ret.push(':');
-            // TODO(@ohsayan): it's all lists right now, so this is okay but fix it later
-            if field_decl.layers().len() == 1 {
-                ret.push_str(field_decl.layers()[0].tag().tag_selector().name_str());
-            } else {
-                ret.push_str(&"[".repeat(field_decl.layers().len() - 1));
-                ret.push_str(
-                    field_decl.layers()[field_decl.layers().len() - 1]
-                        .tag()
-                        .tag_selector()
-                        .name_str(),
-                );
-                ret.push_str(&"]".repeat(field_decl.layers().len() - 1))
+            // a list layer is `[...]` and a map layer is `{...}`
+            let (last, nested) = field_decl.layers().split_last().unwrap();
+            for layer in nested {
+                ret.push(match layer.tag().tag_class() {
+                    TagClass::Map => '{',
+                    _ => '[',
+                });
+            }
+            ret.push_str(last.tag().tag_selector().name_str());
+            for layer in nested.iter().rev() {
+                ret.push(match layer.tag().tag_class() {
+                    TagClass::Map => '}',
+                    _ => ']',
+                });
             }
             if it.peek().is_some() {
                 ret.push(',');
@@ -184,18 +311,25 @@ impl ModelData {
         p_tag: FullTag,
         fields: Fields,
         private: ModelPrivate,
+        index: PrimaryIndexKind,
     ) -> Self {
         let mut slf = Self {
             uuid,
             p_key,
             p_tag,
             fields,
-            data: PrimaryIndex::new_empty(),
+            data: PrimaryIndex::new_empty(index),
             delta: DeltaState::new_resolved(),
             private,
             decl: String::new(),
+            columnar: RwLock::new(None),
+            secondary: RwLock::new(vec![]),
+            unique: RwLock::new(vec![]),
+            ttl: None,
+            append_only: false,
         };
         slf.sync_decl();
+        slf.unique_rebuild(&cpin());
         slf
     }
     pub fn new_restore(
@@ -203,6 +337,7 @@ impl ModelData {
         p_key: Box<str>,
         p_tag: FullTag,
         decl_fields: IndexSTSeqCns<Box<str>, Field>,
+        index: PrimaryIndexKind,
     ) -> Self {
         let mut private = ModelPrivate::empty();
         let p_key = unsafe {
@@ -224,17 +359,52 @@ impl ModelData {
             .for_each(|(field_key, field)| {
                 fields.st_insert(field_key, field);
             });
-        Self::new_with_private(uuid, p_key, p_tag, fields, private)
+        Self::new_with_private(uuid, p_key, p_tag, fields, private, index)
+    }
+    /// Set the ttl of a restored model
+    pub fn with_ttl(mut self, ttl: Option<u64>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+    /// Set if a restored model is append-only
+    pub fn with_append_only(mut self, append_only: bool) -> Self {
+        self.append_only = append_only;
+        self
     }
     pub fn process_create(
         CreateModel {
             model_name: _,
             fields,
-            props,
+            mut props,
             ..
         }: CreateModel,
     ) -> QueryResult<Self> {
         let mut private = ModelPrivate::empty();
+        let strict = match props.remove("strict") {
+            Some(DictEntryGeneric::Data(b)) if b.kind() == TagClass::Bool => b.bool(),
+            Some(_) => return Err(QueryError::QExecDdlInvalidProperties),
+            None => true,
+        };
+        let index = match props.remove("pk_index") {
+            Some(DictEntryGeneric::Data(name)) if name.kind() == TagClass::Str => {
+                match PrimaryIndexKind::from_name(name.str()) {
+                    Some(index) => index,
+                    None => return Err(QueryError::QExecDdlInvalidProperties),
+                }
+            }
+            Some(_) => return Err(QueryError::QExecDdlInvalidProperties),
+            None => PrimaryIndexKind::Hash,
+        };
+        let ttl = ttl::parse_ttl(props.remove("ttl"))?;
+        let append_only = match props.remove("append_only") {
+            Some(DictEntryGeneric::Data(b)) if b.kind() == TagClass::Bool => b.bool(),
+            Some(_) => return Err(QueryError::QExecDdlInvalidProperties),
+            None => false,
+        };
+        // expiring rows are removed, so they can't be written only once
+        if append_only & ttl.is_some() {
+            return Err(QueryError::QExecDdlInvalidProperties);
+        }
         let mut okay = props.is_empty() & !fields.is_empty();
         // validate fields
         let mut field_spec = fields.into_iter();
@@ -247,6 +417,7 @@ impl ModelData {
                 layers,
                 null,
                 primary,
+                unique,
             } = field_spec.next().unwrap();
             let this_field_ptr = unsafe {
                 // UNSAFE(@ohsayan): this is going to go with our alloc, so we're good! if we fail too, the dtor for private will run
@@ -258,26 +429,46 @@ impl ModelData {
                     // UNSAFE(@ohsayan): totally cool, it's all allocated
                     this_field_ptr.clone()
                 });
-                okay &= !null;
+                okay &= !null & !unique;
             }
-            let layer = Field::parse_layers(layers, null)?;
+            let layer = Field::parse_layers(layers, null)?.with_unique(unique);
             okay &= fields.st_insert(this_field_ptr, layer);
         }
         okay &= pk_cnt <= 1;
+        if okay & !strict {
+            let extra = unsafe {
+                // UNSAFE(@ohsayan): same as the other fields
+                private.allocate_or_recycle(EXTRA_FIELD)
+            };
+            // NB: the layers only describe the column; the keys and values are checked on insert (see `dml::ins`)
+            fields.st_insert(
+                extra,
+                Field::new(VInline::from_iter([Layer::list(), Layer::str()]), true),
+            );
+        }
         if okay {
             let last_pk = last_pk.unwrap_or(unsafe {
                 // UNSAFE(@ohsayan): once again, all of this is allocated
                 fields.stseq_ord_key().next().unwrap().clone()
             });
             let tag = fields.st_get(&last_pk).unwrap().layers()[0].tag;
-            if tag.tag_unique().is_unique() {
-                return Ok(Self::new_with_private(
-                    Uuid::new(),
-                    last_pk,
-                    tag,
-                    fields,
-                    private,
-                ));
+            // the radix index needs the bytes of the key
+            let index_okay = (index == PrimaryIndexKind::Hash)
+                | matches!(tag.tag_unique(), TagUnique::Str | TagUnique::Bin);
+            if tag.tag_unique().is_unique() & index_okay {
+                let model =
+                    Self::new_with_private(Uuid::new(), last_pk, tag, fields, private, index)
+                        .with_ttl(ttl)
+                        .with_append_only(append_only);
+                // a unique field needs the same kind of values as a secondary index (and can't be the primary key)
+                let unique_okay = model
+                    .fields()
+                    .stseq_ord_kv()
+                    .filter(|(_, field)| field.is_unique())
+                    .all(|(name, _)| SecondaryIndex::can_index(&model, name.as_str()).is_ok());
+                if unique_okay {
+                    return Ok(model);
+                }
             }
         }
         Err(QueryError::QExecDdlModelBadDefinition)
@@ -291,7 +482,10 @@ impl ModelData {
     ) -> QueryResult<Option<bool>> {
         let (space_name, model_name) = (stmt.model_name.space(), stmt.model_name.entity());
         let if_nx = stmt.if_not_exists;
-        let model = Self::process_create(stmt)?;
+        let (model, hint) = match stmt.snapshot_of {
+            Some(source) => Self::process_snapshot(global, source, stmt.props)?,
+            None => (Self::process_create(stmt)?, 0),
+        };
         global
             .state()
             .namespace()
@@ -335,10 +529,24 @@ impl ModelData {
                 )?;
                 // update global state
                 let _ = space.models_mut().insert(model_name.into());
-                let _ = global.state().namespace().idx_models().write().insert(
+                let mut models = global.state().namespace().idx_models().write();
+                let _ = models.insert(
                     EntityID::new(&space_name, &model_name),
                     Model::new(model, mdl_driver),
                 );
+                if hint != 0 {
+                    // a snapshot starts out with all its rows pending, so get them flushed
+                    let model = models
+                        .get(&EntityIDRef::new(space_name, model_name))
+                        .unwrap();
+                    DeltaState::guard_delta_overflow(
+                        global,
+                        space_name,
+                        model_name,
+                        model.data(),
+                        QueryExecMeta::new(hint),
+                    );
+                }
                 if if_nx {
                     Ok(Some(true))
                 } else {
@@ -346,6 +554,75 @@ impl ModelData {
                 }
             })
     }
+    /// Copy the schema and the rows of `source` into a new model, masking them with the profile in `props` (if any).
+    /// Expired rows are left out. The copied rows are queued as new data deltas (the returned hint is the size of the
+    /// delta queue) so they're written to the new model's own data file
+    fn process_snapshot<G: GlobalInstanceLike>(
+        global: &G,
+        source: EntityIDRef,
+        props: DictGeneric,
+    ) -> QueryResult<(Self, usize)> {
+        global.state().namespace().with_model(source, |src| {
+            let mut fields = IndexSTSeqCns::idx_init_cap(src.fields().len());
+            src.fields().stseq_ord_kv().for_each(|(field_name, field)| {
+                fields.st_insert(field_name.as_str().into(), field.clone());
+            });
+            let model = Self::new_restore(
+                Uuid::new(),
+                src.p_key().into(),
+                src.p_tag(),
+                fields,
+                src.primary_index().kind(),
+            )
+            .with_ttl(src.ttl())
+            .with_append_only(src.is_append_only());
+            let mut mask = MaskProfile::new(&model, props)?;
+            let g = cpin();
+            let (ds, idx) = (model.delta_state(), model.primary_index());
+            let mut hint = 0;
+            let expiry = src.expiry();
+            let _latch = src.primary_index().acquire_exclusive();
+            for row in src.primary_index().__raw_index().mt_iter_entry(&g) {
+                let data = row.resolve_schema_deltas_and_freeze(src.delta_state());
+                if expiry.is_expired(&data) {
+                    continue;
+                }
+                let mut row_data = DcFieldIndex::idx_init_cap(data.fields().len());
+                for field_name in model.fields().stseq_ord_key() {
+                    if let Some(dc) = data.fields().st_get(field_name.as_str()) {
+                        row_data.st_insert(
+                            unsafe {
+                                // UNSAFE(@ohsayan): the key is owned by the new model
+                                field_name.clone()
+                            },
+                            mask.apply(field_name.as_str(), dc.clone()),
+                        );
+                    }
+                }
+                let pk = if mask.is_empty() {
+                    row.d_key().clone()
+                } else {
+                    let pk = VirtualDatacell::new_pk(row.d_key(), src.p_tag());
+                    let pk = mask.apply(src.p_key(), (*pk).clone());
+                    unsafe {
+                        // UNSAFE(@ohsayan): a masked primary key is still of the primary key's type
+                        PrimaryIndexKey::new_from_dc(pk)
+                    }
+                };
+                // a mask can also turn two values of a unique field into the same value
+                model.unique_claim([(&pk, &row_data)])?;
+                let new_version = ds.create_new_data_delta_version();
+                let new_row = Row::new(pk, row_data, ds.schema_current_version(), new_version);
+                if !idx.__raw_index().mt_insert(new_row.clone(), &g) {
+                    // two keys were masked to the same value
+                    return Err(QueryError::QExecDmlDuplicate);
+                }
+                hint =
+                    ds.append_new_data_delta_with(DataDeltaKind::Insert, new_row, new_version, &g);
+            }
+            Ok((model, hint))
+        })
+    }
     pub fn transactional_exec_drop<G: GlobalInstanceLike>(
         global: &G,
         stmt: DropModel,
@@ -373,6 +650,13 @@ impl ModelData {
                     // nope, we can't drop this
                     return Err(QueryError::QExecDdlNotEmpty);
                 }
+                // keep a copy around if dropped models go to the trash
+                let trashed = trash::trash_if_retained(
+                    space_name,
+                    space.get_uuid(),
+                    model_name,
+                    model.data(),
+                )?;
                 // okay this is looking good for us
                 // prepare txn
                 let txn = gns::model::DropModelTxn::new(ModelIDRef::new(
@@ -389,8 +673,16 @@ impl ModelData {
                 global.state().gns_driver().driver_context(
                     global,
                     |drv| drv.commit_event(txn),
-                    || {},
+                    || {
+                        if let Some(entry) = &trashed {
+                            let _ = entry.discard();
+                        }
+                    },
                 )?;
+                if model.driver().status().is_iffy() {
+                    // this driver had a fault but it's being purged anyway so update global status
+                    global.health().report_removal_of_faulty_source();
+                }
                 // request cleanup
                 global.purge_model_driver(
                     space_name,
@@ -408,6 +700,32 @@ impl ModelData {
                 }
             })
     }
+    pub fn transactional_exec_truncate<G: GlobalInstanceLike>(
+        global: &G,
+        stmt: TruncateModel,
+    ) -> QueryResult<()> {
+        /*
+            we hold the model index exclusively so no DML query (or batch flush) is running. every delta created so far is
+            then for a row that we're removing, so we write a single truncate batch and discard them
+        */
+        let mut models_idx = global.state().namespace().idx_models().write();
+        let Some(model) = models_idx.get_mut(&stmt.entity) else {
+            return Err(QueryError::QExecObjectNotFound);
+        };
+        let mdl_id = ModelUniqueIDRef::new(
+            stmt.entity.space(),
+            stmt.entity.entity(),
+            model.data().get_uuid(),
+        );
+        model.driver().truncate(global, mdl_id, model.data())?;
+        let mdl = model.data_mut();
+        mdl.delta_state().truncate_data_deltas();
+        let g = cpin();
+        mdl.primary_index().__raw_index().mt_clear(&g);
+        mdl.columnar_cache_rebuild();
+        mdl.secondary_indexes_rebuild();
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -506,11 +824,11 @@ impl<'a> Drop for ModelMutator<'a> {
     Layer
 */
 
-static G: [u8; 15] = [0, 13, 12, 5, 6, 4, 3, 6, 1, 10, 4, 5, 7, 5, 5];
-static S1: [u8; 7] = [13, 9, 4, 14, 2, 4, 7];
-static S2: [u8; 7] = [12, 8, 2, 6, 4, 9, 9];
+static G: [u8; 15] = [0, 7, 14, 6, 9, 7, 12, 2, 8, 12, 2, 10, 13, 2, 10];
+static S1: [u8; 7] = [11, 2, 8, 5, 11, 14, 5];
+static S2: [u8; 7] = [6, 11, 2, 12, 2, 7, 12];
 
-static LUT: [(&str, FullTag); 14] = [
+static LUT: [(&str, FullTag); 15] = [
     ("bool", FullTag::BOOL),
     ("uint8", FullTag::new_uint(TagSelector::UInt8)),
     ("uint16", FullTag::new_uint(TagSelector::UInt16)),
@@ -525,6 +843,7 @@ static LUT: [(&str, FullTag); 14] = [
     ("binary", FullTag::BIN),
     ("string", FullTag::STR),
     ("list", FullTag::LIST),
+    ("map", FullTag::MAP),
 ];
 
 #[cfg(test)]
@@ -546,15 +865,44 @@ pub static TY_LIST: &str = LUT[13].0;
 pub struct Field {
     layers: VInline<1, Layer>,
     nullable: bool,
+    unique: bool,
 }
 
 impl Field {
+    pub const PROP_UNIQUE: u64 = 0;
+    pub const PROP_C_MAX: u64 = 1;
     pub fn new(layers: VInline<1, Layer>, nullable: bool) -> Self {
-        Self { layers, nullable }
+        Self {
+            layers,
+            nullable,
+            unique: false,
+        }
+    }
+    /// Set if no two rows can have the same value in this field
+    pub fn with_unique(mut self, unique: bool) -> Self {
+        self.unique = unique;
+        self
     }
     pub fn is_nullable(&self) -> bool {
         self.nullable
     }
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+    /// Returns the properties that are set (as `(id, value)`)
+    pub fn props(&self) -> impl Iterator<Item = (u64, u64)> {
+        [(Self::PROP_UNIQUE, self.unique as u64)]
+            .into_iter()
+            .filter(|(_, value)| *value != 0)
+    }
+    /// Set a property, returning false if there's no such property (or it can't have this value)
+    pub fn set_prop(&mut self, id: u64, value: u64) -> bool {
+        match (id, value) {
+            (Self::PROP_UNIQUE, 1) => self.unique = true,
+            _ => return false,
+        }
+        true
+    }
     pub fn layers(&self) -> &[Layer] {
         &self.layers
     }
@@ -565,25 +913,32 @@ impl Field {
         let mut layerview = VInline::new();
         while (layers.len() != 0) & okay & !fin {
             let LayerSpec { ty, props } = layers.next().unwrap();
-            okay &= props.is_empty(); // FIXME(@ohsayan): you know what to do here
-            match Layer::get_layer(&ty) {
+            match Layer::parse(&ty, props) {
                 Some(l) => {
-                    fin = l.tag.tag_selector() != TagSelector::List;
+                    fin = !matches!(l.tag.tag_selector(), TagSelector::List | TagSelector::Map);
                     layerview.push(l);
                 }
                 None => okay = false,
             }
         }
-        okay &= fin & (layers.len() == 0);
+        okay &= fin & (layers.len() == 0) & Self::bounds_okay(&layerview);
         if okay {
             Ok(Self {
                 layers: layerview,
                 nullable,
+                unique: false,
             })
         } else {
             Err(QueryError::QExecDdlInvalidTypeDefinition)
         }
     }
+    /// An element bound only makes sense if the elements are strings or binaries
+    pub(super) fn bounds_okay(layers: &[Layer]) -> bool {
+        layers.windows(2).all(|layers| {
+            (layers[0].bounds.element_max == ListBounds::UNBOUNDED)
+                | matches!(layers[1].tag.tag_class(), TagClass::Bin | TagClass::Str)
+        })
+    }
     #[inline(always)]
     fn compute_index(&self, dc: &Datacell) -> usize {
         if {
@@ -591,7 +946,7 @@ impl Field {
                 | ((self.layers[0].tag.tag_class() != dc.kind()) & !dc.is_null())
         } {
             // illegal states: (1) bad null (2) tags don't match
-            7
+            8
         } else {
             dc.kind().value_word()
         }
@@ -604,6 +959,10 @@ impl Field {
             Self::rvt_data(self.layers(), data)
         }
     }
+    /// Validate an element of a list or a value of a map (against the layers below the first one)
+    pub fn vt_nested(&self, data: &mut Datacell) -> bool {
+        (self.layers.len() > 1) && Self::rvt_data(&self.layers[1..], data)
+    }
     fn rvt_data(layers: &[Layer], data: &mut Datacell) -> bool {
         let layer = layers[0];
         let layers = &layers[1..];
@@ -625,6 +984,20 @@ impl Field {
                 }
                 okay
             }
+            (TagClass::Map, TagClass::Map) => {
+                let okay = unsafe {
+                    // UNSAFE(@ohsayan): +tagck
+                    VTFN[TagClass::Map.value_word()](layer, data)
+                };
+                let map = unsafe {
+                    // UNSAFE(@ohsayan): +tagck
+                    data.read_map()
+                };
+                let mut mwrite = map.write();
+                okay && mwrite
+                    .values_mut()
+                    .all(|value| Self::rvt_data(layers, value))
+            }
             (tag_a, tag_b) if tag_a == tag_b => {
                 unsafe {
                     // UNSAFE(@ohsayan): same tags and lists have non-null elements
@@ -639,6 +1012,67 @@ impl Field {
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Layer {
     tag: FullTag,
+    bounds: ListBounds,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The bounds of a list layer: `list { type: string, max_len: <n>, element_max: <n> }`
+pub struct ListBounds {
+    /// the most elements that the list can hold
+    max_len: u64,
+    /// the most bytes that a (string or binary) element can hold
+    element_max: u64,
+}
+
+impl ListBounds {
+    const UNBOUNDED: u64 = u64::MAX;
+    pub const PROP_MAX_LEN: u64 = 0;
+    pub const PROP_ELEMENT_MAX: u64 = 1;
+    pub const PROP_C_MAX: u64 = 2;
+    pub const fn unbounded() -> Self {
+        Self {
+            max_len: Self::UNBOUNDED,
+            element_max: Self::UNBOUNDED,
+        }
+    }
+    /// Returns the properties that are set (as `(id, value)`)
+    pub fn props(&self) -> impl Iterator<Item = (u64, u64)> {
+        [
+            (Self::PROP_MAX_LEN, self.max_len),
+            (Self::PROP_ELEMENT_MAX, self.element_max),
+        ]
+        .into_iter()
+        .filter(|(_, value)| *value != Self::UNBOUNDED)
+    }
+    /// Set a property, returning false if there's no such property
+    pub fn set_prop(&mut self, id: u64, value: u64) -> bool {
+        match id {
+            Self::PROP_MAX_LEN => self.max_len = value,
+            Self::PROP_ELEMENT_MAX => self.element_max = value,
+            _ => return false,
+        }
+        true
+    }
+    /// Check that the element is within bounds
+    pub fn element_fits(&self, element: &Datacell) -> bool {
+        match element.kind() {
+            TagClass::Bin | TagClass::Str => unsafe {
+                // UNSAFE(@ohsayan): +tagck
+                element.read_bin().len() as u64 <= self.element_max
+            },
+            _ => true,
+        }
+    }
+    /// Check that the list is within bounds
+    pub fn check(&self, list: &[Datacell]) -> bool {
+        (list.len() as u64 <= self.max_len)
+            & ((self.element_max == Self::UNBOUNDED)
+                || list.iter().all(|element| self.element_fits(element)))
+    }
+    /// Check that the element can be appended to a list with `len` elements
+    pub fn check_push(&self, len: usize, element: &Datacell) -> bool {
+        ((len as u64) < self.max_len) & self.element_fits(element)
+    }
 }
 
 #[allow(unused)]
@@ -685,17 +1119,49 @@ impl Layer {
     pub const fn list() -> Self {
         Self::empty(FullTag::LIST)
     }
+    pub const fn map() -> Self {
+        Self::empty(FullTag::MAP)
+    }
 }
 
 impl Layer {
     pub fn tag(&self) -> FullTag {
         self.tag
     }
+    pub fn bounds(&self) -> ListBounds {
+        self.bounds
+    }
+    #[cfg(test)]
     pub fn new_empty_props(tag: FullTag) -> Self {
         Self::new(tag)
     }
     pub const fn new(tag: FullTag) -> Self {
-        Self { tag }
+        Self {
+            tag,
+            bounds: ListBounds::unbounded(),
+        }
+    }
+    pub fn new_with_bounds(tag: FullTag, bounds: ListBounds) -> Self {
+        Self { tag, bounds }
+    }
+    /// Parse a layer along with its properties. Only lists have properties (`max_len` and `element_max`)
+    pub(super) fn parse(ident: &str, mut props: DictGeneric) -> Option<Self> {
+        let mut layer = Self::get_layer(ident)?;
+        if layer.tag.tag_class() == TagClass::List {
+            for (key, id) in [
+                ("max_len", ListBounds::PROP_MAX_LEN),
+                ("element_max", ListBounds::PROP_ELEMENT_MAX),
+            ] {
+                match props.remove(key) {
+                    Some(DictEntryGeneric::Data(dc)) if dc.kind() == TagClass::UnsignedInt => {
+                        layer.bounds.set_prop(id, dc.uint());
+                    }
+                    Some(_) => return None,
+                    None => {}
+                }
+            }
+        }
+        props.is_empty().then_some(layer)
     }
     const fn empty(tag: FullTag) -> Self {
         Self::new(tag)
@@ -712,7 +1178,7 @@ impl Layer {
     fn pf(key: &[u8]) -> u16 {
         (G[Self::hf(key, S1) as usize] as u16 + G[Self::hf(key, S2) as usize] as u16) % 15
     }
-    fn get_layer(ident: &str) -> Option<Self> {
+    pub(super) fn get_layer(ident: &str) -> Option<Self> {
         let idx = Self::pf(ident.as_bytes()) as usize;
         if idx < LUT.len() && LUT[idx].0 == ident {
             Some(Self::empty(LUT[idx].1))
@@ -741,7 +1207,7 @@ pub(super) fn layer_traces() -> Box<[Box<str>]> {
     local_mut!(LAYER_TRACE, |ltrace| ltrace.drain(..).collect())
 }
 
-static VTFN: [unsafe fn(Layer, &mut Datacell) -> bool; 8] = [
+static VTFN: [unsafe fn(Layer, &mut Datacell) -> bool; 9] = [
     vt_bool,
     vt_uint,
     vt_sint,
@@ -749,6 +1215,7 @@ static VTFN: [unsafe fn(Layer, &mut Datacell) -> bool; 8] = [
     vt_bin,
     vt_str,
     vt_list,
+    vt_map,
     |_, _| false,
 ];
 unsafe fn vt_bool(_: Layer, _: &mut Datacell) -> bool {
@@ -778,7 +1245,12 @@ unsafe fn vt_str(_: Layer, _: &mut Datacell) -> bool {
     layertrace("string");
     true
 }
-unsafe fn vt_list(_: Layer, _: &mut Datacell) -> bool {
+unsafe fn vt_list(l: Layer, dc: &mut Datacell) -> bool {
     layertrace("list");
-    true
+    l.bounds.check(&dc.read_list().read())
+}
+unsafe fn vt_map(_: Layer, dc: &mut Datacell) -> bool {
+    layertrace("map");
+    // a map can't hold nulls
+    dc.read_map().read().values().all(|value| !value.is_null())
 }
@@ -0,0 +1,298 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    secondary indexes
+    ---
+    a secondary index maps the values of a field to the keys of the rows that have them, so that a scan with an
+    equality filter on that field only has to read the matching rows. an index can be created on any field (other than
+    the primary key) with a type that could be used for a primary key. nulls are not indexed since an equality filter
+    never matches them anyway.
+
+    like the columnar cache, every insert, update and delete on the model keeps the indexes up to date and the indexes
+    read the row again while holding their lock, so they always end up with the latest value for a row. locks are
+    taken in this order: the index latch, the secondary index lock and then the row lock.
+
+    only the declaration of an index is persisted (in the GNS). the entries are built again from the rows whenever the
+    model is loaded
+*/
+
+use {
+    super::ModelData,
+    crate::engine::{
+        core::index::{PrimaryIndexKey, Row},
+        data::{cell::Datacell, tag::DataTag},
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::STIndex,
+        ql::ddl::{crt::CreateIndex, drop::DropIndex},
+        sync::atm::{cpin, Guard},
+        txn::{gns, ModelIDRef},
+    },
+    std::{
+        collections::{hash_map::Entry, HashMap, HashSet},
+        hash::{Hash, Hasher},
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// An indexed value. A field is either a string or a binary field, so both of them are simply kept as bytes
+pub enum IndexedValue {
+    UInt(u64),
+    SInt(i64),
+    Bytes(Box<[u8]>),
+}
+
+impl IndexedValue {
+    pub(super) fn from_dc(dc: &Datacell) -> Option<Self> {
+        dc.try_uint()
+            .map(Self::UInt)
+            .or_else(|| dc.try_sint().map(Self::SInt))
+            .or_else(|| dc.try_str().map(|s| Self::Bytes(s.as_bytes().into())))
+            .or_else(|| dc.try_bin().map(|b| Self::Bytes(b.into())))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The key of an indexed row. The hash of a primary key only covers the bytes of a string or binary key (which would
+/// put every integer key in the same bucket), so we hash the whole key instead
+pub(super) struct RowKey(pub(super) PrimaryIndexKey);
+
+impl Hash for RowKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.uint().hash(state);
+        self.0.sint().hash(state);
+        self.0.str().hash(state);
+        self.0.bin().hash(state);
+    }
+}
+
+#[derive(Debug)]
+/// A secondary index on a single field of a model
+pub struct SecondaryIndex {
+    name: Box<str>,
+    field: Box<str>,
+    rows: HashMap<IndexedValue, HashSet<RowKey>>,
+    values: HashMap<RowKey, IndexedValue>,
+}
+
+impl SecondaryIndex {
+    /// Create an empty index (see [`Self::build`])
+    pub fn new(name: Box<str>, field: Box<str>) -> Self {
+        Self {
+            name,
+            field,
+            rows: HashMap::new(),
+            values: HashMap::new(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+    /// Check if the field can be indexed
+    pub fn can_index(mdl: &ModelData, field: &str) -> QueryResult<()> {
+        let Some(field_info) = mdl.fields().st_get(field) else {
+            return Err(QueryError::QExecUnknownField);
+        };
+        let okay = mdl.not_pk(field)
+            & (field_info.layers().len() == 1)
+            & field_info.layers()[0].tag().tag_unique().is_unique();
+        if okay {
+            Ok(())
+        } else {
+            Err(QueryError::QExecDdlModelBadDefinition)
+        }
+    }
+    /// Index every row in the model (dropping whatever was indexed before). No other write can run on the model while
+    /// this runs
+    pub fn build(&mut self, mdl: &ModelData, g: &Guard) {
+        // size the maps for the rows up front so that they aren't rehashed over and over while we fill them
+        let rows = mdl.primary_index().count();
+        self.rows = HashMap::with_capacity(rows);
+        self.values = HashMap::with_capacity(rows);
+        for row in mdl.primary_index().__raw_index().mt_iter_entry(g) {
+            self.upsert(mdl, row);
+        }
+    }
+    /// Add or refresh a row (this reads the row, so the caller must not be holding the row lock)
+    pub fn upsert(&mut self, mdl: &ModelData, row: &Row) {
+        let value = row
+            .resolve_schema_deltas_and_freeze(mdl.delta_state())
+            .fields()
+            .st_get(self.field.as_ref())
+            .and_then(IndexedValue::from_dc);
+        let key = RowKey(row.d_key().clone());
+        if self.values.get(&key) == value.as_ref() {
+            return;
+        }
+        self.unlink(&key);
+        if let Some(value) = value {
+            self.rows
+                .entry(value.clone())
+                .or_default()
+                .insert(key.clone());
+            self.values.insert(key, value);
+        }
+    }
+    /// Remove a row (if it was indexed)
+    pub fn remove(&mut self, key: &PrimaryIndexKey) {
+        self.unlink(&RowKey(key.clone()))
+    }
+    /// The keys of the rows with the given value
+    pub fn lookup(&self, value: &IndexedValue) -> impl Iterator<Item = &PrimaryIndexKey> {
+        self.rows
+            .get(value)
+            .into_iter()
+            .flat_map(|keys| keys.iter().map(|key| &key.0))
+    }
+    fn unlink(&mut self, key: &RowKey) {
+        let Some(old) = self.values.remove(key) else {
+            return;
+        };
+        if let Entry::Occupied(mut keys) = self.rows.entry(old) {
+            keys.get_mut().remove(key);
+            if keys.get().is_empty() {
+                keys.remove();
+            }
+        }
+    }
+}
+
+impl ModelData {
+    pub fn transactional_exec_create_index<G: GlobalInstanceLike>(
+        global: &G,
+        stmt: CreateIndex,
+    ) -> QueryResult<Option<bool>> {
+        let (space_name, model_name) = (stmt.model_name.space(), stmt.model_name.entity());
+        global
+            .state()
+            .namespace()
+            .with_model_space_mut_for_ddl(stmt.model_name, |space, model| {
+                SecondaryIndex::can_index(model, stmt.field.as_str())?;
+                let exists = model.secondary.get_mut().iter().any(|index| {
+                    (index.name() == stmt.index_name.as_str())
+                        | (index.field() == stmt.field.as_str())
+                });
+                if exists {
+                    return if stmt.if_not_exists {
+                        Ok(Some(false))
+                    } else {
+                        Err(QueryError::QExecDdlObjectAlreadyExists)
+                    };
+                }
+                // commit txn
+                let txn = gns::model::CreateIndexTxn::new(
+                    ModelIDRef::new_ref(space_name, space, model_name, model),
+                    stmt.index_name.as_str(),
+                    stmt.field.as_str(),
+                );
+                global.state().gns_driver().driver_context(
+                    global,
+                    |drv| drv.commit_event(txn),
+                    || {},
+                )?;
+                // no DML query can run while we hold the model for DDL, so just index everything
+                let mut index = SecondaryIndex::new(
+                    stmt.index_name.as_str().into(),
+                    stmt.field.as_str().into(),
+                );
+                index.build(model, &cpin());
+                model.secondary.get_mut().push(index);
+                if stmt.if_not_exists {
+                    Ok(Some(true))
+                } else {
+                    Ok(None)
+                }
+            })
+    }
+    pub fn transactional_exec_drop_index<G: GlobalInstanceLike>(
+        global: &G,
+        stmt: DropIndex,
+    ) -> QueryResult<Option<bool>> {
+        let (space_name, model_name) = (stmt.entity.space(), stmt.entity.entity());
+        global
+            .state()
+            .namespace()
+            .with_model_space_mut_for_ddl(stmt.entity, |space, model| {
+                let Some(position) = model
+                    .secondary
+                    .get_mut()
+                    .iter()
+                    .position(|index| index.name() == stmt.index_name.as_str())
+                else {
+                    return if stmt.if_exists {
+                        Ok(Some(false))
+                    } else {
+                        Err(QueryError::QExecObjectNotFound)
+                    };
+                };
+                // commit txn
+                let txn = gns::model::DropIndexTxn::new(
+                    ModelIDRef::new_ref(space_name, space, model_name, model),
+                    stmt.index_name.as_str(),
+                );
+                global.state().gns_driver().driver_context(
+                    global,
+                    |drv| drv.commit_event(txn),
+                    || {},
+                )?;
+                model.secondary.get_mut().remove(position);
+                if stmt.if_exists {
+                    Ok(Some(true))
+                } else {
+                    Ok(None)
+                }
+            })
+    }
+    /// Add an (empty) index while restoring the GNS. Returns false if the index can't be created
+    pub fn __raw_create_index(&mut self, name: Box<str>, field: Box<str>) -> bool {
+        let indexes = self.secondary.get_mut();
+        let exists = indexes
+            .iter()
+            .any(|index| (index.name() == name.as_ref()) | (index.field() == field.as_ref()));
+        if exists || SecondaryIndex::can_index(self, &field).is_err() {
+            return false;
+        }
+        self.secondary
+            .get_mut()
+            .push(SecondaryIndex::new(name, field));
+        true
+    }
+    /// Remove an index while restoring the GNS. Returns false if there is no such index
+    pub fn __raw_drop_index(&mut self, name: &str) -> bool {
+        let indexes = self.secondary.get_mut();
+        match indexes.iter().position(|index| index.name() == name) {
+            Some(position) => {
+                indexes.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+}
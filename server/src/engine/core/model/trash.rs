@@ -0,0 +1,175 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    trash
+    ---
+    with a trash retention (`ddl.trash_retention`), `drop model` writes a copy of the model to the trash of its space
+    (see `storage::trash`) before the model is removed. `sysctl undrop model <space>.<model>` brings back the most
+    recently dropped copy of the model (under a new UUID, since the old data directory is removed in the background)
+    as long as the name hasn't been taken since, and the general executor purges the copies that have been in the trash
+    for longer than the retention window. secondary indexes aren't kept, so they have to be created again
+*/
+
+use {
+    super::{Model, ModelData},
+    crate::{
+        engine::{
+            core::{EntityID, EntityIDRef},
+            data::uuid::Uuid,
+            error::{QueryError, QueryResult},
+            fractal::{FractalModelDriver, GlobalInstanceLike},
+            storage::trash::{self, TrashEntry},
+            txn::{gns, SpaceIDRef},
+        },
+        util::os,
+    },
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+/// zero if dropped models aren't kept
+static TRASH_RETENTION: AtomicU64 = AtomicU64::new(0);
+
+/// Set how long (in seconds) dropped models are kept in the trash. If unset, dropped models are deleted right away
+pub fn set_trash_retention(retention: Option<u64>) {
+    TRASH_RETENTION.store(retention.unwrap_or(0), Ordering::Release)
+}
+
+pub fn trash_retention() -> Option<u64> {
+    match TRASH_RETENTION.load(Ordering::Acquire) {
+        0 => None,
+        retention => Some(retention),
+    }
+}
+
+/// Write a copy of the model to the trash if dropped models are kept
+pub(super) fn trash_if_retained(
+    space_name: &str,
+    space_uuid: Uuid,
+    model_name: &str,
+    model: &ModelData,
+) -> QueryResult<Option<TrashEntry>> {
+    if trash_retention().is_none() {
+        return Ok(None);
+    }
+    match trash::trash_model(
+        space_name,
+        space_uuid,
+        model_name,
+        model,
+        (os::get_epoch_time() / 1_000_000_000) as u64,
+    ) {
+        Ok(entry) => Ok(Some(entry)),
+        Err(e) => {
+            error!("failed to move {space_name}.{model_name} to the trash with error `{e}`");
+            Err(QueryError::SysServerError)
+        }
+    }
+}
+
+/// Delete the dropped models that have been in the trash for longer than the retention window. Returns the number of
+/// models that were purged
+pub fn purge_trash(global: &impl GlobalInstanceLike) -> usize {
+    let Some(retention) = trash_retention() else {
+        return 0;
+    };
+    let cutoff = ((os::get_epoch_time() / 1_000_000_000) as u64).saturating_sub(retention);
+    let mut purged = 0;
+    for (space_name, space) in global.state().namespace().idx().read().iter() {
+        match trash::purge(space_name, space.get_uuid(), cutoff) {
+            Ok(count) => purged += count,
+            Err(e) => error!("failed to purge the trash of {space_name} with error `{e}`"),
+        }
+    }
+    purged
+}
+
+impl ModelData {
+    pub fn transactional_exec_undrop<G: GlobalInstanceLike>(
+        global: &G,
+        entity: EntityIDRef,
+    ) -> QueryResult<()> {
+        let (space_name, model_name) = (entity.space(), entity.entity());
+        global
+            .state()
+            .namespace()
+            .ddl_with_space_mut(space_name, |space| {
+                let Some(entry) = trash::list(space_name, space.get_uuid(), Some(model_name))?.pop()
+                else {
+                    return Err(QueryError::QExecObjectNotFound);
+                };
+                if space.models().contains(model_name) {
+                    return Err(QueryError::QExecDdlObjectAlreadyExists);
+                }
+                let mut model = entry.read_model()?;
+                // the old data directory may not have been removed yet
+                model.uuid = Uuid::new();
+                let driver = entry.restore(space_name, space.get_uuid(), model_name, &model)?;
+                // the unique fields have to be built again from the rows that were just loaded
+                model.secondary_indexes_rebuild();
+                // prepare txn
+                let txn = gns::model::CreateModelTxn::new(
+                    SpaceIDRef::new(space_name, space),
+                    model_name,
+                    &model,
+                );
+                // commit txn
+                if let Err(e) = global.state().gns_driver().driver_context(
+                    global,
+                    |drv| drv.commit_event(txn),
+                    || {},
+                ) {
+                    entry.cancel_restore(
+                        driver,
+                        space_name,
+                        space.get_uuid(),
+                        model_name,
+                        model.get_uuid(),
+                    );
+                    return Err(e);
+                }
+                // update global state
+                let _ = space.models_mut().insert(model_name.into());
+                let mdl_driver = FractalModelDriver::uninitialized();
+                mdl_driver.initialize_model_driver(driver);
+                let _ = global
+                    .state()
+                    .namespace()
+                    .idx_models()
+                    .write()
+                    .insert(
+                        EntityID::new(space_name, model_name),
+                        Model::new(model, mdl_driver),
+                    );
+                info!("restored {space_name}.{model_name} from the trash");
+                if let Err(e) = entry.finish_restore() {
+                    // there's no data left in the entry, so it's just purged later
+                    warn!("failed to remove {space_name}.{model_name} from the trash with error `{e}`");
+                }
+                Ok(())
+            })
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    row expiry
+    ---
+    the rows of a model created with a ttl (`with { ttl: 3600 }`, in seconds) expire that many seconds after they were
+    last written (inserted, upserted or updated). every row keeps the time that it was last written and for such models
+    this is written out along with the row. reads skip expired rows right away but they're only removed by the sweep
+    that runs on the general executor (right before the data batches are written out) so that removing them is logged
+    just like any other delete
+*/
+
+use {
+    super::{delta::DataDeltaKind, ModelData},
+    crate::{
+        engine::{
+            core::index::{PrimaryIndexKey, RowData},
+            data::{dict::DictEntryGeneric, tag::TagClass},
+            error::{QueryError, QueryResult},
+            sync::atm::cpin,
+        },
+        util::os,
+    },
+};
+
+/// The largest ttl (in seconds) that a model can have
+pub const TTL_MAX: u64 = (1 << 48) - 1;
+
+/// Parse the `ttl` property of a model
+pub(super) fn parse_ttl(prop: Option<DictEntryGeneric>) -> QueryResult<Option<u64>> {
+    match prop {
+        Some(DictEntryGeneric::Data(ttl)) if ttl.kind() == TagClass::UnsignedInt => {
+            match ttl.uint() {
+                ttl @ 1..=TTL_MAX => Ok(Some(ttl)),
+                _ => Err(QueryError::QExecDdlInvalidProperties),
+            }
+        }
+        Some(_) => Err(QueryError::QExecDdlInvalidProperties),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Decides if a row has expired, as of when it was created (see [`ModelData::expiry`])
+pub struct Expiry {
+    cutoff: Option<u64>,
+}
+
+impl Expiry {
+    pub fn is_expired(&self, row: &RowData) -> bool {
+        self.cutoff.is_some_and(|cutoff| row.written_at() <= cutoff)
+    }
+}
+
+impl ModelData {
+    /// The number of seconds after which a row expires (if rows expire at all)
+    pub fn ttl(&self) -> Option<u64> {
+        self.ttl
+    }
+    /// Returns the rows that have expired by now. Get this once for a statement rather than for every row
+    pub fn expiry(&self) -> Expiry {
+        Expiry {
+            cutoff: self
+                .ttl
+                .map(|ttl| ((os::get_epoch_time() / 1_000_000_000) as u64).saturating_sub(ttl)),
+        }
+    }
+    /// Delete every expired row. Returns the number of rows that were deleted
+    pub fn sweep_expired(&self) -> usize {
+        if self.ttl.is_none() {
+            return 0;
+        }
+        let expiry = self.expiry();
+        let g = cpin();
+        let idx = self.primary_index();
+        // no insert can take the place of an expired row while we're at it
+        let _latch = idx.acquire_exclusive();
+        let expired: Vec<PrimaryIndexKey> = idx
+            .__raw_index()
+            .mt_iter_entry(&g)
+            .filter(|row| expiry.is_expired(&row.d_data().read()))
+            .map(|row| row.d_key().clone())
+            .collect();
+        let ds = self.delta_state();
+        let mut swept = 0;
+        for key in expired {
+            let Some(row) = idx.__raw_index().mt_get_element(&key, &g) else {
+                continue;
+            };
+            // hold the row lock so that an update can't make the row live again while we remove it
+            let row_data = row.d_data().write();
+            if !expiry.is_expired(&row_data) {
+                continue;
+            }
+            let new_version = ds.create_new_data_delta_version();
+            let _removed = idx.__raw_index().mt_delete(&key, &g);
+            debug_assert!(_removed);
+            drop(row_data);
+            self.columnar_cache_remove(row.d_key());
+            self.secondary_indexes_remove(row.d_key());
+            self.unique_release(row.d_key());
+            ds.append_new_data_delta_with(DataDeltaKind::Delete, row.clone(), new_version, &g);
+            swept += 1;
+        }
+        swept
+    }
+}
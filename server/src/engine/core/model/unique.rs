@@ -0,0 +1,171 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    unique fields
+    ---
+    no two rows of a model can have the same (non-null) value in a unique field. like a secondary index, a unique field
+    is backed by a map from its values to the keys of the rows that have them, but a write claims the values of a row
+    *before* it changes the row, so a write that would duplicate a value fails without having changed anything. any
+    number of rows can have a null in a unique field.
+
+    the index never reads a row (the writer hands it the new values instead), so no other lock is ever taken while its
+    lock is held. to keep the claims in line with the rows, inserts, upserts and deletes on a model with unique fields
+    hold the index latch exclusively while updates hold it shared (and the row lock), so no two writes can ever race for
+    the same row. an expired row keeps its values until it is removed or replaced.
+
+    only the declaration is persisted (as a part of the field). the entries are built again from the rows whenever the
+    model is loaded
+*/
+
+use {
+    super::{
+        secondary::{IndexedValue, RowKey},
+        ModelData,
+    },
+    crate::engine::{
+        core::index::{DcFieldIndex, PrimaryIndexKey},
+        error::{QueryError, QueryResult},
+        idx::{STIndex, STIndexSeq},
+        sync::atm::Guard,
+    },
+    std::collections::HashMap,
+};
+
+#[derive(Debug)]
+/// The values of a unique field
+pub struct UniqueIndex {
+    field: Box<str>,
+    keys: HashMap<IndexedValue, RowKey>,
+    values: HashMap<RowKey, IndexedValue>,
+}
+
+impl UniqueIndex {
+    fn new(field: Box<str>) -> Self {
+        Self {
+            field,
+            keys: HashMap::new(),
+            values: HashMap::new(),
+        }
+    }
+    fn value_of(&self, data: &DcFieldIndex) -> Option<IndexedValue> {
+        data.st_get(self.field.as_ref())
+            .and_then(IndexedValue::from_dc)
+    }
+    /// Returns true if the value is used by a row other than the given one
+    fn conflicts(&self, key: &RowKey, value: Option<&IndexedValue>) -> bool {
+        value
+            .and_then(|value| self.keys.get(value))
+            .is_some_and(|owner| owner != key)
+    }
+    /// Set the value of a row (a null releases it) and return its previous value
+    fn set(&mut self, key: &RowKey, value: Option<IndexedValue>) -> Option<IndexedValue> {
+        let old = self.values.remove(key);
+        if let Some(ref old) = old {
+            self.keys.remove(old);
+        }
+        if let Some(value) = value {
+            self.keys.insert(value.clone(), key.clone());
+            self.values.insert(key.clone(), value);
+        }
+        old
+    }
+}
+
+impl ModelData {
+    /// Returns true if any field of the model is unique
+    pub fn has_unique_fields(&self) -> bool {
+        !self.unique.read().is_empty()
+    }
+    /// Claim the values of the unique fields (if any) for the given rows, in order (so a later row with the same key
+    /// replaces the values of an earlier one). If a value is used by any other row, nothing is claimed
+    pub fn unique_claim<'a>(
+        &self,
+        rows: impl IntoIterator<Item = (&'a PrimaryIndexKey, &'a DcFieldIndex)>,
+    ) -> QueryResult<()> {
+        if self.unique.read().is_empty() {
+            return Ok(());
+        }
+        let mut indexes = self.unique.write();
+        let mut claimed: Vec<(RowKey, usize, Option<IndexedValue>)> = Vec::new();
+        for (key, data) in rows {
+            let key = RowKey(key.clone());
+            let values: Vec<_> = indexes.iter().map(|index| index.value_of(data)).collect();
+            let conflict = indexes
+                .iter()
+                .zip(values.iter())
+                .any(|(index, value)| index.conflicts(&key, value.as_ref()));
+            if conflict {
+                // give back what the rows before this one claimed (in reverse, since a row may have taken a value
+                // that an earlier row gave up)
+                for (key, i, old) in claimed.into_iter().rev() {
+                    indexes[i].set(&key, old);
+                }
+                return Err(QueryError::QExecDmlUniqueViolation);
+            }
+            for (i, value) in values.into_iter().enumerate() {
+                let old = indexes[i].set(&key, value);
+                claimed.push((key.clone(), i, old));
+            }
+        }
+        Ok(())
+    }
+    /// Release the values of the unique fields (if any) after a row was removed
+    pub fn unique_release(&self, key: &PrimaryIndexKey) {
+        if self.unique.read().is_empty() {
+            return;
+        }
+        let key = RowKey(key.clone());
+        for index in self.unique.write().iter_mut() {
+            index.set(&key, None);
+        }
+    }
+    /// Build the unique indexes again from the declared fields and the rows. No other write can run on the model while
+    /// this runs
+    pub(super) fn unique_rebuild(&mut self, g: &Guard) {
+        let mut indexes: Vec<_> = self
+            .fields()
+            .stseq_ord_kv()
+            .filter(|(_, field)| field.is_unique())
+            .map(|(name, _)| UniqueIndex::new(name.as_str().into()))
+            .collect();
+        if !indexes.is_empty() {
+            for row in self.primary_index().__raw_index().mt_iter_entry(g) {
+                let data = row.resolve_schema_deltas_and_freeze(self.delta_state());
+                let key = RowKey(row.d_key().clone());
+                for index in indexes.iter_mut() {
+                    let value = index.value_of(data.fields());
+                    // the rows can't have duplicates unless they were written without the constraint, in which case
+                    // the first row keeps the value
+                    if !index.conflicts(&key, value.as_ref()) {
+                        index.set(&key, value);
+                    }
+                }
+            }
+        }
+        *self.unique.get_mut() = indexes;
+    }
+}
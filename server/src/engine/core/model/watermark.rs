@@ -0,0 +1,61 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    watermarks
+    ---
+    change data consumers need to know how far the data of a model has been committed. every data delta has a version
+    (its txn id in the data file) and once a batch is synced, the version of the latest delta in it becomes the
+    committed watermark of the model. watermarks only ever go up, and they're rebuilt from the data file on startup.
+
+    since the versions of different models can't be compared, there's also a global logical clock. it's a hybrid
+    clock: it ticks whenever a watermark advances (and whenever it's read) and it never goes below the wall clock (in
+    microseconds) so that it also keeps going up across restarts. reading the clock reserves a tick, so every watermark
+    that advances after a read has a later clock
+*/
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// Advance the global clock, returning the new time
+pub fn tick() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0);
+    let mut current = CLOCK.load(Ordering::Acquire);
+    loop {
+        let next = (current + 1).max(now);
+        match CLOCK.compare_exchange_weak(current, next, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => return next,
+            Err(actual) => current = actual,
+        }
+    }
+}
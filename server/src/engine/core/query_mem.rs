@@ -0,0 +1,151 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    query memory accounting
+    ---
+    memory used by a query is charged against a per-query limit and a global limit shared by all running queries. that
+    is the result buffer, along with the buffers that a query only needs while it runs: the rows that an `order by` has
+    to collect before it can sort them and the groups of a `group by`. the per-query limit applies to the sum of all
+    of them. a query that goes over its own limit fails with `QExecQueryMemoryLimit` while a query that would push the
+    server over the global limit fails with `SysOutOfMemory`; the client can retry the latter later. the charge for a
+    working buffer is released as soon as the query is done with it while the charge for the result buffer is released
+    once the result has been written. a limit of zero means that there is no limit
+*/
+
+use {
+    crate::engine::error::{QueryError, QueryResult},
+    std::{
+        cell::Cell,
+        mem,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+static QUERY_LIMIT: AtomicUsize = AtomicUsize::new(0);
+static GLOBAL_LIMIT: AtomicUsize = AtomicUsize::new(0);
+static GLOBAL_IN_USE: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the per-query and global limits (in bytes)
+pub fn set_limits(query: Option<u64>, global: Option<u64>) {
+    QUERY_LIMIT.store(query.unwrap_or(0) as usize, Ordering::Release);
+    GLOBAL_LIMIT.store(global.unwrap_or(0) as usize, Ordering::Release);
+}
+
+/// The number of bytes that the elements of `v` (can) take up
+pub fn vec_size<T>(v: &Vec<T>) -> usize {
+    v.capacity() * mem::size_of::<T>()
+}
+
+#[derive(Debug, PartialEq)]
+/// The memory charged to a single query. Everything is released when this is dropped
+pub struct QueryMemory {
+    /// the per-query limit when the query started
+    limit: usize,
+    /// the charge for the result buffer
+    result: Cell<usize>,
+    /// the charge for the working buffers (see [`BufferCharge`])
+    working: Cell<usize>,
+}
+
+impl QueryMemory {
+    pub fn new() -> Self {
+        Self::with_limit(QUERY_LIMIT.load(Ordering::Acquire))
+    }
+    /// A query with its own per-query limit (the global limit still applies)
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit,
+            result: Cell::new(0),
+            working: Cell::new(0),
+        }
+    }
+    /// Make sure that we've charged for at least `size` bytes of the result buffer (typically, its capacity after it
+    /// grew)
+    pub fn charge_upto(&self, size: usize) -> QueryResult<()> {
+        let charged = self.result.get();
+        if size <= charged {
+            return Ok(());
+        }
+        self.charge(size - charged)?;
+        self.result.set(size);
+        Ok(())
+    }
+    /// Start charging a working buffer to this query
+    pub fn working_buffer(&self) -> BufferCharge<'_> {
+        BufferCharge {
+            mem: self,
+            charged: 0,
+        }
+    }
+    fn charge(&self, delta: usize) -> QueryResult<()> {
+        let total = self.result.get() + self.working.get() + delta;
+        if (self.limit != 0) & (total > self.limit) {
+            return Err(QueryError::QExecQueryMemoryLimit);
+        }
+        let global_limit = GLOBAL_LIMIT.load(Ordering::Acquire);
+        let in_use = GLOBAL_IN_USE.fetch_add(delta, Ordering::AcqRel);
+        if (global_limit != 0) & (in_use + delta > global_limit) {
+            GLOBAL_IN_USE.fetch_sub(delta, Ordering::AcqRel);
+            return Err(QueryError::SysOutOfMemory);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for QueryMemory {
+    fn drop(&mut self) {
+        GLOBAL_IN_USE.fetch_sub(self.result.get() + self.working.get(), Ordering::AcqRel);
+    }
+}
+
+/// The charge for a buffer that a query only needs while it runs (and that counts towards the query's limit along
+/// with the result buffer). The charge is released when this is dropped
+pub struct BufferCharge<'a> {
+    mem: &'a QueryMemory,
+    charged: usize,
+}
+
+impl BufferCharge<'_> {
+    /// Make sure that we've charged for at least `size` bytes
+    pub fn charge_upto(&mut self, size: usize) -> QueryResult<()> {
+        if size <= self.charged {
+            return Ok(());
+        }
+        let delta = size - self.charged;
+        self.mem.charge(delta)?;
+        self.mem.working.set(self.mem.working.get() + delta);
+        self.charged = size;
+        Ok(())
+    }
+}
+
+impl Drop for BufferCharge<'_> {
+    fn drop(&mut self) {
+        self.mem.working.set(self.mem.working.get() - self.charged);
+        GLOBAL_IN_USE.fetch_sub(self.charged, Ordering::AcqRel);
+    }
+}
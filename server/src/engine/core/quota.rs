@@ -0,0 +1,215 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    write quotas
+    ---
+    every user (except root) gets its own token buckets for write queries and for the bytes sent with write queries,
+    refilled every second up to the configured limit. so, a user that is running a bulk job can only use up its own
+    quota and not that of everyone else on the server. a write is let through as long as the buckets aren't empty
+    (even if it is larger than what's left) and the difference is paid back over the next few seconds. a limit of
+    zero means that there is no limit. a user can also have its own limits (set with `sysctl alter user`), which
+    are stored with the user and take the place of the server-wide limits
+*/
+
+use {
+    crate::engine::error::{QueryError, QueryResult},
+    parking_lot::{Mutex, RwLock},
+    std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            OnceLock,
+        },
+        time::Instant,
+    },
+};
+
+static WRITE_OPS_LIMIT: AtomicU64 = AtomicU64::new(0);
+static WRITE_BYTES_LIMIT: AtomicU64 = AtomicU64::new(0);
+static BUCKETS: OnceLock<RwLock<HashMap<Box<str>, Mutex<UserBuckets>>>> = OnceLock::new();
+
+/// Set the per-user limits for writes and write bytes per second
+pub fn set_limits(ops: Option<u64>, bytes: Option<u64>) {
+    WRITE_OPS_LIMIT.store(ops.unwrap_or(0), Ordering::Release);
+    WRITE_BYTES_LIMIT.store(bytes.unwrap_or(0), Ordering::Release);
+}
+
+/// Charge a write of `size` bytes against the user's quota (using the user's own limits, if it has any)
+pub fn charge_write(username: &str, limits: UserWriteLimits, size: usize) -> QueryResult<()> {
+    let ops_limit = limits
+        .ops()
+        .unwrap_or_else(|| WRITE_OPS_LIMIT.load(Ordering::Acquire));
+    let bytes_limit = limits
+        .bytes()
+        .unwrap_or_else(|| WRITE_BYTES_LIMIT.load(Ordering::Acquire));
+    if (ops_limit == 0) & (bytes_limit == 0) {
+        return Ok(());
+    }
+    let buckets = BUCKETS.get_or_init(Default::default);
+    let now = Instant::now();
+    let charge = |user: &Mutex<UserBuckets>| {
+        let mut user = user.lock();
+        let ops_okay = user.ops.refill(ops_limit, now);
+        let bytes_okay = user.bytes.refill(bytes_limit, now);
+        if ops_okay & bytes_okay {
+            user.ops.take(1);
+            user.bytes.take(size as u64);
+            Ok(())
+        } else {
+            Err(QueryError::SysWriteQuotaExceeded)
+        }
+    };
+    if let Some(user) = buckets.read().get(username) {
+        return charge(user);
+    }
+    let mut buckets = buckets.write();
+    let user = buckets
+        .entry(username.into())
+        .or_insert_with(|| Mutex::new(UserBuckets::new(ops_limit, bytes_limit, now)));
+    charge(user)
+}
+
+/// Forget the quota state of a user (for example, when the user is dropped)
+pub fn forget_user(username: &str) {
+    if let Some(buckets) = BUCKETS.get() {
+        buckets.write().remove(username);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+/// The write limits of a single user. A limit that isn't set falls back to the server-wide limit (and like that
+/// one, zero means that there is no limit)
+pub struct UserWriteLimits {
+    ops: Option<u64>,
+    bytes: Option<u64>,
+}
+
+impl UserWriteLimits {
+    pub fn new(ops: Option<u64>, bytes: Option<u64>) -> Self {
+        Self { ops, bytes }
+    }
+    pub fn ops(&self) -> Option<u64> {
+        self.ops
+    }
+    pub fn bytes(&self) -> Option<u64> {
+        self.bytes
+    }
+    pub fn set_ops(&mut self, ops: Option<u64>) {
+        self.ops = ops;
+    }
+    pub fn set_bytes(&mut self, bytes: Option<u64>) {
+        self.bytes = bytes;
+    }
+}
+
+#[derive(Debug)]
+struct UserBuckets {
+    ops: TokenBucket,
+    bytes: TokenBucket,
+}
+
+impl UserBuckets {
+    fn new(ops_limit: u64, bytes_limit: u64, now: Instant) -> Self {
+        Self {
+            ops: TokenBucket::new(ops_limit, now),
+            bytes: TokenBucket::new(bytes_limit, now),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A token bucket that can hold a second worth of tokens. The bucket can go into debt
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: u64, now: Instant) -> Self {
+        Self {
+            tokens: limit as f64,
+            refilled_at: now,
+        }
+    }
+    /// Add the tokens accrued since the last refill and return true if there are any tokens left. A bucket
+    /// without a limit always has tokens
+    fn refill(&mut self, limit: u64, now: Instant) -> bool {
+        if limit == 0 {
+            return true;
+        }
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit as f64).min(limit as f64);
+        self.refilled_at = now;
+        self.tokens > 0.0
+    }
+    fn take(&mut self, count: u64) {
+        self.tokens -= count as f64;
+    }
+}
+
+#[test]
+fn token_bucket() {
+    use std::time::Duration;
+    let start = Instant::now();
+    let mut bucket = TokenBucket::new(10, start);
+    for _ in 0..10 {
+        assert!(bucket.refill(10, start));
+        bucket.take(1);
+    }
+    assert!(!bucket.refill(10, start));
+    // after half a second, half the bucket is back
+    assert!(bucket.refill(10, start + Duration::from_millis(500)));
+    assert_eq!(bucket.tokens, 5.0);
+    // a large write goes through but puts the bucket into debt
+    bucket.take(25);
+    assert!(!bucket.refill(10, start + Duration::from_secs(2)));
+    assert!(bucket.refill(10, start + Duration::from_secs(3)));
+    // no limit
+    assert!(TokenBucket::new(0, start).refill(0, start));
+}
+
+#[test]
+fn user_limits_override() {
+    // there's no server-wide limit (the tests don't set one)
+    for _ in 0..10 {
+        assert!(charge_write("quota_test_a", UserWriteLimits::default(), 1 << 20).is_ok());
+    }
+    // the user's own limit
+    let limits = UserWriteLimits::new(None, Some(100));
+    assert!(charge_write("quota_test_b", limits, 150).is_ok());
+    assert_eq!(
+        charge_write("quota_test_b", limits, 1),
+        Err(QueryError::SysWriteQuotaExceeded)
+    );
+    // no limit for this user
+    let limits = UserWriteLimits::new(Some(0), Some(0));
+    for _ in 0..10 {
+        assert!(charge_write("quota_test_c", limits, 1 << 20).is_ok());
+    }
+}
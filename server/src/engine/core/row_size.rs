@@ -0,0 +1,269 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    row size limits
+    ---
+    a row is written out as a single event in a data batch, so one giant row can hold up the batch (and everything
+    else that is waiting on it). inserts and updates check every string and binary value (including the ones in a
+    list) against the field limit and the encoded size of the whole row against the row limit. a query that goes
+    over the field limit fails with `QExecDmlFieldTooLarge` and one that goes over the row limit fails with
+    `QExecDmlRowTooLarge`. a limit of zero means that there is no limit
+*/
+
+use {
+    crate::engine::{
+        core::index::{DcFieldIndex, PrimaryIndexKey},
+        data::{
+            cell::Datacell,
+            tag::{DataTag, TagClass, TagUnique},
+        },
+        error::{QueryError, QueryResult},
+        idx::STIndex,
+    },
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+static ROW_LIMIT: AtomicU64 = AtomicU64::new(0);
+static FIELD_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Set the row and field size limits (in bytes)
+pub fn set_limits(row: Option<u64>, field: Option<u64>) {
+    ROW_LIMIT.store(row.unwrap_or(0), Ordering::Release);
+    FIELD_LIMIT.store(field.unwrap_or(0), Ordering::Release);
+}
+
+/// Check a new row, given all its cells (including the primary key)
+pub fn check_cells<'a>(cells: impl Iterator<Item = &'a Datacell>) -> QueryResult<()> {
+    match Sizer::new() {
+        Some(sizer) => sizer.check_cells(cells),
+        None => Ok(()),
+    }
+}
+
+/// Check an existing row (for example, after it was updated)
+pub fn check_row(pk: &PrimaryIndexKey, fields: &DcFieldIndex) -> QueryResult<()> {
+    match Sizer::new() {
+        Some(sizer) => sizer.check_row(pk, fields),
+        None => Ok(()),
+    }
+}
+
+struct Sizer {
+    row_limit: u64,
+    field_limit: u64,
+    size: u64,
+}
+
+impl Sizer {
+    fn new() -> Option<Self> {
+        let row_limit = ROW_LIMIT.load(Ordering::Acquire);
+        let field_limit = FIELD_LIMIT.load(Ordering::Acquire);
+        if (row_limit == 0) & (field_limit == 0) {
+            return None;
+        }
+        Some(Self::with_limits(row_limit, field_limit))
+    }
+    const fn with_limits(row_limit: u64, field_limit: u64) -> Self {
+        Self {
+            row_limit,
+            field_limit,
+            size: 0,
+        }
+    }
+    fn check_cells<'a>(mut self, cells: impl Iterator<Item = &'a Datacell>) -> QueryResult<()> {
+        for cell in cells {
+            self.add(cell)?;
+        }
+        self.finish()
+    }
+    fn check_row(mut self, pk: &PrimaryIndexKey, fields: &DcFieldIndex) -> QueryResult<()> {
+        // [tag][value]
+        self.size = 1 + match pk.tag() {
+            TagUnique::UnsignedInt | TagUnique::SignedInt => sizeof!(u64) as u64,
+            TagUnique::Str | TagUnique::Bin => {
+                let len = unsafe {
+                    // UNSAFE(@ohsayan): +tagck
+                    pk.read_bin()
+                }
+                .len() as u64;
+                self.check_field(len)?;
+                sizeof!(u64) as u64 + len
+            }
+            TagUnique::Illegal => unsafe {
+                // UNSAFE(@ohsayan): a pk can't be constructed with illegal
+                impossible!()
+            },
+        };
+        for cell in fields.st_iter_value() {
+            self.add(cell)?;
+        }
+        self.finish()
+    }
+    fn check_field(&self, len: u64) -> QueryResult<()> {
+        if (self.field_limit != 0) & (len > self.field_limit) {
+            Err(QueryError::QExecDmlFieldTooLarge)
+        } else {
+            Ok(())
+        }
+    }
+    /// Add the encoded size of the cell (this is the same as what the data batch encoder writes)
+    fn add(&mut self, dc: &Datacell) -> QueryResult<()> {
+        // [tag]
+        self.size += 1;
+        if dc.is_null() {
+            return Ok(());
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            match dc.tag().tag_class() {
+                TagClass::Bool => self.size += dc.is_init() as u64,
+                TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float => {
+                    self.size += sizeof!(u64) as u64
+                }
+                TagClass::Str | TagClass::Bin => {
+                    let len = dc.read_bin().len() as u64;
+                    self.check_field(len)?;
+                    self.size += sizeof!(u64) as u64 + len;
+                }
+                TagClass::List => {
+                    let list = dc.read_list().read();
+                    self.size += sizeof!(u64) as u64;
+                    for item in list.iter() {
+                        self.add(item)?;
+                    }
+                }
+                TagClass::Map => {
+                    let map = dc.read_map().read();
+                    self.size += sizeof!(u64) as u64;
+                    for (key, value) in map.iter() {
+                        self.size += sizeof!(u64) as u64 + key.len() as u64;
+                        self.add(value)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    fn finish(self) -> QueryResult<()> {
+        if (self.row_limit != 0) & (self.size > self.row_limit) {
+            Err(QueryError::QExecDmlRowTooLarge)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Sizer,
+        crate::engine::{
+            core::index::{DcFieldIndex, PrimaryIndexKey},
+            data::cell::Datacell,
+            error::QueryError,
+            idx::STIndex,
+        },
+    };
+
+    fn sample_row() -> Vec<Datacell> {
+        vec![
+            Datacell::new_str("sayan".into()),
+            Datacell::new_uint_default(100),
+            Datacell::null(),
+            Datacell::new_bool(true),
+            Datacell::new_bin(b"binary".to_vec().into_boxed_slice()),
+            Datacell::new_list(vec![
+                Datacell::new_str("a".into()),
+                Datacell::new_str("bb".into()),
+            ]),
+        ]
+    }
+
+    /// the encoded size of [`sample_row`]
+    const SAMPLE_ROW_SIZE: u64 = {
+        let str = 1 + 8 + 5;
+        let uint = 1 + 8;
+        let null = 1;
+        let bool = 1 + 1;
+        let bin = 1 + 8 + 6;
+        let list = 1 + 8 + (1 + 8 + 1) + (1 + 8 + 2);
+        str + uint + null + bool + bin + list
+    };
+
+    #[test]
+    fn size_is_encoded_size() {
+        let mut sizer = Sizer::with_limits(0, 0);
+        sample_row().iter().for_each(|dc| sizer.add(dc).unwrap());
+        assert_eq!(sizer.size, SAMPLE_ROW_SIZE);
+    }
+
+    #[test]
+    fn row_limit() {
+        let row = sample_row();
+        let size = SAMPLE_ROW_SIZE;
+        assert_eq!(Sizer::with_limits(size, 0).check_cells(row.iter()), Ok(()));
+        assert_eq!(
+            Sizer::with_limits(size - 1, 0).check_cells(row.iter()),
+            Err(QueryError::QExecDmlRowTooLarge)
+        );
+    }
+
+    #[test]
+    fn field_limit() {
+        let row = sample_row();
+        // `binary` is the largest value
+        assert_eq!(Sizer::with_limits(0, 6).check_cells(row.iter()), Ok(()));
+        assert_eq!(
+            Sizer::with_limits(0, 5).check_cells(row.iter()),
+            Err(QueryError::QExecDmlFieldTooLarge)
+        );
+        // list elements are checked too
+        let list = [Datacell::new_list(vec![Datacell::new_str("abcdef".into())])];
+        assert_eq!(
+            Sizer::with_limits(0, 5).check_cells(list.iter()),
+            Err(QueryError::QExecDmlFieldTooLarge)
+        );
+    }
+
+    #[test]
+    fn existing_row() {
+        let pk = PrimaryIndexKey::try_from_dc(Datacell::new_str("sayan".into())).unwrap();
+        let mut fields = DcFieldIndex::default();
+        fields.st_insert("password".into(), Datacell::new_str("pass123".into()));
+        // [tag][len]sayan + [tag][len]pass123
+        assert_eq!(Sizer::with_limits(30, 0).check_row(&pk, &fields), Ok(()));
+        assert_eq!(
+            Sizer::with_limits(29, 0).check_row(&pk, &fields),
+            Err(QueryError::QExecDmlRowTooLarge)
+        );
+        // the primary key is a string too
+        assert_eq!(
+            Sizer::with_limits(0, 4).check_row(&pk, &fields),
+            Err(QueryError::QExecDmlFieldTooLarge)
+        );
+    }
+}
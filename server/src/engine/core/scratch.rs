@@ -0,0 +1,110 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    per-connection scratch space
+    ---
+    every statement needs a token buffer (to lex the query) and most reads need a buffer for their response, and both
+    are thrown away as soon as the response has been written. instead, every connection (or session, on a multiplexed
+    connection) keeps them in its scratch space: they are reset (but keep their capacity) after every statement, so a
+    connection that has warmed up doesn't go to the allocator for them at all. (short statements fit into the inline
+    part of the token buffer anyway; keeping it around is what saves the allocations for long ones.)
+
+    the executors don't have access to the connection, so the response buffer is handed to a statement for as long as
+    it runs on a thread (just like cancellation flags). a buffer that has grown beyond `MAX_RETAINED` bytes is dropped
+    instead of being kept, so that one large result doesn't stay pinned to an idle connection
+*/
+
+use {
+    crate::engine::ql::lex::{Token, TokenBuf},
+    std::{cell::RefCell, mem},
+};
+
+/// A buffer with a capacity larger than this is freed instead of being kept for the next statement
+const MAX_RETAINED: usize = 64 * 1024;
+
+thread_local! {
+    /// The response buffer of the statement that is running on this thread (if it hasn't taken it yet)
+    static RESPONSE: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Default, PartialEq)]
+/// The buffers that a connection reuses across statements
+pub struct Scratch {
+    tokens: TokenBuf<'static>,
+    response: Vec<u8>,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Take the token buffer for the next statement (it is always empty)
+    pub fn take_tokens<'a>(&mut self) -> TokenBuf<'a> {
+        let tokens = mem::take(&mut self.tokens);
+        unsafe {
+            // UNSAFE(@ohsayan): the buffer is empty, so it doesn't hold anything with the wrong lifetime
+            mem::transmute::<TokenBuf<'static>, TokenBuf<'a>>(tokens)
+        }
+    }
+    /// Give back the token buffer once the statement is done with its tokens
+    pub fn recycle_tokens(&mut self, mut tokens: TokenBuf) {
+        tokens.clear();
+        if tokens.capacity() * sizeof!(Token<'_>) > MAX_RETAINED {
+            return;
+        }
+        self.tokens = unsafe {
+            // UNSAFE(@ohsayan): the buffer is empty, so nothing that it holds can outlive the data it borrowed from
+            mem::transmute::<TokenBuf<'_>, TokenBuf<'static>>(tokens)
+        };
+    }
+    /// Take the response buffer for the next statement (it is always empty)
+    pub fn take_response_buffer(&mut self) -> Vec<u8> {
+        mem::take(&mut self.response)
+    }
+    /// Give back a response buffer once it has been written (or if the statement didn't use it)
+    pub fn recycle_response_buffer(&mut self, mut buf: Vec<u8>) {
+        if (buf.capacity() <= MAX_RETAINED) & (buf.capacity() > self.response.capacity()) {
+            buf.clear();
+            self.response = buf;
+        }
+    }
+}
+
+/// Run `f` on this thread with `buf` as the buffer that the statement can use for its response (see
+/// [`response_buffer`]). The buffer is returned if the statement didn't take it
+pub fn scope<T>(buf: Vec<u8>, f: impl FnOnce() -> T) -> (T, Option<Vec<u8>>) {
+    let prev = RESPONSE.with(|r| r.replace(Some(buf)));
+    let ret = f();
+    let unused = RESPONSE.with(|r| r.replace(prev));
+    (ret, unused)
+}
+
+/// Returns an empty buffer for the response of the statement that is running on this thread, reusing the buffer of
+/// its connection if it has one
+pub fn response_buffer() -> Vec<u8> {
+    RESPONSE.with(|r| r.borrow_mut().take()).unwrap_or_default()
+}
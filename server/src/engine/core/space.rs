@@ -25,16 +25,19 @@
 */
 
 use {
-    super::EntityIDRef,
+    super::{dml, EntityIDRef},
     crate::engine::{
-        data::{dict, uuid::Uuid, DictEntryGeneric, DictGeneric},
+        data::{cell::Datacell, dict, uuid::Uuid, DictEntryGeneric, DictGeneric},
         error::{QueryError, QueryResult},
         fractal::{GenericTask, GlobalInstanceLike, Task},
         idx::STIndex,
+        mem::IntegerRepr,
+        net::protocol::{Response, ResponseType},
         ql::ddl::{alt::AlterSpace, crt::CreateSpace, drop::DropSpace},
+        storage::safe_interfaces::{paths_v1, FileSystem},
         txn::{self, SpaceIDRef},
     },
-    std::collections::HashSet,
+    std::{collections::HashSet, path::Path},
 };
 
 #[derive(Debug, PartialEq)]
@@ -52,6 +55,74 @@ struct ProcedureCreate {
     if_not_exists: bool,
 }
 
+#[derive(Debug, PartialEq)]
+/// A model that was removed along with its space
+pub struct DroppedModel {
+    name: Box<str>,
+    indexes: Vec<Box<str>>,
+    rows: u64,
+}
+
+impl DroppedModel {
+    fn new(name: Box<str>, indexes: Vec<Box<str>>, rows: u64) -> Self {
+        Self {
+            name,
+            indexes,
+            rows,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_test(name: &str, indexes: &[&str], rows: u64) -> Self {
+        Self::new(
+            name.into(),
+            indexes.iter().map(|index| (*index).into()).collect(),
+            rows,
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// The result of a `drop space`
+pub enum DropSpaceResult {
+    /// the space was empty (or didn't exist); this is the same as any other DDL query
+    Status(Option<bool>),
+    /// the space was dropped with `allow not empty`, along with these models (sorted by name)
+    Report(Vec<DroppedModel>),
+}
+
+impl DropSpaceResult {
+    /// The report is returned as a row for every model that was removed: the model name, its secondary indexes and
+    /// the number of rows that it had
+    pub fn into_response(self) -> Response {
+        match self {
+            Self::Status(Some(b)) => Response::Bool(b),
+            Self::Status(None) => Response::Empty,
+            Self::Report(models) => {
+                let mut data = vec![];
+                let size = models.len();
+                for DroppedModel {
+                    name,
+                    indexes,
+                    rows,
+                } in models
+                {
+                    IntegerRepr::scoped(3u64, |repr| data.extend(repr));
+                    data.push(b'\n');
+                    dml::encode_cell(&mut data, &Datacell::new_str(name));
+                    let indexes = indexes.into_iter().map(Datacell::new_str).collect();
+                    dml::encode_cell(&mut data, &Datacell::new_list(indexes));
+                    dml::encode_cell(&mut data, &Datacell::new_uint_default(rows));
+                }
+                Response::Serialized {
+                    ty: ResponseType::MultiRow,
+                    size,
+                    data,
+                }
+            }
+        }
+    }
+}
+
 impl Space {
     pub fn new(uuid: Uuid, models: HashSet<Box<str>>, props: DictGeneric) -> Self {
         Self {
@@ -88,6 +159,19 @@ impl Space {
     pub fn props_mut(&mut self) -> &mut DictGeneric {
         &mut self.props
     }
+    /// Returns the custom storage root for this space, if one was set during creation
+    pub fn storage_path(&self) -> Option<&str> {
+        match self.props.get(Self::KEY_STORAGE_PATH) {
+            Some(DictEntryGeneric::Data(d)) => d.try_str(),
+            _ => None,
+        }
+    }
+    /// Make the storage layer aware of this space's custom storage root (if any)
+    pub fn register_storage_path(&self) {
+        if let Some(root) = self.storage_path() {
+            paths_v1::set_space_root(self.uuid, root);
+        }
+    }
     #[cfg(test)]
     pub fn env(&self) -> &DictGeneric {
         match self.props().get(Self::KEY_ENV).unwrap() {
@@ -99,6 +183,22 @@ impl Space {
 
 impl Space {
     const KEY_ENV: &'static str = "env";
+    const KEY_STORAGE_PATH: &'static str = "storage_path";
+    /// Validate a custom storage root. It must be an absolute path to an existing directory
+    fn process_storage_path(path: Option<DictEntryGeneric>) -> QueryResult<Option<Box<str>>> {
+        let path = match path {
+            None => return Ok(None),
+            Some(DictEntryGeneric::Data(d)) => match d.into_str() {
+                Some(path) => path,
+                None => return Err(QueryError::QExecDdlInvalidProperties),
+            },
+            Some(DictEntryGeneric::Map(_)) => return Err(QueryError::QExecDdlInvalidProperties),
+        };
+        if !Path::new(&path).is_absolute() || !FileSystem::is_dir(&path) {
+            return Err(QueryError::QExecDdlInvalidProperties);
+        }
+        Ok(Some(path.into_boxed_str()))
+    }
     #[inline]
     /// Validate a `create` stmt
     fn process_create(
@@ -110,6 +210,7 @@ impl Space {
     ) -> QueryResult<ProcedureCreate> {
         let space_name = space_name.to_string().into_boxed_str();
         // now let's check our props
+        let storage_path = Self::process_storage_path(props.remove(Self::KEY_STORAGE_PATH))?;
         match props.get(Self::KEY_ENV) {
             Some(d) if props.len() == 1 => {
                 match d {
@@ -134,6 +235,12 @@ impl Space {
                 return Err(QueryError::QExecDdlInvalidProperties);
             }
         }
+        if let Some(storage_path) = storage_path {
+            let _ = props.st_insert(
+                Self::KEY_STORAGE_PATH.into(),
+                DictEntryGeneric::Data(Datacell::new_str(storage_path)),
+            );
+        }
         Ok(ProcedureCreate {
             space_name,
             space: Space::new_empty_auto(dict::rflatten_metadata(props)),
@@ -166,16 +273,18 @@ impl Space {
             // prepare txn
             let txn = txn::gns::space::CreateSpaceTxn::new(space.props(), &space_name, &space);
             // try to create space for...the space
-            global.initialize_space(&space_name, space.get_uuid())?;
+            global.initialize_space(&space_name, space.get_uuid(), space.storage_path())?;
+            // the space's directory exists, so the storage layer can look for it under its root
+            space.register_storage_path();
             // commit txn
             global.state().gns_driver().driver_context(
                 global,
                 |drv| drv.commit_event(txn),
                 || {
-                    global.taskmgr_post_standard_priority(Task::new(GenericTask::delete_space_dir(
-                        &space_name,
-                        space.get_uuid(),
-                    )))
+                    global.taskmgr_post_standard_priority(Task::new(
+                        GenericTask::delete_space_dir(&space_name, space.get_uuid()),
+                    ));
+                    paths_v1::remove_space_root(space.get_uuid());
                 },
             )?;
             // update global state
@@ -239,15 +348,15 @@ impl Space {
             force,
             if_exists,
         }: DropSpace,
-    ) -> QueryResult<Option<bool>> {
+    ) -> QueryResult<DropSpaceResult> {
         if force {
             global
                 .state()
                 .namespace()
                 .ddl_with_all_mut(|spaces, models| {
-                    let Some(space) = spaces.remove(space_name.as_str()) else {
+                    let Some(space) = spaces.get(space_name.as_str()) else {
                         if if_exists {
-                            return Ok(Some(false));
+                            return Ok(DropSpaceResult::Status(Some(false)));
                         } else {
                             return Err(QueryError::QExecObjectNotFound);
                         }
@@ -255,17 +364,20 @@ impl Space {
                     // commit drop
                     // prepare txn
                     let txn =
-                        txn::gns::space::DropSpaceTxn::new(SpaceIDRef::new(&space_name, &space));
+                        txn::gns::space::DropSpaceTxn::new(SpaceIDRef::new(&space_name, space));
                     // commit txn
                     global.state().gns_driver().driver_context(
                         global,
                         |drv| drv.commit_event(txn),
                         || {},
                     )?;
+                    let space = spaces.remove(space_name.as_str()).unwrap();
                     // request cleanup
                     global.taskmgr_post_standard_priority(Task::new(
                         GenericTask::delete_space_dir(&space_name, space.get_uuid()),
                     ));
+                    paths_v1::remove_space_root(space.get_uuid());
+                    let mut report = Vec::with_capacity(space.models.len());
                     for model in space.models.into_iter() {
                         let e: EntityIDRef<'static> = unsafe {
                             // UNSAFE(@ohsayan): I want to try what the borrow checker has been trying
@@ -278,19 +390,24 @@ impl Space {
                             // yes this driver had a fault but it's being purged anyway so update global status
                             global.health().report_removal_of_faulty_source();
                         }
+                        let indexes = mdl
+                            .data()
+                            .secondary_indexes()
+                            .read()
+                            .iter()
+                            .map(|index| index.name().into())
+                            .collect();
+                        let rows = mdl.data().primary_index().count() as u64;
+                        report.push(DroppedModel::new(model, indexes, rows));
                     }
-                    let _ = spaces.st_delete(space_name.as_str());
-                    if if_exists {
-                        Ok(Some(true))
-                    } else {
-                        Ok(None)
-                    }
+                    report.sort_by(|a, b| a.name.cmp(&b.name));
+                    Ok(DropSpaceResult::Report(report))
                 })
         } else {
             global.state().namespace().ddl_with_spaces_write(|spaces| {
                 let Some(space) = spaces.get(space_name.as_str()) else {
                     if if_exists {
-                        return Ok(Some(false));
+                        return Ok(DropSpaceResult::Status(Some(false)));
                     } else {
                         return Err(QueryError::QExecObjectNotFound);
                     }
@@ -313,12 +430,9 @@ impl Space {
                     &space_name,
                     space.get_uuid(),
                 )));
+                paths_v1::remove_space_root(space.get_uuid());
                 let _ = spaces.st_delete(space_name.as_str());
-                if if_exists {
-                    Ok(Some(true))
-                } else {
-                    Ok(None)
-                }
+                Ok(DropSpaceResult::Status(if_exists.then_some(true)))
             })
         }
     }
@@ -25,34 +25,65 @@
 */
 
 use {
-    super::RWLIdx,
+    super::{kdf, quota::UserWriteLimits, token::TokenStore, RWLIdx},
     crate::engine::{
         error::{QueryError, QueryResult},
         fractal::GlobalInstanceLike,
-        txn::gns::sysctl::{AlterUserTxn, CreateUserTxn, DropUserTxn},
+        txn::gns::sysctl::{
+            AlterUserLimitsTxn, AlterUserTxn, CreateUserTxn, DropUserTxn, SetReadOnlyTxn,
+        },
+    },
+    parking_lot::RwLock,
+    std::{
+        collections::hash_map::Entry,
+        sync::atomic::{AtomicU64, Ordering},
     },
-    std::collections::hash_map::Entry,
 };
 
+/// Token epochs are never reused (not even across users), so a user that is dropped and created
+/// again can't use the tokens that were issued to the old user
+static TOKEN_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+fn next_token_epoch() -> u64 {
+    TOKEN_EPOCH.fetch_add(1, Ordering::Relaxed)
+}
+
 #[derive(Debug)]
 pub struct SystemDatabase {
     users: RWLIdx<Box<str>, User>,
+    tokens: TokenStore,
+    /// if set, all DDL and DML writes are rejected (set with `sysctl set read_only`)
+    read_only: RwLock<bool>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct User {
     phash: Box<[u8]>,
+    /// tokens are only valid for the epoch they were issued in. This changes when the password is
+    /// changed
+    token_epoch: u64,
+    /// the user's own write limits (see [`quota`](super::quota))
+    write_limits: UserWriteLimits,
 }
 
 impl User {
     pub fn new(password_hash: Box<[u8]>) -> Self {
         Self {
             phash: password_hash,
+            token_epoch: next_token_epoch(),
+            write_limits: UserWriteLimits::default(),
         }
     }
     pub fn hash(&self) -> &[u8] {
         &self.phash
     }
+    pub fn write_limits(&self) -> UserWriteLimits {
+        self.write_limits
+    }
+    fn set_password_hash(&mut self, password_hash: Box<[u8]>) {
+        self.phash = password_hash;
+        self.token_epoch = next_token_epoch();
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -74,32 +105,112 @@ impl SystemDatabase {
     pub fn empty() -> Self {
         Self {
             users: RWLIdx::default(),
+            tokens: TokenStore::new(),
+            read_only: RwLock::new(false),
         }
     }
     pub fn users(&self) -> &RWLIdx<Box<str>, User> {
         &self.users
     }
+    pub fn is_read_only(&self) -> bool {
+        *self.read_only.read()
+    }
+    /// Check the user's password
+    ///
+    /// NB: the KDF is slow (and blocking), so call this from a blocking context
     pub fn verify_user(&self, username: &str, password: &[u8]) -> VerifyUser {
+        // don't hold up every other login (or user DDL) while we hash
+        let Some(hash) = self
+            .users
+            .read()
+            .get(username)
+            .map(|user| user.phash.clone())
+        else {
+            return VerifyUser::NotFound;
+        };
+        if password.is_empty() || !kdf::verify(password, &hash) {
+            VerifyUser::IncorrectPassword
+        } else if username == Self::ROOT_ACCOUNT {
+            VerifyUser::OkayRoot
+        } else {
+            VerifyUser::Okay
+        }
+    }
+    /// Check that the user exists, without a password (for endpoints that don't need one)
+    pub fn trust_user(&self, username: &str) -> VerifyUser {
+        if self.users.read().get(username).is_none() {
+            VerifyUser::NotFound
+        } else if username == Self::ROOT_ACCOUNT {
+            VerifyUser::OkayRoot
+        } else {
+            VerifyUser::Okay
+        }
+    }
+    pub fn verify_user_token(&self, username: &str, token: &[u8]) -> VerifyUser {
+        let Some(epoch) = self.token_epoch(username) else {
+            return VerifyUser::NotFound;
+        };
+        if !self.tokens.verify(username, epoch, token) {
+            VerifyUser::IncorrectPassword
+        } else if username == Self::ROOT_ACCOUNT {
+            VerifyUser::OkayRoot
+        } else {
+            VerifyUser::Okay
+        }
+    }
+    /// Returns the user's own write limits (a user that doesn't exist has none)
+    pub fn write_limits(&self, username: &str) -> UserWriteLimits {
         self.users
             .read()
             .get(username)
-            .map(|user| {
-                if password.is_empty() {
-                    return VerifyUser::IncorrectPassword;
-                }
-                match rcrypt::verify(password, user.hash()) {
-                    Ok(true) => {
-                        if username == Self::ROOT_ACCOUNT {
-                            VerifyUser::OkayRoot
-                        } else {
-                            VerifyUser::Okay
-                        }
-                    }
-                    Ok(false) => VerifyUser::IncorrectPassword,
-                    Err(_) => unreachable!(),
-                }
-            })
-            .unwrap_or(VerifyUser::NotFound)
+            .map(User::write_limits)
+            .unwrap_or_default()
+    }
+    fn token_epoch(&self, username: &str) -> Option<u64> {
+        self.users.read().get(username).map(|user| user.token_epoch)
+    }
+    /// Issue a session token for this user
+    pub fn issue_token(&self, username: &str) -> Option<String> {
+        self.token_epoch(username)
+            .map(|epoch| self.tokens.issue(username, epoch))
+    }
+    /// Revoke a session token that was issued to this user. Returns false if the token is invalid
+    pub fn revoke_token(&self, username: &str, token: &[u8]) -> bool {
+        self.token_epoch(username)
+            .is_some_and(|epoch| self.tokens.revoke(username, epoch, token))
+    }
+    /// Upgrade the user's password hash if it wasn't created with the configured KDF. This should
+    /// only be called after the password was verified
+    ///
+    /// NB: hashing is slow (and blocking), so call this from a blocking context
+    pub fn rehash_if_needed(
+        &self,
+        global: &impl GlobalInstanceLike,
+        username: &str,
+        password: &[u8],
+    ) -> QueryResult<()> {
+        let old_hash = match self.users.read().get(username) {
+            Some(user) if kdf::needs_rehash(user.hash()) => user.phash.clone(),
+            _ => return Ok(()),
+        };
+        // don't hold up every other login (or user DDL) while we hash
+        let password_hash = kdf::hash(password);
+        let mut users = self.users.write();
+        // if the password was changed (or the user dropped) meanwhile, there's nothing left to upgrade
+        let Some(user) = users
+            .get_mut(username)
+            .filter(|user| user.phash == old_hash)
+        else {
+            return Ok(());
+        };
+        global.state().gns_driver().driver_context(
+            global,
+            |drv| drv.commit_event(AlterUserTxn::new(username, &password_hash)),
+            || {},
+        )?;
+        // same password, so the user's tokens remain valid
+        user.phash = password_hash;
+        Ok(())
     }
 }
 
@@ -113,13 +224,25 @@ impl SystemDatabase {
             Entry::Occupied(_) => false,
         }
     }
+    pub fn __raw_set_read_only(&self, read_only: bool) {
+        *self.read_only.write() = read_only;
+    }
     pub fn __raw_delete_user(&self, username: &str) -> bool {
         self.users.write().remove(username).is_some()
     }
     pub fn __raw_alter_user(&self, username: &str, new_password_hash: Box<[u8]>) -> bool {
         match self.users.write().get_mut(username) {
             Some(user) => {
-                user.phash = new_password_hash;
+                user.set_password_hash(new_password_hash);
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn __raw_alter_user_limits(&self, username: &str, limits: UserWriteLimits) -> bool {
+        match self.users.write().get_mut(username) {
+            Some(user) => {
+                user.write_limits = limits;
                 true
             }
             None => false,
@@ -138,13 +261,13 @@ impl SystemDatabase {
         if users.contains_key(&username) {
             return Err(QueryError::SysAuthError);
         }
-        let password_hash = rcrypt::hash(password, rcrypt::DEFAULT_COST).unwrap();
+        let password_hash = kdf::hash(password);
         global.state().gns_driver().driver_context(
             global,
             |drv| drv.commit_event(CreateUserTxn::new(&username, &password_hash)),
             || {},
         )?;
-        users.insert(username, User::new(password_hash.into_boxed_slice()));
+        users.insert(username, User::new(password_hash));
         Ok(())
     }
     pub fn alter_user(
@@ -155,13 +278,35 @@ impl SystemDatabase {
     ) -> QueryResult<()> {
         match self.users.write().get_mut(username) {
             Some(user) => {
-                let password_hash = rcrypt::hash(password, rcrypt::DEFAULT_COST).unwrap();
+                let password_hash = kdf::hash(password);
                 global.state().gns_driver().driver_context(
                     global,
                     |drv| drv.commit_event(AlterUserTxn::new(username, &password_hash)),
                     || {},
                 )?;
-                user.phash = password_hash.into_boxed_slice();
+                user.set_password_hash(password_hash);
+                Ok(())
+            }
+            None => Err(QueryError::SysAuthError),
+        }
+    }
+    /// Change the user's own write limits. The new limits are persisted
+    pub fn alter_user_limits(
+        &self,
+        global: &impl GlobalInstanceLike,
+        username: &str,
+        update: impl FnOnce(&mut UserWriteLimits),
+    ) -> QueryResult<()> {
+        match self.users.write().get_mut(username) {
+            Some(user) => {
+                let mut limits = user.write_limits;
+                update(&mut limits);
+                global.state().gns_driver().driver_context(
+                    global,
+                    |drv| drv.commit_event(AlterUserLimitsTxn::new(username, limits)),
+                    || {},
+                )?;
+                user.write_limits = limits;
                 Ok(())
             }
             None => Err(QueryError::SysAuthError),
@@ -180,4 +325,22 @@ impl SystemDatabase {
         let _ = users.remove(username);
         Ok(())
     }
+    /// Allow or reject all DDL and DML writes. The setting is persisted
+    pub fn set_read_only(
+        &self,
+        global: &impl GlobalInstanceLike,
+        read_only: bool,
+    ) -> QueryResult<()> {
+        let mut current = self.read_only.write();
+        if *current == read_only {
+            return Ok(());
+        }
+        global.state().gns_driver().driver_context(
+            global,
+            |drv| drv.commit_event(SetReadOnlyTxn::new(read_only)),
+            || {},
+        )?;
+        *current = read_only;
+        Ok(())
+    }
 }
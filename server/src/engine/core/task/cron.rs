@@ -0,0 +1,234 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    cron expressions
+    ---
+    a schedule has the five standard fields: `<minute> <hour> <day of month> <month> <day of week>`. a field is `*`,
+    a value, a range (`a-b`) or a list of those (`a,b-c`), and a `*` or a range can have a step (`0-30/10`). sunday
+    is both 0 and 7. like cron, if both the day of the month and the day of the week are restricted then a day
+    matches if either of them does. all times are in UTC
+*/
+
+use {
+    crate::engine::error::{QueryError, QueryResult},
+    chrono::{DateTime, Datelike, Timelike, Utc},
+    std::time::Duration,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+/// A parsed cron expression. Every field is a bitmap of the values that it matches
+pub struct Schedule {
+    src: Box<str>,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    day_or: bool,
+}
+
+impl Schedule {
+    pub fn parse(src: &str) -> QueryResult<Self> {
+        let expr = match src.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expr => expr,
+        };
+        let fields: Vec<&str> = expr.split_ascii_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(QueryError::QLInvalidSyntax);
+        };
+        let weekdays = parse_field(weekday, 0, 7)?;
+        Ok(Self {
+            src: src.into(),
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            // 7 is sunday too
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7F,
+            day_or: !(day.starts_with('*') | weekday.starts_with('*')),
+        })
+    }
+    /// The expression, as it was written
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+    /// Returns true if the schedule is due in the minute that `t` falls in
+    pub fn matches(&self, t: &DateTime<Utc>) -> bool {
+        let has = |field: u64, v: u32| field & (1 << v) != 0;
+        let day = has(self.days, t.day());
+        let weekday = has(self.weekdays, t.weekday().num_days_from_sunday());
+        let day = if self.day_or {
+            day | weekday
+        } else {
+            day & weekday
+        };
+        has(self.minutes, t.minute())
+            & has(self.hours, t.hour())
+            & has(self.months, t.month())
+            & day
+    }
+}
+
+/// Returns how long it is until the start of the next minute
+pub fn until_next_minute(now: &DateTime<Utc>) -> Duration {
+    Duration::from_millis(60_000 - now.timestamp_millis().rem_euclid(60_000) as u64)
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> QueryResult<u64> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(parse_value(step)?)),
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+            // `n/step` runs until the end of the range
+            None if step.is_some() => (parse_value(range)?, max),
+            None => {
+                let value = parse_value(range)?;
+                (value, value)
+            }
+        };
+        let step = step.unwrap_or(1);
+        if (step == 0) | (start < min) | (end > max) | (start > end) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn parse_value(value: &str) -> QueryResult<u32> {
+    if value.is_empty() | !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(QueryError::QLInvalidSyntax);
+    }
+    value.parse().map_err(|_| QueryError::QLInvalidSyntax)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{until_next_minute, Schedule},
+        chrono::{TimeZone, Utc},
+        std::time::Duration,
+    };
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+    #[test]
+    fn every_minute() {
+        let s = Schedule::parse("* * * * *").unwrap();
+        assert!(s.matches(&at(2026, 10, 16, 0, 0)));
+        assert!(s.matches(&at(2026, 2, 28, 23, 59)));
+    }
+    #[test]
+    fn steps_ranges_and_lists() {
+        let s = Schedule::parse("*/15 9-17 * * *").unwrap();
+        assert!(s.matches(&at(2026, 10, 16, 9, 45)));
+        assert!(!s.matches(&at(2026, 10, 16, 9, 46)));
+        assert!(!s.matches(&at(2026, 10, 16, 18, 0)));
+        let s = Schedule::parse("5,10-20/5 0 1 1,6 *").unwrap();
+        for minute in [5, 10, 15, 20] {
+            assert!(s.matches(&at(2026, 6, 1, 0, minute)));
+        }
+        assert!(!s.matches(&at(2026, 6, 1, 0, 25)));
+        assert!(!s.matches(&at(2026, 7, 1, 0, 5)));
+        // `n/step` runs until the end of the range
+        let s = Schedule::parse("50/5 * * * *").unwrap();
+        assert!(s.matches(&at(2026, 10, 16, 3, 55)));
+        assert!(!s.matches(&at(2026, 10, 16, 3, 0)));
+    }
+    #[test]
+    fn weekdays() {
+        // 2026-10-16 is a friday and 2026-10-18 is a sunday
+        let s = Schedule::parse("0 0 * * 5").unwrap();
+        assert!(s.matches(&at(2026, 10, 16, 0, 0)));
+        assert!(!s.matches(&at(2026, 10, 17, 0, 0)));
+        let s = Schedule::parse("0 0 * * 7").unwrap();
+        assert!(s.matches(&at(2026, 10, 18, 0, 0)));
+        assert_eq!(s.weekdays, Schedule::parse("0 0 * * 0").unwrap().weekdays);
+        // if both days are restricted, either one can match
+        let s = Schedule::parse("0 0 1 * 0").unwrap();
+        assert!(s.matches(&at(2026, 10, 1, 0, 0)));
+        assert!(s.matches(&at(2026, 10, 18, 0, 0)));
+        assert!(!s.matches(&at(2026, 10, 16, 0, 0)));
+        // but not if one of them is a `*`
+        let s = Schedule::parse("0 0 */2 * 0").unwrap();
+        assert!(!s.matches(&at(2026, 10, 1, 0, 0)));
+        assert!(!s.matches(&at(2026, 10, 18, 0, 0)));
+        assert!(s.matches(&at(2026, 10, 25, 0, 0)));
+    }
+    #[test]
+    fn shorthands() {
+        let s = Schedule::parse("@daily").unwrap();
+        assert_eq!(s.src(), "@daily");
+        assert!(s.matches(&at(2026, 10, 16, 0, 0)));
+        assert!(!s.matches(&at(2026, 10, 16, 1, 0)));
+        let s = Schedule::parse("@hourly").unwrap();
+        assert!(s.matches(&at(2026, 10, 16, 1, 0)));
+        assert!(!s.matches(&at(2026, 10, 16, 1, 1)));
+    }
+    #[test]
+    fn bad_expressions() {
+        for bad in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "+5 * * * *",
+            "1,,2 * * * *",
+            "@often",
+            "mon * * * *",
+        ] {
+            assert!(Schedule::parse(bad).is_err(), "{bad:?}");
+        }
+    }
+    #[test]
+    fn next_minute() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 30, 45).unwrap();
+        assert_eq!(until_next_minute(&now), Duration::from_secs(15));
+        assert_eq!(
+            until_next_minute(&at(2026, 10, 16, 12, 30)),
+            Duration::from_secs(60)
+        );
+    }
+}
@@ -0,0 +1,396 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    scheduled tasks
+    ---
+    a task runs a statement (as root) on a cron schedule. the task definitions are stored in the GNS. the scheduler
+    wakes up at the start of every minute on the general executor and posts a run for each task that is due, which
+    then runs on a blocking thread. a task never overlaps with itself: if its previous run hasn't finished yet, the
+    new run is skipped (and recorded as such). the latest runs can be seen with `inspect sys.task_runs`
+*/
+
+mod cron;
+#[cfg(test)]
+mod tests;
+
+pub use cron::{until_next_minute, Schedule};
+
+use {
+    crate::engine::{
+        error::{QueryError, QueryResult},
+        fractal::{GenericTask, GlobalInstanceLike, Task},
+        net::protocol::SQuery,
+        ql::{
+            ddl::task::{AlterTask, CreateTask, DropTask},
+            lex::SecureLexer,
+        },
+        txn::gns::task::{AlterTaskTxn, CreateTaskTxn, DropTaskTxn},
+    },
+    chrono::{DateTime, Utc},
+    parking_lot::{Mutex, RwLock},
+    std::{
+        collections::{btree_map::Entry, BTreeMap, VecDeque},
+        sync::{
+            atomic::{AtomicI64, AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// the number of runs that we hold on to (so that they can be inspected)
+const MAX_TASK_RUNS: usize = 128;
+
+#[derive(Debug, PartialEq, Clone)]
+/// The statement that a task runs, laid out just like a client sends it: the query text followed by the parameters
+pub struct TaskBody {
+    payload: Box<[u8]>,
+    q_window: usize,
+}
+
+impl TaskBody {
+    pub fn new(payload: Box<[u8]>, q_window: usize) -> Self {
+        Self { payload, q_window }
+    }
+    /// Take the statement out of a `create task <name> schedule ? do <statement>` query. Names and keywords can't
+    /// have a `?` in them, so the first one is always the schedule (which also makes it the first parameter). The
+    /// statement is the text after `do`, along with the rest of the parameters
+    pub fn from_create(query: &SQuery) -> QueryResult<Self> {
+        let text = query.query();
+        let statement = text.iter().position(|b| *b == b'?').and_then(|i| {
+            let rest = text[i + 1..].trim_ascii_start();
+            ((rest.len() > 2)
+                && rest[..2].eq_ignore_ascii_case(b"do")
+                && rest[2].is_ascii_whitespace())
+            .then(|| rest[2..].trim_ascii())
+        });
+        // the schedule is a string: `0x06<length>\n<body>`
+        let params = match query.params() {
+            [6, rest @ ..] => rest.iter().position(|b| *b == b'\n').and_then(|lf| {
+                let len: usize = core::str::from_utf8(&rest[..lf]).ok()?.parse().ok()?;
+                rest.get(len.checked_add(lf + 1)?..)
+            }),
+            _ => None,
+        };
+        let (Some(statement), Some(params)) = (statement, params) else {
+            return Err(QueryError::QLInvalidSyntax);
+        };
+        // the statement is only parsed when it runs, but it has to lex on its own
+        SecureLexer::new_with_segments(statement, params).lex()?;
+        let mut payload = statement.to_vec();
+        payload.extend(params);
+        Ok(Self::new(payload.into_boxed_slice(), statement.len()))
+    }
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+    pub fn q_window(&self) -> usize {
+        self.q_window
+    }
+    pub fn query(&self) -> &[u8] {
+        &self.payload[..self.q_window]
+    }
+    pub fn squery(&self) -> SQuery<'_> {
+        SQuery::new(&self.payload, self.q_window)
+    }
+}
+
+#[derive(Debug)]
+pub struct ScheduledTask {
+    schedule: Schedule,
+    body: Arc<TaskBody>,
+    enabled: bool,
+    /// the run that is in progress (if any), which we use to make sure that runs never overlap
+    running: Option<u64>,
+}
+
+impl ScheduledTask {
+    fn new(schedule: Schedule, body: TaskBody) -> Self {
+        Self {
+            schedule,
+            body: Arc::new(body),
+            enabled: true,
+            running: None,
+        }
+    }
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+    pub fn body(&self) -> &TaskBody {
+        &self.body
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+#[derive(Debug)]
+pub struct TaskRun {
+    task: Box<str>,
+    /// the start of the minute that the run was due in
+    due: DateTime<Utc>,
+    /// `None` if the run was skipped
+    id: Option<u64>,
+    /// set once the statement has run
+    outcome: Option<(Option<QueryError>, Duration)>,
+}
+
+impl TaskRun {
+    fn new(task: Box<str>, due: DateTime<Utc>, id: Option<u64>) -> Self {
+        Self {
+            task,
+            due,
+            id,
+            outcome: None,
+        }
+    }
+    pub fn task(&self) -> &str {
+        &self.task
+    }
+    pub fn due(&self) -> &DateTime<Utc> {
+        &self.due
+    }
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+    pub fn status(&self) -> &'static str {
+        match (self.id, self.outcome) {
+            (None, _) => "skipped",
+            (Some(_), None) => "running",
+            (Some(_), Some((None, _))) => "completed",
+            (Some(_), Some((Some(_), _))) => "failed",
+        }
+    }
+    pub fn error(&self) -> Option<QueryError> {
+        self.outcome.and_then(|(error, _)| error)
+    }
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.outcome.map(|(_, elapsed)| elapsed)
+    }
+}
+
+#[derive(Debug)]
+/// All the tasks, and their latest runs
+pub struct TaskRegistry {
+    tasks: RwLock<BTreeMap<Box<str>, ScheduledTask>>,
+    runs: Mutex<VecDeque<TaskRun>>,
+    /// the last minute (since the epoch) that the scheduler ran for
+    last_tick: AtomicI64,
+    next_run_id: AtomicU64,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self {
+            tasks: RwLock::new(BTreeMap::new()),
+            runs: Mutex::new(VecDeque::new()),
+            last_tick: AtomicI64::new(i64::MIN),
+            next_run_id: AtomicU64::new(0),
+        }
+    }
+    pub fn tasks(&self) -> &RwLock<BTreeMap<Box<str>, ScheduledTask>> {
+        &self.tasks
+    }
+    /// The latest runs, oldest first
+    pub fn runs(&self) -> &Mutex<VecDeque<TaskRun>> {
+        &self.runs
+    }
+    fn record_run(&self, run: TaskRun) {
+        let mut runs = self.runs.lock();
+        if runs.len() == MAX_TASK_RUNS {
+            runs.pop_front();
+        }
+        runs.push_back(run);
+    }
+    fn record_outcome(&self, run_id: u64, result: QueryResult<()>, elapsed: Duration) {
+        if let Some(run) = self
+            .runs
+            .lock()
+            .iter_mut()
+            .rev()
+            .find(|run| run.id == Some(run_id))
+        {
+            run.outcome = Some((result.err(), elapsed));
+        }
+    }
+}
+
+impl TaskRegistry {
+    pub fn __raw_create_task(
+        &self,
+        task_name: Box<str>,
+        schedule: Schedule,
+        body: TaskBody,
+    ) -> bool {
+        match self.tasks.write().entry(task_name) {
+            Entry::Vacant(ve) => {
+                ve.insert(ScheduledTask::new(schedule, body));
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+    pub fn __raw_alter_task(&self, task_name: &str, enabled: bool) -> bool {
+        match self.tasks.write().get_mut(task_name) {
+            Some(task) => {
+                task.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn __raw_drop_task(&self, task_name: &str) -> bool {
+        self.tasks.write().remove(task_name).is_some()
+    }
+}
+
+/*
+    ddl
+*/
+
+pub fn create_task(
+    global: &impl GlobalInstanceLike,
+    stmt: CreateTask,
+    body: TaskBody,
+) -> QueryResult<()> {
+    let schedule = Schedule::parse(&stmt.schedule)?;
+    let mut tasks = global.state().namespace().tasks().tasks.write();
+    if tasks.contains_key(stmt.task_name.as_str()) {
+        return Err(QueryError::QExecDdlObjectAlreadyExists);
+    }
+    global.state().gns_driver().driver_context(
+        global,
+        |drv| {
+            drv.commit_event(CreateTaskTxn::new(
+                stmt.task_name.as_str(),
+                schedule.src(),
+                body.payload(),
+                body.q_window(),
+            ))
+        },
+        || {},
+    )?;
+    tasks.insert(
+        stmt.task_name.boxed_str(),
+        ScheduledTask::new(schedule, body),
+    );
+    Ok(())
+}
+
+pub fn alter_task(global: &impl GlobalInstanceLike, stmt: AlterTask) -> QueryResult<()> {
+    let mut tasks = global.state().namespace().tasks().tasks.write();
+    let Some(task) = tasks.get_mut(stmt.task_name.as_str()) else {
+        return Err(QueryError::QExecObjectNotFound);
+    };
+    if task.enabled == stmt.enabled {
+        return Ok(());
+    }
+    global.state().gns_driver().driver_context(
+        global,
+        |drv| drv.commit_event(AlterTaskTxn::new(stmt.task_name.as_str(), stmt.enabled)),
+        || {},
+    )?;
+    task.enabled = stmt.enabled;
+    Ok(())
+}
+
+pub fn drop_task(global: &impl GlobalInstanceLike, stmt: DropTask) -> QueryResult<()> {
+    let mut tasks = global.state().namespace().tasks().tasks.write();
+    if !tasks.contains_key(stmt.task_name.as_str()) {
+        return Err(QueryError::QExecObjectNotFound);
+    }
+    global.state().gns_driver().driver_context(
+        global,
+        |drv| drv.commit_event(DropTaskTxn::new(stmt.task_name.as_str())),
+        || {},
+    )?;
+    // a run that is in progress is left to finish
+    let _ = tasks.remove(stmt.task_name.as_str());
+    Ok(())
+}
+
+/*
+    scheduler
+*/
+
+/// Post a run for every enabled task that is due in the minute that `now` falls in. This does nothing if the
+/// scheduler has already run for that minute
+pub fn tick(global: &impl GlobalInstanceLike, now: DateTime<Utc>) {
+    let registry = global.state().namespace().tasks();
+    let minute = now.timestamp().div_euclid(60);
+    if registry.last_tick.fetch_max(minute, Ordering::AcqRel) >= minute {
+        return;
+    }
+    let due = DateTime::from_timestamp(minute * 60, 0).unwrap_or(now);
+    let mut runs = vec![];
+    let mut tasks = registry.tasks.write();
+    for (name, task) in tasks
+        .iter_mut()
+        .filter(|(_, task)| task.enabled && task.schedule.matches(&now))
+    {
+        if task.running.is_some() {
+            warn!("tasks: skipping a run of `{name}` because the previous one hasn't finished");
+            registry.record_run(TaskRun::new(name.clone(), due, None));
+            continue;
+        }
+        let id = registry.next_run_id.fetch_add(1, Ordering::Relaxed);
+        task.running = Some(id);
+        registry.record_run(TaskRun::new(name.clone(), due, Some(id)));
+        runs.push((name.clone(), id));
+    }
+    // a run may start as soon as it's posted, and it needs to look up its task
+    drop(tasks);
+    for (name, id) in runs {
+        global.taskmgr_post_standard_priority(Task::new(GenericTask::RunTask(name, id)));
+    }
+}
+
+/// Run the statement for a task (for the run with the given id)
+pub fn run(global: &impl GlobalInstanceLike, task_name: &str, run_id: u64) {
+    let registry = global.state().namespace().tasks();
+    let start = Instant::now();
+    let body = registry
+        .tasks
+        .read()
+        .get(task_name)
+        .map(|task| task.body.clone());
+    let result = match body {
+        Some(body) => global.exec_task_statement(&body),
+        // dropped since
+        None => Err(QueryError::QExecObjectNotFound),
+    };
+    if let Err(e) = result {
+        warn!("tasks: run {run_id} of `{task_name}` failed with {e:?}");
+    }
+    // the task might have been dropped and created again since, in which case this isn't its run anymore
+    if let Some(task) = registry.tasks.write().get_mut(task_name) {
+        if task.running == Some(run_id) {
+            task.running = None;
+        }
+    }
+    registry.record_outcome(run_id, result, start.elapsed());
+}
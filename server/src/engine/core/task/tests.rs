@@ -0,0 +1,279 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::TaskBody,
+    crate::engine::{
+        core::task,
+        error::{QueryError, QueryResult},
+        fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+        net::protocol::SQuery,
+        ql::{
+            ast::parse_ast_node_full,
+            ddl::task::{AlterTask, CreateTask, DropTask},
+            tests::lex_insecure,
+        },
+    },
+    chrono::{DateTime, TimeZone, Utc},
+};
+
+fn body(query: &str) -> TaskBody {
+    TaskBody::new(query.as_bytes().into(), query.len())
+}
+
+fn at(h: u32, m: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 16, h, m, 0).unwrap()
+}
+
+fn exec_create(global: &impl GlobalInstanceLike, create: &str, body: TaskBody) -> QueryResult<()> {
+    let tok = lex_insecure(create.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full::<CreateTask>(&tok[2..]).unwrap();
+    task::create_task(global, stmt, body)
+}
+
+fn exec_alter(global: &impl GlobalInstanceLike, alter: &str) -> QueryResult<()> {
+    let tok = lex_insecure(alter.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full::<AlterTask>(&tok[2..]).unwrap();
+    task::alter_task(global, stmt)
+}
+
+fn exec_drop(global: &impl GlobalInstanceLike, drop: &str) -> QueryResult<()> {
+    let tok = lex_insecure(drop.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full::<DropTask>(&tok[2..]).unwrap();
+    task::drop_task(global, stmt)
+}
+
+fn task_enabled(global: &impl GlobalInstanceLike, task: &str) -> Option<bool> {
+    global
+        .state()
+        .namespace()
+        .tasks()
+        .tasks()
+        .read()
+        .get(task)
+        .map(|task| task.is_enabled())
+}
+
+#[test]
+fn body_from_create() {
+    let query = b"create task sweep schedule ? do delete from apps.sessions where id = ?";
+    let mut payload = query.to_vec();
+    payload.extend(b"\x0611\n*/5 * * * *\x061\nx");
+    let body = TaskBody::from_create(&SQuery::new(&payload, query.len())).unwrap();
+    assert_eq!(body.query(), b"delete from apps.sessions where id = ?");
+    assert_eq!(&body.payload()[body.q_window()..], b"\x061\nx");
+    // the schedule has to be a string
+    let mut payload = query.to_vec();
+    payload.extend(b"\x025\n\x061\nx");
+    assert_eq!(
+        TaskBody::from_create(&SQuery::new(&payload, query.len())),
+        Err(QueryError::QLInvalidSyntax)
+    );
+    // and there has to be a statement
+    let query = b"create task sweep schedule ? do";
+    let mut payload = query.to_vec();
+    payload.extend(b"\x069\n* * * * *");
+    assert_eq!(
+        TaskBody::from_create(&SQuery::new(&payload, query.len())),
+        Err(QueryError::QLInvalidSyntax)
+    );
+}
+
+#[test]
+fn create_alter_drop() {
+    let global = TestGlobal::new_with_driver_id("task_create_alter_drop");
+    let sweep = body("delete from apps.sessions where id = ?");
+    exec_create(
+        &global,
+        "create task sweep schedule '*/5 * * * *' do delete from apps.sessions where id = 'x'",
+        sweep.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        exec_create(
+            &global,
+            "create task sweep schedule '@daily' do delete from apps.sessions where id = 'x'",
+            sweep.clone(),
+        ),
+        Err(QueryError::QExecDdlObjectAlreadyExists)
+    );
+    assert_eq!(
+        exec_create(
+            &global,
+            "create task other schedule '*/5 * *' do delete from apps.sessions where id = 'x'",
+            sweep,
+        ),
+        Err(QueryError::QLInvalidSyntax)
+    );
+    assert_eq!(task_enabled(&global, "sweep"), Some(true));
+    exec_alter(&global, "alter task sweep disable").unwrap();
+    assert_eq!(task_enabled(&global, "sweep"), Some(false));
+    assert_eq!(
+        exec_alter(&global, "alter task other enable"),
+        Err(QueryError::QExecObjectNotFound)
+    );
+    exec_drop(&global, "drop task sweep").unwrap();
+    assert_eq!(task_enabled(&global, "sweep"), None);
+    assert_eq!(
+        exec_drop(&global, "drop task sweep"),
+        Err(QueryError::QExecObjectNotFound)
+    );
+}
+
+#[test]
+fn tasks_are_restored() {
+    let log_name = "task_restore";
+    {
+        let global = TestGlobal::new_with_driver_id(log_name);
+        exec_create(
+            &global,
+            "create task sweep schedule '*/5 * * * *' do delete from apps.sessions where id = 'x'",
+            TaskBody::new(
+                b"delete from apps.sessions where id = ?\x061\nx"[..].into(),
+                38,
+            ),
+        )
+        .unwrap();
+        exec_create(
+            &global,
+            "create task rollup schedule '@hourly' do delete from apps.logs where id = 'x'",
+            body("delete from apps.logs where id = 'x'"),
+        )
+        .unwrap();
+        exec_create(
+            &global,
+            "create task gone schedule '@daily' do delete from apps.logs where id = 'x'",
+            body("delete from apps.logs where id = 'x'"),
+        )
+        .unwrap();
+        exec_alter(&global, "alter task rollup disable").unwrap();
+        exec_drop(&global, "drop task gone").unwrap();
+    }
+    let global = TestGlobal::new_with_driver_id(log_name);
+    let tasks = global.state().namespace().tasks().tasks().read();
+    assert_eq!(
+        tasks.keys().map(|k| k.as_ref()).collect::<Vec<_>>(),
+        ["rollup", "sweep"]
+    );
+    let sweep = tasks.get("sweep").unwrap();
+    assert_eq!(sweep.schedule().src(), "*/5 * * * *");
+    assert_eq!(
+        sweep.body().payload(),
+        b"delete from apps.sessions where id = ?\x061\nx"
+    );
+    assert_eq!(sweep.body().q_window(), 38);
+    assert!(sweep.is_enabled());
+    assert!(!tasks.get("rollup").unwrap().is_enabled());
+}
+
+#[test]
+fn scheduler_runs_due_tasks() {
+    let global = TestGlobal::new_with_driver_id("task_scheduler_runs_due_tasks");
+    exec_create(
+        &global,
+        "create task sweep schedule '* * * * *' do delete from apps.sessions where id = 'x'",
+        body("delete from apps.sessions where id = 'x'"),
+    )
+    .unwrap();
+    exec_create(
+        &global,
+        "create task rollup schedule '0 * * * *' do delete from apps.logs where id = 'x'",
+        body("delete from apps.logs where id = 'x'"),
+    )
+    .unwrap();
+    exec_create(
+        &global,
+        "create task off schedule '* * * * *' do delete from apps.logs where id = 'x'",
+        body("delete from apps.logs where id = 'x'"),
+    )
+    .unwrap();
+    exec_alter(&global, "alter task off disable").unwrap();
+    task::tick(&global, at(12, 30));
+    assert_eq!(
+        global.task_log(),
+        [body("delete from apps.sessions where id = 'x'")]
+    );
+    // the scheduler only runs once a minute
+    task::tick(&global, at(12, 30) + chrono::Duration::seconds(30));
+    assert_eq!(global.task_log().len(), 1);
+    task::tick(&global, at(13, 0));
+    assert_eq!(global.task_log().len(), 3);
+    let runs = global.state().namespace().tasks().runs().lock();
+    assert_eq!(
+        runs.iter()
+            .map(|run| (run.task(), run.status(), *run.due()))
+            .collect::<Vec<_>>(),
+        [
+            ("sweep", "completed", at(12, 30)),
+            ("rollup", "completed", at(13, 0)),
+            ("sweep", "completed", at(13, 0)),
+        ]
+    );
+    assert!(runs
+        .iter()
+        .all(|run| run.id().is_some() && run.error().is_none() && run.elapsed().is_some()));
+}
+
+#[test]
+fn scheduler_skips_overlapping_runs() {
+    let global = TestGlobal::new_with_driver_id("task_scheduler_skips_overlapping_runs");
+    exec_create(
+        &global,
+        "create task sweep schedule '* * * * *' do delete from apps.sessions where id = 'x'",
+        body("delete from apps.sessions where id = 'x'"),
+    )
+    .unwrap();
+    // pretend that the last run hasn't finished yet
+    global
+        .state()
+        .namespace()
+        .tasks()
+        .tasks()
+        .write()
+        .get_mut("sweep")
+        .unwrap()
+        .running = Some(u64::MAX);
+    task::tick(&global, at(12, 30));
+    assert!(global.task_log().is_empty());
+    global
+        .state()
+        .namespace()
+        .tasks()
+        .tasks()
+        .write()
+        .get_mut("sweep")
+        .unwrap()
+        .running = None;
+    task::tick(&global, at(12, 31));
+    assert_eq!(global.task_log().len(), 1);
+    let runs = global.state().namespace().tasks().runs().lock();
+    assert_eq!(
+        runs.iter()
+            .map(|run| (run.status(), run.id().is_some()))
+            .collect::<Vec<_>>(),
+        [("skipped", false), ("completed", true)]
+    );
+}
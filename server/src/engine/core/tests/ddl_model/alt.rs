@@ -76,7 +76,7 @@ fn exec_plan(
 mod plan {
     use crate::{
         engine::{
-            core::model::{self, alt::AlterAction, Field, Layer},
+            core::model::{self, alt::AlterAction, Field, Layer, ListBounds},
             error::QueryError,
         },
         vecfuse,
@@ -152,6 +152,36 @@ mod plan {
             },
         );
     }
+    #[test]
+    fn update_list_bounds() {
+        let mut bounds = ListBounds::unbounded();
+        bounds.set_prop(ListBounds::PROP_MAX_LEN, 10);
+        super::plan(
+            "create model myspace.mymodel(username: string, tags: list { type: string })",
+            "alter model myspace.mymodel update tags { type: list { type: string, max_len: 10 } }",
+            |plan| {
+                assert!(plan.no_lock);
+                assert_eq!(
+                    plan.action,
+                    AlterAction::Update(into_dict! {
+                        "tags" => Field::new(
+                            [Layer::new_with_bounds(Layer::list().tag(), bounds), Layer::str()].into(),
+                            false
+                        )
+                    })
+                );
+            },
+        );
+        assert_eq!(
+            super::with_plan(
+                "create model myspace.mymodel(username: string, ids: list { type: uint64 })",
+                "alter model myspace.mymodel update ids { type: list { type: uint64, element_max: 8 } }",
+                |_| {}
+            )
+            .unwrap_err(),
+            QueryError::QExecDdlModelAlterIllegal
+        );
+    }
     /*
         Illegal
     */
@@ -352,9 +382,13 @@ mod plan {
 
 mod exec {
     use crate::engine::{
-        core::model::{DeltaVersion, Field, Layer},
+        core::{
+            model::{DeltaVersion, Field, Layer},
+            tests::ddl_model::exec_create,
+            EntityIDRef,
+        },
         error::QueryError,
-        fractal::test_utils::TestGlobal,
+        fractal::{test_utils::TestGlobal, GlobalInstanceLike},
         idx::{STIndex, STIndexSeq},
     };
     #[test]
@@ -445,4 +479,39 @@ mod exec {
             QueryError::QExecNeedLock
         );
     }
+    #[test]
+    fn fingerprint_tracks_schema() {
+        let global = TestGlobal::new_with_driver_id("fingerprint_tracks_schema");
+        let altered = std::cell::Cell::new(0);
+        super::exec_plan(
+            &global,
+            true,
+            "create model myspace.mymodel(username: string, password: binary)",
+            "alter model myspace.mymodel add col1 { type: uint64 }",
+            |model| altered.set(model.fingerprint()),
+        )
+        .unwrap();
+        exec_create(
+            &global,
+            "create model myspace.same(username: string, password: binary, col1: uint64)",
+            false,
+        )
+        .unwrap();
+        exec_create(
+            &global,
+            "create model myspace.original(username: string, password: binary)",
+            false,
+        )
+        .unwrap();
+        let models = global.state().namespace().idx_models().read();
+        let fingerprint = |name| {
+            models
+                .get(&EntityIDRef::new("myspace", name))
+                .unwrap()
+                .data()
+                .fingerprint()
+        };
+        assert_eq!(fingerprint("same"), altered.get());
+        assert_ne!(fingerprint("original"), altered.get());
+    }
 }
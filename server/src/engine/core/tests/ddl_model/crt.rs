@@ -28,7 +28,10 @@ mod validation {
     use {
         super::super::create,
         crate::engine::{
-            core::model::{DeltaVersion, Field, Layer},
+            core::{
+                index::PrimaryIndexKind,
+                model::{DeltaVersion, Field, Layer},
+            },
             data::tag::{DataTag, FullTag},
             error::QueryError,
             idx::STIndexSeq,
@@ -58,6 +61,105 @@ mod validation {
         );
     }
 
+    #[test]
+    fn non_strict() {
+        let model = create(
+            "create model myspace.mymodel(username: string, password: binary) with { strict: false }",
+        )
+        .unwrap();
+        assert!(!model.is_strict());
+        // the hidden column is not a part of the declaration
+        assert_eq!(model.describe(), "{*username:String,!password:Binary}");
+        assert!(
+            create("create model myspace.mymodel(username: string, password: binary)")
+                .unwrap()
+                .is_strict()
+        );
+        assert_eq!(
+            create("create model myspace.mymodel(username: string) with { strict: 'no' }")
+                .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+    }
+
+    #[test]
+    fn radix_index() {
+        let model = create(
+            "create model myspace.mymodel(primary url: string, hits: uint64) with { pk_index: 'radix' }",
+        )
+        .unwrap();
+        assert_eq!(model.primary_index().kind(), PrimaryIndexKind::Radix);
+        assert_eq!(
+            create("create model myspace.mymodel(primary url: string, hits: uint64)")
+                .unwrap()
+                .primary_index()
+                .kind(),
+            PrimaryIndexKind::Hash
+        );
+        // only string and binary keys can go into a radix tree
+        assert_eq!(
+            create("create model myspace.mymodel(primary id: uint64, hits: uint64) with { pk_index: 'radix' }")
+                .unwrap_err(),
+            QueryError::QExecDdlModelBadDefinition
+        );
+        assert_eq!(
+            create("create model myspace.mymodel(primary url: string) with { pk_index: 'btree' }")
+                .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+    }
+
+    #[test]
+    fn ttl() {
+        let model =
+            create("create model myspace.mymodel(primary k: string, v: binary) with { ttl: 3600 }")
+                .unwrap();
+        assert_eq!(model.ttl(), Some(3600));
+        assert_eq!(
+            create("create model myspace.mymodel(primary k: string, v: binary)")
+                .unwrap()
+                .ttl(),
+            None
+        );
+        assert_eq!(
+            create("create model myspace.mymodel(primary k: string, v: binary) with { ttl: 0 }")
+                .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+        assert_eq!(
+            create("create model myspace.mymodel(primary k: string, v: binary) with { ttl: -1 }")
+                .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+    }
+
+    #[test]
+    fn append_only() {
+        let model = create(
+            "create model myspace.mymodel(primary k: string, v: binary) with { append_only: true }",
+        )
+        .unwrap();
+        assert!(model.is_append_only());
+        assert!(
+            !create("create model myspace.mymodel(primary k: string, v: binary)")
+                .unwrap()
+                .is_append_only()
+        );
+        assert_eq!(
+            create("create model myspace.mymodel(primary k: string, v: binary) with { append_only: 1 }")
+                .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+        // expiring rows are deleted
+        assert_eq!(
+            create(
+                "create model myspace.mymodel(primary k: string, v: binary) with { append_only: true, ttl: 3600 }"
+            )
+            .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+    }
+
     #[test]
     fn idiotic_order() {
         let model =
@@ -134,12 +236,21 @@ mod validation {
 mod exec {
     use crate::engine::{
         core::{
-            model::{DeltaVersion, Field, Layer},
-            tests::ddl_model::{exec_create_new_space, with_model},
+            dml,
+            model::{trash, DeltaVersion, Field, Layer, ModelData},
+            tests::ddl_model::{exec_create, exec_create_new_space, with_model},
+            EntityIDRef,
         },
-        data::tag::{DataTag, FullTag},
-        fractal::test_utils::TestGlobal,
-        idx::STIndexSeq,
+        data::{
+            cell::Datacell,
+            lit::Lit,
+            tag::{DataTag, FullTag},
+        },
+        error::QueryError,
+        fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+        idx::{STIndex, STIndexSeq},
+        ql::{ast::parse_ast_node_full, tests::lex_insecure},
+        sync::atm::cpin,
     };
 
     const SPACE: &str = "myspace";
@@ -179,4 +290,183 @@ mod exec {
             );
         });
     }
+
+    #[test]
+    fn if_not_exists() {
+        let global = TestGlobal::new_with_driver_id("exec_create_if_not_exists");
+        exec_create_new_space(
+            &global,
+            "create model myspace.mymodel(username: string, password: binary)",
+        )
+        .unwrap();
+        assert_eq!(
+            exec_create(
+                &global,
+                "create model myspace.mymodel(username: string, password: string)",
+                false
+            )
+            .unwrap_err(),
+            QueryError::QExecDdlObjectAlreadyExists
+        );
+        // silently does nothing
+        exec_create(
+            &global,
+            "create model if not exists myspace.mymodel(username: string, password: string)",
+            false,
+        )
+        .unwrap();
+        with_model(&global, SPACE, "mymodel", |model| {
+            assert_eq!(
+                model.fields().st_get("password").unwrap(),
+                &Field::new([Layer::bin()].into(), false)
+            );
+        });
+        // same with drop
+        let tok = lex_insecure(b"drop model if exists myspace.othermodel").unwrap();
+        assert_eq!(
+            ModelData::transactional_exec_drop(&global, parse_ast_node_full(&tok[2..]).unwrap()),
+            Ok(Some(false))
+        );
+        let tok = lex_insecure(b"drop model myspace.othermodel").unwrap();
+        assert_eq!(
+            ModelData::transactional_exec_drop(&global, parse_ast_node_full(&tok[2..]).unwrap()),
+            Err(QueryError::QExecObjectNotFound)
+        );
+    }
+
+    fn insert(global: &impl GlobalInstanceLike, insert: &str) {
+        let tok = lex_insecure(insert.as_bytes()).unwrap();
+        dml::insert(global, parse_ast_node_full(&tok[1..]).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn snapshot() {
+        let global = TestGlobal::new_with_driver_id("exec_snapshot_create");
+        exec_create_new_space(
+            &global,
+            "create model myspace.mymodel(username: string, null password: binary)",
+        )
+        .unwrap();
+        insert(&global, "insert into myspace.mymodel('sayan', null)");
+        insert(&global, "insert into myspace.mymodel('robot', null)");
+        exec_create(
+            &global,
+            "create model myspace.mysnapshot as snapshot of myspace.mymodel",
+            false,
+        )
+        .unwrap();
+        // diverging writes don't show up in the snapshot
+        insert(&global, "insert into myspace.mymodel('douglas', null)");
+        with_model(&global, SPACE, "mysnapshot", |snapshot| {
+            assert_eq!(snapshot.p_key(), "username");
+            assert_eq!(
+                snapshot
+                    .fields()
+                    .stseq_ord_value()
+                    .cloned()
+                    .collect::<Vec<Field>>(),
+                [
+                    Field::new([Layer::str()].into(), false),
+                    Field::new([Layer::bin()].into(), true)
+                ]
+            );
+            assert_eq!(snapshot.primary_index().count(), 2);
+        });
+        with_model(&global, SPACE, "mymodel", |model| {
+            assert_eq!(model.primary_index().count(), 3);
+        });
+    }
+
+    #[test]
+    fn snapshot_masked() {
+        let global = TestGlobal::new_with_driver_id("exec_snapshot_masked");
+        exec_create_new_space(
+            &global,
+            "create model myspace.mymodel(username: string, null email: string, age: uint8)",
+        )
+        .unwrap();
+        insert(
+            &global,
+            "insert into myspace.mymodel('sayan', 'sayan@example.com', 25)",
+        );
+        exec_create(
+            &global,
+            "create model myspace.mysnapshot as snapshot of myspace.mymodel with { mask: { username: 'token', email: 'hash', age: 'zero' } }",
+            false,
+        )
+        .unwrap();
+        with_model(&global, SPACE, "mysnapshot", |snapshot| {
+            let g = cpin();
+            let row = snapshot
+                .primary_index()
+                .select(Lit::from("token_0"), &g)
+                .unwrap();
+            let mut data = row.cloned_data();
+            data.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let (age, email) = (&data[0].1, &data[1].1);
+            assert_eq!(age, &Datacell::new_uint_default(0));
+            assert_eq!(email.str().len(), 64);
+            assert_ne!(email.str(), "sayan@example.com");
+        });
+        // the primary key can't be nulled
+        assert_eq!(
+            exec_create(
+                &global,
+                "create model myspace.mysnapshot2 as snapshot of myspace.mymodel with { mask: { username: 'null' } }",
+                false,
+            )
+            .unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+    }
+
+    fn exec_drop(global: &impl GlobalInstanceLike, drop: &str) {
+        let tok = lex_insecure(drop.as_bytes()).unwrap();
+        ModelData::transactional_exec_drop(global, parse_ast_node_full(&tok[2..]).unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn undrop() {
+        let global = TestGlobal::new_with_driver_id("exec_undrop");
+        exec_create_new_space(
+            &global,
+            "create model myspace.mymodel(username: string, null password: binary)",
+        )
+        .unwrap();
+        insert(&global, "insert into myspace.mymodel('sayan', null)");
+        insert(&global, "insert into myspace.mymodel('robot', null)");
+        let entity = EntityIDRef::new(SPACE, "mymodel");
+        // nothing in the trash yet
+        assert_eq!(
+            ModelData::transactional_exec_undrop(&global, entity),
+            Err(QueryError::QExecObjectNotFound)
+        );
+        trash::set_trash_retention(Some(60));
+        exec_drop(&global, "drop model allow not empty myspace.mymodel");
+        trash::set_trash_retention(None);
+        // can't restore while the name is taken
+        exec_create(
+            &global,
+            "create model myspace.mymodel(id: uint64, name: string)",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            ModelData::transactional_exec_undrop(&global, entity),
+            Err(QueryError::QExecDdlObjectAlreadyExists)
+        );
+        exec_drop(&global, "drop model myspace.mymodel");
+        ModelData::transactional_exec_undrop(&global, entity).unwrap();
+        with_model(&global, SPACE, "mymodel", |model| {
+            assert_eq!(model.p_key(), "username");
+            assert_eq!(model.primary_index().count(), 2);
+        });
+        // the restored model is no longer in the trash
+        exec_drop(&global, "drop model allow not empty myspace.mymodel");
+        assert_eq!(
+            ModelData::transactional_exec_undrop(&global, entity),
+            Err(QueryError::QExecObjectNotFound)
+        );
+    }
 }
@@ -42,7 +42,10 @@ fn layerview(layer_def: &str) -> QueryResult<Field> {
 mod layer_spec_validation {
     use {
         super::layerview,
-        crate::engine::{core::model::Layer, error::QueryError},
+        crate::engine::{
+            core::model::{Layer, ListBounds},
+            error::QueryError,
+        },
     };
 
     #[test]
@@ -60,6 +63,23 @@ mod layer_spec_validation {
         );
     }
 
+    #[test]
+    fn map() {
+        assert_eq!(
+            layerview("map { type: list { type: uint8 } }")
+                .unwrap()
+                .layers(),
+            [Layer::map(), Layer::list(), Layer::uint8()]
+        );
+        for def in ["map", "map { type: string, max_len: 8 }"] {
+            assert_eq!(
+                layerview(def).unwrap_err(),
+                QueryError::QExecDdlInvalidTypeDefinition,
+                "{def}"
+            );
+        }
+    }
+
     #[test]
     fn invalid_list() {
         assert_eq!(
@@ -75,6 +95,54 @@ mod layer_spec_validation {
             QueryError::QExecDdlInvalidTypeDefinition
         );
     }
+
+    #[test]
+    fn list_bounds() {
+        let field = layerview("list { type: string, max_len: 100, element_max: 256 }").unwrap();
+        assert_eq!(
+            field.layers()[0].bounds().props().collect::<Vec<_>>(),
+            [
+                (ListBounds::PROP_MAX_LEN, 100),
+                (ListBounds::PROP_ELEMENT_MAX, 256)
+            ]
+        );
+        assert_eq!(field.layers()[1], Layer::str());
+        // the props can go before the type too
+        let field =
+            layerview("list { max_len: 10, type: list { type: binary, element_max: 8 } }").unwrap();
+        assert_eq!(
+            field
+                .layers()
+                .iter()
+                .map(|layer| layer.bounds().props().collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [
+                vec![(ListBounds::PROP_MAX_LEN, 10)],
+                vec![(ListBounds::PROP_ELEMENT_MAX, 8)],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_list_bounds() {
+        for def in [
+            // only strings and binaries have a length
+            "list { type: uint8, element_max: 8 }",
+            "list { type: list { type: string }, element_max: 8 }",
+            // only lists have bounds
+            "string { max_len: 8 }",
+            "list { type: string, max_len: -1 }",
+            "list { type: string, max_len: '100' }",
+            "list { type: string, min_len: 1 }",
+        ] {
+            assert_eq!(
+                layerview(def).unwrap_err(),
+                QueryError::QExecDdlInvalidTypeDefinition,
+                "{def}"
+            );
+        }
+    }
 }
 
 mod layer_data_validation {
@@ -226,6 +294,46 @@ mod layer_data_validation {
         );
     }
     #[test]
+    fn list_bounds() {
+        let layer = layerview("list { type: string, max_len: 2, element_max: 4 }").unwrap();
+        let mut dc = Datacell::new_list(vec![Datacell::from("I"), Datacell::from("love")]);
+        assert!(layer.vt_data_fpath(&mut dc));
+        assert_vecstreq_exact!(model::layer_traces(), ["list", "string", "string"]);
+        let mut dc = Datacell::new_list(vec![Datacell::from("I"), Datacell::from("cats")]);
+        assert!(layer.vt_data_fpath(&mut dc));
+        // too many elements
+        let mut dc = Datacell::new_list(vec![
+            Datacell::from("I"),
+            Datacell::from("love"),
+            Datacell::from("cats"),
+        ]);
+        assert!(!layer.vt_data_fpath(&mut dc));
+        // an element that's too long
+        let mut dc = Datacell::new_list(vec![Datacell::from("I"), Datacell::from("adore")]);
+        assert!(!layer.vt_data_fpath(&mut dc));
+        let _ = model::layer_traces();
+    }
+    #[test]
+    fn map_simple() {
+        let layer = layerview("map { type: uint8 }").unwrap();
+        let mut dc = Datacell::new_map(
+            [
+                ("a".into(), Datacell::new_uint_default(1)),
+                ("b".into(), Datacell::new_uint_default(2)),
+            ]
+            .into(),
+        );
+        assert!(layer.vt_data_fpath(&mut dc));
+        assert_vecstreq_exact!(model::layer_traces(), ["map", "uint", "uint"]);
+        // out of range
+        let mut dc = Datacell::new_map([("a".into(), Datacell::new_uint_default(256))].into());
+        assert!(!layer.vt_data_fpath(&mut dc));
+        // maps can't hold nulls
+        let mut dc = Datacell::new_map([("a".into(), Datacell::null())].into());
+        assert!(!layer.vt_data_fpath(&mut dc));
+        let _ = model::layer_traces();
+    }
+    #[test]
     fn nullval_fpath() {
         let layer = layerview_nullable("string", true).unwrap();
         assert!(layer.vt_data_fpath(&mut Datacell::null()));
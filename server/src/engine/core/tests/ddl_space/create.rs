@@ -29,8 +29,31 @@ use crate::engine::{
     data::{cell::Datacell, DictEntryGeneric},
     error::QueryError,
     fractal::test_utils::TestGlobal,
+    storage::safe_interfaces::{paths_v1, FileSystem},
 };
 
+/// A storage root under the OS temp directory that is removed (along with the space directories in it) when dropped
+struct TempRoot(String);
+
+impl TempRoot {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir();
+        let root = format!("{}/{name}", root.to_str().unwrap().trim_end_matches('/'));
+        std::fs::create_dir_all(&root).unwrap();
+        // a space's storage path is checked on the storage file system (which is virtual in tests)
+        FileSystem::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+}
+
+impl Drop for TempRoot {
+    fn drop(&mut self) {
+        // the space directories are created on the (virtual) storage file system, but the root itself is a real one
+        let _ = FileSystem::remove_dir_all(&self.0);
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[test]
 fn exec_create_space_simple() {
     let global = TestGlobal::new_with_driver_id("exec_create_space_simple");
@@ -40,6 +63,26 @@ fn exec_create_space_simple() {
     .unwrap();
 }
 
+#[test]
+fn exec_create_space_if_not_exists() {
+    let global = TestGlobal::new_with_driver_id("exec_create_space_if_not_exists");
+    let uuid = super::exec_create(&global, "create space myspace", |_| {}).unwrap();
+    assert_eq!(
+        super::exec_create(&global, "create space myspace", |_| {}).unwrap_err(),
+        QueryError::QExecDdlObjectAlreadyExists
+    );
+    // silently does nothing
+    assert_eq!(
+        super::exec_create(
+            &global,
+            "create space if not exists myspace with { env: { MY_NEW_PROPERTY: 100 } }",
+            |spc| assert!(spc.env().is_empty())
+        )
+        .unwrap(),
+        uuid
+    );
+}
+
 #[test]
 fn exec_create_space_with_env() {
     let global = TestGlobal::new_with_driver_id("exec_create_space_with_env");
@@ -89,3 +132,35 @@ fn exec_create_space_with_random_property() {
         QueryError::QExecDdlInvalidProperties
     );
 }
+
+#[test]
+fn exec_create_space_with_storage_path() {
+    let global = TestGlobal::new_with_driver_id("exec_create_space_with_storage_path");
+    let root = TempRoot::new("exec_create_space_with_storage_path");
+    let root = root.0.as_str();
+    super::exec_create(
+        &global,
+        &format!("create space myspace with {{ storage_path: '{root}' }}"),
+        |space| {
+            assert_eq!(space.storage_path(), Some(root));
+            assert!(paths_v1::space_dir("myspace", space.get_uuid())
+                .starts_with(&format!("{root}/myspace-")));
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn exec_create_space_with_bad_storage_path() {
+    let global = TestGlobal::new_with_driver_id("exec_create_space_with_bad_storage_path");
+    for query in [
+        "create space myspace with { storage_path: 100 }",
+        "create space myspace with { storage_path: 'relative/path' }",
+        "create space myspace with { storage_path: '/this/path/does/not/exist' }",
+    ] {
+        assert_eq!(
+            super::exec_create(&global, query, |_| {}).unwrap_err(),
+            QueryError::QExecDdlInvalidProperties
+        );
+    }
+}
@@ -0,0 +1,113 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{
+        model::ModelData,
+        space::{DropSpaceResult, DroppedModel},
+        tests::ddl_model,
+        EntityIDRef,
+    },
+    error::QueryError,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    ql::{ast::parse_ast_node_full, dml::ins::InsertStatement, tests::lex_insecure as lex},
+};
+
+fn exec_insert(global: &impl GlobalInstanceLike, insert: &str) {
+    let tok = lex(insert.as_bytes()).unwrap();
+    let insert = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+    crate::engine::core::dml::insert(global, insert).unwrap();
+}
+
+fn exec_create_index(global: &impl GlobalInstanceLike, create: &str) {
+    let tok = lex(create.as_bytes()).unwrap();
+    let create = parse_ast_node_full(&tok[2..]).unwrap();
+    ModelData::transactional_exec_create_index(global, create).unwrap();
+}
+
+#[test]
+fn drop_space_empty() {
+    let global = TestGlobal::new_with_driver_id("drop_space_empty");
+    super::exec_create(&global, "create space myspace", |_| {}).unwrap();
+    assert_eq!(
+        super::exec_drop(&global, "drop space myspace").unwrap(),
+        DropSpaceResult::Status(None)
+    );
+    assert_eq!(
+        super::exec_drop(&global, "drop space myspace").unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+    assert_eq!(
+        super::exec_drop(&global, "drop space if exists myspace").unwrap(),
+        DropSpaceResult::Status(Some(false))
+    );
+    // nothing to report if the space had no models
+    super::exec_create(&global, "create space myspace", |_| {}).unwrap();
+    assert_eq!(
+        super::exec_drop(&global, "drop space allow not empty myspace").unwrap(),
+        DropSpaceResult::Report(vec![])
+    );
+}
+
+#[test]
+fn drop_space_not_empty() {
+    let global = TestGlobal::new_with_driver_id("drop_space_not_empty");
+    super::exec_create(&global, "create space myspace", |_| {}).unwrap();
+    for model in [
+        "create model myspace.users(username: string, city: string)",
+        "create model myspace.apps(name: string, owner: string)",
+    ] {
+        ddl_model::exec_create(&global, model, false).unwrap();
+    }
+    exec_create_index(&global, "create index by_city on myspace.users(city)");
+    exec_create_index(&global, "create index by_owner on myspace.apps(owner)");
+    exec_insert(&global, "insert into myspace.users('sayan', 'london')");
+    exec_insert(&global, "insert into myspace.users('douglas', 'london')");
+    exec_insert(&global, "insert into myspace.apps('skytable', 'sayan')");
+    // the models have to be dropped explicitly
+    assert_eq!(
+        super::exec_drop(&global, "drop space myspace").unwrap_err(),
+        QueryError::QExecDdlNotEmpty
+    );
+    assert_eq!(
+        super::exec_drop(&global, "drop space if exists allow not empty myspace").unwrap(),
+        DropSpaceResult::Report(vec![
+            DroppedModel::new_test("apps", &["by_owner"], 1),
+            DroppedModel::new_test("users", &["by_city"], 2),
+        ])
+    );
+    assert!(global
+        .state()
+        .namespace()
+        .idx()
+        .read()
+        .get("myspace")
+        .is_none());
+    let models = global.state().namespace().idx_models().read();
+    for model in ["users", "apps"] {
+        assert!(models.get(&EntityIDRef::new("myspace", model)).is_none());
+    }
+}
@@ -26,9 +26,10 @@
 
 mod alter;
 mod create;
+mod drop;
 
 use crate::engine::{
-    core::space::Space,
+    core::space::{DropSpaceResult, Space},
     data::uuid::Uuid,
     error::QueryResult,
     fractal::GlobalInstanceLike,
@@ -81,3 +82,10 @@ pub fn exec_create_alter(
     assert_eq!(uuid_crt, uuid_alt);
     Ok(uuid_alt)
 }
+
+pub fn exec_drop(gns: &impl GlobalInstanceLike, drop: &str) -> QueryResult<DropSpaceResult> {
+    let tok = lex(drop.as_bytes()).unwrap();
+    let ast_node =
+        ast::parse_ast_node_full::<crate::engine::ql::ddl::drop::DropSpace>(&tok[2..]).unwrap();
+    Space::transactional_exec_drop(gns, ast_node)
+}
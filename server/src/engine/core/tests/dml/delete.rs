@@ -54,3 +54,100 @@ fn delete_nonexisting() {
         QueryError::QExecDmlRowNotFound
     );
 }
+
+#[test]
+fn append_only() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_delete_append_only");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string) with { append_only: true }",
+    )
+    .unwrap();
+    super::exec_insert_only(&global, "insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+    assert_eq!(
+        super::_exec_delete_only(
+            &global,
+            "delete from myspace.mymodel where username = 'sayan'",
+            "sayan"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlAppendOnly
+    );
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set password = 'pass456' where username = 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlAppendOnly
+    );
+    assert_eq!(
+        super::exec_upsert_only(&global, "upsert into myspace.mymodel('sayan', 'pass456')")
+            .unwrap_err(),
+        QueryError::QExecDmlAppendOnly
+    );
+    // inserts are fine
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('elizabeth', 'pass123')",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass123"]
+    );
+    // but the rows can still be cleared by truncating the model
+    super::_exec_only_truncate(&global, "truncate model myspace.mymodel").unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+}
+
+#[test]
+fn truncate_model() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_delete_truncate_model");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    for username in ["sayan", "elizabeth", "john"] {
+        super::exec_insert_only(
+            &global,
+            &format!("insert into myspace.mymodel('{username}', 'pass123')"),
+        )
+        .unwrap();
+    }
+    super::_exec_only_truncate(&global, "truncate model myspace.mymodel").unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    // the model is still around
+    super::exec_insert_only(&global, "insert into myspace.mymodel('sayan', 'pass456')").unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass456"]
+    );
+    assert_eq!(
+        super::_exec_only_truncate(&global, "truncate model myspace.nomodel").unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+}
@@ -0,0 +1,159 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::EntityIDRef,
+    error::QueryError,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+};
+
+const MODEL: &str =
+    "create model myspace.mymodel(username: string, password: string) with { ttl: 3600 }";
+
+fn init(global: &impl GlobalInstanceLike) {
+    super::_exec_only_create_space_model(global, MODEL).unwrap();
+    for username in ["sayan", "elizabeth", "john"] {
+        super::exec_insert_only(
+            global,
+            &format!("insert into myspace.mymodel('{username}', 'pass123')"),
+        )
+        .unwrap();
+    }
+}
+
+/// Make the row look like it was last written a long time ago
+fn expire(global: &impl GlobalInstanceLike, key: &str) {
+    super::_exec_only_read_key_and_then(
+        global,
+        EntityIDRef::new("myspace", "mymodel"),
+        key,
+        |row| row.d_data().write().set_written_at(0),
+    )
+    .unwrap()
+}
+
+fn sweep(global: &impl GlobalInstanceLike) -> usize {
+    global
+        .state()
+        .namespace()
+        .with_model(EntityIDRef::new("myspace", "mymodel"), |mdl| {
+            Ok(mdl.sweep_expired())
+        })
+        .unwrap()
+}
+
+#[test]
+fn expired_rows_are_not_read() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_expiry_not_read");
+    init(&global);
+    expire(&global, "john");
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'john'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass123"]
+    );
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select username from myspace.mymodel where username in ['john', 'sayan']"
+        )
+        .unwrap(),
+        vec![None, Some(intovec!["sayan"])]
+    );
+    let mut usernames: Vec<_> =
+        super::_exec_only_select_all(&global, "select all username from myspace.mymodel limit 10")
+            .unwrap()
+            .into_iter()
+            .map(|mut row| row.swap_remove(0).into_str().unwrap())
+            .collect();
+    usernames.sort();
+    assert_eq!(usernames, ["elizabeth", "sayan"]);
+}
+
+#[test]
+fn expired_rows_are_not_written() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_expiry_not_written");
+    init(&global);
+    expire(&global, "john");
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set password = 'pass' where username = 'john'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    assert_eq!(
+        super::_exec_delete_only(
+            &global,
+            "delete from myspace.mymodel where username = 'john'",
+            "john"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    // but an insert takes the place of the expired row
+    super::exec_insert_only(&global, "insert into myspace.mymodel('john', 'newpass')").unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'john'"
+        )
+        .unwrap(),
+        intovec!["john", "newpass"]
+    );
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel('john', 'newpass')")
+            .unwrap_err(),
+        QueryError::QExecDmlDuplicate
+    );
+}
+
+#[test]
+fn sweep_expired() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_expiry_sweep");
+    init(&global);
+    expire(&global, "john");
+    expire(&global, "elizabeth");
+    assert_eq!(sweep(&global), 2);
+    assert_eq!(sweep(&global), 0);
+    assert_eq!(
+        super::_exec_only_select_all(&global, "select all username from myspace.mymodel limit 10")
+            .unwrap(),
+        vec![intovec!["sayan"]]
+    );
+}
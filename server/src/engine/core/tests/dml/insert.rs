@@ -24,7 +24,12 @@
  *
 */
 
-use crate::engine::{data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal};
+use crate::engine::{
+    core::EntityIDRef,
+    data::cell::Datacell,
+    error::QueryError,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+};
 
 #[derive(sky_macros::Wrapper, Debug)]
 struct Tuple(Vec<(Box<str>, Datacell)>);
@@ -86,3 +91,376 @@ fn insert_duplicate() {
         QueryError::QExecDmlDuplicate
     );
 }
+
+#[test]
+fn insert_non_strict() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_non_strict");
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string) with { strict: false }",
+        "insert into myspace.mymodel { username: 'sayan', password: 'pass123', color: 'red', age: 30 }",
+        "sayan",
+        |row| {
+            assert_veceq_transposed!(
+                row.cloned_data(),
+                Tuple(pairvec!(
+                    ("password", "pass123"),
+                    (
+                        "#extra",
+                        Datacell::from([
+                            Datacell::from("age"),
+                            Datacell::new_uint_default(30),
+                            Datacell::from("color"),
+                            Datacell::from("red")
+                        ])
+                    )
+                ))
+            );
+        },
+    )
+    .unwrap();
+    // no ad-hoc fields
+    super::exec_insert_core(
+        &global,
+        "insert into myspace.mymodel('ohsayan', 'pass123')",
+        "ohsayan",
+        |row| {
+            assert_veceq_transposed!(
+                row.cloned_data(),
+                Tuple(pairvec!(
+                    ("password", "pass123"),
+                    ("#extra", Datacell::null())
+                ))
+            );
+        },
+    )
+    .unwrap();
+    // the output of a select can go back in as is
+    super::exec_insert_core(
+        &global,
+        "insert into myspace.mymodel('sayan2', 'pass123', ['color', 'blue'])",
+        "sayan2",
+        |row| {
+            assert_veceq_transposed!(
+                row.cloned_data(),
+                Tuple(pairvec!(
+                    ("password", "pass123"),
+                    (
+                        "#extra",
+                        Datacell::from([Datacell::from("color"), Datacell::from("blue")])
+                    )
+                ))
+            );
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        super::exec_insert_only(
+            &global,
+            "insert into myspace.mymodel('sayan3', 'pass123', ['color'])"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    // ad-hoc fields can't shadow declared fields, be repeated or hold lists
+    for insert in [
+        "insert into myspace.mymodel('sayan3', 'pass123', ['password', 'pass456'])",
+        "insert into myspace.mymodel('sayan3', 'pass123', ['color', 'red', 'color', 'blue'])",
+        "insert into myspace.mymodel { username: 'sayan3', password: 'pass123', colors: ['red', 'blue'] }",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+}
+
+#[test]
+fn insert_null_violation() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_null_violation");
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, not null password: string, null email: string)",
+        "insert into myspace.mymodel { username: 'sayan', password: 'pass123' }",
+        "sayan",
+        |row| {
+            assert_veceq_transposed!(
+                row.cloned_data(),
+                Tuple(pairvec!(
+                    ("password", "pass123"),
+                    ("email", Datacell::null())
+                ))
+            );
+        },
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('elizabeth', null, null)",
+        "insert into myspace.mymodel { username: 'elizabeth', email: 'e@example.com' }",
+        "insert into myspace.mymodel { password: 'pass456' }",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlNullViolation
+        );
+    }
+}
+
+#[test]
+fn insert_unique_violation() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_unique_violation");
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, unique email: string, unique null phone: uint64)",
+        "insert into myspace.mymodel('sayan', 'sayan@example.com', null)",
+        "sayan",
+        |_| {},
+    )
+    .unwrap();
+    // any number of rows can have a null
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('elizabeth', 'e@example.com', null)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('john', 'sayan@example.com', 12345)",
+        // the rows of a statement can't share a value either
+        "insert into myspace.mymodel('john', 'john@example.com', 12345), ('jane', 'jane@example.com', 12345)",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlUniqueViolation
+        );
+    }
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select * from myspace.mymodel where username = 'john'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    assert_eq!(
+        super::exec_upsert_only(
+            &global,
+            "upsert into myspace.mymodel('sayan', 'e@example.com', null)"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlUniqueViolation
+    );
+    // a row can keep its own value, and the value is free again once the row is gone
+    super::exec_upsert_only(
+        &global,
+        "upsert into myspace.mymodel('sayan', 'sayan@example.com', 12345)",
+    )
+    .unwrap();
+    super::_exec_delete_only(
+        &global,
+        "delete from myspace.mymodel where username = 'sayan'",
+        "sayan",
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('john', 'sayan@example.com', 12345)",
+    )
+    .unwrap();
+}
+
+#[test]
+fn unique_field_definition() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_unique_field_definition");
+    for model in [
+        // the primary key is already unique
+        "create model myspace.mymodel(primary unique username: string, password: string)",
+        "create model myspace.mymodel(unique username: string, password: string)",
+        // only the values that could be used for a primary key can be unique
+        "create model myspace.mymodel(username: string, unique tags: list { type: string })",
+    ] {
+        assert_eq!(
+            super::_exec_only_create_space_model(&global, model).unwrap_err(),
+            QueryError::QExecDdlModelBadDefinition
+        );
+    }
+}
+
+#[test]
+fn insert_strict_rejects_adhoc_fields() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_strict_adhoc");
+    assert_eq!(
+        super::exec_insert(
+            &global,
+            "create model myspace.mymodel(username: string, password: string)",
+            "insert into myspace.mymodel { username: 'sayan', password: 'pass123', color: 'red' }",
+            "sayan",
+            |_| {},
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn insert_multi() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_multi");
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123'), ('elizabeth', 'pass456'), { username: 'john', password: 'pass789' }",
+        "sayan",
+        |row| {
+            assert_veceq_transposed!(row.cloned_data(), Tuple(pairvec!(("password", "pass123"))));
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select password from myspace.mymodel where username = 'elizabeth'"
+        )
+        .unwrap(),
+        intovec!["pass456"]
+    );
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select password from myspace.mymodel where username = 'john'"
+        )
+        .unwrap(),
+        intovec!["pass789"]
+    );
+}
+
+#[test]
+fn insert_multi_is_atomic() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_multi_is_atomic");
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "sayan",
+        |_| {},
+    )
+    .unwrap();
+    // key already present
+    assert_eq!(
+        super::exec_insert_only(
+            &global,
+            "insert into myspace.mymodel('elizabeth', 'pass456'), ('sayan', 'pass789')"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlDuplicate
+    );
+    // key repeated within the batch
+    assert_eq!(
+        super::exec_insert_only(
+            &global,
+            "insert into myspace.mymodel('elizabeth', 'pass456'), ('elizabeth', 'pass789')"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlDuplicate
+    );
+    // a bad row anywhere in the batch
+    assert_eq!(
+        super::exec_insert_only(
+            &global,
+            "insert into myspace.mymodel('elizabeth', 'pass456'), ('john', 12345)"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select password from myspace.mymodel where username = 'elizabeth'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+}
+
+#[test]
+fn upsert() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_upsert");
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string, null email: string)",
+        "insert into myspace.mymodel('sayan', 'pass123', 'sayan@example.com')",
+        "sayan",
+        |_| {},
+    )
+    .unwrap();
+    // replaces the existing row and inserts the new one
+    super::exec_upsert_only(
+        &global,
+        "upsert into myspace.mymodel('sayan', 'pass456', null), ('elizabeth', 'pass789', null)",
+    )
+    .unwrap();
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass456", Datacell::null()]
+    );
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select * from myspace.mymodel where username = 'elizabeth'"
+        )
+        .unwrap(),
+        intovec!["elizabeth", "pass789", Datacell::null()]
+    );
+    // nothing is changed if any row is bad
+    assert_eq!(
+        super::exec_upsert_only(
+            &global,
+            "upsert into myspace.mymodel('sayan', 'pass000', null), ('john', 12345, null)"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select password from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["pass456"]
+    );
+}
+
+#[test]
+fn insert_advances_watermark() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_insert_advances_watermark");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    let watermark = || {
+        global
+            .state()
+            .namespace()
+            .with_model(EntityIDRef::new("myspace", "mymodel"), |mdl| {
+                Ok(mdl.delta_state().committed_watermark())
+            })
+            .unwrap()
+    };
+    assert_eq!(watermark(), (None, 0));
+    // every insert is written out right away
+    super::exec_insert_only(&global, "insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+    let (committed, first_clock) = watermark();
+    assert_eq!(committed, Some(0));
+    assert_ne!(first_clock, 0);
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('elizabeth', 'pass123')",
+    )
+    .unwrap();
+    let (committed, clock) = watermark();
+    assert_eq!(committed, Some(1));
+    assert!(clock > first_clock);
+}
@@ -25,19 +25,28 @@
 */
 
 mod delete;
+mod expiry;
 mod insert;
 mod select;
 mod update;
 
 use crate::engine::{
-    core::{dml, index::Row, model::ModelData, space::Space, EntityIDRef},
+    core::{
+        dml,
+        index::Row,
+        model::{ColumnarCache, ModelData},
+        query_mem::QueryMemory,
+        space::Space,
+        EntityIDRef,
+    },
     data::{cell::Datacell, lit::Lit},
     error::QueryResult,
     fractal::GlobalInstanceLike,
+    net::protocol::Response,
     ql::{
         ast::parse_ast_node_full,
-        dml::{del::DeleteStatement, ins::InsertStatement},
-        tests::lex_insecure,
+        dml::{del::DeleteStatement, ins::InsertStatement, sel::SelectStatement},
+        tests::{lex_insecure, lex_secure},
     },
     sync,
 };
@@ -67,6 +76,18 @@ fn _exec_only_insert<T>(
     Ok(r)
 }
 
+// the test lexer can't read floats, so inserts that need them pass every value as a parameter
+fn _exec_only_insert_params(
+    global: &impl GlobalInstanceLike,
+    insert: &str,
+    params: &[u8],
+) -> QueryResult<()> {
+    let query = [insert.as_bytes(), params].concat();
+    let lex_insert = lex_secure(&query, insert.len()).unwrap();
+    let stmt_insert = parse_ast_node_full::<InsertStatement>(&lex_insert[1..]).unwrap();
+    dml::insert(global, stmt_insert)
+}
+
 fn _exec_only_read_key_and_then<T>(
     global: &impl GlobalInstanceLike,
     entity: EntityIDRef,
@@ -100,6 +121,12 @@ fn _exec_delete_only(global: &impl GlobalInstanceLike, delete: &str, key: &str)
     Ok(())
 }
 
+fn _exec_only_truncate(global: &impl GlobalInstanceLike, truncate: &str) -> QueryResult<()> {
+    let lex_truncate = lex_insecure(truncate.as_bytes()).unwrap();
+    let truncate = parse_ast_node_full(&lex_truncate[2..]).unwrap();
+    ModelData::transactional_exec_truncate(global, truncate)
+}
+
 fn _exec_only_select(global: &impl GlobalInstanceLike, select: &str) -> QueryResult<Vec<Datacell>> {
     let lex_sel = lex_insecure(select.as_bytes()).unwrap();
     let select = parse_ast_node_full(&lex_sel[1..]).unwrap();
@@ -108,6 +135,126 @@ fn _exec_only_select(global: &impl GlobalInstanceLike, select: &str) -> QueryRes
     Ok(r)
 }
 
+/// Run an aggregate select with the given memory budget (the rows themselves aren't charged)
+fn _exec_only_aggregate_charged(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+    mem: &QueryMemory,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    let lex_sel = lex_insecure(select.as_bytes()).unwrap();
+    let select = parse_ast_node_full(&lex_sel[1..]).unwrap();
+    let mut r = Vec::new();
+    dml::aggregate(global, select, mem, |row| {
+        r.push(row.to_vec());
+        Ok(())
+    })?;
+    Ok(r)
+}
+
+fn _exec_only_select_multi(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+) -> QueryResult<Vec<Option<Vec<Datacell>>>> {
+    _exec_only_select_multi_charged(global, select, &QueryMemory::new())
+}
+
+/// Run a multi-get with the given memory budget (the rows themselves aren't charged)
+fn _exec_only_select_multi_charged(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+    mem: &QueryMemory,
+) -> QueryResult<Vec<Option<Vec<Datacell>>>> {
+    let lex_sel = lex_insecure(select.as_bytes()).unwrap();
+    let mut select: SelectStatement = parse_ast_node_full(&lex_sel[1..]).unwrap();
+    let multi = select.take_multi_get().unwrap();
+    let mut r: Vec<Option<Vec<Datacell>>> = Vec::new();
+    dml::select_multi(
+        global,
+        select,
+        multi,
+        mem,
+        &mut r,
+        |rows, col_c| rows.push(col_c.map(Vec::with_capacity)),
+        |rows, dc| {
+            rows.last_mut().unwrap().as_mut().unwrap().push(dc.clone());
+            Ok(())
+        },
+    )?;
+    Ok(r)
+}
+
+fn _exec_only_select_all(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    _exec_only_select_all_charged(global, select, &QueryMemory::new())
+}
+
+/// Run a select all with the given memory budget (the rows themselves aren't charged)
+fn _exec_only_select_all_charged(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+    mem: &QueryMemory,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    let lex_sel = lex_insecure(select.as_bytes()).unwrap();
+    let select = parse_ast_node_full(&lex_sel[2..]).unwrap();
+    let mut r: Vec<Vec<Datacell>> = Vec::new();
+    dml::select_all(
+        global,
+        select,
+        mem,
+        &mut r,
+        |_, _, _| {},
+        |rows, dc, col_cnt| {
+            match rows.last_mut() {
+                Some(row) if row.len() != col_cnt => row.push(dc.clone()),
+                _ => rows.push(vec![dc.clone()]),
+            }
+            Ok(())
+        },
+    )?;
+    Ok(r)
+}
+
+fn _exec_only_cache_model(global: &impl GlobalInstanceLike, entity: EntityIDRef) -> usize {
+    global
+        .state()
+        .namespace()
+        .with_model(entity, |mdl| {
+            let _latch = mdl.primary_index().acquire_exclusive();
+            let g = sync::atm::cpin();
+            let cache = ColumnarCache::build(mdl, &g).unwrap();
+            let cached = cache.len();
+            *mdl.columnar_cache().write() = Some(cache);
+            Ok(cached)
+        })
+        .unwrap()
+}
+
+fn _exec_only_create_index(
+    global: &impl GlobalInstanceLike,
+    create: &str,
+) -> QueryResult<Option<bool>> {
+    let lex_create = lex_insecure(create.as_bytes()).unwrap();
+    let create = parse_ast_node_full(&lex_create[2..]).unwrap();
+    ModelData::transactional_exec_create_index(global, create)
+}
+
+fn _exec_only_drop_index(
+    global: &impl GlobalInstanceLike,
+    drop: &str,
+) -> QueryResult<Option<bool>> {
+    let lex_drop = lex_insecure(drop.as_bytes()).unwrap();
+    let drop = parse_ast_node_full(&lex_drop[2..]).unwrap();
+    ModelData::transactional_exec_drop_index(global, drop)
+}
+
+fn _exec_only_explain(global: &impl GlobalInstanceLike, explain: &str) -> QueryResult<dml::Plan> {
+    let lex_explain = lex_insecure(explain.as_bytes()).unwrap();
+    let explain = parse_ast_node_full(&lex_explain[1..]).unwrap();
+    dml::explain(global, explain)
+}
+
 fn _exec_only_update(global: &impl GlobalInstanceLike, update: &str) -> QueryResult<()> {
     let lex_upd = lex_insecure(update.as_bytes()).unwrap();
     let update = parse_ast_node_full(&lex_upd[1..]).unwrap();
@@ -140,6 +287,12 @@ pub(self) fn exec_insert_only(global: &impl GlobalInstanceLike, insert: &str) ->
     _exec_only_insert(global, insert, |_| {})
 }
 
+pub(self) fn exec_upsert_only(global: &impl GlobalInstanceLike, upsert: &str) -> QueryResult<()> {
+    let lex_upsert = lex_insecure(upsert.as_bytes()).unwrap();
+    let stmt_upsert = parse_ast_node_full::<InsertStatement>(&lex_upsert[1..]).unwrap();
+    dml::upsert(global, stmt_upsert)
+}
+
 pub(self) fn exec_delete(
     global: &impl GlobalInstanceLike,
     model: &str,
@@ -165,30 +318,61 @@ pub(self) fn exec_select(
     _exec_only_select(global, select)
 }
 
-pub(self) fn exec_select_all(
+pub(self) fn exec_select_resp(
     global: &impl GlobalInstanceLike,
     model: &str,
-    inserts: &[&str],
+    insert: &str,
     select: &str,
-) -> QueryResult<Vec<Vec<Datacell>>> {
+    meta: bool,
+) -> QueryResult<Response> {
     _exec_only_create_space_model(global, model)?;
-    for insert in inserts {
-        _exec_only_insert(global, insert, |_| {})?;
+    _exec_only_insert(global, insert, |_| {})?;
+    let lex_sel = lex_insecure(select.as_bytes()).unwrap();
+    let select = parse_ast_node_full(&lex_sel[1..]).unwrap();
+    dml::select_resp(
+        global,
+        select,
+        dml::ResultFormat {
+            meta,
+            packed: false,
+        },
+    )
+}
+
+pub(self) fn exec_select_all_packed(
+    global: &impl GlobalInstanceLike,
+    model: &str,
+    insert: &str,
+    rows: &[&[u8]],
+    select: &str,
+) -> QueryResult<Response> {
+    _exec_only_create_space_model(global, model)?;
+    for params in rows {
+        _exec_only_insert_params(global, insert, params)?;
     }
     let lex_sel = lex_insecure(select.as_bytes()).unwrap();
     let select = parse_ast_node_full(&lex_sel[2..]).unwrap();
-    let mut r: Vec<Vec<Datacell>> = Vec::new();
-    dml::select_all(
+    dml::select_all_resp(
         global,
         select,
-        &mut r,
-        |_, _, _| {},
-        |rows, dc, col_cnt| match rows.last_mut() {
-            Some(row) if row.len() != col_cnt => row.push(dc.clone()),
-            _ => rows.push(vec![dc.clone()]),
+        dml::ResultFormat {
+            meta: false,
+            packed: true,
         },
-    )?;
-    Ok(r)
+    )
+}
+
+pub(self) fn exec_select_all(
+    global: &impl GlobalInstanceLike,
+    model: &str,
+    inserts: &[&str],
+    select: &str,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    _exec_only_create_space_model(global, model)?;
+    for insert in inserts {
+        _exec_only_insert(global, insert, |_| {})?;
+    }
+    _exec_only_select_all(global, select)
 }
 
 pub(self) fn exec_select_only(
@@ -25,7 +25,16 @@
 */
 
 use {
-    crate::engine::{data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal},
+    crate::engine::{
+        core::{dml, query_mem::QueryMemory, EntityIDRef},
+        data::{
+            cell::Datacell,
+            tag::{FloatSpec, FullTag, TagSelector, UIntSpec},
+        },
+        error::QueryError,
+        fractal::test_utils::TestGlobal,
+        net::protocol::{Response, ResponseType},
+    },
     std::collections::HashMap,
 };
 
@@ -44,6 +53,25 @@ fn simple_select_wildcard() {
     );
 }
 
+#[test]
+fn select_wildcard_non_strict() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_wildcard_non_strict");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, password: string) with { strict: false }",
+            "insert into myspace.mymodel { username: 'sayan', password: 'pass123', color: 'red' }",
+            "select * from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            "pass123",
+            Datacell::from([Datacell::from("color"), Datacell::from("red")])
+        ]
+    );
+}
+
 #[test]
 fn simple_select_specified_same_order() {
     let global = TestGlobal::new_with_driver_id_instant_update(
@@ -108,6 +136,335 @@ fn select_nonexisting() {
     );
 }
 
+#[test]
+fn select_where_is_null() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_where_is_null");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null password: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "select username from myspace.mymodel where username = 'sayan' and password is null",
+        )
+        .unwrap(),
+        intovec!["sayan"]
+    );
+}
+
+#[test]
+fn select_where_is_not_null_fails_on_null() {
+    let global = TestGlobal::new_with_driver_id_instant_update(
+        "dml_select_select_where_is_not_null_fails_on_null",
+    );
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null password: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "select username from myspace.mymodel where username = 'sayan' and password is not null",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+}
+
+#[test]
+fn select_where_cmp_null() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_where_cmp_null");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null password: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "select username from myspace.mymodel where username = 'sayan' and password = null",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    // a comparison with null is unknown, so it doesn't match even if the field is null (that's what `is null` is for)
+    for query in [
+        "select username from myspace.mymodel where username = 'sayan' and password != null",
+        "select username from myspace.mymodel where username = null",
+    ] {
+        assert_eq!(
+            super::_exec_only_select(&global, query).unwrap_err(),
+            QueryError::QExecDmlRowNotFound
+        );
+    }
+}
+
+#[test]
+fn select_coalesce() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_coalesce");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null password: string, null email: string)",
+            "insert into myspace.mymodel('sayan', null, 'sayan@example.com')",
+            "select username, coalesce(password, email, 'none'), ifnull(password, 'none') from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec!["sayan", "sayan@example.com", "none"]
+    );
+}
+
+#[test]
+fn select_result_meta() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_result_meta");
+    let resp = super::exec_select_resp(
+        &global,
+        "create model myspace.mymodel(username: string, null password: string)",
+        "insert into myspace.mymodel('sayan', null)",
+        "select username, coalesce(password, 'none') from myspace.mymodel where username = 'sayan'",
+        true,
+    )
+    .unwrap();
+    let Response::WithMeta { meta, resp } = resp else {
+        panic!("expected a metadata frame")
+    };
+    let mut expected = vec![ResponseType::Meta.value_u8()];
+    expected.extend(b"2\n8\nusername");
+    expected.extend([ResponseType::String.value_u8(), 0]);
+    // computed columns have no name or type
+    expected.extend(b"0\n\xFF\x01");
+    assert_eq!(meta, expected);
+    assert!(matches!(
+        *resp,
+        Response::Serialized {
+            ty: ResponseType::Row,
+            size: 2,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn select_coalesce_all_null() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_coalesce_all_null");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null password: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "select coalesce(password, null) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec![Datacell::null()]
+    );
+}
+
+#[test]
+fn select_unknown_function() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_unknown_function");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null password: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "select nvl(password, 'none') from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecUnknownFunction
+    );
+}
+
+#[test]
+fn select_cast() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_cast");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, age: uint8, balance: sint64, code: string)",
+            "insert into myspace.mymodel('sayan', 22, -100, '42')",
+            "select cast(age as string), cast(balance as float64), cast(code as uint16) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec![
+            "22",
+            Datacell::new_float(-100.0, unsafe { FloatSpec::from_full(FullTag::new_float(TagSelector::Float64)) }),
+            Datacell::new_uint(42, unsafe { UIntSpec::from_full(FullTag::new_uint(TagSelector::UInt16)) })
+        ]
+    );
+}
+
+#[test]
+fn select_cast_lossy() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_cast_lossy");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, balance: sint64)",
+            "insert into myspace.mymodel('sayan', -100)",
+            "select cast(balance as uint64) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlLossyCast
+    );
+}
+
+#[test]
+fn select_cast_invalid() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_cast_invalid");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, code: string)",
+            "insert into myspace.mymodel('sayan', 'forty two')",
+            "select cast(code as uint64) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn select_where_cast() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_where_cast");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(id: uint64, username: string)",
+            "insert into myspace.mymodel(42, 'sayan')",
+            "select username from myspace.mymodel where id = cast('42' as uint64)",
+        )
+        .unwrap(),
+        intovec!["sayan"]
+    );
+}
+
+#[test]
+fn select_scalar_functions() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_scalar_functions");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, name: string, balance: sint64, rating: float64)",
+    )
+    .unwrap();
+    super::_exec_only_insert_params(
+        &global,
+        "insert into myspace.mymodel(?, ?, ?, ?)",
+        b"\x065\nsayan\x0612\nSayan Nandan\x03-100\n\x044.567\n",
+    )
+    .unwrap();
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select lower(name), upper(name), length(name), substr(name, 7), substr(name, 1, 5), concat(username, ':', balance), abs(balance), round(rating, 1) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec![
+            "sayan nandan",
+            "SAYAN NANDAN",
+            12u64,
+            "Nandan",
+            "Sayan",
+            "sayan:-100",
+            100i64,
+            4.6f64
+        ]
+    );
+}
+
+#[test]
+fn select_scalar_function_null_propagation() {
+    let global = TestGlobal::new_with_driver_id_instant_update(
+        "dml_select_select_scalar_function_null_propagation",
+    );
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, null name: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "select upper(name), concat(username, name), ifnull(length(name), 0) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec![Datacell::null(), Datacell::null(), 0u64]
+    );
+}
+
+#[test]
+fn select_scalar_function_bad_args() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_scalar_function_bad_args");
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, balance: sint64)",
+            "insert into myspace.mymodel('sayan', -100)",
+            "select upper(balance) from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn select_arithmetic() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_arithmetic");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8, balance: sint64, score: float64, null bonus: uint64)",
+    )
+    .unwrap();
+    super::_exec_only_insert_params(
+        &global,
+        "insert into myspace.mymodel(?, ?, ?, ?, ?)",
+        b"\x065\nsayan\x0222\n\x03-100\n\x041.5\n\x00",
+    )
+    .unwrap();
+    let select = |query: &str| super::_exec_only_select(&global, query);
+    assert_eq!(
+        select("select age + 1, age * 2 - balance, score / 2, (age + 2) * 3, age + bonus from myspace.mymodel where username = 'sayan'")
+            .unwrap(),
+        intovec![23u64, 144i64, 0.75f64, 72u64, Datacell::null()]
+    );
+    // unsigned integers stay unsigned
+    assert_eq!(
+        select("select age - 30 from myspace.mymodel where username = 'sayan'").unwrap_err(),
+        QueryError::QExecDmlLossyCast
+    );
+    assert_eq!(
+        select("select age / 0 from myspace.mymodel where username = 'sayan'").unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert_eq!(
+        select("select username + 1 from myspace.mymodel where username = 'sayan'").unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    // anything with a null is null (even if it would fail otherwise)
+    assert_eq!(
+        select("select bonus * 0, bonus - bonus, null + 1, age / null from myspace.mymodel where username = 'sayan'")
+            .unwrap(),
+        intovec![Datacell::null(), Datacell::null(), Datacell::null(), Datacell::null()]
+    );
+}
+
+#[test]
+fn select_result_meta_aliases() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_result_meta_aliases");
+    let resp = super::exec_select_resp(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8)",
+        "insert into myspace.mymodel('sayan', 22)",
+        "select username as name, age + 1 as next_age from myspace.mymodel where username = 'sayan'",
+        true,
+    )
+    .unwrap();
+    let Response::WithMeta { meta, .. } = resp else {
+        panic!("expected a metadata frame")
+    };
+    let mut expected = vec![ResponseType::Meta.value_u8()];
+    // an aliased field keeps the type of the field
+    expected.extend(b"2\n4\nname");
+    expected.extend([ResponseType::String.value_u8(), 0]);
+    expected.extend(b"8\nnext_age\xFF\x01");
+    assert_eq!(meta, expected);
+}
+
 /*
     select all
 */
@@ -165,3 +522,990 @@ fn select_all_onefield() {
     assert_eq!(ret.get("hgwells").unwrap(), &intovec![]);
     assert_eq!(ret.get("orwell").unwrap(), &intovec![]);
 }
+
+#[test]
+fn select_all_where() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_where");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8, rating: float64, null nick: string)",
+    )
+    .unwrap();
+    for params in [
+        &b"\x065\nsayan\x0225\n\x044.5\n\x067\nohsayan"[..],
+        b"\x065\nrobot\x023\n\x044.9\n\x00",
+        b"\x067\ndouglas\x0242\n\x042.0\n\x0610\nhitchhiker",
+        b"\x067\nhgwells\x0279\n\x043.5\n\x00",
+        b"\x066\norwell\x0246\n\x044.0\n\x066\ngeorge",
+    ] {
+        super::_exec_only_insert_params(&global, "insert into myspace.mymodel(?, ?, ?, ?)", params)
+            .unwrap();
+    }
+    let ret = super::_exec_only_select_all(
+        &global,
+        "select all username from myspace.mymodel where age >= 18 and rating > 3 and nick is not null limit 100",
+    )
+    .unwrap();
+    let mut ret: Vec<String> = ret
+        .into_iter()
+        .map(|mut d| d.swap_remove(0).into_str().unwrap())
+        .collect();
+    ret.sort();
+    assert_eq!(ret, ["orwell", "sayan"]);
+}
+
+#[test]
+fn select_all_where_three_valued() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_where_three_valued");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, null score: sint64)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', -1)",
+        "insert into myspace.mymodel('robot', null)",
+        "insert into myspace.mymodel('douglas', 42)",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    let select = |clause: &str| {
+        let mut ret: Vec<String> = super::_exec_only_select_all(
+            &global,
+            &format!("select all username from myspace.mymodel where {clause} limit 100"),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|mut d| d.swap_remove(0).into_str().unwrap())
+        .collect();
+        ret.sort();
+        ret
+    };
+    // a null field never passes a comparison, not even `!=`
+    assert_eq!(select("score != 0"), ["douglas", "sayan"]);
+    assert_eq!(select("score > -100"), ["douglas", "sayan"]);
+    // and neither does any field compared with null
+    assert!(select("score = null").is_empty());
+    assert!(select("score != null").is_empty());
+    assert_eq!(select("score is null"), ["robot"]);
+    // the same on the columnar cache
+    assert_eq!(
+        super::_exec_only_cache_model(&global, EntityIDRef::new("myspace", "mymodel")),
+        3
+    );
+    assert_eq!(select("score != 0"), ["douglas", "sayan"]);
+    assert!(select("score = null").is_empty());
+}
+
+#[test]
+fn select_all_packed_columns() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_packed");
+    let resp = super::exec_select_all_packed(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint64, rating: float64, null score: uint64)",
+        "insert into myspace.mymodel(?, ?, ?, ?)",
+        &[
+            b"\x065\nsayan\x0225\n\x044.5\n\x00",
+            b"\x065\nrobot\x0225\n\x044.5\n\x0210\n",
+        ],
+        "select all age, rating, score from myspace.mymodel limit 100",
+    )
+    .unwrap();
+    let Response::SerializedCharged {
+        ty: ResponseType::PackedRows,
+        size: 2,
+        data,
+        ..
+    } = resp
+    else {
+        panic!("expected packed rows")
+    };
+    let mut expected = b"3\n\x01".to_vec();
+    expected.extend([25u64.to_le_bytes(), 25u64.to_le_bytes()].concat());
+    expected.push(2);
+    expected.extend([4.5f64.to_le_bytes(), 4.5f64.to_le_bytes()].concat());
+    // a column with nulls falls back to regular cells
+    expected.push(0);
+    let (null_first, null_last) = (
+        [&expected[..], b"\x00\x0510\n"].concat(),
+        [&expected[..], b"\x0510\n\x00"].concat(),
+    );
+    assert!(data == null_first || data == null_last);
+}
+
+#[test]
+fn select_all_where_limit_counts_matches() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_where_limit");
+    let inserts: Vec<String> = (0..3000u64)
+        .map(|i| format!("insert into myspace.mymodel({i}, {})", i % 3))
+        .collect();
+    let inserts: Vec<&str> = inserts.iter().map(String::as_str).collect();
+    let ret = super::exec_select_all(
+        &global,
+        "create model myspace.mymodel(id: uint64, bucket: uint8)",
+        &inserts,
+        "select all id, bucket from myspace.mymodel where bucket = 1 and id < 2500 limit 500",
+    )
+    .unwrap();
+    // 833 rows match but we only asked for 500 of them
+    assert_eq!(ret.len(), 500);
+    assert!(ret
+        .iter()
+        .all(|row| (row[1].uint() == 1) & (row[0].uint() < 2500)));
+}
+
+#[test]
+fn select_all_offset() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_offset");
+    let inserts: Vec<String> = (0..10u64)
+        .map(|i| format!("insert into myspace.mymodel({i}, {})", i % 2))
+        .collect();
+    let inserts: Vec<&str> = inserts.iter().map(String::as_str).collect();
+    let ret = super::exec_select_all(
+        &global,
+        "create model myspace.mymodel(id: uint64, bucket: uint8)",
+        &inserts,
+        "select all id from myspace.mymodel limit 4 offset 8",
+    )
+    .unwrap();
+    assert_eq!(ret.len(), 2);
+    // paging through the model visits every row exactly once
+    let mut seen: Vec<u64> = [0, 3, 6, 9]
+        .into_iter()
+        .flat_map(|offset| {
+            super::_exec_only_select_all(
+                &global,
+                &format!("select all id from myspace.mymodel limit 3 offset {offset}"),
+            )
+            .unwrap()
+        })
+        .map(|row| row[0].uint())
+        .collect();
+    seen.sort();
+    assert_eq!(seen, (0..10).collect::<Vec<_>>());
+    // skipping past the end just returns nothing
+    assert!(super::_exec_only_select_all(
+        &global,
+        "select all id from myspace.mymodel limit 3 offset 10"
+    )
+    .unwrap()
+    .is_empty());
+    // the offset counts the rows that match the filter
+    let ret = super::_exec_only_select_all(
+        &global,
+        "select all id, bucket from myspace.mymodel where bucket = 1 limit 100 offset 3",
+    )
+    .unwrap();
+    assert_eq!(ret.len(), 2);
+    assert!(ret.iter().all(|row| row[1].uint() == 1));
+}
+
+#[test]
+fn select_all_order_by() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_order_by");
+    let usernames = |ret: Vec<Vec<Datacell>>| -> Vec<String> {
+        ret.into_iter()
+            .map(|mut d| d.swap_remove(0).into_str().unwrap())
+            .collect()
+    };
+    let ret = super::exec_select_all(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8, null score: sint64)",
+        &[
+            "insert into myspace.mymodel('sayan', 25, -7)",
+            "insert into myspace.mymodel('robot', 3, null)",
+            "insert into myspace.mymodel('douglas', 42, -42)",
+            "insert into myspace.mymodel('hgwells', 79, -100)",
+            "insert into myspace.mymodel('orwell', 46, -1)",
+        ],
+        "select all username from myspace.mymodel order by age limit 100",
+    )
+    .unwrap();
+    assert_eq!(
+        usernames(ret),
+        ["robot", "sayan", "douglas", "orwell", "hgwells"]
+    );
+    // nulls come first in descending order, and the bounds apply to the ordered rows
+    let ret = super::_exec_only_select_all(
+        &global,
+        "select all username from myspace.mymodel order by score desc limit 2 offset 1",
+    )
+    .unwrap();
+    assert_eq!(usernames(ret), ["orwell", "sayan"]);
+    let ret = super::_exec_only_select_all(
+        &global,
+        "select all username from myspace.mymodel where age >= 18 order by username limit 100",
+    )
+    .unwrap();
+    assert_eq!(usernames(ret), ["douglas", "hgwells", "orwell", "sayan"]);
+    let ret = super::_exec_only_select_all(
+        &global,
+        "select all username from myspace.mymodel order by username desc limit 2",
+    )
+    .unwrap();
+    assert_eq!(usernames(ret), ["sayan", "robot"]);
+    assert_eq!(
+        super::_exec_only_select_all(
+            &global,
+            "select all username from myspace.mymodel order by email limit 100",
+        )
+        .unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+}
+
+#[test]
+fn select_working_memory_is_charged() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_working_memory_is_charged");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint64)",
+    )
+    .unwrap();
+    for i in 0..100u64 {
+        super::_exec_only_insert(
+            &global,
+            &format!("insert into myspace.mymodel('user{i}', {i})"),
+            |_| {},
+        )
+        .unwrap();
+    }
+    const TINY: usize = 64;
+    const ROOMY: usize = 1 << 20;
+    // the rows that an order by sorts
+    for select in [
+        "select all username from myspace.mymodel order by age limit 1",
+        "select all username from myspace.mymodel where age >= 10 order by age limit 1",
+    ] {
+        assert_eq!(
+            super::_exec_only_select_all_charged(&global, select, &QueryMemory::with_limit(TINY))
+                .unwrap_err(),
+            QueryError::QExecQueryMemoryLimit
+        );
+        let mem = QueryMemory::with_limit(ROOMY);
+        assert_eq!(
+            super::_exec_only_select_all_charged(&global, select, &mem)
+                .unwrap()
+                .len(),
+            1
+        );
+        // the rows are released once the query is done with them
+        mem.charge_upto(ROOMY).unwrap();
+    }
+    // the keys of an ordered multi-get
+    let select = "select username from myspace.mymodel where username in ['user1', 'user2', 'user3', 'user4'] order by age desc";
+    assert_eq!(
+        super::_exec_only_select_multi_charged(&global, select, &QueryMemory::with_limit(TINY))
+            .unwrap_err(),
+        QueryError::QExecQueryMemoryLimit
+    );
+    let mem = QueryMemory::with_limit(ROOMY);
+    assert_eq!(
+        super::_exec_only_select_multi_charged(&global, select, &mem).unwrap(),
+        vec![
+            Some(intovec!["user4"]),
+            Some(intovec!["user3"]),
+            Some(intovec!["user2"]),
+            Some(intovec!["user1"]),
+        ]
+    );
+    mem.charge_upto(ROOMY).unwrap();
+    // the groups of a group by
+    let select = "select username, count(*) from myspace.mymodel group by username";
+    assert_eq!(
+        super::_exec_only_aggregate_charged(&global, select, &QueryMemory::with_limit(TINY))
+            .unwrap_err(),
+        QueryError::QExecQueryMemoryLimit
+    );
+    let mem = QueryMemory::with_limit(ROOMY);
+    assert_eq!(
+        super::_exec_only_aggregate_charged(&global, select, &mem)
+            .unwrap()
+            .len(),
+        100
+    );
+    mem.charge_upto(ROOMY).unwrap();
+    // the working buffers and the result buffer share the query's limit
+    let mem = QueryMemory::with_limit(ROOMY);
+    mem.charge_upto(ROOMY - TINY).unwrap();
+    assert_eq!(
+        super::_exec_only_select_all_charged(
+            &global,
+            "select all username from myspace.mymodel order by age limit 1",
+            &mem
+        )
+        .unwrap_err(),
+        QueryError::QExecQueryMemoryLimit
+    );
+}
+
+#[test]
+fn select_all_where_unsupported() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_where_unsupported");
+    assert_eq!(
+        super::exec_select_all(
+            &global,
+            "create model myspace.mymodel(username: string, password: string)",
+            &["insert into myspace.mymodel('sayan', 'password123')"],
+            "select all * from myspace.mymodel where password = 'password123' limit 100",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+}
+
+#[test]
+fn select_all_where_columnar_cache() {
+    let global =
+        TestGlobal::new_with_driver_id_instant_update("dml_select_select_all_where_columnar_cache");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8, null score: sint64)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', 25, -1)",
+        "insert into myspace.mymodel('robot', 3, null)",
+        "insert into myspace.mymodel('douglas', 42, -42)",
+        "insert into myspace.mymodel('hgwells', 79, -7)",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    assert_eq!(
+        super::_exec_only_cache_model(&global, EntityIDRef::new("myspace", "mymodel")),
+        4
+    );
+    // the cache should follow every change made after it was built
+    super::_exec_only_insert(
+        &global,
+        "insert into myspace.mymodel('orwell', 46, -19)",
+        |_| {},
+    )
+    .unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set age = 17 where username = 'sayan'",
+    )
+    .unwrap();
+    super::_exec_delete_only(
+        &global,
+        "delete from myspace.mymodel where username = 'douglas'",
+        "douglas",
+    )
+    .unwrap();
+    let mut ret: Vec<String> = super::_exec_only_select_all(
+        &global,
+        "select all username from myspace.mymodel where age >= 18 and score is not null limit 100",
+    )
+    .unwrap()
+    .into_iter()
+    .map(|mut d| d.swap_remove(0).into_str().unwrap())
+    .collect();
+    ret.sort();
+    assert_eq!(ret, ["hgwells", "orwell"]);
+    let ret = super::_exec_only_select_all(
+        &global,
+        "select all username, score from myspace.mymodel where score < -20 limit 100",
+    )
+    .unwrap();
+    assert!(ret.is_empty());
+}
+
+#[test]
+fn select_all_where_secondary_index() {
+    let global = TestGlobal::new_with_driver_id_instant_update(
+        "dml_select_select_all_where_secondary_index",
+    );
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8, null city: string, score: float64)",
+    )
+    .unwrap();
+    for params in [
+        &b"\x065\nsayan\x0225\n\x066\nlondon\x041.0\n"[..],
+        b"\x065\nrobot\x023\n\x00\x042.0\n",
+        b"\x067\ndouglas\x0242\n\x066\nlondon\x043.0\n",
+        b"\x067\nhgwells\x0279\n\x067\nbromley\x044.0\n",
+    ] {
+        super::_exec_only_insert_params(&global, "insert into myspace.mymodel(?, ?, ?, ?)", params)
+            .unwrap();
+    }
+    let select_city = |city: &str| {
+        super::_exec_only_select_all(
+            &global,
+            &format!("select all username from myspace.mymodel where city = '{city}' limit 100"),
+        )
+        .map(|rows| {
+            let mut rows: Vec<String> = rows
+                .into_iter()
+                .map(|mut d| d.swap_remove(0).into_str().unwrap())
+                .collect();
+            rows.sort();
+            rows
+        })
+    };
+    assert_eq!(
+        select_city("london").unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+    // bad indexes
+    for (create, error) in [
+        (
+            "create index idx on myspace.mymodel(nope)",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "create index idx on myspace.mymodel(username)",
+            QueryError::QExecDdlModelBadDefinition,
+        ),
+        (
+            "create index idx on myspace.mymodel(score)",
+            QueryError::QExecDdlModelBadDefinition,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_create_index(&global, create).unwrap_err(),
+            error
+        );
+    }
+    assert_eq!(
+        super::_exec_only_create_index(&global, "create index by_city on myspace.mymodel(city)"),
+        Ok(None)
+    );
+    assert_eq!(
+        super::_exec_only_create_index(&global, "create index by_city on myspace.mymodel(age)")
+            .unwrap_err(),
+        QueryError::QExecDdlObjectAlreadyExists
+    );
+    assert_eq!(
+        super::_exec_only_create_index(
+            &global,
+            "create index if not exists city_again on myspace.mymodel(city)"
+        ),
+        Ok(Some(false))
+    );
+    assert_eq!(select_city("london").unwrap(), ["douglas", "sayan"]);
+    // the index should follow every change made after it was built
+    super::_exec_only_insert_params(
+        &global,
+        "insert into myspace.mymodel(?, ?, ?, ?)",
+        b"\x066\norwell\x0246\n\x066\nlondon\x045.0\n",
+    )
+    .unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set city = 'bromley' where username = 'sayan'",
+    )
+    .unwrap();
+    super::_exec_delete_only(
+        &global,
+        "delete from myspace.mymodel where username = 'douglas'",
+        "douglas",
+    )
+    .unwrap();
+    assert_eq!(select_city("london").unwrap(), ["orwell"]);
+    assert_eq!(select_city("bromley").unwrap(), ["hgwells", "sayan"]);
+    assert!(select_city("paris").unwrap().is_empty());
+    // the rest of the filter still applies to the rows found through the index
+    assert_eq!(
+        super::_exec_only_select_all(
+            &global,
+            "select all username from myspace.mymodel where city = 'bromley' and age > 50 limit 100",
+        )
+        .unwrap(),
+        vec![intovec!["hgwells"]]
+    );
+    // and it's gone
+    assert_eq!(
+        super::_exec_only_drop_index(&global, "drop index by_city on myspace.mymodel"),
+        Ok(None)
+    );
+    assert_eq!(
+        super::_exec_only_drop_index(&global, "drop index by_city on myspace.mymodel").unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+    assert_eq!(
+        super::_exec_only_drop_index(&global, "drop index if exists by_city on myspace.mymodel"),
+        Ok(Some(false))
+    );
+    assert_eq!(
+        select_city("london").unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+}
+
+#[test]
+fn select_multi_get() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_select_multi_get");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', 25)",
+        "insert into myspace.mymodel('robot', 3)",
+        "insert into myspace.mymodel('douglas', 42)",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    // rows come back in the order of the keys, with a marker for the keys that weren't found
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select username, age from myspace.mymodel where username in ['douglas', 'orwell', 'sayan']",
+        )
+        .unwrap(),
+        vec![
+            Some(intovec!["douglas", 42u64]),
+            None,
+            Some(intovec!["sayan", 25u64]),
+        ]
+    );
+    // the bounds apply to the keys, including the ones that weren't found
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select username from myspace.mymodel where username in ['douglas', 'orwell', 'sayan', 'robot'] limit 2 offset 1",
+        )
+        .unwrap(),
+        vec![None, Some(intovec!["sayan"])]
+    );
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select * from myspace.mymodel where username in []"
+        )
+        .unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        super::_exec_only_select_multi(&global, "select * from myspace.mymodel where age in [25]")
+            .unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select * from myspace.mymodel where username in [25]"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn select_multi_get_order_by() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_multi_get_order_by");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', 25)",
+        "insert into myspace.mymodel('robot', 3)",
+        "insert into myspace.mymodel('douglas', 42)",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select username from myspace.mymodel where username in ['douglas', 'orwell', 'sayan', 'robot'] order by age",
+        )
+        .unwrap(),
+        vec![
+            Some(intovec!["robot"]),
+            Some(intovec!["sayan"]),
+            Some(intovec!["douglas"]),
+            None,
+        ]
+    );
+    // the keys that weren't found are ordered like nulls
+    assert_eq!(
+        super::_exec_only_select_multi(
+            &global,
+            "select username from myspace.mymodel where username in ['douglas', 'orwell', 'sayan', 'robot'] order by age desc limit 2 offset 1",
+        )
+        .unwrap(),
+        vec![Some(intovec!["douglas"]), Some(intovec!["sayan"])]
+    );
+}
+
+#[test]
+fn select_all_pk_starts_with() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_all_pk_starts_with");
+    for index in ["radix", "hash"] {
+        super::_exec_only_create_space_model(
+            &global,
+            &format!(
+                "create model myspace.{index}(path: string, size: uint64) with {{ pk_index: '{index}' }}"
+            ),
+        )
+        .unwrap();
+        for (path, size) in [
+            ("/usr/bin/ls", 10),
+            ("/usr/bin/cat", 20),
+            ("/usr/lib/libc.so", 30),
+            ("/var/log/syslog", 40),
+        ] {
+            super::_exec_only_insert(
+                &global,
+                &format!("insert into myspace.{index}('{path}', {size})"),
+                |_| {},
+            )
+            .unwrap();
+        }
+        let mut ret: Vec<String> = super::_exec_only_select_all(
+            &global,
+            &format!(
+                "select all path from myspace.{index} where path starts with '/usr/bin/' limit 100"
+            ),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|mut d| d.swap_remove(0).into_str().unwrap())
+        .collect();
+        // only the radix index returns keys in order
+        ret.sort();
+        assert_eq!(ret, ["/usr/bin/cat", "/usr/bin/ls"]);
+        assert_eq!(
+            super::_exec_only_select_all(
+                &global,
+                &format!(
+                    "select all path from myspace.{index} where path starts with '/usr' and size > 15 limit 100"
+                ),
+            )
+            .unwrap()
+            .len(),
+            2
+        );
+        assert_eq!(
+            super::_exec_only_select_all(
+                &global,
+                &format!("select all path from myspace.{index} where size starts with 1 limit 100"),
+            )
+            .unwrap_err(),
+            QueryError::QExecDmlWhereHasUnindexedColumn
+        );
+    }
+}
+
+#[test]
+fn select_all_pk_range() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_all_pk_range");
+    for index in ["radix", "hash"] {
+        super::_exec_only_create_space_model(
+            &global,
+            &format!(
+                "create model myspace.{index}(name: string, size: uint64) with {{ pk_index: '{index}' }}"
+            ),
+        )
+        .unwrap();
+        for (name, size) in [
+            ("user:099", 1),
+            ("user:100", 2),
+            ("user:150", 3),
+            ("user:199", 4),
+            ("user:200", 5),
+            ("zebra", 6),
+        ] {
+            super::_exec_only_insert(
+                &global,
+                &format!("insert into myspace.{index}('{name}', {size})"),
+                |_| {},
+            )
+            .unwrap();
+        }
+        let select = |clause: &str| {
+            let mut ret: Vec<String> = super::_exec_only_select_all(
+                &global,
+                &format!("select all name from myspace.{index} where {clause} limit 100"),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|mut d| d.swap_remove(0).into_str().unwrap())
+            .collect();
+            ret.sort();
+            ret
+        };
+        assert_eq!(
+            select("name between 'user:100' and 'user:199'"),
+            ["user:100", "user:150", "user:199"]
+        );
+        assert_eq!(
+            select("name > 'user:100' and name < 'user:200'"),
+            ["user:150", "user:199"]
+        );
+        assert_eq!(
+            select("name >= 'user:150' and size < 6"),
+            ["user:150", "user:199", "user:200"]
+        );
+        assert_eq!(select("name <= 'user:100'"), ["user:099", "user:100"]);
+        assert!(select("name between 'user:200' and 'user:100'").is_empty());
+    }
+    // and on integer keys
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.numbers(id: uint64, square: uint64)",
+    )
+    .unwrap();
+    for id in 0..20u64 {
+        super::_exec_only_insert(
+            &global,
+            &format!("insert into myspace.numbers({id}, {})", id * id),
+            |_| {},
+        )
+        .unwrap();
+    }
+    let mut ret: Vec<u64> = super::_exec_only_select_all(
+        &global,
+        "select all id from myspace.numbers where id between 5 and 8 limit 100",
+    )
+    .unwrap()
+    .into_iter()
+    .map(|mut d| d.swap_remove(0).uint())
+    .collect();
+    ret.sort();
+    assert_eq!(ret, [5, 6, 7, 8]);
+}
+
+#[test]
+fn select_aggregate() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_aggregate");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.users(username: string, null age: uint64, balance: sint64)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.users('a', 20, -5)",
+        "insert into myspace.users('b', 30, -10)",
+        "insert into myspace.users('c', null, -1)",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    let select = |query: &str| super::_exec_only_select(&global, query);
+    assert_eq!(
+        select("select count(*) from myspace.users").unwrap(),
+        intovec![3u64]
+    );
+    // nulls are skipped
+    assert_eq!(
+        select("select count(age), sum(age), avg(age), min(age), max(age) from myspace.users")
+            .unwrap(),
+        intovec![2u64, 50u64, 25.0f64, 20u64, 30u64]
+    );
+    assert_eq!(
+        select("select sum(balance), min(username), max(balance) from myspace.users").unwrap(),
+        intovec![-16i64, "a", -1i64]
+    );
+    assert_eq!(
+        select("select count(*), sum(age) from myspace.users where age > 20").unwrap(),
+        intovec![1u64, 30u64]
+    );
+    // nothing to aggregate
+    assert_eq!(
+        select(
+            "select count(*), sum(age), avg(age), max(username) from myspace.users where age > 100"
+        )
+        .unwrap(),
+        intovec![0u64, Datacell::null(), Datacell::null(), Datacell::null()]
+    );
+    assert_eq!(
+        select("select sum(username) from myspace.users").unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert_eq!(
+        select("select count(email) from myspace.users").unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+}
+
+#[test]
+fn select_group_by() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_group_by");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.users(username: string, null city: string, age: uint64)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.users('a', 'paris', 20)",
+        "insert into myspace.users('b', 'tokyo', 30)",
+        "insert into myspace.users('c', 'paris', 40)",
+        "insert into myspace.users('d', null, 50)",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    // groups come back in no particular order
+    let select = |query: &str, col_c: usize| -> Vec<Vec<Datacell>> {
+        let cells = super::_exec_only_select(&global, query).unwrap();
+        cells.chunks(col_c).map(|row| row.to_vec()).collect()
+    };
+    let rows = select(
+        "select city, count(*), sum(age) from myspace.users group by city",
+        3,
+    );
+    assert_eq!(rows.len(), 3);
+    for row in [
+        intovec!["paris", 2u64, 60u64],
+        intovec!["tokyo", 1u64, 30u64],
+        intovec![Datacell::null(), 1u64, 50u64],
+    ] {
+        assert!(rows.contains(&row), "missing group {row:?}");
+    }
+    let rows = select(
+        "select max(username), city from myspace.users where age > 25 group by city",
+        2,
+    );
+    assert_eq!(rows.len(), 3);
+    for row in [
+        intovec!["c", "paris"],
+        intovec!["b", "tokyo"],
+        intovec!["d", Datacell::null()],
+    ] {
+        assert!(rows.contains(&row), "missing group {row:?}");
+    }
+    // no rows, no groups
+    assert!(select(
+        "select city, count(*) from myspace.users where age > 100 group by city",
+        2
+    )
+    .is_empty());
+    assert_eq!(
+        super::_exec_only_select(&global, "select count(*) from myspace.users group by email")
+            .unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+}
+
+#[test]
+fn select_group_by_resp() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_group_by_resp");
+    let resp = super::exec_select_resp(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "select password, count(*) from myspace.mymodel group by password",
+        false,
+    )
+    .unwrap();
+    let Response::SerializedCharged { ty, size, data, .. } = resp else {
+        panic!("expected rows")
+    };
+    assert_eq!((ty, size), (ResponseType::MultiRow, 1));
+    assert_eq!(
+        data,
+        [
+            &b"2\n"[..],
+            &[ResponseType::String.value_u8()],
+            b"7\npass123",
+            &[ResponseType::UInt64.value_u8()],
+            b"1\n",
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn select_aggregate_scalar_resp() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_aggregate_scalar_resp");
+    let resp = super::exec_select_resp(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "select count(*) from myspace.mymodel",
+        false,
+    )
+    .unwrap();
+    let Response::Cell(cell) = resp else {
+        panic!("expected a scalar")
+    };
+    assert_eq!(cell, [ResponseType::UInt64.value_u8(), b'1', b'\n']);
+}
+
+#[test]
+fn explain_select() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_select_explain_select");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, age: uint8, null city: string)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', 25, 'london')",
+        "insert into myspace.mymodel('robot', 3, null)",
+        "insert into myspace.mymodel('douglas', 42, 'london')",
+        "insert into myspace.mymodel('hgwells', 79, 'bromley')",
+    ] {
+        super::_exec_only_insert(&global, insert, |_| {}).unwrap();
+    }
+    let explain = |explain: &str| super::_exec_only_explain(&global, explain);
+    assert_eq!(
+        explain("explain select * from myspace.mymodel where username = 'sayan' and city is null")
+            .unwrap(),
+        dml::Plan::new_test(
+            dml::AccessPath::PointLookup,
+            None,
+            1,
+            &["city is null", "username = ?"]
+        )
+    );
+    assert_eq!(
+        explain(
+            "explain select * from myspace.mymodel where username in ['sayan', 'robot', 'nope']"
+        )
+        .unwrap(),
+        dml::Plan::new_test(dml::AccessPath::MultiGet, None, 3, &["username in ?"])
+    );
+    assert_eq!(
+        explain("explain select count(*) from myspace.mymodel where age > 30").unwrap(),
+        dml::Plan::new_test(dml::AccessPath::FullScan, None, 1, &["age > ?"])
+    );
+    assert_eq!(
+        explain("explain select all * from myspace.mymodel limit 2 offset 1").unwrap(),
+        dml::Plan::new_test(dml::AccessPath::FullScan, None, 2, &[])
+    );
+    assert_eq!(
+        explain("explain select all * from myspace.mymodel where age > 30 limit 100").unwrap(),
+        dml::Plan::new_test(dml::AccessPath::FullScan, None, 4, &["age > ?"])
+    );
+    // the plan follows the index once it's built
+    assert_eq!(
+        explain("explain select all * from myspace.mymodel where city = 'london' limit 100")
+            .unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+    super::_exec_only_create_index(&global, "create index by_city on myspace.mymodel(city)")
+        .unwrap();
+    assert_eq!(
+        explain("explain select all * from myspace.mymodel where city = 'london' limit 100")
+            .unwrap(),
+        dml::Plan::new_test(
+            dml::AccessPath::IndexScan,
+            Some("by_city"),
+            2,
+            &["city = ?"]
+        )
+    );
+    // and the columnar cache once it's built
+    super::_exec_only_cache_model(&global, EntityIDRef::new("myspace", "mymodel"));
+    assert_eq!(
+        explain("explain select all * from myspace.mymodel where age > 30 limit 100").unwrap(),
+        dml::Plan::new_test(dml::AccessPath::CacheScan, None, 4, &["age > ?"])
+    );
+    // nothing is run, but the select must still be valid
+    assert_eq!(
+        explain("explain select * from myspace.mymodel where age = 25").unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+    assert_eq!(
+        explain("explain select all * from myspace.mymodel order by nope limit 10").unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+}
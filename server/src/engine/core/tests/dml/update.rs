@@ -24,8 +24,11 @@
  *
 */
 
-use crate::engine::{
-    core::dml, data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal,
+use {
+    crate::engine::{
+        core::dml, data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal,
+    },
+    std::collections::BTreeMap,
 };
 
 #[test]
@@ -47,6 +50,36 @@ fn simple() {
     );
 }
 
+#[test]
+fn with_cast() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_with_cast");
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, followers: uint64, score: float64)",
+    )
+    .unwrap();
+    // the test lexer can't read floats
+    super::_exec_only_insert_params(
+        &global,
+        "insert into myspace.mymodel(?, ?, ?)",
+        b"\x065\nsayan\x02100\n\x041.5\n",
+    )
+    .unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set followers += cast('50' as uint64), score = cast(2 as float64) where username = cast('sayan' as string)",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", 150_u64, 2.0_f64]
+    );
+}
+
 #[test]
 fn with_null() {
     let global = TestGlobal::new_with_driver_id_instant_update("dml_update_with_null");
@@ -84,6 +117,33 @@ fn with_list() {
     assert_eq!(dml::update_flow_trace(), ["list;sametag"]);
 }
 
+#[test]
+fn with_list_bounds() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_with_list_bounds");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(link: string, click_ids: list { type: string, max_len: 1, element_max: 8 })",
+            "insert into myspace.mymodel('example.com', [])",
+            "update myspace.mymodel set click_ids += 'ios_uuid' where link = 'example.com'",
+            "select * from myspace.mymodel where link = 'example.com'"
+        )
+        .unwrap(),
+        intovec!["example.com", Datacell::new_list(intovec!["ios_uuid"])]
+    );
+    assert_eq!(dml::update_flow_trace(), ["list;sametag"]);
+    // the list is full
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set click_ids += 'web_uuid' where link = 'example.com'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert_eq!(dml::update_flow_trace(), ["list;outofbounds", "rollback"]);
+}
+
 #[test]
 fn fail_operation_on_null() {
     let global = TestGlobal::new_with_driver_id_instant_update("dml_update_fail_operation_on_null");
@@ -158,3 +218,327 @@ fn fail_typedef_violation() {
         intovec!["sayan", "pass123", 1u64]
     );
 }
+
+#[test]
+fn list_elements() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_list_elements");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(link: string, clicks: list { type: uint64 })",
+            "insert into myspace.mymodel('example.com', [1, 2, 3])",
+            "update myspace.mymodel set clicks[0] = 10, clicks[2] += 5 where link = 'example.com'",
+            "select * from myspace.mymodel where link = 'example.com'"
+        )
+        .unwrap(),
+        intovec![
+            "example.com",
+            Datacell::new_list(intovec![10_u64, 2_u64, 8_u64])
+        ]
+    );
+    assert_eq!(dml::update_flow_trace(), ["list;element", "list;element"]);
+    // pop off the end
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set clicks -= 2, clicks += 4 where link = 'example.com'",
+    )
+    .unwrap();
+    assert_eq!(dml::update_flow_trace(), ["list;pop", "list;sametag"]);
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select clicks from myspace.mymodel where link = 'example.com'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_list(intovec![10_u64, 4_u64])]
+    );
+}
+
+#[test]
+fn fail_list_elements() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_fail_list_elements");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(link: string, owner: string, tags: list { type: string, element_max: 4 })",
+            "insert into myspace.mymodel('example.com', 'sayan', ['a', 'b'])",
+            "update myspace.mymodel set tags[0] = 'c', tags[1] = 'd' where link = 'example.com'",
+            "select tags from myspace.mymodel where link = 'example.com'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_list(intovec!["c", "d"])]
+    );
+    for (update, trace) in [
+        // out of range
+        (
+            "update myspace.mymodel set tags[0] = 'e', tags[2] = 'f' where link = 'example.com'",
+            ["list;element", "list;element;invalid", "rollback"].as_slice(),
+        ),
+        // the element is too large
+        (
+            "update myspace.mymodel set tags[1] += 'efgh' where link = 'example.com'",
+            &["list;element;invalid", "rollback"],
+        ),
+        // not a list
+        (
+            "update myspace.mymodel set owner[0] = 'e' where link = 'example.com'",
+            &["list;element;badtag", "rollback"],
+        ),
+        // more elements than there are in the list
+        (
+            "update myspace.mymodel set tags[0] = 'e', tags -= 3 where link = 'example.com'",
+            &["list;element", "list;pop;outofbounds", "rollback"],
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_update(&global, update).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+        assert_eq!(dml::update_flow_trace(), trace);
+    }
+    // nothing was changed
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select tags from myspace.mymodel where link = 'example.com'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_list(intovec!["c", "d"])]
+    );
+}
+
+#[test]
+fn map_keys() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_map_keys");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, prefs: map { type: string })",
+            "insert into myspace.mymodel('sayan', { 'theme': 'light', 'lang': 'en' })",
+            "update myspace.mymodel set prefs['theme'] = 'dark', prefs['font'] = 'mono', prefs -= 'lang' where username = 'sayan'",
+            "select prefs from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_map(BTreeMap::from([
+            ("font".into(), Datacell::new_str("mono".into())),
+            ("theme".into(), Datacell::new_str("dark".into())),
+        ]))]
+    );
+    assert_eq!(
+        dml::update_flow_trace(),
+        ["map;key", "map;key", "map;remove"]
+    );
+}
+
+#[test]
+fn fail_map_keys() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_fail_map_keys");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, counts: map { type: uint8 })",
+            "insert into myspace.mymodel('sayan', { 'a': 1 })",
+            "update myspace.mymodel set counts['a'] += 1 where username = 'sayan'",
+            "select counts from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_map(BTreeMap::from([(
+            "a".into(),
+            Datacell::new_uint_default(2)
+        )]))]
+    );
+    for (update, trace) in [
+        // overflows the value type
+        (
+            "update myspace.mymodel set counts['a'] += 255 where username = 'sayan'",
+            ["map;key;invalid", "rollback"].as_slice(),
+        ),
+        // only an assignment can add a key
+        (
+            "update myspace.mymodel set counts['b'] += 1 where username = 'sayan'",
+            &["map;key;invalid", "rollback"],
+        ),
+        // bad value type
+        (
+            "update myspace.mymodel set counts['b'] = 'x' where username = 'sayan'",
+            &["map;key;invalid", "rollback"],
+        ),
+        // a map is not indexed by position
+        (
+            "update myspace.mymodel set counts[0] = 1 where username = 'sayan'",
+            &["map;key;badtag", "rollback"],
+        ),
+        // no such key
+        (
+            "update myspace.mymodel set counts['b'] = 2, counts -= 'c' where username = 'sayan'",
+            &["map;key", "map;remove;notfound", "rollback"],
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_update(&global, update).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+        assert_eq!(dml::update_flow_trace(), trace);
+    }
+    // nothing was changed
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select counts from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_map(BTreeMap::from([(
+            "a".into(),
+            Datacell::new_uint_default(2)
+        )]))]
+    );
+}
+
+#[test]
+fn set_null() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_set_null");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, not null password: string, null email: string)",
+            "insert into myspace.mymodel('sayan', 'pass123', 'sayan@example.com')",
+            "update myspace.mymodel set email = null where username = 'sayan'",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass123", Datacell::null()]
+    );
+    assert_eq!(dml::update_flow_trace(), ["null"]);
+    // password isn't nullable
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set email = 'sayan@example.com', password = null where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlNullViolation
+    );
+    assert_eq!(
+        dml::update_flow_trace(),
+        ["sametag;orignull", "null;notnullable", "rollback"]
+    );
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass123", Datacell::null()]
+    );
+}
+
+#[test]
+fn arithmetic_with_null() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_arithmetic_with_null");
+    // arithmetic on a null field leaves it null
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, followers: uint64, null bonus: uint64)",
+            "insert into myspace.mymodel('sayan', 100, null)",
+            "update myspace.mymodel set bonus += 1 where username = 'sayan'",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", 100u64, Datacell::null()]
+    );
+    assert_eq!(dml::update_flow_trace(), ["sametag;orignull;propagated"]);
+    // and arithmetic with a null makes the field null
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set bonus = 10 where username = 'sayan'",
+    )
+    .unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set bonus *= null where username = 'sayan'",
+    )
+    .unwrap();
+    assert_eq!(dml::update_flow_trace(), ["null"]);
+    // unless the field isn't nullable
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += null where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlNullViolation
+    );
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", 100u64, Datacell::null()]
+    );
+}
+
+#[test]
+fn where_cmp_null() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_where_cmp_null");
+    // a comparison with null never matches, even if the field is null
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, null email: string)",
+            "insert into myspace.mymodel('sayan', null)",
+            "update myspace.mymodel set email = 'sayan@example.com' where username = 'sayan' and email = null",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+}
+
+#[test]
+fn unique_violation() {
+    let global = TestGlobal::new_with_driver_id_instant_update("dml_update_unique_violation");
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, unique email: string)",
+            "insert into myspace.mymodel('sayan', 'sayan@example.com')",
+            "update myspace.mymodel set email = 'sayan@skytable.io' where username = 'sayan'",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "sayan@skytable.io"]
+    );
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('elizabeth', 'sayan@example.com')",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set email = 'sayan@example.com' where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlUniqueViolation
+    );
+    assert_eq!(
+        dml::update_flow_trace(),
+        ["sametag;nonnull", "unique;violation", "rollback"]
+    );
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "sayan@skytable.io"]
+    );
+    // setting a row's own value again is fine
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set email = 'sayan@skytable.io' where username = 'sayan'",
+    )
+    .unwrap();
+}
@@ -27,3 +27,4 @@
 mod ddl_model;
 mod ddl_space;
 mod dml;
+mod sysctl;
@@ -0,0 +1,161 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{dcl, quota::UserWriteLimits, system_db::VerifyUser},
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    net::protocol::ClientLocalState,
+    ql::{ast, dcl::SysctlCommand, tests::lex_insecure},
+};
+
+fn exec_sysctl(global: &TestGlobal, query: &str) -> QueryResult<()> {
+    let tokens = lex_insecure(query.as_bytes()).unwrap();
+    let cmd = ast::parse_ast_node_full::<SysctlCommand>(&tokens[1..]).unwrap();
+    dcl::exec(global, &ClientLocalState::new_local_root(), cmd).map(|_| ())
+}
+
+#[test]
+fn token_verify_and_revoke() {
+    let global = TestGlobal::new_with_driver_id("token_verify_and_revoke");
+    let sys_db = global.state().namespace().sys_db();
+    sys_db
+        .create_user(&global, "sayan".into(), "pass123")
+        .unwrap();
+    let token = sys_db.issue_token("sayan").unwrap();
+    assert_eq!(
+        sys_db.verify_user_token("sayan", token.as_bytes()),
+        VerifyUser::Okay
+    );
+    assert!(sys_db.revoke_token("sayan", token.as_bytes()));
+    assert_eq!(
+        sys_db.verify_user_token("sayan", token.as_bytes()),
+        VerifyUser::IncorrectPassword
+    );
+}
+
+#[test]
+fn token_invalid_after_password_change() {
+    let global = TestGlobal::new_with_driver_id("token_invalid_after_password_change");
+    let sys_db = global.state().namespace().sys_db();
+    sys_db
+        .create_user(&global, "sayan".into(), "pass123")
+        .unwrap();
+    let token = sys_db.issue_token("sayan").unwrap();
+    sys_db.alter_user(&global, "sayan", "pass456").unwrap();
+    assert_eq!(
+        sys_db.verify_user_token("sayan", token.as_bytes()),
+        VerifyUser::IncorrectPassword
+    );
+    assert!(!sys_db.revoke_token("sayan", token.as_bytes()));
+    // tokens issued after the change work
+    let token = sys_db.issue_token("sayan").unwrap();
+    assert_eq!(
+        sys_db.verify_user_token("sayan", token.as_bytes()),
+        VerifyUser::Okay
+    );
+}
+
+#[test]
+fn token_invalid_after_user_recreated() {
+    let global = TestGlobal::new_with_driver_id("token_invalid_after_user_recreated");
+    let sys_db = global.state().namespace().sys_db();
+    sys_db
+        .create_user(&global, "sayan".into(), "pass123")
+        .unwrap();
+    let token = sys_db.issue_token("sayan").unwrap();
+    sys_db.drop_user(&global, "sayan").unwrap();
+    assert_eq!(
+        sys_db.verify_user_token("sayan", token.as_bytes()),
+        VerifyUser::NotFound
+    );
+    assert!(sys_db.issue_token("sayan").is_none());
+    // same name and same password, but a different user
+    sys_db
+        .create_user(&global, "sayan".into(), "pass123")
+        .unwrap();
+    assert_eq!(
+        sys_db.verify_user_token("sayan", token.as_bytes()),
+        VerifyUser::IncorrectPassword
+    );
+}
+
+#[test]
+fn alter_user_write_limits() {
+    {
+        let global = TestGlobal::new_with_driver_id("alter_user_write_limits");
+        let sys_db = global.state().namespace().sys_db();
+        sys_db
+            .create_user(&global, "sayan".into(), "pass123")
+            .unwrap();
+        assert_eq!(sys_db.write_limits("sayan"), UserWriteLimits::default());
+        exec_sysctl(
+            &global,
+            "sysctl alter user sayan with { write_ops_limit: 100, write_bytes_limit: 4096 }",
+        )
+        .unwrap();
+        assert_eq!(
+            sys_db.write_limits("sayan"),
+            UserWriteLimits::new(Some(100), Some(4096))
+        );
+        // only change one of them; the password is left alone
+        exec_sysctl(
+            &global,
+            "sysctl alter user sayan with { write_ops_limit: null }",
+        )
+        .unwrap();
+        assert_eq!(
+            sys_db.write_limits("sayan"),
+            UserWriteLimits::new(None, Some(4096))
+        );
+        assert_eq!(sys_db.verify_user("sayan", b"pass123"), VerifyUser::Okay);
+        // bad limits
+        for query in [
+            "sysctl alter user sayan with { write_ops_limit: 'many' }",
+            "sysctl alter user sayan with { write_ops_limit: -1 }",
+            "sysctl alter user sayan with { write_limit: 100 }",
+        ] {
+            assert_eq!(
+                exec_sysctl(&global, query).unwrap_err(),
+                QueryError::QExecDdlInvalidProperties
+            );
+        }
+        assert_eq!(
+            exec_sysctl(
+                &global,
+                "sysctl alter user root with { write_ops_limit: 1 }"
+            )
+            .unwrap_err(),
+            QueryError::SysAuthError
+        );
+    }
+    // the limits are stored with the user
+    let global = TestGlobal::new_with_driver_id("alter_user_write_limits");
+    assert_eq!(
+        global.state().namespace().sys_db().write_limits("sayan"),
+        UserWriteLimits::new(None, Some(4096))
+    );
+}
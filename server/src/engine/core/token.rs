@@ -0,0 +1,197 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    session tokens
+    ---
+    A token is `{expiry}.{id}.{signature}` where the signature is an HMAC (SHA256) over the username,
+    the user's token epoch, the expiry and the token ID. The signing key is generated when the server
+    starts, so all tokens are invalidated on restart. The token epoch changes whenever the user's
+    password is changed or the user is created again, which invalidates all the tokens that were
+    issued to the user before. Revoked token IDs are held in memory until the token would have
+    expired anyway.
+*/
+
+use {
+    crate::util::os,
+    openssl::{hash::MessageDigest, memcmp, pkey::PKey, rand, sign::Signer},
+    parking_lot::RwLock,
+    std::collections::HashMap,
+};
+
+/// How long a token remains valid (in seconds)
+pub const TOKEN_TTL: u64 = 60 * 60;
+const KEY_LEN: usize = 32;
+const ID_LEN: usize = 16;
+
+pub(in crate::engine::core) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(in crate::engine::core) fn unhex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 {
+        return None;
+    }
+    let mut ret = [0u8; N];
+    for (i, byte) in ret.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(ret)
+}
+
+/// A decoded (but not yet verified) token
+struct RawToken<'a> {
+    expiry: u64,
+    id: [u8; ID_LEN],
+    signed: &'a str,
+    signature: [u8; 32],
+}
+
+impl<'a> RawToken<'a> {
+    fn decode(token: &'a [u8]) -> Option<Self> {
+        let token = core::str::from_utf8(token).ok()?;
+        let (signed, signature) = token.rsplit_once('.')?;
+        let (expiry, id) = signed.split_once('.')?;
+        Some(Self {
+            expiry: expiry.parse().ok()?,
+            id: unhex(id)?,
+            signed,
+            signature: unhex(signature)?,
+        })
+    }
+}
+
+pub struct TokenStore {
+    key: [u8; KEY_LEN],
+    revoked: RwLock<HashMap<[u8; ID_LEN], u64>>,
+}
+
+impl core::fmt::Debug for TokenStore {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // don't leak the key
+        f.debug_struct("TokenStore").finish_non_exhaustive()
+    }
+}
+
+impl TokenStore {
+    pub fn new() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        rand::rand_bytes(&mut key).unwrap();
+        Self {
+            key,
+            revoked: RwLock::new(HashMap::new()),
+        }
+    }
+    fn sign(&self, username: &str, epoch: u64, signed: &str) -> Vec<u8> {
+        let key = PKey::hmac(&self.key).unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+        signer.update(username.as_bytes()).unwrap();
+        signer.update(b"\0").unwrap();
+        signer.update(&epoch.to_le_bytes()).unwrap();
+        signer.update(signed.as_bytes()).unwrap();
+        signer.sign_to_vec().unwrap()
+    }
+    /// Issue a new token for the given user (with the user's current token epoch)
+    pub fn issue(&self, username: &str, epoch: u64) -> String {
+        let mut id = [0u8; ID_LEN];
+        rand::rand_bytes(&mut id).unwrap();
+        let signed = format!(
+            "{}.{}",
+            (os::get_epoch_time() / 1_000_000_000) as u64 + TOKEN_TTL,
+            hex(&id)
+        );
+        let signature = self.sign(username, epoch, &signed);
+        format!("{signed}.{}", hex(&signature))
+    }
+    /// Decode and verify the token, returning it only if it is valid for this user
+    fn verify_raw<'a>(&self, username: &str, epoch: u64, token: &'a [u8]) -> Option<RawToken<'a>> {
+        let token = RawToken::decode(token)?;
+        let signature = self.sign(username, epoch, token.signed);
+        let okay = memcmp::eq(&signature, &token.signature)
+            & (token.expiry > (os::get_epoch_time() / 1_000_000_000) as u64);
+        okay.then_some(token)
+    }
+    /// Returns true if the token was issued to this user in this token epoch, hasn't expired and
+    /// hasn't been revoked
+    pub fn verify(&self, username: &str, epoch: u64, token: &[u8]) -> bool {
+        match self.verify_raw(username, epoch, token) {
+            Some(token) => !self.revoked.read().contains_key(&token.id),
+            None => false,
+        }
+    }
+    /// Revoke a token issued to this user. Returns false if the token is invalid
+    pub fn revoke(&self, username: &str, epoch: u64, token: &[u8]) -> bool {
+        let Some(token) = self.verify_raw(username, epoch, token) else {
+            return false;
+        };
+        let mut revoked = self.revoked.write();
+        // forget about tokens that have expired anyway
+        let now = (os::get_epoch_time() / 1_000_000_000) as u64;
+        revoked.retain(|_, expiry| *expiry > now);
+        revoked.insert(token.id, token.expiry);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenStore;
+
+    #[test]
+    fn issue_verify() {
+        let store = TokenStore::new();
+        let token = store.issue("sayan", 1);
+        assert!(store.verify("sayan", 1, token.as_bytes()));
+        assert!(!store.verify("root", 1, token.as_bytes()));
+        assert!(!TokenStore::new().verify("sayan", 1, token.as_bytes()));
+    }
+    #[test]
+    fn new_epoch() {
+        let store = TokenStore::new();
+        let token = store.issue("sayan", 1);
+        assert!(!store.verify("sayan", 2, token.as_bytes()));
+        assert!(!store.revoke("sayan", 2, token.as_bytes()));
+    }
+    #[test]
+    fn tampered() {
+        let store = TokenStore::new();
+        let token = store.issue("sayan", 1);
+        let (expiry, rest) = token.split_once('.').unwrap();
+        let forged = format!("{}.{rest}", expiry.parse::<u64>().unwrap() + 1);
+        assert!(!store.verify("sayan", 1, forged.as_bytes()));
+        assert!(!store.verify("sayan", 1, b"garbage"));
+    }
+    #[test]
+    fn revoke() {
+        let store = TokenStore::new();
+        let token = store.issue("sayan", 1);
+        let other = store.issue("sayan", 1);
+        assert!(!store.revoke("root", 1, token.as_bytes()));
+        assert!(store.revoke("sayan", 1, token.as_bytes()));
+        assert!(!store.verify("sayan", 1, token.as_bytes()));
+        assert!(store.verify("sayan", 1, other.as_bytes()));
+    }
+}
@@ -0,0 +1,442 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+/*
+    statement tracing
+    ---
+    if an OTLP endpoint is configured (`system.otlp_endpoint`), every statement is exported as a span with child spans
+    for its phases: `parse` (lexing the query), `plan` (compiling the filter of a scan), `execute`, `journal write`
+    (committing a DDL event to the GNS journal) and `encode` (writing the response). a client can tie its statements
+    into a distributed trace with `set traceparent = '<W3C traceparent>'`; otherwise every statement starts a new trace.
+
+    finished statements are batched and sent to `<endpoint>/v1/traces` (OTLP/HTTP with the JSON encoding) by a
+    background thread. if the collector is slow or down, statements are dropped rather than holding up queries
+*/
+
+use {
+    super::token::{hex, unhex},
+    openssl::rand,
+    parking_lot::{const_mutex, Mutex},
+    std::{
+        cell::RefCell,
+        fmt::Write as _,
+        io::{Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+        sync::{
+            mpsc::{self, RecvTimeoutError, SyncSender},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// How many finished statements can wait for the exporter before we start dropping them
+const EXPORT_QUEUE_SIZE: usize = 4096;
+/// The exporter sends a batch once it has these many statements...
+const EXPORT_BATCH_SIZE: usize = 256;
+/// ...or once the batch is this old
+const EXPORT_INTERVAL: Duration = Duration::from_secs(1);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+const SPAN_KIND_INTERNAL: u8 = 1;
+const SPAN_KIND_SERVER: u8 = 2;
+const STATUS_CODE_ERROR: u8 = 2;
+
+static EXPORTER: Mutex<Option<SyncSender<StatementTrace>>> = const_mutex(None);
+
+thread_local! {
+    /// The trace of the statement that is running on this thread
+    static CURRENT: RefCell<Option<TraceHandle>> = const { RefCell::new(None) };
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn new_id<const N: usize>() -> [u8; N] {
+    let mut id = [0u8; N];
+    rand::rand_bytes(&mut id).unwrap();
+    id
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A W3C trace context (`00-<trace id>-<parent span id>-<flags>`)
+pub struct TraceParent {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+}
+
+impl TraceParent {
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.split('-');
+        let (version, trace_id, span_id, flags) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if (version != "00") | parts.next().is_some() | (flags.len() != 2) {
+            return None;
+        }
+        let (trace_id, span_id) = (unhex(trace_id)?, unhex(span_id)?);
+        // all zero IDs are invalid
+        if (trace_id == [0; 16]) | (span_id == [0; 8]) {
+            return None;
+        }
+        Some(Self { trace_id, span_id })
+    }
+}
+
+#[derive(Debug)]
+struct Span {
+    name: &'static str,
+    id: [u8; 8],
+    start: u64,
+    end: u64,
+}
+
+#[derive(Debug)]
+struct StatementTrace {
+    trace_id: [u8; 16],
+    parent: Option<[u8; 8]>,
+    statement: Span,
+    operation: &'static str,
+    error: Option<u16>,
+    phases: Vec<Span>,
+}
+
+#[derive(Debug, Clone)]
+/// The trace of a running statement
+pub struct TraceHandle(Arc<Mutex<StatementTrace>>);
+
+impl TraceHandle {
+    /// Start tracing a statement (if tracing is enabled)
+    pub fn start(parent: Option<TraceParent>) -> Option<Self> {
+        if EXPORTER.lock().is_none() {
+            return None;
+        }
+        let trace = StatementTrace {
+            trace_id: parent.map_or_else(new_id, |p| p.trace_id),
+            parent: parent.map(|p| p.span_id),
+            statement: Span {
+                name: "statement",
+                id: new_id(),
+                start: now(),
+                end: 0,
+            },
+            operation: "",
+            error: None,
+            phases: vec![],
+        };
+        Some(Self(Arc::new(Mutex::new(trace))))
+    }
+    /// Run `f` as a phase of this statement
+    pub fn span<T>(&self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        let _phase = self.phase(name);
+        f()
+    }
+    /// Start a phase of this statement. It ends when the returned guard is dropped
+    pub fn phase(&self, name: &'static str) -> Phase {
+        Phase {
+            trace: self.clone(),
+            name,
+            start: now(),
+        }
+    }
+    pub fn set_operation(&self, operation: &'static str) {
+        self.0.lock().operation = operation;
+    }
+    pub fn set_error(&self, code: u16) {
+        self.0.lock().error = Some(code);
+    }
+    /// Finish the statement and queue it for export
+    pub fn finish(self) {
+        let mut trace = match Arc::try_unwrap(self.0) {
+            Ok(trace) => trace.into_inner(),
+            // a phase is somehow still holding on to it, so there's nothing sensible to export
+            Err(_) => return,
+        };
+        trace.statement.end = now();
+        if let Some(exporter) = EXPORTER.lock().as_ref() {
+            // if the queue is full, drop it
+            let _ = exporter.try_send(trace);
+        }
+    }
+}
+
+impl PartialEq for TraceHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A running phase of a statement (see [`TraceHandle::phase`])
+pub struct Phase {
+    trace: TraceHandle,
+    name: &'static str,
+    start: u64,
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let span = Span {
+            name: self.name,
+            id: new_id(),
+            start: self.start,
+            end: now(),
+        };
+        self.trace.0.lock().phases.push(span);
+    }
+}
+
+/// Run `f` on this thread with `trace` as the current statement's trace
+pub fn scope<T>(trace: Option<TraceHandle>, f: impl FnOnce() -> T) -> T {
+    let prev = CURRENT.with(|c| c.replace(trace));
+    let ret = f();
+    CURRENT.with(|c| *c.borrow_mut() = prev);
+    ret
+}
+
+/// Run `f` as a phase of the statement running on this thread (if it's being traced)
+pub fn span<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    match CURRENT.with(|c| c.borrow().clone()) {
+        Some(trace) => trace.span(name, f),
+        None => f(),
+    }
+}
+
+/*
+    exporter
+*/
+
+/// Start exporting traces to the OTLP/HTTP collector at `endpoint` (`http://<host>:<port>`)
+pub fn init(endpoint: &str) -> std::io::Result<()> {
+    let addr = endpoint
+        .strip_prefix("http://")
+        .map(|addr| addr.trim_end_matches('/'))
+        .filter(|addr| !addr.is_empty() && !addr.contains('/'))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "bad OTLP endpoint"))?
+        .to_owned();
+    let (tx, rx) = mpsc::sync_channel(EXPORT_QUEUE_SIZE);
+    thread::Builder::new()
+        .name("otlp-exporter".into())
+        .spawn(move || {
+            let mut batch = vec![];
+            let mut failing = false;
+            let mut oldest = Instant::now();
+            loop {
+                let closed = match rx.recv_timeout(EXPORT_INTERVAL) {
+                    Ok(trace) => {
+                        if batch.is_empty() {
+                            oldest = Instant::now();
+                        }
+                        batch.push(trace);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+                let due = (batch.len() >= EXPORT_BATCH_SIZE) | (oldest.elapsed() >= EXPORT_INTERVAL);
+                if !batch.is_empty() && (due | closed) {
+                    let body = encode_batch(&batch);
+                    batch.clear();
+                    match post(&addr, &body) {
+                        Ok(()) if failing => {
+                            info!("otlp: exporting traces to {addr} again");
+                            failing = false;
+                        }
+                        Ok(()) => {}
+                        Err(e) if !failing => {
+                            warn!("otlp: failed to export traces to {addr}: {e}. dropping traces until it recovers");
+                            failing = true;
+                        }
+                        Err(_) => {}
+                    }
+                }
+                if closed {
+                    break;
+                }
+            }
+        })?;
+    *EXPORTER.lock() = Some(tx);
+    Ok(())
+}
+
+/// Stop exporting traces (the exporter sends whatever is pending and exits)
+pub fn shutdown() {
+    let _ = EXPORTER.lock().take();
+}
+
+fn post(addr: &str, body: &str) -> std::io::Result<()> {
+    let sock = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address"))?;
+    let mut con = TcpStream::connect_timeout(&sock, EXPORT_TIMEOUT)?;
+    con.set_read_timeout(Some(EXPORT_TIMEOUT))?;
+    con.set_write_timeout(Some(EXPORT_TIMEOUT))?;
+    write!(
+        con,
+        "POST /v1/traces HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    // HTTP/1.1 200 OK
+    let mut status = [0u8; 12];
+    con.read_exact(&mut status)?;
+    match &status[9..] {
+        b"200" | b"202" | b"204" => Ok(()),
+        status => Err(std::io::Error::other(format!(
+            "collector returned {}",
+            String::from_utf8_lossy(status)
+        ))),
+    }
+}
+
+fn encode_span(
+    buf: &mut String,
+    trace_id: &[u8; 16],
+    span: &Span,
+    parent: Option<&[u8; 8]>,
+    kind: u8,
+    attrs: &[(&str, &str)],
+    error: bool,
+) {
+    let _ = write!(
+        buf,
+        r#"{{"traceId":"{}","spanId":"{}","#,
+        hex(trace_id),
+        hex(&span.id)
+    );
+    if let Some(parent) = parent {
+        let _ = write!(buf, r#""parentSpanId":"{}","#, hex(parent));
+    }
+    let _ = write!(
+        buf,
+        r#""name":"{}","kind":{kind},"startTimeUnixNano":"{}","endTimeUnixNano":"{}","attributes":["#,
+        span.name, span.start, span.end
+    );
+    for (i, (key, value)) in attrs.iter().enumerate() {
+        if i != 0 {
+            buf.push(',');
+        }
+        // keys and values are all our own ASCII identifiers, so there's nothing to escape
+        let _ = write!(
+            buf,
+            r#"{{"key":"{key}","value":{{"stringValue":"{value}"}}}}"#
+        );
+    }
+    buf.push(']');
+    if error {
+        let _ = write!(buf, r#","status":{{"code":{STATUS_CODE_ERROR}}}"#);
+    }
+    buf.push('}');
+}
+
+fn encode_batch(batch: &[StatementTrace]) -> String {
+    let mut buf = String::from(
+        r#"{"resourceSpans":[{"resource":{"attributes":[{"key":"service.name","value":{"stringValue":"skytable"}}]},"scopeSpans":[{"scope":{"name":"skyd"},"spans":["#,
+    );
+    let mut first = true;
+    for trace in batch {
+        let error = trace.error.map(|e| e.to_string());
+        let mut attrs = vec![("db.system", "skytable"), ("db.operation", trace.operation)];
+        if let Some(error) = error.as_deref() {
+            attrs.push(("skytable.error_code", error));
+        }
+        if !first {
+            buf.push(',');
+        }
+        first = false;
+        encode_span(
+            &mut buf,
+            &trace.trace_id,
+            &trace.statement,
+            trace.parent.as_ref(),
+            SPAN_KIND_SERVER,
+            &attrs,
+            error.is_some(),
+        );
+        for phase in trace.phases.iter() {
+            buf.push(',');
+            encode_span(
+                &mut buf,
+                &trace.trace_id,
+                phase,
+                Some(&trace.statement.id),
+                SPAN_KIND_INTERNAL,
+                &[],
+                false,
+            );
+        }
+    }
+    buf.push_str("]}]}]}");
+    buf
+}
+
+#[test]
+fn traceparent() {
+    let parent =
+        TraceParent::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+    assert_eq!(hex(&parent.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(hex(&parent.span_id), "00f067aa0ba902b7");
+    for bad in [
+        "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6-00f067aa0ba902b7-01",
+    ] {
+        assert!(TraceParent::parse(bad).is_none());
+    }
+}
+
+#[test]
+fn encode_statement() {
+    let trace = StatementTrace {
+        trace_id: [0xAB; 16],
+        parent: Some([0x01; 8]),
+        statement: Span {
+            name: "statement",
+            id: [0x02; 8],
+            start: 100,
+            end: 200,
+        },
+        operation: "select",
+        error: Some(111),
+        phases: vec![Span {
+            name: "parse",
+            id: [0x03; 8],
+            start: 110,
+            end: 120,
+        }],
+    };
+    let body = encode_batch(&[trace]);
+    assert!(body.contains(r#""traceId":"abababababababababababababababab","spanId":"0202020202020202","parentSpanId":"0101010101010101","name":"statement","kind":2,"startTimeUnixNano":"100","endTimeUnixNano":"200""#));
+    assert!(body.contains(
+        r#"{"key":"skytable.error_code","value":{"stringValue":"111"}}],"status":{"code":2}}"#
+    ));
+    assert!(body.contains(
+        r#""spanId":"0303030303030303","parentSpanId":"0202020202020202","name":"parse","kind":1"#
+    ));
+    assert!(body.ends_with("]}]}]}"));
+}
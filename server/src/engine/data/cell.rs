@@ -45,6 +45,7 @@ use {
         slice, str,
     },
     parking_lot::RwLock,
+    std::collections::BTreeMap,
 };
 
 pub struct Datacell {
@@ -269,6 +270,25 @@ impl Datacell {
             Some(ManuallyDrop::into_inner(rwl).into_inner())
         }
     }
+    // map
+    pub fn new_map(m: BTreeMap<Box<str>, Self>) -> Self {
+        unsafe {
+            // UNSAFE(@ohsayan): Correct because we are initializing Self with the correct tag
+            Self::new(FullTag::MAP, DataRaw::map(RwLock::new(m)))
+        }
+    }
+    pub unsafe fn read_map(&self) -> &RwLock<BTreeMap<Box<str>, Self>> {
+        &self.data.map
+    }
+    pub fn try_map(&self) -> Option<&RwLock<BTreeMap<Box<str>, Self>>> {
+        self.checked_tag(TagClass::Map, || unsafe {
+            // UNSAFE(@ohsayan): Correct because we just verified the tag
+            self.read_map()
+        })
+    }
+    pub fn map(&self) -> &RwLock<BTreeMap<Box<str>, Self>> {
+        self.try_map().unwrap()
+    }
     pub unsafe fn new_qw(qw: u64, tag: FullTag) -> Datacell {
         Self::new(
             tag,
@@ -294,6 +314,7 @@ direct_from! {
         &'static str as new_str,
         Vec<Self> as new_list,
         Box<[Self]> as new_list,
+        BTreeMap<Box<str>, Self> as new_map,
     }
 }
 
@@ -408,6 +429,7 @@ impl fmt::Debug for Datacell {
             Bin => self.bin(),
             Str => self.str(),
             List => self.list(),
+            Map => self.map(),
         );
         f.finish()
     }
@@ -432,6 +454,7 @@ impl PartialEq for Datacell {
                 let l2: &[Self] = l2_l.as_ref();
                 l1 == l2
             }
+            (TagClass::Map, TagClass::Map) => *self.map().read() == *other.map().read(),
             _ => false,
         }
     }
@@ -443,6 +466,7 @@ union! {
     union DataRaw {
         !word: NativeQword,
         !rwl: RwLock<Vec<Datacell>>,
+        !map: RwLock<BTreeMap<Box<str>, Datacell>>,
     }
 }
 
@@ -457,6 +481,11 @@ impl DataRaw {
             rwl: ManuallyDrop::new(rwl),
         }
     }
+    fn map(map: RwLock<BTreeMap<Box<str>, Datacell>>) -> Self {
+        Self {
+            map: ManuallyDrop::new(map),
+        }
+    }
 }
 
 impl Drop for Datacell {
@@ -471,12 +500,15 @@ impl Drop for Datacell {
                 // UNSAFE(@ohsayan): we have checked that the cell is initialized (uninit will not satisfy this class), and we have checked its class
                 ManuallyDrop::drop(&mut self.data.rwl)
             },
+            TagClass::Map => unsafe {
+                // UNSAFE(@ohsayan): we have checked that the cell is initialized (uninit will not satisfy this class), and we have checked its class
+                ManuallyDrop::drop(&mut self.data.map)
+            },
             _ => {}
         }
     }
 }
 
-#[cfg(test)]
 impl Clone for Datacell {
     fn clone(&self) -> Self {
         let data = match self.kind() {
@@ -493,6 +525,10 @@ impl Clone for Datacell {
                 let data = self.read_list().read().iter().cloned().collect();
                 DataRaw::rwl(RwLock::new(data))
             },
+            TagClass::Map => unsafe {
+                // UNSAFE(@ohsayan): we have checked that the cell is initialized (uninit will not satisfy this class), and we have checked its class
+                DataRaw::map(RwLock::new(self.read_map().read().clone()))
+            },
             _ => unsafe {
                 // UNSAFE(@ohsayan): we have checked that the cell is a stack class
                 DataRaw::word(mem::transmute_copy(&self.data.word))
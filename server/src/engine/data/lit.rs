@@ -299,7 +299,7 @@ impl<'a> fmt::Debug for Lit<'a> {
                 TagClass::Float => d!(self.float()),
                 TagClass::Bin => d!(self.bin()),
                 TagClass::Str => d!(self.str()),
-                TagClass::List => panic!("found 2D in 1D"),
+                TagClass::List | TagClass::Map => panic!("found 2D in 1D"),
             }
         }
         field.finish()
@@ -353,7 +353,7 @@ impl<'a> ToString for Lit<'a> {
                 TagClass::Float => self.float().to_string(),
                 TagClass::Bin => format!("{:?}", self.bin()),
                 TagClass::Str => format!("{:?}", self.str()),
-                TagClass::List => panic!("found 2D in 1D"),
+                TagClass::List | TagClass::Map => panic!("found 2D in 1D"),
             }
         }
     }
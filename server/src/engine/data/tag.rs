@@ -54,6 +54,7 @@ pub enum TagClass {
     Bin = 4,
     Str = 5,
     List = 6,
+    Map = 7,
 }
 
 strid! {
@@ -85,6 +86,7 @@ strid! {
         Binary = 11,
         String = 12,
         List = 13,
+        Map = 14,
     }
 }
 
@@ -108,6 +110,7 @@ impl TagSelector {
             TagUnique::Bin,         // bin
             TagUnique::Str,         // str
             TagUnique::Illegal,     // list
+            TagUnique::Illegal,     // map
         ][self.value_word()]
     }
     pub const fn tag_class(&self) -> TagClass {
@@ -126,6 +129,7 @@ impl TagSelector {
             TagClass::Bin,         // bin
             TagClass::Str,         // str
             TagClass::List,        // recursive list
+            TagClass::Map,         // map (with string keys)
         ][self.value_word()]
     }
 }
@@ -165,6 +169,7 @@ pub trait DataTag {
     const BIN: Self;
     const STR: Self;
     const LIST: Self;
+    const MAP: Self;
     fn tag_class(&self) -> TagClass;
     fn tag_selector(&self) -> TagSelector;
     fn tag_unique(&self) -> TagUnique;
@@ -213,6 +218,7 @@ impl DataTag for FullTag {
     const BIN: Self = fulltag!(Bin, Binary, Bin);
     const STR: Self = fulltag!(Str, String, Str);
     const LIST: Self = fulltag!(List, List);
+    const MAP: Self = fulltag!(Map, Map);
     fn tag_class(&self) -> TagClass {
         self.class
     }
@@ -0,0 +1,566 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Embedded mode
+//!
+//! Run the engine in-process, without any network endpoints. [`Database::open`] loads (or creates) a data directory
+//! and [`Database::execute`] runs BlueQL statements against it as the root user, returning decoded results.
+//!
+//! The engine keeps its state in process-wide globals, so only one database can be open in a process at a time. The
+//! storage engine resolves its paths against the data directory instead of the current directory, which is left
+//! alone. [`Database::open_in_memory`] runs the storage engine on an in-memory file system instead, which never
+//! touches the disk. Each thread has its own view of the file system, so the database only switches the threads that
+//! run its storage engine over to its own
+
+use {
+    super::{
+        config::{self, ConfigReturn},
+        core::exec,
+        error::{ErrorKind, QueryError, QueryResult, RuntimeResult},
+        fractal::{self, FractalHandle, Global},
+        net::protocol::{ClientLocalState, Response, ResponseType, SQuery},
+        storage::safe_interfaces::{FSContext, FSScope, FileSystem},
+    },
+    crate::util::os::FileLock,
+    std::{
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicBool, Ordering},
+    },
+    tokio::{runtime::Runtime, sync::broadcast},
+};
+
+/// set while a database is open in this process
+static OPEN: AtomicBool = AtomicBool::new(false);
+#[cfg(test)]
+/// only one database can be open in a process, so the tests that open one take turns
+pub(crate) static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/*
+    values
+*/
+
+#[derive(Debug, PartialEq, Clone)]
+/// A value that is passed as a query parameter or returned in a result
+pub enum Value {
+    Null,
+    Bool(bool),
+    UInt(u64),
+    SInt(i64),
+    Float(f64),
+    Binary(Vec<u8>),
+    String(String),
+    List(Vec<Value>),
+    /// only returned (by `sysctl` and the like); can't be used as a parameter
+    Dict(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Encode this value as a query parameter
+    fn encode_param(&self, buf: &mut Vec<u8>) -> QueryResult<()> {
+        match self {
+            Self::Null => buf.push(0),
+            Self::Bool(b) => buf.extend([1, *b as u8]),
+            Self::UInt(u) => buf.extend(format!("\x02{u}\n").as_bytes()),
+            Self::SInt(s) => buf.extend(format!("\x03{s}\n").as_bytes()),
+            Self::Float(f) => buf.extend(format!("\x04{f}\n").as_bytes()),
+            Self::Binary(b) => {
+                buf.extend(format!("\x05{}\n", b.len()).as_bytes());
+                buf.extend(b);
+            }
+            Self::String(s) => {
+                buf.extend(format!("\x06{}\n", s.len()).as_bytes());
+                buf.extend(s.as_bytes());
+            }
+            Self::List(l) => {
+                buf.extend(format!("\x07{}\n", l.len()).as_bytes());
+                for item in l {
+                    if let Self::List(_) = item {
+                        // lists can't be nested in parameters
+                        return Err(QueryError::QLInvalidSyntax);
+                    }
+                    item.encode_param(buf)?;
+                }
+            }
+            Self::Dict(_) => return Err(QueryError::QLInvalidSyntax),
+        }
+        Ok(())
+    }
+}
+
+/// The result of a statement
+#[derive(Debug, PartialEq, Clone)]
+pub enum Output {
+    /// the statement doesn't return anything
+    Empty,
+    /// a single value
+    Value(Value),
+    /// a single row
+    Row(Vec<Value>),
+    /// any number of rows
+    Rows(Vec<Vec<Value>>),
+}
+
+impl Output {
+    fn decode(resp: Response) -> QueryResult<Self> {
+        let (ty, size, data) = match resp {
+            Response::Empty => return Ok(Self::Empty),
+            Response::Null => return Ok(Self::Value(Value::Null)),
+            Response::Bool(b) => return Ok(Self::Value(Value::Bool(b))),
+            Response::Cell(cell) => return Ok(Self::Value(Decoder::new(&cell).value()?)),
+            Response::Serialized { ty, size, data }
+            | Response::SerializedCharged { ty, size, data, .. } => (ty, size, data),
+            // the columns are already known to the caller
+            Response::WithMeta { resp, .. } => return Self::decode(*resp),
+            Response::ErrorAt { error, .. } => return Err(error),
+        };
+        let mut decoder = Decoder::new(&data);
+        let ret = match ty {
+            ResponseType::Row => Self::Row(decoder.values(size)?),
+            ResponseType::MultiRow => {
+                // [column count]\n[rows]
+                let columns = decoder.uint()? as usize;
+                let mut rows = Vec::with_capacity(size);
+                for _ in 0..size {
+                    rows.push(decoder.values(columns)?);
+                }
+                Self::Rows(rows)
+            }
+            ResponseType::PackedRows => {
+                // [column count]\n then [encoding][values] for every column
+                let columns = decoder.uint()? as usize;
+                let mut rows = vec![Vec::with_capacity(columns); size];
+                for _ in 0..columns {
+                    let column = decoder.column(size)?;
+                    rows.iter_mut().zip(column).for_each(|(row, v)| row.push(v));
+                }
+                Self::Rows(rows)
+            }
+            ResponseType::List => Self::Value(Value::List(decoder.values(size)?)),
+            ResponseType::Dict => Self::Value(decoder.dict(size)?),
+            // the size is the value
+            ResponseType::UInt64 => Self::Value(Value::UInt(size as u64)),
+            ResponseType::String => Self::Value(Value::String(
+                String::from_utf8(data).map_err(|_| QueryError::SysServerError)?,
+            )),
+            _ => return Err(QueryError::SysServerError),
+        };
+        Ok(ret)
+    }
+}
+
+/// Decodes the cells of a serialized response (see [`ResponseType`] for the type codes)
+struct Decoder<'a> {
+    data: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, cursor: 0 }
+    }
+    fn byte(&mut self) -> QueryResult<u8> {
+        let b = *self
+            .data
+            .get(self.cursor)
+            .ok_or(QueryError::SysServerError)?;
+        self.cursor += 1;
+        Ok(b)
+    }
+    fn block(&mut self, len: usize) -> QueryResult<&'a [u8]> {
+        let block = self
+            .data
+            .get(self.cursor..self.cursor + len)
+            .ok_or(QueryError::SysServerError)?;
+        self.cursor += len;
+        Ok(block)
+    }
+    /// Read up to (and skip) the next LF
+    fn line(&mut self) -> QueryResult<&'a str> {
+        let len = self.data[self.cursor..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .ok_or(QueryError::SysServerError)?;
+        let line = self.block(len)?;
+        self.cursor += 1;
+        core::str::from_utf8(line).map_err(|_| QueryError::SysServerError)
+    }
+    fn parse<T: core::str::FromStr>(&mut self) -> QueryResult<T> {
+        self.line()?.parse().map_err(|_| QueryError::SysServerError)
+    }
+    fn uint(&mut self) -> QueryResult<u64> {
+        self.parse()
+    }
+    fn string(&mut self) -> QueryResult<String> {
+        let len = self.uint()? as usize;
+        String::from_utf8(self.block(len)?.to_vec()).map_err(|_| QueryError::SysServerError)
+    }
+    fn values(&mut self, count: usize) -> QueryResult<Vec<Value>> {
+        (0..count).map(|_| self.value()).collect()
+    }
+    /// Read a column of a packed result (see `core::dml::sel` for the encodings)
+    fn column(&mut self, count: usize) -> QueryResult<Vec<Value>> {
+        let encoding = self.byte()?;
+        if encoding == 0 {
+            return self.values(count);
+        }
+        let mut ret = Vec::with_capacity(count);
+        for _ in 0..count {
+            let value = u64::from_le_bytes(self.block(sizeof!(u64))?.try_into().unwrap());
+            ret.push(match encoding {
+                1 => Value::UInt(value),
+                2 => Value::Float(f64::from_bits(value)),
+                _ => return Err(QueryError::SysServerError),
+            });
+        }
+        Ok(ret)
+    }
+    fn dict(&mut self, count: usize) -> QueryResult<Value> {
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let key = self.string()?;
+            entries.push((key, self.value()?));
+        }
+        Ok(Value::Dict(entries))
+    }
+    fn value(&mut self) -> QueryResult<Value> {
+        Ok(match self.byte()? {
+            0x00 => Value::Null,
+            0x01 => Value::Bool(self.byte()? == 1),
+            0x02..=0x05 => Value::UInt(self.uint()?),
+            0x06..=0x09 => Value::SInt(self.parse()?),
+            0x0A | 0x0B => Value::Float(self.parse()?),
+            0x0C => {
+                let len = self.uint()? as usize;
+                Value::Binary(self.block(len)?.to_vec())
+            }
+            0x0D => Value::String(self.string()?),
+            0x0E => {
+                let len = self.uint()? as usize;
+                Value::List(self.values(len)?)
+            }
+            0x0F => {
+                let len = self.uint()? as usize;
+                self.dict(len)?
+            }
+            _ => return Err(QueryError::SysServerError),
+        })
+    }
+}
+
+/*
+    database
+*/
+
+/// An embedded database
+pub struct Database {
+    runtime: Runtime,
+    global: Global,
+    cstate: ClientLocalState,
+    /// dropping this stops the fractal engine
+    signal: Option<broadcast::Sender<()>>,
+    fractal: Option<FractalHandle>,
+    /// the lock on the PID file (and its path), if the database is on disk
+    pid_file: Option<(FileLock, PathBuf)>,
+    /// the file system that the storage engine of this database runs on
+    fs: FSScope,
+}
+
+impl Database {
+    /// The directory on the virtual file system that in-memory databases keep their files in, so that they never get in
+    /// the way of anything else on it (see [`super::fault`])
+    pub const IN_MEMORY_ROOT: &'static str = "embedded";
+    /// Open the database in the directory at `path` (which is created if it doesn't exist). `options` are the
+    /// server's CLI options (like `--auth-root-password`), except that no endpoints are ever started
+    pub fn open(path: &str, options: &[&str]) -> RuntimeResult<Self> {
+        Self::open_with(Some(path), options)
+    }
+    /// Open a database that is kept in memory (on a virtual file system) instead of on disk. The data is around until
+    /// the process exits, so an in-memory database that is opened again in the same process has the data of the last
+    /// one
+    pub fn open_in_memory(options: &[&str]) -> RuntimeResult<Self> {
+        Self::open_with(None, options)
+    }
+    fn open_with(path: Option<&str>, options: &[&str]) -> RuntimeResult<Self> {
+        if OPEN.swap(true, Ordering::AcqRel) {
+            return Err(
+                ErrorKind::Other("a database is already open in this process".into()).into(),
+            );
+        }
+        let ret = Self::open_inner(path, options);
+        if ret.is_err() {
+            OPEN.store(false, Ordering::Release);
+        }
+        ret
+    }
+    fn open_inner(path: Option<&str>, options: &[&str]) -> RuntimeResult<Self> {
+        let mut args = vec!["skyd".to_owned()];
+        args.extend(options.iter().map(|opt| opt.to_string()));
+        let config = match config::check_configuration_from(args)? {
+            ConfigReturn::Config(cfg) => *cfg,
+            ConfigReturn::HelpMessage(msg) => return Err(ErrorKind::Other(msg).into()),
+        };
+        // the file system has to be set up before the storage engine opens any file
+        let (fs, pid_file) = match path {
+            Some(path) => {
+                std::fs::create_dir_all(path)?;
+                let pid_path = Path::new(path).join(crate::SKY_PID_FILE);
+                let pid_file = FileLock::new(&pid_path)?;
+                (
+                    FSScope::new(FSContext::Local, Some(path)),
+                    Some((pid_file, pid_path)),
+                )
+            }
+            None => {
+                let _fs = FileSystem::enter(FSScope::new(FSContext::Virtual, None));
+                FileSystem::create_dir_all(Self::IN_MEMORY_ROOT)?;
+                (
+                    FSScope::new(FSContext::Virtual, Some(Self::IN_MEMORY_ROOT)),
+                    None,
+                )
+            }
+        };
+        // the storage engine runs on this thread (for as long as we're in here) and on the runtime's threads
+        let _fs = FileSystem::enter(fs.clone());
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("embedded")
+            .on_thread_start({
+                let fs = fs.clone();
+                move || {
+                    FileSystem::set_scope(fs.clone());
+                }
+            })
+            .enable_all()
+            .build()?;
+        let (config, fractal::GlobalStateStart { global, boot }) = {
+            let _rt = runtime.enter();
+            super::load_all(config)?
+        };
+        let (signal, _) = broadcast::channel(1);
+        let fractal = {
+            let _rt = runtime.enter();
+            boot.boot(&signal, config.system.reliability_system_window)
+        };
+        Ok(Self {
+            runtime,
+            global,
+            cstate: ClientLocalState::new_local_root(),
+            signal: Some(signal),
+            fractal: Some(fractal),
+            pid_file,
+            fs,
+        })
+    }
+    /// Run a statement. Every `?` in the query is replaced with the next parameter
+    pub fn execute(&mut self, query: &str, params: &[Value]) -> QueryResult<Output> {
+        #[cfg(any(test, feature = "fault-injection"))]
+        if super::fault::take_query_panic() {
+            panic!("injected fault: query panicked");
+        }
+        let mut payload = query.as_bytes().to_vec();
+        for param in params {
+            param.encode_param(&mut payload)?;
+        }
+        let Self {
+            runtime,
+            global,
+            cstate,
+            fs,
+            ..
+        } = self;
+        let _fs = FileSystem::enter(fs.clone());
+        let resp = runtime.block_on(exec::dispatch_to_executor(
+            global,
+            cstate,
+            SQuery::new(&payload, query.len()),
+        ))?;
+        Output::decode(resp)
+    }
+    /// Flush all data and close the database
+    pub fn close(mut self) {
+        self.shutdown()
+    }
+    fn shutdown(&mut self) {
+        let Some(fractal) = self.fractal.take() else {
+            return;
+        };
+        let _fs = FileSystem::enter(self.fs.clone());
+        drop(self.signal.take());
+        let (hp, lp) = self
+            .runtime
+            .block_on(async { tokio::join!(fractal.hp_handle, fractal.lp_handle) });
+        if let Err(e) = hp.and(lp) {
+            error!("error while terminating fractal engine: {e}");
+        }
+        super::finish(self.global.clone());
+        if let Some((_, pid_path)) = self.pid_file.take() {
+            if let Err(e) = std::fs::remove_file(pid_path) {
+                error!("failed to remove PID file: {e}");
+            }
+        }
+        OPEN.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        self.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_params() {
+        let mut buf = vec![];
+        for param in [
+            Value::Null,
+            Value::Bool(true),
+            Value::UInt(42),
+            Value::SInt(-1),
+            Value::String("sky".into()),
+            Value::List(vec![Value::UInt(1), Value::Binary(vec![0xFF])]),
+        ] {
+            param.encode_param(&mut buf).unwrap();
+        }
+        assert_eq!(
+            buf,
+            b"\x00\x01\x01\x0242\n\x03-1\n\x063\nsky\x072\n\x021\n\x051\n\xFF"
+        );
+        assert!(Value::List(vec![Value::List(vec![])])
+            .encode_param(&mut buf)
+            .is_err());
+    }
+
+    #[test]
+    fn decode_output() {
+        // select * from m where k = ?
+        let row = Response::Serialized {
+            ty: ResponseType::Row,
+            size: 3,
+            data: b"\x0D5\nsayan\x0542\n\x0E2\n\x00\x01\x01".to_vec(),
+        };
+        assert_eq!(
+            Output::decode(row).unwrap(),
+            Output::Row(vec![
+                Value::String("sayan".into()),
+                Value::UInt(42),
+                Value::List(vec![Value::Null, Value::Bool(true)])
+            ])
+        );
+        // select all * from m limit ?
+        let rows = Response::Serialized {
+            ty: ResponseType::MultiRow,
+            size: 2,
+            data: b"1\n\x09-1\n\x0B1.5\n".to_vec(),
+        };
+        assert_eq!(
+            Output::decode(rows).unwrap(),
+            Output::Rows(vec![vec![Value::SInt(-1)], vec![Value::Float(1.5)]])
+        );
+        // the same with `set packed_columns = true`
+        let mut data = b"1\n\x02".to_vec();
+        data.extend([1.5f64.to_le_bytes(), 2.5f64.to_le_bytes()].concat());
+        let rows = Response::Serialized {
+            ty: ResponseType::PackedRows,
+            size: 2,
+            data,
+        };
+        assert_eq!(
+            Output::decode(rows).unwrap(),
+            Output::Rows(vec![vec![Value::Float(1.5)], vec![Value::Float(2.5)]])
+        );
+        assert_eq!(Output::decode(Response::Empty).unwrap(), Output::Empty);
+        // truncated
+        let bad = Response::Serialized {
+            ty: ResponseType::Row,
+            size: 1,
+            data: b"\x0D5\nsay".to_vec(),
+        };
+        assert!(Output::decode(bad).is_err());
+        // the position of an error is only for network clients
+        let at = Response::ErrorAt {
+            error: QueryError::QLUnexpectedTrailingTokens,
+            position: 2,
+        };
+        assert_eq!(
+            Output::decode(at).unwrap_err(),
+            QueryError::QLUnexpectedTrailingTokens
+        );
+    }
+
+    #[test]
+    fn space_with_absolute_storage_path() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = std::env::temp_dir();
+        let tmp = tmp.to_str().unwrap().trim_end_matches('/');
+        let data = format!("{tmp}/embedded_space_with_absolute_storage_path");
+        let spaces = format!("{tmp}/embedded_space_with_absolute_storage_path_spaces");
+        let _ = std::fs::remove_dir_all(&data);
+        let _ = std::fs::remove_dir_all(&spaces);
+        std::fs::create_dir_all(&spaces).unwrap();
+        let options = ["--auth-root-password=mypassword12345678"];
+        let mut db = Database::open(&data, &options).unwrap();
+        db.execute(
+            &format!("create space myspace with {{ storage_path: '{spaces}' }}"),
+            &[],
+        )
+        .unwrap();
+        db.execute(
+            "create model myspace.mymodel(username: string, password: string)",
+            &[],
+        )
+        .unwrap();
+        db.execute(
+            "insert into myspace.mymodel(?, ?)",
+            &[Value::String("sayan".into()), Value::String("pass".into())],
+        )
+        .unwrap();
+        db.close();
+        // the space is in the storage path itself, and not in a copy of it under the data directory
+        let space_dirs: Vec<String> = std::fs::read_dir(&spaces)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(space_dirs.len(), 1);
+        assert!(space_dirs[0].starts_with("myspace-"));
+        assert!(!Path::new(&format!("{data}/{spaces}")).exists());
+        // and it's found there again
+        let mut db = Database::open(&data, &options).unwrap();
+        assert_eq!(
+            db.execute(
+                "select password from myspace.mymodel where username = ?",
+                &[Value::String("sayan".into())],
+            )
+            .unwrap(),
+            Output::Row(vec![Value::String("pass".into())])
+        );
+        db.close();
+        let _ = std::fs::remove_dir_all(&data);
+        let _ = std::fs::remove_dir_all(&spaces);
+    }
+}
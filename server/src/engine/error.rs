@@ -48,6 +48,14 @@ pub enum QueryError {
     /// insufficient permissions error
     SysPermissionDenied = 5,
     SysNetworkSystemIllegalClientPacket = 6,
+    /// the server is in read-only mode because storage has failed (for example, the disk is full)
+    SysReadOnly = 7,
+    /// the job was cancelled before it could finish
+    SysJobCancelled = 8,
+    /// the server can't write data to disk fast enough, so the write was rejected (try again later)
+    SysWriteBackpressure = 9,
+    /// the user has used up its write quota for now (try again later)
+    SysWriteQuotaExceeded = 10,
     // QL
     /// something like an integer that randomly has a character to attached to it like `1234q`
     LexInvalidInput = 25,
@@ -67,6 +75,8 @@ pub enum QueryError {
     QLExpectedStatement = 32,
     /// unknown statement
     QLUnknownStatement = 33,
+    /// the statement was followed by more tokens (the error response has the position of the first of them)
+    QLUnexpectedTrailingTokens = 34,
     // exec
     /// the object to be used as the "query container" is missing (for example, insert when the model was missing)
     QExecObjectNotFound = 100,
@@ -95,6 +105,40 @@ pub enum QueryError {
     QExecDmlRowNotFound = 111,
     /// this query needs a lock for execution, but that wasn't explicitly allowed anywhere
     QExecNeedLock = 112,
+    /// the function called in an expression does not exist
+    QExecUnknownFunction = 113,
+    /// the value cannot be converted into the requested type without losing information
+    QExecDmlLossyCast = 114,
+    /// the query needs more (transient) memory than a single query is allowed to use
+    QExecQueryMemoryLimit = 115,
+    /// the query was cancelled (with `sysctl cancel query <id>`) before it could finish
+    QExecQueryCancelled = 116,
+    /// the query ran for longer than its statement timeout
+    QExecQueryTimeout = 117,
+    /// a string or binary value is larger than the server allows for a single field
+    QExecDmlFieldTooLarge = 118,
+    /// the row is larger than the server allows for a single row
+    QExecDmlRowTooLarge = 119,
+    /// the model is append-only, so its rows can't be updated or deleted
+    QExecDmlAppendOnly = 120,
+    /// a field that isn't nullable was given a null (or was left out)
+    QExecDmlNullViolation = 121,
+    /// the value of a unique field is already used by another row
+    QExecDmlUniqueViolation = 122,
+}
+
+impl QueryError {
+    /// Returns true if a statement that failed with this error may have changed something before it failed. Every
+    /// other error is raised before a statement changes anything
+    pub fn is_indeterminate(&self) -> bool {
+        matches!(
+            self,
+            Self::SysServerError
+                | Self::SysOutOfMemory
+                | Self::SysUnknownError
+                | Self::SysTransactionalError
+        )
+    }
 }
 
 direct_from! {
@@ -215,6 +259,10 @@ enumerate_err! {
         DataBatchRestoreCorruptedBatchFile = "batch-corrupted-file",
         /// the system database is corrupted
         SysDBCorrupted = "sysdb-corrupted",
+        /// the server version history (lineage) file is corrupted
+        LineageCorrupted = "lineage-corrupted",
+        /// the metadata of a dropped model in the trash is corrupted
+        TrashCorrupted = "trash-corrupted",
         // raw journal errors
         RawJournalEventCorruptedMetadata = "journal-event-metadata-corrupted",
         RawJournalEventCorrupted = "journal-invalid-event",
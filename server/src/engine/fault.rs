@@ -0,0 +1,66 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Fault injection
+//!
+//! Crash-consistency testing for the storage engine. Faults are injected into the virtual file system that the storage
+//! engine runs on in tests: run a workload, crash the files that it wrote with a [`Fault`] and then open them again to
+//! check what survived. The engine's own tests use this, and the `fault-injection` feature makes it public so that
+//! workloads can be run against an [in-memory database](super::embedded::Database::open_in_memory) from outside the
+//! crate. An in-memory database keeps its files under [`Database::IN_MEMORY_ROOT`](super::embedded::Database::IN_MEMORY_ROOT),
+//! so the paths that are crashed have to include it.
+//!
+//! Faults can also be injected into query execution: see [`panic_on_next_query`]
+
+pub use super::storage::safe_interfaces::Fault;
+use {super::storage::safe_interfaces::VirtualFS, crate::IoResult};
+
+local! {
+    /// set to make the next query (that this thread runs) panic
+    static PANIC_ON_NEXT_QUERY: bool = false;
+}
+
+/// Simulate a crash for the file at `path`: it is left with whatever was synced to it, along with the unsynced writes
+/// that the fault lets through
+pub fn crash_file(path: &str, fault: Fault) -> IoResult<()> {
+    VirtualFS::instance().read().crash_file(path, fault)
+}
+
+/// Simulate a crash for the directory at `path`: its files go back to the ones it had when it was last synced (the
+/// data in the files is left as is; see [`crash_file`])
+pub fn crash_dir(path: &str) -> IoResult<()> {
+    VirtualFS::instance().write().crash_dir(path)
+}
+
+/// Make the next query that the current thread runs on an [embedded database](super::embedded::Database) panic
+pub fn panic_on_next_query() {
+    local_mut!(PANIC_ON_NEXT_QUERY, |panic| *panic = true)
+}
+
+/// Returns true (once) if the next query should panic
+pub(super) fn take_query_panic() -> bool {
+    local_mut!(PANIC_ON_NEXT_QUERY, |panic| core::mem::take(panic))
+}
@@ -0,0 +1,117 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    compute pool
+    ---
+    statements that scan a lot of data would otherwise run on the network runtime's worker threads and hold up every
+    other connection scheduled on them (point reads in particular). such statements are offloaded to a small, fixed
+    pool of threads (one per core) so that the reactor stays responsive. unlike tokio's blocking pool, the number of
+    threads here doesn't grow with the load; work simply queues up until a thread is free
+*/
+
+use {
+    crate::engine::error::{QueryError, QueryResult},
+    parking_lot::Mutex,
+    std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            mpsc::{self, Receiver, Sender},
+            Arc, OnceLock,
+        },
+        thread,
+    },
+    tokio::sync::oneshot,
+};
+
+type ComputeTask = Box<dyn FnOnce() + Send + 'static>;
+
+static POOL: OnceLock<Mutex<Sender<ComputeTask>>> = OnceLock::new();
+
+fn pool() -> &'static Mutex<Sender<ComputeTask>> {
+    POOL.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<ComputeTask>();
+        let rx = Arc::new(Mutex::new(rx));
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        for i in 0..threads {
+            let rx = rx.clone();
+            thread::Builder::new()
+                .name(format!("compute-{i}"))
+                .spawn(move || worker(rx))
+                .unwrap();
+        }
+        Mutex::new(tx)
+    })
+}
+
+fn worker(rx: Arc<Mutex<Receiver<ComputeTask>>>) {
+    loop {
+        let task = rx.lock().recv();
+        match task {
+            Ok(task) => task(),
+            Err(_) => return,
+        }
+    }
+}
+
+/// Run `f` on the compute pool and wait for it to finish without blocking the runtime
+pub async fn offload<T, F>(f: F) -> QueryResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> QueryResult<T> + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    let task: ComputeTask = Box::new(move || {
+        // a panic must not take down the thread with it; the caller will see a server error instead
+        let r = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+            error!("compute: a task panicked");
+            Err(QueryError::SysServerError)
+        });
+        let _ = tx.send(r);
+    });
+    if pool().lock().send(task).is_err() {
+        return Err(QueryError::SysServerError);
+    }
+    rx.await.unwrap_or(Err(QueryError::SysServerError))
+}
+
+#[test]
+fn offload_compute() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let main = thread::current().id();
+        let ran_on = offload(|| Ok(thread::current().id())).await.unwrap();
+        assert_ne!(ran_on, main);
+        assert_eq!(
+            offload::<(), _>(|| panic!("oops")).await,
+            Err(QueryError::SysServerError)
+        );
+        // the pool is still alive after a panic
+        assert_eq!(offload(|| Ok(1 + 1)).await, Ok(2));
+    })
+}
@@ -25,12 +25,17 @@
 */
 
 use {
-    super::{util, GlobalInstanceLike},
+    super::{util, GlobalInstanceLike, ModelUniqueIDRef},
     crate::{
         engine::{
+            core::{model::ModelData, trace},
+            data::uuid::Uuid,
             error::{QueryError, QueryResult, RuntimeResult},
             fractal::{CriticalTask, Task},
-            storage::{GNSDriver, ModelDriver},
+            storage::{
+                safe_interfaces::{paths_v1, TruncateModelBatch},
+                BatchStats, GNSDriver, ModelDriver,
+            },
         },
         util::compiler,
     },
@@ -54,6 +59,13 @@ impl FractalGNSDriver {
     pub(super) fn status(&self) -> &util::Status {
         &self.status
     }
+    fn unavailable(g: &impl GlobalInstanceLike) -> QueryError {
+        if g.health().is_read_only() {
+            QueryError::SysReadOnly
+        } else {
+            QueryError::SysServerError
+        }
+    }
     pub fn driver_context<T>(
         &self,
         g: &impl GlobalInstanceLike,
@@ -61,18 +73,18 @@ impl FractalGNSDriver {
         on_failure: impl Fn(),
     ) -> QueryResult<T> {
         if self.status.is_iffy() {
-            return Err(QueryError::SysServerError);
+            return Err(Self::unavailable(g));
         }
         let mut txn_driver = self.txn_driver.lock();
-        match f(&mut txn_driver) {
+        match trace::span("journal write", || f(&mut txn_driver)) {
             Ok(v) => Ok(v),
             Err(e) => compiler::cold_call(|| {
                 self.status.set_iffy();
-                g.health().report_fault();
+                g.health().report_fault(&e);
                 on_failure();
                 g.taskmgr_post_high_priority(Task::new(CriticalTask::CheckGNSDriver));
                 error!("GNS driver failed with: {e}");
-                Err(QueryError::SysServerError)
+                Err(Self::unavailable(g))
             }),
         }
     }
@@ -114,7 +126,84 @@ impl FractalModelDriver {
     pub fn batch_driver(&self) -> &Mutex<Option<ModelDriver>> {
         &self.batch_driver
     }
+    /// Compact the model's data file, dropping all dead entries. If this fails, the driver is marked as
+    /// faulted (and an autorecovery is attempted if the driver is still around)
+    pub fn compact(
+        &self,
+        g: &impl GlobalInstanceLike,
+        mdl_id: ModelUniqueIDRef,
+        space_uuid: Uuid,
+        model: &ModelData,
+    ) -> QueryResult<()> {
+        if self.status.is_iffy() {
+            return Err(QueryError::SysServerError);
+        }
+        let model_data_file_path =
+            paths_v1::model_path(mdl_id.space, space_uuid, mdl_id.model, mdl_id.uuid);
+        let mut drv = self.batch_driver.lock();
+        match ModelDriver::compact_model_driver(&mut drv, model, &model_data_file_path) {
+            Ok(()) => {
+                model
+                    .delta_state()
+                    .__reset_persisted_events(model.primary_index().count());
+                Ok(())
+            }
+            Err(e) => compiler::cold_call(|| {
+                self.status.set_iffy();
+                g.health().report_fault(&e);
+                error!(
+                    "failed to compact model {}.{} with error `{e}`",
+                    mdl_id.space, mdl_id.model
+                );
+                if drv.is_some() {
+                    g.taskmgr_post_high_priority(Task::new(CriticalTask::TryModelAutorecoverLWT(
+                        mdl_id.into(),
+                    )));
+                }
+                Err(QueryError::SysServerError)
+            }),
+        }
+    }
+    /// Write a truncate batch to the model's data file. If this fails, the driver is marked as faulted (and an
+    /// autorecovery is attempted)
+    ///
+    /// NB: the caller must remove the rows (and discard the pending deltas) only if this succeeds
+    pub fn truncate(
+        &self,
+        g: &impl GlobalInstanceLike,
+        mdl_id: ModelUniqueIDRef,
+        model: &ModelData,
+    ) -> QueryResult<()> {
+        if self.status.is_iffy() {
+            return Err(QueryError::SysServerError);
+        }
+        let mut drv = self.batch_driver.lock();
+        let r = trace::span("journal write", || {
+            drv.as_mut()
+                .unwrap()
+                .commit_with_ctx(TruncateModelBatch::new(model), BatchStats::new())
+        });
+        match r {
+            Ok(()) => Ok(()),
+            Err(e) => compiler::cold_call(|| {
+                self.status.set_iffy();
+                g.health().report_fault(&e);
+                error!(
+                    "failed to truncate model {}.{} with error `{e}`",
+                    mdl_id.space, mdl_id.model
+                );
+                g.taskmgr_post_high_priority(Task::new(CriticalTask::TryModelAutorecoverLWT(
+                    mdl_id.into(),
+                )));
+                Err(QueryError::SysServerError)
+            }),
+        }
+    }
     pub fn close(self) -> RuntimeResult<()> {
-        ModelDriver::close_driver(&mut self.batch_driver.into_inner().unwrap())
+        match self.batch_driver.into_inner() {
+            Some(mut drv) => ModelDriver::close_driver(&mut drv),
+            // the driver was lost to a failed compaction
+            None => Ok(()),
+        }
     }
 }
@@ -55,6 +55,14 @@ impl Error {
     pub fn kind(&self) -> &ErrorKind {
         &self.kind
     }
+    /// Returns true if this error was caused by the storage device itself (it's full, or is failing)
+    /// rather than by bad data
+    pub fn is_storage_failure(&self) -> bool {
+        match &self.kind {
+            ErrorKind::IoError(e) => e.is_storage_failure(),
+            _ => false,
+        }
+    }
     /// Replace the origin in self
     pub fn add_origin(self, origin: Subsystem) -> Self {
         Self::_new(self.kind, Some(origin), self.dmsg)
@@ -0,0 +1,303 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    background jobs
+    ---
+    long running maintenance and schema work (compaction, backfills, index builds) is run as a job on the
+    general executor so that the issuing connection isn't blocked. the client gets a job ID which it can use to
+    track progress (`inspect sys.jobs`) or to cancel the job (`sysctl cancel job <id>`). jobs are cooperative:
+    cancellation is only observed at the points where a job checks for it
+*/
+
+use {
+    super::{GlobalInstanceLike, ModelUniqueID, ModelUniqueIDRef},
+    crate::engine::{
+        core::{
+            import::{self, ImportSpec},
+            EntityIDRef,
+        },
+        error::{QueryError, QueryResult},
+    },
+    parking_lot::{Mutex, RwLock},
+    std::{
+        collections::BTreeMap,
+        fmt,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// the number of finished jobs that we hold on to (so that they can be inspected)
+const MAX_FINISHED_JOBS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// The work that a job does
+pub enum JobKind {
+    /// Compact the model's data file
+    CompactModel(ModelUniqueID),
+    /// Copy a space from another server
+    ImportSpace(ImportSpec),
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CompactModel(mdl) => write!(f, "compact model {}.{}", mdl.space(), mdl.model()),
+            Self::ImportSpace(spec) => {
+                write!(f, "import space {} from {}", spec.space(), spec.addr())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    id: u64,
+    kind: JobKind,
+    status: Mutex<JobStatus>,
+    cancelled: AtomicBool,
+    progress_done: AtomicU64,
+    progress_total: AtomicU64,
+}
+
+impl Job {
+    fn new(id: u64, kind: JobKind) -> Self {
+        Self {
+            id,
+            kind,
+            status: Mutex::new(JobStatus::Queued),
+            cancelled: AtomicBool::new(false),
+            progress_done: AtomicU64::new(0),
+            progress_total: AtomicU64::new(0),
+        }
+    }
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn kind(&self) -> &JobKind {
+        &self.kind
+    }
+    pub fn status(&self) -> JobStatus {
+        *self.status.lock()
+    }
+    /// Returns (done, total)
+    pub fn progress(&self) -> (u64, u64) {
+        (
+            self.progress_done.load(Ordering::Acquire),
+            self.progress_total.load(Ordering::Acquire),
+        )
+    }
+    pub fn set_progress(&self, done: u64, total: u64) {
+        self.progress_total.store(total, Ordering::Release);
+        self.progress_done.store(done, Ordering::Release);
+    }
+    /// Returns true if the job was asked to stop. Jobs should check this at safe points
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+    /// Request cancellation. This does nothing if the job has already finished
+    fn cancel(&self) {
+        let mut status = self.status.lock();
+        match *status {
+            JobStatus::Queued => {
+                // never started, so we're done
+                self.cancelled.store(true, Ordering::Release);
+                *status = JobStatus::Cancelled;
+            }
+            JobStatus::Running => self.cancelled.store(true, Ordering::Release),
+            _ => {}
+        }
+    }
+    /// Mark the job as running. Returns false if it was cancelled before it could start
+    fn start(&self) -> bool {
+        let mut status = self.status.lock();
+        if *status == JobStatus::Queued {
+            *status = JobStatus::Running;
+            true
+        } else {
+            false
+        }
+    }
+    fn finish(&self, result: QueryResult<()>) {
+        let mut status = self.status.lock();
+        *status = match result {
+            Ok(()) => JobStatus::Completed,
+            Err(QueryError::SysJobCancelled) => JobStatus::Cancelled,
+            Err(_) => JobStatus::Failed,
+        };
+    }
+}
+
+#[derive(Debug)]
+/// Tracks all running (and recently finished) jobs
+pub struct JobTracker {
+    next_id: AtomicU64,
+    jobs: RwLock<BTreeMap<u64, Arc<Job>>>,
+}
+
+impl JobTracker {
+    pub const fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            jobs: RwLock::new(BTreeMap::new()),
+        }
+    }
+    /// Register a new job. The job still needs to be posted to the task manager
+    pub fn submit(&self, kind: JobKind) -> Arc<Job> {
+        let id = self.next_id.fetch_add(1, Ordering::AcqRel);
+        let job = Arc::new(Job::new(id, kind));
+        let mut jobs = self.jobs.write();
+        jobs.insert(id, job.clone());
+        // get rid of the oldest finished jobs
+        let finished: Vec<u64> = jobs
+            .values()
+            .filter(|job| job.status().is_finished())
+            .map(|job| job.id())
+            .collect();
+        if finished.len() > MAX_FINISHED_JOBS {
+            for id in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+                jobs.remove(id);
+            }
+        }
+        job
+    }
+    pub fn get(&self, id: u64) -> Option<Arc<Job>> {
+        self.jobs.read().get(&id).cloned()
+    }
+    /// Returns all jobs, in the order that they were submitted
+    pub fn list(&self) -> Vec<Arc<Job>> {
+        self.jobs.read().values().cloned().collect()
+    }
+    /// Cancel the job with the given ID
+    pub fn cancel(&self, id: u64) -> QueryResult<()> {
+        match self.get(id) {
+            Some(job) => {
+                job.cancel();
+                Ok(())
+            }
+            None => Err(QueryError::QExecObjectNotFound),
+        }
+    }
+}
+
+/// Run a job to completion (on the current thread)
+pub(super) fn run(global: &impl GlobalInstanceLike, job: &Job) {
+    if !job.start() {
+        // cancelled before we could get to it
+        return;
+    }
+    info!("jobs: starting job {} ({})", job.id(), job.kind());
+    let result = match job.kind() {
+        JobKind::CompactModel(mdl_id) => compact_model(global, job, mdl_id),
+        JobKind::ImportSpace(spec) => import::import_space(global, job, spec),
+    };
+    match &result {
+        Ok(()) => info!("jobs: job {} completed", job.id()),
+        Err(QueryError::SysJobCancelled) => info!("jobs: job {} was cancelled", job.id()),
+        Err(e) => error!("jobs: job {} failed with error `{e:?}`", job.id()),
+    }
+    job.finish(result)
+}
+
+fn compact_model(
+    global: &impl GlobalInstanceLike,
+    job: &Job,
+    mdl_id: &ModelUniqueID,
+) -> QueryResult<()> {
+    job.set_progress(0, 1);
+    let models = global.state().namespace().idx_models().read();
+    let model = match models.get(&EntityIDRef::new(mdl_id.space(), mdl_id.model())) {
+        Some(model) if model.data().get_uuid() == mdl_id.uuid() => model,
+        // the model was dropped (and maybe recreated) since
+        Some(_) | None => return Err(QueryError::QExecObjectNotFound),
+    };
+    if job.is_cancelled() {
+        return Err(QueryError::SysJobCancelled);
+    }
+    let space_uuid = global
+        .state()
+        .namespace()
+        .idx()
+        .read()
+        .get(mdl_id.space())
+        .unwrap()
+        .get_uuid();
+    model.driver().compact(
+        global,
+        ModelUniqueIDRef::from(mdl_id),
+        space_uuid,
+        model.data(),
+    )?;
+    job.set_progress(1, 1);
+    Ok(())
+}
+
+#[test]
+fn cancel_queued_job() {
+    let tracker = JobTracker::new();
+    let job = tracker.submit(JobKind::CompactModel(ModelUniqueID::new(
+        "apps",
+        "social",
+        crate::engine::data::uuid::Uuid::new(),
+    )));
+    assert_eq!(job.status(), JobStatus::Queued);
+    tracker.cancel(job.id()).unwrap();
+    assert!(job.is_cancelled());
+    assert_eq!(job.status(), JobStatus::Cancelled);
+    // a cancelled job never starts
+    assert!(!job.start());
+    assert_eq!(
+        tracker.cancel(job.id() + 1),
+        Err(QueryError::QExecObjectNotFound)
+    );
+}
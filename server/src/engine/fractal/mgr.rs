@@ -25,12 +25,12 @@
 */
 
 use {
-    super::{ModelUniqueID, ModelUniqueIDRef},
+    super::{jobs, Job, ModelUniqueID, ModelUniqueIDRef},
     crate::{
         engine::{
             core::{
-                model::{delta::DataDelta, ModelData},
-                EntityIDRef,
+                model::{delta::DataDelta, trash, Model, ModelData},
+                task, EntityIDRef,
             },
             data::uuid::Uuid,
             error::ErrorKind,
@@ -42,7 +42,7 @@ use {
         },
         util::os,
     },
-    std::{path::PathBuf, time::Duration},
+    std::{path::PathBuf, sync::Arc, time::Duration},
     tokio::{
         fs,
         sync::{
@@ -54,6 +54,12 @@ use {
 };
 
 pub const GENERAL_EXECUTOR_WINDOW: u64 = 5 * 60;
+/// a model's data file is compacted once atleast this fraction of the events in it are dead ...
+const COMPACTION_DEAD_RATIO: f64 = 0.5;
+/// ... provided that it has atleast these many events
+const COMPACTION_MIN_EVENTS: usize = 100_000;
+/// the general executor syncs all pending batches once atleast these many bytes have been written in a group commit window
+const GROUP_COMMIT_WINDOW_MAX_BYTES: u64 = 32 * 1024 * 1024;
 const TASK_THRESHOLD: usize = 10;
 const TASK_FAILURE_SLEEP_DURATION: u64 = 30;
 
@@ -91,6 +97,10 @@ pub enum GenericTask {
     DeleteFile(PathBuf),
     /// Delete a directory (and all its children)
     DeleteDirAll(PathBuf),
+    /// Run a scheduled task (the name of the task and the id of the run)
+    RunTask(Box<str>, u64),
+    /// Run a background job
+    RunJob(Arc<Job>),
 }
 
 impl GenericTask {
@@ -133,7 +143,7 @@ pub(super) struct FractalRTStat {
 
 impl FractalRTStat {
     fn init(model_cnt: usize) -> Self {
-        let mem_free_bytes = os::free_memory_in_bytes();
+        let mem_free_bytes = os::available_memory_in_bytes();
         let allowed_delta_limit = mem_free_bytes as f64 * 0.02;
         let per_model_limit = allowed_delta_limit / model_cnt.max(1) as f64;
         Self {
@@ -386,6 +396,7 @@ impl FractalMgr {
                     }
                 };
                 match self.try_write_model_data_batch(
+                    &global,
                     ModelUniqueIDRef::from(&model_id),
                     mdl.data(),
                     observed_size,
@@ -425,6 +436,8 @@ impl FractalMgr {
         rs_window: u64,
     ) {
         let dur = std::time::Duration::from_secs(rs_window);
+        // the scheduler wakes up every minute, so the flush deadline has to survive the other branches
+        let mut next_flush = tokio::time::Instant::now() + dur;
         loop {
             tokio::select! {
                 _ = sigterm.recv() => {
@@ -434,9 +447,13 @@ impl FractalMgr {
                     info!("flp: exited executor service");
                     break;
                 },
-                _ = tokio::time::sleep(dur) => {
+                _ = tokio::time::sleep_until(next_flush) => {
                     let global = global.clone();
-                    tokio::task::spawn_blocking(|| self.general_executor(global)).await.unwrap()
+                    tokio::task::spawn_blocking(|| self.general_executor(global)).await.unwrap();
+                    next_flush = tokio::time::Instant::now() + dur;
+                }
+                _ = tokio::time::sleep(task::until_next_minute(&chrono::Utc::now())) => {
+                    task::tick(&global, chrono::Utc::now())
                 }
                 task = lpq.recv() => {
                     let Task { threshold, task } = match task {
@@ -465,30 +482,58 @@ impl FractalMgr {
                                 ).unwrap();
                             }
                         }
+                        GenericTask::RunTask(task_name, run_id) => {
+                            // a statement can take a while, so don't hold up the queue
+                            let global = global.clone();
+                            tokio::task::spawn_blocking(move || task::run(&global, &task_name, run_id));
+                        }
+                        GenericTask::RunJob(job) => {
+                            // jobs can take a while, so don't hold up the queue
+                            let global = global.clone();
+                            tokio::task::spawn_blocking(move || jobs::run(&global, &job));
+                        }
                     }
                 }
             }
         }
     }
     fn general_executor(&'static self, global: super::Global) {
-        for (model_id, model) in global.state().namespace().idx_models().read().iter() {
+        /*
+            write out the batches for all models but only sync them once the group commit window is full (or we're done).
+            when a lot of small models have pending changes, this saves us a sync per model
+        */
+        let models = global.state().namespace().idx_models().read();
+        let mut window = GroupCommitWindow::new();
+        for (model_id, model) in models.iter() {
+            // remove the expired rows first so that the deletes go out with this batch
+            let swept = model.data().sweep_expired();
+            if swept != 0 {
+                info!(
+                    "flp: removed {swept} expired row(s) from {}.{}",
+                    model_id.space(),
+                    model_id.entity()
+                );
+            }
             let observed_len = model
                 .data()
                 .delta_state()
                 .__fractal_take_full_from_data_delta(super::FractalToken::new());
-            match self.try_write_model_data_batch(
-                ModelUniqueIDRef::new(model_id.space(), model_id.entity(), model.data().get_uuid()),
+            let mdl_id =
+                ModelUniqueIDRef::new(model_id.space(), model_id.entity(), model.data().get_uuid());
+            match self.write_model_data_batch(
+                &global,
+                mdl_id,
                 model.data(),
                 observed_len,
                 model.driver(),
+                false,
             ) {
-                Ok(()) => {
+                Ok(bytes) => {
                     if observed_len != 0 {
-                        info!(
-                            "flp: completed maintenance task for {}.{}, synced={observed_len}",
-                            model_id.space(),
-                            model_id.entity()
-                        )
+                        window.push(mdl_id, model, observed_len, bytes);
+                    }
+                    if window.bytes >= GROUP_COMMIT_WINDOW_MAX_BYTES {
+                        self.sync_group_commit_window(&global, &mut window);
                     }
                 }
                 Err((e, stats)) => {
@@ -510,21 +555,164 @@ impl FractalMgr {
                 }
             }
         }
+        self.sync_group_commit_window(&global, &mut window);
+        // now that everything is on disk, see if any of the data files need to be compacted
+        for (model_id, model) in models.iter() {
+            if model.driver().status().is_iffy() || !Self::needs_compaction(model.data()) {
+                continue;
+            }
+            let mdl_id =
+                ModelUniqueIDRef::new(model_id.space(), model_id.entity(), model.data().get_uuid());
+            let space_uuid = global
+                .state()
+                .namespace()
+                .idx()
+                .read()
+                .get(model_id.space())
+                .unwrap()
+                .get_uuid();
+            if model
+                .driver()
+                .compact(&global, mdl_id, space_uuid, model.data())
+                .is_ok()
+            {
+                info!(
+                    "flp: compacted data file for {}.{}",
+                    model_id.space(),
+                    model_id.entity()
+                )
+            }
+        }
+        drop(models);
+        // finally, reclaim the space held by models that were dropped a while ago
+        let purged = trash::purge_trash(&global);
+        if purged != 0 {
+            info!("flp: purged {purged} dropped model(s) from the trash");
+        }
+    }
+    /// Sync every batch pending in the group commit window and reset the window
+    fn sync_group_commit_window(
+        &'static self,
+        global: &super::Global,
+        window: &mut GroupCommitWindow,
+    ) {
+        if !window.pending.is_empty() {
+            info!(
+                "flp: syncing {} model(s) in group commit window, bytes={}",
+                window.pending.len(),
+                window.bytes
+            );
+        }
+        for PendingBatch {
+            mdl_id,
+            model,
+            observed_len,
+            bytes,
+        } in window.pending.drain(..)
+        {
+            let mut driver = model.driver().batch_driver().lock();
+            match driver.as_mut().unwrap().sync() {
+                Ok(()) => {
+                    let delta_state = model.data().delta_state();
+                    delta_state.__record_persisted_events(observed_len);
+                    delta_state.__commit_written();
+                    info!(
+                        "flp: completed maintenance task for {}.{}, synced={observed_len}, bytes={bytes}",
+                        mdl_id.space,
+                        mdl_id.model
+                    )
+                }
+                Err(e) => {
+                    error!(
+                        "flp: failed to sync data file for {}.{} with error `{e}`",
+                        mdl_id.space, mdl_id.model
+                    );
+                    model.driver().status().set_iffy();
+                    global.health().report_fault(&e);
+                    self.hp_dispatcher
+                        .send(Task::new(CriticalTask::TryModelAutorecoverLWT(
+                            mdl_id.into(),
+                        )))
+                        .unwrap();
+                }
+            }
+        }
+        window.bytes = 0;
+    }
+}
+
+/// A batch that has been written to a model's data file but is yet to be synced
+struct PendingBatch<'a> {
+    mdl_id: ModelUniqueIDRef<'a>,
+    model: &'a Model,
+    observed_len: usize,
+    bytes: u64,
+}
+
+/// The group commit window: batches across models are written out and then synced together
+struct GroupCommitWindow<'a> {
+    pending: Vec<PendingBatch<'a>>,
+    bytes: u64,
+}
+
+impl<'a> GroupCommitWindow<'a> {
+    fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            bytes: 0,
+        }
+    }
+    fn push(
+        &mut self,
+        mdl_id: ModelUniqueIDRef<'a>,
+        model: &'a Model,
+        observed_len: usize,
+        bytes: u64,
+    ) {
+        self.bytes += bytes;
+        self.pending.push(PendingBatch {
+            mdl_id,
+            model,
+            observed_len,
+            bytes,
+        });
     }
 }
 
 // util
 impl FractalMgr {
+    /// Check if enough of the model's data file is dead for it to be worth compacting
+    fn needs_compaction(model: &ModelData) -> bool {
+        let events = model.delta_state().persisted_events();
+        let live = model.primary_index().count();
+        (events >= COMPACTION_MIN_EVENTS)
+            & ((events.saturating_sub(live) as f64 / events as f64) >= COMPACTION_DEAD_RATIO)
+    }
     /// Attempt to write a model data batch with the observed size.
     ///
     /// The zero check is essential
     fn try_write_model_data_batch(
         &'static self,
+        global: &super::Global,
         mdl_id: ModelUniqueIDRef,
         model: &ModelData,
         observed_size: usize,
         mdl_driver_: &super::drivers::FractalModelDriver,
     ) -> Result<(), (super::error::Error, BatchStats)> {
+        self.write_model_data_batch(global, mdl_id, model, observed_size, mdl_driver_, true)
+            .map(|_| ())
+    }
+    /// Write a model data batch with the observed size, returning the number of bytes written. If `sync` is not set,
+    /// the batch is not durable until the driver is synced
+    fn write_model_data_batch(
+        &'static self,
+        global: &super::Global,
+        mdl_id: ModelUniqueIDRef,
+        model: &ModelData,
+        observed_size: usize,
+        mdl_driver_: &super::drivers::FractalModelDriver,
+        sync: bool,
+    ) -> Result<u64, (super::error::Error, BatchStats)> {
         if mdl_driver_.status().is_iffy() {
             // don't mess this up any further
             return Err((
@@ -536,25 +724,35 @@ impl FractalMgr {
         }
         if observed_size == 0 {
             // no changes, all good
-            return Ok(());
+            return Ok(0);
         }
         // try flushing the batch
         let batch_stats = BatchStats::new();
         let mut mdl_driver = mdl_driver_.batch_driver().lock();
         let batch_driver = mdl_driver.as_mut().unwrap();
-        batch_driver
-            .commit_with_ctx(
-                StdModelBatch::new(model, observed_size),
-                batch_stats.clone(),
-            )
-            .map_err(|e| {
-                mdl_driver_.status().set_iffy();
-                self.hp_dispatcher
-                    .send(Task::new(CriticalTask::TryModelAutorecoverLWT(
-                        mdl_id.into(),
-                    )))
-                    .unwrap();
-                (e, BatchStats::into_inner(batch_stats))
-            })
+        let start = batch_driver.cursor();
+        let batch = StdModelBatch::new(model, observed_size);
+        let ret = if sync {
+            batch_driver.commit_with_ctx(batch, batch_stats.clone())
+        } else {
+            batch_driver.commit_with_ctx_deferred_sync(batch, batch_stats.clone())
+        };
+        ret.map(|_| {
+            if sync {
+                model.delta_state().__record_persisted_events(observed_size);
+                model.delta_state().__commit_written();
+            }
+            batch_driver.cursor() - start
+        })
+        .map_err(|e| {
+            mdl_driver_.status().set_iffy();
+            global.health().report_fault(&e);
+            self.hp_dispatcher
+                .send(Task::new(CriticalTask::TryModelAutorecoverLWT(
+                    mdl_id.into(),
+                )))
+                .unwrap();
+            (e, BatchStats::into_inner(batch_stats))
+        })
     }
 }
@@ -26,32 +26,39 @@
 
 use {
     super::{
-        core::{dml::QueryExecMeta, model::ModelData, GlobalNS},
+        core::{dml::QueryExecMeta, exec, model::ModelData, task::TaskBody, GlobalNS},
         data::uuid::Uuid,
+        net::protocol::ClientLocalState,
         storage::{
             safe_interfaces::{paths_v1, FileSystem},
             GNSDriver, ModelDriver,
         },
     },
-    crate::{engine::error::RuntimeResult, util::compiler},
+    crate::{
+        engine::error::{QueryResult, RuntimeResult},
+        util::compiler,
+    },
     std::{
         fmt,
         mem::MaybeUninit,
-        sync::atomic::{AtomicUsize, Ordering},
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     tokio::sync::mpsc::unbounded_channel,
 };
 
+pub mod compute;
 pub mod context;
 mod drivers;
 pub mod error;
+mod jobs;
 mod mgr;
 #[cfg(test)]
 pub mod test_utils;
 mod util;
 pub use {
     drivers::{FractalGNSDriver, FractalModelDriver},
-    mgr::{CriticalTask, GenericTask, Task, GENERAL_EXECUTOR_WINDOW},
+    jobs::{Job, JobKind, JobTracker},
+    mgr::{CriticalTask, FractalHandle, GenericTask, Task, GENERAL_EXECUTOR_WINDOW},
     util::FractalToken,
 };
 
@@ -93,38 +100,77 @@ pub unsafe fn load_and_enable_all(gns: GlobalNS) -> GlobalStateStart {
 
 pub struct GlobalHealth {
     faults: AtomicUsize,
+    read_only: AtomicBool,
 }
 
 impl GlobalHealth {
     pub fn status_okay(&self) -> bool {
         self.faults.load(Ordering::Acquire) == 0
     }
+    /// Returns true if writes are being rejected because storage has failed (the disk is full or is
+    /// returning I/O errors). This is cleared once all the faulty drivers have recovered
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Acquire)
+    }
     const fn new() -> Self {
         Self {
             faults: AtomicUsize::new(0),
+            read_only: AtomicBool::new(false),
         }
     }
-    fn report_fault(&self) {
+    fn report_fault(&self, e: &error::Error) {
         self.faults.fetch_add(1, Ordering::Release);
+        if e.is_storage_failure() && !self.read_only.swap(true, Ordering::AcqRel) {
+            error!("storage failure ({e}). switching to read-only mode until storage recovers");
+        }
     }
     fn report_recovery(&self) {
-        self.faults.fetch_sub(1, Ordering::Release);
+        if self.faults.fetch_sub(1, Ordering::AcqRel) == 1
+            && self.read_only.swap(false, Ordering::AcqRel)
+        {
+            info!("storage has recovered. accepting writes again");
+        }
     }
     pub fn report_removal_of_faulty_source(&self) {
         self.report_recovery()
     }
 }
 
+#[test]
+fn health_read_only_on_storage_failure() {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+    let health = GlobalHealth::new();
+    health.report_fault(&IoError::from(IoErrorKind::InvalidData).into());
+    assert!(!health.is_read_only());
+    health.report_fault(&IoError::from(IoErrorKind::StorageFull).into());
+    assert!(health.is_read_only());
+    // still one faulty driver left
+    health.report_recovery();
+    assert!(health.is_read_only());
+    health.report_recovery();
+    assert!(!health.is_read_only());
+    assert!(health.status_okay());
+}
+
 /// Something that represents the global state
 pub trait GlobalInstanceLike {
     // stat
     fn health(&self) -> &GlobalHealth;
     fn get_max_delta_size(&self) -> usize;
+    // jobs
+    fn jobs(&self) -> &JobTracker;
     // global namespace
     fn state(&self) -> &GlobalNS;
-    fn initialize_space(&self, space_name: &str, space_uuid: Uuid) -> RuntimeResult<()> {
-        e!(FileSystem::create_dir_all(&paths_v1::space_dir(
-            space_name, space_uuid
+    fn initialize_space(
+        &self,
+        space_name: &str,
+        space_uuid: Uuid,
+        storage_path: Option<&str>,
+    ) -> RuntimeResult<()> {
+        e!(FileSystem::create_dir_all(&paths_v1::space_dir_in(
+            storage_path,
+            space_name,
+            space_uuid
         )))
     }
     // model drivers
@@ -145,6 +191,9 @@ pub trait GlobalInstanceLike {
     // taskmgr
     fn taskmgr_post_high_priority(&self, task: Task<CriticalTask>);
     fn taskmgr_post_standard_priority(&self, task: Task<GenericTask>);
+    // scheduled tasks
+    /// Run a scheduled task's statement as root. This is called from a blocking thread, so it may block
+    fn exec_task_statement(&self, body: &TaskBody) -> QueryResult<()>;
     // default impls
     #[inline(always)]
     fn request_batch_resolve_if_cache_full(
@@ -186,6 +235,13 @@ impl GlobalInstanceLike for Global {
         }
         .health
     }
+    fn jobs(&self) -> &JobTracker {
+        &unsafe {
+            // UNSAFE(@ohsayan): we expect the system to be initialized
+            self.__gref()
+        }
+        .jobs
+    }
     // taskmgr
     fn taskmgr_post_high_priority(&self, task: Task<CriticalTask>) {
         self._post_high_priority_task(task)
@@ -193,6 +249,14 @@ impl GlobalInstanceLike for Global {
     fn taskmgr_post_standard_priority(&self, task: Task<GenericTask>) {
         self._post_standard_priority_task(task)
     }
+    // scheduled tasks
+    fn exec_task_statement(&self, body: &TaskBody) -> QueryResult<()> {
+        // runs are on a blocking thread, so we can simply wait for the executor here
+        let mut cstate = ClientLocalState::new_local_root();
+        tokio::runtime::Handle::current()
+            .block_on(exec::dispatch_to_executor(self, &mut cstate, body.squery()))
+            .map(|_| ())
+    }
     // stat
     fn get_max_delta_size(&self) -> usize {
         self._get_max_delta_size()
@@ -295,6 +359,7 @@ struct GlobalState {
     gns: GlobalNS,
     task_mgr: mgr::FractalMgr,
     health: GlobalHealth,
+    jobs: JobTracker,
 }
 
 impl GlobalState {
@@ -303,6 +368,7 @@ impl GlobalState {
             gns,
             task_mgr,
             health: GlobalHealth::new(),
+            jobs: JobTracker::new(),
         }
     }
     pub(self) fn fractal_mgr(&self) -> &mgr::FractalMgr {
@@ -323,6 +389,7 @@ pub struct ModelUniqueID {
     uuid: Uuid,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct ModelUniqueIDRef<'a> {
     space: &'a str,
     model: &'a str,
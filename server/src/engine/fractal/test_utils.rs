@@ -26,13 +26,16 @@
 
 use {
     super::{
-        drivers::FractalGNSDriver, CriticalTask, FractalModelDriver, GenericTask, GlobalHealth,
-        GlobalInstanceLike, Task,
+        drivers::FractalGNSDriver, jobs, CriticalTask, FractalModelDriver, GenericTask,
+        GlobalHealth, GlobalInstanceLike, JobTracker, Task,
     },
     crate::engine::{
-        core::{EntityIDRef, GNSData, GlobalNS},
+        core::{
+            task::{self, TaskBody},
+            EntityIDRef, GNSData, GlobalNS,
+        },
         data::uuid::Uuid,
-        error::ErrorKind,
+        error::{ErrorKind, QueryResult},
         storage::{
             safe_interfaces::{paths_v1, FileSystem, StdModelBatch},
             BatchStats, GNSDriver, ModelDriver,
@@ -48,6 +51,8 @@ pub struct TestGlobal {
     lp_queue: RwLock<Vec<Task<GenericTask>>>,
     max_delta_size: usize,
     health: GlobalHealth,
+    task_log: RwLock<Vec<TaskBody>>,
+    jobs: JobTracker,
 }
 
 impl TestGlobal {
@@ -57,11 +62,36 @@ impl TestGlobal {
             lp_queue: RwLock::default(),
             max_delta_size: usize::MAX,
             health: GlobalHealth::new(),
+            task_log: RwLock::default(),
+            jobs: JobTracker::new(),
         }
     }
+    /// Returns the statements that scheduled tasks have run (instead of actually running them)
+    pub fn task_log(&self) -> Vec<TaskBody> {
+        self.task_log.read().clone()
+    }
     pub fn set_max_data_pressure(&mut self, max_data_pressure: usize) {
         self.max_delta_size = max_data_pressure;
     }
+    /// Write out the model's pending data deltas like a group commit does, that is, without syncing them (so they're
+    /// not durable until the driver is synced or closed)
+    pub fn write_batch_deferred(&self, mdl_id: EntityIDRef) {
+        let models = self.gns.namespace().idx_models().read();
+        let mdl = models.get(&mdl_id).unwrap();
+        let delta_count = mdl
+            .data()
+            .delta_state()
+            .__fractal_take_full_from_data_delta(super::FractalToken::new());
+        let mut mdl_driver = mdl.driver().batch_driver().lock();
+        mdl_driver
+            .as_mut()
+            .unwrap()
+            .commit_with_ctx_deferred_sync(
+                StdModelBatch::new(mdl.data(), delta_count),
+                BatchStats::new(),
+            )
+            .unwrap();
+    }
     /// Normally, model drivers are not loaded on startup because of shared global state. Calling this will attempt to load
     /// all model drivers
     fn load_model_drivers(&self) -> RuntimeResult<()> {
@@ -115,6 +145,9 @@ impl GlobalInstanceLike for TestGlobal {
     fn health(&self) -> &GlobalHealth {
         &self.health
     }
+    fn jobs(&self) -> &JobTracker {
+        &self.jobs
+    }
     fn state(&self) -> &GlobalNS {
         &self.gns
     }
@@ -130,14 +163,24 @@ impl GlobalInstanceLike for TestGlobal {
                     .as_mut()
                     .unwrap()
                     .commit_with_ctx(StdModelBatch::new(mdl.data(), count), BatchStats::new())
-                    .unwrap()
+                    .unwrap();
+                mdl.data().delta_state().__commit_written();
             }
             CriticalTask::TryModelAutorecoverLWT(_) => {}
             CriticalTask::CheckGNSDriver => {}
         }
     }
     fn taskmgr_post_standard_priority(&self, task: Task<GenericTask>) {
-        self.lp_queue.write().push(task)
+        match task.into_task() {
+            // run tasks and jobs right away so that tests can check on them
+            GenericTask::RunTask(task_name, run_id) => task::run(self, &task_name, run_id),
+            GenericTask::RunJob(job) => jobs::run(self, &job),
+            task => self.lp_queue.write().push(Task::new(task)),
+        }
+    }
+    fn exec_task_statement(&self, body: &TaskBody) -> QueryResult<()> {
+        self.task_log.write().push(body.clone());
+        Ok(())
     }
     fn get_max_delta_size(&self) -> usize {
         self.max_delta_size
@@ -27,6 +27,7 @@
 #![deny(unreachable_patterns)]
 
 pub mod meta;
+pub mod mtart;
 pub mod mtchm;
 mod stdhm;
 mod stord;
@@ -48,6 +49,7 @@ pub type IndexSTSeqCns<K, V> = stord::IndexSTSeqDll<K, V, stord::config::Conserv
 #[cfg(test)]
 pub type IndexSTSeqLib<K, V> = stord::IndexSTSeqDll<K, V, stord::config::LiberalConfig<K, V>>;
 pub type IndexMTRaw<E> = mtchm::imp::Raw<E, mtchm::meta::DefConfig>;
+pub type IndexMTArt<E> = mtart::RawArt<E>;
 pub type IndexST<K, V, S = std::collections::hash_map::RandomState> =
     std::collections::hash_map::HashMap<K, V, S>;
 
@@ -0,0 +1,770 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    radix tree index
+    ---
+    This is an adaptive radix tree[1] for keys that are byte strings. Keys that share a prefix share the nodes for that
+    prefix, and a run of bytes without any branches is stored once in the node where it ends. A node that has no
+    children doesn't store its bytes at all since they're already in the element's key. So long keys that look alike
+    (URLs, paths and the like) take up far less memory than they would in the hash index. The keys are kept in order
+    which means that we can iterate in key order, or only over the keys that start with a given prefix.
+
+    Inner nodes come in four sizes (4, 16, 48 and 256 children) and the smallest one that fits is used.
+
+    ## Concurrency
+    Readers never block. A write never changes a node that a reader can reach; instead, it copies the nodes on the
+    path from the root to the node that changes and then swaps in the new root. Writers are serialized using a mutex
+    (writes are far more expensive than reads anyway since they allocate the whole path). The nodes that are no
+    longer reachable are freed once no pinned reader can be looking at them. This also means that an iterator sees the
+    tree as it was when the iterator was created.
+
+    ---
+    References:
+    [1]: Viktor Leis, Alfons Kemper, and Thomas Neumann. 2013. The adaptive radix tree: ARTful indexing for main-memory
+    databases. In 2013 IEEE 29th International Conference on Data Engineering (ICDE), 38-49.
+    https://doi.org/10.1109/ICDE.2013.6544812
+*/
+
+#[cfg(test)]
+mod tests;
+
+use {
+    super::{meta::Comparable, mtchm::meta::TreeElement, IndexBaseSpec},
+    crate::engine::sync::atm::{cpin, Guard, ORD_ACQ, ORD_ACR, ORD_REL, ORD_RLX},
+    parking_lot::Mutex,
+    std::{
+        fmt,
+        marker::PhantomData,
+        ptr,
+        sync::atomic::{AtomicPtr, AtomicUsize},
+    },
+};
+
+/// A key that can be used with a [`RawArt`]. Keys are ordered by their bytes
+pub trait RadixKey {
+    /// Returns the bytes of this key
+    fn radix_key(&self) -> &[u8];
+}
+
+impl RadixKey for [u8] {
+    fn radix_key(&self) -> &[u8] {
+        self
+    }
+}
+
+impl RadixKey for str {
+    fn radix_key(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl RadixKey for String {
+    fn radix_key(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl RadixKey for Vec<u8> {
+    fn radix_key(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<T: RadixKey + ?Sized> RadixKey for Box<T> {
+    fn radix_key(&self) -> &[u8] {
+        T::radix_key(self)
+    }
+}
+
+/*
+    nodes
+*/
+
+type NodePtr<E> = *mut Node<E>;
+
+struct Node<E> {
+    /// the bytes between the parent's branch and this node. empty if the node has no children (the element's key
+    /// has these bytes)
+    prefix: Box<[u8]>,
+    /// the element whose key ends at this node
+    leaf: Option<E>,
+    children: Children<E>,
+}
+
+impl<E: TreeElement> Node<E>
+where
+    E::Key: RadixKey,
+{
+    /// Allocate a new node. A node without children must have an element, and its prefix is ignored
+    fn alloc(prefix: &[u8], leaf: Option<E>, children: Children<E>) -> NodePtr<E> {
+        debug_assert!(leaf.is_some() | !children.is_empty());
+        let prefix = if children.is_empty() {
+            Box::default()
+        } else {
+            prefix.into()
+        };
+        Box::into_raw(Box::new(Self {
+            prefix,
+            leaf,
+            children,
+        }))
+    }
+    /// Returns the prefix of this node, given that this node starts at `depth` in the key
+    fn prefix(&self, depth: usize) -> &[u8] {
+        match (&self.children, &self.leaf) {
+            (Children::Empty, Some(e)) => &e.key().radix_key()[depth..],
+            _ => &self.prefix,
+        }
+    }
+}
+
+enum Children<E> {
+    Empty,
+    N4(Box<Sorted<E, 4>>),
+    N16(Box<Sorted<E, 16>>),
+    N48(Box<Indexed<E>>),
+    N256(Box<Direct<E>>),
+}
+
+/// Children in key order
+struct Sorted<E, const N: usize> {
+    len: usize,
+    keys: [u8; N],
+    ptrs: [NodePtr<E>; N],
+}
+
+impl<E, const N: usize> Sorted<E, N> {
+    fn new(children: &[(u8, NodePtr<E>)]) -> Self {
+        let mut slf = Self {
+            len: children.len(),
+            keys: [0; N],
+            ptrs: [ptr::null_mut(); N],
+        };
+        for (i, (b, child)) in children.iter().enumerate() {
+            slf.keys[i] = *b;
+            slf.ptrs[i] = *child;
+        }
+        slf
+    }
+    fn get(&self, b: u8) -> NodePtr<E> {
+        match self.keys[..self.len].iter().position(|k| *k == b) {
+            Some(i) => self.ptrs[i],
+            None => ptr::null_mut(),
+        }
+    }
+    fn next(&self, cursor: usize) -> Option<(usize, u8, NodePtr<E>)> {
+        (cursor < self.len).then(|| (cursor + 1, self.keys[cursor], self.ptrs[cursor]))
+    }
+}
+
+/// Children looked up by a byte (holding the slot of the child, plus one)
+struct Indexed<E> {
+    len: usize,
+    slots: [u8; 256],
+    ptrs: [NodePtr<E>; 48],
+}
+
+impl<E> Indexed<E> {
+    fn new(children: &[(u8, NodePtr<E>)]) -> Self {
+        let mut slf = Self {
+            len: children.len(),
+            slots: [0; 256],
+            ptrs: [ptr::null_mut(); 48],
+        };
+        for (i, (b, child)) in children.iter().enumerate() {
+            slf.slots[*b as usize] = i as u8 + 1;
+            slf.ptrs[i] = *child;
+        }
+        slf
+    }
+    fn get(&self, b: u8) -> NodePtr<E> {
+        match self.slots[b as usize] {
+            0 => ptr::null_mut(),
+            slot => self.ptrs[slot as usize - 1],
+        }
+    }
+    fn next(&self, cursor: usize) -> Option<(usize, u8, NodePtr<E>)> {
+        (cursor..256)
+            .find(|b| self.slots[*b] != 0)
+            .map(|b| (b + 1, b as u8, self.ptrs[self.slots[b] as usize - 1]))
+    }
+}
+
+/// A child for every byte
+struct Direct<E> {
+    len: usize,
+    ptrs: [NodePtr<E>; 256],
+}
+
+impl<E> Direct<E> {
+    fn new(children: &[(u8, NodePtr<E>)]) -> Self {
+        let mut slf = Self {
+            len: children.len(),
+            ptrs: [ptr::null_mut(); 256],
+        };
+        for (b, child) in children {
+            slf.ptrs[*b as usize] = *child;
+        }
+        slf
+    }
+    fn get(&self, b: u8) -> NodePtr<E> {
+        self.ptrs[b as usize]
+    }
+    fn next(&self, cursor: usize) -> Option<(usize, u8, NodePtr<E>)> {
+        (cursor..256)
+            .find(|b| !self.ptrs[*b].is_null())
+            .map(|b| (b + 1, b as u8, self.ptrs[b]))
+    }
+}
+
+impl<E> Children<E> {
+    /// Use the smallest node that fits the given children (which must be in key order)
+    fn from_sorted(children: &[(u8, NodePtr<E>)]) -> Self {
+        debug_assert!(children.windows(2).all(|w| w[0].0 < w[1].0));
+        match children.len() {
+            0 => Self::Empty,
+            1..=4 => Self::N4(Box::new(Sorted::new(children))),
+            5..=16 => Self::N16(Box::new(Sorted::new(children))),
+            17..=48 => Self::N48(Box::new(Indexed::new(children))),
+            _ => Self::N256(Box::new(Direct::new(children))),
+        }
+    }
+    fn is_empty(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+    fn len(&self) -> usize {
+        match self {
+            Self::Empty => 0,
+            Self::N4(n) => n.len,
+            Self::N16(n) => n.len,
+            Self::N48(n) => n.len,
+            Self::N256(n) => n.len,
+        }
+    }
+    /// Returns the child for the given byte (or null)
+    fn get(&self, b: u8) -> NodePtr<E> {
+        match self {
+            Self::Empty => ptr::null_mut(),
+            Self::N4(n) => n.get(b),
+            Self::N16(n) => n.get(b),
+            Self::N48(n) => n.get(b),
+            Self::N256(n) => n.get(b),
+        }
+    }
+    /// Returns the first child at or after `cursor` (in key order), along with the cursor for the child after it
+    fn next(&self, cursor: usize) -> Option<(usize, u8, NodePtr<E>)> {
+        match self {
+            Self::Empty => None,
+            Self::N4(n) => n.next(cursor),
+            Self::N16(n) => n.next(cursor),
+            Self::N48(n) => n.next(cursor),
+            Self::N256(n) => n.next(cursor),
+        }
+    }
+    fn to_vec(&self) -> Vec<(u8, NodePtr<E>)> {
+        let mut children = Vec::with_capacity(self.len());
+        let mut cursor = 0;
+        while let Some((next, b, child)) = self.next(cursor) {
+            children.push((b, child));
+            cursor = next;
+        }
+        children
+    }
+    fn copy(&self) -> Self {
+        Self::from_sorted(&self.to_vec())
+    }
+    /// Returns a copy with the child for `b` set to `child` (or removed if `child` is null)
+    fn with(&self, b: u8, child: NodePtr<E>) -> Self {
+        let mut children = self.to_vec();
+        match children.binary_search_by_key(&b, |(k, _)| *k) {
+            Ok(i) if child.is_null() => {
+                children.remove(i);
+            }
+            Ok(i) => children[i].1 = child,
+            Err(i) if !child.is_null() => children.insert(i, (b, child)),
+            Err(_) => {}
+        }
+        Self::from_sorted(&children)
+    }
+}
+
+/*
+    tree
+*/
+
+/// A concurrent, ordered index for elements with byte string keys (see the notes above)
+pub struct RawArt<E: TreeElement> {
+    root: AtomicPtr<Node<E>>,
+    len: AtomicUsize,
+    /// held by writers
+    w: Mutex<()>,
+}
+
+// UNSAFE(@ohsayan): readers on any thread can get a ref to an element and elements are dropped by whichever thread
+// frees the node
+unsafe impl<E: TreeElement + Send + Sync> Send for RawArt<E> {}
+unsafe impl<E: TreeElement + Send + Sync> Sync for RawArt<E> {}
+
+impl<E: TreeElement> RawArt<E> {
+    /// Free the node and every node under it
+    ///
+    /// ## Safety
+    /// No one should be able to reach the node anymore
+    unsafe fn free_tree(root: NodePtr<E>) {
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if node.is_null() {
+                continue;
+            }
+            let node = Box::from_raw(node);
+            let mut cursor = 0;
+            while let Some((next, _, child)) = node.children.next(cursor) {
+                stack.push(child);
+                cursor = next;
+            }
+        }
+    }
+    /// Free the given nodes (but not their children) once every reader that could have seen them is done
+    fn retire(nodes: Vec<NodePtr<E>>, g: &Guard) {
+        if nodes.is_empty() {
+            return;
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): these are no longer reachable from the root, and a node that's reachable from the new
+            // root is never retired (we only retire the nodes that we copied)
+            g.defer_unchecked(move || nodes.into_iter().for_each(|node| drop(Box::from_raw(node))))
+        }
+    }
+}
+
+impl<E: TreeElement> IndexBaseSpec for RawArt<E>
+where
+    E::Key: RadixKey,
+{
+    const PREALLOC: bool = false;
+
+    #[cfg(debug_assertions)]
+    type Metrics = super::DummyMetrics;
+
+    fn idx_init() -> Self {
+        Self::new()
+    }
+
+    fn idx_init_with(s: Self) -> Self {
+        s
+    }
+
+    #[cfg(debug_assertions)]
+    fn idx_metrics(&self) -> &Self::Metrics {
+        &super::DummyMetrics
+    }
+}
+
+impl<E: TreeElement> RawArt<E>
+where
+    E::Key: RadixKey,
+{
+    fn new() -> Self {
+        Self {
+            root: AtomicPtr::new(ptr::null_mut()),
+            len: AtomicUsize::new(0),
+            w: Mutex::new(()),
+        }
+    }
+    fn find<'g, Q>(&self, key: &Q, _: &'g Guard) -> Option<&'g E>
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+    {
+        let bytes = key.radix_key();
+        let mut node = self.root.load(ORD_ACQ);
+        let mut depth = 0;
+        while !node.is_null() {
+            let n = unsafe {
+                // UNSAFE(@ohsayan): reachable from the root and we're pinned
+                &*node
+            };
+            let prefix = n.prefix(depth);
+            if !bytes[depth..].starts_with(prefix) {
+                return None;
+            }
+            depth += prefix.len();
+            if depth == bytes.len() {
+                return n.leaf.as_ref().filter(|e| key.cmp_eq(e.key()));
+            }
+            node = n.children.get(bytes[depth]);
+            depth += 1;
+        }
+        None
+    }
+    /// Insert the element, or replace the element with the same key if `upsert` is set. Returns false if the key
+    /// already exists and `upsert` isn't set
+    fn patch_insert(&self, e: E, upsert: bool, g: &Guard) -> bool {
+        let _w = self.w.lock();
+        let key: Box<[u8]> = e.key().radix_key().into();
+        let mut retired = vec![];
+        // the nodes that we go through, where they start in the key and the byte we take
+        let mut path = vec![];
+        let mut node = self.root.load(ORD_ACQ);
+        let mut depth = 0;
+        let mut replaced = false;
+        let mut new = loop {
+            if node.is_null() {
+                break Node::alloc(&[], Some(e), Children::Empty);
+            }
+            let n = unsafe {
+                // UNSAFE(@ohsayan): reachable from the root and only writers free nodes
+                &*node
+            };
+            let node_depth = depth;
+            let prefix = n.prefix(node_depth);
+            let common = prefix
+                .iter()
+                .zip(&key[depth..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if common < prefix.len() {
+                // the key ends or branches off inside this node's prefix, so split the node
+                let old = Node::alloc(&prefix[common + 1..], n.leaf.clone(), n.children.copy());
+                let (leaf, children) = if depth + common == key.len() {
+                    (Some(e), vec![(prefix[common], old)])
+                } else {
+                    let new = Node::alloc(&[], Some(e), Children::Empty);
+                    let mut children = vec![(prefix[common], old), (key[depth + common], new)];
+                    children.sort_unstable_by_key(|(b, _)| *b);
+                    (None, children)
+                };
+                retired.push(node);
+                break Node::alloc(&prefix[..common], leaf, Children::from_sorted(&children));
+            }
+            depth += common;
+            if depth == key.len() {
+                if n.leaf.is_some() & !upsert {
+                    return false;
+                }
+                replaced = n.leaf.is_some();
+                retired.push(node);
+                break Node::alloc(prefix, Some(e), n.children.copy());
+            }
+            path.push((node, node_depth, key[depth]));
+            node = n.children.get(key[depth]);
+            depth += 1;
+        };
+        // copy the path
+        while let Some((node, node_depth, b)) = path.pop() {
+            let n = unsafe {
+                // UNSAFE(@ohsayan): same as above
+                &*node
+            };
+            new = Node::alloc(
+                n.prefix(node_depth),
+                n.leaf.clone(),
+                n.children.with(b, new),
+            );
+            retired.push(node);
+        }
+        self.root.store(new, ORD_REL);
+        if !replaced {
+            self.len.fetch_add(1, ORD_RLX);
+        }
+        Self::retire(retired, g);
+        true
+    }
+    /// Remove the element with the given key, returning a ref to it (that's valid until the guard is dropped)
+    fn patch_delete<'g, Q>(&self, key: &Q, g: &'g Guard) -> Option<&'g E>
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+    {
+        let _w = self.w.lock();
+        let bytes = key.radix_key();
+        let mut path = vec![];
+        let mut node = self.root.load(ORD_ACQ);
+        let mut depth = 0;
+        let node_depth = loop {
+            if node.is_null() {
+                return None;
+            }
+            let n = unsafe {
+                // UNSAFE(@ohsayan): reachable from the root and only writers free nodes
+                &*node
+            };
+            let node_depth = depth;
+            let prefix = n.prefix(node_depth);
+            if !bytes[depth..].starts_with(prefix) {
+                return None;
+            }
+            depth += prefix.len();
+            if depth == bytes.len() {
+                break node_depth;
+            }
+            path.push((node, node_depth, bytes[depth]));
+            node = n.children.get(bytes[depth]);
+            depth += 1;
+        };
+        let n = unsafe {
+            // UNSAFE(@ohsayan): same as above. the node is only freed once the guard is dropped, so we can hand out a
+            // ref to its element
+            &*node
+        };
+        let removed = n.leaf.as_ref().filter(|e| key.cmp_eq(e.key()))?;
+        let mut retired = vec![node];
+        let mut new = Self::compact(
+            n.prefix(node_depth),
+            node_depth,
+            None,
+            n.children.copy(),
+            &mut retired,
+        );
+        // copy the path
+        while let Some((node, node_depth, b)) = path.pop() {
+            let n = unsafe {
+                // UNSAFE(@ohsayan): same as above
+                &*node
+            };
+            retired.push(node);
+            new = Self::compact(
+                n.prefix(node_depth),
+                node_depth,
+                n.leaf.clone(),
+                n.children.with(b, new),
+                &mut retired,
+            );
+        }
+        self.root.store(new, ORD_REL);
+        self.len.fetch_sub(1, ORD_RLX);
+        Self::retire(retired, g);
+        Some(removed)
+    }
+    /// Allocate a node at `depth`, but don't leave behind an empty node or a node that only leads to another node
+    fn compact(
+        prefix: &[u8],
+        depth: usize,
+        leaf: Option<E>,
+        children: Children<E>,
+        retired: &mut Vec<NodePtr<E>>,
+    ) -> NodePtr<E> {
+        match (leaf.is_some(), children.len()) {
+            (false, 0) => ptr::null_mut(),
+            (false, 1) => {
+                // merge with the only child
+                let (_, b, child) = children.next(0).unwrap();
+                let c = unsafe {
+                    // UNSAFE(@ohsayan): either reachable from the root or allocated by us
+                    &*child
+                };
+                let mut merged = prefix.to_vec();
+                merged.push(b);
+                merged.extend_from_slice(c.prefix(depth + prefix.len() + 1));
+                retired.push(child);
+                Node::alloc(&merged, c.leaf.clone(), c.children.copy())
+            }
+            _ => Node::alloc(prefix, leaf, children),
+        }
+    }
+}
+
+impl<E: TreeElement> RawArt<E>
+where
+    E::Key: RadixKey,
+{
+    /// Returns the number of elements
+    pub fn mt_len(&self) -> usize {
+        self.len.load(ORD_RLX)
+    }
+    /// Remove every element
+    pub fn mt_clear(&self, g: &Guard) {
+        let _w = self.w.lock();
+        let root = self.root.swap(ptr::null_mut(), ORD_ACR);
+        self.len.store(0, ORD_RLX);
+        if !root.is_null() {
+            unsafe {
+                // UNSAFE(@ohsayan): no longer reachable
+                g.defer_unchecked(move || Self::free_tree(root))
+            }
+        }
+    }
+    /// Returns true if the element was inserted; returns false if an element with the same key exists
+    pub fn mt_insert(&self, e: E, g: &Guard) -> bool {
+        self.patch_insert(e, false, g)
+    }
+    #[cfg(test)]
+    /// Insert the element, replacing the element with the same key (if any)
+    pub fn mt_upsert(&self, e: E, g: &Guard) {
+        let _ = self.patch_insert(e, true, g);
+    }
+    #[cfg(test)]
+    pub fn mt_contains<Q>(&self, key: &Q, g: &Guard) -> bool
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+    {
+        self.find(key, g).is_some()
+    }
+    pub fn mt_get<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E::Value>
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.find(key, g).map(TreeElement::val)
+    }
+    pub fn mt_get_element<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E>
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.find(key, g)
+    }
+    /// Returns true if the element was deleted
+    pub fn mt_delete<Q>(&self, key: &Q, g: &Guard) -> bool
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+    {
+        self.patch_delete(key, g).is_some()
+    }
+    pub fn mt_delete_return<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E::Value>
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.patch_delete(key, g).map(TreeElement::val)
+    }
+    pub fn mt_delete_return_entry<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E>
+    where
+        Q: ?Sized + RadixKey + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.patch_delete(key, g)
+    }
+    /// Iterate over every element, in key order
+    pub fn mt_iter_entry<'t, 'g, 'v>(&'t self, _: &'g Guard) -> IterEntry<'t, 'g, 'v, E> {
+        IterEntry::new(self.root.load(ORD_ACQ))
+    }
+    /// Iterate over the elements whose keys start with `prefix`, in key order
+    pub fn mt_iter_prefix<'t, 'g, 'v>(
+        &'t self,
+        prefix: &[u8],
+        _: &'g Guard,
+    ) -> IterEntry<'t, 'g, 'v, E> {
+        let mut node = self.root.load(ORD_ACQ);
+        let mut depth = 0;
+        while !node.is_null() {
+            let n = unsafe {
+                // UNSAFE(@ohsayan): reachable from the root and we're pinned
+                &*node
+            };
+            let node_prefix = n.prefix(depth);
+            let rest = &prefix[depth..];
+            if rest.len() <= node_prefix.len() {
+                // the prefix ends in this node, so either everything under it matches or nothing does
+                if node_prefix.starts_with(rest) {
+                    return IterEntry::new(node);
+                }
+                break;
+            }
+            if !rest.starts_with(node_prefix) {
+                break;
+            }
+            depth += node_prefix.len();
+            node = n.children.get(prefix[depth]);
+            depth += 1;
+        }
+        IterEntry::new(ptr::null_mut())
+    }
+}
+
+impl<E: TreeElement> Drop for RawArt<E> {
+    fn drop(&mut self) {
+        unsafe {
+            // UNSAFE(@ohsayan): we have exclusive access
+            Self::free_tree(*self.root.get_mut())
+        }
+    }
+}
+
+impl<E: TreeElement> fmt::Debug for RawArt<E>
+where
+    E::Key: RadixKey + fmt::Debug,
+    E::Value: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let g = cpin();
+        f.debug_map()
+            .entries(self.mt_iter_entry(&g).map(|e| (e.key(), e.val())))
+            .finish()
+    }
+}
+
+/// An iterator over the elements of a [`RawArt`], in key order
+pub struct IterEntry<'t, 'g, 'v, E: TreeElement> {
+    /// the nodes that we're in and a cursor for each (0 if we haven't looked at the node's element yet, else one
+    /// more than the cursor for its next child)
+    stack: Vec<(NodePtr<E>, usize)>,
+    _lt: PhantomData<(&'t RawArt<E>, &'g Guard, &'v E)>,
+}
+
+impl<'t, 'g, 'v, E: TreeElement> IterEntry<'t, 'g, 'v, E> {
+    fn new(root: NodePtr<E>) -> Self {
+        Self {
+            stack: if root.is_null() {
+                vec![]
+            } else {
+                vec![(root, 0)]
+            },
+            _lt: PhantomData,
+        }
+    }
+}
+
+impl<'t, 'g, 'v, E: TreeElement> Iterator for IterEntry<'t, 'g, 'v, E> {
+    type Item = &'v E;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, cursor) = self.stack.last_mut()?;
+            let n = unsafe {
+                // UNSAFE(@ohsayan): the guard keeps every node of the tree that we started with alive
+                &**node
+            };
+            if *cursor == 0 {
+                *cursor = 1;
+                if let Some(e) = n.leaf.as_ref() {
+                    return Some(e);
+                }
+            }
+            match n.children.next(*cursor - 1) {
+                Some((next, _, child)) => {
+                    *cursor = next + 1;
+                    self.stack.push((child, 0));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::RawArt,
+    crate::engine::{idx::IndexBaseSpec, sync::atm::cpin},
+    std::{sync::Arc, thread},
+};
+
+type Art = RawArt<(String, usize)>;
+
+const SPAM_QCOUNT: usize = if crate::util::IS_ON_CI {
+    1_024
+} else if cfg!(miri) {
+    32
+} else {
+    16_384
+};
+const SPAM_TENANTS: usize = if cfg!(miri) { 2 } else { 16 };
+
+fn keys(prefix: &str, count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("{prefix}{i}")).collect()
+}
+
+fn ordered<'a>(it: impl Iterator<Item = &'a (String, usize)>) -> Vec<&'a str> {
+    it.map(|(k, _)| k.as_str()).collect()
+}
+
+#[test]
+fn drop_empty() {
+    let idx = Art::idx_init();
+    drop(idx);
+}
+
+#[test]
+fn get_empty() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    assert!(idx.mt_get("hello", &g).is_none());
+    assert!(!idx.mt_delete("hello", &g));
+    assert_eq!(idx.mt_iter_entry(&g).count(), 0);
+}
+
+#[test]
+fn insert_get_delete() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    let keys = keys("https://example.com/path/", 1000);
+    for (i, key) in keys.iter().enumerate() {
+        assert!(idx.mt_insert((key.clone(), i), &g));
+    }
+    assert_eq!(idx.mt_len(), keys.len());
+    for (i, key) in keys.iter().enumerate() {
+        assert!(!idx.mt_insert((key.clone(), 0), &g));
+        assert_eq!(idx.mt_get(key.as_str(), &g).copied(), Some(i));
+    }
+    assert!(idx.mt_get("https://example.com/path/", &g).is_none());
+    assert!(idx.mt_get("https://example.com/path/1000", &g).is_none());
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(idx.mt_delete_return(key.as_str(), &g).copied(), Some(i));
+        assert!(!idx.mt_contains(key.as_str(), &g));
+    }
+    assert_eq!(idx.mt_len(), 0);
+    assert_eq!(idx.mt_iter_entry(&g).count(), 0);
+}
+
+#[test]
+fn keys_that_are_prefixes() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    for (i, key) in ["", "a", "ab", "abc", "abd", "b"].into_iter().enumerate() {
+        assert!(idx.mt_insert((key.into(), i), &g));
+    }
+    assert_eq!(idx.mt_get("", &g).copied(), Some(0));
+    assert_eq!(idx.mt_get("ab", &g).copied(), Some(2));
+    assert!(idx.mt_delete("ab", &g));
+    assert_eq!(idx.mt_get("abc", &g).copied(), Some(3));
+    assert!(idx.mt_delete("a", &g));
+    assert!(idx.mt_delete("", &g));
+    assert_eq!(ordered(idx.mt_iter_entry(&g)), ["abc", "abd", "b"]);
+}
+
+#[test]
+fn upsert() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    idx.mt_upsert(("hello".into(), 1), &g);
+    idx.mt_upsert(("hello".into(), 2), &g);
+    assert_eq!(idx.mt_len(), 1);
+    assert_eq!(idx.mt_get("hello", &g).copied(), Some(2));
+}
+
+#[test]
+fn iter_in_key_order() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    // enough children under the same node to go through every node size
+    let mut keys: Vec<String> = (0..128u8)
+        .rev()
+        .map(|b| format!("k{}", char::from(b)))
+        .collect();
+    keys.extend(["", "k", "kz/long/suffix", "a"].map(String::from));
+    for key in keys.iter() {
+        assert!(idx.mt_insert((key.clone(), 0), &g));
+    }
+    keys.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+    assert_eq!(ordered(idx.mt_iter_entry(&g)), keys);
+    // and then back down again
+    for key in keys.iter().filter(|k| k.len() == 2) {
+        assert!(idx.mt_delete(key.as_str(), &g));
+    }
+    assert_eq!(
+        ordered(idx.mt_iter_entry(&g)),
+        ["", "a", "k", "kz/long/suffix"]
+    );
+}
+
+#[test]
+fn iter_prefix() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    for key in [
+        "/usr/bin/ls",
+        "/usr/bin/cat",
+        "/usr/lib/libc.so",
+        "/usr",
+        "/var/log/syslog",
+    ] {
+        assert!(idx.mt_insert((key.into(), 0), &g));
+    }
+    assert_eq!(
+        ordered(idx.mt_iter_prefix(b"/usr/bin/", &g)),
+        ["/usr/bin/cat", "/usr/bin/ls"]
+    );
+    assert_eq!(
+        ordered(idx.mt_iter_prefix(b"/usr", &g)),
+        ["/usr", "/usr/bin/cat", "/usr/bin/ls", "/usr/lib/libc.so"]
+    );
+    // ends inside a node's prefix
+    assert_eq!(
+        ordered(idx.mt_iter_prefix(b"/var/l", &g)),
+        ["/var/log/syslog"]
+    );
+    assert_eq!(idx.mt_iter_prefix(b"/usr/sbin", &g).count(), 0);
+    assert_eq!(idx.mt_iter_prefix(b"/var/log/syslog.1", &g).count(), 0);
+    assert_eq!(idx.mt_iter_prefix(b"", &g).count(), 5);
+}
+
+#[test]
+fn clear() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    for key in keys("key", 100) {
+        assert!(idx.mt_insert((key, 0), &g));
+    }
+    idx.mt_clear(&g);
+    assert_eq!(idx.mt_len(), 0);
+    assert_eq!(idx.mt_iter_entry(&g).count(), 0);
+    assert!(idx.mt_insert(("key0".into(), 1), &g));
+    assert_eq!(idx.mt_get("key0", &g).copied(), Some(1));
+}
+
+#[test]
+fn iter_sees_snapshot() {
+    let idx = Art::idx_init();
+    let g = cpin();
+    for key in keys("key", 10) {
+        assert!(idx.mt_insert((key, 0), &g));
+    }
+    let it = idx.mt_iter_entry(&g);
+    for key in keys("key", 10) {
+        assert!(idx.mt_delete(key.as_str(), &g));
+    }
+    assert_eq!(it.count(), 10);
+    assert_eq!(idx.mt_iter_entry(&g).count(), 0);
+}
+
+#[test]
+fn multispam_insert_delete() {
+    let idx = Arc::new(Art::idx_init());
+    let keys = Arc::new(keys("https://skytable.io/docs/", SPAM_QCOUNT));
+    let chunk = SPAM_QCOUNT / SPAM_TENANTS;
+    let spawn = |f: fn(&Art, &[String])| {
+        (0..SPAM_TENANTS)
+            .map(|tid| {
+                let (idx, keys) = (idx.clone(), keys.clone());
+                thread::spawn(move || f(&idx, &keys[tid * chunk..(tid + 1) * chunk]))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|h| h.join().unwrap())
+    };
+    spawn(|idx, keys| {
+        let g = cpin();
+        for key in keys {
+            assert!(idx.mt_insert((key.clone(), key.len()), &g));
+        }
+    });
+    assert_eq!(idx.mt_len(), SPAM_QCOUNT);
+    spawn(|idx, keys| {
+        let g = cpin();
+        for key in keys {
+            assert_eq!(idx.mt_get(key.as_str(), &g).copied(), Some(key.len()));
+        }
+    });
+    spawn(|idx, keys| {
+        let g = cpin();
+        for key in keys {
+            assert!(idx.mt_delete(key.as_str(), &g));
+        }
+    });
+    assert_eq!(idx.mt_len(), 0);
+}
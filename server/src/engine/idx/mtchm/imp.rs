@@ -139,7 +139,7 @@ impl<E: TreeElement, C: Config> MTIndex<E, E::Key, E::Value> for Raw<E, C> {
     where
         Q: ?Sized + Comparable<E::Key>,
     {
-        self.contains_key(key, g)
+        self._lookup(super::access::RModeExists::new(key), g)
     }
 
     fn mt_get<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E::Value>
@@ -262,9 +262,17 @@ impl<T: TreeElement, C: Config> RawTree<T, C> {
 
 impl<T: TreeElement, C: Config> RawTree<T, C> {
     fn transactional_clear(&self, g: &Guard) {
-        self.iter_key(g).for_each(|k| {
-            let _ = self.remove(k, g);
-        });
+        // a remove can compress the branch we're iterating over (and we then skip its keys), so go again until a pass
+        // finds nothing to remove
+        loop {
+            let mut removed = false;
+            self.iter_key(g).for_each(|k| {
+                removed |= self.remove(k, g);
+            });
+            if !removed {
+                break;
+            }
+        }
     }
     fn patch<'g, P: patch::PatchWrite<T>>(&'g self, mut patch: P, g: &'g Guard) -> P::Ret<'g> {
         let hash = self.hash(patch.target());
@@ -436,9 +444,6 @@ impl<T: TreeElement, C: Config> RawTree<T, C> {
         }
     }
 
-    fn contains_key<'g, Q: ?Sized + Comparable<T::Key>>(&'g self, k: &Q, g: &'g Guard) -> bool {
-        self._lookup(access::RModeExists::new(k), g)
-    }
     fn get<'g, Q: ?Sized + Comparable<T::Key>>(
         &'g self,
         k: &Q,
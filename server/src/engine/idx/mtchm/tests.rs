@@ -109,6 +109,18 @@ fn update_empty() {
     assert!(!idx.mt_update((10, 20), &cpin()));
 }
 
+#[test]
+fn clear_full() {
+    let idx = Chm::<usize, usize>::idx_init();
+    let g = cpin();
+    for i in 0..1_000 {
+        assert!(idx.mt_insert((i, i), &g));
+    }
+    idx.mt_clear(&g);
+    assert_eq!(idx.mt_len(), 0);
+    assert!((0..1_000).all(|i| idx.mt_get(&i, &g).is_none()));
+}
+
 const SPAM_QCOUNT: usize = if crate::util::IS_ON_CI {
     1_024
 } else if cfg!(miri) {
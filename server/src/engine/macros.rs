@@ -206,6 +206,12 @@ macro_rules! Token {
     (primary) => {
         __kw_misc!(Primary)
     };
+    (index) => {
+        __kw_misc!(Index)
+    };
+    (on) => {
+        __kw_misc!(On)
+    };
     // ddl misc
     (with) => {
         __kw_misc!(With)
@@ -232,6 +238,9 @@ macro_rules! Token {
     (update) => {
         __kw_stmt!(Update)
     };
+    (upsert) => {
+        __kw_stmt!(Upsert)
+    };
     (delete) => {
         __kw_stmt!(Delete)
     };
@@ -242,12 +251,24 @@ macro_rules! Token {
     (limit) => {
         __kw_misc!(Limit)
     };
+    (order) => {
+        __kw_misc!(Order)
+    };
+    (group) => {
+        __kw_misc!(Group)
+    };
+    (truncate) => {
+        __kw_misc!(Truncate)
+    };
     (from) => {
         __kw_misc!(From)
     };
     (into) => {
         __kw_misc!(Into)
     };
+    (in) => {
+        __kw_misc!(In)
+    };
     (where) => {
         __kw_misc!(Where)
     };
@@ -263,6 +284,9 @@ macro_rules! Token {
     (by) => {
         __kw_misc!(By)
     };
+    (of) => {
+        __kw_misc!(Of)
+    };
     (asc) => {
         __kw_misc!(Asc)
     };
@@ -410,6 +434,7 @@ macro_rules! local_mut {
     }};
 }
 
+#[cfg(test)]
 macro_rules! local_ref {
     ($ident:ident, $call:expr) => {{
         #[inline(always)]
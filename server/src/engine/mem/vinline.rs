@@ -248,6 +248,16 @@ impl<const N: usize, T> VInline<N, T> {
     }
 }
 
+// the data is owned, just like a `Vec`
+unsafe impl<const N: usize, T: Send> Send for VInline<N, T> {}
+unsafe impl<const N: usize, T: Sync> Sync for VInline<N, T> {}
+
+impl<const N: usize, T> Default for VInline<N, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const N: usize, T> Deref for VInline<N, T> {
     type Target = [T];
     fn deref(&self) -> &Self::Target {
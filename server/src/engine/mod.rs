@@ -29,7 +29,10 @@ mod macros;
 pub mod config;
 mod core;
 mod data;
+pub mod embedded;
 mod error;
+#[cfg(any(test, feature = "fault-injection"))]
+pub mod fault;
 mod fractal;
 mod idx;
 mod mem;
@@ -48,13 +51,29 @@ use crate::engine::storage::SELoaded;
 
 use {
     self::{
-        config::{ConfigEndpoint, ConfigEndpointTls, ConfigMode, Configuration},
-        fractal::context::{self, Subsystem},
+        config::{ConfigEndpoint, ConfigEndpointAdditional, ConfigMode, Configuration},
+        fractal::{
+            context::{self, Subsystem},
+            Global,
+        },
+        net::notice::{self, Notice, NoticeKind},
+    },
+    crate::util::{
+        logger,
+        os::{self, TerminationSignal},
+    },
+    std::{
+        future::{self, Future},
+        sync::Arc,
+        task::Poll,
+        time::Duration,
     },
-    crate::util::os::TerminationSignal,
     tokio::sync::broadcast,
 };
 
+/// How long we give connections to push the shutdown notice out before we close them
+const SHUTDOWN_NOTICE_GRACE: Duration = Duration::from_millis(500);
+
 pub(super) fn set_context_init(msg: &'static str) {
     context::set(Subsystem::Init, msg)
 }
@@ -66,9 +85,40 @@ pub fn load_all(
     config: Configuration,
 ) -> RuntimeResult<(Configuration, fractal::GlobalStateStart)> {
     // load configuration
+    if let Some(file) = config.logging.file.as_deref() {
+        info!("logging to {file}");
+        logger::log_to_file(
+            file,
+            logger::Rotation {
+                max_size: config.logging.max_size,
+                max_age: config.logging.rotate_interval.map(Duration::from_secs),
+                compress: config.logging.compress,
+                retain: config.logging.retain as usize,
+            },
+        )?;
+    }
     if config.mode == ConfigMode::Dev {
         warn!("running in dev mode");
     }
+    self::core::kdf::set_kdf(config.auth.kdf);
+    self::core::model::delta::set_delta_batches(config.system.delta_batches);
+    self::core::query_mem::set_limits(
+        config.system.query_memory_limit,
+        config.system.query_memory_global_limit,
+    );
+    self::core::quota::set_limits(
+        config.system.user_write_ops_limit,
+        config.system.user_write_bytes_limit,
+    );
+    self::core::row_size::set_limits(config.system.row_size_limit, config.system.field_size_limit);
+    self::core::model::trash::set_trash_retention(config.ddl.trash_retention);
+    if let Some(size) = config.system.journal_prealloc {
+        storage::safe_interfaces::set_prealloc_chunk_size(size);
+    }
+    if let Some(endpoint) = config.system.otlp_endpoint.as_deref() {
+        self::core::trace::init(endpoint)?;
+        info!("exporting statement traces to {endpoint}");
+    }
     info!("starting storage engine");
     context::set_origin(Subsystem::Storage);
     let SELoaded { gns } = storage::load(&config)?;
@@ -80,43 +130,161 @@ pub fn load_all(
     Ok((config, global))
 }
 
-enum EndpointListeners {
-    Insecure(net::Listener),
-    Secure {
-        listener: net::Listener,
-        ssl: openssl::ssl::SslAcceptor,
-    },
-    Multi {
-        tcp: net::Listener,
-        tls: net::Listener,
-        ssl: openssl::ssl::SslAcceptor,
-    },
+/// An endpoint that we're listening on
+enum EndpointListener {
+    Tcp(net::Listener),
+    Tls(net::Listener, Arc<net::tls::TlsAcceptor>),
+    #[cfg(unix)]
+    Uds(net::uds::UdsListener),
 }
 
-impl EndpointListeners {
+impl EndpointListener {
     async fn listen(&mut self) {
         match self {
-            Self::Insecure(l) => l.listen_tcp().await,
-            Self::Secure { listener, ssl } => listener.listen_tls(ssl).await,
-            Self::Multi { tcp, tls, ssl } => {
-                tokio::join!(tcp.listen_tcp(), tls.listen_tls(ssl));
-            }
+            Self::Tcp(l) => l.listen_tcp().await,
+            Self::Tls(l, ssl) => l.listen_tls(ssl).await,
+            #[cfg(unix)]
+            Self::Uds(l) => l.listen().await,
         }
     }
     async fn finish(self) {
         match self {
-            Self::Insecure(l) | Self::Secure { listener: l, .. } => l.terminate().await,
-            Self::Multi { tcp, tls, .. } => {
-                tokio::join!(tcp.terminate(), tls.terminate());
+            Self::Tcp(l) | Self::Tls(l, _) => l.terminate().await,
+            #[cfg(unix)]
+            Self::Uds(l) => l.terminate().await,
+        }
+    }
+}
+
+/// All the endpoints that we're listening on. The TLS endpoints share one acceptor (and so, one certificate)
+struct EndpointListeners {
+    endpoints: Vec<EndpointListener>,
+    ssl: Option<Arc<net::tls::TlsAcceptor>>,
+}
+
+impl EndpointListeners {
+    /// Bind to all the endpoints. Returns the listeners and a description of the endpoints
+    async fn bind(
+        endpoints: &ConfigEndpoint,
+        additional: &[ConfigEndpointAdditional],
+        tcp_keepalive: Option<u64>,
+        global: &Global,
+        signal: &broadcast::Sender<()>,
+    ) -> RuntimeResult<(Self, String)> {
+        let (insecure, secure) = match endpoints {
+            ConfigEndpoint::Insecure(tcp) => (Some(tcp), None),
+            ConfigEndpoint::Secure(tls) => (None, Some(tls)),
+            ConfigEndpoint::Multi(tcp, tls) => (Some(tcp), Some(tls)),
+        };
+        let mut listeners = vec![];
+        let mut names = vec![];
+        if let Some(tcp) = insecure {
+            let listener =
+                net::Listener::new_cfg(tcp, tcp_keepalive, global.clone(), signal.clone()).await?;
+            listeners.push(EndpointListener::Tcp(listener));
+            names.push(format!("tcp@{tcp}"));
+        }
+        let ssl = match secure {
+            Some(tls) => {
+                let listener = net::Listener::new_cfg(
+                    tls.tcp(),
+                    tcp_keepalive,
+                    global.clone(),
+                    signal.clone(),
+                )
+                .await?;
+                context::set_dmsg("initializing TLS");
+                let ssl = Arc::new(net::tls::TlsAcceptor::new(tls)?);
+                listeners.push(EndpointListener::Tls(listener, ssl.clone()));
+                names.push(format!("tls@{}", tls.tcp()));
+                Some(ssl)
             }
+            None => None,
+        };
+        for ep in additional {
+            let listener = match ep {
+                ConfigEndpointAdditional::Tcp(tcp) => EndpointListener::Tcp(
+                    net::Listener::new_cfg(tcp, tcp_keepalive, global.clone(), signal.clone())
+                        .await?,
+                ),
+                ConfigEndpointAdditional::Tls(tcp) => EndpointListener::Tls(
+                    net::Listener::new_cfg(tcp, tcp_keepalive, global.clone(), signal.clone())
+                        .await?,
+                    // the configuration only allows more TLS endpoints if there is a main one
+                    ssl.clone().unwrap(),
+                ),
+                #[cfg(unix)]
+                ConfigEndpointAdditional::Uds(uds) => EndpointListener::Uds(
+                    net::uds::UdsListener::new(uds, global.clone(), signal.clone())?,
+                ),
+                #[cfg(not(unix))]
+                ConfigEndpointAdditional::Uds(_) => {
+                    unreachable!("the configuration doesn't allow UDS endpoints on this platform")
+                }
+            };
+            listeners.push(listener);
+            names.push(ep.to_string());
         }
+        let description = match names.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+            None => unreachable!("there is always a main endpoint"),
+        };
+        Ok((
+            Self {
+                endpoints: listeners,
+                ssl,
+            },
+            description,
+        ))
+    }
+    async fn listen(&mut self) {
+        let Self { endpoints, ssl } = self;
+        let watch = async {
+            if let Some(ssl) = ssl {
+                ssl.watch().await
+            }
+        };
+        tokio::join!(
+            join_all(endpoints.iter_mut().map(EndpointListener::listen)),
+            watch
+        );
+    }
+    async fn finish(self) {
+        join_all(self.endpoints.into_iter().map(EndpointListener::finish)).await
     }
 }
 
+/// Run all the futures (on this task) until every one of them is done
+async fn join_all<F: Future<Output = ()>>(futures: impl Iterator<Item = F>) {
+    let mut futures: Vec<_> = futures.map(|f| Some(Box::pin(f))).collect();
+    future::poll_fn(|cx| {
+        let mut done = true;
+        for slot in futures.iter_mut() {
+            if let Some(f) = slot {
+                if f.as_mut().poll(cx).is_ready() {
+                    *slot = None;
+                } else {
+                    done = false;
+                }
+            }
+        }
+        if done {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
 pub async fn start(
     termsig: TerminationSignal,
     Configuration {
-        endpoints, system, ..
+        endpoints,
+        additional_endpoints,
+        system,
+        ..
     }: Configuration,
     fractal::GlobalStateStart { global, boot }: fractal::GlobalStateStart,
 ) -> RuntimeResult<()> {
@@ -127,56 +295,47 @@ pub async fn start(
     let fractal_handle = boot.boot(&signal, system.reliability_system_window);
     // create our server
     context::set(Subsystem::Network, "initializing endpoints");
-    let str;
-    let mut endpoint_handles = match &endpoints {
-        ConfigEndpoint::Secure(ConfigEndpointTls { tcp, .. }) | ConfigEndpoint::Insecure(tcp) => {
-            let listener =
-                net::Listener::new(tcp.host(), tcp.port(), global.clone(), signal.clone()).await?;
-            if let ConfigEndpoint::Secure(s) = endpoints {
-                context::set_dmsg("initializing TLS");
-                let acceptor = net::Listener::init_tls(s.cert(), s.private_key(), s.pkey_pass())?;
-                str = format!("listening on tls@{}:{}", s.tcp().host(), s.tcp().port());
-                EndpointListeners::Secure {
-                    listener,
-                    ssl: acceptor,
-                }
-            } else {
-                str = format!("listening on tcp@{}:{}", tcp.host(), tcp.port());
-                EndpointListeners::Insecure(listener)
-            }
-        }
-        ConfigEndpoint::Multi(insecure_ep, secure_ep) => {
-            let tcp_listener =
-                net::Listener::new_cfg(insecure_ep, global.clone(), signal.clone()).await?;
-            let tls_listener =
-                net::Listener::new_cfg(secure_ep.tcp(), global.clone(), signal.clone()).await?;
-            context::set_dmsg("initializing TLS");
-            let acceptor = net::Listener::init_tls(
-                secure_ep.cert(),
-                secure_ep.private_key(),
-                secure_ep.pkey_pass(),
-            )?;
-            str = format!(
-                "listening on tcp@{}:{} and tls@{}:{}",
-                insecure_ep.host(),
-                insecure_ep.port(),
-                secure_ep.tcp().host(),
-                secure_ep.tcp().port()
-            );
-            EndpointListeners::Multi {
-                tcp: tcp_listener,
-                tls: tls_listener,
-                ssl: acceptor,
-            }
-        }
-    };
+    let (mut endpoint_handles, endpoint_names) = EndpointListeners::bind(
+        &endpoints,
+        &additional_endpoints,
+        system.tcp_keepalive,
+        &global,
+        &signal,
+    )
+    .await?;
+    let str = format!("listening on {endpoint_names}");
     info!("{str}");
+    // all data has been restored and we're listening, so tell the service manager that we're ready (if it cares)
+    if os::sd_notify(&format!("READY=1\nSTATUS={str}")) {
+        info!("notified service manager");
+    }
+    let watchdog = os::sd_watchdog_interval().map(|interval| {
+        info!("service manager watchdog enabled. pinging every {interval:?}");
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                os::sd_notify("WATCHDOG=1");
+            }
+        })
+    });
     tokio::select! {
         _ = endpoint_handles.listen() => {}
         _ = termsig => {
             info!("received terminate signal. waiting for inflight tasks to complete ...");
         }
     }
+    os::sd_notify("STOPPING=1");
+    if let Some(watchdog) = watchdog {
+        watchdog.abort();
+    }
+    if notice::post(Notice::new(
+        NoticeKind::Shutdown,
+        "the server is shutting down",
+    )) != 0
+    {
+        tokio::time::sleep(SHUTDOWN_NOTICE_GRACE).await;
+    }
     drop(signal);
     endpoint_handles.finish().await;
     info!("waiting for fractal engine to exit ...");
@@ -192,7 +351,184 @@ pub async fn start(
     Ok(())
 }
 
+/// Run `skyd check`: validate a data directory offline and print a JSON report. Returns the process
+/// exit code
+pub fn check_data_dir(args: &[String]) -> i32 {
+    let mut data_dir = ".";
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data-dir" => match args.next() {
+                Some(dir) => data_dir = dir,
+                None => {
+                    eprintln!("error: missing value for `--data-dir`");
+                    return 0x02;
+                }
+            },
+            arg if arg.starts_with("--data-dir=") => data_dir = &arg["--data-dir=".len()..],
+            arg => {
+                eprintln!("error: unknown argument `{arg}` for `skyd check`");
+                return 0x02;
+            }
+        }
+    }
+    if let Err(e) = std::env::set_current_dir(data_dir) {
+        eprintln!("error: failed to open data directory `{data_dir}`: {e}");
+        return 0x02;
+    }
+    let report = storage::check::check_data_dir();
+    println!("{}", report.to_json());
+    if report.is_ok() {
+        0x00
+    } else {
+        0x01
+    }
+}
+
+/// Run `skyd decode-gns`: print every event in a GNS journal as a JSON object (one per line). Returns the process
+/// exit code
+pub fn decode_gns_command(args: &[String]) -> i32 {
+    let [file] = args else {
+        eprintln!("usage: skyd decode-gns <file>");
+        return 0x02;
+    };
+    if !std::path::Path::new(file).is_file() {
+        eprintln!("error: `{file}` is not a file");
+        return 0x02;
+    }
+    let r = storage::decode::decode_gns(file, |event| println!("{}", event.to_json()));
+    match r {
+        Ok(()) => 0x00,
+        Err(e) => {
+            eprintln!("error: failed to read `{file}` any further: {e}");
+            0x01
+        }
+    }
+}
+
+/// Run `skyd config`: validate a configuration file or print the default configuration. Returns the
+/// process exit code
+pub fn config_command(args: &[String]) -> i32 {
+    match args {
+        [cmd, file] if cmd == "validate" => {
+            let src = match std::fs::read_to_string(file) {
+                Ok(src) => src,
+                Err(e) => {
+                    eprintln!("error: failed to read configuration file `{file}`: {e}");
+                    return 0x02;
+                }
+            };
+            let issues = config::validate_config_file(&src);
+            if issues.is_empty() {
+                println!("{file}: configuration is valid");
+                0x00
+            } else {
+                for issue in issues {
+                    eprintln!("{file}: {issue}");
+                }
+                0x01
+            }
+        }
+        [cmd] if cmd == "defaults" => {
+            print!("{}", config::default_config_file());
+            0x00
+        }
+        _ => {
+            eprintln!("usage: skyd config validate <file>\n       skyd config defaults");
+            0x02
+        }
+    }
+}
+
+/// Run `skyd replay`: copy the data directory into a scratch directory with its journals cut off at the given
+/// events, and return the configuration to start a read-only server over the copy. On failure, the process exit code
+/// is returned instead
+pub fn replay_command(args: &[String]) -> Result<config::Configuration, i32> {
+    let mut data_dir = ".";
+    let mut scratch = None;
+    let mut point = storage::replay::ReplayPoint::default();
+    let mut server_args = vec!["skyd".to_owned()];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (key, value) = match arg.split_once('=') {
+            _ if arg == "--" => {
+                server_args.extend(args.by_ref().cloned());
+                break;
+            }
+            Some((key, value)) => (key, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        if !matches!(key, "--data-dir" | "--scratch" | "--until" | "--model") {
+            eprintln!("error: unknown argument `{arg}` for `skyd replay`");
+            return Err(0x02);
+        }
+        let Some(value) = value.or_else(|| args.next().map(String::as_str)) else {
+            eprintln!("error: missing value for `{key}`");
+            return Err(0x02);
+        };
+        match key {
+            "--data-dir" => data_dir = value,
+            "--scratch" => scratch = Some(value),
+            "--until" => match value.parse() {
+                Ok(until) => point.gns_until = Some(until),
+                Err(_) => {
+                    eprintln!("error: invalid event `{value}` for `--until`");
+                    return Err(0x02);
+                }
+            },
+            _ => match value.rsplit_once('=').map(|(m, e)| (m, e.parse())) {
+                Some((model, Ok(until))) => point.models.push((model.to_owned(), until)),
+                _ => {
+                    eprintln!("error: invalid value `{value}` for `--model`. expected `<space>.<model>=<event>`");
+                    return Err(0x02);
+                }
+            },
+        }
+    }
+    let Some(scratch) = scratch else {
+        eprintln!("error: `skyd replay` needs a `--scratch` directory");
+        return Err(0x02);
+    };
+    // load the configuration first, since it may refer to files relative to the current directory
+    let config = match config::check_configuration_from(server_args) {
+        Ok(config::ConfigReturn::Config(cfg)) => *cfg,
+        Ok(config::ConfigReturn::HelpMessage(msg)) => {
+            eprintln!("{msg}");
+            return Err(0x00);
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            return Err(0x02);
+        }
+    };
+    let scratch = match std::env::current_dir() {
+        Ok(cwd) => cwd.join(scratch),
+        Err(e) => {
+            eprintln!("error: failed to get current directory: {e}");
+            return Err(0x02);
+        }
+    };
+    if let Err(e) = std::env::set_current_dir(data_dir) {
+        eprintln!("error: failed to open data directory `{data_dir}`: {e}");
+        return Err(0x02);
+    }
+    match storage::replay::replay_into(&scratch.to_string_lossy(), &point) {
+        Ok(()) => {
+            info!(
+                "starting read-only server over replayed data in `{}`",
+                scratch.display()
+            );
+            Ok(config)
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            Err(0x01)
+        }
+    }
+}
+
 pub fn finish(g: fractal::Global) {
+    self::core::trace::shutdown();
     unsafe {
         // UNSAFE(@ohsayan): the only thing we do before exit
         g.unload_all();
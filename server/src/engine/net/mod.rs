@@ -24,7 +24,12 @@
  *
 */
 
+pub mod notice;
 pub mod protocol;
+mod proxy;
+pub mod tls;
+#[cfg(unix)]
+pub mod uds;
 
 use {
     crate::engine::{
@@ -34,7 +39,6 @@ use {
     bytes::BytesMut,
     openssl::{
         pkey::PKey,
-        ssl::Ssl,
         ssl::{SslAcceptor, SslMethod},
         x509::X509,
     },
@@ -53,6 +57,7 @@ pub type IoResult<T> = Result<T, std::io::Error>;
 const BUF_WRITE_CAP: usize = 16384;
 const BUF_READ_CAP: usize = 16384;
 const CLIMIT: usize = 50000;
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
 
 static CLIM: Semaphore = Semaphore::const_new(CLIMIT);
 
@@ -68,6 +73,8 @@ enum QueryLoopResult {
 
 impl Socket for TcpStream {}
 impl Socket for SslStream<TcpStream> {}
+#[cfg(unix)]
+impl Socket for tokio::net::UnixStream {}
 
 struct NetBackoff {
     at: Cell<u8>,
@@ -100,6 +107,8 @@ pub struct ConnectionHandler<S> {
     socket: BufWriter<S>,
     buffer: BytesMut,
     global: Global,
+    /// if unset, the client doesn't need a password
+    auth: bool,
     sig_terminate: broadcast::Receiver<()>,
     _sig_inflight_complete: mpsc::Sender<()>,
 }
@@ -108,6 +117,7 @@ impl<S: Socket> ConnectionHandler<S> {
     pub fn new(
         socket: S,
         global: Global,
+        auth: bool,
         term_sig: broadcast::Receiver<()>,
         _inflight_complete: mpsc::Sender<()>,
     ) -> Self {
@@ -115,6 +125,7 @@ impl<S: Socket> ConnectionHandler<S> {
             socket: BufWriter::with_capacity(BUF_WRITE_CAP, socket),
             buffer: BytesMut::with_capacity(BUF_READ_CAP),
             global,
+            auth,
             sig_terminate: term_sig,
             _sig_inflight_complete: _inflight_complete,
         }
@@ -124,11 +135,12 @@ impl<S: Socket> ConnectionHandler<S> {
             socket,
             buffer,
             global,
+            auth,
             ..
         } = self;
         loop {
             tokio::select! {
-                ret = protocol::query_loop(socket, buffer, global) => {
+                ret = protocol::query_loop(socket, buffer, global, *auth) => {
                     socket.flush().await?;
                     match ret {
                         Ok(QueryLoopResult::Fin) => return Ok(()),
@@ -153,6 +165,8 @@ impl<S: Socket> ConnectionHandler<S> {
 pub struct Listener {
     global: Global,
     listener: TcpListener,
+    tcp_keepalive: Option<Duration>,
+    proxy_protocol: bool,
     sig_shutdown: broadcast::Sender<()>,
     sig_inflight: mpsc::Sender<()>,
     sig_inflight_wait: mpsc::Receiver<()>,
@@ -161,14 +175,25 @@ pub struct Listener {
 impl Listener {
     pub async fn new_cfg(
         tcp: &ConfigEndpointTcp,
+        tcp_keepalive: Option<u64>,
         global: Global,
         sig_shutdown: broadcast::Sender<()>,
     ) -> RuntimeResult<Self> {
-        Self::new(tcp.host(), tcp.port(), global, sig_shutdown).await
+        Self::new(
+            tcp.host(),
+            tcp.port(),
+            tcp_keepalive,
+            tcp.proxy_protocol(),
+            global,
+            sig_shutdown,
+        )
+        .await
     }
     pub async fn new(
         host: &str,
         port: u16,
+        tcp_keepalive: Option<u64>,
+        proxy_protocol: bool,
         global: Global,
         sig_shutdown: broadcast::Sender<()>,
     ) -> RuntimeResult<Self> {
@@ -179,6 +204,8 @@ impl Listener {
         Ok(Self {
             global,
             listener,
+            tcp_keepalive: tcp_keepalive.map(Duration::from_secs),
+            proxy_protocol,
             sig_shutdown,
             sig_inflight,
             sig_inflight_wait,
@@ -195,11 +222,23 @@ impl Listener {
         drop(sig_inflight); // could be that we are the only ones holding this lol
         let _ = sig_inflight_wait.recv().await; // wait
     }
+    fn set_keepalive(&self, stream: &TcpStream) {
+        if let Some(idle) = self.tcp_keepalive {
+            let keepalive = socket2::TcpKeepalive::new().with_time(idle);
+            if let Err(e) = socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive) {
+                // not fatal; the connection will just not be probed by the OS
+                warn!("failed to enable TCP keepalive on accepted socket: `{e}`");
+            }
+        }
+    }
     async fn accept(&mut self) -> IoResult<(TcpStream, SocketAddr)> {
         let backoff = NetBackoff::new();
         loop {
             match self.listener.accept().await {
-                Ok(s) => return Ok(s),
+                Ok(s) => {
+                    self.set_keepalive(&s.0);
+                    return Ok(s);
+                }
                 Err(e) => {
                     if backoff.should_disconnect() {
                         // that's enough of your crappy connection dear sir
@@ -210,11 +249,31 @@ impl Listener {
             backoff.spin().await;
         }
     }
+    /// Get the address of the client on a freshly accepted connection. If this endpoint sits behind a proxy, we read
+    /// the PROXY header and return the address of the actual client. This runs in the connection's own task so that
+    /// a client that is slow to send the header doesn't hold up the accept loop
+    async fn client_addr(
+        proxy_protocol: bool,
+        stream: &mut TcpStream,
+        addr: SocketAddr,
+    ) -> IoResult<SocketAddr> {
+        if !proxy_protocol {
+            return Ok(addr);
+        }
+        match tokio::time::timeout(PROXY_HEADER_TIMEOUT, proxy::read_v2_header(stream)).await {
+            Ok(Ok(client)) => Ok(client.unwrap_or(addr)),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "timed out waiting for PROXY header",
+            )),
+        }
+    }
     pub async fn listen_tcp(&mut self) {
         loop {
             // acquire a permit
             let permit = CLIM.acquire().await.unwrap();
-            let (stream, _) = match self.accept().await {
+            let (mut stream, addr) = match self.accept().await {
                 Ok(s) => s,
                 Err(e) => {
                     /*
@@ -224,15 +283,25 @@ impl Listener {
                     continue;
                 }
             };
-            let mut handler = ConnectionHandler::new(
-                stream,
-                self.global.clone(),
-                self.sig_shutdown.subscribe(),
-                self.sig_inflight.clone(),
-            );
+            let proxy_protocol = self.proxy_protocol;
+            let global = self.global.clone();
+            let sig_shutdown = self.sig_shutdown.subscribe();
+            let sig_inflight = self.sig_inflight.clone();
             tokio::spawn(async move {
+                let addr = match Self::client_addr(proxy_protocol, &mut stream, addr).await {
+                    Ok(addr) => addr,
+                    Err(e) => {
+                        /*
+                            SECURITY: IGNORE THIS ERROR
+                        */
+                        warn!("failed to accept connection on TCP socket: `{e}`");
+                        return;
+                    }
+                };
+                let mut handler =
+                    ConnectionHandler::new(stream, global, true, sig_shutdown, sig_inflight);
                 if let Err(e) = handler.run().await {
-                    warn!("error handling client connection: `{e}`");
+                    warn!("error handling client connection from `{addr}`: `{e}`");
                 }
             });
             // return the permit
@@ -259,16 +328,16 @@ impl Listener {
         let acceptor = build_acceptor().set_dmsg("failed to initialize TLS socket")?;
         Ok(acceptor)
     }
-    pub async fn listen_tls(&mut self, acceptor: &SslAcceptor) {
+    /// Accept TLS connections. The acceptor is shared by all the TLS endpoints, so it is watched for certificate changes
+    /// by whoever owns it (see [`tls::TlsAcceptor::watch`])
+    pub async fn listen_tls(&mut self, acceptor: &tls::TlsAcceptor) {
         loop {
-            let stream = async {
-                let (stream, _) = self.accept().await?;
-                let ssl = Ssl::new(acceptor.context())?;
-                let mut stream = SslStream::new(ssl, stream)?;
-                Pin::new(&mut stream).accept().await?;
-                RuntimeResult::Ok(stream)
+            let accepted = async {
+                let (stream, addr) = self.accept().await?;
+                let ssl = acceptor.new_ssl()?;
+                RuntimeResult::Ok((stream, addr, ssl))
             };
-            let stream = match stream.await {
+            let (mut stream, addr, ssl) = match accepted.await {
                 Ok(s) => s,
                 Err(e) => {
                     /*
@@ -278,15 +347,32 @@ impl Listener {
                     continue;
                 }
             };
-            let mut handler = ConnectionHandler::new(
-                stream,
-                self.global.clone(),
-                self.sig_shutdown.subscribe(),
-                self.sig_inflight.clone(),
-            );
+            let proxy_protocol = self.proxy_protocol;
+            let global = self.global.clone();
+            let sig_shutdown = self.sig_shutdown.subscribe();
+            let sig_inflight = self.sig_inflight.clone();
             tokio::spawn(async move {
+                // the PROXY header (if any) comes in before the TLS handshake
+                let stream = async {
+                    let addr = Self::client_addr(proxy_protocol, &mut stream, addr).await?;
+                    let mut stream = SslStream::new(ssl, stream)?;
+                    Pin::new(&mut stream).accept().await?;
+                    RuntimeResult::Ok((stream, addr))
+                };
+                let (stream, addr) = match stream.await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        /*
+                            SECURITY: Once again, ignore this error
+                        */
+                        warn!("failed to accept connection on TLS socket: `{e}`");
+                        return;
+                    }
+                };
+                let mut handler =
+                    ConnectionHandler::new(stream, global, true, sig_shutdown, sig_inflight);
                 if let Err(e) = handler.run().await {
-                    warn!("error handling client TLS connection: `{e}`");
+                    warn!("error handling client TLS connection from `{addr}`: `{e}`");
                 }
             });
         }
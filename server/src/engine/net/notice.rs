@@ -0,0 +1,123 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    server notices
+    ---
+    notices are pushed by the server on authenticated connections that handshake with the notice exchange mode,
+    outside of the request/response cycle. a notice is only written while the connection is idle (i.e. not in the
+    middle of a response), so clients only need to check for a notice frame before reading a response.
+
+    frame: [0x14][kind: u8][message length]\n[message]
+*/
+
+use {
+    super::protocol::ResponseType,
+    crate::engine::mem::IntegerRepr,
+    std::sync::{Arc, OnceLock},
+    tokio::sync::broadcast,
+};
+
+/// The number of notices that a slow connection can fall behind by before it starts missing them
+const NOTICE_BACKLOG: usize = 64;
+
+static NOTICES: OnceLock<broadcast::Sender<Arc<Notice>>> = OnceLock::new();
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoticeKind {
+    /// The server is shutting down
+    Shutdown = 0,
+    /// A message from an administrator
+    Message = 1,
+    /// The server's operating mode changed (for example, it was made read-only)
+    ModeChanged = 2,
+    /// The account of the receiving user was changed or removed
+    AccountChanged = 3,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notice {
+    kind: NoticeKind,
+    /// if set, only connections authenticated as this user get the notice
+    user: Option<Box<str>>,
+    message: Box<str>,
+}
+
+impl Notice {
+    /// A notice for all connections
+    pub fn new(kind: NoticeKind, message: impl Into<Box<str>>) -> Self {
+        Self {
+            kind,
+            user: None,
+            message: message.into(),
+        }
+    }
+    /// A notice for connections authenticated as `user`
+    pub fn for_user(kind: NoticeKind, user: &str, message: impl Into<Box<str>>) -> Self {
+        Self {
+            kind,
+            user: Some(user.into()),
+            message: message.into(),
+        }
+    }
+    pub fn is_for(&self, username: &str) -> bool {
+        self.user.as_deref().is_none_or(|user| user == username)
+    }
+    /// Encode the notice frame
+    pub fn encode(&self) -> Vec<u8> {
+        let mut frame = vec![ResponseType::Notice.value_u8(), self.kind as u8];
+        frame.extend(IntegerRepr::new().as_bytes(self.message.len() as u64));
+        frame.push(b'\n');
+        frame.extend(self.message.as_bytes());
+        frame
+    }
+}
+
+fn channel() -> &'static broadcast::Sender<Arc<Notice>> {
+    NOTICES.get_or_init(|| broadcast::channel(NOTICE_BACKLOG).0)
+}
+
+/// Post a notice. Returns the number of connections that will see it (before filtering by user)
+pub fn post(notice: Notice) -> usize {
+    // no connections is not an error
+    channel().send(Arc::new(notice)).unwrap_or(0)
+}
+
+/// Subscribe to all notices posted from now on
+pub fn subscribe() -> broadcast::Receiver<Arc<Notice>> {
+    channel().subscribe()
+}
+
+/// Wait for the next notice. A connection without a subscription never gets one
+pub async fn next(
+    notices: &mut Option<broadcast::Receiver<Arc<Notice>>>,
+) -> Result<Arc<Notice>, broadcast::error::RecvError> {
+    match notices {
+        Some(notices) => notices.recv().await,
+        None => std::future::pending().await,
+    }
+}
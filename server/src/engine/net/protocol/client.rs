@@ -0,0 +1,269 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    skyhash client
+    ---
+    a minimal (blocking) Skyhash/2 client that we use when we need to talk to another server, for example to import
+    data from it. only plaintext connections and simple queries are supported. response values are decoded straight
+    into datacells since the response encoding for a value is the same as the encoding for a cell:
+    [type: tag selector + 1; 0 if null][payload]
+*/
+
+use {
+    super::ResponseType,
+    crate::engine::{data::cell::Datacell, mem::IntegerRepr},
+    std::{
+        collections::BTreeMap,
+        fmt,
+        io::{self, BufRead, BufReader, Read, Write},
+        net::TcpStream,
+        time::Duration,
+    },
+};
+
+/// how long we wait for the other end before we give up
+const CLIENT_IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// an I/O error
+    Io(io::Error),
+    /// the server sent us something that we couldn't make sense of
+    Protocol,
+    /// the server rejected the handshake with the given code
+    HandshakeRejected(u8),
+    /// the server returned an error code for the query
+    Query(u16),
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "i/o error: {e}"),
+            Self::Protocol => write!(f, "invalid response from server"),
+            Self::HandshakeRejected(e) => write!(f, "handshake rejected with code {e}"),
+            Self::Query(e) => write!(f, "query failed with code {e}"),
+        }
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+#[derive(Debug, PartialEq)]
+pub enum ClientResponse {
+    Empty,
+    Value(Datacell),
+    Row(Vec<Datacell>),
+    Rows(Vec<Vec<Datacell>>),
+}
+
+/// Called with the kind and message of every notice that the server pushes
+pub type NoticeHandler = Box<dyn FnMut(u8, &str) + Send>;
+
+pub struct Client {
+    con: BufReader<TcpStream>,
+    on_notice: Option<NoticeHandler>,
+}
+
+impl Client {
+    /// Connect to the server at `addr` (`host:port`) and authenticate with the given credentials
+    pub fn connect(addr: &str, username: &str, password: &str) -> ClientResult<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(CLIENT_IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_IO_TIMEOUT))?;
+        // [H][hs version][protocol][exchange mode][query mode][auth mode][uname len]\n[pwd len]\n[uname][pwd]
+        // we ask for notices (exchange mode 3) so that they can be passed on to the notice handler
+        let mut hs = vec![b'H', 0, 0, 3, 0, 0];
+        put_int(&mut hs, username.len() as u64);
+        put_int(&mut hs, password.len() as u64);
+        hs.extend(username.as_bytes());
+        hs.extend(password.as_bytes());
+        stream.write_all(&hs)?;
+        let mut con = BufReader::new(stream);
+        let mut resp = [0u8; 4];
+        con.read_exact(&mut resp)?;
+        match resp {
+            [b'H', 0, 0, 0] => Ok(Self {
+                con,
+                on_notice: None,
+            }),
+            [b'H', 0, 1, e] => Err(ClientError::HandshakeRejected(e)),
+            _ => Err(ClientError::Protocol),
+        }
+    }
+    /// Set the handler for server notices. Notices are only seen while waiting for a response; without a handler
+    /// they are dropped
+    pub fn set_notice_handler(&mut self, f: impl FnMut(u8, &str) + Send + 'static) {
+        self.on_notice = Some(Box::new(f));
+    }
+    /// Run a simple query (without parameters)
+    pub fn query(&mut self, query: &str) -> ClientResult<ClientResponse> {
+        // [S][packet size]\n[query window]\n[query]
+        let mut md2 = vec![];
+        put_int(&mut md2, query.len() as u64);
+        let mut packet = vec![b'S'];
+        put_int(&mut packet, (md2.len() + query.len()) as u64);
+        packet.extend(md2);
+        packet.extend(query.as_bytes());
+        self.con.get_mut().write_all(&packet)?;
+        self.read_response()
+    }
+    fn read_response(&mut self) -> ClientResult<ClientResponse> {
+        let mut ty = self.read_byte()?;
+        while ty == ResponseType::Notice.value_u8() {
+            // [kind][message length]\n[message]
+            let kind = self.read_byte()?;
+            let len = self.read_int()?;
+            let mut message = vec![0; len as usize];
+            self.con.read_exact(&mut message)?;
+            if let Some(on_notice) = self.on_notice.as_mut() {
+                on_notice(kind, &String::from_utf8_lossy(&message));
+            }
+            ty = self.read_byte()?;
+        }
+        if ty == ResponseType::Retry.value_u8() {
+            // [flags]
+            let _flags = self.read_byte()?;
+            ty = self.read_byte()?;
+        }
+        if ty == ResponseType::Empty.value_u8() {
+            Ok(ClientResponse::Empty)
+        } else if ty == ResponseType::Error.value_u8() {
+            let mut code = [0u8; 2];
+            self.con.read_exact(&mut code)?;
+            Err(ClientError::Query(u16::from_le_bytes(code)))
+        } else if ty == ResponseType::ErrorAt.value_u8() {
+            // [code][position]\n
+            let mut code = [0u8; 2];
+            self.con.read_exact(&mut code)?;
+            let _position = self.read_int()?;
+            Err(ClientError::Query(u16::from_le_bytes(code)))
+        } else if ty == ResponseType::Row.value_u8() {
+            let column_count = self.read_int()?;
+            self.read_row(column_count).map(ClientResponse::Row)
+        } else if ty == ResponseType::MultiRow.value_u8() {
+            // [row count]\n[column count]\n[rows]
+            let row_count = self.read_int()?;
+            let column_count = self.read_int()?;
+            let mut rows = vec![];
+            for _ in 0..row_count {
+                rows.push(self.read_row(column_count)?);
+            }
+            Ok(ClientResponse::Rows(rows))
+        } else {
+            self.read_cell(ty).map(ClientResponse::Value)
+        }
+    }
+    fn read_row(&mut self, column_count: u64) -> ClientResult<Vec<Datacell>> {
+        let mut row = vec![];
+        for _ in 0..column_count {
+            let ty = self.read_byte()?;
+            row.push(self.read_cell(ty)?);
+        }
+        Ok(row)
+    }
+    fn read_cell(&mut self, ty: u8) -> ClientResult<Datacell> {
+        Ok(match ty {
+            0x00 => Datacell::null(),
+            0x01 => Datacell::new_bool(self.read_byte()? == 1),
+            0x02..=0x05 => Datacell::new_uint_default(self.read_int()?),
+            0x06..=0x09 => {
+                let line = self.read_line()?;
+                Datacell::new_sint_default(line.parse().map_err(|_| ClientError::Protocol)?)
+            }
+            0x0A | 0x0B => {
+                let line = self.read_line()?;
+                Datacell::new_float_default(line.parse().map_err(|_| ClientError::Protocol)?)
+            }
+            0x0C | 0x0D => {
+                let len = self.read_int()?;
+                let mut data = vec![0; len as usize];
+                self.con.read_exact(&mut data)?;
+                if ty == ResponseType::Binary.value_u8() {
+                    Datacell::new_bin(data.into_boxed_slice())
+                } else {
+                    Datacell::new_str(
+                        String::from_utf8(data)
+                            .map_err(|_| ClientError::Protocol)?
+                            .into_boxed_str(),
+                    )
+                }
+            }
+            0x0E => {
+                let len = self.read_int()?;
+                let mut list = vec![];
+                for _ in 0..len {
+                    let ty = self.read_byte()?;
+                    list.push(self.read_cell(ty)?);
+                }
+                Datacell::new_list(list)
+            }
+            0x0F => {
+                let len = self.read_int()?;
+                let mut map = BTreeMap::new();
+                for _ in 0..len {
+                    let key_len = self.read_int()?;
+                    let mut key = vec![0; key_len as usize];
+                    self.con.read_exact(&mut key)?;
+                    let key = String::from_utf8(key).map_err(|_| ClientError::Protocol)?;
+                    let ty = self.read_byte()?;
+                    map.insert(key.into_boxed_str(), self.read_cell(ty)?);
+                }
+                Datacell::new_map(map)
+            }
+            _ => return Err(ClientError::Protocol),
+        })
+    }
+    fn read_byte(&mut self) -> ClientResult<u8> {
+        let mut b = [0u8];
+        self.con.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+    /// Read a LF terminated line (without the LF)
+    fn read_line(&mut self) -> ClientResult<String> {
+        let mut line = vec![];
+        self.con.read_until(b'\n', &mut line)?;
+        if line.pop() != Some(b'\n') {
+            return Err(ClientError::Protocol);
+        }
+        String::from_utf8(line).map_err(|_| ClientError::Protocol)
+    }
+    fn read_int(&mut self) -> ClientResult<u64> {
+        self.read_line()?.parse().map_err(|_| ClientError::Protocol)
+    }
+}
+
+fn put_int(buf: &mut Vec<u8>, v: u64) {
+    IntegerRepr::scoped(v, |b| buf.extend(b));
+    buf.push(b'\n');
+}
@@ -38,6 +38,12 @@ impl Resume {
         self.0
     }
 }
+impl Resume {
+    /// Returns the number of bytes that were consumed
+    pub(super) const fn consumed(&self) -> usize {
+        self.0
+    }
+}
 impl Default for Resume {
     fn default() -> Self {
         Self(0)
@@ -57,6 +63,46 @@ pub(super) unsafe fn resume<'a>(
     (Resume(scanner.cursor()), ret)
 }
 
+/*
+    MX
+    ---
+    in the multiplexed exchange mode, every packet is prefixed with a stream header: `M<stream id>\n`. every response
+    is tagged with the stream too, and since the streams run concurrently, the responses for different streams can
+    come back in any order
+*/
+
+#[derive(Debug, PartialEq)]
+pub(super) enum StreamHeader {
+    /// read the header for the given stream; the packet starts after `size` bytes
+    Completed { stream: u64, size: usize },
+    /// need more data
+    Pending,
+    /// not a stream header
+    Error,
+}
+
+pub(super) fn scan_stream_header(buf: &[u8]) -> StreamHeader {
+    if buf.is_empty() {
+        return StreamHeader::Pending;
+    }
+    let mut scanner = BufferedScanner::new(buf);
+    if unsafe {
+        // UNSAFE(@ohsayan): +lenck
+        scanner.next_byte()
+    } != b'M'
+    {
+        return StreamHeader::Error;
+    }
+    match super::scan_int(&mut scanner, 0) {
+        Ok(AccumlatorStatus::Completed(stream)) => StreamHeader::Completed {
+            stream,
+            size: scanner.cursor(),
+        },
+        Ok(AccumlatorStatus::Pending(_)) => StreamHeader::Pending,
+        Err(()) => StreamHeader::Error,
+    }
+}
+
 /*
     SQ
 */
@@ -68,7 +114,7 @@ pub struct SQuery<'a> {
 }
 
 impl<'a> SQuery<'a> {
-    pub(super) fn new(q: &'a [u8], q_window: usize) -> Self {
+    pub(in crate::engine) fn new(q: &'a [u8], q_window: usize) -> Self {
         Self { q, q_window }
     }
     pub fn payload(&self) -> &'a [u8] {
@@ -219,7 +265,8 @@ impl QExchangeState {
     }
     fn resume_data<'a>(mut self, scanner: &mut BufferedScanner<'a>) -> QExchangeResult<'a> {
         let df_size = self.target - scanner.cursor();
-        if scanner.remaining() == df_size {
+        // a multiplexed client can pipeline packets, so there may be more data after this packet
+        if scanner.remaining() >= df_size {
             unsafe {
                 QExchangeResult::SQCompleted(SQuery::new(
                     scanner.next_chunk_variable(df_size),
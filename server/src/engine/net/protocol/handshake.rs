@@ -62,12 +62,27 @@ pub enum HandshakeVersion {
     Original = 0,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, sky_macros::EnumMethods, sky_macros::TaggedEnum)]
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    sky_macros::EnumMethods,
+    sky_macros::TaggedEnum,
+)]
 #[repr(u8)]
-/// the skyhash protocol version
+/// the skyhash protocol version. every version understands all the responses of the versions before it
 pub enum ProtocolVersion {
     /// Skyhash/2.0 protocol
     Original = 0,
+    /// Skyhash/2.0 protocol with typed map responses (see [`DictWriter`](super::resp::DictWriter))
+    Dict = 1,
+    /// Skyhash/2.0 protocol where an error can point at a token of the statement (see
+    /// [`ResponseType::ErrorAt`](super::ResponseType::ErrorAt))
+    ErrorPositions = 2,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, sky_macros::EnumMethods, sky_macros::TaggedEnum)]
@@ -76,6 +91,15 @@ pub enum ProtocolVersion {
 pub enum DataExchangeMode {
     /// query-time data exchange mode
     QueryTime = 0,
+    /// query-time data exchange mode where every statement is acked with its statement ID before it is run (so
+    /// that the client can cancel it with `sysctl cancel query <id>`)
+    QueryTimeAck = 1,
+    /// query-time data exchange mode where every packet is tagged with a stream ID so that several independent
+    /// sessions can share one connection
+    Multiplexed = 2,
+    /// query-time data exchange mode where the server can also push notices while the connection is idle (see
+    /// [`notice`](crate::engine::net::notice))
+    QueryTimeNotice = 3,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, sky_macros::EnumMethods, sky_macros::TaggedEnum)]
@@ -91,13 +115,15 @@ pub enum QueryMode {
 /// the authentication mode
 pub enum AuthMode {
     Password = 0,
+    /// a session token issued with `sysctl issue token` (sent in place of the password)
+    Token = 1,
 }
 
 impl AuthMode {
     /// returns the minimum number of metadata bytes need to parse the payload for this auth mode
     const fn min_payload_bytes(&self) -> usize {
         match self {
-            Self::Password => 4,
+            Self::Password | Self::Token => 4,
         }
     }
 }
@@ -318,8 +344,14 @@ impl<'a> CHandshake<'a> {
         // init header
         let static_header = CHandshakeStatic::new(
             HandshakeVersion::Original,
-            ProtocolVersion::Original,
-            DataExchangeMode::QueryTime,
+            unsafe {
+                // UNSAFE(@ohsayan): already checked
+                ProtocolVersion::from_raw(buf[2])
+            },
+            unsafe {
+                // UNSAFE(@ohsayan): already checked
+                DataExchangeMode::from_raw(buf[3])
+            },
             QueryMode::Bql1,
             unsafe {
                 // UNSAFE(@ohsayan): already checked
@@ -371,7 +403,7 @@ impl<'a> CHandshake<'a> {
         }
         // we seem to have enough data for this auth mode
         match static_header.auth_mode {
-            AuthMode::Password => {}
+            AuthMode::Password | AuthMode::Token => {}
         }
         // let us see if we can parse the username length
         let uname_l = match scanner.try_next_ascii_u64_lf_separated_with_result_or_restore_cursor()
@@ -379,7 +411,7 @@ impl<'a> CHandshake<'a> {
             ScannerDecodeResult::NeedMore => {
                 return HandshakeResult::ChangeState {
                     new_state: HandshakeState::StaticBlock(static_header),
-                    expect: AuthMode::Password.min_payload_bytes(), // 2 for uname_l and 2 for pwd_l
+                    expect: static_header.auth_mode.min_payload_bytes(), // 2 for uname_l and 2 for pwd_l
                 };
             }
             ScannerDecodeResult::Value(v) => v as usize,
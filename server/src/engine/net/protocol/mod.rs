@@ -34,38 +34,57 @@
  * without any integer payload is equivalent to a zero value. we allow this because it's easier to specify formally
  * as states
  * - Handshake parameter versions: We currently only evaluate values for the version "original" (shipped with
- * Skytable 0.8.0)
+ * Skytable 0.8.0). The only exception is the protocol version, where "dict" has the server return typed maps (instead
+ * of JSON strings) for `inspect`
+ * - Multiplexing: in the multiplexed exchange mode, every packet is tagged with a stream ID and every stream has its
+ * own session (current space, statement timeout and so on). Statements on different streams run concurrently, so
+ * their responses can come back in any order (every response is tagged with its stream); the statements of a stream
+ * run one at a time, in the order that they were received
  * - FIXME(@ohsayan) Optimistic retry without timeout: Our current algorithm does not apply a timeout to receive data
  * and optimistically retries infinitely until the target block size is received
 */
 
+pub mod client;
 mod exchange;
 mod handshake;
+mod mux;
+pub mod resp;
 #[cfg(test)]
 mod tests;
 
 // re-export
 pub use exchange::SQuery;
 
-use crate::engine::core::system_db::VerifyUser;
-
 use {
     self::{
-        exchange::{QExchangeResult, QExchangeState},
+        exchange::{QExchangeResult, QExchangeState, StreamHeader},
         handshake::{
             AuthMode, CHandshake, DataExchangeMode, HandshakeResult, HandshakeState,
             HandshakeVersion, ProtocolError, ProtocolVersion, QueryMode,
         },
+        resp::DictFormat,
     },
-    super::{IoResult, QueryLoopResult, Socket},
+    super::{notice, IoResult, QueryLoopResult, Socket},
     crate::engine::{
         self,
-        error::QueryError,
+        core::{
+            cancel::{CancelFlag, RunningStatement},
+            dml::ResultFormat,
+            query_mem::QueryMemory,
+            scratch::Scratch,
+            system_db::{SystemDatabase, VerifyUser},
+            trace::{TraceHandle, TraceParent},
+        },
+        error::{QueryError, QueryResult},
         fractal::{Global, GlobalInstanceLike},
         mem::{BufferedScanner, IntegerRepr},
     },
     bytes::{Buf, BytesMut},
-    tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter},
+    std::time::Duration,
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt, BufWriter},
+        sync::broadcast::error::RecvError,
+    },
 };
 
 #[repr(u8)]
@@ -92,6 +111,13 @@ pub enum ResponseType {
     Row = 0x11,
     Empty = 0x12,
     MultiRow = 0x13,
+    Notice = 0x14,
+    Ack = 0x15,
+    Stream = 0x16,
+    Meta = 0x17,
+    PackedRows = 0x18,
+    ErrorAt = 0x19,
+    Retry = 0x1A,
 }
 
 #[derive(Debug, PartialEq)]
@@ -100,6 +126,15 @@ pub struct ClientLocalState {
     root: bool,
     hs: handshake::CHandshakeStatic,
     cs: Option<Box<str>>,
+    cancel: Option<CancelFlag>,
+    statement_timeout: Option<Duration>,
+    result_format: ResultFormat,
+    traceparent: Option<TraceParent>,
+    trace: Option<TraceHandle>,
+    scratch: Scratch,
+    strict_parsing: bool,
+    retry_info: bool,
+    retry: Option<RetryInfo>,
 }
 
 impl ClientLocalState {
@@ -109,8 +144,32 @@ impl ClientLocalState {
             root,
             hs,
             cs: None,
+            cancel: None,
+            statement_timeout: None,
+            result_format: ResultFormat::default(),
+            traceparent: None,
+            trace: None,
+            scratch: Scratch::new(),
+            strict_parsing: true,
+            retry_info: false,
+            retry: None,
         }
     }
+    /// The state of a client that runs within the server (such as a scheduled task or an embedded client). It is always
+    /// root and never goes through a handshake
+    pub fn new_local_root() -> Self {
+        Self::new(
+            SystemDatabase::ROOT_ACCOUNT.into(),
+            true,
+            handshake::CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Original,
+                DataExchangeMode::QueryTime,
+                QueryMode::Bql1,
+                AuthMode::Password,
+            ),
+        )
+    }
     pub fn is_root(&self) -> bool {
         self.root
     }
@@ -126,6 +185,92 @@ impl ClientLocalState {
     pub fn get_cs(&self) -> Option<&str> {
         self.cs.as_deref()
     }
+    /// Returns the format that map responses (like the ones for `inspect`) should be written in
+    pub fn dict_format(&self) -> DictFormat {
+        if self.hs.protocol() >= ProtocolVersion::Dict {
+            DictFormat::Typed
+        } else {
+            DictFormat::Json
+        }
+    }
+    /// Returns true if the client can decode errors that point at a token of the statement
+    pub fn reports_error_positions(&self) -> bool {
+        self.hs.protocol() >= ProtocolVersion::ErrorPositions
+    }
+    /// Returns true if the client wants every statement to be acked with its statement ID
+    pub fn acks_statements(&self) -> bool {
+        self.hs.exchange_mode() == DataExchangeMode::QueryTimeAck
+    }
+    /// Returns true if the client wants the server's notices pushed to it
+    pub fn wants_notices(&self) -> bool {
+        self.hs.exchange_mode() == DataExchangeMode::QueryTimeNotice
+    }
+    /// Returns true if several sessions share this connection (each on its own stream)
+    pub fn is_multiplexed(&self) -> bool {
+        self.hs.exchange_mode() == DataExchangeMode::Multiplexed
+    }
+    /// A new session (on another stream of a multiplexed connection) for the same user
+    pub fn new_session(&self) -> Self {
+        Self::new(self.username.clone(), self.root, self.hs)
+    }
+    /// The cancellation flag of the statement that is currently running (if it can be cancelled)
+    pub fn cancel_flag(&self) -> Option<&CancelFlag> {
+        self.cancel.as_ref()
+    }
+    pub fn set_cancel_flag(&mut self, flag: Option<CancelFlag>) {
+        self.cancel = flag;
+    }
+    /// The timeout for statements that don't set their own (`set statement_timeout = ...`)
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        self.statement_timeout
+    }
+    pub fn set_statement_timeout(&mut self, timeout: Option<Duration>) {
+        self.statement_timeout = timeout;
+    }
+    /// How select results should be encoded (`set result_metadata` and `set packed_columns`)
+    pub fn result_format(&self) -> ResultFormat {
+        self.result_format
+    }
+    pub fn result_format_mut(&mut self) -> &mut ResultFormat {
+        &mut self.result_format
+    }
+    /// The trace context that this session's statements are traced under (`set traceparent = ...`)
+    pub fn traceparent(&self) -> Option<TraceParent> {
+        self.traceparent
+    }
+    pub fn set_traceparent(&mut self, parent: Option<TraceParent>) {
+        self.traceparent = parent;
+    }
+    /// The trace of the statement that is currently running (if tracing is enabled)
+    pub fn trace(&self) -> Option<&TraceHandle> {
+        self.trace.as_ref()
+    }
+    pub fn set_trace(&mut self, trace: Option<TraceHandle>) {
+        self.trace = trace;
+    }
+    /// Returns false if tokens after the end of a statement are ignored instead of rejected (`set strict_parsing = ...`)
+    pub fn strict_parsing(&self) -> bool {
+        self.strict_parsing
+    }
+    pub fn set_strict_parsing(&mut self, strict: bool) {
+        self.strict_parsing = strict;
+    }
+    /// Set if every response is preceded by a retry frame (`set retry_info = ...`)
+    pub fn set_retry_info(&mut self, enabled: bool) {
+        self.retry_info = enabled;
+    }
+    /// Set the retry flags of the statement that just ran
+    pub fn set_retry(&mut self, retry: RetryInfo) {
+        self.retry = Some(retry);
+    }
+    /// The retry flags of the statement that just ran, if the session wants them
+    pub fn take_retry(&mut self) -> Option<RetryInfo> {
+        self.retry.take().filter(|_| self.retry_info)
+    }
+    /// The buffers that this session reuses across statements
+    pub fn scratch_mut(&mut self) -> &mut Scratch {
+        &mut self.scratch
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -137,17 +282,69 @@ pub enum Response {
         size: usize,
         data: Vec<u8>,
     },
+    /// A serialized response whose payload stays charged to the query's memory budget until it has been written
+    SerializedCharged {
+        ty: ResponseType,
+        size: usize,
+        data: Vec<u8>,
+        mem: QueryMemory,
+    },
     Bool(bool),
+    /// A single (encoded) cell, for statements that return a scalar
+    Cell(Vec<u8>),
+    /// A response that is preceded by a result metadata frame (see `set result_metadata`)
+    WithMeta {
+        meta: Vec<u8>,
+        resp: Box<Response>,
+    },
+    /// An error that points at a token of the statement (by its index)
+    ErrorAt {
+        error: QueryError,
+        position: usize,
+    },
+}
+
+/*
+    retry info
+    ---
+    if a session turns on retry info (`set retry_info = true`), every response is preceded by a retry frame:
+    `[0x1A][flags]`. a driver can use the flags to decide if a statement can be retried without asking the user:
+    - bit 0: the statement can't change any data (like a select or an inspect), so it is always safe to retry
+    - bit 1: the statement failed before it changed anything, so it is safe to retry even if it is a write
+    a write that succeeded (or that failed with a storage or server error) has neither bit set
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The retry flags of a statement (see `set retry_info`)
+pub struct RetryInfo {
+    side_effect_free: bool,
+    not_applied: bool,
+}
+
+impl RetryInfo {
+    const FLAG_SIDE_EFFECT_FREE: u8 = 1 << 0;
+    const FLAG_NOT_APPLIED: u8 = 1 << 1;
+    pub fn new(side_effect_free: bool, not_applied: bool) -> Self {
+        Self {
+            side_effect_free,
+            not_applied,
+        }
+    }
+    pub fn flags(&self) -> u8 {
+        (self.side_effect_free as u8 * Self::FLAG_SIDE_EFFECT_FREE)
+            | (self.not_applied as u8 * Self::FLAG_NOT_APPLIED)
+    }
 }
 
 pub(super) async fn query_loop<S: Socket>(
     con: &mut BufWriter<S>,
     buf: &mut BytesMut,
     global: &Global,
+    auth: bool,
 ) -> IoResult<QueryLoopResult> {
     // handshake
-    let mut client_state = match do_handshake(con, buf, global).await? {
-        PostHandshake::Okay(hs) => hs,
+    let mut client_state = match do_handshake(con, buf, global, auth).await? {
+        PostHandshake::Okay(hs) => *hs,
         PostHandshake::ConnectionClosedFin => return Ok(QueryLoopResult::Fin),
         PostHandshake::ConnectionClosedRst => return Ok(QueryLoopResult::Rst),
         PostHandshake::Error(e) => {
@@ -160,25 +357,84 @@ pub(super) async fn query_loop<S: Socket>(
     // done handshaking
     con.write_all(b"H\x00\x00\x00").await?;
     con.flush().await?;
+    let mut notices = client_state.wants_notices().then(notice::subscribe);
     let mut state = QExchangeState::default();
     let mut cursor = Default::default();
+    // multiplexed exchange: the sessions on this connection, the statements running on them and the stream of the
+    // packet that we're reading
+    let mut streams = mux::Streams::new();
+    let mut running = mux::Running::new();
+    let mut stream = None;
+    // set if a (pipelined) packet is already in the buffer
+    let mut buffered = false;
     loop {
-        if con.read_buf(buf).await? == 0 {
-            if buf.is_empty() {
-                return Ok(QueryLoopResult::Fin);
-            } else {
-                return Ok(QueryLoopResult::Rst);
+        if !buffered {
+            let read = tokio::select! {
+                read = con.read_buf(buf) => read?,
+                notice = notice::next(&mut notices) => {
+                    // we're idle (not writing a response), so it's safe to push the notice
+                    match notice {
+                        Ok(notice) if notice.is_for(client_state.username()) => {
+                            con.write_all(&notice.encode()).await?;
+                            con.flush().await?;
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("connection missed {missed} notice(s) because it fell behind")
+                        }
+                        Err(RecvError::Closed) => unreachable!("the notice channel is never closed"),
+                    }
+                    continue;
+                }
+                Some(done) = running.next(), if !running.is_empty() => {
+                    // a statement on a stream is done
+                    write_stream_header(con, done.id).await?;
+                    let mut session = done.session;
+                    if let Some(retry) = session.take_retry() {
+                        write_retry(con, retry).await?;
+                    }
+                    write_traced_response(con, done.resp, done.trace, session.scratch_mut()).await?;
+                    con.flush().await?;
+                    if let Some((session, query)) = streams.complete(done.id, session) {
+                        running.spawn(global, done.id, session, query);
+                    }
+                    continue;
+                }
+            };
+            if read == 0 {
+                if buf.is_empty() {
+                    return Ok(QueryLoopResult::Fin);
+                } else {
+                    return Ok(QueryLoopResult::Rst);
+                }
+            }
+        }
+        buffered = false;
+        if client_state.is_multiplexed() & stream.is_none() {
+            match exchange::scan_stream_header(buf) {
+                StreamHeader::Completed { stream: id, size } => {
+                    buf.advance(size);
+                    stream = Some(id);
+                }
+                StreamHeader::Pending => continue,
+                StreamHeader::Error => {
+                    // we can't tell where the next packet starts, so drop everything that we have
+                    write_error(con, QueryError::SysNetworkSystemIllegalClientPacket).await?;
+                    con.flush().await?;
+                    buf.clear();
+                    continue;
+                }
             }
         }
         if !state.has_reached_target(buf) {
             // we haven't buffered sufficient bytes; keep working
             continue;
         }
-        let sq = match unsafe {
+        let (consumed, sq) = match unsafe {
             // UNSAFE(@ohsayan): as the resume cursor is private, we can't access this anyways
             exchange::resume(buf, cursor, state)
         } {
-            (_, QExchangeResult::SQCompleted(sq)) => sq,
+            (consumed, QExchangeResult::SQCompleted(sq)) => (consumed, sq),
             (new_cursor, QExchangeResult::ChangeState(new_state)) => {
                 cursor = new_cursor;
                 state = new_state;
@@ -186,10 +442,10 @@ pub(super) async fn query_loop<S: Socket>(
             }
             (_, QExchangeResult::Error) => {
                 // respond with error
-                let [a, b] = (QueryError::SysNetworkSystemIllegalClientPacket.value_u8() as u16)
-                    .to_le_bytes();
-                con.write_all(&[ResponseType::Error.value_u8(), a, b])
-                    .await?;
+                if let Some(id) = stream.take() {
+                    write_stream_header(con, id).await?;
+                }
+                write_error(con, QueryError::SysNetworkSystemIllegalClientPacket).await?;
                 con.flush().await?;
                 // reset buffer, cursor and state
                 buf.clear();
@@ -198,49 +454,203 @@ pub(super) async fn query_loop<S: Socket>(
                 continue;
             }
         };
-        // now execute query
-        match engine::core::exec::dispatch_to_executor(global, &mut client_state, sq).await {
-            Ok(Response::Empty) => {
-                con.write_all(&[ResponseType::Empty.value_u8()]).await?;
-            }
-            Ok(Response::Serialized { ty, size, data }) => {
-                con.write_u8(ty.value_u8()).await?;
-                let mut irep = IntegerRepr::new();
-                con.write_all(irep.as_bytes(size as u64)).await?;
-                con.write_u8(b'\n').await?;
-                con.write_all(&data).await?;
-            }
-            Ok(Response::Bool(b)) => {
-                con.write_all(&[ResponseType::Bool.value_u8(), b as u8])
-                    .await?
+        match stream.take() {
+            Some(id) => {
+                // the response is written once the statement is done
+                match streams.submit(id, mux::StreamQuery::new(&sq), &client_state) {
+                    Ok(Some((session, query))) => running.spawn(global, id, session, query),
+                    Ok(None) => {}
+                    Err(e) => {
+                        write_stream_header(con, id).await?;
+                        write_error(con, e).await?;
+                    }
+                }
             }
-            Ok(Response::Null) => con.write_u8(ResponseType::Null.value_u8()).await?,
-            Err(e) => {
-                let [a, b] = (e.value_u8() as u16).to_le_bytes();
-                con.write_all(&[ResponseType::Error.value_u8(), a, b])
-                    .await?;
+            None => {
+                // if the client asked for it, tell it the ID of the statement before we run it so that it can be
+                // cancelled
+                let stmt = if client_state.acks_statements() {
+                    let stmt = RunningStatement::register(client_state.username());
+                    write_ack(con, stmt.id()).await?;
+                    client_state.set_cancel_flag(Some(stmt.flag().clone()));
+                    Some(stmt)
+                } else {
+                    None
+                };
+                // now execute query
+                let trace = start_trace(&mut client_state);
+                let resp =
+                    engine::core::exec::dispatch_to_executor(global, &mut client_state, sq).await;
+                client_state.set_cancel_flag(None);
+                client_state.set_trace(None);
+                drop(stmt);
+                if let Some(retry) = client_state.take_retry() {
+                    write_retry(con, retry).await?;
+                }
+                write_traced_response(con, resp, trace, client_state.scratch_mut()).await?;
             }
         }
         con.flush().await?;
         // reset buffer, cursor and state
-        buf.clear();
+        if client_state.is_multiplexed() {
+            // the client may have already sent the next packet
+            buf.advance(consumed.consumed());
+            buffered = !buf.is_empty();
+        } else {
+            buf.clear();
+        }
         cursor = Default::default();
         state = QExchangeState::default();
     }
 }
 
+/// Start tracing the next statement of this session (if tracing is enabled)
+fn start_trace(session: &mut ClientLocalState) -> Option<TraceHandle> {
+    let trace = TraceHandle::start(session.traceparent());
+    session.set_trace(trace.clone());
+    trace
+}
+
+async fn write_traced_response<S: Socket>(
+    con: &mut BufWriter<S>,
+    resp: QueryResult<Response>,
+    trace: Option<TraceHandle>,
+    scratch: &mut Scratch,
+) -> IoResult<()> {
+    let Some(trace) = trace else {
+        return write_response(con, resp, scratch).await;
+    };
+    match &resp {
+        Err(e) | Ok(Response::ErrorAt { error: e, .. }) => trace.set_error(e.value_u8() as u16),
+        Ok(_) => {}
+    }
+    let encode = trace.phase("encode");
+    let ret = write_response(con, resp, scratch).await;
+    drop(encode);
+    trace.finish();
+    ret
+}
+
+/// Write the response, handing its buffer back to the connection's scratch space once it has been written
+async fn write_response<S: Socket>(
+    con: &mut BufWriter<S>,
+    resp: QueryResult<Response>,
+    scratch: &mut Scratch,
+) -> IoResult<()> {
+    let resp = match resp {
+        Ok(Response::WithMeta { meta, resp }) => {
+            con.write_all(&meta).await?;
+            Ok(*resp)
+        }
+        resp => resp,
+    };
+    match resp {
+        Ok(Response::Empty) => con.write_all(&[ResponseType::Empty.value_u8()]).await,
+        Ok(Response::Serialized { ty, size, data }) => {
+            write_serialized(con, ty, size, &data).await?;
+            scratch.recycle_response_buffer(data);
+            Ok(())
+        }
+        Ok(Response::SerializedCharged {
+            ty,
+            size,
+            data,
+            mem,
+        }) => {
+            write_serialized(con, ty, size, &data).await?;
+            // the charge goes away once the buffer has been written
+            drop(mem);
+            scratch.recycle_response_buffer(data);
+            Ok(())
+        }
+        Ok(Response::Bool(b)) => {
+            con.write_all(&[ResponseType::Bool.value_u8(), b as u8])
+                .await
+        }
+        Ok(Response::Null) => con.write_u8(ResponseType::Null.value_u8()).await,
+        Ok(Response::Cell(cell)) => {
+            con.write_all(&cell).await?;
+            scratch.recycle_response_buffer(cell);
+            Ok(())
+        }
+        Ok(Response::WithMeta { .. }) => unreachable!("metadata frames are never nested"),
+        Ok(Response::ErrorAt { error, position }) => write_error_at(con, error, position).await,
+        Err(e) => write_error(con, e).await,
+    }
+}
+
+async fn write_error<S: Socket>(con: &mut BufWriter<S>, e: QueryError) -> IoResult<()> {
+    let [a, b] = (e.value_u8() as u16).to_le_bytes();
+    con.write_all(&[ResponseType::Error.value_u8(), a, b]).await
+}
+
+/// Write an error along with the index of the token that it points at: `[0x19][code][position]\n` (only for clients
+/// that handshake with [`ProtocolVersion::ErrorPositions`])
+async fn write_error_at<S: Socket>(
+    con: &mut BufWriter<S>,
+    e: QueryError,
+    position: usize,
+) -> IoResult<()> {
+    let [a, b] = (e.value_u8() as u16).to_le_bytes();
+    con.write_all(&[ResponseType::ErrorAt.value_u8(), a, b])
+        .await?;
+    let mut irep = IntegerRepr::new();
+    con.write_all(irep.as_bytes(position as u64)).await?;
+    con.write_u8(b'\n').await
+}
+
+/// Write the retry frame that precedes a response: `[0x1A][flags]`
+async fn write_retry<S: Socket>(con: &mut BufWriter<S>, retry: RetryInfo) -> IoResult<()> {
+    con.write_all(&[ResponseType::Retry.value_u8(), retry.flags()])
+        .await
+}
+
+/// Write the header that tags a response with its stream (multiplexed exchange): `[0x16][stream ID]\n`
+async fn write_stream_header<S: Socket>(con: &mut BufWriter<S>, id: u64) -> IoResult<()> {
+    con.write_u8(ResponseType::Stream.value_u8()).await?;
+    let mut irep = IntegerRepr::new();
+    con.write_all(irep.as_bytes(id)).await?;
+    con.write_u8(b'\n').await
+}
+
+/// Write (and flush) the ack frame for a statement: `[0x15][statement ID]\n`
+async fn write_ack<S: Socket>(con: &mut BufWriter<S>, id: u64) -> IoResult<()> {
+    con.write_u8(ResponseType::Ack.value_u8()).await?;
+    let mut irep = IntegerRepr::new();
+    con.write_all(irep.as_bytes(id)).await?;
+    con.write_u8(b'\n').await?;
+    con.flush().await
+}
+
+async fn write_serialized<S: Socket>(
+    con: &mut BufWriter<S>,
+    ty: ResponseType,
+    size: usize,
+    data: &[u8],
+) -> IoResult<()> {
+    con.write_u8(ty.value_u8()).await?;
+    let mut irep = IntegerRepr::new();
+    con.write_all(irep.as_bytes(size as u64)).await?;
+    con.write_u8(b'\n').await?;
+    con.write_all(data).await
+}
+
 #[derive(Debug, PartialEq)]
 enum PostHandshake {
-    Okay(ClientLocalState),
+    // boxed, since the client state holds the connection's inline token buffer
+    Okay(Box<ClientLocalState>),
     Error(ProtocolError),
     ConnectionClosedFin,
     ConnectionClosedRst,
 }
 
+/// Read the client's handshake and authenticate it. If `auth` is unset, the endpoint doesn't need a password (or a
+/// token) and the client is let in as the user that it names
 async fn do_handshake<S: Socket>(
     con: &mut BufWriter<S>,
     buf: &mut BytesMut,
     global: &Global,
+    auth: bool,
 ) -> IoResult<PostHandshake> {
     let mut expected = CHandshake::INITIAL_READ;
     let mut state = HandshakeState::default();
@@ -281,29 +691,52 @@ async fn do_handshake<S: Socket>(
             handshake.hs_static().hs_version(),
             HandshakeVersion::Original
         );
-        assert_eq!(handshake.hs_static().protocol(), ProtocolVersion::Original);
-        assert_eq!(
-            handshake.hs_static().exchange_mode(),
-            DataExchangeMode::QueryTime
-        );
         assert_eq!(handshake.hs_static().query_mode(), QueryMode::Bql1);
-        assert_eq!(handshake.hs_static().auth_mode(), AuthMode::Password);
     }
     match core::str::from_utf8(handshake.hs_auth().username()) {
         Ok(uname) => {
-            match global
-                .state()
-                .namespace()
-                .sys_db()
-                .verify_user(uname, handshake.hs_auth().password())
-            {
+            let verify = if !auth {
+                global.state().namespace().sys_db().trust_user(uname)
+            } else if handshake.hs_static().auth_mode() == AuthMode::Token {
+                // a token isn't the password, so it is checked directly
+                global
+                    .state()
+                    .namespace()
+                    .sys_db()
+                    .verify_user_token(uname, handshake.hs_auth().password())
+            } else {
+                // the password KDF is slow on purpose, so keep it off the connection's worker
+                let (c_glob, c_uname, password) = (
+                    global.clone(),
+                    uname.to_owned(),
+                    handshake.hs_auth().password().to_vec(),
+                );
+                tokio::task::spawn_blocking(move || {
+                    let sys_db = c_glob.state().namespace().sys_db();
+                    let verify = sys_db.verify_user(&c_uname, &password);
+                    if matches!(verify, VerifyUser::Okay | VerifyUser::OkayRoot) {
+                        // upgrade the password hash if the KDF has changed since it was created
+                        if let Err(e) = sys_db.rehash_if_needed(&c_glob, &c_uname, &password) {
+                            warn!("failed to upgrade password hash for `{c_uname}`: {e:?}");
+                        }
+                    }
+                    verify
+                })
+                .await
+                .unwrap_or_else(|e| {
+                    // the check didn't finish, so the user isn't authenticated
+                    error!("password verification for `{uname}` failed: {e}");
+                    VerifyUser::IncorrectPassword
+                })
+            };
+            match verify {
                 okay @ (VerifyUser::Okay | VerifyUser::OkayRoot) => {
                     let hs = handshake.hs_static();
-                    let ret = Ok(PostHandshake::Okay(ClientLocalState::new(
+                    let ret = Ok(PostHandshake::Okay(Box::new(ClientLocalState::new(
                         uname.into(),
                         okay.is_root(),
                         hs,
-                    )));
+                    ))));
                     buf.advance(cursor);
                     return ret;
                 }
@@ -0,0 +1,214 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    multiplexed exchange
+    ---
+    every stream of a multiplexed connection has its own session. statements on different streams run concurrently
+    (each on its own task), while the statements of a stream run one at a time in the order that they were received
+    (the ones that arrive while the stream is busy wait in the stream's queue)
+*/
+
+use {
+    super::{ClientLocalState, Response, SQuery},
+    crate::engine::{
+        core::{exec, trace::TraceHandle},
+        error::{QueryError, QueryResult},
+        fractal::Global,
+    },
+    std::{
+        collections::{HashMap, VecDeque},
+        mem,
+    },
+    tokio::task::JoinSet,
+};
+
+/// The maximum number of sessions (streams) on a multiplexed connection
+pub(super) const MAX_STREAMS: usize = 256;
+/// The maximum number of statements that can wait (behind a running statement on their stream) on a multiplexed
+/// connection
+const MAX_QUEUED: usize = 1024;
+
+#[derive(Debug, PartialEq)]
+/// A statement that was received on a stream. It is copied out of the connection's read buffer, which is reused
+/// while the statement waits or runs
+pub(super) struct StreamQuery {
+    payload: Box<[u8]>,
+    q_window: usize,
+}
+
+impl StreamQuery {
+    pub fn new(sq: &SQuery) -> Self {
+        Self {
+            payload: sq.payload().into(),
+            q_window: sq.q_window(),
+        }
+    }
+    pub fn squery(&self) -> SQuery<'_> {
+        SQuery::new(&self.payload, self.q_window)
+    }
+}
+
+#[derive(Debug)]
+enum Stream {
+    /// waiting for a statement (the session is boxed since it's far larger than a queue)
+    Idle(Box<ClientLocalState>),
+    /// running a statement (the session is with the statement); these statements run after it
+    Busy(VecDeque<StreamQuery>),
+}
+
+#[derive(Debug)]
+/// The streams of a multiplexed connection
+pub(super) struct Streams {
+    streams: HashMap<u64, Stream>,
+    queued: usize,
+}
+
+impl Streams {
+    pub fn new() -> Self {
+        Self {
+            streams: HashMap::new(),
+            queued: 0,
+        }
+    }
+    /// Submit a statement on a stream (a new stream gets a new session for the connection's user). If the stream is
+    /// idle, this returns the statement along with the session to run it on; else the statement waits for its turn
+    pub fn submit(
+        &mut self,
+        id: u64,
+        query: StreamQuery,
+        base: &ClientLocalState,
+    ) -> QueryResult<Option<(ClientLocalState, StreamQuery)>> {
+        let stream_count = self.streams.len();
+        match self.streams.get_mut(&id) {
+            Some(Stream::Busy(queue)) => {
+                if self.queued == MAX_QUEUED {
+                    return Err(QueryError::SysNetworkSystemIllegalClientPacket);
+                }
+                queue.push_back(query);
+                self.queued += 1;
+                Ok(None)
+            }
+            Some(stream) => {
+                let Stream::Idle(session) = mem::replace(stream, Stream::Busy(VecDeque::new()))
+                else {
+                    unreachable!()
+                };
+                Ok(Some((*session, query)))
+            }
+            None if stream_count == MAX_STREAMS => {
+                Err(QueryError::SysNetworkSystemIllegalClientPacket)
+            }
+            None => {
+                self.streams.insert(id, Stream::Busy(VecDeque::new()));
+                Ok(Some((base.new_session(), query)))
+            }
+        }
+    }
+    /// The statement that was running on the stream is done. This returns the stream's next statement (if any) along
+    /// with the session to run it on; else, the stream goes back to waiting
+    pub fn complete(
+        &mut self,
+        id: u64,
+        session: ClientLocalState,
+    ) -> Option<(ClientLocalState, StreamQuery)> {
+        let stream = self
+            .streams
+            .get_mut(&id)
+            .expect("statement completed on an unknown stream");
+        let Stream::Busy(queue) = stream else {
+            unreachable!("statement completed on an idle stream")
+        };
+        match queue.pop_front() {
+            Some(query) => {
+                self.queued -= 1;
+                Some((session, query))
+            }
+            None => {
+                *stream = Stream::Idle(Box::new(session));
+                None
+            }
+        }
+    }
+}
+
+/// A statement that has finished running on a stream
+pub(super) struct StreamDone {
+    pub id: u64,
+    pub session: ClientLocalState,
+    pub resp: QueryResult<Response>,
+    pub trace: Option<TraceHandle>,
+}
+
+/// The statements that are running on the streams of a connection
+pub(super) struct Running(JoinSet<StreamDone>);
+
+impl Running {
+    pub fn new() -> Self {
+        Self(JoinSet::new())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Run a statement on its stream's session
+    pub fn spawn(
+        &mut self,
+        global: &Global,
+        id: u64,
+        mut session: ClientLocalState,
+        query: StreamQuery,
+    ) {
+        let global = global.clone();
+        self.0.spawn(async move {
+            let trace = super::start_trace(&mut session);
+            let resp = exec::dispatch_to_executor(&global, &mut session, query.squery()).await;
+            session.set_trace(None);
+            StreamDone {
+                id,
+                session,
+                resp,
+                trace,
+            }
+        });
+    }
+    /// Wait for the next statement to finish
+    pub async fn next(&mut self) -> Option<StreamDone> {
+        match self.0.join_next().await? {
+            Ok(done) => Some(done),
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            // only if the runtime is shutting down
+            Err(_) => None,
+        }
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        // like on a connection that isn't multiplexed, a statement that is already running is never cut short (even if
+        // the client goes away)
+        self.0.detach_all()
+    }
+}
@@ -0,0 +1,254 @@
+/*
+ * Created on Thu Oct 15 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+ * Map (dictionary) responses
+ * ---
+ * A dict response is framed as:
+ * [0x0F][entry count]\n[entries]
+ *
+ * Every entry is a key, followed by a typed value:
+ * [key len]\n[key][value type][value payload]
+ *
+ * Value payloads use the same encoding as the cells of a row, with one extension: a value can itself be a
+ * dict ([0x0F][entry count]\n[entries]), so that nested structures can be returned without flattening.
+ *
+ * Only clients that handshake with [`ProtocolVersion::Dict`](super::handshake::ProtocolVersion::Dict) (or a later
+ * version) can decode these, so everyone else gets the same structure as a JSON object (in a string response)
+*/
+
+use {
+    super::{Response, ResponseType},
+    crate::engine::mem::IntegerRepr,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// How a [`DictWriter`] encodes its entries
+pub enum DictFormat {
+    /// a [`ResponseType::Dict`]
+    Typed,
+    /// a JSON object in a [`ResponseType::String`]
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+/// A writer for [`ResponseType::Dict`] responses
+pub struct DictWriter {
+    format: DictFormat,
+    count: usize,
+    data: Vec<u8>,
+}
+
+impl DictWriter {
+    pub fn new(format: DictFormat) -> Self {
+        Self {
+            format,
+            count: 0,
+            data: vec![],
+        }
+    }
+    /// A new (empty) dictionary in the same format, to be nested in this one
+    pub fn nested(&self) -> Self {
+        Self::new(self.format)
+    }
+    fn put_len(&mut self, l: usize) {
+        IntegerRepr::scoped(l as u64, |b| self.data.extend(b));
+        self.data.push(b'\n');
+    }
+    fn put_key(&mut self, k: &str, ty: ResponseType) {
+        match self.format {
+            DictFormat::Typed => {
+                self.put_len(k.len());
+                self.data.extend(k.as_bytes());
+                self.data.push(ty.value_u8());
+            }
+            DictFormat::Json => {
+                if self.count != 0 {
+                    self.data.push(b',');
+                }
+                json_str(&mut self.data, k);
+                self.data.push(b':');
+            }
+        }
+        self.count += 1;
+    }
+    fn put_str_value(&mut self, v: &str) {
+        match self.format {
+            DictFormat::Typed => {
+                self.put_len(v.len());
+                self.data.extend(v.as_bytes());
+            }
+            DictFormat::Json => json_str(&mut self.data, v),
+        }
+    }
+    fn put_uint_value(&mut self, v: u64) {
+        IntegerRepr::scoped(v, |b| self.data.extend(b));
+        if self.format == DictFormat::Typed {
+            self.data.push(b'\n');
+        }
+    }
+    /// Write the values of a list, each with `f`
+    fn put_list<T>(
+        &mut self,
+        k: &str,
+        v: impl ExactSizeIterator<Item = T>,
+        mut f: impl FnMut(&mut Self, T),
+    ) {
+        self.put_key(k, ResponseType::List);
+        match self.format {
+            DictFormat::Typed => {
+                self.put_len(v.len());
+                v.for_each(|item| f(self, item));
+            }
+            DictFormat::Json => {
+                self.data.push(b'[');
+                for (i, item) in v.enumerate() {
+                    if i != 0 {
+                        self.data.push(b',');
+                    }
+                    f(self, item);
+                }
+                self.data.push(b']');
+            }
+        }
+    }
+    fn put_dict_value(&mut self, v: DictWriter) {
+        match self.format {
+            DictFormat::Typed => {
+                self.put_len(v.count);
+                self.data.extend(v.data);
+            }
+            DictFormat::Json => {
+                self.data.push(b'{');
+                self.data.extend(v.data);
+                self.data.push(b'}');
+            }
+        }
+    }
+    fn put_value_type(&mut self, ty: ResponseType) {
+        if self.format == DictFormat::Typed {
+            self.data.push(ty.value_u8());
+        }
+    }
+    pub fn put_null(&mut self, k: &str) {
+        self.put_key(k, ResponseType::Null);
+        if self.format == DictFormat::Json {
+            self.data.extend(b"null");
+        }
+    }
+    pub fn put_bool(&mut self, k: &str, v: bool) {
+        self.put_key(k, ResponseType::Bool);
+        match self.format {
+            DictFormat::Typed => self.data.push(v as u8),
+            DictFormat::Json => self.data.extend(if v { &b"true"[..] } else { b"false" }),
+        }
+    }
+    pub fn put_uint(&mut self, k: &str, v: u64) {
+        self.put_key(k, ResponseType::UInt64);
+        self.put_uint_value(v);
+    }
+    /// Write the integer if there is one and a null otherwise
+    pub fn put_uint_or_null(&mut self, k: &str, v: Option<u64>) {
+        match v {
+            Some(v) => self.put_uint(k, v),
+            None => self.put_null(k),
+        }
+    }
+    pub fn put_str(&mut self, k: &str, v: &str) {
+        self.put_key(k, ResponseType::String);
+        self.put_str_value(v);
+    }
+    /// Write the string if there is one and a null otherwise
+    pub fn put_str_or_null(&mut self, k: &str, v: Option<&str>) {
+        match v {
+            Some(v) => self.put_str(k, v),
+            None => self.put_null(k),
+        }
+    }
+    /// Write a list of strings
+    pub fn put_str_list<'a>(&mut self, k: &str, v: impl ExactSizeIterator<Item = &'a str>) {
+        self.put_list(k, v, |me, item| {
+            me.put_value_type(ResponseType::String);
+            me.put_str_value(item);
+        })
+    }
+    /// Write a list of integers
+    pub fn put_uint_list(&mut self, k: &str, v: impl ExactSizeIterator<Item = u64>) {
+        self.put_list(k, v, |me, item| {
+            me.put_value_type(ResponseType::UInt64);
+            me.put_uint_value(item);
+        })
+    }
+    /// Write a list of dictionaries (each created with [`Self::nested`])
+    pub fn put_dict_list(&mut self, k: &str, v: impl ExactSizeIterator<Item = DictWriter>) {
+        self.put_list(k, v, |me, item| {
+            me.put_value_type(ResponseType::Dict);
+            me.put_dict_value(item);
+        })
+    }
+    /// Write a nested dictionary (created with [`Self::nested`])
+    pub fn put_dict(&mut self, k: &str, v: DictWriter) {
+        self.put_key(k, ResponseType::Dict);
+        self.put_dict_value(v);
+    }
+    pub fn into_response(self) -> Response {
+        match self.format {
+            DictFormat::Typed => Response::Serialized {
+                ty: ResponseType::Dict,
+                size: self.count,
+                data: self.data,
+            },
+            DictFormat::Json => {
+                let mut data = Vec::with_capacity(self.data.len() + 2);
+                data.push(b'{');
+                data.extend(self.data);
+                data.push(b'}');
+                Response::Serialized {
+                    ty: ResponseType::String,
+                    size: data.len(),
+                    data,
+                }
+            }
+        }
+    }
+}
+
+/// Append the string as a JSON string literal (quoted and escaped)
+fn json_str(buf: &mut Vec<u8>, s: &str) {
+    buf.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.extend(b"\\\""),
+            '\\' => buf.extend(b"\\\\"),
+            '\n' => buf.extend(b"\\n"),
+            '\r' => buf.extend(b"\\r"),
+            '\t' => buf.extend(b"\\t"),
+            c if c.is_control() => buf.extend(format!("\\u{:04x}", c as u32).as_bytes()),
+            c => buf.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    buf.push(b'"');
+}
@@ -30,10 +30,12 @@ use {
     super::{
         exchange::{self, QExchangeResult, QExchangeState},
         handshake::ProtocolError,
-        SQuery,
+        mux::{self, StreamQuery, Streams},
+        ClientLocalState, SQuery,
     },
     crate::{
         engine::{
+            error::QueryError,
             mem::BufferedScanner,
             net::protocol::{
                 handshake::{
@@ -186,10 +188,10 @@ fn parse_auth_with_state_updates() {
 
 const HS_BAD_PACKET: [u8; 6] = *b"I\x00\0\0\0\0";
 const HS_BAD_VERSION_HS: [u8; 6] = *b"H\x01\0\0\0\0";
-const HS_BAD_VERSION_PROTO: [u8; 6] = *b"H\0\x01\0\0\0";
-const HS_BAD_MODE_XCHG: [u8; 6] = *b"H\0\0\x01\0\0";
+const HS_BAD_VERSION_PROTO: [u8; 6] = *b"H\0\x02\0\0\0";
+const HS_BAD_MODE_XCHG: [u8; 6] = *b"H\0\0\x03\0\0";
 const HS_BAD_MODE_QUERY: [u8; 6] = *b"H\0\0\0\x01\0";
-const HS_BAD_MODE_AUTH: [u8; 6] = *b"H\0\0\0\0\x01";
+const HS_BAD_MODE_AUTH: [u8; 6] = *b"H\0\0\0\0\x02";
 
 fn scan_hs(hs: impl AsRef<[u8]>, f: impl Fn(HandshakeResult)) {
     let mut scanner = BufferedScanner::new(hs.as_ref());
@@ -284,6 +286,101 @@ fn hs_bad_auth_mode() {
     })
 }
 
+#[test]
+fn hs_token_auth() {
+    let hs = b"H\0\0\0\0\x015\n5\nsayantoken";
+    let mut scanner = BufferedScanner::new(hs);
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::Completed(CHandshake::new(
+            CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Original,
+                DataExchangeMode::QueryTime,
+                QueryMode::Bql1,
+                AuthMode::Token,
+            ),
+            CHandshakeAuth::new(b"sayan", b"token")
+        ))
+    );
+}
+
+#[test]
+fn hs_dict_protocol() {
+    let hs = b"H\0\x01\0\0\x005\n8\nsayanpassword";
+    let mut scanner = BufferedScanner::new(hs);
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::Completed(CHandshake::new(
+            CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Dict,
+                DataExchangeMode::QueryTime,
+                QueryMode::Bql1,
+                AuthMode::Password,
+            ),
+            CHandshakeAuth::new(b"sayan", b"password")
+        ))
+    );
+}
+
+#[test]
+fn hs_error_positions_protocol() {
+    let hs = b"H\0\x02\0\0\x005\n8\nsayanpassword";
+    let mut scanner = BufferedScanner::new(hs);
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::Completed(CHandshake::new(
+            CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::ErrorPositions,
+                DataExchangeMode::QueryTime,
+                QueryMode::Bql1,
+                AuthMode::Password,
+            ),
+            CHandshakeAuth::new(b"sayan", b"password")
+        ))
+    );
+}
+
+#[test]
+fn hs_ack_exchange_mode() {
+    let hs = b"H\0\0\x01\0\x005\n8\nsayanpassword";
+    let mut scanner = BufferedScanner::new(hs);
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::Completed(CHandshake::new(
+            CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Original,
+                DataExchangeMode::QueryTimeAck,
+                QueryMode::Bql1,
+                AuthMode::Password,
+            ),
+            CHandshakeAuth::new(b"sayan", b"password")
+        ))
+    );
+}
+
+#[test]
+fn hs_notice_exchange_mode() {
+    let hs = b"H\0\0\x03\0\x005\n8\nsayanpassword";
+    let mut scanner = BufferedScanner::new(hs);
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::Completed(CHandshake::new(
+            CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Original,
+                DataExchangeMode::QueryTimeNotice,
+                QueryMode::Bql1,
+                AuthMode::Password,
+            ),
+            CHandshakeAuth::new(b"sayan", b"password")
+        ))
+    );
+}
+
 /*
     QT-DEX/SQ
 */
@@ -492,3 +589,210 @@ fn num_accumulate() {
         assert_eq!(bs.cursor(), cursor);
     }
 }
+
+/*
+    dict responses
+*/
+
+#[test]
+fn dict_response_encode() {
+    use super::{
+        resp::{DictFormat, DictWriter},
+        Response, ResponseType,
+    };
+    let mut dw = DictWriter::new(DictFormat::Typed);
+    let mut nested = dw.nested();
+    nested.put_uint("rows", 10);
+    nested.put_null("owner");
+    dw.put_str("decl", "{x: string}");
+    dw.put_bool("root", true);
+    dw.put_str_list("models", ["a", "bc"].into_iter());
+    dw.put_dict("stats", nested);
+    let mut expected = vec![];
+    expected.extend(b"4\ndecl\x0D11\n{x: string}");
+    expected.extend(b"4\nroot\x01\x01");
+    expected.extend(b"6\nmodels\x0E2\n\x0D1\na\x0D2\nbc");
+    expected.extend(b"5\nstats\x0F2\n4\nrows\x0510\n5\nowner\x00");
+    assert_eq!(
+        dw.into_response(),
+        Response::Serialized {
+            ty: ResponseType::Dict,
+            size: 4,
+            data: expected
+        }
+    );
+}
+
+#[test]
+fn dict_response_encode_json() {
+    use super::{
+        resp::{DictFormat, DictWriter},
+        Response, ResponseType,
+    };
+    let mut dw = DictWriter::new(DictFormat::Json);
+    let mut job = dw.nested();
+    job.put_uint("id", 1);
+    job.put_uint_list("progress", [5, 10].into_iter());
+    dw.put_str("path", "/mnt/\"fast\"\\ssd\n");
+    dw.put_bool("root", false);
+    dw.put_null("ttl");
+    dw.put_str_list("models", ["a", "bc"].into_iter());
+    dw.put_dict_list("jobs", [job].into_iter());
+    dw.put_dict("settings", dw.nested());
+    let expected: &[u8] = br#"{"path":"/mnt/\"fast\"\\ssd\n","root":false,"ttl":null,"models":["a","bc"],"jobs":[{"id":1,"progress":[5,10]}],"settings":{}}"#;
+    assert_eq!(
+        dw.into_response(),
+        Response::Serialized {
+            ty: ResponseType::String,
+            size: expected.len(),
+            data: expected.to_vec()
+        }
+    );
+}
+
+/*
+    notices
+*/
+
+#[test]
+fn notice_encode() {
+    use crate::engine::net::notice::{Notice, NoticeKind};
+    let notice = Notice::new(NoticeKind::Shutdown, "bye");
+    assert_eq!(notice.encode(), b"\x14\x003\nbye");
+    assert!(notice.is_for("sayan"));
+    let notice = Notice::for_user(NoticeKind::AccountChanged, "sayan", "hello");
+    assert_eq!(notice.encode(), b"\x14\x035\nhello");
+    assert!(notice.is_for("sayan"));
+    assert!(!notice.is_for("root"));
+}
+
+/*
+    multiplexed exchange
+*/
+
+#[test]
+fn stream_header() {
+    use exchange::StreamHeader;
+    assert_eq!(exchange::scan_stream_header(b""), StreamHeader::Pending);
+    assert_eq!(exchange::scan_stream_header(b"M12"), StreamHeader::Pending);
+    assert_eq!(
+        exchange::scan_stream_header(b"M12\nS"),
+        StreamHeader::Completed {
+            stream: 12,
+            size: 4
+        }
+    );
+    assert_eq!(exchange::scan_stream_header(b"S12\n"), StreamHeader::Error);
+    assert_eq!(exchange::scan_stream_header(b"M1a\n"), StreamHeader::Error);
+}
+
+#[test]
+fn pipelined_packets() {
+    let first = create_simple_query("use $current", []);
+    let mut buf = first.clone();
+    buf.extend(b"M2\n");
+    buf.extend(create_simple_query(SQ, ["sayan"]));
+    let mut state = QExchangeState::default();
+    let mut cursor = Default::default();
+    loop {
+        match unsafe { exchange::resume(&buf, cursor, state) } {
+            (new_cursor, QExchangeResult::ChangeState(new_state)) => {
+                cursor = new_cursor;
+                state = new_state;
+            }
+            (consumed, QExchangeResult::SQCompleted(q)) => {
+                assert_eq!(q.query_str(), "use $current");
+                // the next packet is left untouched
+                assert_eq!(consumed.consumed(), first.len());
+                break;
+            }
+            (_, QExchangeResult::Error) => panic!(),
+        }
+    }
+}
+
+fn stream_query(query: &str) -> StreamQuery {
+    let packet = create_simple_query(query, []);
+    let mut state = QExchangeState::default();
+    let mut cursor = Default::default();
+    loop {
+        match unsafe { exchange::resume(&packet, cursor, state) } {
+            (new_cursor, QExchangeResult::ChangeState(new_state)) => {
+                cursor = new_cursor;
+                state = new_state;
+            }
+            (_, QExchangeResult::SQCompleted(q)) => return StreamQuery::new(&q),
+            (_, QExchangeResult::Error) => panic!(),
+        }
+    }
+}
+
+#[test]
+fn streams_run_concurrently() {
+    let base = ClientLocalState::new_local_root();
+    let mut streams = Streams::new();
+    // every stream gets its own session
+    let (mut s1, q1) = streams
+        .submit(1, stream_query("use myspace"), &base)
+        .unwrap()
+        .unwrap();
+    assert_eq!(q1.squery().query_str(), "use myspace");
+    assert_eq!(s1, base.new_session());
+    // stream 2 doesn't wait for stream 1
+    let (s2, q2) = streams
+        .submit(2, stream_query("inspect global"), &base)
+        .unwrap()
+        .unwrap();
+    assert_eq!(q2.squery().query_str(), "inspect global");
+    // but the statements of a stream wait for the one that is running
+    assert_eq!(
+        streams.submit(1, stream_query("select 1"), &base).unwrap(),
+        None
+    );
+    assert_eq!(
+        streams.submit(1, stream_query("select 2"), &base).unwrap(),
+        None
+    );
+    assert_eq!(streams.complete(2, s2), None);
+    // and then run in order, on the same session
+    s1.set_cs("myspace".into());
+    let (s1, q) = streams.complete(1, s1).unwrap();
+    assert_eq!(q.squery().query_str(), "select 1");
+    assert_eq!(s1.get_cs(), Some("myspace"));
+    let (s1, q) = streams.complete(1, s1).unwrap();
+    assert_eq!(q.squery().query_str(), "select 2");
+    assert_eq!(streams.complete(1, s1), None);
+    // the stream keeps its session for the next statement
+    let (s1, _) = streams
+        .submit(1, stream_query("select 3"), &base)
+        .unwrap()
+        .unwrap();
+    assert_eq!(s1.get_cs(), Some("myspace"));
+}
+
+#[test]
+fn streams_limit() {
+    let base = ClientLocalState::new_local_root();
+    let mut streams = Streams::new();
+    let mut sessions: Vec<_> = (0..mux::MAX_STREAMS as u64)
+        .map(|id| {
+            streams
+                .submit(id, stream_query("select 1"), &base)
+                .unwrap()
+                .unwrap()
+                .0
+        })
+        .collect();
+    assert_eq!(
+        streams
+            .submit(mux::MAX_STREAMS as u64, stream_query("select 1"), &base)
+            .unwrap_err(),
+        QueryError::SysNetworkSystemIllegalClientPacket
+    );
+    // the streams that we have can still be used
+    assert_eq!(streams.complete(0, sessions.remove(0)), None);
+    assert!(streams
+        .submit(0, stream_query("select 1"), &base)
+        .unwrap()
+        .is_some());
+}
@@ -0,0 +1,171 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    PROXY protocol (v2)
+    ---
+    When skyd sits behind a load balancer, the peer address of every connection is the balancer's.
+    If enabled for an endpoint, the balancer prepends a binary PROXY v2 header to every connection
+    and we read the real client address off of it before doing anything else with the stream.
+    See: https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+*/
+
+use {
+    super::IoResult,
+    std::{
+        io::{Error as IoError, ErrorKind},
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    },
+    tokio::io::{AsyncRead, AsyncReadExt},
+};
+
+/// The fixed signature that every v2 header starts with
+const SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// signature + version/command + family + length
+const HEADER_LEN: usize = 16;
+/// We don't care about TLVs, but we won't buffer an arbitrarily large header either
+const MAX_PAYLOAD_LEN: usize = 512;
+
+const VERSION: u8 = 0x2;
+const CMD_LOCAL: u8 = 0x0;
+const CMD_PROXY: u8 = 0x1;
+const FAM_TCP4: u8 = 0x11;
+const FAM_TCP6: u8 = 0x21;
+const ADDR_LEN_TCP4: usize = 12;
+const ADDR_LEN_TCP6: usize = 36;
+
+fn err_bad_header() -> IoError {
+    IoError::new(ErrorKind::InvalidData, "invalid PROXY v2 header")
+}
+
+/// Read a PROXY v2 header from the connection, returning the source address of the proxied
+/// connection.
+///
+/// `None` is returned if the proxy is talking to us on its own behalf (`LOCAL`, for example a
+/// health check) or if it proxied a connection for an address family we don't track.
+pub async fn read_v2_header<S: AsyncRead + Unpin>(con: &mut S) -> IoResult<Option<SocketAddr>> {
+    let mut header = [0u8; HEADER_LEN];
+    con.read_exact(&mut header).await?;
+    let payload_len = u16::from_be_bytes([header[14], header[15]]) as usize;
+    if header[..12] != SIGNATURE || payload_len > MAX_PAYLOAD_LEN {
+        return Err(err_bad_header());
+    }
+    let mut payload = [0u8; MAX_PAYLOAD_LEN];
+    let payload = &mut payload[..payload_len];
+    con.read_exact(payload).await?;
+    decode_v2(header[12], header[13], payload)
+}
+
+/// Decode the source address given the version/command byte, the family byte and the payload
+fn decode_v2(ver_cmd: u8, fam: u8, payload: &[u8]) -> IoResult<Option<SocketAddr>> {
+    if ver_cmd >> 4 != VERSION {
+        return Err(err_bad_header());
+    }
+    match ver_cmd & 0x0F {
+        CMD_LOCAL => return Ok(None),
+        CMD_PROXY => {}
+        _ => return Err(err_bad_header()),
+    }
+    match fam {
+        FAM_TCP4 if payload.len() >= ADDR_LEN_TCP4 => {
+            let ip: [u8; 4] = payload[..4].try_into().unwrap();
+            let port = u16::from_be_bytes([payload[8], payload[9]]);
+            Ok(Some(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::from(ip),
+                port,
+            ))))
+        }
+        FAM_TCP6 if payload.len() >= ADDR_LEN_TCP6 => {
+            let ip: [u8; 16] = payload[..16].try_into().unwrap();
+            let port = u16::from_be_bytes([payload[32], payload[33]]);
+            Ok(Some(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(ip),
+                port,
+                0,
+                0,
+            ))))
+        }
+        FAM_TCP4 | FAM_TCP6 => Err(err_bad_header()),
+        // UNSPEC, UDP or UNIX; nothing useful for us
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    fn header(ver_cmd: u8, fam: u8, payload: &[u8]) -> Vec<u8> {
+        let mut h = super::SIGNATURE.to_vec();
+        h.push(ver_cmd);
+        h.push(fam);
+        h.extend((payload.len() as u16).to_be_bytes());
+        h.extend(payload);
+        h
+    }
+    async fn read(data: Vec<u8>) -> std::io::Result<Option<SocketAddr>> {
+        super::read_v2_header(&mut data.as_slice()).await
+    }
+    #[tokio::test]
+    async fn proxy_tcp4() {
+        let payload = [192, 168, 1, 10, 10, 0, 0, 1, 0x1F, 0x90, 0x07, 0xD3];
+        assert_eq!(
+            read(header(0x21, 0x11, &payload)).await.unwrap(),
+            Some("192.168.1.10:8080".parse().unwrap())
+        );
+    }
+    #[tokio::test]
+    async fn proxy_tcp6() {
+        let mut payload = vec![0; 36];
+        payload[15] = 1;
+        payload[31] = 1;
+        payload[32..34].copy_from_slice(&8080u16.to_be_bytes());
+        payload[34..36].copy_from_slice(&2003u16.to_be_bytes());
+        assert_eq!(
+            read(header(0x21, 0x21, &payload)).await.unwrap(),
+            Some("[::1]:8080".parse().unwrap())
+        );
+    }
+    #[tokio::test]
+    async fn proxy_local() {
+        assert_eq!(read(header(0x20, 0x00, &[])).await.unwrap(), None);
+    }
+    #[tokio::test]
+    async fn proxy_bad() {
+        // bad signature
+        let mut h = header(0x21, 0x11, &[0; 12]);
+        h[0] = b'X';
+        assert!(read(h).await.is_err());
+        // bad version
+        assert!(read(header(0x11, 0x11, &[0; 12])).await.is_err());
+        // truncated address
+        assert!(read(header(0x21, 0x11, &[0; 4])).await.is_err());
+        // truncated stream
+        let mut h = header(0x21, 0x11, &[0; 12]);
+        h.truncate(20);
+        assert!(read(h).await.is_err());
+    }
+}
@@ -0,0 +1,116 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::Listener,
+    crate::engine::{
+        config::{ConfigEndpointTls, TlsFiles},
+        error::RuntimeResult,
+    },
+    openssl::{
+        error::ErrorStack,
+        ssl::{Ssl, SslAcceptor},
+    },
+    parking_lot::{Mutex, RwLock},
+    std::{
+        fs,
+        time::{Duration, SystemTime},
+    },
+    tokio::sync::Notify,
+};
+
+/// How often we check the certificate files for changes
+const TLS_WATCH_INTERVAL: Duration = Duration::from_secs(60);
+/// Explicit reload requests (`sysctl reload tls`)
+static TLS_RELOAD: Notify = Notify::const_new();
+
+/// Request the TLS endpoints (if any) to reload its certificate and private key from disk
+pub fn request_reload() {
+    TLS_RELOAD.notify_one()
+}
+
+/// Modification times of the certificate and the private key
+type FileStamp = Option<(SystemTime, SystemTime)>;
+
+fn file_stamp(files: &TlsFiles) -> FileStamp {
+    let mtime = |path: &str| fs::metadata(path).and_then(|md| md.modified()).ok();
+    Some((mtime(files.cert())?, mtime(files.private_key())?))
+}
+
+/// A TLS acceptor that can be swapped out while the server is running.
+///
+/// A reload only affects new connections; sessions that were already established keep using the
+/// context they were accepted with
+pub struct TlsAcceptor {
+    files: TlsFiles,
+    acceptor: RwLock<SslAcceptor>,
+    stamp: Mutex<FileStamp>,
+}
+
+impl TlsAcceptor {
+    pub fn new(tls: &ConfigEndpointTls) -> RuntimeResult<Self> {
+        let acceptor = Listener::init_tls(tls.cert(), tls.private_key(), tls.pkey_pass())?;
+        Ok(Self {
+            files: tls.files().clone(),
+            acceptor: RwLock::new(acceptor),
+            stamp: Mutex::new(file_stamp(tls.files())),
+        })
+    }
+    /// Create a new SSL session for an incoming connection
+    pub fn new_ssl(&self) -> Result<Ssl, ErrorStack> {
+        Ssl::new(self.acceptor.read().context())
+    }
+    fn reload(&self) -> RuntimeResult<()> {
+        let stamp = file_stamp(&self.files);
+        let cert = fs::read_to_string(self.files.cert())?;
+        let private_key = fs::read_to_string(self.files.private_key())?;
+        let pkey_pass = fs::read_to_string(self.files.pkey_pass())?;
+        let acceptor = Listener::init_tls(&cert, &private_key, &pkey_pass)?;
+        *self.acceptor.write() = acceptor;
+        *self.stamp.lock() = stamp;
+        Ok(())
+    }
+    /// Reload the acceptor whenever the certificate files change on disk or when a reload is
+    /// explicitly requested. If the new certificate fails to load, we keep the current one
+    pub async fn watch(&self) {
+        let mut interval = tokio::time::interval(TLS_WATCH_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if file_stamp(&self.files) == *self.stamp.lock() {
+                        continue;
+                    }
+                    info!("TLS certificate files changed on disk. reloading");
+                }
+                _ = TLS_RELOAD.notified() => info!("reloading TLS configuration"),
+            }
+            match self.reload() {
+                Ok(()) => info!("TLS configuration reloaded"),
+                Err(e) => error!("failed to reload TLS configuration (keeping current): {e}"),
+            }
+        }
+    }
+}
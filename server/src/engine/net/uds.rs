@@ -0,0 +1,146 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    unix domain socket endpoints
+    ---
+    A UDS endpoint can only be reached from this machine, and the permissions of the socket file
+    decide who can connect. That's why it is the only kind of endpoint that can let its clients in
+    without a password (`uds@path?auth=off`), in which case a client is logged in as the user that
+    it names in its handshake.
+*/
+
+use {
+    super::{ConnectionHandler, IoResult, NetBackoff, CLIM},
+    crate::engine::{
+        config::ConfigEndpointUds,
+        error::RuntimeResult,
+        fractal::{error::ErrorContext, Global},
+    },
+    std::{fs, os::unix::fs::FileTypeExt},
+    tokio::{
+        net::{UnixListener, UnixStream},
+        sync::{broadcast, mpsc},
+    },
+};
+
+/// A listener bound to a Unix domain socket
+pub struct UdsListener {
+    global: Global,
+    listener: UnixListener,
+    path: String,
+    auth: bool,
+    sig_shutdown: broadcast::Sender<()>,
+    sig_inflight: mpsc::Sender<()>,
+    sig_inflight_wait: mpsc::Receiver<()>,
+}
+
+impl UdsListener {
+    pub fn new(
+        uds: &ConfigEndpointUds,
+        global: Global,
+        sig_shutdown: broadcast::Sender<()>,
+    ) -> RuntimeResult<Self> {
+        let path = uds.path();
+        // a socket that was left behind by a server that didn't shut down cleanly would fail the bind, but one that
+        // someone is still listening on is left alone
+        let stale = fs::symlink_metadata(path).is_ok_and(|md| md.file_type().is_socket())
+            && std::os::unix::net::UnixStream::connect(path).is_err();
+        if stale {
+            let _ = fs::remove_file(path);
+        }
+        let (sig_inflight, sig_inflight_wait) = mpsc::channel(1);
+        let listener =
+            UnixListener::bind(path).set_dmsg(format!("failed to bind to socket `{path}`"))?;
+        Ok(Self {
+            global,
+            listener,
+            path: path.into(),
+            auth: uds.auth(),
+            sig_shutdown,
+            sig_inflight,
+            sig_inflight_wait,
+        })
+    }
+    pub async fn terminate(self) {
+        let Self {
+            listener,
+            path,
+            mut sig_inflight_wait,
+            sig_inflight,
+            sig_shutdown,
+            ..
+        } = self;
+        drop(listener);
+        drop(sig_shutdown);
+        drop(sig_inflight);
+        // wait for the connections to close
+        let _ = sig_inflight_wait.recv().await;
+        // nobody can connect anymore, so don't leave the socket behind
+        let _ = fs::remove_file(path);
+    }
+    async fn accept(&mut self) -> IoResult<UnixStream> {
+        let backoff = NetBackoff::new();
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => return Ok(stream),
+                Err(e) => {
+                    if backoff.should_disconnect() {
+                        return Err(e);
+                    }
+                }
+            }
+            backoff.spin().await;
+        }
+    }
+    pub async fn listen(&mut self) {
+        loop {
+            // acquire a permit
+            let permit = CLIM.acquire().await.unwrap();
+            let stream = match self.accept().await {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("failed to accept connection on UDS socket: `{e}`");
+                    continue;
+                }
+            };
+            let global = self.global.clone();
+            let auth = self.auth;
+            let sig_shutdown = self.sig_shutdown.subscribe();
+            let sig_inflight = self.sig_inflight.clone();
+            let path = self.path.clone();
+            tokio::spawn(async move {
+                let mut handler =
+                    ConnectionHandler::new(stream, global, auth, sig_shutdown, sig_inflight);
+                if let Err(e) = handler.run().await {
+                    warn!("error handling client connection on `{path}`: `{e}`");
+                }
+            });
+            // return the permit
+            drop(permit);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    explain
+    ---
+    `explain <select>` asks for the plan of a select (the access path, an estimate of the rows and the filters that
+    are pushed down into the scan) instead of running it. the plan is produced by the executor (see
+    `core::dml::explain`)
+*/
+
+use {
+    super::{traits::ASTNode, QueryData, State},
+    crate::engine::{
+        error::{QueryError, QueryResult},
+        ql::dml::sel::{SelectAllStatement, SelectStatement},
+    },
+};
+
+#[derive(Debug, PartialEq)]
+/// `explain select ...` or `explain select all ...`
+pub enum Explain<'a> {
+    Select(SelectStatement<'a>),
+    SelectAll(SelectAllStatement<'a>),
+}
+
+impl<'a> ASTNode<'a> for Explain<'a> {
+    const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+    const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+    fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+        state: &mut State<'a, Qd>,
+    ) -> QueryResult<Self> {
+        if state.exhausted() || !state.cursor_eq(Token![select]) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        state.cursor_ahead();
+        if state.cursor_rounded_eq(Token![all]) {
+            state.cursor_ahead();
+            SelectAllStatement::__base_impl_parse_from_state(state).map(Self::SelectAll)
+        } else {
+            SelectStatement::parse_select(state).map(Self::Select)
+        }
+    }
+}
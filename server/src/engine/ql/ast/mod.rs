@@ -24,6 +24,7 @@
  *
 */
 
+pub mod explain;
 pub mod traits;
 
 #[cfg(test)]
@@ -51,6 +52,8 @@ pub struct State<'a, Qd> {
     i: usize,
     f: bool,
     cs: Option<&'static str>,
+    /// ignore any tokens after the end of the statement (`set strict_parsing = false`)
+    lenient: bool,
 }
 
 impl<'a> State<'a, InplaceData> {
@@ -142,6 +145,7 @@ impl<'a, Qd: QueryData<'a>> State<'a, Qd> {
             t,
             d,
             cs: None,
+            lenient: false,
         }
     }
     #[inline(always)]
@@ -284,6 +288,15 @@ impl<'a, Qd: QueryData<'a>> State<'a, Qd> {
             & self.has_remaining(3)
     }
     #[inline(always)]
+    /// Check if the current token stream matches the signature of an arity(1) fn (with any single token as the
+    /// argument); rounded
+    pub(crate) fn cursor_signature_match_fn_arity1_rounded(&self) -> bool {
+        (self.offset_current_r(0).is_ident())
+            & (Token![() open].eq(self.offset_current_r(1)))
+            & (Token![() close].eq(self.offset_current_r(3)))
+            & self.has_remaining(4)
+    }
+    #[inline(always)]
     /// Reads a lit using the given token and the internal data source and return a data type
     ///
     /// ## Safety
@@ -326,6 +339,19 @@ impl<'a, Qd: QueryData<'a>> State<'a, Qd> {
             }
         }
     }
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+    /// The end of statement check that every statement goes through once it has been parsed. Any tokens that are
+    /// left over fail the statement with [`QueryError::QLUnexpectedTrailingTokens`] and the cursor is left at the
+    /// first of them (so that the error can point at it), unless the state is lenient
+    pub fn ensure_end_of_statement(&self) -> QueryResult<()> {
+        if self.not_exhausted() & !self.lenient {
+            compiler::cold_rerr(QueryError::QLUnexpectedTrailingTokens)
+        } else {
+            Ok(())
+        }
+    }
     pub fn ensure_minimum_for_blocking_stmt(&self) -> QueryResult<()> {
         if self.remaining() < 2 {
             return Err(QueryError::QLExpectedStatement);
@@ -54,9 +54,7 @@ pub trait ASTNode<'a>: Sized {
         }
         if Self::MUST_USE_FULL_TOKEN_RANGE {
             if !Self::VERIFIES_FULL_TOKEN_RANGE_USAGE {
-                if state.not_exhausted() {
-                    return Err(QueryError::QLInvalidSyntax);
-                }
+                state.ensure_end_of_statement()?;
             }
         }
         Ok(r)
@@ -104,7 +102,10 @@ pub trait ASTNode<'a>: Sized {
     fn from_insecure_tokens_full(tok: &'a [Token<'a>]) -> QueryResult<Self> {
         let mut state = State::new(tok, InplaceData::new());
         let r = <Self as ASTNode>::test_parse_from_state(&mut state)?;
-        assert!(state.exhausted());
+        if !state.exhausted() {
+            // the statement ended before the tokens did
+            return Err(QueryError::QLInvalidSyntax);
+        }
         Ok(r)
     }
     #[cfg(test)]
@@ -115,7 +116,10 @@ pub trait ASTNode<'a>: Sized {
         let mut state = State::new(tok, InplaceData::new());
         state.set_space(space_name);
         let r = <Self as ASTNode>::test_parse_from_state(&mut state)?;
-        assert!(state.exhausted());
+        if !state.exhausted() {
+            // the statement ended before the tokens did
+            return Err(QueryError::QLInvalidSyntax);
+        }
         Ok(r)
     }
     #[cfg(test)]
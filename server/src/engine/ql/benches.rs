@@ -0,0 +1,116 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    parse throughput
+    ---
+    these benchmarks lex (and parse) the point queries that make up most workloads. `lex_point_queries_heap` collects
+    the tokens into a `Vec`, which is what every statement used to pay for before the tokens were kept in an inline
+    buffer. run them with `cargo +nightly bench --features nightly -p skyd ql::benches`
+*/
+
+extern crate test;
+
+use {
+    crate::engine::{
+        core::scratch::Scratch,
+        ql::{
+            ast::{traits::ASTNode, State},
+            dml::sel::SelectStatement,
+            lex::{SecureLexer, Token},
+        },
+    },
+    test::{black_box, Bencher},
+};
+
+const POINT_QUERIES: [(&[u8], &[u8]); 4] = [
+    (
+        b"select * from myspace.mymodel where username = ?",
+        b"\x065\nsayan",
+    ),
+    (
+        b"select email, followers from myspace.mymodel where username = ?",
+        b"\x065\nsayan",
+    ),
+    (
+        b"insert into myspace.mymodel(?, ?, ?)",
+        b"\x065\nsayan\x067\npass123\x021000\n",
+    ),
+    (
+        b"update myspace.mymodel set followers += ? where username = ?",
+        b"\x021\n\x065\nsayan",
+    ),
+];
+
+#[bench]
+fn lex_point_queries(b: &mut Bencher) {
+    b.iter(|| {
+        for (query, params) in POINT_QUERIES {
+            let tokens = SecureLexer::new_with_segments(query, params).lex().unwrap();
+            black_box(&tokens[..]);
+        }
+    })
+}
+
+#[bench]
+fn lex_point_queries_heap(b: &mut Bencher) {
+    b.iter(|| {
+        for (query, params) in POINT_QUERIES {
+            let tokens: Vec<Token> = SecureLexer::new_with_segments(query, params)
+                .lex()
+                .unwrap()
+                .into_iter()
+                .collect();
+            black_box(&tokens[..]);
+        }
+    })
+}
+
+#[bench]
+fn lex_point_queries_recycled(b: &mut Bencher) {
+    let mut scratch = Scratch::new();
+    b.iter(|| {
+        for (query, params) in POINT_QUERIES {
+            let tokens = SecureLexer::new_with_segments_in(query, params, scratch.take_tokens())
+                .lex()
+                .unwrap();
+            black_box(&tokens[..]);
+            scratch.recycle_tokens(tokens);
+        }
+    })
+}
+
+#[bench]
+fn parse_point_select(b: &mut Bencher) {
+    let (query, params) = POINT_QUERIES[0];
+    b.iter(|| {
+        let tokens = SecureLexer::new_with_segments(query, params).lex().unwrap();
+        // skip `select`
+        let select: SelectStatement =
+            ASTNode::parse_from_state_hardened(&mut State::new_inplace(&tokens[1..])).unwrap();
+        black_box(select);
+    })
+}
@@ -25,6 +25,7 @@
 */
 
 use crate::engine::{
+    core::EntityIDRef,
     data::DictGeneric,
     error::{QueryError, QueryResult},
     ql::{
@@ -44,11 +45,38 @@ pub enum SysctlCommand<'a> {
     AlterUser(UserDecl<'a>),
     /// `sysctl status`
     ReportStatus,
+    /// `sysctl reload tls`
+    ReloadTls,
+    /// `sysctl issue token`
+    IssueToken,
+    /// `sysctl revoke token <token>`
+    RevokeToken(&'a str),
+    /// `sysctl set read_only = <true|false>`
+    SetReadOnly(bool),
+    /// `sysctl compact model <space>.<model>`
+    CompactModel(EntityIDRef<'a>),
+    /// `sysctl cancel job <id>`
+    CancelJob(u64),
+    /// `sysctl cancel query <id>`
+    CancelQuery(u64),
+    /// `sysctl import from '<url>' space <name> [with { ... }]`
+    ImportSpace(ImportDecl<'a>),
+    /// `sysctl post notice <message>`
+    PostNotice(&'a str),
+    /// `sysctl cache model <space>.<model>`
+    CacheModel(EntityIDRef<'a>),
+    /// `sysctl uncache model <space>.<model>`
+    UncacheModel(EntityIDRef<'a>),
+    /// `sysctl undrop model <space>.<model>`
+    UndropModel(EntityIDRef<'a>),
 }
 
 impl<'a> SysctlCommand<'a> {
     pub fn needs_root(&self) -> bool {
-        !matches!(self, Self::ReportStatus)
+        !matches!(
+            self,
+            Self::ReportStatus | Self::IssueToken | Self::RevokeToken(_) | Self::CancelQuery(_)
+        )
     }
 }
 
@@ -66,7 +94,35 @@ impl<'a> traits::ASTNode<'a> for SysctlCommand<'a> {
         let create = Token![create].eq(a) & b.ident_eq("user");
         let drop = Token![drop].eq(a) & b.ident_eq("user");
         let status = a.ident_eq("report") & b.ident_eq("status");
-        if !(create | drop | status | alter) {
+        let reload_tls = a.ident_eq("reload") & b.ident_eq("tls");
+        let issue_token = a.ident_eq("issue") & b.ident_eq("token");
+        let revoke_token = a.ident_eq("revoke") & b.ident_eq("token");
+        let set_read_only = Token![set].eq(a) & b.ident_eq("read_only");
+        let compact_model = a.ident_eq("compact") & Token![model].eq(b);
+        let cancel_job = a.ident_eq("cancel") & b.ident_eq("job");
+        let cancel_query = a.ident_eq("cancel") & b.ident_eq("query");
+        let import = a.ident_eq("import") & Token![from].eq(b);
+        let post_notice = a.ident_eq("post") & b.ident_eq("notice");
+        let cache_model = a.ident_eq("cache") & Token![model].eq(b);
+        let uncache_model = a.ident_eq("uncache") & Token![model].eq(b);
+        let undrop_model = a.ident_eq("undrop") & Token![model].eq(b);
+        if !(create
+            | drop
+            | status
+            | alter
+            | reload_tls
+            | issue_token
+            | revoke_token
+            | set_read_only
+            | compact_model
+            | cancel_job
+            | cancel_query
+            | import
+            | post_notice
+            | cache_model
+            | uncache_model
+            | undrop_model)
+        {
             return Err(QueryError::QLUnknownStatement);
         }
         if create {
@@ -75,12 +131,90 @@ impl<'a> traits::ASTNode<'a> for SysctlCommand<'a> {
             UserDel::parse(state).map(SysctlCommand::DropUser)
         } else if alter {
             UserDecl::parse(state).map(SysctlCommand::AlterUser)
+        } else if reload_tls {
+            Ok(SysctlCommand::ReloadTls)
+        } else if issue_token {
+            Ok(SysctlCommand::IssueToken)
+        } else if revoke_token {
+            parse_string(state).map(SysctlCommand::RevokeToken)
+        } else if set_read_only {
+            parse_set_bool(state).map(SysctlCommand::SetReadOnly)
+        } else if compact_model {
+            state
+                .try_entity_ref_result()
+                .map(SysctlCommand::CompactModel)
+        } else if cancel_job {
+            parse_id(state).map(SysctlCommand::CancelJob)
+        } else if cancel_query {
+            parse_id(state).map(SysctlCommand::CancelQuery)
+        } else if import {
+            ImportDecl::parse(state).map(SysctlCommand::ImportSpace)
+        } else if post_notice {
+            parse_string(state).map(SysctlCommand::PostNotice)
+        } else if cache_model {
+            state.try_entity_ref_result().map(SysctlCommand::CacheModel)
+        } else if uncache_model {
+            state
+                .try_entity_ref_result()
+                .map(SysctlCommand::UncacheModel)
+        } else if undrop_model {
+            state
+                .try_entity_ref_result()
+                .map(SysctlCommand::UndropModel)
         } else {
             Ok(SysctlCommand::ReportStatus)
         }
     }
 }
 
+/// Parse a single string (the token in `revoke token <token>` or the message in `post notice <message>`)
+fn parse_string<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<&'a str> {
+    if state.not_exhausted() && state.can_read_lit_rounded() {
+        let token = unsafe {
+            // UNSAFE(@ohsayan): +boundck
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        if let Some(token) = token.try_str() {
+            return Ok(token);
+        }
+    }
+    Err(QueryError::QLInvalidSyntax)
+}
+
+/// Parse the ID in `cancel job <id>` or `cancel query <id>`
+fn parse_id<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<u64> {
+    if state.not_exhausted() && state.can_read_lit_rounded() {
+        let id = unsafe {
+            // UNSAFE(@ohsayan): +boundck
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        if let Some(id) = id.try_uint() {
+            return Ok(id);
+        }
+    }
+    Err(QueryError::QLInvalidSyntax)
+}
+
+/// Parse the value in `set <setting> = <true|false>`
+fn parse_set_bool<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<bool> {
+    if state.has_remaining(2) && state.cursor_eq(Token![=]) {
+        state.cursor_ahead();
+        if state.can_read_lit_rounded() {
+            let value = unsafe {
+                // UNSAFE(@ohsayan): +boundck
+                state.read_cursor_lit_unchecked()
+            };
+            state.cursor_ahead();
+            if let Some(value) = value.try_bool() {
+                return Ok(value);
+            }
+        }
+    }
+    Err(QueryError::QLInvalidSyntax)
+}
+
 fn parse<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<UserMeta<'a>> {
     /*
         [username] with { password: [password], ... }
@@ -105,8 +239,7 @@ fn parse<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<UserMe
         // UNSAFE(@ohsayan): the dict parse ensures state correctness
         token_buffer[0].uck_read_ident()
     };
-    if state.not_exhausted() | !state.okay() {
-        // we shouldn't have more tokens
+    if !state.okay() {
         return Err(QueryError::QLInvalidSyntax);
     }
     Ok(UserMeta {
@@ -154,10 +287,8 @@ impl<'a> UserDel<'a> {
         Self { username }
     }
     /// Parse a `user del` DCL command
-    ///
-    /// MUSTENDSTREAM: YES
     pub fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
-        if state.cursor_has_ident_rounded() & (state.remaining() == 1) {
+        if state.cursor_has_ident_rounded() & state.not_exhausted() {
             let username = unsafe {
                 // UNSAFE(@ohsayan): +boundck
                 state.read().uck_read_ident()
@@ -171,3 +302,73 @@ impl<'a> UserDel<'a> {
         self.username.as_str()
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct ImportDecl<'a> {
+    url: &'a str,
+    space: Ident<'a>,
+    options: DictGeneric,
+}
+
+impl<'a> ImportDecl<'a> {
+    pub(in crate::engine::ql) fn new(url: &'a str, space: Ident<'a>, options: DictGeneric) -> Self {
+        Self {
+            url,
+            space,
+            options,
+        }
+    }
+    /// Parse `'<url>' space <name> [with { ... }]`
+    pub fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        if (state.remaining() < 3) | !state.can_read_lit_rounded() {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let url = unsafe {
+            // UNSAFE(@ohsayan): +boundck
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        let Some(url) = url.try_str() else {
+            return Err(QueryError::QLInvalidSyntax);
+        };
+        if !state.cursor_eq(Token![space]) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        state.cursor_ahead();
+        if !state.cursor_is_ident() {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let space = unsafe {
+            // UNSAFE(@ohsayan): +tokck
+            state.fw_read().uck_read_ident()
+        };
+        let options = if state.cursor_rounded_eq(Token![with]) {
+            state.cursor_ahead();
+            if state.remaining() < 2 {
+                return Err(QueryError::QLInvalidSyntax);
+            }
+            let Some(dict) = syn::parse_dict(state) else {
+                return Err(QueryError::QLInvalidCollectionSyntax);
+            };
+            dict
+        } else {
+            DictGeneric::new()
+        };
+        if !state.okay() {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        Ok(Self::new(url, space, options))
+    }
+    pub fn url(&self) -> &str {
+        self.url
+    }
+    pub fn space(&self) -> &str {
+        self.space.as_str()
+    }
+    pub fn options_mut(&mut self) -> &mut DictGeneric {
+        &mut self.options
+    }
+    pub fn options(&self) -> &DictGeneric {
+        &self.options
+    }
+}
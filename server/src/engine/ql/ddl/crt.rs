@@ -33,7 +33,7 @@ use {
             error::{QueryError, QueryResult},
             ql::{
                 ast::{QueryData, State},
-                lex::Ident,
+                lex::{Ident, Token},
             },
         },
         util::compiler,
@@ -109,6 +109,8 @@ pub struct CreateModel<'a> {
     pub(in crate::engine) props: DictGeneric,
     /// if not exists
     pub(in crate::engine) if_not_exists: bool,
+    /// the model to copy the schema and data from (`as snapshot of <model>`)
+    pub(in crate::engine) snapshot_of: Option<EntityIDRef<'a>>,
 }
 
 /*
@@ -116,6 +118,8 @@ pub struct CreateModel<'a> {
     create model mymodel(
         [primary|null] ident: type,
     )
+    or a snapshot of an existing model (optionally masked; see `core::model::mask`):
+    create model mymodel as snapshot of othermodel [with { mask: { field: 'hash' } }]
 */
 
 impl<'a> CreateModel<'a> {
@@ -131,9 +135,69 @@ impl<'a> CreateModel<'a> {
             fields,
             props,
             if_not_exists,
+            snapshot_of: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_snapshot(
+        model_name: EntityIDRef<'a>,
+        snapshot_of: EntityIDRef<'a>,
+        if_not_exists: bool,
+    ) -> Self {
+        Self {
+            model_name,
+            fields: vec![],
+            props: DictGeneric::new(),
+            if_not_exists,
+            snapshot_of: Some(snapshot_of),
+        }
+    }
+    /// Returns true if this is a `create model <model> as snapshot of <model>` statement
+    fn sig_snapshot<Qd: QueryData<'a>>(state: &State<'a, Qd>) -> bool {
+        let start = sig_if_not_exists(state) as usize * 3;
+        let entity_len = if Token![.].eq(state.offset_current_r(start + 1)) {
+            3
+        } else {
+            1
+        };
+        Token![as].eq(state.offset_current_r(start + entity_len))
+    }
+    fn parse_snapshot<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        let if_not_exists = sig_if_not_exists(state);
+        state.cursor_ahead_by(if_not_exists as usize * 3);
+        let model_name = state.try_entity_ref_result()?;
+        state.cursor_ahead(); // +AS
+                              // `snapshot of <model>`
+        if compiler::unlikely(state.remaining() < 3) {
+            return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
+        }
+        let (snapshot, of) = (state.fw_read(), state.fw_read());
+        if !(snapshot.ident_eq("snapshot") & Token![of].eq(of)) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let snapshot_of = state.try_entity_ref_result()?;
+        // masking profile
+        let mut props = DictGeneric::new();
+        if state.cursor_rounded_eq(Token![with]) {
+            state.cursor_ahead();
+            syn::rfold_dict(DictFoldState::OB, state, &mut props);
+        }
+        if state.okay() {
+            Ok(Self {
+                model_name,
+                fields: vec![],
+                props,
+                if_not_exists,
+                snapshot_of: Some(snapshot_of),
+            })
+        } else {
+            Err(QueryError::QLInvalidSyntax)
         }
     }
     fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        if Self::sig_snapshot(state) {
+            return Self::parse_snapshot(state);
+        }
         if compiler::unlikely(state.remaining() < 10) {
             return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
         }
@@ -173,6 +237,7 @@ impl<'a> CreateModel<'a> {
                 fields,
                 props,
                 if_not_exists,
+                snapshot_of: None,
             })
         } else {
             Err(QueryError::QLInvalidSyntax)
@@ -180,9 +245,73 @@ impl<'a> CreateModel<'a> {
     }
 }
 
+#[derive(Debug, PartialEq)]
+/// A secondary index on a field of a model
+pub struct CreateIndex<'a> {
+    /// the index name (unique within the model)
+    pub(in crate::engine) index_name: Ident<'a>,
+    /// the model
+    pub(in crate::engine) model_name: EntityIDRef<'a>,
+    /// the indexed field
+    pub(in crate::engine) field: Ident<'a>,
+    /// if not exists
+    pub(in crate::engine) if_not_exists: bool,
+}
+
+/*
+    index definition:
+    create index [if not exists] myindex on myspace.mymodel(field)
+*/
+
+impl<'a> CreateIndex<'a> {
+    #[cfg(test)]
+    pub fn new(
+        index_name: Ident<'a>,
+        model_name: EntityIDRef<'a>,
+        field: Ident<'a>,
+        if_not_exists: bool,
+    ) -> Self {
+        Self {
+            index_name,
+            model_name,
+            field,
+            if_not_exists,
+        }
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        // smallest declaration: `create index myindex on mymodel(field)` -> >= 6 tokens
+        if compiler::unlikely(state.remaining() < 6) {
+            return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
+        }
+        let if_not_exists = sig_if_not_exists(state);
+        state.cursor_ahead_by(if_not_exists as usize * 3);
+        if compiler::unlikely(state.remaining() < 6) {
+            return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
+        }
+        let index_name = match (state.fw_read(), state.fw_read()) {
+            (Token::Ident(index_name), Token![on]) => *index_name,
+            _ => return Err(QueryError::QLInvalidSyntax),
+        };
+        let model_name = state.try_entity_ref_result()?;
+        // `(field)`
+        if compiler::unlikely(state.remaining() < 3) {
+            return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
+        }
+        match (state.fw_read(), state.fw_read(), state.fw_read()) {
+            (Token![() open], Token::Ident(field), Token![() close]) => Ok(Self {
+                index_name,
+                model_name,
+                field: *field,
+                if_not_exists,
+            }),
+            _ => Err(QueryError::QLInvalidSyntax),
+        }
+    }
+}
+
 mod impls {
     use {
-        super::{CreateModel, CreateSpace},
+        super::{CreateIndex, CreateModel, CreateSpace},
         crate::engine::{
             error::QueryResult,
             ql::ast::{traits::ASTNode, QueryData, State},
@@ -206,4 +335,13 @@ mod impls {
             Self::parse(state)
         }
     }
+    impl<'a> ASTNode<'a> for CreateIndex<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse(state)
+        }
+    }
 }
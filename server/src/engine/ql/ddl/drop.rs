@@ -29,7 +29,7 @@ use crate::engine::{
     error::{QueryError, QueryResult},
     ql::{
         ast::{QueryData, State},
-        lex::Ident,
+        lex::{Ident, Token},
     },
 };
 
@@ -138,9 +138,65 @@ impl<'a> DropModel<'a> {
     }
 }
 
+#[derive(Debug, PartialEq)]
+/// A `truncate model` query, which removes all the rows in a model but keeps the model itself
+pub struct TruncateModel<'a> {
+    pub(in crate::engine) entity: EntityIDRef<'a>,
+}
+
+impl<'a> TruncateModel<'a> {
+    #[inline(always)]
+    pub fn new(entity: EntityIDRef<'a>) -> Self {
+        Self { entity }
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        /*
+            truncate model <model>
+            (the `truncate model` part has already been consumed)
+        */
+        if state.exhausted() {
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
+        state.try_entity_ref_result().map(Self::new)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A `drop index <index> on <model>` query
+pub struct DropIndex<'a> {
+    pub(in crate::engine) index_name: Ident<'a>,
+    pub(in crate::engine) entity: EntityIDRef<'a>,
+    pub(in crate::engine) if_exists: bool,
+}
+
+impl<'a> DropIndex<'a> {
+    #[inline(always)]
+    pub fn new(index_name: Ident<'a>, entity: EntityIDRef<'a>, if_exists: bool) -> Self {
+        Self {
+            index_name,
+            entity,
+            if_exists,
+        }
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        let if_exists = check_if_exists(state)?;
+        // `<index> on <model>`
+        if state.remaining() < 3 {
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
+        match (state.fw_read(), state.fw_read()) {
+            (Token::Ident(index_name), Token![on]) => {
+                let entity = state.try_entity_ref_result()?;
+                Ok(DropIndex::new(*index_name, entity, if_exists))
+            }
+            _ => Err(QueryError::QLInvalidSyntax),
+        }
+    }
+}
+
 mod impls {
     use {
-        super::{DropModel, DropSpace},
+        super::{DropIndex, DropModel, DropSpace, TruncateModel},
         crate::engine::{
             error::QueryResult,
             ql::ast::{traits::ASTNode, QueryData, State},
@@ -164,4 +220,22 @@ mod impls {
             Self::parse(state)
         }
     }
+    impl<'a> ASTNode<'a> for TruncateModel<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse(state)
+        }
+    }
+    impl<'a> ASTNode<'a> for DropIndex<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse(state)
+        }
+    }
 }
@@ -29,6 +29,7 @@ pub(in crate::engine) mod syn;
 pub(in crate::engine) mod alt;
 pub(in crate::engine) mod crt;
 pub(in crate::engine) mod drop;
+pub(in crate::engine) mod task;
 
 use {
     super::{
@@ -57,7 +58,7 @@ impl<'a> ASTNode<'a> for Use<'a> {
         /*
             should have either an ident or null
         */
-        if state.exhausted() | (state.remaining() > 2) {
+        if state.exhausted() {
             return Err(QueryError::QLInvalidSyntax);
         }
         Ok(match state.fw_read() {
@@ -82,6 +83,16 @@ pub enum Inspect<'a> {
     Global,
     Space(Ident<'a>),
     Model(EntityIDRef<'a>),
+    /// `inspect sys.tasks`
+    Tasks,
+    /// `inspect sys.task_runs`
+    TaskRuns,
+    /// `inspect model <entity> fingerprint`
+    ModelFingerprint(EntityIDRef<'a>),
+    /// `inspect sys.jobs`
+    Jobs,
+    /// `inspect sys.watermarks`
+    Watermarks,
 }
 
 impl<'a> ASTNode<'a> for Inspect<'a> {
@@ -106,7 +117,35 @@ impl<'a> ASTNode<'a> for Inspect<'a> {
             }
             Token![model] => {
                 let entity = state.try_entity_ref_result()?;
-                Self::Model(entity)
+                match state.current() {
+                    [Token::Ident(id), ..] if id.eq_ignore_ascii_case("fingerprint") => {
+                        state.cursor_ahead();
+                        Self::ModelFingerprint(entity)
+                    }
+                    _ => Self::Model(entity),
+                }
+            }
+            Token::Ident(id) if id.eq_ignore_ascii_case("sys") => {
+                if state.remaining() < 2 {
+                    return Err(QueryError::QLUnexpectedEndOfStatement);
+                }
+                match (state.fw_read(), state.fw_read()) {
+                    (Token![.], Token::Ident(table)) if table.eq_ignore_ascii_case("tasks") => {
+                        Self::Tasks
+                    }
+                    (Token![.], Token::Ident(table)) if table.eq_ignore_ascii_case("task_runs") => {
+                        Self::TaskRuns
+                    }
+                    (Token![.], Token::Ident(table)) if table.eq_ignore_ascii_case("jobs") => {
+                        Self::Jobs
+                    }
+                    (Token![.], Token::Ident(table))
+                        if table.eq_ignore_ascii_case("watermarks") =>
+                    {
+                        Self::Watermarks
+                    }
+                    _ => return Err(QueryError::QLInvalidSyntax),
+                }
             }
             _ => return Err(QueryError::QLInvalidSyntax),
         };
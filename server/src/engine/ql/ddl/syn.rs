@@ -346,10 +346,12 @@ pub struct FieldSpec<'a> {
     pub(in crate::engine) field_name: Ident<'a>,
     /// layers
     pub(in crate::engine) layers: Vec<LayerSpec<'a>>,
-    /// is null
+    /// is null (`null`). fields are not null unless they say so (`not null` is the same as leaving it out)
     pub(in crate::engine) null: bool,
     /// is primary
     pub(in crate::engine) primary: bool,
+    /// is unique (`unique`). no two rows can have the same (non-null) value in the field
+    pub(in crate::engine) unique: bool,
 }
 
 impl<'a> FieldSpec<'a> {
@@ -365,19 +367,38 @@ impl<'a> FieldSpec<'a> {
             layers,
             null,
             primary,
+            unique: false,
         }
     }
+    #[cfg(test)]
+    pub fn with_unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
     pub fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
         if compiler::unlikely(state.remaining() < 2) {
             // smallest field: `ident: type`
             return Err(QueryError::QLUnexpectedEndOfStatement);
         }
-        // check if primary or null
+        // check if primary, unique, null or not null
         let is_primary = state.cursor_eq(Token![primary]);
         state.cursor_ahead_if(is_primary);
-        let is_null = state.cursor_eq(Token![null]);
+        // `unique` isn't a keyword (so that it can still be used as a field name), so it's only a modifier if it isn't
+        // followed by a `:`
+        let is_unique = state.has_remaining(2)
+            && state.read().ident_eq("unique")
+            && !Token![:].eq(state.offset_current_r(1));
+        state.cursor_ahead_if(is_unique);
+        let is_not_null = state.cursor_rounded_eq(Token![not]);
+        state.cursor_ahead_if(is_not_null);
+        let is_null = state.cursor_rounded_eq(Token![null]);
         state.cursor_ahead_if(is_null);
+        state.poison_if(is_not_null & !is_null);
+        let is_null = is_null & !is_not_null;
         state.poison_if(is_primary & is_null);
+        if compiler::unlikely(state.remaining() < 2) {
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
         // parse layers
         // field name
         let field_name = match (state.fw_read(), state.fw_read()) {
@@ -393,6 +414,7 @@ impl<'a> FieldSpec<'a> {
                 layers,
                 null: is_null,
                 primary: is_primary,
+                unique: is_unique,
             })
         } else {
             Err(QueryError::QLInvalidTypeDefinitionSyntax)
@@ -0,0 +1,177 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::super::{
+        ast::{QueryData, State},
+        lex::{Ident, Keyword, KeywordStmt, Token},
+    },
+    crate::engine::error::{QueryError, QueryResult},
+};
+
+#[derive(Debug, PartialEq)]
+/// A `create task <name> schedule <cron expr> do <statement>` query. The statement is stored just the way it was sent
+/// and is only parsed when the task runs, so all we check here is that there is one
+pub struct CreateTask<'a> {
+    pub(in crate::engine) task_name: Ident<'a>,
+    pub(in crate::engine) schedule: Box<str>,
+}
+
+impl<'a> CreateTask<'a> {
+    #[inline(always)]
+    pub fn new(task_name: Ident<'a>, schedule: Box<str>) -> Self {
+        Self {
+            task_name,
+            schedule,
+        }
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        // `<name> schedule <cron expr> do <statement>`
+        if state.remaining() < 5 {
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
+        let (Token::Ident(task_name), true) = (state.fw_read(), state.read().ident_eq("schedule"))
+        else {
+            return Err(QueryError::QLInvalidSyntax);
+        };
+        state.cursor_ahead();
+        if !state.can_read_lit_rounded() {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let schedule = unsafe {
+            // UNSAFE(@ohsayan): +boundck
+            state.read_cursor_lit_unchecked()
+        };
+        let Some(schedule) = schedule.try_str().map(Box::from) else {
+            return Err(QueryError::QLInvalidSyntax);
+        };
+        state.cursor_ahead();
+        if !state.fw_read().ident_eq("do") {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        match state.current() {
+            // a task can't manage other tasks
+            [Token::Keyword(Keyword::Statement(
+                KeywordStmt::Create | KeywordStmt::Alter | KeywordStmt::Drop,
+            )), target, ..]
+                if target.ident_eq("task") =>
+            {
+                return Err(QueryError::QLInvalidSyntax)
+            }
+            [Token::Keyword(Keyword::Statement(_)), ..] | [Token![truncate], ..] => {}
+            _ => return Err(QueryError::QLExpectedStatement),
+        }
+        state.cursor_ahead_by(state.remaining());
+        Ok(Self::new(*task_name, schedule))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// An `alter task <name> enable` or `alter task <name> disable` query
+pub struct AlterTask<'a> {
+    pub(in crate::engine) task_name: Ident<'a>,
+    pub(in crate::engine) enabled: bool,
+}
+
+impl<'a> AlterTask<'a> {
+    #[inline(always)]
+    pub fn new(task_name: Ident<'a>, enabled: bool) -> Self {
+        Self { task_name, enabled }
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        if state.remaining() < 2 {
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
+        match (state.fw_read(), state.fw_read()) {
+            (Token::Ident(task_name), action) if action.ident_eq("enable") => {
+                Ok(Self::new(*task_name, true))
+            }
+            (Token::Ident(task_name), action) if action.ident_eq("disable") => {
+                Ok(Self::new(*task_name, false))
+            }
+            _ => Err(QueryError::QLInvalidSyntax),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A `drop task <name>` query
+pub struct DropTask<'a> {
+    pub(in crate::engine) task_name: Ident<'a>,
+}
+
+impl<'a> DropTask<'a> {
+    #[inline(always)]
+    pub fn new(task_name: Ident<'a>) -> Self {
+        Self { task_name }
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        if state.exhausted() {
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
+        match state.fw_read() {
+            Token::Ident(task_name) => Ok(Self::new(*task_name)),
+            _ => Err(QueryError::QLInvalidSyntax),
+        }
+    }
+}
+
+mod impls {
+    use {
+        super::{AlterTask, CreateTask, DropTask},
+        crate::engine::{
+            error::QueryResult,
+            ql::ast::{traits::ASTNode, QueryData, State},
+        },
+    };
+    impl<'a> ASTNode<'a> for CreateTask<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse(state)
+        }
+    }
+    impl<'a> ASTNode<'a> for AlterTask<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse(state)
+        }
+    }
+    impl<'a> ASTNode<'a> for DropTask<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse(state)
+        }
+    }
+}
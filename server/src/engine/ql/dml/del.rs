@@ -89,8 +89,9 @@ impl<'a> DeleteStatement<'a> {
         // where + clauses
         state.poison_if_not(state.cursor_eq(Token![where]));
         state.cursor_ahead(); // ignore errors
-        let wc = WhereClause::parse_where(state);
+        let mut wc = WhereClause::parse_where(state);
         if compiler::likely(state.okay()) {
+            wc.apply_casts()?;
             Ok(Self {
                 entity: unsafe {
                     // UNSAFE(@ohsayan): Safety guaranteed by state
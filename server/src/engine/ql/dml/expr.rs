@@ -0,0 +1,284 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::{
+    engine::{
+        data::cell::Datacell,
+        ql::{
+            ast::{QueryData, State},
+            lex::{Ident, Token},
+        },
+    },
+    util::compiler,
+};
+
+/*
+    Scalar expressions
+    ---
+    expr := term (('+' | '-') term)*
+    term := factor (('*' | '/') factor)*
+    factor := field | literal | null | '(' expr ')' | cast '(' expr as type ')' | fn '(' [expr (',' expr)*] ')'
+    aggregate := (count | sum | avg | min | max) '(' field ')' | count '(' '*' ')'
+
+    aggregates can only be used at the top level of a select's field list, since they're evaluated over every row that
+    the select matches rather than a single row. a `-` has to be followed by a space when it's used to subtract a
+    literal, since the lexer reads something like `-1` as a negative number
+*/
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr<'a> {
+    /// a field of the current row
+    Field(Ident<'a>),
+    /// a literal value (including null)
+    Value(Datacell),
+    /// a call to a scalar function
+    Call(Ident<'a>, Vec<Expr<'a>>),
+    /// an explicit type conversion
+    Cast(Box<Expr<'a>>, Ident<'a>),
+    /// an aggregate over the matched rows
+    Aggregate(Aggregate<'a>),
+    /// an arithmetic operation on two expressions
+    Binary(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AggregateFn {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFn {
+    fn from_ident(id: &str) -> Option<Self> {
+        [
+            ("count", Self::Count),
+            ("sum", Self::Sum),
+            ("avg", Self::Avg),
+            ("min", Self::Min),
+            ("max", Self::Max),
+        ]
+        .into_iter()
+        .find_map(|(name, func)| id.eq_ignore_ascii_case(name).then_some(func))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// `count(*)` or `<fn>(<field>)`
+pub struct Aggregate<'a> {
+    func: AggregateFn,
+    /// `None` for `count(*)`
+    field: Option<Ident<'a>>,
+}
+
+impl<'a> Aggregate<'a> {
+    pub const fn new(func: AggregateFn, field: Option<Ident<'a>>) -> Self {
+        Self { func, field }
+    }
+    pub fn func(&self) -> AggregateFn {
+        self.func
+    }
+    pub fn field(&self) -> Option<Ident<'a>> {
+        self.field
+    }
+    /// Parse an aggregate if the cursor is at one. The state is poisoned if the aggregate has a bad argument
+    fn try_parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Self> {
+        let func = match state.read() {
+            Token::Ident(id) if Token![() open].eq(state.offset_current_r(1)) => {
+                AggregateFn::from_ident(id.as_str())?
+            }
+            _ => return None,
+        };
+        if !state.cursor_signature_match_fn_arity1_rounded() {
+            state.poison();
+            return Some(Self::new(func, None));
+        }
+        let field = match state.offset_current_r(2) {
+            Token::Ident(field) => Some(*field),
+            Token![*] => None,
+            _ => {
+                state.poison();
+                None
+            }
+        };
+        // only count can go over whole rows
+        state.poison_if(field.is_none() & (func != AggregateFn::Count));
+        state.cursor_ahead_by(4);
+        Some(Self::new(func, field))
+    }
+}
+
+impl<'a> Expr<'a> {
+    /// The maximum depth of nested function calls, parentheses and operators that we will parse
+    pub const MAX_DEPTH: usize = 16;
+    /// Parse an expression, poisoning the state on error
+    pub fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Self {
+        Self::parse_nested(state, 0)
+    }
+    /// Parse a column of a select's field list, which may also be an aggregate
+    pub fn parse_column<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Self {
+        match Aggregate::try_parse(state) {
+            Some(agg) => Self::Aggregate(agg),
+            None => Self::parse(state),
+        }
+    }
+    fn parse_nested<Qd: QueryData<'a>>(state: &mut State<'a, Qd>, mut depth: usize) -> Self {
+        let mut lhs = Self::parse_term(state, depth);
+        while state.okay() {
+            let op = match state.current().first() {
+                Some(Token![+]) => BinaryOp::Add,
+                Some(Token![-]) => BinaryOp::Sub,
+                _ => break,
+            };
+            state.cursor_ahead();
+            // every operator nests the tree one level deeper
+            depth += 1;
+            let rhs = Self::parse_term(state, depth);
+            lhs = Self::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        lhs
+    }
+    fn parse_term<Qd: QueryData<'a>>(state: &mut State<'a, Qd>, mut depth: usize) -> Self {
+        let mut lhs = Self::parse_factor(state, depth);
+        while state.okay() {
+            let op = match state.current().first() {
+                Some(Token![*]) => BinaryOp::Mul,
+                Some(Token![/]) => BinaryOp::Div,
+                _ => break,
+            };
+            state.cursor_ahead();
+            depth += 1;
+            let rhs = Self::parse_factor(state, depth);
+            lhs = Self::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        lhs
+    }
+    fn parse_factor<Qd: QueryData<'a>>(state: &mut State<'a, Qd>, depth: usize) -> Self {
+        if compiler::unlikely(state.exhausted() | (depth > Self::MAX_DEPTH)) {
+            state.poison();
+            return Self::Value(Datacell::null());
+        }
+        match state.fw_read() {
+            Token::Ident(func)
+                if func.eq_ignore_ascii_case("cast") & state.cursor_rounded_eq(Token![() open]) =>
+            {
+                state.cursor_ahead();
+                let expr = Self::parse_nested(state, depth + 1);
+                state.poison_if_not(state.cursor_rounded_eq(Token![as]));
+                state.cursor_ahead_if(state.okay());
+                let ty = state.current().first().filter(|tok| tok.is_ident());
+                state.poison_if(ty.is_none());
+                state.cursor_ahead_if(state.okay());
+                state.poison_if_not(state.cursor_rounded_eq(Token![() close]));
+                state.cursor_ahead_if(state.okay());
+                match ty {
+                    Some(Token::Ident(ty)) if state.okay() => Self::Cast(Box::new(expr), *ty),
+                    _ => Self::Value(Datacell::null()),
+                }
+            }
+            Token::Ident(func) if state.cursor_rounded_eq(Token![() open]) => {
+                state.cursor_ahead();
+                let mut args = Vec::new();
+                let mut close = state.cursor_rounded_eq(Token![() close]);
+                while !close & state.okay() & state.not_exhausted() {
+                    args.push(Self::parse_nested(state, depth + 1));
+                    let nx_comma = state.cursor_rounded_eq(Token![,]);
+                    close = state.cursor_rounded_eq(Token![() close]);
+                    state.poison_if_not(nx_comma | close);
+                    state.cursor_ahead_if(nx_comma);
+                }
+                state.poison_if_not(close);
+                state.cursor_ahead_if(close);
+                Self::Call(*func, args)
+            }
+            Token::Ident(id) => Self::Field(*id),
+            Token![() open] => {
+                let expr = Self::parse_nested(state, depth + 1);
+                state.poison_if_not(state.cursor_rounded_eq(Token![() close]));
+                state.cursor_ahead_if(state.okay());
+                expr
+            }
+            Token![null] => Self::Value(Datacell::null()),
+            tok if state.can_read_lit_from(tok) => Self::Value(unsafe {
+                // UNSAFE(@ohsayan): the if guard guarantees correctness
+                state.read_lit_into_data_type_unchecked_from(tok)
+            }),
+            _ => {
+                state.cursor_back();
+                state.poison();
+                Self::Value(Datacell::null())
+            }
+        }
+    }
+    /// Returns the field, if this expression is a plain field
+    pub fn as_field(&self) -> Option<Ident<'a>> {
+        match self {
+            Self::Field(id) => Some(*id),
+            _ => None,
+        }
+    }
+    /// Returns the aggregate, if this expression is one
+    pub fn as_aggregate(&self) -> Option<Aggregate<'a>> {
+        match self {
+            Self::Aggregate(agg) => Some(*agg),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod impls {
+    use {
+        super::Expr,
+        crate::engine::{
+            error::{QueryError, QueryResult},
+            ql::ast::{traits::ASTNode, QueryData, State},
+        },
+    };
+    impl<'a> ASTNode<'a> for Expr<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = false;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            let expr = Self::parse(state);
+            if state.okay() {
+                Ok(expr)
+            } else {
+                Err(QueryError::QLInvalidSyntax)
+            }
+        }
+    }
+}
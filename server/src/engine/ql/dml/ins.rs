@@ -38,7 +38,8 @@ use {
         util::compiler,
     },
     std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
+        iter,
         time::{Duration, SystemTime, UNIX_EPOCH},
     },
     uuid::Uuid,
@@ -155,6 +156,11 @@ pub(super) fn parse_list<'a, Qd: QueryData<'a>>(
                 }
                 Datacell::new_list(nested_list)
             }
+            Token![open {}] if state.not_exhausted() => {
+                let mut map = BTreeMap::new();
+                parse_map(state, &mut map);
+                Datacell::new_map(map)
+            }
             Token![@] if state.cursor_signature_match_fn_arity0_rounded() => match unsafe {
                 // UNSAFE(@ohsayan): Just verified at guard
                 handle_func_sub(state)
@@ -183,6 +189,63 @@ pub(super) fn parse_list<'a, Qd: QueryData<'a>>(
     overall_dscr
 }
 
+/// Parse a map literal (`{ 'key': value, ... }`); the keys are always strings
+///
+/// ## Panics
+/// - If tt length is less than 1
+pub(super) fn parse_map<'a, Qd: QueryData<'a>>(
+    state: &mut State<'a, Qd>,
+    map: &mut BTreeMap<Box<str>, Datacell>,
+) {
+    let mut stop = state.cursor_eq(Token![close {}]);
+    state.cursor_ahead_if(stop);
+    while state.has_remaining(3) && state.okay() && !stop {
+        let key = state.fw_read();
+        let colon = state.fw_read();
+        let value = state.fw_read();
+        state.poison_if_not(Token![:].eq(colon) & state.can_read_lit_from(key));
+        if !state.okay() {
+            state.cursor_back_by(3);
+            break;
+        }
+        let key = unsafe {
+            // UNSAFE(@ohsayan): just verified that we can read a lit
+            state.read_lit_into_data_type_unchecked_from(key)
+        };
+        let value = match value {
+            tok if state.can_read_lit_from(tok) => unsafe {
+                // UNSAFE(@ohsayan): the if guard guarantees correctness
+                state.read_lit_into_data_type_unchecked_from(tok)
+            },
+            Token![open []] if state.not_exhausted() => {
+                let mut l = Vec::new();
+                let _ = parse_list(state, &mut l);
+                Datacell::new_list(l)
+            }
+            Token![open {}] if state.not_exhausted() => {
+                let mut m = BTreeMap::new();
+                parse_map(state, &mut m);
+                Datacell::new_map(m)
+            }
+            _ => {
+                state.cursor_back();
+                state.poison();
+                break;
+            }
+        };
+        match key.into_str() {
+            Some(key) => state.poison_if_not(map.insert(key.into_boxed_str(), value).is_none()),
+            None => state.poison(),
+        }
+        let nx_comma = state.cursor_rounded_eq(Token![,]);
+        let nx_csbrc = state.cursor_rounded_eq(Token![close {}]);
+        state.poison_if_not(nx_comma | nx_csbrc);
+        state.cursor_ahead_if(state.okay());
+        stop = nx_csbrc;
+    }
+    state.poison_if_not(stop);
+}
+
 #[inline(always)]
 /// ## Safety
 /// - Cursor must match arity(0) function signature
@@ -211,6 +274,11 @@ pub(super) fn parse_data_tuple_syntax<'a, Qd: QueryData<'a>>(
                 let _ = parse_list(state, &mut l);
                 data.push(l.into());
             }
+            Token![open {}] if state.not_exhausted() => {
+                let mut m = BTreeMap::new();
+                parse_map(state, &mut m);
+                data.push(m.into());
+            }
             Token![null] => data.push(Datacell::null()),
             Token![@] if state.cursor_signature_match_fn_arity0_rounded() => match unsafe {
                 // UNSAFE(@ohsayan): Just verified at guard
@@ -266,6 +334,11 @@ pub(super) fn parse_data_map_syntax<'a, Qd: QueryData<'a>>(
                 let _ = parse_list(state, &mut l);
                 state.poison_if_not(data.insert(*id, l.into()).is_none());
             }
+            (Token::Ident(id), Token![open {}]) if state.not_exhausted() => {
+                let mut m = BTreeMap::new();
+                parse_map(state, &mut m);
+                state.poison_if_not(data.insert(*id, m.into()).is_none());
+            }
             (Token::Ident(id), Token![@]) if state.cursor_signature_match_fn_arity0_rounded() => {
                 match unsafe {
                     // UNSAFE(@ohsayan): Just verified at guard
@@ -324,20 +397,38 @@ impl<'a> From<HashMap<Ident<'static>, Datacell>> for InsertData<'a> {
 pub struct InsertStatement<'a> {
     pub(super) entity: EntityIDRef<'a>,
     pub(super) data: InsertData<'a>,
+    /// any rows after the first one (for `insert into model (...), (...)`)
+    pub(super) more: Vec<InsertData<'a>>,
 }
 
 impl<'a> InsertStatement<'a> {
     #[inline(always)]
-    #[cfg(test)]
     pub fn new(entity: EntityIDRef<'a>, data: InsertData<'a>) -> Self {
-        Self { entity, data }
+        Self::new_multi(entity, data, vec![])
+    }
+    #[inline(always)]
+    pub fn new_multi(
+        entity: EntityIDRef<'a>,
+        data: InsertData<'a>,
+        more: Vec<InsertData<'a>>,
+    ) -> Self {
+        Self { entity, data, more }
     }
     pub fn entity(&self) -> EntityIDRef<'a> {
         self.entity
     }
+    /// Returns the number of rows to be inserted
+    pub fn row_count(&self) -> usize {
+        1 + self.more.len()
+    }
+    /// Returns the data for the first row
     pub fn data(self) -> InsertData<'a> {
         self.data
     }
+    /// Returns the data for every row, in order
+    pub fn rows(self) -> impl Iterator<Item = InsertData<'a>> {
+        iter::once(self.data).chain(self.more)
+    }
 }
 
 impl<'a> InsertStatement<'a> {
@@ -355,36 +446,53 @@ impl<'a> InsertStatement<'a> {
 
         // entity
         let entity = state.try_entity_buffered_into_state_uninit();
-        let mut data = None;
-        match state.fw_read() {
-            Token![() open] if state.not_exhausted() => {
-                let this_data = parse_data_tuple_syntax(state);
-                data = Some(InsertData::Ordered(this_data));
-            }
-            Token![open {}] if state.not_exhausted() => {
-                let this_data = parse_data_map_syntax(state);
-                data = Some(InsertData::Map(this_data));
-            }
-            _ => {
-                state.poison();
-            }
+        let data = Self::parse_row(state);
+        // more rows: `, (...)` or `, {...}`
+        let mut more = vec![];
+        while state.okay() && state.has_remaining(2) && state.cursor_eq(Token![,]) {
+            state.cursor_ahead();
+            more.push(Self::parse_row(state));
         }
         if state.okay() {
             let data = unsafe {
                 // UNSAFE(@ohsayan): state's flag guarantees correctness (see wildcard branch)
                 data.unwrap_unchecked()
             };
+            let more = more
+                .into_iter()
+                .map(|row| unsafe {
+                    // UNSAFE(@ohsayan): state's flag guarantees correctness (see wildcard branch)
+                    row.unwrap_unchecked()
+                })
+                .collect();
             Ok(InsertStatement {
                 entity: unsafe {
                     // UNSAFE(@ohsayan): state's flag ensures correctness (see Entity::parse_entity)
                     entity.assume_init()
                 },
                 data,
+                more,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
         }
     }
+    /// Parse a single row (either a tuple or a map). The state is poisoned (and nothing is returned) if this
+    /// isn't a row
+    fn parse_row<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<InsertData<'a>> {
+        match state.fw_read() {
+            Token![() open] if state.not_exhausted() => {
+                Some(InsertData::Ordered(parse_data_tuple_syntax(state)))
+            }
+            Token![open {}] if state.not_exhausted() => {
+                Some(InsertData::Map(parse_data_map_syntax(state)))
+            }
+            _ => {
+                state.poison();
+                None
+            }
+        }
+    }
 }
 
 #[cfg(test)]
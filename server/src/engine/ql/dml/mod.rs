@@ -30,6 +30,7 @@
 */
 
 pub mod del;
+pub mod expr;
 pub mod ins;
 pub mod sel;
 pub mod upd;
@@ -37,10 +38,13 @@ pub mod upd;
 use {
     super::{
         ast::{QueryData, State},
-        lex::Ident,
+        lex::{Ident, Token},
     },
-    crate::{engine::data::lit::Lit, util::compiler},
-    std::collections::HashMap,
+    crate::{
+        engine::{core::dml::cast_lit, data::lit::Lit, error::QueryResult},
+        util::compiler,
+    },
+    std::collections::{hash_map::Entry, HashMap},
 };
 
 #[inline(always)]
@@ -52,6 +56,40 @@ fn u(b: bool) -> u8 {
     Misc
 */
 
+/// Parse a lit, which can also be cast to another type (`CAST(<lit> AS <type>)`). The type is returned along with the
+/// lit since a cast can fail, and this can only be reported once the statement has been parsed (see [`cast_lit`])
+fn parse_lit_or_cast<'a, Qd: QueryData<'a>>(
+    state: &mut State<'a, Qd>,
+) -> Option<(Lit<'a>, Option<Ident<'a>>)> {
+    let is_cast = (state.remaining() > 1)
+        && state.read().ident_eq("cast")
+        && (state.offset_current_r(1) == &Token![() open]);
+    state.cursor_ahead_by(2 * is_cast as usize);
+    state.poison_if_not(state.can_read_lit_rounded());
+    if compiler::unlikely(!state.okay()) {
+        return None;
+    }
+    let lit = unsafe {
+        // UNSAFE(@ohsayan): we verified this above
+        state.read_cursor_lit_unchecked()
+    };
+    state.cursor_ahead();
+    if !is_cast {
+        return Some((lit, None));
+    }
+    state.poison_if_not(state.cursor_rounded_eq(Token![as]));
+    state.cursor_ahead_if(state.okay());
+    let ty = state.current().first().filter(|tok| tok.is_ident());
+    state.poison_if(ty.is_none());
+    state.cursor_ahead_if(state.okay());
+    state.poison_if_not(state.cursor_rounded_eq(Token![() close]));
+    state.cursor_ahead_if(state.okay());
+    match ty {
+        Some(Token::Ident(ty)) if state.okay() => Some((lit, Some(*ty))),
+        _ => None,
+    }
+}
+
 /*
     Contexts
 */
@@ -61,25 +99,104 @@ pub struct RelationalExpr<'a> {
     pub(super) lhs: Ident<'a>,
     pub(super) rhs: Lit<'a>,
     pub(super) opc: u8,
+    /// a second comparison on the same field (`BETWEEN` or a field that is compared twice)
+    pub(super) bound: Option<(u8, Lit<'a>)>,
+    /// the types that `rhs` and the literal of `bound` are cast to, until the casts are applied
+    pub(super) casts: (Option<Ident<'a>>, Option<Ident<'a>>),
 }
 
 impl<'a> RelationalExpr<'a> {
     #[inline(always)]
     pub(super) fn new(lhs: Ident<'a>, rhs: Lit<'a>, opc: u8) -> RelationalExpr<'a> {
-        Self { lhs, rhs, opc }
-    }
-    pub(super) const OP_EQ: u8 = 1;
-    pub(super) const OP_NE: u8 = 2;
-    pub(super) const OP_GT: u8 = 3;
-    pub(super) const OP_GE: u8 = 4;
-    pub(super) const OP_LT: u8 = 5;
-    pub(super) const OP_LE: u8 = 6;
+        Self {
+            lhs,
+            rhs,
+            opc,
+            bound: None,
+            casts: (None, None),
+        }
+    }
+    #[cfg(test)]
+    pub(super) fn new_bounded(
+        lhs: Ident<'a>,
+        (opc, rhs): (u8, Lit<'a>),
+        bound: (u8, Lit<'a>),
+    ) -> RelationalExpr<'a> {
+        Self {
+            lhs,
+            rhs,
+            opc,
+            bound: Some(bound),
+            casts: (None, None),
+        }
+    }
+    pub const OP_EQ: u8 = 1;
+    pub const OP_NE: u8 = 2;
+    pub const OP_GT: u8 = 3;
+    pub const OP_GE: u8 = 4;
+    pub const OP_LT: u8 = 5;
+    pub const OP_LE: u8 = 6;
+    pub const OP_IS_NULL: u8 = 7;
+    pub const OP_IS_NOT_NULL: u8 = 8;
+    pub const OP_STARTS_WITH: u8 = 9;
+    /// A comparison with null (like `x = null` or `x > null`). Its result is unknown, so no row ever passes it
+    pub const OP_CMP_NULL: u8 = 10;
     pub fn filter_hint_none(&self) -> bool {
         self.opc == Self::OP_EQ
     }
+    /// If this is a null test (`IS [NOT] NULL`), returns whether the field is expected to be null
+    pub fn null_test(&self) -> Option<bool> {
+        match self.opc {
+            Self::OP_IS_NULL => Some(true),
+            Self::OP_IS_NOT_NULL => Some(false),
+            _ => None,
+        }
+    }
+    /// Returns true if this is a comparison with null (see [`Self::OP_CMP_NULL`])
+    pub fn is_cmp_null(&self) -> bool {
+        self.opc == Self::OP_CMP_NULL
+    }
     pub fn rhs(&self) -> Lit<'a> {
         self.rhs.clone()
     }
+    /// Returns the literal without copying it (a copy of a string or binary literal that we own is a new allocation,
+    /// so anything read from the copy can't outlive it)
+    pub fn rhs_ref(&self) -> &Lit<'a> {
+        &self.rhs
+    }
+    pub fn opc(&self) -> u8 {
+        self.opc
+    }
+    /// Returns the second comparison on this field, if there is one
+    pub fn bound(&self) -> Option<(u8, &Lit<'a>)> {
+        self.bound.as_ref().map(|(opc, rhs)| (*opc, rhs))
+    }
+    fn is_ordering(opc: u8) -> bool {
+        (Self::OP_GT..=Self::OP_LE).contains(&opc)
+    }
+    /// Attempt to add another comparison on the same field. Only two ordering comparisons (`<`, `<=`, `>`, `>=`) can
+    /// be combined
+    fn try_bound_with(&mut self, other: Self) -> bool {
+        let okay = Self::is_ordering(self.opc)
+            & Self::is_ordering(other.opc)
+            & self.bound.is_none()
+            & other.bound.is_none();
+        if okay {
+            self.bound = Some((other.opc, other.rhs));
+            self.casts.1 = other.casts.0;
+        }
+        okay
+    }
+    /// Apply the casts on the literals (if any)
+    fn apply_casts(&mut self) -> QueryResult<()> {
+        if let Some(ty) = self.casts.0.take() {
+            self.rhs = cast_lit(self.rhs.clone(), ty)?;
+        }
+        if let (Some(ty), Some((_, bound))) = (self.casts.1.take(), self.bound.as_mut()) {
+            *bound = cast_lit(bound.clone(), ty)?;
+        }
+        Ok(())
+    }
     #[inline(always)]
     fn parse_operator<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> u8 {
         let tok = state.current();
@@ -102,20 +219,105 @@ impl<'a> RelationalExpr<'a> {
         let ident = state.read();
         state.poison_if_not(ident.is_ident());
         state.cursor_ahead(); // ignore any errors
+        if state.read().ident_eq("is") {
+            return Self::try_parse_null_test(state, ident);
+        }
+        if state.read().ident_eq("starts") {
+            return Self::try_parse_starts_with(state, ident);
+        }
+        if state.read().ident_eq("between") {
+            return Self::try_parse_between(state, ident);
+        }
         let operator = Self::parse_operator(state);
-        state.poison_if_not(state.can_read_lit_rounded());
+        if state.cursor_rounded_eq(Token![null]) {
+            // like a null test, the literal is just a placeholder
+            state.cursor_ahead();
+            return state.okay().then(|| unsafe {
+                // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
+                Self::new(
+                    ident.uck_read_ident(),
+                    Lit::new_bool(false),
+                    Self::OP_CMP_NULL,
+                )
+            });
+        }
+        match parse_lit_or_cast(state) {
+            Some((lit, cast)) if compiler::likely(state.okay()) => unsafe {
+                // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
+                let mut expr = Self::new(ident.uck_read_ident(), lit, operator);
+                expr.casts.0 = cast;
+                Some(expr)
+            },
+            _ => None,
+        }
+    }
+    #[inline(always)]
+    /// Parse `IS NULL` or `IS NOT NULL` (the cursor must be at `IS`)
+    ///
+    /// The literal for a null test is just a placeholder and must not be used
+    fn try_parse_null_test<Qd: QueryData<'a>>(
+        state: &mut State<'a, Qd>,
+        ident: &'a Token<'a>,
+    ) -> Option<Self> {
+        state.cursor_ahead();
+        let is_not = state.cursor_rounded_eq(Token![not]);
+        state.cursor_ahead_if(is_not);
+        state.poison_if_not(state.cursor_rounded_eq(Token![null]));
+        state.cursor_ahead_if(state.okay());
         if compiler::likely(state.okay()) {
+            let opc = if is_not {
+                Self::OP_IS_NOT_NULL
+            } else {
+                Self::OP_IS_NULL
+            };
             unsafe {
-                // UNSAFE(@ohsayan): we verified this above
-                let lit = state.read_cursor_lit_unchecked();
-                state.cursor_ahead();
                 // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
-                Some(Self::new(ident.uck_read_ident(), lit, operator))
+                Some(Self::new(ident.uck_read_ident(), Lit::new_bool(false), opc))
             }
         } else {
             None
         }
     }
+    #[inline(always)]
+    /// Parse `STARTS WITH <lit>` (the cursor must be at `STARTS`)
+    fn try_parse_starts_with<Qd: QueryData<'a>>(
+        state: &mut State<'a, Qd>,
+        ident: &'a Token<'a>,
+    ) -> Option<Self> {
+        state.cursor_ahead();
+        state.poison_if_not(state.cursor_rounded_eq(Token![with]));
+        state.cursor_ahead_if(state.okay());
+        match parse_lit_or_cast(state) {
+            Some((lit, cast)) if compiler::likely(state.okay()) => unsafe {
+                // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
+                let mut expr = Self::new(ident.uck_read_ident(), lit, Self::OP_STARTS_WITH);
+                expr.casts.0 = cast;
+                Some(expr)
+            },
+            _ => None,
+        }
+    }
+    #[inline(always)]
+    /// Parse `BETWEEN <lit> AND <lit>` (the cursor must be at `BETWEEN`). Both ends are inclusive
+    fn try_parse_between<Qd: QueryData<'a>>(
+        state: &mut State<'a, Qd>,
+        ident: &'a Token<'a>,
+    ) -> Option<Self> {
+        state.cursor_ahead();
+        let (lo, lo_cast) = parse_lit_or_cast(state)?;
+        state.poison_if_not(state.cursor_rounded_eq(Token![and]));
+        state.cursor_ahead_if(state.okay());
+        match parse_lit_or_cast(state) {
+            Some((hi, hi_cast)) if compiler::likely(state.okay()) => unsafe {
+                // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
+                let mut expr = Self::new(ident.uck_read_ident(), lo, Self::OP_GE);
+                expr.bound = Some((Self::OP_LE, hi));
+                expr.casts = (lo_cast, hi_cast);
+                Some(expr)
+            },
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -130,9 +332,18 @@ impl<'a> WhereClause<'a> {
     pub(super) fn new(c: WhereClauseCollection<'a>) -> Self {
         Self { c }
     }
+    pub fn clauses(&self) -> &WhereClauseCollection<'a> {
+        &self.c
+    }
     pub fn clauses_mut(&mut self) -> &mut WhereClauseCollection<'a> {
         &mut self.c
     }
+    /// Apply the casts on the literals of every clause. This must be done once the statement has been parsed
+    pub(super) fn apply_casts(&mut self) -> QueryResult<()> {
+        self.c
+            .values_mut()
+            .try_for_each(RelationalExpr::apply_casts)
+    }
     #[inline(always)]
     fn parse_where_and_append_to<Qd: QueryData<'a>>(
         state: &mut State<'a, Qd>,
@@ -141,7 +352,15 @@ impl<'a> WhereClause<'a> {
         let mut has_more = true;
         while has_more && state.not_exhausted() && state.okay() {
             if let Some(expr) = RelationalExpr::try_parse(state) {
-                state.poison_if_not(c.insert(expr.lhs, expr).is_none());
+                match c.entry(expr.lhs) {
+                    Entry::Vacant(ve) => {
+                        ve.insert(expr);
+                    }
+                    // a field can only be compared twice with `<`, `<=`, `>` or `>=` (for a range)
+                    Entry::Occupied(mut oe) => {
+                        state.poison_if_not(oe.get_mut().try_bound_with(expr))
+                    }
+                }
             }
             has_more = state.cursor_rounded_eq(Token![and]);
             state.cursor_ahead_if(has_more);
@@ -177,7 +396,10 @@ mod impls {
         fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
             state: &mut State<'a, Qd>,
         ) -> QueryResult<Self> {
-            let wh = Self::parse_where(state);
+            let mut wh = Self::parse_where(state);
+            if state.okay() {
+                wh.apply_casts()?;
+            }
             Ok(wh)
         }
     }
@@ -187,7 +409,9 @@ mod impls {
         fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
             state: &mut State<'a, Qd>,
         ) -> QueryResult<Self> {
-            Self::try_parse(state).ok_or(QueryError::QLInvalidSyntax)
+            let mut expr = Self::try_parse(state).ok_or(QueryError::QLInvalidSyntax)?;
+            expr.apply_casts()?;
+            Ok(expr)
         }
     }
 }
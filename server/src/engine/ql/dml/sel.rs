@@ -27,10 +27,11 @@
 #[cfg(test)]
 use super::WhereClauseCollection;
 use {
-    super::WhereClause,
+    super::{expr::Expr, WhereClause},
     crate::{
         engine::{
             core::EntityIDRef,
+            data::lit::Lit,
             error::{QueryError, QueryResult},
             ql::{
                 ast::{QueryData, State},
@@ -49,12 +50,177 @@ use {
 pub struct SelectStatement<'a> {
     /// the entity
     pub(super) entity: EntityIDRef<'a>,
-    /// fields in order of querying. will be zero when wildcard is set
-    pub(super) fields: Vec<Ident<'a>>,
+    /// fields (or expressions) in order of querying. will be zero when wildcard is set
+    pub(super) fields: Vec<Expr<'a>>,
+    /// the alias of each field (`<expr> as <alias>`)
+    pub(super) aliases: Vec<Option<Ident<'a>>>,
     /// whether a wildcard was passed
     pub(super) wildcard: bool,
     /// where clause
     pub(super) clause: WhereClause<'a>,
+    /// the keys, if this is a multi-get (`where <key> in [...]`)
+    pub(super) multi: Option<MultiGet<'a>>,
+    /// the order of the rows of a multi-get (`order by <field> [asc | desc]`)
+    pub(super) order_by: Option<OrderBy<'a>>,
+    /// bounds on the keys of a multi-get (`limit <n> offset <n>`)
+    pub(super) bounds: Bounds,
+    /// the fields that the rows are grouped by (`group by <field> [, <field>]*`)
+    pub(super) group_by: Vec<Ident<'a>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// `order by <field> [asc | desc]`
+pub struct OrderBy<'a> {
+    field: Ident<'a>,
+    desc: bool,
+}
+
+impl<'a> OrderBy<'a> {
+    pub const fn new(field: Ident<'a>, desc: bool) -> Self {
+        Self { field, desc }
+    }
+    pub fn field(&self) -> Ident<'a> {
+        self.field
+    }
+    pub fn is_desc(&self) -> bool {
+        self.desc
+    }
+    /// Parse `[order by <field> [asc | desc]]`
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Self> {
+        if !state.cursor_rounded_eq(Token![order]) {
+            return None;
+        }
+        state.cursor_ahead();
+        state.poison_if_not(state.cursor_rounded_eq(Token![by]));
+        state.cursor_ahead_if(state.okay());
+        state.poison_if_not(state.not_exhausted() && state.cursor_is_ident());
+        if !state.okay() {
+            return None;
+        }
+        let field = unsafe {
+            // UNSAFE(@ohsayan): we just checked that this is an ident
+            state.fw_read().uck_read_ident()
+        };
+        let desc = state.cursor_rounded_eq(Token![desc]);
+        state.cursor_ahead_if(desc | state.cursor_rounded_eq(Token![asc]));
+        Some(Self::new(field, desc))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// `[limit <n>] [offset <n>]`
+pub struct Bounds {
+    limit: u64,
+    offset: u64,
+}
+
+impl Bounds {
+    pub const fn new(limit: u64, offset: u64) -> Self {
+        Self { limit, offset }
+    }
+    pub const fn unbounded() -> Self {
+        Self::new(u64::MAX, 0)
+    }
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// Parse `[limit <n>] [offset <n>]`
+    fn parse<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Self {
+        let mut bounds = Self::unbounded();
+        if state.cursor_rounded_eq(Token![limit]) {
+            state.cursor_ahead();
+            bounds.limit = parse_uint(state);
+        }
+        bounds.offset = Self::parse_offset(state);
+        bounds
+    }
+    /// Parse `[offset <n>]`
+    fn parse_offset<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> u64 {
+        if state.not_exhausted() && state.read().ident_eq("offset") {
+            state.cursor_ahead();
+            parse_uint(state)
+        } else {
+            0
+        }
+    }
+}
+
+/// Read the unsigned integer at the cursor, poisoning the state if there isn't one
+pub(super) fn parse_uint<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> u64 {
+    state.poison_if_not(state.not_exhausted() && state.can_read_lit_rounded());
+    if !state.okay() {
+        return 0;
+    }
+    let lit = unsafe {
+        // UNSAFE(@ohsayan): we just checked that we can read a lit
+        state.read_cursor_lit_unchecked()
+    };
+    state.cursor_ahead();
+    match lit.try_uint() {
+        Some(n) => n,
+        None => {
+            state.poison();
+            0
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A multi-get: `where <key> in [<key 1>, <key 2>, ...]`. The list is usually sent as a single list parameter
+pub struct MultiGet<'a> {
+    field: Ident<'a>,
+    keys: Vec<Lit<'a>>,
+}
+
+impl<'a> MultiGet<'a> {
+    #[cfg(test)]
+    pub(crate) fn new(field: Ident<'a>, keys: Vec<Lit<'a>>) -> Self {
+        Self { field, keys }
+    }
+    pub fn field(&self) -> Ident<'a> {
+        self.field
+    }
+    pub fn into_keys(self) -> Vec<Lit<'a>> {
+        self.keys
+    }
+    /// Parse `<key> in [...]` (the cursor must be at the key)
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Self> {
+        let field = unsafe {
+            // UNSAFE(@ohsayan): the caller checked that this is an ident
+            state.fw_read().uck_read_ident()
+        };
+        state.cursor_ahead(); // skip in
+        state.poison_if_not(state.cursor_rounded_eq(Token![open []]));
+        state.cursor_ahead_if(state.okay());
+        let mut keys = Vec::new();
+        let mut stop = state.cursor_rounded_eq(Token![close []]);
+        state.cursor_ahead_if(stop);
+        while state.not_exhausted() && state.okay() && !stop {
+            state.poison_if_not(state.can_read_lit_rounded());
+            if !state.okay() {
+                break;
+            }
+            unsafe {
+                // UNSAFE(@ohsayan): we just checked that we can read a lit
+                keys.push(state.read_cursor_lit_unchecked());
+            }
+            state.cursor_ahead();
+            let nx_comma = state.cursor_rounded_eq(Token![,]);
+            let nx_csqrb = state.cursor_rounded_eq(Token![close []]);
+            state.poison_if_not(nx_comma | nx_csqrb);
+            state.cursor_ahead_if(state.okay());
+            stop = nx_csqrb;
+        }
+        state.poison_if_not(stop);
+        if state.okay() {
+            Some(Self { field, keys })
+        } else {
+            None
+        }
+    }
 }
 
 impl<'a> SelectStatement<'a> {
@@ -66,34 +232,119 @@ impl<'a> SelectStatement<'a> {
         wildcard: bool,
         clauses: WhereClauseCollection<'a>,
     ) -> SelectStatement<'a> {
-        Self::new(entity, fields, wildcard, clauses)
+        Self::new(
+            entity,
+            fields.into_iter().map(Expr::Field).collect(),
+            wildcard,
+            clauses,
+        )
+    }
+    #[cfg(test)]
+    pub(crate) fn new_test_exprs(
+        entity: EntityIDRef<'a>,
+        fields: Vec<Expr<'a>>,
+        clauses: WhereClauseCollection<'a>,
+    ) -> SelectStatement<'a> {
+        Self::new(entity, fields, false, clauses)
     }
     #[inline(always)]
     #[cfg(test)]
     fn new(
         entity: EntityIDRef<'a>,
-        fields: Vec<Ident<'a>>,
+        fields: Vec<Expr<'a>>,
         wildcard: bool,
         clauses: WhereClauseCollection<'a>,
     ) -> SelectStatement<'a> {
         Self {
             entity,
+            aliases: vec![None; fields.len()],
             fields,
             wildcard,
             clause: WhereClause::new(clauses),
+            multi: None,
+            order_by: None,
+            bounds: Bounds::unbounded(),
+            group_by: vec![],
         }
     }
+    #[cfg(test)]
+    pub(crate) fn new_multi_test(
+        entity: EntityIDRef<'a>,
+        fields: Vec<Ident<'a>>,
+        wildcard: bool,
+        multi: MultiGet<'a>,
+    ) -> SelectStatement<'a> {
+        Self {
+            entity,
+            aliases: vec![None; fields.len()],
+            fields: fields.into_iter().map(Expr::Field).collect(),
+            wildcard,
+            clause: WhereClause::new(Default::default()),
+            multi: Some(multi),
+            order_by: None,
+            bounds: Bounds::unbounded(),
+            group_by: vec![],
+        }
+    }
+    #[cfg(test)]
+    pub(crate) fn with_bounds(mut self, bounds: Bounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+    #[cfg(test)]
+    pub(crate) fn with_order_by(mut self, order_by: OrderBy<'a>) -> Self {
+        self.order_by = Some(order_by);
+        self
+    }
+    #[cfg(test)]
+    pub(crate) fn with_aliases(mut self, aliases: Vec<Option<Ident<'a>>>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+    #[cfg(test)]
+    pub(crate) fn with_group_by(mut self, group_by: Vec<Ident<'a>>) -> Self {
+        self.group_by = group_by;
+        self
+    }
     pub fn entity(&self) -> EntityIDRef<'a> {
         self.entity
     }
+    pub fn clauses(&self) -> &WhereClause<'a> {
+        &self.clause
+    }
     pub fn clauses_mut(&mut self) -> &mut WhereClause<'a> {
         &mut self.clause
     }
     pub fn is_wildcard(&self) -> bool {
         self.wildcard
     }
-    pub fn into_fields(self) -> Vec<Ident<'a>> {
-        self.fields
+    /// Returns true if the fields are aggregates (over every row that the where clause matches) or if the rows are
+    /// grouped
+    pub fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty()
+            || self
+                .fields
+                .first()
+                .is_some_and(|expr| expr.as_aggregate().is_some())
+    }
+    pub fn group_by(&self) -> &[Ident<'a>] {
+        &self.group_by
+    }
+    pub fn fields(&self) -> &[Expr<'a>] {
+        &self.fields
+    }
+    /// The alias of each field (in the same order as the fields)
+    pub fn aliases(&self) -> &[Option<Ident<'a>>] {
+        &self.aliases
+    }
+    pub fn take_multi_get(&mut self) -> Option<MultiGet<'a>> {
+        self.multi.take()
+    }
+    pub fn order_by(&self) -> Option<OrderBy<'a>> {
+        self.order_by
+    }
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
     }
 }
 
@@ -109,14 +360,15 @@ impl<'a> SelectStatement<'a> {
             return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
         }
         let mut select_fields = Vec::new();
+        let mut aliases = Vec::new();
         let is_wildcard = state.cursor_eq(Token![*]);
         state.cursor_ahead_if(is_wildcard);
         while state.not_exhausted() && state.okay() && !is_wildcard {
-            match state.read() {
-                Token::Ident(id) => select_fields.push(*id),
-                _ => break,
+            if state.cursor_eq(Token![from]) {
+                break;
             }
-            state.cursor_ahead();
+            select_fields.push(Expr::parse_column(state));
+            aliases.push(parse_alias(state));
             let nx_comma = state.cursor_rounded_eq(Token![,]);
             let nx_from = state.cursor_rounded_eq(Token![from]);
             state.poison_if_not(nx_comma | nx_from);
@@ -131,20 +383,59 @@ impl<'a> SelectStatement<'a> {
         state.cursor_ahead(); // ignore errors
         let entity = state.try_entity_buffered_into_state_uninit();
         let mut clauses = <_ as Default>::default();
+        let mut multi = None;
         if state.cursor_rounded_eq(Token![where]) {
             state.cursor_ahead();
-            WhereClause::parse_where_and_append_to(state, &mut clauses);
-            state.poison_if(clauses.is_empty());
+            if state.has_remaining(3)
+                && state.cursor_is_ident()
+                && (state.offset_current_r(1) == &Token![in])
+            {
+                multi = MultiGet::parse(state);
+            } else {
+                WhereClause::parse_where_and_append_to(state, &mut clauses);
+                state.poison_if(clauses.is_empty());
+            }
+        }
+        let group_by = parse_group_by(state);
+        let order_by = OrderBy::parse(state);
+        let bounds = Bounds::parse(state);
+        // a single row can't be ordered or bounded
+        state.poison_if((order_by.is_some() | (bounds != Bounds::unbounded())) & multi.is_none());
+        // aggregates always go over every matched row and can only be mixed with the fields that the rows are grouped
+        // by
+        let aggregates = select_fields
+            .iter()
+            .filter(|expr| expr.as_aggregate().is_some())
+            .count();
+        if group_by.is_empty() {
+            state.poison_if(
+                (aggregates != 0) & ((aggregates != select_fields.len()) | multi.is_some()),
+            );
+        } else {
+            let grouped = select_fields.iter().all(|expr| {
+                expr.as_aggregate().is_some()
+                    || expr
+                        .as_field()
+                        .is_some_and(|field| group_by.contains(&field))
+            });
+            state.poison_if(!grouped | is_wildcard | multi.is_some());
         }
         if compiler::likely(state.okay()) {
+            let mut clause = WhereClause::new(clauses);
+            clause.apply_casts()?;
             Ok(SelectStatement {
                 entity: unsafe {
                     // UNSAFE(@ohsayan): `process_entity` and `okay` assert correctness
                     entity.assume_init()
                 },
                 fields: select_fields,
+                aliases,
                 wildcard: is_wildcard,
-                clause: WhereClause::new(clauses),
+                clause,
+                multi,
+                order_by,
+                bounds,
+                group_by,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
@@ -152,12 +443,57 @@ impl<'a> SelectStatement<'a> {
     }
 }
 
+/// Parse `[as <alias>]` after a field
+fn parse_alias<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Ident<'a>> {
+    if !state.cursor_rounded_eq(Token![as]) {
+        return None;
+    }
+    state.cursor_ahead();
+    state.poison_if_not(state.not_exhausted() && state.cursor_is_ident());
+    if !state.okay() {
+        return None;
+    }
+    Some(unsafe {
+        // UNSAFE(@ohsayan): we just checked that this is an ident
+        state.fw_read().uck_read_ident()
+    })
+}
+
+/// Parse `[group by <field> [, <field>]*]`
+fn parse_group_by<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Vec<Ident<'a>> {
+    let mut fields = vec![];
+    if !state.cursor_rounded_eq(Token![group]) {
+        return fields;
+    }
+    state.cursor_ahead();
+    state.poison_if_not(state.cursor_rounded_eq(Token![by]));
+    state.cursor_ahead_if(state.okay());
+    loop {
+        state.poison_if_not(state.not_exhausted() && state.cursor_is_ident());
+        if !state.okay() {
+            break;
+        }
+        fields.push(unsafe {
+            // UNSAFE(@ohsayan): we just checked that this is an ident
+            state.fw_read().uck_read_ident()
+        });
+        if !state.cursor_rounded_eq(Token![,]) {
+            break;
+        }
+        state.cursor_ahead();
+    }
+    fields
+}
+
 #[derive(Debug, PartialEq)]
 pub struct SelectAllStatement<'a> {
     pub entity: EntityIDRef<'a>,
     pub fields: Vec<Ident<'a>>,
     pub wildcard: bool,
+    pub clause: Option<WhereClause<'a>>,
+    pub order_by: Option<OrderBy<'a>>,
     pub limit: u64,
+    pub offset: u64,
 }
 
 impl<'a> SelectAllStatement<'a> {
@@ -168,19 +504,61 @@ impl<'a> SelectAllStatement<'a> {
         wildcard: bool,
         limit: u64,
     ) -> Self {
-        Self::new(entity, fields, wildcard, limit)
+        Self::new(entity, fields, wildcard, None, None, limit, 0)
+    }
+    #[cfg(test)]
+    pub fn test_new_where(
+        entity: EntityIDRef<'a>,
+        fields: Vec<Ident<'a>>,
+        wildcard: bool,
+        clauses: WhereClauseCollection<'a>,
+        limit: u64,
+    ) -> Self {
+        Self::new(
+            entity,
+            fields,
+            wildcard,
+            Some(WhereClause::new(clauses)),
+            None,
+            limit,
+            0,
+        )
     }
-    fn new(entity: EntityIDRef<'a>, fields: Vec<Ident<'a>>, wildcard: bool, limit: u64) -> Self {
+    #[cfg(test)]
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+    #[cfg(test)]
+    pub fn with_order_by(mut self, order_by: OrderBy<'a>) -> Self {
+        self.order_by = Some(order_by);
+        self
+    }
+    fn new(
+        entity: EntityIDRef<'a>,
+        fields: Vec<Ident<'a>>,
+        wildcard: bool,
+        clause: Option<WhereClause<'a>>,
+        order_by: Option<OrderBy<'a>>,
+        limit: u64,
+        offset: u64,
+    ) -> Self {
         Self {
             entity,
             fields,
             wildcard,
+            clause,
+            order_by,
             limit,
+            offset,
         }
     }
     fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
         /*
             smallest query: select all * from mymodel limit 10
+            (with an optional filter: select all * from mymodel where x > 10 limit 10)
+            (and an optional offset: select all * from mymodel limit 10 offset 20)
+            (and an optional order: select all * from mymodel order by x desc limit 10)
         */
         if state.remaining() < 5 {
             return Err(QueryError::QLUnexpectedEndOfStatement);
@@ -206,25 +584,35 @@ impl<'a> SelectAllStatement<'a> {
         state.poison_if_not(state.cursor_eq(Token![from]));
         state.cursor_ahead(); // ignore error
         let entity = state.try_entity_buffered_into_state_uninit();
+        let mut clauses = <_ as Default>::default();
+        if state.cursor_rounded_eq(Token![where]) {
+            state.cursor_ahead();
+            WhereClause::parse_where_and_append_to(state, &mut clauses);
+            state.poison_if(clauses.is_empty());
+        }
+        let order_by = OrderBy::parse(state);
         state.poison_if_not(state.cursor_rounded_eq(Token![limit]));
         state.cursor_ahead_if(state.okay()); // we did read limit
         state.poison_if(state.exhausted()); // we MUST have the limit
+        let limit = parse_uint(state);
+        let offset = Bounds::parse_offset(state);
         if state.okay() {
-            let lit = unsafe { state.fw_read().uck_read_lit() };
-            match lit.try_uint() {
-                Some(limit) => {
-                    return unsafe {
-                        // UNSAFE(@ohsayan): state guarantees this works
-                        Ok(Self::new(
-                            entity.assume_init(),
-                            select_fields,
-                            is_wildcard,
-                            limit,
-                        ))
-                    };
-                }
-                _ => {}
+            let mut clause = (!clauses.is_empty()).then(|| WhereClause::new(clauses));
+            if let Some(clause) = clause.as_mut() {
+                clause.apply_casts()?;
             }
+            return unsafe {
+                // UNSAFE(@ohsayan): state guarantees this works
+                Ok(Self::new(
+                    entity.assume_init(),
+                    select_fields,
+                    is_wildcard,
+                    clause,
+                    order_by,
+                    limit,
+                    offset,
+                ))
+            };
         }
         Err(QueryError::QLInvalidSyntax)
     }
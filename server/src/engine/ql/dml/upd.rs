@@ -25,11 +25,14 @@
 */
 
 use {
-    super::{u, WhereClause},
+    super::{parse_lit_or_cast, u, WhereClause},
     crate::{
         engine::{
-            core::{query_meta::AssignmentOperator, EntityIDRef},
-            data::lit::Lit,
+            core::{dml::cast_lit, query_meta::AssignmentOperator, EntityIDRef},
+            data::{
+                lit::Lit,
+                tag::{DataTag, TagClass},
+            },
             error::{QueryError, QueryResult},
             ql::{
                 ast::{QueryData, State},
@@ -57,18 +60,50 @@ static OPERATOR: [AssignmentOperator; 6] = [
 pub struct AssignmentExpression<'a> {
     /// the LHS ident
     pub lhs: Ident<'a>,
-    /// the RHS lit
-    pub rhs: Lit<'a>,
+    /// the element of a list LHS (`x[1] = y`) or the key of a map LHS (`x['k'] = y`), if any
+    pub index: Option<Lit<'a>>,
+    /// the RHS lit (`None` for a null, like `x = null` or `x += null`)
+    pub rhs: Option<Lit<'a>>,
     /// operator
     pub operator_fn: AssignmentOperator,
+    /// the type that the RHS lit is cast to, until the cast is applied
+    cast: Option<Ident<'a>>,
 }
 
 impl<'a> AssignmentExpression<'a> {
+    #[cfg(test)]
     pub fn new(lhs: Ident<'a>, rhs: Lit<'a>, operator_fn: AssignmentOperator) -> Self {
         Self {
             lhs,
-            rhs,
+            index: None,
+            rhs: Some(rhs),
+            operator_fn,
+            cast: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_null(lhs: Ident<'a>) -> Self {
+        Self {
+            lhs,
+            index: None,
+            rhs: None,
+            operator_fn: AssignmentOperator::Assign,
+            cast: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_element(
+        lhs: Ident<'a>,
+        index: Lit<'a>,
+        rhs: Lit<'a>,
+        operator_fn: AssignmentOperator,
+    ) -> Self {
+        Self {
+            lhs,
+            index: Some(index),
+            rhs: Some(rhs),
             operator_fn,
+            cast: None,
         }
     }
     fn parse_and_append_expression<Qd: QueryData<'a>>(
@@ -78,6 +113,7 @@ impl<'a> AssignmentExpression<'a> {
         /*
             smallest expr:
             x = y
+            (or for an element of a list: x[1] = y, or for a key of a map: x['k'] = y)
         */
         if compiler::unlikely(state.remaining() < 3) {
             state.poison();
@@ -85,6 +121,30 @@ impl<'a> AssignmentExpression<'a> {
         }
         let lhs = state.fw_read();
         state.poison_if_not(lhs.is_ident());
+        let mut index = None;
+        if state.cursor_rounded_eq(Token![open []]) {
+            state.cursor_ahead();
+            state.poison_if_not(state.can_read_lit_rounded());
+            if state.okay() {
+                let lit = unsafe {
+                    // UNSAFE(@ohsayan): just verified that we can read a lit
+                    state.read_cursor_lit_unchecked()
+                };
+                state.cursor_ahead();
+                // a list element is picked by its position and a map value by its key
+                state.poison_if_not(matches!(
+                    lit.kind().tag_class(),
+                    TagClass::UnsignedInt | TagClass::Str
+                ));
+                index = Some(lit);
+            }
+            state.poison_if_not(state.cursor_rounded_eq(Token![close []]));
+            state.cursor_ahead_if(state.okay());
+            if compiler::unlikely(!state.okay() | (state.remaining() < 2)) {
+                state.poison();
+                return;
+            }
+        }
         let op_ass = u(state.cursor_eq(Token![=]));
         let op_add = u(state.cursor_eq(Token![+])) * 2;
         let op_sub = u(state.cursor_eq(Token![-])) * 3;
@@ -104,22 +164,34 @@ impl<'a> AssignmentExpression<'a> {
         let single_assign_okay = operator_code == 1 && !double_assign_okay;
         state.poison_if_not(single_assign_okay | double_assign_okay);
         state.cursor_ahead_if(double_assign_okay);
-        state.poison_if_not(state.can_read_lit_rounded());
-
-        if state.okay() {
-            unsafe {
-                // UNSAFE(@ohsayan): Checked lit, state flag ensures we have ident for lhs
-                let rhs = state.read_cursor_lit_unchecked();
-                state.cursor_ahead();
-                expressions.push(AssignmentExpression::new(
-                    // UNSAFE(@ohsayan): we verified if `lhs` returns `is_ident`
-                    lhs.uck_read_ident(),
-                    rhs,
-                    OPERATOR[operator_code as usize],
-                ))
-            }
+        // a null can be assigned (`x = null`) or used in arithmetic (`x += null`, which makes `x` null)
+        let is_null = state.cursor_rounded_eq(Token![null]);
+        state.cursor_ahead_if(is_null);
+        let rhs = if is_null {
+            Some((None, None))
+        } else {
+            parse_lit_or_cast(state).map(|(lit, cast)| (Some(lit), cast))
+        };
+        if let Some((rhs, cast)) = rhs.filter(|_| state.okay()) {
+            expressions.push(AssignmentExpression {
+                lhs: unsafe {
+                    // UNSAFE(@ohsayan): state flag ensures that we verified if `lhs` returns `is_ident`
+                    lhs.uck_read_ident()
+                },
+                index,
+                rhs,
+                operator_fn: OPERATOR[operator_code as usize],
+                cast,
+            })
         }
     }
+    /// Apply the cast on the RHS lit (if any). This must be done once the statement has been parsed
+    fn apply_cast(&mut self) -> QueryResult<()> {
+        if let (Some(ty), Some(rhs)) = (self.cast.take(), self.rhs.as_mut()) {
+            *rhs = cast_lit(rhs.clone(), ty)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -195,13 +267,18 @@ impl<'a> UpdateStatement<'a> {
         WhereClause::parse_where_and_append_to(state, &mut clauses);
         state.poison_if(clauses.is_empty()); // NOTE: volcano
         if compiler::likely(state.okay()) {
+            expressions
+                .iter_mut()
+                .try_for_each(AssignmentExpression::apply_cast)?;
+            let mut wc = WhereClause::new(clauses);
+            wc.apply_casts()?;
             Ok(Self {
                 entity: unsafe {
                     // UNSAFE(@ohsayan): This is safe because of `parse_entity` and `okay`
                     entity.assume_init()
                 },
                 expressions,
-                wc: WhereClause::new(clauses),
+                wc,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
@@ -240,13 +317,17 @@ mod impls {
                 let mut expr = Vec::new();
                 AssignmentExpression::parse_and_append_expression(state, &mut expr);
                 state.poison_if_not(expr.len() == 1);
-                Ok(expr.remove(0))
+                let mut expr = expr.remove(0);
+                expr.apply_cast()?;
+                Ok(expr)
             }
             fn _multiple_from_state<Qd: QueryData<'a>>(
                 state: &mut State<'a, Qd>,
             ) -> QueryResult<Vec<Self>> {
                 let mut expr = Vec::new();
                 AssignmentExpression::parse_and_append_expression(state, &mut expr);
+                expr.iter_mut()
+                    .try_for_each(AssignmentExpression::apply_cast)?;
                 Ok(expr)
             }
         }
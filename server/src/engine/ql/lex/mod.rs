@@ -33,7 +33,7 @@ use {
     crate::engine::{
         data::lit::Lit,
         error::{QueryError, QueryResult},
-        mem::BufferedScanner,
+        mem::{BufferedScanner, VInline},
     },
     core::slice,
 };
@@ -44,20 +44,36 @@ use {
 
 type Slice<'a> = &'a [u8];
 
+/// The number of tokens that a [`TokenBuf`] holds without going to the heap. Point queries (`select * from
+/// myspace.mymodel where username = ?`) lex into about a dozen tokens, so this leaves plenty of room for a few more
+/// fields
+const TOKEN_BUF_INLINE: usize = 32;
+/// The number of distinct idents that are interned per statement (any more are simply not interned)
+const IDENT_INTERN_MAX: usize = 16;
+
+/// The tokens of a statement. A short statement is lexed without any heap allocation at all
+pub type TokenBuf<'a> = VInline<TOKEN_BUF_INLINE, Token<'a>>;
+
 #[derive(Debug, PartialEq)]
 /// The internal lexer impl
 pub struct Lexer<'a> {
     token_buffer: BufferedScanner<'a>,
-    tokens: Vec<Token<'a>>,
+    tokens: TokenBuf<'a>,
+    idents: VInline<IDENT_INTERN_MAX, Slice<'a>>,
     last_error: Option<QueryError>,
 }
 
 impl<'a> Lexer<'a> {
     /// Initialize a new lexer
     fn new(src: &'a [u8]) -> Self {
+        Self::new_in(src, TokenBuf::new())
+    }
+    /// Initialize a new lexer that pushes its tokens into `tokens` (which must be empty)
+    fn new_in(src: &'a [u8], tokens: TokenBuf<'a>) -> Self {
         Self {
             token_buffer: BufferedScanner::new(src),
-            tokens: Vec::new(),
+            tokens,
+            idents: VInline::new(),
             last_error: None,
         }
     }
@@ -74,6 +90,23 @@ impl<'a> Lexer<'a> {
     fn no_error(&self) -> bool {
         self.last_error.is_none()
     }
+    /// Intern an ident: if the statement already had the same ident, its slice is returned so that comparing the two
+    /// (which the parser does all the time, for example to look up fields) only has to compare the pointers
+    fn intern(&mut self, ident: Slice<'a>) -> Slice<'a> {
+        if let Some(interned) = self
+            .idents
+            .iter()
+            .copied()
+            .find(|interned| *interned == ident)
+        {
+            return interned;
+        }
+        if self.idents.len() < IDENT_INTERN_MAX {
+            // never spills since the table is only as large as its inline capacity
+            self.idents.push(ident);
+        }
+        ident
+    }
 }
 
 impl<'a> Lexer<'a> {
@@ -104,10 +137,13 @@ impl<'a> Lexer<'a> {
             None if s.eq_ignore_ascii_case(b"true") || s.eq_ignore_ascii_case(b"false") => {
                 self.push_token(Lit::new_bool(s.eq_ignore_ascii_case(b"true")))
             }
-            None => self.tokens.push(unsafe {
-                // UNSAFE(@ohsayan): scan_ident only returns a valid ident which is always a string
-                Token::Ident(Ident::new(s))
-            }),
+            None => {
+                let s = self.intern(s);
+                self.tokens.push(unsafe {
+                    // UNSAFE(@ohsayan): scan_ident only returns a valid ident which is always a string
+                    Token::Ident(Ident::new(s))
+                })
+            }
         }
     }
     fn scan_byte(&mut self, byte: u8) {
@@ -208,7 +244,7 @@ mod insecure_impl {
                 }
             }
             match self.l.last_error {
-                None => Ok(self.l.tokens),
+                None => Ok(self.l.tokens.into_iter().collect()),
                 Some(e) => Err(e),
             }
         }
@@ -344,16 +380,21 @@ pub struct SecureLexer<'a> {
 
 impl<'a> SecureLexer<'a> {
     pub fn new_with_segments(q: &'a [u8], p: &'a [u8]) -> Self {
+        Self::new_with_segments_in(q, p, TokenBuf::new())
+    }
+    /// Same as [`Self::new_with_segments`], but the tokens are pushed into `tokens` (which must be empty) so that its
+    /// allocation can be reused
+    pub fn new_with_segments_in(q: &'a [u8], p: &'a [u8], tokens: TokenBuf<'a>) -> Self {
         Self {
-            l: Lexer::new(q),
+            l: Lexer::new_in(q, tokens),
             param_buffer: BufferedScanner::new(p),
         }
     }
-    pub fn lex(self) -> QueryResult<Vec<Token<'a>>> {
+    pub fn lex(self) -> QueryResult<TokenBuf<'a>> {
         self._lex()
     }
     #[cfg(test)]
-    pub fn lex_with_window(src: &'a [u8], query_window: usize) -> QueryResult<Vec<Token<'a>>> {
+    pub fn lex_with_window(src: &'a [u8], query_window: usize) -> QueryResult<TokenBuf<'a>> {
         Self {
             l: Lexer::new(&src[..query_window]),
             param_buffer: BufferedScanner::new(&src[query_window..]),
@@ -363,7 +404,7 @@ impl<'a> SecureLexer<'a> {
 }
 
 impl<'a> SecureLexer<'a> {
-    fn _lex(mut self) -> QueryResult<Vec<Token<'a>>> {
+    fn _lex(mut self) -> QueryResult<TokenBuf<'a>> {
         while self.l.no_error() & !self.l.token_buffer.eof() {
             let b = unsafe {
                 // UNSAFE(@ohsayan): loop invariant
@@ -415,8 +456,11 @@ impl<'a> SecureLexer<'a> {
     }
 }
 
-const SCAN_PARAM_EXPECT: [u8; 8] = [0, 1, 2, 2, 2, 2, 2, 0];
-static SCAN_PARAM: [unsafe fn(&mut SecureLexer); 8] = unsafe {
+/// The code for a list parameter. A list is sent as `<count>\n` followed by the elements, which are encoded like any
+/// other parameter (lists can't be nested)
+const SCAN_PARAM_LIST: u8 = 7;
+const SCAN_PARAM_EXPECT: [u8; 9] = [0, 1, 2, 2, 2, 2, 2, 2, 0];
+static SCAN_PARAM: [unsafe fn(&mut SecureLexer); 9] = unsafe {
     [
         // null
         |s| s.l.push_token(Token![null]),
@@ -499,6 +543,38 @@ static SCAN_PARAM: [unsafe fn(&mut SecureLexer); 8] = unsafe {
                 _ => slf.l.set_error(QueryError::LexInvalidInput),
             }
         },
+        // list
+        |slf| {
+            let Some(count) = slf
+                .param_buffer
+                .try_next_ascii_u64_lf_separated_or_restore_cursor()
+            else {
+                slf.l.set_error(QueryError::LexInvalidInput);
+                return;
+            };
+            // the list is expanded into tokens, as if it were written out in the query
+            slf.l.push_token(Token![open []]);
+            for i in 0..count {
+                if i != 0 {
+                    slf.l.push_token(Token![,]);
+                }
+                let mut target = SCAN_PARAM.len() - 1;
+                if slf
+                    .param_buffer
+                    .rounded_cursor_matches(|code| *code < SCAN_PARAM_LIST)
+                {
+                    target = slf.param_buffer.next_byte() as usize;
+                }
+                if !slf.param_buffer.has_left(SCAN_PARAM_EXPECT[target] as _) {
+                    target = SCAN_PARAM.len() - 1;
+                }
+                SCAN_PARAM[target](slf);
+                if !slf.l.no_error() {
+                    return;
+                }
+            }
+            slf.l.push_token(Token![close []]);
+        },
         // ecc
         |s| s.l.set_error(QueryError::LexInvalidInput),
     ]
@@ -26,7 +26,13 @@
 
 use {
     crate::engine::data::lit::Lit,
-    core::{borrow::Borrow, fmt, ops::Deref, str},
+    core::{
+        borrow::Borrow,
+        fmt,
+        hash::{Hash, Hasher},
+        ops::Deref,
+        ptr, str,
+    },
 };
 
 /*
@@ -34,7 +40,7 @@ use {
 */
 
 #[repr(transparent)]
-#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Clone, Copy)]
 pub struct Ident<'a>(&'a [u8]);
 impl<'a> Ident<'a> {
     pub const unsafe fn new(v: &'a [u8]) -> Self {
@@ -56,6 +62,21 @@ impl<'a> Ident<'a> {
         self.as_str().to_string().into_boxed_str()
     }
 }
+impl<'a> PartialEq for Ident<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        // the lexer interns the idents of a statement, so the same ident is almost always the same slice
+        ptr::eq(self.0, other.0) || self.0 == other.0
+    }
+}
+
+impl<'a> Eq for Ident<'a> {}
+
+impl<'a> Hash for Ident<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
 impl<'a> fmt::Debug for Ident<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(self.as_str())
@@ -349,6 +370,7 @@ flattened_lut! {
                 Update = 9,
                 Delete = 10,
                 Exists = 11,
+                Upsert = 12,
             }
         },
         /// Hi
@@ -415,8 +437,8 @@ flattened_lut! {
                 Key,
                 Value,
                 Primary,
-                // temporarily reserved (will probably be removed in the future)
-                Truncate, // TODO: decide what we want to do with this
+                // ddl misc
+                Truncate,
             }
         }
     }
@@ -432,13 +454,14 @@ impl Keyword {
         }
     }
     fn compute(key: &[u8]) -> Option<Self> {
-        static G: [u8; 69] = [
-            0, 0, 9, 64, 16, 43, 7, 49, 24, 8, 41, 37, 19, 66, 18, 0, 17, 0, 12, 63, 34, 56, 3, 24,
-            55, 14, 0, 67, 7, 0, 39, 60, 56, 0, 51, 23, 31, 19, 30, 12, 10, 58, 20, 39, 32, 0, 6,
-            30, 26, 58, 52, 62, 39, 27, 24, 9, 4, 21, 24, 68, 10, 38, 40, 21, 62, 27, 53, 27, 44,
+        static G: [u8; 85] = [
+            0, 0, 0, 19, 0, 61, 0, 13, 3, 0, 41, 10, 66, 38, 0, 0, 76, 73, 0, 47, 49, 9, 1, 0, 57,
+            39, 80, 0, 0, 15, 54, 56, 58, 36, 18, 23, 0, 80, 0, 71, 0, 0, 56, 76, 1, 81, 77, 2, 52,
+            53, 0, 49, 0, 41, 40, 80, 29, 74, 19, 68, 59, 72, 0, 61, 50, 65, 33, 0, 67, 44, 45, 71,
+            23, 51, 41, 13, 45, 0, 42, 80, 0, 1, 81, 27, 77,
         ];
-        static M1: [u8; 11] = *b"D8N5FwqrxdA";
-        static M2: [u8; 11] = *b"FsIPJv9hsXx";
+        static M1: [u8; 11] = *b"uMHwkpu9mq9";
+        static M2: [u8; 11] = *b"Ugk9QgmyjjY";
         let h1 = Self::_sum(key, M1) % G.len();
         let h2 = Self::_sum(key, M2) % G.len();
         let h = (G[h1] + G[h2]) as usize % G.len();
@@ -465,4 +488,17 @@ impl KeywordStmt {
     pub const fn is_blocking(&self) -> bool {
         self.value_u8() <= Self::Drop.value_u8()
     }
+    /// Returns true if this statement modifies data or schema
+    pub const fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Self::Create
+                | Self::Alter
+                | Self::Drop
+                | Self::Insert
+                | Self::Update
+                | Self::Delete
+                | Self::Upsert
+        )
+    }
 }
@@ -31,5 +31,9 @@ pub(super) mod dcl;
 pub(super) mod ddl;
 pub(super) mod dml;
 pub(super) mod lex;
+pub(super) mod session;
 #[cfg(test)]
 pub(in crate::engine) mod tests;
+bench! {
+    mod benches;
+}
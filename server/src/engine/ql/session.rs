@@ -0,0 +1,161 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    session variables and statement options
+    ---
+    `set <variable> = <value>` changes a variable for the rest of the connection (`statement_timeout`,
+    `result_metadata`, `packed_columns`, `traceparent`, `strict_parsing` and `retry_info`) and a statement can override the session's timeout with a
+    trailing `with timeout <duration>` clause. a duration is either an unsigned integer (in milliseconds) or a string with a unit like `'500ms'`, `'2s'`
+    or `'1m'` (the lexer doesn't accept something like `500ms` as a literal). a zero timeout means no timeout
+*/
+
+use {
+    super::{
+        ast::{traits::ASTNode, QueryData, State},
+        lex::Token,
+    },
+    crate::engine::{
+        core::trace::TraceParent,
+        data::lit::Lit,
+        error::{QueryError, QueryResult},
+    },
+    std::time::Duration,
+};
+
+/// Parse a duration (see the module docs for the format)
+pub fn parse_duration(lit: &Lit) -> Option<Duration> {
+    if let Some(ms) = lit.try_uint() {
+        return Some(Duration::from_millis(ms));
+    }
+    let duration = lit.try_str()?.trim();
+    let (n, unit) = duration.split_at(
+        duration
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(duration.len()),
+    );
+    let n: u64 = n.parse().ok()?;
+    match unit.trim() {
+        "" | "ms" => Some(Duration::from_millis(n)),
+        "s" => Some(Duration::from_secs(n)),
+        "m" => Some(Duration::from_secs(n.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// `set <variable> = <value>`
+pub enum SetSession {
+    /// `set statement_timeout = <duration | null>`
+    StatementTimeout(Option<Duration>),
+    /// `set result_metadata = <true | false>`
+    ResultMetadata(bool),
+    /// `set packed_columns = <true | false>`
+    PackedColumns(bool),
+    /// `set traceparent = <W3C traceparent | null>`
+    TraceParent(Option<TraceParent>),
+    /// `set strict_parsing = <true | false>`
+    StrictParsing(bool),
+    /// `set retry_info = <true | false>`
+    RetryInfo(bool),
+}
+
+impl<'a> ASTNode<'a> for SetSession {
+    const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+    const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+    fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+        state: &mut State<'a, Qd>,
+    ) -> QueryResult<Self> {
+        if state.remaining() < 3 {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let (variable, eq) = (state.fw_read(), state.fw_read());
+        let statement_timeout = variable.ident_eq("statement_timeout");
+        let result_metadata = variable.ident_eq("result_metadata");
+        let packed_columns = variable.ident_eq("packed_columns");
+        let traceparent = variable.ident_eq("traceparent");
+        let strict_parsing = variable.ident_eq("strict_parsing");
+        let retry_info = variable.ident_eq("retry_info");
+        if !((statement_timeout
+            | result_metadata
+            | packed_columns
+            | traceparent
+            | strict_parsing
+            | retry_info)
+            & Token![=].eq(eq))
+        {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        if (statement_timeout | traceparent) && state.cursor_eq(Token![null]) {
+            state.cursor_ahead();
+            return Ok(if statement_timeout {
+                Self::StatementTimeout(None)
+            } else {
+                Self::TraceParent(None)
+            });
+        }
+        if !state.can_read_lit_rounded() {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let lit = unsafe {
+            // UNSAFE(@ohsayan): +boundck
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        let ret = if statement_timeout {
+            parse_duration(&lit)
+                .map(|timeout| Self::StatementTimeout(Some(timeout).filter(|t| !t.is_zero())))
+        } else if result_metadata {
+            lit.try_bool().map(Self::ResultMetadata)
+        } else if traceparent {
+            lit.try_str()
+                .and_then(TraceParent::parse)
+                .map(|parent| Self::TraceParent(Some(parent)))
+        } else if strict_parsing {
+            lit.try_bool().map(Self::StrictParsing)
+        } else if retry_info {
+            lit.try_bool().map(Self::RetryInfo)
+        } else {
+            lit.try_bool().map(Self::PackedColumns)
+        };
+        ret.ok_or(QueryError::QLInvalidSyntax)
+    }
+}
+
+/// Split off the `with timeout <duration>` clause at the end of a statement (if it has one)
+pub fn split_timeout_clause<'a, 'b>(
+    tokens: &'b [Token<'a>],
+) -> QueryResult<(&'b [Token<'a>], Option<Duration>)> {
+    match tokens {
+        [stmt @ .., Token![with], timeout, Token::Lit(lit)] if timeout.ident_eq("timeout") => {
+            match parse_duration(lit) {
+                Some(timeout) => Ok((stmt, Some(timeout))),
+                None => Err(QueryError::QLInvalidSyntax),
+            }
+        }
+        _ => Ok((tokens, None)),
+    }
+}
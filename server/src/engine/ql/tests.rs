@@ -49,7 +49,7 @@ pub fn lex_insecure(src: &[u8]) -> QueryResult<Vec<Token<'_>>> {
     InsecureLexer::lex(src)
 }
 pub fn lex_secure<'a>(src: &'a [u8], query_window: usize) -> QueryResult<Vec<Token<'a>>> {
-    SecureLexer::lex_with_window(src, query_window)
+    SecureLexer::lex_with_window(src, query_window).map(|tokens| tokens.into_iter().collect())
 }
 
 pub trait NullableData<T> {
@@ -294,11 +294,15 @@ mod map_syntax {
 mod stmt_insert {
     use {
         super::*,
-        crate::engine::ql::{
-            ast::parse_ast_node_full,
-            dml::{self, ins::InsertStatement},
-            lex::Ident,
+        crate::engine::{
+            data::cell::Datacell,
+            ql::{
+                ast::parse_ast_node_full,
+                dml::{self, ins::InsertStatement},
+                lex::Ident,
+            },
         },
+        std::collections::BTreeMap,
     };
 
     #[test]
@@ -491,17 +495,87 @@ mod stmt_insert {
         );
         assert_eq!(ret, expected);
     }
+    #[test]
+    fn insert_map_literal() {
+        let tok = lex_insecure(
+            br#"insert into app.profiles ("sayan", { "theme": "dark", "langs": { "en": 1, "bn": 2 } })"#,
+        )
+        .unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let langs = BTreeMap::from([
+            ("bn".into(), Datacell::new_uint_default(2)),
+            ("en".into(), Datacell::new_uint_default(1)),
+        ]);
+        let prefs = BTreeMap::from([
+            ("langs".into(), Datacell::new_map(langs)),
+            ("theme".into(), Datacell::new_str("dark".into())),
+        ]);
+        let expected = InsertStatement::new(
+            ("app", "profiles").into(),
+            vec![Datacell::new_str("sayan".into()), Datacell::new_map(prefs)].into(),
+        );
+        assert_eq!(ret, expected);
+        for query in [
+            br#"insert into app.profiles ("sayan", { "theme": })"#.as_slice(),
+            br#"insert into app.profiles ("sayan", { theme: "dark" })"#,
+            br#"insert into app.profiles ("sayan", { 1: "dark" })"#,
+            br#"insert into app.profiles ("sayan", { "a": 1, "a": 2 })"#,
+            br#"insert into app.profiles ("sayan", { "a": 1 )"#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<InsertStatement>(&tok[1..]).is_err());
+        }
+    }
+    #[test]
+    fn insert_multi() {
+        let tok = lex_insecure(
+            br#"insert into twitter.users ("sayan", 12345), ("elizabeth", 67890), { username: "john", id: 1 }"#,
+        )
+        .unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new_multi(
+            ("twitter", "users").into(),
+            into_array_nullable!["sayan", 12345].to_vec().into(),
+            vec![
+                into_array_nullable!["elizabeth", 67890].to_vec().into(),
+                dict_nullable! {
+                    "username" => "john",
+                    "id" => 1,
+                }
+                .into(),
+            ],
+        );
+        assert_eq!(ret.row_count(), 3);
+        assert_eq!(ret, expected);
+    }
+    #[test]
+    fn insert_multi_bad() {
+        for query in [
+            br#"insert into twitter.users ("sayan"),"#.as_slice(),
+            br#"insert into twitter.users ("sayan"), "elizabeth""#,
+            br#"insert into twitter.users ("sayan") ("elizabeth")"#,
+            br#"insert into twitter.users ("sayan"),, ("elizabeth")"#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<InsertStatement>(&tok[1..]).is_err());
+        }
+    }
 }
 
 mod stmt_select {
     use {
         super::*,
         crate::engine::{
-            data::lit::Lit,
+            data::{cell::Datacell, lit::Lit},
             ql::{
                 ast::{parse_ast_node_full, parse_ast_node_full_with_space},
-                dml::{sel::SelectStatement, RelationalExpr},
+                dml::{
+                    expr::{Aggregate, AggregateFn, BinaryOp, Expr},
+                    sel::{Bounds, MultiGet, OrderBy, SelectStatement},
+                    RelationalExpr,
+                },
                 lex::Ident,
+                tests::lex_secure,
             },
         },
     };
@@ -589,6 +663,444 @@ mod stmt_select {
         );
         assert_eq!(r, e);
     }
+    #[test]
+    fn select_multi_get() {
+        let tok = lex_insecure(
+            br#"
+                select field1 from twitter.users where username in ["sayan", "robot"]
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        let e = SelectStatement::new_multi_test(
+            ("twitter", "users").into(),
+            [Ident::from("field1")].to_vec(),
+            false,
+            MultiGet::new(
+                Ident::from("username"),
+                vec![Lit::new_str("sayan"), Lit::new_str("robot")],
+            ),
+        );
+        assert_eq!(r, e);
+        // the list is usually sent as a parameter
+        let query =
+            b"select * from twitter.users where username in ?\x072\n\x065\nsayan\x065\nrobot";
+        let tok = lex_secure(query, 47).unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        let e = SelectStatement::new_multi_test(
+            ("twitter", "users").into(),
+            vec![],
+            true,
+            MultiGet::new(
+                Ident::from("username"),
+                vec![
+                    Lit::new_string("sayan".into()),
+                    Lit::new_string("robot".into()),
+                ],
+            ),
+        );
+        assert_eq!(r, e);
+        // an empty list is fine, but other clauses can't be mixed in
+        let tok = lex_insecure(b"select * from twitter.users where username in []").unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_ok());
+        let tok = lex_insecure(
+            b"select * from twitter.users where username in ['sayan'] and nick is null",
+        )
+        .unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+    }
+    #[test]
+    fn select_multi_get_bounds() {
+        let tok = lex_insecure(
+            b"select * from twitter.users where username in ['a', 'b', 'c'] limit 1 offset 1",
+        )
+        .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        let e = SelectStatement::new_multi_test(
+            ("twitter", "users").into(),
+            vec![],
+            true,
+            MultiGet::new(
+                Ident::from("username"),
+                vec![Lit::new_str("a"), Lit::new_str("b"), Lit::new_str("c")],
+            ),
+        )
+        .with_bounds(Bounds::new(1, 1));
+        assert_eq!(r, e);
+        // either can be left out
+        let tok =
+            lex_insecure(b"select * from twitter.users where username in ['a', 'b'] offset 1")
+                .unwrap();
+        assert_eq!(
+            parse_ast_node_full::<SelectStatement>(&tok[1..])
+                .unwrap()
+                .bounds(),
+            Bounds::new(u64::MAX, 1)
+        );
+        // a single row can't be bounded
+        let tok =
+            lex_insecure(b"select * from twitter.users where username = 'sayan' limit 1").unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+        let tok =
+            lex_insecure(b"select * from twitter.users where username in ['a'] limit -1").unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+    }
+    #[test]
+    fn select_multi_get_order_by() {
+        let tok = lex_insecure(
+            b"select * from twitter.users where username in ['a', 'b'] order by age desc limit 1",
+        )
+        .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        let e = SelectStatement::new_multi_test(
+            ("twitter", "users").into(),
+            vec![],
+            true,
+            MultiGet::new(
+                Ident::from("username"),
+                vec![Lit::new_str("a"), Lit::new_str("b")],
+            ),
+        )
+        .with_order_by(OrderBy::new(Ident::from("age"), true))
+        .with_bounds(Bounds::new(1, 0));
+        assert_eq!(r, e);
+        // ascending is the default
+        for query in [
+            b"select * from twitter.users where username in ['a'] order by age".as_slice(),
+            b"select * from twitter.users where username in ['a'] order by age asc",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert_eq!(
+                parse_ast_node_full::<SelectStatement>(&tok[1..])
+                    .unwrap()
+                    .order_by(),
+                Some(OrderBy::new(Ident::from("age"), false))
+            );
+        }
+        // a single row can't be ordered
+        let tok =
+            lex_insecure(b"select * from twitter.users where username = 'sayan' order by age")
+                .unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+        let tok =
+            lex_insecure(b"select * from twitter.users where username in ['a'] order age").unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+        let tok =
+            lex_insecure(b"select * from twitter.users where username in ['a'] order by").unwrap();
+        assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+    }
+    #[test]
+    fn select_aggregate() {
+        let tok = lex_insecure(b"select count(*) from app.users").unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        assert_eq!(
+            r,
+            SelectStatement::new_test_exprs(
+                ("app", "users").into(),
+                vec![Expr::Aggregate(Aggregate::new(AggregateFn::Count, None))],
+                dict! {},
+            )
+        );
+        assert!(r.is_aggregate());
+        let tok = lex_insecure(
+            b"select count(email), SUM(age), avg(age), min(age), max(age) from app.users where age > 18",
+        )
+        .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        let age = Some(Ident::from("age"));
+        assert_eq!(
+            r,
+            SelectStatement::new_test_exprs(
+                ("app", "users").into(),
+                vec![
+                    Expr::Aggregate(Aggregate::new(
+                        AggregateFn::Count,
+                        Some(Ident::from("email"))
+                    )),
+                    Expr::Aggregate(Aggregate::new(AggregateFn::Sum, age)),
+                    Expr::Aggregate(Aggregate::new(AggregateFn::Avg, age)),
+                    Expr::Aggregate(Aggregate::new(AggregateFn::Min, age)),
+                    Expr::Aggregate(Aggregate::new(AggregateFn::Max, age)),
+                ],
+                dict! {
+                    Ident::from("age") => RelationalExpr::new(
+                        Ident::from("age"), Lit::new_uint(18), RelationalExpr::OP_GT
+                    ),
+                },
+            )
+        );
+    }
+    #[test]
+    fn select_aggregate_bad() {
+        for query in [
+            // only count can go over whole rows
+            &b"select sum(*) from app.users"[..],
+            b"select count(1) from app.users",
+            b"select count(email, age) from app.users",
+            b"select count(*",
+            // aggregates can't be mixed with fields
+            b"select email, count(*) from app.users",
+            b"select count(*), email from app.users",
+            b"select count(*) from app.users where username in ['a']",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+        }
+    }
+    #[test]
+    fn select_group_by() {
+        let tok =
+            lex_insecure(b"select city, count(*) from app.users where age > 18 group by city")
+                .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        assert_eq!(
+            r,
+            SelectStatement::new_test_exprs(
+                ("app", "users").into(),
+                vec![
+                    Expr::Field(Ident::from("city")),
+                    Expr::Aggregate(Aggregate::new(AggregateFn::Count, None)),
+                ],
+                dict! {
+                    Ident::from("age") => RelationalExpr::new(
+                        Ident::from("age"), Lit::new_uint(18), RelationalExpr::OP_GT
+                    ),
+                },
+            )
+            .with_group_by(vec![Ident::from("city")])
+        );
+        assert!(r.is_aggregate());
+        // grouped fields don't have to be selected
+        let tok = lex_insecure(b"select max(age), country from app.users group by country, city")
+            .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        assert_eq!(
+            r,
+            SelectStatement::new_test_exprs(
+                ("app", "users").into(),
+                vec![
+                    Expr::Aggregate(Aggregate::new(AggregateFn::Max, Some(Ident::from("age")))),
+                    Expr::Field(Ident::from("country")),
+                ],
+                dict! {},
+            )
+            .with_group_by(vec![Ident::from("country"), Ident::from("city")])
+        );
+    }
+    #[test]
+    fn select_group_by_bad() {
+        for query in [
+            &b"select city, count(*) from app.users group city"[..],
+            b"select city, count(*) from app.users group by",
+            b"select city, count(*) from app.users group by city,",
+            b"select city, count(*) from app.users group by 'city'",
+            // every field has to be grouped
+            b"select city, email, count(*) from app.users group by city",
+            b"select * from app.users group by city",
+            b"select city from app.users where username in ['a'] group by city",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+        }
+    }
+    #[test]
+    fn select_aliases() {
+        let tok = lex_insecure(
+            b"select username as name, age + 1 as next_age, email from app.users where username = 'sayan'",
+        )
+        .unwrap();
+        let r = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        assert_eq!(
+            r,
+            SelectStatement::new_test_exprs(
+                ("app", "users").into(),
+                vec![
+                    Expr::Field(Ident::from("username")),
+                    Expr::Binary(
+                        BinaryOp::Add,
+                        Box::new(Expr::Field(Ident::from("age"))),
+                        Box::new(Expr::Value(Datacell::new_uint_default(1)))
+                    ),
+                    Expr::Field(Ident::from("email")),
+                ],
+                dict! {
+                    Ident::from("username") => RelationalExpr::new(
+                        Ident::from("username"), Lit::new_str("sayan"), RelationalExpr::OP_EQ
+                    ),
+                },
+            )
+            .with_aliases(vec![
+                Some(Ident::from("name")),
+                Some(Ident::from("next_age")),
+                None
+            ])
+        );
+        for query in [
+            &b"select username as from app.users where username = 'sayan'"[..],
+            b"select username as 'name' from app.users where username = 'sayan'",
+            b"select username name from app.users where username = 'sayan'",
+            b"select * as everything from app.users where username = 'sayan'",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<SelectStatement>(&tok[1..]).is_err());
+        }
+    }
+}
+mod scalar_expr {
+    use {
+        super::*,
+        crate::engine::{
+            data::cell::Datacell,
+            ql::{
+                ast::parse_ast_node_full,
+                dml::expr::{BinaryOp, Expr},
+                lex::Ident,
+            },
+        },
+    };
+    fn field(name: &'static str) -> Box<Expr<'static>> {
+        Box::new(Expr::Field(Ident::from(name)))
+    }
+    fn uint(v: u64) -> Box<Expr<'static>> {
+        Box::new(Expr::Value(Datacell::new_uint_default(v)))
+    }
+    #[test]
+    fn expr_arithmetic() {
+        // `*` and `/` bind tighter than `+` and `-`, and both are left associative
+        let tok = lex_insecure(b"a + b * 2 - c / 4").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(
+            r,
+            Expr::Binary(
+                BinaryOp::Sub,
+                Box::new(Expr::Binary(
+                    BinaryOp::Add,
+                    field("a"),
+                    Box::new(Expr::Binary(BinaryOp::Mul, field("b"), uint(2)))
+                )),
+                Box::new(Expr::Binary(BinaryOp::Div, field("c"), uint(4)))
+            )
+        );
+    }
+    #[test]
+    fn expr_arithmetic_parens() {
+        let tok = lex_insecure(b"(age + 1) * abs(balance)").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(
+            r,
+            Expr::Binary(
+                BinaryOp::Mul,
+                Box::new(Expr::Binary(BinaryOp::Add, field("age"), uint(1))),
+                Box::new(Expr::Call(
+                    Ident::from("abs"),
+                    vec![Expr::Field(Ident::from("balance"))]
+                ))
+            )
+        );
+    }
+    #[test]
+    fn expr_arithmetic_bad() {
+        for src in [&b"age +"[..], b"age + * 2", b"(age + 1", b"()"] {
+            let tok = lex_insecure(src).unwrap();
+            assert!(parse_ast_node_full::<Expr>(&tok).is_err());
+        }
+        // operators count towards the maximum depth
+        let src = format!("a{}", " + a".repeat(Expr::MAX_DEPTH + 1));
+        let tok = lex_insecure(src.as_bytes()).unwrap();
+        assert!(parse_ast_node_full::<Expr>(&tok).is_err());
+    }
+    #[test]
+    fn expr_field() {
+        let tok = lex_insecure(b"email").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(r, Expr::Field(Ident::from("email")));
+    }
+    #[test]
+    fn expr_call() {
+        let tok = lex_insecure(b"coalesce(email, null, 'none')").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(
+            r,
+            Expr::Call(
+                Ident::from("coalesce"),
+                vec![
+                    Expr::Field(Ident::from("email")),
+                    Expr::Value(Datacell::null()),
+                    Expr::Value(Datacell::from("none")),
+                ]
+            )
+        );
+    }
+    #[test]
+    fn expr_call_nested() {
+        let tok = lex_insecure(b"ifnull(email, ifnull(phone, 0))").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(
+            r,
+            Expr::Call(
+                Ident::from("ifnull"),
+                vec![
+                    Expr::Field(Ident::from("email")),
+                    Expr::Call(
+                        Ident::from("ifnull"),
+                        vec![
+                            Expr::Field(Ident::from("phone")),
+                            Expr::Value(Datacell::new_uint_default(0))
+                        ]
+                    ),
+                ]
+            )
+        );
+    }
+    #[test]
+    fn expr_call_noargs() {
+        let tok = lex_insecure(b"coalesce()").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(r, Expr::Call(Ident::from("coalesce"), vec![]));
+    }
+    #[test]
+    fn expr_cast() {
+        let tok = lex_insecure(b"cast(ifnull(age, 0) as string)").unwrap();
+        let r = parse_ast_node_full::<Expr>(&tok).unwrap();
+        assert_eq!(
+            r,
+            Expr::Cast(
+                Box::new(Expr::Call(
+                    Ident::from("ifnull"),
+                    vec![
+                        Expr::Field(Ident::from("age")),
+                        Expr::Value(Datacell::new_uint_default(0))
+                    ]
+                )),
+                Ident::from("string")
+            )
+        );
+    }
+    #[test]
+    fn expr_cast_bad() {
+        for src in [
+            &b"cast(age)"[..],
+            b"cast(age as)",
+            b"cast(age as string",
+            b"cast(age as 'string')",
+            b"cast(age, string)",
+        ] {
+            let tok = lex_insecure(src).unwrap();
+            assert!(parse_ast_node_full::<Expr>(&tok).is_err());
+        }
+    }
+    #[test]
+    fn expr_call_bad() {
+        for src in [
+            &b"coalesce(email,)"[..],
+            b"coalesce(email",
+            b"coalesce(email email)",
+            b"coalesce(,)",
+        ] {
+            let tok = lex_insecure(src).unwrap();
+            assert!(parse_ast_node_full::<Expr>(&tok).is_err());
+        }
+    }
 }
 mod expression_tests {
     use {
@@ -596,7 +1108,12 @@ mod expression_tests {
         crate::engine::{
             core::query_meta::AssignmentOperator,
             data::lit::Lit,
-            ql::{ast::parse_ast_node_full, dml::upd::AssignmentExpression, lex::Ident},
+            error::QueryError,
+            ql::{
+                ast::{parse_ast_node_full, parse_ast_node_full_with_space},
+                dml::upd::{AssignmentExpression, UpdateStatement},
+                lex::Ident,
+            },
         },
     };
     #[test]
@@ -613,6 +1130,20 @@ mod expression_tests {
         );
     }
     #[test]
+    fn expr_assign_null() {
+        let src = lex_insecure(b"email = null").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(r, AssignmentExpression::new_null(Ident::from("email")));
+        // arithmetic can have a null too
+        let src = lex_insecure(b"followers += null").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(r.rhs, None);
+        assert_eq!(r.operator_fn, AssignmentOperator::AddAssign);
+        let src =
+            lex_insecure(b"update app set followers += null where username = 'sayan'").unwrap();
+        assert!(parse_ast_node_full_with_space::<UpdateStatement>(&src[1..], "apps").is_ok());
+    }
+    #[test]
     fn expr_add_assign() {
         let src = lex_insecure(b"followers += 100").unwrap();
         let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
@@ -664,6 +1195,73 @@ mod expression_tests {
             )
         );
     }
+    #[test]
+    fn expr_cast_assign() {
+        let src = lex_insecure(b"followers += cast('100' as uint64)").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(
+            r,
+            AssignmentExpression::new(
+                Ident::from("followers"),
+                Lit::new_uint(100),
+                AssignmentOperator::AddAssign
+            )
+        );
+        let src = lex_insecure(b"followers += cast(-100 as uint64)").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<AssignmentExpression>(&src).unwrap_err(),
+            QueryError::QExecDmlLossyCast
+        );
+    }
+    #[test]
+    fn expr_element_assign() {
+        let src = lex_insecure(b"tags[1] = 'sayan'").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(
+            r,
+            AssignmentExpression::new_element(
+                Ident::from("tags"),
+                Lit::new_uint(1),
+                Lit::new_str("sayan"),
+                AssignmentOperator::Assign
+            )
+        );
+        let src = lex_insecure(b"scores[0] += 10").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(
+            r,
+            AssignmentExpression::new_element(
+                Ident::from("scores"),
+                Lit::new_uint(0),
+                Lit::new_uint(10),
+                AssignmentOperator::AddAssign
+            )
+        );
+    }
+    #[test]
+    fn expr_key_assign() {
+        let src = lex_insecure(br#"prefs["theme"] = "dark""#).unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(
+            r,
+            AssignmentExpression::new_element(
+                Ident::from("prefs"),
+                Lit::new_str("theme"),
+                Lit::new_str("dark"),
+                AssignmentOperator::Assign
+            )
+        );
+        let src = lex_insecure(b"prefs -= 'theme'").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(
+            r,
+            AssignmentExpression::new(
+                Ident::from("prefs"),
+                Lit::new_str("theme"),
+                AssignmentOperator::SubAssign
+            )
+        );
+    }
 }
 mod update_statement {
     use {
@@ -671,6 +1269,7 @@ mod update_statement {
         crate::engine::{
             core::query_meta::AssignmentOperator,
             data::lit::Lit,
+            error::QueryError,
             ql::{
                 ast::{parse_ast_node_full, parse_ast_node_full_with_space},
                 dml::{
@@ -708,6 +1307,22 @@ mod update_statement {
         assert_eq!(r, e);
     }
     #[test]
+    fn update_element_bad() {
+        for src in [
+            b"update app set tags[] = 'sayan' where username = 'sayan'".as_slice(),
+            b"update app set tags[true] = 'sayan' where username = 'sayan'",
+            b"update app set tags[1 = 'sayan' where username = 'sayan'",
+            b"update app set tags[1] where username = 'sayan'",
+            b"update app set tags[1] = where username = 'sayan'",
+        ] {
+            let tok = lex_insecure(src).unwrap();
+            assert_eq!(
+                parse_ast_node_full_with_space::<UpdateStatement>(&tok[1..], "apps").unwrap_err(),
+                QueryError::QLInvalidSyntax
+            );
+        }
+    }
+    #[test]
     fn update() {
         let tok = lex_insecure(
             br#"
@@ -812,6 +1427,7 @@ mod relational_expr {
         super::*,
         crate::engine::{
             data::lit::Lit,
+            error::QueryError,
             ql::{ast::parse_ast_node_full, dml::RelationalExpr, lex::Ident},
         },
     };
@@ -825,7 +1441,9 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_EQ
+                opc: RelationalExpr::OP_EQ,
+                bound: None,
+                casts: (None, None)
             }
         );
     }
@@ -838,7 +1456,9 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_NE
+                opc: RelationalExpr::OP_NE,
+                bound: None,
+                casts: (None, None)
             }
         );
     }
@@ -851,7 +1471,9 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_GT
+                opc: RelationalExpr::OP_GT,
+                bound: None,
+                casts: (None, None)
             }
         );
     }
@@ -864,7 +1486,9 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_GE
+                opc: RelationalExpr::OP_GE,
+                bound: None,
+                casts: (None, None)
             }
         );
     }
@@ -877,7 +1501,9 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_LT
+                opc: RelationalExpr::OP_LT,
+                bound: None,
+                casts: (None, None)
             }
         );
     }
@@ -894,6 +1520,136 @@ mod relational_expr {
             )
         );
     }
+    #[test]
+    fn expr_is_null() {
+        let expr = lex_insecure(b"email is null").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert_eq!(r.null_test(), Some(true));
+        assert_eq!(r.lhs, Ident::from("email"));
+    }
+    #[test]
+    fn expr_is_not_null() {
+        let expr = lex_insecure(b"email is not null").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert_eq!(r.null_test(), Some(false));
+        assert_eq!(r.lhs, Ident::from("email"));
+    }
+    #[test]
+    fn expr_cmp_null() {
+        for expr in [&b"email = null"[..], b"email != null", b"email < null"] {
+            let expr = lex_insecure(expr).unwrap();
+            let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+            assert!(r.is_cmp_null());
+            assert_eq!(r.null_test(), None);
+            assert_eq!(r.lhs, Ident::from("email"));
+        }
+    }
+    #[test]
+    fn expr_is_bad() {
+        for expr in [&b"email is 10"[..], b"email is not", b"email is not 10"] {
+            let expr = lex_insecure(expr).unwrap();
+            assert!(parse_ast_node_full::<RelationalExpr>(&expr).is_err());
+        }
+    }
+    #[test]
+    fn expr_starts_with() {
+        let expr = lex_insecure(b"url starts with 'https://'").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert_eq!(
+            r,
+            RelationalExpr::new(
+                Ident::from("url"),
+                Lit::new_str("https://"),
+                RelationalExpr::OP_STARTS_WITH
+            )
+        );
+        for expr in [
+            &b"url starts 'https://'"[..],
+            b"url starts with",
+            b"url starts with with",
+        ] {
+            let expr = lex_insecure(expr).unwrap();
+            assert!(parse_ast_node_full::<RelationalExpr>(&expr).is_err());
+        }
+    }
+    #[test]
+    fn expr_cast() {
+        let expr = lex_insecure(b"primary_key = cast('10' as uint8)").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert_eq!(
+            r,
+            RelationalExpr::new(
+                Ident::from("primary_key"),
+                Lit::new_uint(10),
+                RelationalExpr::OP_EQ
+            )
+        );
+        // (the test lexer can't read floats)
+        for (src, lo, hi) in [
+            (
+                &b"primary_key between cast(-1 as float64) and cast(5 as float64)"[..],
+                Lit::new_float(-1.0),
+                Lit::new_float(5.0),
+            ),
+            (
+                b"primary_key between cast(1 as float64) and 3",
+                Lit::new_float(1.0),
+                Lit::new_uint(3),
+            ),
+            (
+                b"primary_key between 1 and cast('3' as uint64)",
+                Lit::new_uint(1),
+                Lit::new_uint(3),
+            ),
+        ] {
+            let expr = lex_insecure(src).unwrap();
+            let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+            assert_eq!(
+                r,
+                RelationalExpr::new_bounded(
+                    Ident::from("primary_key"),
+                    (RelationalExpr::OP_GE, lo),
+                    (RelationalExpr::OP_LE, hi),
+                )
+            );
+        }
+    }
+    #[test]
+    fn expr_cast_bad() {
+        for expr in [
+            &b"primary_key = cast(10)"[..],
+            b"primary_key = cast(10 as)",
+            b"primary_key = cast(10 as uint8",
+            b"primary_key = cast(age as uint8)",
+        ] {
+            let expr = lex_insecure(expr).unwrap();
+            assert_eq!(
+                parse_ast_node_full::<RelationalExpr>(&expr).unwrap_err(),
+                QueryError::QLInvalidSyntax
+            );
+        }
+        // the casts themselves
+        for (expr, error) in [
+            (
+                &b"primary_key = cast(300 as uint8)"[..],
+                QueryError::QExecDmlLossyCast,
+            ),
+            (
+                b"primary_key = cast('ten' as uint8)",
+                QueryError::QExecDmlValidationError,
+            ),
+            (
+                b"primary_key = cast(10 as int)",
+                QueryError::QExecDmlValidationError,
+            ),
+        ] {
+            let expr = lex_insecure(expr).unwrap();
+            assert_eq!(
+                parse_ast_node_full::<RelationalExpr>(&expr).unwrap_err(),
+                error
+            );
+        }
+    }
 }
 mod where_clause {
     use {
@@ -956,14 +1712,58 @@ mod where_clause {
         .unwrap();
         assert!(parse_ast_node_full::<WhereClause>(&tok).is_err());
     }
+    #[test]
+    fn where_range() {
+        let expected = WhereClause::new(dict! {
+            Ident::from("userid") => RelationalExpr::new_bounded(
+                Ident::from("userid"),
+                (RelationalExpr::OP_GE, Lit::new_uint(100)),
+                (RelationalExpr::OP_LE, Lit::new_uint(200)),
+            ),
+            Ident::from("pass") => RelationalExpr::new(
+                Ident::from("pass"),
+                Lit::new_str("password"),
+                RelationalExpr::OP_EQ
+            )
+        });
+        let tok = lex_insecure(br#"userid between 100 and 200 and pass = "password""#).unwrap();
+        assert_eq!(expected, parse_ast_node_full::<WhereClause>(&tok).unwrap());
+        let tok =
+            lex_insecure(br#"userid >= 100 and pass = "password" and userid <= 200"#).unwrap();
+        assert_eq!(expected, parse_ast_node_full::<WhereClause>(&tok).unwrap());
+    }
+    #[test]
+    fn where_range_bad() {
+        for bad in [
+            "userid between 100",
+            "userid between 100 and",
+            "userid between 100 or 200",
+            "userid between and 200",
+            "userid > 100 and userid < 200 and userid != 150",
+            "userid between 100 and 200 and userid < 150",
+            "userid is null and userid < 150",
+            "userid starts with 'a' and userid < 'b'",
+        ] {
+            let tok = lex_insecure(bad.as_bytes()).unwrap();
+            assert!(parse_ast_node_full::<WhereClause>(&tok).is_err(), "{bad}");
+        }
+    }
 }
 
 mod select_all {
     use {
         super::lex_insecure,
         crate::engine::{
+            data::lit::Lit,
             error::QueryError,
-            ql::{ast::parse_ast_node_full_with_space, dml::sel::SelectAllStatement},
+            ql::{
+                ast::parse_ast_node_full_with_space,
+                dml::{
+                    sel::{OrderBy, SelectAllStatement},
+                    RelationalExpr,
+                },
+                lex::Ident,
+            },
         },
     };
 
@@ -990,6 +1790,94 @@ mod select_all {
         );
     }
 
+    #[test]
+    fn select_all_where() {
+        let tok =
+            lex_insecure(b"select all * from mymodel where age >= 18 and score < 100 limit 100")
+                .unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap(),
+            SelectAllStatement::test_new_where(
+                ("myspace", "mymodel").into(),
+                vec![],
+                true,
+                dict! {
+                    Ident::from("age") => RelationalExpr::new(
+                        Ident::from("age"), Lit::new_uint(18), RelationalExpr::OP_GE
+                    ),
+                    Ident::from("score") => RelationalExpr::new(
+                        Ident::from("score"), Lit::new_uint(100), RelationalExpr::OP_LT
+                    ),
+                },
+                100
+            )
+        );
+        // the where clause can't be empty
+        let tok = lex_insecure(b"select all * from mymodel where limit 100").unwrap();
+        assert!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").is_err()
+        );
+    }
+
+    #[test]
+    fn select_all_offset() {
+        let tok = lex_insecure(b"select all * from mymodel limit 100 offset 200").unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap(),
+            SelectAllStatement::test_new(("myspace", "mymodel").into(), vec![], true, 100)
+                .with_offset(200)
+        );
+        // the offset goes after the limit
+        let tok = lex_insecure(b"select all * from mymodel offset 200 limit 100").unwrap();
+        assert!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").is_err()
+        );
+        let tok = lex_insecure(b"select all * from mymodel limit 100 offset").unwrap();
+        assert!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").is_err()
+        );
+    }
+
+    #[test]
+    fn select_all_order_by() {
+        let tok = lex_insecure(
+            b"select all * from mymodel where age >= 18 order by age desc limit 100 offset 10",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap(),
+            SelectAllStatement::test_new_where(
+                ("myspace", "mymodel").into(),
+                vec![],
+                true,
+                dict! {
+                    Ident::from("age") => RelationalExpr::new(
+                        Ident::from("age"), Lit::new_uint(18), RelationalExpr::OP_GE
+                    ),
+                },
+                100
+            )
+            .with_order_by(OrderBy::new(Ident::from("age"), true))
+            .with_offset(10)
+        );
+        let tok = lex_insecure(b"select all username from mymodel order by age limit 100").unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap(),
+            SelectAllStatement::test_new(
+                ("myspace", "mymodel").into(),
+                into_vec!["username"],
+                false,
+                100
+            )
+            .with_order_by(OrderBy::new(Ident::from("age"), false))
+        );
+        // the order goes before the limit
+        let tok = lex_insecure(b"select all * from mymodel limit 100 order by age").unwrap();
+        assert!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").is_err()
+        );
+    }
+
     #[test]
     fn select_all_missing_limit() {
         let tok = lex_insecure(b"select all * from mymodel").unwrap();
@@ -50,6 +50,20 @@ fn lex_ident() {
     );
 }
 
+#[test]
+fn lex_keywords() {
+    let src = v!("upsert INSERT truncate Primary");
+    assert_eq!(
+        lex_insecure(&src).unwrap(),
+        vec![
+            Token![upsert],
+            Token![insert],
+            Token![truncate],
+            Token![primary]
+        ]
+    );
+}
+
 // literals
 #[test]
 fn lex_unsigned_int() {
@@ -356,3 +370,95 @@ fn safe_params_shuffled() {
         )
     }
 }
+
+#[test]
+fn safe_query_list() {
+    let (query, query_window) = make_safe_query(b"?", b"\x073\n\x021\n\x00\x065\nsayan");
+    let ret = lex_secure(&query, query_window).unwrap();
+    assert_eq!(
+        ret,
+        vec![
+            Token![open []],
+            Token::Lit(Lit::new_uint(1)),
+            Token![,],
+            Token![null],
+            Token![,],
+            Token::Lit(Lit::new_string("sayan".into())),
+            Token![close []],
+        ]
+    );
+    // empty
+    let (query, query_window) = make_safe_query(b"?", b"\x070\n");
+    let ret = lex_secure(&query, query_window).unwrap();
+    assert_eq!(ret, vec![Token![open []], Token![close []]]);
+    // nested lists and missing elements are rejected
+    for bad in [&b"\x071\n\x071\n\x00"[..], b"\x072\n\x021\n"] {
+        let (query, query_window) = make_safe_query(b"?", bad);
+        assert_eq!(
+            lex_secure(&query, query_window).unwrap_err(),
+            QueryError::LexInvalidInput
+        );
+    }
+}
+
+#[test]
+fn safe_query_recycled_tokens() {
+    use crate::engine::{core::scratch::Scratch, ql::lex::SecureLexer};
+    let mut scratch = Scratch::new();
+    // a point query fits into the inline part of the buffer
+    let (query, query_window) =
+        make_safe_query(b"select * from myspace.mymodel where x = ?", b"\x021\n");
+    let tokens = SecureLexer::new_with_segments_in(
+        &query[..query_window],
+        &query[query_window..],
+        scratch.take_tokens(),
+    )
+    .lex()
+    .unwrap();
+    assert_eq!(tokens[..], lex_secure(&query, query_window).unwrap()[..]);
+    assert!(tokens.on_stack());
+    scratch.recycle_tokens(tokens);
+    // a long statement spills to the heap, and the next statement reuses the allocation
+    let long_query = format!("insert into myspace.mymodel({})", ["?"; 40].join(", "));
+    let (query, query_window) = make_safe_query(long_query.as_bytes(), &b"\x021\n".repeat(40));
+    let tokens = SecureLexer::new_with_segments_in(
+        &query[..query_window],
+        &query[query_window..],
+        scratch.take_tokens(),
+    )
+    .lex()
+    .unwrap();
+    assert_eq!(tokens[..], lex_secure(&query, query_window).unwrap()[..]);
+    assert!(!tokens.on_stack());
+    let capacity = tokens.capacity();
+    scratch.recycle_tokens(tokens);
+    // the buffer borrows from the next statement as soon as it's taken, so the statement has to outlive it
+    let (next_query, next_window) =
+        make_safe_query(b"select * from mymodel where x = ?", b"\x021\n");
+    let tokens = scratch.take_tokens();
+    assert!(tokens.is_empty());
+    assert_eq!(tokens.capacity(), capacity);
+    let tokens = SecureLexer::new_with_segments_in(
+        &next_query[..next_window],
+        &next_query[next_window..],
+        tokens,
+    )
+    .lex()
+    .unwrap();
+    assert_eq!(
+        tokens[..],
+        lex_secure(&next_query, next_window).unwrap()[..]
+    );
+    assert_eq!(tokens.capacity(), capacity);
+}
+
+#[test]
+fn interned_idents() {
+    let tokens = lex_insecure(b"select username, email from users where username = 'x'").unwrap();
+    let (Token::Ident(a), Token::Ident(b)) = (&tokens[1], &tokens[7]) else {
+        panic!("expected idents")
+    };
+    assert_eq!(a, b);
+    // the same ident is the same slice
+    assert_eq!(a.as_slice().as_ptr(), b.as_slice().as_ptr());
+}
@@ -25,9 +25,22 @@
 */
 
 use super::*;
-use crate::engine::ql::{
-    ast::{traits::ASTNode, State},
-    ddl::{Inspect, Use},
+use {
+    crate::engine::{
+        data::lit::Lit,
+        error::QueryError,
+        ql::{
+            ast::{explain::Explain, parse_ast_node_full, traits::ASTNode, State},
+            ddl::{Inspect, Use},
+            dml::{
+                sel::{SelectAllStatement, SelectStatement},
+                RelationalExpr,
+            },
+            lex::Ident,
+            session::{self, SetSession},
+        },
+    },
+    std::time::Duration,
 };
 
 /*
@@ -84,6 +97,24 @@ fn use_current() {
     );
 }
 
+#[test]
+fn use_trailing_tokens() {
+    let t = lex_insecure(b"use myspace otherspace").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Use::parse_from_state_hardened(&mut state).unwrap_err(),
+        QueryError::QLUnexpectedTrailingTokens
+    );
+    // the cursor is left at the first trailing token
+    assert_eq!(state.cursor(), 1);
+    let mut state = State::new_inplace(&t[1..]);
+    state.set_lenient(true);
+    assert_eq!(
+        Use::parse_from_state_hardened(&mut state).unwrap(),
+        Use::Space("myspace".into())
+    );
+}
+
 #[test]
 fn inspect_global() {
     let t = lex_insecure(b"inspect global").unwrap();
@@ -113,3 +144,227 @@ fn inspect_model() {
         Inspect::Model(("myspace", "mymodel").into())
     );
 }
+
+#[test]
+fn inspect_tasks() {
+    let t = lex_insecure(b"inspect sys.tasks").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Inspect::test_parse_from_state(&mut state).unwrap(),
+        Inspect::Tasks
+    );
+    let t = lex_insecure(b"inspect sys.task_runs").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Inspect::test_parse_from_state(&mut state).unwrap(),
+        Inspect::TaskRuns
+    );
+    let t = lex_insecure(b"inspect sys.users").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(Inspect::test_parse_from_state(&mut state).is_err());
+}
+
+#[test]
+fn inspect_model_fingerprint() {
+    let t = lex_insecure(b"inspect model myspace.mymodel fingerprint").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Inspect::test_parse_from_state(&mut state).unwrap(),
+        Inspect::ModelFingerprint(("myspace", "mymodel").into())
+    );
+}
+
+#[test]
+fn inspect_jobs() {
+    let t = lex_insecure(b"inspect sys.jobs").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Inspect::test_parse_from_state(&mut state).unwrap(),
+        Inspect::Jobs
+    );
+    let t = lex_insecure(b"inspect sys.watermarks").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Inspect::test_parse_from_state(&mut state).unwrap(),
+        Inspect::Watermarks
+    );
+    let t = lex_insecure(b"inspect sys.users").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(Inspect::test_parse_from_state(&mut state).is_err());
+}
+
+/*
+    session
+*/
+
+#[test]
+fn set_statement_timeout() {
+    let t = lex_insecure(b"set statement_timeout = 500").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::StatementTimeout(Some(Duration::from_millis(500)))
+    );
+    let t = lex_insecure(b"set statement_timeout = '2s'").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::StatementTimeout(Some(Duration::from_secs(2)))
+    );
+    for reset in [
+        b"set statement_timeout = null".as_slice(),
+        b"set statement_timeout = 0",
+    ] {
+        let t = lex_insecure(reset).unwrap();
+        let mut state = State::new_inplace(&t[1..]);
+        assert_eq!(
+            SetSession::test_parse_from_state(&mut state).unwrap(),
+            SetSession::StatementTimeout(None)
+        );
+    }
+    for bad in [
+        b"set statement_timeout = '2h'".as_slice(),
+        b"set statement_time = 500",
+        b"set statement_timeout 500",
+    ] {
+        let t = lex_insecure(bad).unwrap();
+        let mut state = State::new_inplace(&t[1..]);
+        assert!(SetSession::test_parse_from_state(&mut state).is_err());
+    }
+}
+
+#[test]
+fn set_result_metadata() {
+    let t = lex_insecure(b"set result_metadata = true").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::ResultMetadata(true)
+    );
+    let t = lex_insecure(b"set result_metadata = null").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(SetSession::test_parse_from_state(&mut state).is_err());
+}
+
+#[test]
+fn set_packed_columns() {
+    let t = lex_insecure(b"set packed_columns = false").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::PackedColumns(false)
+    );
+    let t = lex_insecure(b"set packed_columns = 1").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(SetSession::test_parse_from_state(&mut state).is_err());
+}
+
+#[test]
+fn set_traceparent() {
+    let t = lex_insecure(
+        b"set traceparent = '00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01'",
+    )
+    .unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(matches!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::TraceParent(Some(_))
+    ));
+    let t = lex_insecure(b"set traceparent = null").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::TraceParent(None)
+    );
+    let t = lex_insecure(b"set traceparent = 'garbage'").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(SetSession::test_parse_from_state(&mut state).is_err());
+}
+
+#[test]
+fn set_strict_parsing() {
+    let t = lex_insecure(b"set strict_parsing = false").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::StrictParsing(false)
+    );
+    let t = lex_insecure(b"set strict_parsing = 'no'").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(SetSession::test_parse_from_state(&mut state).is_err());
+}
+
+#[test]
+fn set_retry_info() {
+    let t = lex_insecure(b"set retry_info = true").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        SetSession::test_parse_from_state(&mut state).unwrap(),
+        SetSession::RetryInfo(true)
+    );
+    let t = lex_insecure(b"set retry_info = 1").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert!(SetSession::test_parse_from_state(&mut state).is_err());
+}
+
+#[test]
+fn statement_timeout_clause() {
+    let t = lex_insecure(b"select all * from apps.social limit 10 with timeout '200ms'").unwrap();
+    let (stmt, timeout) = session::split_timeout_clause(&t).unwrap();
+    assert_eq!(stmt, &t[..t.len() - 3]);
+    assert_eq!(timeout, Some(Duration::from_millis(200)));
+    let t = lex_insecure(b"select all * from apps.social limit 10").unwrap();
+    let (stmt, timeout) = session::split_timeout_clause(&t).unwrap();
+    assert_eq!(stmt, &t[..]);
+    assert_eq!(timeout, None);
+    let t = lex_insecure(b"select all * from apps.social limit 10 with timeout 'soon'").unwrap();
+    assert!(session::split_timeout_clause(&t).is_err());
+}
+
+/*
+    explain
+*/
+
+#[test]
+fn explain_select() {
+    let t = lex_insecure(b"explain select * from apps.users where username = 'sayan'").unwrap();
+    assert_eq!(
+        parse_ast_node_full::<Explain>(&t[1..]).unwrap(),
+        Explain::Select(SelectStatement::new_test(
+            ("apps", "users").into(),
+            vec![],
+            true,
+            dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"), Lit::new_str("sayan"), RelationalExpr::OP_EQ
+                ),
+            },
+        ))
+    );
+}
+
+#[test]
+fn explain_select_all() {
+    let t = lex_insecure(b"explain select all * from apps.users limit 10").unwrap();
+    assert_eq!(
+        parse_ast_node_full::<Explain>(&t[1..]).unwrap(),
+        Explain::SelectAll(SelectAllStatement::test_new(
+            ("apps", "users").into(),
+            vec![],
+            true,
+            10
+        ))
+    );
+}
+
+#[test]
+fn explain_bad() {
+    for bad in [&b"explain insert into apps.users('sayan')"[..], b"explain"] {
+        let t = lex_insecure(bad).unwrap();
+        let mut state = State::new_inplace(&t[1..]);
+        assert_eq!(
+            Explain::test_parse_from_state(&mut state).unwrap_err(),
+            QueryError::QLInvalidSyntax
+        );
+    }
+}
@@ -303,6 +303,53 @@ mod fields {
         )
     }
     #[test]
+    fn field_not_null() {
+        let tok = lex_insecure(b"not null username: string").unwrap();
+        let f = parse_ast_node_full::<FieldSpec>(&tok).unwrap();
+        assert_eq!(
+            f,
+            FieldSpec::new(
+                Ident::from("username"),
+                [LayerSpec::new(Ident::from("string"), null_dict! {})].into(),
+                false,
+                false
+            )
+        );
+        let tok = lex_insecure(b"primary not null username: string").unwrap();
+        assert!(parse_ast_node_full::<FieldSpec>(&tok).unwrap().primary);
+        for bad in [
+            &b"not username: string"[..],
+            b"primary null username: string",
+        ] {
+            let tok = lex_insecure(bad).unwrap();
+            assert!(parse_ast_node_full::<FieldSpec>(&tok).is_err());
+        }
+    }
+    #[test]
+    fn field_unique() {
+        let tok = lex_insecure(b"unique null email: string").unwrap();
+        let f = parse_ast_node_full::<FieldSpec>(&tok).unwrap();
+        assert_eq!(
+            f,
+            FieldSpec::new(
+                Ident::from("email"),
+                [LayerSpec::new(Ident::from("string"), null_dict! {})].into(),
+                true,
+                false
+            )
+            .with_unique()
+        );
+        // a field can still be called `unique`
+        let tok = lex_insecure(b"unique: string").unwrap();
+        let f = parse_ast_node_full::<FieldSpec>(&tok).unwrap();
+        assert_eq!(f.field_name, Ident::from("unique"));
+        assert!(!f.unique);
+        let tok = lex_insecure(b"unique unique: string").unwrap();
+        let f = parse_ast_node_full::<FieldSpec>(&tok).unwrap();
+        assert_eq!(f.field_name, Ident::from("unique"));
+        assert!(f.unique);
+    }
+    #[test]
     fn field_pro() {
         let tok = lex_insecure(
             b"
@@ -379,6 +426,54 @@ mod schemas {
         syn::{FieldSpec, LayerSpec},
     };
     #[test]
+    fn schema_snapshot() {
+        fullparse_verify_substmt_with_space(
+            "create model mysnapshot as snapshot of mymodel",
+            "apps",
+            |r: CreateModel| {
+                assert_eq!(
+                    r,
+                    CreateModel::new_snapshot(
+                        ("apps", "mysnapshot").into(),
+                        ("apps", "mymodel").into(),
+                        false
+                    )
+                )
+            },
+        );
+        fullparse_verify_substmt_with_space(
+            "create model if not exists apps.mysnapshot as snapshot of apps.mymodel",
+            "apps",
+            |r: CreateModel| {
+                assert_eq!(
+                    r,
+                    CreateModel::new_snapshot(
+                        ("apps", "mysnapshot").into(),
+                        ("apps", "mymodel").into(),
+                        true
+                    )
+                )
+            },
+        );
+        fullparse_verify_substmt_with_space(
+            "create model mysnapshot as snapshot of mymodel with { mask: { email: 'hash' } }",
+            "apps",
+            |r: CreateModel| {
+                let mut snapshot = CreateModel::new_snapshot(
+                    ("apps", "mysnapshot").into(),
+                    ("apps", "mymodel").into(),
+                    false,
+                );
+                snapshot.props = null_dict! {
+                    "mask" => null_dict! {
+                        "email" => Lit::new_string("hash".into())
+                    }
+                };
+                assert_eq!(r, snapshot)
+            },
+        );
+    }
+    #[test]
     fn schema_mini() {
         let mut ret = CreateModel::new(
             ("apps", "mymodel").into(),
@@ -1094,7 +1189,11 @@ mod ddl_other_query_tests {
         super::*,
         crate::engine::ql::{
             ast::{parse_ast_node_full, parse_ast_node_full_with_space},
-            ddl::drop::{DropModel, DropSpace},
+            ddl::{
+                crt::CreateIndex,
+                drop::{DropIndex, DropModel, DropSpace, TruncateModel},
+                task::{AlterTask, CreateTask, DropTask},
+            },
             lex::Ident,
         },
     };
@@ -1150,4 +1249,115 @@ mod ddl_other_query_tests {
             DropModel::new(("apps", "mymodel").into(), true, true)
         );
     }
+    #[test]
+    fn create_task() {
+        let src = lex_insecure(
+            br"create task sweep schedule '*/5 * * * *' do delete from apps.sessions where id = 'x'",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_ast_node_full::<CreateTask>(&src[2..]).unwrap(),
+            CreateTask::new(Ident::from("sweep"), "*/5 * * * *".into())
+        );
+        let src =
+            lex_insecure(br"create task compact schedule '@daily' do truncate model apps.logs")
+                .unwrap();
+        assert_eq!(
+            parse_ast_node_full::<CreateTask>(&src[2..]).unwrap(),
+            CreateTask::new(Ident::from("compact"), "@daily".into())
+        );
+        for bad in [
+            &b"create task sweep schedule '@daily' do"[..],
+            b"create task sweep '@daily' do delete from apps.sessions where id = 'x'",
+            b"create task sweep schedule 5 do delete from apps.sessions where id = 'x'",
+            b"create task sweep schedule '@daily' delete from apps.sessions where id = 'x'",
+            b"create task sweep schedule '@daily' do apps.sessions",
+            b"create task sweep schedule '@daily' do drop task other",
+        ] {
+            let src = lex_insecure(bad).unwrap();
+            assert!(parse_ast_node_full::<CreateTask>(&src[2..]).is_err());
+        }
+    }
+    #[test]
+    fn alter_drop_task() {
+        let src = lex_insecure(br"alter task sweep disable").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<AlterTask>(&src[2..]).unwrap(),
+            AlterTask::new(Ident::from("sweep"), false)
+        );
+        let src = lex_insecure(br"alter task sweep enable").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<AlterTask>(&src[2..]).unwrap(),
+            AlterTask::new(Ident::from("sweep"), true)
+        );
+        let src = lex_insecure(br"alter task sweep pause").unwrap();
+        assert!(parse_ast_node_full::<AlterTask>(&src[2..]).is_err());
+        let src = lex_insecure(br"drop task sweep").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<DropTask>(&src[2..]).unwrap(),
+            DropTask::new(Ident::from("sweep"))
+        );
+    }
+    #[test]
+    fn truncate_model() {
+        let src = lex_insecure(br"truncate model apps.mymodel").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<TruncateModel>(&src[2..]).unwrap(),
+            TruncateModel::new(("apps", "mymodel").into())
+        );
+        let src = lex_insecure(br"truncate model mymodel").unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<TruncateModel>(&src[2..], "apps").unwrap(),
+            TruncateModel::new(("apps", "mymodel").into())
+        );
+        let src = lex_insecure(br"truncate model apps.mymodel allow not empty").unwrap();
+        assert!(parse_ast_node_full::<TruncateModel>(&src[2..]).is_err());
+    }
+    #[test]
+    fn create_index() {
+        let src = lex_insecure(br"create index by_email on apps.users(email)").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<CreateIndex>(&src[2..]).unwrap(),
+            CreateIndex::new(
+                Ident::from("by_email"),
+                ("apps", "users").into(),
+                Ident::from("email"),
+                false
+            )
+        );
+        let src = lex_insecure(br"create index if not exists by_email on users(email)").unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<CreateIndex>(&src[2..], "apps").unwrap(),
+            CreateIndex::new(
+                Ident::from("by_email"),
+                ("apps", "users").into(),
+                Ident::from("email"),
+                true
+            )
+        );
+        for bad in [
+            &b"create index by_email apps.users(email)"[..],
+            b"create index by_email on apps.users(email, name)",
+            b"create index by_email on apps.users email",
+            b"create index on apps.users(email)",
+        ] {
+            let src = lex_insecure(bad).unwrap();
+            assert!(parse_ast_node_full::<CreateIndex>(&src[2..]).is_err());
+        }
+    }
+    #[test]
+    fn drop_index() {
+        let src = lex_insecure(br"drop index by_email on apps.users").unwrap();
+        assert_eq!(
+            parse_ast_node_full::<DropIndex>(&src[2..]).unwrap(),
+            DropIndex::new(Ident::from("by_email"), ("apps", "users").into(), false)
+        );
+        let src = lex_insecure(br"drop index if exists by_email on users").unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<DropIndex>(&src[2..], "apps").unwrap(),
+            DropIndex::new(Ident::from("by_email"), ("apps", "users").into(), true)
+        );
+        let src = lex_insecure(br"drop index by_email apps.users").unwrap();
+        assert!(parse_ast_node_full::<DropIndex>(&src[2..]).is_err());
+    }
 }
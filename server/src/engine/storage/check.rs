@@ -0,0 +1,234 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Offline consistency checks for a data directory (`skyd check`)
+
+use {
+    super::{
+        common::paths_v1,
+        v1,
+        v2::{
+            self,
+            impls::{gns_log::GNSDriver, mdl_journal::ModelDriver},
+        },
+    },
+    crate::engine::core::GNSData,
+    std::{collections::HashSet, fmt::Write, fs, path::Path},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Issue {
+    level: Level,
+    object: String,
+    message: String,
+}
+
+#[derive(Debug, Default)]
+/// The result of checking a data directory
+pub struct Report {
+    spaces: usize,
+    models: usize,
+    rows: usize,
+    issues: Vec<Issue>,
+}
+
+impl Report {
+    fn error(&mut self, object: impl ToString, message: impl ToString) {
+        self.issue(Level::Error, object, message)
+    }
+    fn warn(&mut self, object: impl ToString, message: impl ToString) {
+        self.issue(Level::Warning, object, message)
+    }
+    fn issue(&mut self, level: Level, object: impl ToString, message: impl ToString) {
+        self.issues.push(Issue {
+            level,
+            object: object.to_string(),
+            message: message.to_string(),
+        })
+    }
+    /// Returns true if no errors were found (warnings are fine)
+    pub fn is_ok(&self) -> bool {
+        self.issues.iter().all(|issue| issue.level != Level::Error)
+    }
+    /// Returns the report as a JSON object
+    pub fn to_json(&self) -> String {
+        let mut ret = format!(
+            "{{\"ok\":{},\"spaces\":{},\"models\":{},\"rows\":{},\"issues\":[",
+            self.is_ok(),
+            self.spaces,
+            self.models,
+            self.rows
+        );
+        let mut issues = self.issues.iter().peekable();
+        while let Some(Issue {
+            level,
+            object,
+            message,
+        }) = issues.next()
+        {
+            let _ = write!(
+                ret,
+                "{{\"level\":\"{}\",\"object\":{object:?},\"message\":{message:?}}}",
+                level.as_str()
+            );
+            if issues.peek().is_some() {
+                ret.push(',');
+            }
+        }
+        ret.push_str("]}");
+        ret
+    }
+}
+
+/// Check the data directory in the current working directory. Nothing is written to disk
+pub fn check_data_dir() -> Report {
+    let mut report = Report::default();
+    if Path::new(v1::SYSDB_PATH).is_file() {
+        report.error(
+            v1::SYSDB_PATH,
+            "data directory uses the older storage format. start the server once to upgrade it",
+        );
+        return report;
+    }
+    if Path::new(crate::SKY_PID_FILE).is_file() {
+        report.warn(
+            crate::SKY_PID_FILE,
+            "the server may be running. results may be inaccurate",
+        );
+    }
+    if !Path::new(v2::GNS_PATH).is_file() {
+        report.error(v2::GNS_PATH, "GNS is missing. not a data directory");
+        return report;
+    }
+    // header, event chain and checksums of the GNS
+    let gns = GNSData::empty();
+    if let Err(e) = GNSDriver::verify_gns(&gns) {
+        report.error(v2::GNS_PATH, e);
+        return report;
+    }
+    let mut known_dirs = HashSet::new();
+    // spaces
+    for (space_name, space) in gns.idx().read().iter() {
+        report.spaces += 1;
+        space.register_storage_path();
+        let space_dir = paths_v1::space_dir(space_name, space.get_uuid());
+        if !Path::new(&space_dir).is_dir() {
+            report.error(
+                &space_dir,
+                format!("directory for space `{space_name}` is missing"),
+            );
+        }
+        known_dirs.insert(space_dir);
+        known_dirs.insert(paths_v1::trash_dir(space_name, space.get_uuid()));
+    }
+    // models
+    for (id, model) in gns.idx_models().read().iter() {
+        report.models += 1;
+        let model_name = format!("{}.{}", id.space(), id.entity());
+        let Some(space_uuid) = gns
+            .idx()
+            .read()
+            .get(id.space())
+            .map(|space| space.get_uuid())
+        else {
+            report.error(&model_name, "model belongs to a space that doesn't exist");
+            continue;
+        };
+        let model_uuid = model.data().get_uuid();
+        known_dirs.insert(paths_v1::model_dir(
+            id.space(),
+            space_uuid,
+            id.entity(),
+            model_uuid,
+        ));
+        let model_path = paths_v1::model_path(id.space(), space_uuid, id.entity(), model_uuid);
+        if !Path::new(&model_path).is_file() {
+            report.error(
+                &model_path,
+                format!("data file for `{model_name}` is missing"),
+            );
+            continue;
+        }
+        // header, event chain and batch checksums of the model
+        match ModelDriver::verify_model_driver(model.data(), &model_path) {
+            Ok(()) => report.rows += model.data().primary_index().count(),
+            Err(e) => report.error(&model_path, e),
+        }
+    }
+    // anything on disk that the GNS doesn't know about
+    let mut scan = vec![v2::DATA_DIR.to_owned()];
+    scan.extend(
+        known_dirs
+            .iter()
+            .filter(|dir| !dir.contains("/mdl_"))
+            .cloned(),
+    );
+    for dir in scan {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = format!("{dir}/{}", entry.file_name().to_string_lossy());
+            let is_dir = entry.file_type().map(|ty| ty.is_dir()).unwrap_or(false);
+            if is_dir && !known_dirs.contains(&path) {
+                report.warn(&path, "directory is not referenced by the GNS");
+            }
+        }
+    }
+    report
+}
+
+#[test]
+fn report_json() {
+    let mut report = Report::default();
+    assert_eq!(
+        report.to_json(),
+        r#"{"ok":true,"spaces":0,"models":0,"rows":0,"issues":[]}"#
+    );
+    report.warn("data/x", "unreferenced");
+    assert!(report.is_ok());
+    report.error("gns.db-tlog", "bad \"checksum\"");
+    assert!(!report.is_ok());
+    assert_eq!(
+        report.to_json(),
+        r#"{"ok":false,"spaces":0,"models":0,"rows":0,"issues":[{"level":"warning","object":"data/x","message":"unreferenced"},{"level":"error","object":"gns.db-tlog","message":"bad \"checksum\""}]}"#
+    );
+}
@@ -30,16 +30,90 @@
     file system
 */
 
-#[cfg(test)]
-use super::vfs::{VFileDescriptor, VirtualFS};
 use {
+    super::vfs::{VFileDescriptor, VirtualFS},
     crate::IoResult,
     std::{
+        borrow::Cow,
         fs as std_fs,
         io::{BufReader, BufWriter, Error, ErrorKind, Read, Seek, SeekFrom, Write},
+        sync::Arc,
     },
 };
 
+/// A file system that the storage layer can run on
+pub trait Vfs {
+    fn fs_is_file(&self, path: &str) -> bool;
+    fn fs_is_dir(&self, path: &str) -> bool;
+    #[cfg(test)]
+    fn fs_read(&self, path: &str) -> IoResult<Vec<u8>>;
+    fn fs_create_dir(&mut self, path: &str) -> IoResult<()>;
+    fn fs_create_dir_all(&mut self, path: &str) -> IoResult<()>;
+    #[cfg(test)]
+    fn fs_delete_dir(&mut self, path: &str) -> IoResult<()>;
+    fn fs_delete_dir_all(&mut self, path: &str) -> IoResult<()>;
+    fn fs_remove_file(&mut self, path: &str) -> IoResult<()>;
+    fn fs_rename(&mut self, from: &str, to: &str) -> IoResult<()>;
+    /// Returns the names of the entries in the directory (in no particular order)
+    fn fs_read_dir(&self, path: &str) -> IoResult<Vec<String>>;
+    /// Sync the directory entry so that any creates, renames or deletes in it are durable
+    fn fs_sync_dir(&mut self, path: &str) -> IoResult<()>;
+}
+
+/// The local (OS) file system
+pub struct LocalFS;
+
+impl Vfs for LocalFS {
+    fn fs_is_file(&self, path: &str) -> bool {
+        std::path::Path::new(path).is_file()
+    }
+    fn fs_is_dir(&self, path: &str) -> bool {
+        std::path::Path::new(path).is_dir()
+    }
+    #[cfg(test)]
+    fn fs_read(&self, path: &str) -> IoResult<Vec<u8>> {
+        std_fs::read(path)
+    }
+    fn fs_create_dir(&mut self, path: &str) -> IoResult<()> {
+        std_fs::create_dir(path)
+    }
+    fn fs_create_dir_all(&mut self, path: &str) -> IoResult<()> {
+        std_fs::create_dir_all(path)
+    }
+    #[cfg(test)]
+    fn fs_delete_dir(&mut self, path: &str) -> IoResult<()> {
+        std_fs::remove_dir(path)
+    }
+    fn fs_delete_dir_all(&mut self, path: &str) -> IoResult<()> {
+        std_fs::remove_dir_all(path)
+    }
+    fn fs_remove_file(&mut self, path: &str) -> IoResult<()> {
+        std_fs::remove_file(path)
+    }
+    fn fs_rename(&mut self, from: &str, to: &str) -> IoResult<()> {
+        std_fs::rename(from, to)
+    }
+    fn fs_read_dir(&self, path: &str) -> IoResult<Vec<String>> {
+        let mut names = vec![];
+        for entry in std_fs::read_dir(path)? {
+            names.push(entry?.file_name().to_string_lossy().into_owned());
+        }
+        Ok(names)
+    }
+    fn fs_sync_dir(&mut self, path: &str) -> IoResult<()> {
+        #[cfg(unix)]
+        {
+            std_fs::File::open(path)?.sync_all()
+        }
+        #[cfg(not(unix))]
+        {
+            // directories can't be synced on windows; NTFS journals metadata anyway
+            let _ = path;
+            Ok(())
+        }
+    }
+}
+
 pub struct FileSystem {}
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -48,90 +122,199 @@ pub enum FSContext {
     Virtual,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+/// The file system that a thread's storage calls go to, and the directory that their paths are relative to
+pub struct FSScope {
+    ctx: FSContext,
+    root: Option<Arc<str>>,
+}
+
+impl FSScope {
+    pub fn new(ctx: FSContext, root: Option<&str>) -> Self {
+        Self {
+            ctx,
+            root: root.map(Arc::from),
+        }
+    }
+    /// The scope that threads start out with: the in-memory [`VirtualFS`] for tests and the local file system
+    /// otherwise, with paths relative to the current directory (or the root of the virtual file system)
+    fn default_scope() -> Self {
+        Self::new(
+            if cfg!(test) {
+                FSContext::Virtual
+            } else {
+                FSContext::Local
+            },
+            None,
+        )
+    }
+    pub fn context(&self) -> FSContext {
+        self.ctx
+    }
+    fn resolve<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        match (&self.root, path) {
+            (None, path) => Cow::Borrowed(path),
+            (Some(root), "" | ".") => Cow::Owned(root.to_string()),
+            // absolute paths (like a space's storage path) are already where they should be
+            (Some(_), path) if std::path::Path::new(path).is_absolute() => Cow::Borrowed(path),
+            (Some(root), path) => Cow::Owned(format!("{root}/{path}")),
+        }
+    }
+}
+
+local! {
+    /// the scope of this thread's storage calls
+    static FS_SCOPE: FSScope = FSScope::default_scope();
+}
+
+#[must_use]
+/// Puts the thread back in the scope that it was in before [`FileSystem::enter`], once dropped
+pub struct FSScopeGuard {
+    prev: Option<FSScope>,
+}
+
+impl Drop for FSScopeGuard {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            FileSystem::set_scope(prev);
+        }
+    }
+}
+
 impl FileSystem {
-    fn context() -> FSContext {
-        local! { static CTX: FSContext = FSContext::Virtual; }
-        local_ref!(CTX, |ctx| *ctx)
+    pub fn context() -> FSContext {
+        local_mut!(FS_SCOPE, |scope| scope.ctx)
+    }
+    pub fn scope() -> FSScope {
+        local_mut!(FS_SCOPE, |scope| scope.clone())
+    }
+    /// Run this thread's storage calls in the given scope (for good), returning the scope that it was in. This must be
+    /// done before the thread opens any file
+    pub fn set_scope(scope: FSScope) -> FSScope {
+        local_mut!(FS_SCOPE, |current| core::mem::replace(current, scope))
+    }
+    /// Run this thread's storage calls in the given scope until the guard is dropped
+    pub fn enter(scope: FSScope) -> FSScopeGuard {
+        FSScopeGuard {
+            prev: Some(Self::set_scope(scope)),
+        }
+    }
+    /// Resolve a storage path against the root of this thread's scope
+    fn resolve(path: &str) -> Cow<'_, str> {
+        local_mut!(FS_SCOPE, |scope| scope.resolve(path))
+    }
+    fn with_fs<T>(f: impl FnOnce(&mut dyn Vfs) -> IoResult<T>) -> IoResult<T> {
+        match Self::context() {
+            FSContext::Local => f(&mut LocalFS),
+            FSContext::Virtual => f(&mut *VirtualFS::instance().write()),
+        }
     }
 }
 
 impl FileSystem {
+    #[inline(always)]
+    pub fn is_file(path: &str) -> bool {
+        Self::with_fs(|fs| Ok(fs.fs_is_file(&Self::resolve(path)))).unwrap_or(false)
+    }
+    #[inline(always)]
+    pub fn is_dir(path: &str) -> bool {
+        Self::with_fs(|fs| Ok(fs.fs_is_dir(&Self::resolve(path)))).unwrap_or(false)
+    }
+    #[cfg(test)]
     #[inline(always)]
     pub fn read(path: &str) -> IoResult<Vec<u8>> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().read().get_data(path),
-            }
-        }
-        std_fs::read(path)
+        Self::with_fs(|fs| fs.fs_read(&Self::resolve(path)))
     }
     #[inline(always)]
     pub fn create_dir(path: &str) -> IoResult<()> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().write().fs_create_dir(path),
-            }
-        }
-        std_fs::create_dir(path)
+        Self::with_fs(|fs| fs.fs_create_dir(&Self::resolve(path)))
     }
     #[inline(always)]
     pub fn create_dir_all(path: &str) -> IoResult<()> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().write().fs_create_dir_all(path),
-            }
-        }
-        std_fs::create_dir_all(path)
+        Self::with_fs(|fs| fs.fs_create_dir_all(&Self::resolve(path)))
     }
+    #[cfg(test)]
     #[inline(always)]
     pub fn remove_dir(path: &str) -> IoResult<()> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().write().fs_delete_dir(path),
-            }
-        }
-        std_fs::remove_dir(path)
+        Self::with_fs(|fs| fs.fs_delete_dir(&Self::resolve(path)))
     }
     #[inline(always)]
     pub fn remove_dir_all(path: &str) -> IoResult<()> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().write().fs_delete_dir_all(path),
-            }
-        }
-        std_fs::remove_dir_all(path)
+        Self::with_fs(|fs| fs.fs_delete_dir_all(&Self::resolve(path)))
     }
     #[inline(always)]
     pub fn remove_file(path: &str) -> IoResult<()> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().write().fs_remove_file(path),
-            }
-        }
-        std_fs::remove_file(path)
+        Self::with_fs(|fs| fs.fs_remove_file(&Self::resolve(path)))
     }
     #[inline(always)]
     pub fn rename(from: &str, to: &str) -> IoResult<()> {
-        #[cfg(test)]
-        {
-            match Self::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => return VirtualFS::instance().write().fs_rename(from, to),
-            }
+        Self::with_fs(|fs| fs.fs_rename(&Self::resolve(from), &Self::resolve(to)))
+    }
+    #[inline(always)]
+    pub fn read_dir(path: &str) -> IoResult<Vec<String>> {
+        Self::with_fs(|fs| fs.fs_read_dir(&Self::resolve(path)))
+    }
+    /// Sync the directory entry so that any creates, renames or deletes in it are durable
+    #[inline(always)]
+    pub fn sync_dir(path: &str) -> IoResult<()> {
+        Self::with_fs(|fs| fs.fs_sync_dir(&Self::resolve(path)))
+    }
+}
+
+/*
+    temporary files
+*/
+
+/// A temporary file that atomically replaces a target file once it is committed. If dropped before
+/// being committed, the temporary file is removed and the target is left untouched
+#[derive(Debug)]
+pub struct TempFile {
+    tmp: String,
+    target: String,
+    committed: bool,
+}
+
+impl TempFile {
+    const SUFFIX: &'static str = ".tmp";
+    /// Prepare a temporary path for the given target. Leftovers from an earlier (crashed) attempt are
+    /// removed
+    pub fn new(target: &str) -> IoResult<Self> {
+        let tmp = format!("{target}{}", Self::SUFFIX);
+        match FileSystem::remove_file(&tmp) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(Self {
+            tmp,
+            target: target.into(),
+            committed: false,
+        })
+    }
+    /// Path of the temporary file
+    pub fn path(&self) -> &str {
+        &self.tmp
+    }
+    /// Atomically move the temporary file over the target and sync the parent directory so that the
+    /// rename itself is durable.
+    ///
+    /// NB: All data must have been synced to the temporary file before calling this
+    pub fn commit(mut self) -> IoResult<()> {
+        FileSystem::rename(&self.tmp, &self.target)?;
+        self.committed = true;
+        let parent = match std::path::Path::new(&self.target).parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_string_lossy().into_owned(),
+            _ => ".".into(),
+        };
+        FileSystem::sync_dir(&parent)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = FileSystem::remove_file(&self.tmp);
         }
-        std_fs::rename(from, to)
     }
 }
 
@@ -163,8 +346,7 @@ pub trait FileWrite {
                         Err(Error::new(
                             ErrorKind::WriteZero,
                             format!("could only write {} of {} bytes", written, buf.len()),
-                        )
-                        .into()),
+                        )),
                     )
                 }
                 Ok(n) => written += n,
@@ -177,8 +359,9 @@ pub trait FileWrite {
 
 pub trait FileWriteExt {
     fn fsync_all(&mut self) -> IoResult<()>;
-    fn fsync_data(&mut self) -> IoResult<()>;
     fn f_truncate(&mut self, new_size: u64) -> IoResult<()>;
+    /// Reserve space for `len` bytes from `offset` without changing the length of the file
+    fn f_preallocate(&mut self, offset: u64, len: u64) -> IoResult<()>;
 }
 
 pub trait FileExt {
@@ -207,12 +390,12 @@ impl FileWriteExt for File {
     fn fsync_all(&mut self) -> IoResult<()> {
         self.f.fsync_all()
     }
-    fn fsync_data(&mut self) -> IoResult<()> {
-        self.f.fsync_data()
-    }
     fn f_truncate(&mut self, new_size: u64) -> IoResult<()> {
         self.f.f_truncate(new_size)
     }
+    fn f_preallocate(&mut self, offset: u64, len: u64) -> IoResult<()> {
+        self.f.f_preallocate(offset, len)
+    }
 }
 
 impl FileExt for File {
@@ -270,12 +453,12 @@ impl<Lf: LocalFile> FileWriteExt for Lf {
     fn fsync_all(&mut self) -> IoResult<()> {
         self._mut().sync_all()
     }
-    fn fsync_data(&mut self) -> IoResult<()> {
-        self._mut().sync_data()
-    }
     fn f_truncate(&mut self, new_size: u64) -> IoResult<()> {
         self._mut().set_len(new_size)
     }
+    fn f_preallocate(&mut self, offset: u64, len: u64) -> IoResult<()> {
+        crate::util::os::preallocate(self._ref(), offset, len)
+    }
 }
 
 impl<Lf: LocalFile> FileExt for Lf {
@@ -294,7 +477,6 @@ impl<Lf: LocalFile> FileExt for Lf {
     impls for vfile
 */
 
-#[cfg(test)]
 impl<Lf: FileWrite> FileWrite for AnyFile<Lf> {
     fn fwrite(&mut self, buf: &[u8]) -> IoResult<u64> {
         match self {
@@ -306,7 +488,6 @@ impl<Lf: FileWrite> FileWrite for AnyFile<Lf> {
     }
 }
 
-#[cfg(test)]
 impl<Lf: FileRead> FileRead for AnyFile<Lf> {
     fn fread_exact(&mut self, buf: &mut [u8]) -> IoResult<()> {
         match self {
@@ -318,18 +499,13 @@ impl<Lf: FileRead> FileRead for AnyFile<Lf> {
     }
 }
 
-#[cfg(test)]
 impl<Lf: FileWriteExt> FileWriteExt for AnyFile<Lf> {
     fn fsync_all(&mut self) -> IoResult<()> {
         match self {
             Self::Local(lf) => lf.fsync_all(),
-            Self::Virtual(_) => Ok(()),
-        }
-    }
-    fn fsync_data(&mut self) -> IoResult<()> {
-        match self {
-            Self::Local(lf) => lf.fsync_data(),
-            Self::Virtual(_) => Ok(()),
+            Self::Virtual(vf) => VirtualFS::instance()
+                .read()
+                .with_file_mut(&vf.0, |f| f.sync()),
         }
     }
     fn f_truncate(&mut self, new_size: u64) -> IoResult<()> {
@@ -340,9 +516,14 @@ impl<Lf: FileWriteExt> FileWriteExt for AnyFile<Lf> {
                 .with_file_mut(&vf.0, |f| f.truncate(new_size)),
         }
     }
+    fn f_preallocate(&mut self, offset: u64, len: u64) -> IoResult<()> {
+        match self {
+            Self::Local(lf) => lf.f_preallocate(offset, len),
+            Self::Virtual(_) => Ok(()),
+        }
+    }
 }
 
-#[cfg(test)]
 impl<Lf: FileExt> FileExt for AnyFile<Lf> {
     fn f_len(&self) -> IoResult<u64> {
         match self {
@@ -374,7 +555,6 @@ impl<Lf: FileExt> FileExt for AnyFile<Lf> {
     file abstraction
 */
 
-#[cfg(test)]
 #[derive(Debug)]
 enum AnyFile<Lf = std_fs::File> {
     Local(Lf),
@@ -383,62 +563,53 @@ enum AnyFile<Lf = std_fs::File> {
 
 #[derive(Debug)]
 pub struct File {
-    #[cfg(test)]
     f: AnyFile,
-    #[cfg(not(test))]
-    f: std_fs::File,
 }
 
 impl File {
-    pub fn open(path: &str) -> IoResult<Self> {
-        #[cfg(test)]
+    /// Options used to open local files. A file is either freshly created or opened as an existing file
+    /// (never both), so callers always know which of the two happened.
+    ///
+    /// On Windows, writes go straight through the cache to disk and other processes may only read our
+    /// files. Deletes and renames are still shared so that a file can be replaced while it's open
+    fn local_options() -> std_fs::OpenOptions {
+        let mut options = std_fs::File::options();
+        options.read(true).write(true);
+        #[cfg(windows)]
         {
-            match FileSystem::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => {
-                    return VirtualFS::instance()
-                        .write()
-                        .fs_fopen_rw(path)
-                        .map(|f| Self {
-                            f: AnyFile::Virtual(f),
-                        })
-                }
-            }
+            use {
+                std::os::windows::fs::OpenOptionsExt,
+                windows::Win32::Storage::FileSystem::{
+                    FILE_FLAG_WRITE_THROUGH, FILE_SHARE_DELETE, FILE_SHARE_READ,
+                },
+            };
+            options
+                .share_mode(FILE_SHARE_READ.0 | FILE_SHARE_DELETE.0)
+                .custom_flags(FILE_FLAG_WRITE_THROUGH.0);
         }
-        let file = std_fs::File::options().read(true).write(true).open(path)?;
-        Ok(Self {
-            #[cfg(test)]
-            f: AnyFile::Local(file),
-            #[cfg(not(test))]
-            f: file,
-        })
+        options
+    }
+    pub fn open(path: &str) -> IoResult<Self> {
+        let path = FileSystem::resolve(path);
+        let f = match FileSystem::context() {
+            FSContext::Local => AnyFile::Local(Self::local_options().open(&*path)?),
+            FSContext::Virtual => {
+                AnyFile::Virtual(VirtualFS::instance().write().fs_fopen_rw(&path)?)
+            }
+        };
+        Ok(Self { f })
     }
     pub fn create(path: &str) -> IoResult<Self> {
-        #[cfg(test)]
-        {
-            match FileSystem::context() {
-                FSContext::Local => {}
-                FSContext::Virtual => {
-                    return VirtualFS::instance()
-                        .write()
-                        .fs_fcreate_rw(path)
-                        .map(|f| Self {
-                            f: AnyFile::Virtual(f),
-                        })
-                }
+        let path = FileSystem::resolve(path);
+        let f = match FileSystem::context() {
+            FSContext::Local => {
+                AnyFile::Local(Self::local_options().create_new(true).open(&*path)?)
             }
-        }
-        let file = std_fs::File::options()
-            .create_new(true)
-            .read(true)
-            .write(true)
-            .open(path)?;
-        Ok(Self {
-            #[cfg(test)]
-            f: AnyFile::Local(file),
-            #[cfg(not(test))]
-            f: file,
-        })
+            FSContext::Virtual => {
+                AnyFile::Virtual(VirtualFS::instance().write().fs_fcreate_rw(&path)?)
+            }
+        };
+        Ok(Self { f })
     }
     pub fn into_buffered_reader(self) -> BufferedReader {
         BufferedReader::new(self.f)
@@ -453,33 +624,24 @@ impl File {
 */
 
 pub struct BufferedReader {
-    #[cfg(test)]
     f: AnyFile<BufReader<std_fs::File>>,
-    #[cfg(not(test))]
-    f: BufReader<std_fs::File>,
 }
 
 impl BufferedReader {
-    fn new(#[cfg(test)] f: AnyFile<std_fs::File>, #[cfg(not(test))] f: std_fs::File) -> Self {
+    fn new(f: AnyFile<std_fs::File>) -> Self {
         Self {
-            #[cfg(test)]
             f: match f {
                 AnyFile::Local(lf) => AnyFile::Local(BufReader::new(lf)),
                 AnyFile::Virtual(vf) => AnyFile::Virtual(vf),
             },
-            #[cfg(not(test))]
-            f: BufReader::new(f),
         }
     }
     pub fn into_inner(self) -> File {
         File {
-            #[cfg(test)]
             f: match self.f {
                 AnyFile::Local(lf) => AnyFile::Local(lf.into_inner()),
                 AnyFile::Virtual(vf) => AnyFile::Virtual(vf),
             },
-            #[cfg(not(test))]
-            f: self.f.into_inner(),
         }
     }
 }
@@ -503,63 +665,122 @@ impl FileExt for BufferedReader {
 }
 
 pub struct BufferedWriter {
-    #[cfg(test)]
     f: AnyFile<BufWriter<std_fs::File>>,
-    #[cfg(not(test))]
-    f: BufWriter<std_fs::File>,
 }
 
 impl BufferedWriter {
     pub fn into_inner(self) -> IoResult<File> {
-        let mut local;
-        #[cfg(test)]
-        {
-            match self.f {
-                AnyFile::Local(lf) => local = lf,
-                AnyFile::Virtual(vf) => {
-                    return Ok(File {
-                        f: AnyFile::Virtual(vf),
-                    })
-                }
+        let mut local = match self.f {
+            AnyFile::Local(lf) => lf,
+            AnyFile::Virtual(vf) => {
+                return Ok(File {
+                    f: AnyFile::Virtual(vf),
+                })
             }
-        }
-        #[cfg(not(test))]
-        {
-            local = self.f;
-        }
+        };
         local.flush()?;
         let local = local.into_inner().unwrap();
         Ok(File {
-            #[cfg(test)]
             f: AnyFile::Local(local),
-            #[cfg(not(test))]
-            f: local,
         })
     }
-    pub fn flush(&mut self) -> IoResult<()> {
-        let local;
-        #[cfg(test)]
-        {
-            match self.f {
-                AnyFile::Local(ref mut l) => local = l,
-                AnyFile::Virtual(_) => return Ok(()),
-            }
-        }
-        #[cfg(not(test))]
-        {
-            local = &mut self.f;
-        }
-        local.flush()
-    }
-    fn new(#[cfg(test)] f: AnyFile<std_fs::File>, #[cfg(not(test))] f: std_fs::File) -> Self {
+    fn new(f: AnyFile<std_fs::File>) -> Self {
         Self {
-            #[cfg(test)]
             f: match f {
                 AnyFile::Local(lf) => AnyFile::Local(BufWriter::new(lf)),
                 AnyFile::Virtual(vf) => AnyFile::Virtual(vf),
             },
-            #[cfg(not(test))]
-            f: BufWriter::new(f),
         }
     }
 }
+
+#[test]
+fn virtual_fs() {
+    assert_eq!(FileSystem::context(), FSContext::Virtual);
+    FileSystem::create_dir_all("vfs_test/a/b").unwrap();
+    let mut f = File::create("vfs_test/a/b/file").unwrap();
+    f.fwrite_all(b"hello").unwrap();
+    f.fsync_all().unwrap();
+    drop(f);
+    assert!(FileSystem::is_file("vfs_test/a/b/file"));
+    FileSystem::rename("vfs_test/a/b/file", "vfs_test/a/file").unwrap();
+    FileSystem::sync_dir("vfs_test/a").unwrap();
+    assert!(!FileSystem::is_file("vfs_test/a/b/file"));
+    assert_eq!(FileSystem::read("vfs_test/a/file").unwrap(), b"hello");
+    FileSystem::remove_dir("vfs_test/a/b").unwrap();
+    assert_eq!(FileSystem::read_dir("vfs_test/a").unwrap(), vec!["file"]);
+    assert_eq!(
+        FileSystem::read_dir("vfs_test/a/b").unwrap_err().kind(),
+        std::io::ErrorKind::NotFound
+    );
+    // not empty
+    assert!(FileSystem::remove_dir("vfs_test/a").is_err());
+    FileSystem::remove_dir_all("vfs_test").unwrap();
+    assert!(!FileSystem::is_file("vfs_test/a/file"));
+}
+
+#[test]
+fn virtual_fs_dir_sync() {
+    FileSystem::create_dir_all("vfs_dir_sync/a").unwrap();
+    let create = |path: &str, data: &[u8]| {
+        let mut f = File::create(path).unwrap();
+        f.fwrite_all(data).unwrap();
+        f.fsync_all().unwrap();
+    };
+    let crash = || crate::engine::fault::crash_dir("vfs_dir_sync").unwrap();
+    // the file was synced, but its directory entry wasn't
+    create("vfs_dir_sync/file", b"hello");
+    crash();
+    assert!(!FileSystem::is_file("vfs_dir_sync/file"));
+    // subdirectories aren't affected
+    assert_eq!(FileSystem::read_dir("vfs_dir_sync").unwrap(), vec!["a"]);
+    create("vfs_dir_sync/file", b"hello");
+    FileSystem::sync_dir("vfs_dir_sync").unwrap();
+    crash();
+    assert_eq!(FileSystem::read("vfs_dir_sync/file").unwrap(), b"hello");
+    // a rename that wasn't synced is undone, but the file keeps its data
+    FileSystem::rename("vfs_dir_sync/file", "vfs_dir_sync/renamed").unwrap();
+    crash();
+    assert!(!FileSystem::is_file("vfs_dir_sync/renamed"));
+    assert_eq!(FileSystem::read("vfs_dir_sync/file").unwrap(), b"hello");
+    FileSystem::rename("vfs_dir_sync/file", "vfs_dir_sync/renamed").unwrap();
+    FileSystem::sync_dir("vfs_dir_sync").unwrap();
+    crash();
+    assert!(!FileSystem::is_file("vfs_dir_sync/file"));
+    assert_eq!(FileSystem::read("vfs_dir_sync/renamed").unwrap(), b"hello");
+    // and so is a remove
+    FileSystem::remove_file("vfs_dir_sync/renamed").unwrap();
+    crash();
+    assert_eq!(FileSystem::read("vfs_dir_sync/renamed").unwrap(), b"hello");
+    // a directory that is created again was never synced
+    FileSystem::remove_dir_all("vfs_dir_sync").unwrap();
+    FileSystem::create_dir("vfs_dir_sync").unwrap();
+    create("vfs_dir_sync/file", b"hello");
+    crash();
+    assert!(FileSystem::read_dir("vfs_dir_sync").unwrap().is_empty());
+    FileSystem::remove_dir_all("vfs_dir_sync").unwrap();
+    assert!(FileSystem::sync_dir("vfs_dir_sync").is_err());
+}
+
+#[test]
+fn virtual_fs_rename_keeps_unsynced_writes() {
+    use crate::engine::fault::{self, Fault};
+    FileSystem::create_dir("vfs_rename_unsynced").unwrap();
+    let mut f = File::create("vfs_rename_unsynced/file").unwrap();
+    f.fwrite_all(b"hello").unwrap();
+    f.fsync_all().unwrap();
+    f.fwrite_all(b" world").unwrap();
+    drop(f);
+    FileSystem::rename("vfs_rename_unsynced/file", "vfs_rename_unsynced/renamed").unwrap();
+    assert_eq!(
+        FileSystem::read("vfs_rename_unsynced/renamed").unwrap(),
+        b"hello world"
+    );
+    // the write after the sync still isn't durable
+    fault::crash_file("vfs_rename_unsynced/renamed", Fault::DropUnsynced).unwrap();
+    assert_eq!(
+        FileSystem::read("vfs_rename_unsynced/renamed").unwrap(),
+        b"hello"
+    );
+    FileSystem::remove_dir_all("vfs_rename_unsynced").unwrap();
+}
@@ -31,5 +31,4 @@
 //!
 
 pub mod fs;
-#[cfg(test)]
-mod vfs;
+pub mod vfs;
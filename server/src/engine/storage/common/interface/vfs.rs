@@ -25,6 +25,7 @@
 */
 
 use {
+    super::fs::Vfs,
     crate::{engine::sync::cell::Lazy, IoResult},
     parking_lot::RwLock,
     std::{
@@ -33,6 +34,7 @@ use {
             HashMap,
         },
         io::{Error, ErrorKind},
+        sync::Arc,
     },
 };
 
@@ -45,21 +47,31 @@ use {
     definitions
     ---
     fs, node, dir, file
+
+    like on a real file system, a file's data and its directory entry are synced separately. a file (and whatever was
+    synced to it) is shared by its directory and by the snapshot of that directory's entries as of its last sync, so
+    if we crash before the directory is synced the entries go back to the snapshot: files created since are gone,
+    removed files are back and renamed files are back under their old names (with their data)
 */
 
 /// A virtual directory
 type VDir = HashMap<Box<str>, VNode>;
+/// A virtual file, which is shared by all the directory entries (synced or not) that point to it
+type VFileRef = Arc<RwLock<VFile>>;
 /// An iterator over the components of a file path (alias)
 type ComponentIter<'a> = std::iter::Take<std::vec::IntoIter<&'a str>>;
 
 pub struct VirtualFS {
     root: HashMap<Box<str>, VNode>,
+    /// the files in every directory as of the last time it was synced (directories without an entry were never
+    /// synced)
+    synced_dirs: HashMap<Box<str>, HashMap<Box<str>, VFileRef>>,
 }
 
 #[derive(Debug)]
 enum VNode {
     Dir(HashMap<Box<str>, Self>),
-    File(RwLock<VFile>),
+    File(VFileRef),
 }
 
 #[derive(Debug)]
@@ -68,17 +80,33 @@ pub(super) struct VFile {
     write: bool,
     data: Vec<u8>,
     pos: usize,
+    /// what is known to be on disk (as of the last sync)
+    synced: Vec<u8>,
+    /// writes since the last sync, in order
+    pending: Vec<VWrite>,
+}
+
+#[derive(Debug, Clone)]
+/// A write that hasn't been synced yet
+enum VWrite {
+    Write(usize, Vec<u8>),
+    Truncate(usize),
 }
 
-#[derive(Debug, PartialEq)]
-/// Result of opening a file
-/// - Created: newly created file
-/// - Existing: existing file that was reopened
-pub enum FileOpen<CF, EF = CF> {
-    /// new file
-    Created(CF),
-    /// existing file
-    Existing(EF),
+#[cfg(any(test, feature = "fault-injection"))]
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// What happens to the writes that weren't synced when we [`VirtualFS::crash_file`]
+pub enum Fault {
+    /// every write since the last sync is lost
+    DropUnsynced,
+    /// only the first `n` writes since the last sync make it to disk
+    KeepWrites(usize),
+    /// the first `writes` writes since the last sync make it to disk along with the first `bytes` of the write after
+    /// them
+    TornWrite { writes: usize, bytes: usize },
+    /// every write since the last sync makes it to disk, except for the `n`th one (the writes after it were
+    /// persisted before it)
+    SkipWrite(usize),
 }
 
 #[derive(Debug)]
@@ -86,15 +114,13 @@ pub struct VFileDescriptor(pub(super) Box<str>);
 
 impl Drop for VFileDescriptor {
     fn drop(&mut self) {
-        VirtualFS::instance()
-            .write()
-            .with_file_mut(&self.0, |f| {
-                f.pos = 0;
-                f.write = false;
-                f.read = false;
-                Ok(())
-            })
-            .unwrap()
+        // the file might have been renamed or removed while it was open, and then there's nothing to reset
+        let _ = VirtualFS::instance().write().with_file_mut(&self.0, |f| {
+            f.pos = 0;
+            f.write = false;
+            f.read = false;
+            Ok(())
+        });
     }
 }
 
@@ -105,13 +131,13 @@ impl Drop for VFileDescriptor {
 impl VFile {
     pub fn truncate(&mut self, to: u64) -> IoResult<()> {
         if !self.write {
-            return Err(Error::new(ErrorKind::PermissionDenied, "Write permission denied").into());
-        }
-        if to as usize > self.data.len() {
-            self.data.resize(to as usize, 0);
-        } else {
-            self.data.truncate(to as usize);
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Write permission denied",
+            ));
         }
+        Self::apply(&mut self.data, &VWrite::Truncate(to as usize));
+        self.pending.push(VWrite::Truncate(to as usize));
         if self.pos > self.data.len() {
             self.pos = self.data.len();
         }
@@ -125,18 +151,24 @@ impl VFile {
     }
     pub fn seek_from_start(&mut self, by: u64) -> IoResult<()> {
         if by > self.data.len() as u64 {
-            return Err(Error::new(ErrorKind::InvalidInput, "Can't seek beyond file's end").into());
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Can't seek beyond file's end",
+            ));
         }
         self.pos = by as usize;
         Ok(())
     }
     pub fn fread_exact(&mut self, buf: &mut [u8]) -> IoResult<()> {
         if !self.read {
-            return Err(Error::new(ErrorKind::PermissionDenied, "Read permission denied").into());
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Read permission denied",
+            ));
         }
         let available_bytes = self.current().len();
         if available_bytes < buf.len() {
-            return Err(Error::from(ErrorKind::UnexpectedEof).into());
+            return Err(Error::from(ErrorKind::UnexpectedEof));
         }
         buf.copy_from_slice(&self.data[self.pos..self.pos + buf.len()]);
         self.pos += buf.len();
@@ -144,15 +176,22 @@ impl VFile {
     }
     pub fn fwrite(&mut self, bytes: &[u8]) -> IoResult<u64> {
         if !self.write {
-            return Err(Error::new(ErrorKind::PermissionDenied, "Write permission denied").into());
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Write permission denied",
+            ));
         }
-        if self.pos + bytes.len() > self.data.len() {
-            self.data.resize(self.pos + bytes.len(), 0);
-        }
-        self.data[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        let write = VWrite::Write(self.pos, bytes.to_vec());
+        Self::apply(&mut self.data, &write);
+        self.pending.push(write);
         self.pos += bytes.len();
         Ok(bytes.len() as _)
     }
+    pub fn sync(&mut self) -> IoResult<()> {
+        self.synced.clone_from(&self.data);
+        self.pending.clear();
+        Ok(())
+    }
 }
 
 impl VFile {
@@ -160,13 +199,52 @@ impl VFile {
         Self {
             read,
             write,
+            synced: data.clone(),
             data,
             pos,
+            pending: vec![],
         }
     }
     fn current(&self) -> &[u8] {
         &self.data[self.pos..]
     }
+    fn apply(data: &mut Vec<u8>, write: &VWrite) {
+        match write {
+            VWrite::Write(pos, bytes) => {
+                if pos + bytes.len() > data.len() {
+                    data.resize(pos + bytes.len(), 0);
+                }
+                data[*pos..pos + bytes.len()].copy_from_slice(bytes);
+            }
+            VWrite::Truncate(to) => data.resize(*to, 0),
+        }
+    }
+    #[cfg(any(test, feature = "fault-injection"))]
+    /// Throw away everything that wasn't synced, except for what the fault lets through
+    fn crash(&mut self, fault: Fault) {
+        let mut data = core::mem::take(&mut self.synced);
+        let pending = core::mem::take(&mut self.pending);
+        for (i, write) in pending.into_iter().enumerate() {
+            let write = match fault {
+                Fault::DropUnsynced => break,
+                Fault::KeepWrites(n) if i >= n => break,
+                Fault::TornWrite { writes, .. } if i > writes => break,
+                Fault::TornWrite { writes, bytes } if i == writes => match write {
+                    VWrite::Write(pos, mut partial) => {
+                        partial.truncate(bytes);
+                        VWrite::Write(pos, partial)
+                    }
+                    truncate => truncate,
+                },
+                Fault::SkipWrite(n) if i == n => continue,
+                _ => write,
+            };
+            Self::apply(&mut data, &write);
+        }
+        self.synced.clone_from(&data);
+        self.data = data;
+        self.pos = 0;
+    }
 }
 
 impl VNode {
@@ -184,8 +262,26 @@ impl VirtualFS {
             Lazy::new(|| RwLock::new(VirtualFS::new()));
         &GLOBAL_VFS
     }
-    pub fn get_data(&self, path: &str) -> IoResult<Vec<u8>> {
-        self.with_file(path, |f| Ok(f.data.clone()))
+    #[cfg(any(test, feature = "fault-injection"))]
+    /// Simulate a crash for the given file: it is left with what was synced to it, along with whatever unsynced writes
+    /// the fault lets through
+    pub fn crash_file(&self, fpath: &str, fault: Fault) -> IoResult<()> {
+        self.with_file_mut(fpath, |f| Ok(f.crash(fault)))
+    }
+    #[cfg(any(test, feature = "fault-injection"))]
+    /// Simulate a crash for the given directory: its files go back to what they were when the directory was last
+    /// synced (none if it never was). The data in the files is left as is (see [`Self::crash_file`])
+    pub fn crash_dir(&mut self, dpath: &str) -> IoResult<()> {
+        let dpath = util::dir_key(dpath);
+        let synced = self.synced_dirs.get(dpath).cloned().unwrap_or_default();
+        let dir = self.find_dir_mut(dpath)?;
+        dir.retain(|_, node| matches!(node, VNode::Dir(_)));
+        dir.extend(
+            synced
+                .into_iter()
+                .map(|(name, file)| (name, VNode::File(file))),
+        );
+        Ok(())
     }
     pub fn fs_fcreate_rw(&mut self, fpath: &str) -> IoResult<VFileDescriptor> {
         let (target_file, components) = util::split_target_and_components(fpath);
@@ -193,26 +289,27 @@ impl VirtualFS {
         match target_dir.entry(target_file.into()) {
             Entry::Occupied(k) => {
                 match k.get() {
-                    VNode::Dir(_) => {
-                        return Err(Error::new(
-                            ErrorKind::AlreadyExists,
-                            "found directory with same name where file was to be created",
-                        )
-                        .into());
-                    }
+                    VNode::Dir(_) => Err(Error::new(
+                        ErrorKind::AlreadyExists,
+                        "found directory with same name where file was to be created",
+                    )),
                     VNode::File(_) => {
                         // the file already exists
-                        return Err(Error::new(
+                        Err(Error::new(
                             ErrorKind::AlreadyExists,
                             "the file already exists",
-                        )
-                        .into());
+                        ))
                     }
                 }
             }
             Entry::Vacant(v) => {
                 // no file exists, we can create this
-                v.insert(VNode::File(RwLock::new(VFile::new(true, true, vec![], 0))));
+                v.insert(VNode::File(Arc::new(RwLock::new(VFile::new(
+                    true,
+                    true,
+                    vec![],
+                    0,
+                )))));
                 Ok(VFileDescriptor(fpath.into()))
             }
         }
@@ -224,34 +321,54 @@ impl VirtualFS {
             Ok(VFileDescriptor(fpath.into()))
         })
     }
-    pub fn fs_rename(&mut self, from: &str, to: &str) -> IoResult<()> {
-        // get file data
-        let data = self.with_file(from, |f| Ok(f.data.clone()))?;
-        // create new file
-        let file = self.fs_fopen_or_create_rw(to)?;
-        match file {
-            FileOpen::Created(c) => self.with_file_mut(&c.0, |f| Ok(f.data = data))?,
-            FileOpen::Existing(c) => self.with_file_mut(&c.0, |f| {
-                f.data = data;
-                f.pos = 0;
-                f.read = false;
-                f.write = false;
-                Ok(())
-            })?,
+}
+
+impl Vfs for VirtualFS {
+    fn fs_is_file(&self, fpath: &str) -> bool {
+        self.with_file(fpath, |_| Ok(())).is_ok()
+    }
+    fn fs_is_dir(&self, fpath: &str) -> bool {
+        self.find_dir(fpath).is_ok()
+    }
+    #[cfg(test)]
+    fn fs_read(&self, fpath: &str) -> IoResult<Vec<u8>> {
+        self.with_file(fpath, |f| Ok(f.data.clone()))
+    }
+    fn fs_rename(&mut self, from: &str, to: &str) -> IoResult<()> {
+        let (target_file, components) = util::split_target_and_components(to);
+        if let Some(VNode::Dir(_)) =
+            util::find_target_dir(components.clone(), &self.root)?.get(target_file)
+        {
+            return err::item_is_not_file();
         }
-        // delete old file
-        self.fs_remove_file(from)
+        // only the directory entry moves; the file keeps its data, along with what was (and wasn't) synced to it
+        let node = self.with_item_mut(from, |e| match e.get() {
+            VNode::File(_) => Ok(e.remove()),
+            VNode::Dir(_) => err::item_is_not_file(),
+        })?;
+        if let VNode::File(ref file) = node {
+            let mut file = file.write();
+            file.read = false;
+            file.write = false;
+            file.pos = 0;
+        }
+        util::find_target_dir_mut(components, &mut self.root)?.insert(target_file.into(), node);
+        Ok(())
+    }
+    fn fs_read_dir(&self, fpath: &str) -> IoResult<Vec<String>> {
+        self.find_dir(fpath)
+            .map(|dir| dir.keys().map(|name| name.to_string()).collect())
     }
-    pub fn fs_remove_file(&mut self, fpath: &str) -> IoResult<()> {
+    fn fs_remove_file(&mut self, fpath: &str) -> IoResult<()> {
         self.with_item_mut(fpath, |e| match e.get() {
             VNode::File(_) => {
                 e.remove();
                 Ok(())
             }
-            _ => return err::item_is_not_file(),
+            _ => err::item_is_not_file(),
         })
     }
-    pub fn fs_create_dir(&mut self, fpath: &str) -> IoResult<()> {
+    fn fs_create_dir(&mut self, fpath: &str) -> IoResult<()> {
         // get root dir
         let mut current = &mut self.root;
         // process components
@@ -266,14 +383,14 @@ impl VirtualFS {
             }
         }
         match current.entry(target.into()) {
-            Entry::Occupied(_) => return Err(Error::from(ErrorKind::AlreadyExists).into()),
+            Entry::Occupied(_) => Err(Error::from(ErrorKind::AlreadyExists)),
             Entry::Vacant(ve) => {
                 ve.insert(VNode::Dir(into_dict!()));
                 Ok(())
             }
         }
     }
-    pub fn fs_create_dir_all(&mut self, fpath: &str) -> IoResult<()> {
+    fn fs_create_dir_all(&mut self, fpath: &str) -> IoResult<()> {
         fn create_ahead(mut ahead: &[&str], current: &mut VDir) -> IoResult<()> {
             if ahead.is_empty() {
                 return Ok(());
@@ -284,53 +401,73 @@ impl VirtualFS {
                 Some(VNode::Dir(d)) => {
                     if ahead.is_empty() {
                         // hmm, this was the list dir that was to be created, but it already exists
-                        return Err(Error::from(ErrorKind::AlreadyExists).into());
+                        return Err(Error::from(ErrorKind::AlreadyExists));
                     }
-                    return create_ahead(ahead, d);
+                    create_ahead(ahead, d)
                 }
-                Some(VNode::File(_)) => return err::file_in_dir_path(),
+                Some(VNode::File(_)) => err::file_in_dir_path(),
                 None => {
                     let _ = current.insert(this.into(), VNode::Dir(into_dict!()));
                     let dir = current.get_mut(this).unwrap().as_dir_mut().unwrap();
-                    return create_ahead(ahead, dir);
+                    create_ahead(ahead, dir)
                 }
             }
         }
         let pieces = util::split_parts(fpath);
         create_ahead(&pieces, &mut self.root)
     }
-    pub fn fs_delete_dir(&mut self, fpath: &str) -> IoResult<()> {
+    #[cfg(test)]
+    fn fs_delete_dir(&mut self, fpath: &str) -> IoResult<()> {
         self.dir_delete(fpath, false)
     }
-    pub fn fs_delete_dir_all(&mut self, fpath: &str) -> IoResult<()> {
+    fn fs_delete_dir_all(&mut self, fpath: &str) -> IoResult<()> {
         self.dir_delete(fpath, true)
     }
+    fn fs_sync_dir(&mut self, fpath: &str) -> IoResult<()> {
+        let fpath = util::dir_key(fpath);
+        let files = self
+            .find_dir(fpath)?
+            .iter()
+            .filter_map(|(name, node)| match node {
+                VNode::File(file) => Some((name.clone(), file.clone())),
+                VNode::Dir(_) => None,
+            })
+            .collect();
+        self.synced_dirs.insert(fpath.into(), files);
+        Ok(())
+    }
 }
 
 impl VirtualFS {
     fn new() -> Self {
         Self {
             root: HashMap::new(),
+            synced_dirs: HashMap::new(),
         }
     }
-    fn fs_fopen_or_create_rw(&mut self, fpath: &str) -> IoResult<FileOpen<VFileDescriptor>> {
-        // components
-        let (target_file, components) = util::split_target_and_components(fpath);
-        let target_dir = util::find_target_dir_mut(components, &mut self.root)?;
-        match target_dir.entry(target_file.into()) {
-            Entry::Occupied(oe) => match oe.get() {
-                VNode::File(f) => {
-                    let mut f = f.write();
-                    f.read = true;
-                    f.write = true;
-                    Ok(FileOpen::Existing(VFileDescriptor(fpath.into())))
-                }
-                VNode::Dir(_) => return err::item_is_not_file(),
-            },
-            Entry::Vacant(v) => {
-                v.insert(VNode::File(RwLock::new(VFile::new(true, true, vec![], 0))));
-                Ok(FileOpen::Created(VFileDescriptor(fpath.into())))
-            }
+    fn find_dir(&self, dpath: &str) -> IoResult<&VDir> {
+        let dpath = util::dir_key(dpath);
+        if dpath.is_empty() {
+            return Ok(&self.root);
+        }
+        let (target, components) = util::split_target_and_components(dpath);
+        match util::find_target_dir(components, &self.root)?.get(target) {
+            Some(VNode::Dir(dir)) => Ok(dir),
+            Some(VNode::File(_)) => err::file_in_dir_path(),
+            None => err::could_not_find_item(),
+        }
+    }
+    #[cfg(any(test, feature = "fault-injection"))]
+    fn find_dir_mut(&mut self, dpath: &str) -> IoResult<&mut VDir> {
+        let dpath = util::dir_key(dpath);
+        if dpath.is_empty() {
+            return Ok(&mut self.root);
+        }
+        let (target, components) = util::split_target_and_components(dpath);
+        match util::find_target_dir_mut(components, &mut self.root)?.get_mut(target) {
+            Some(VNode::Dir(dir)) => Ok(dir),
+            Some(VNode::File(_)) => err::file_in_dir_path(),
+            None => err::could_not_find_item(),
         }
     }
     pub(super) fn with_file_mut<T>(
@@ -345,8 +482,8 @@ impl VirtualFS {
                 let mut file = file.write();
                 f(&mut file)
             }
-            Some(VNode::Dir(_)) => return err::item_is_not_file(),
-            None => return Err(Error::from(ErrorKind::NotFound).into()),
+            Some(VNode::Dir(_)) => err::item_is_not_file(),
+            None => Err(Error::from(ErrorKind::NotFound)),
         }
     }
     pub(super) fn with_file<T>(
@@ -361,8 +498,8 @@ impl VirtualFS {
                 let f_ = file.read();
                 f(&f_)
             }
-            Some(VNode::Dir(_)) => return err::item_is_not_file(),
-            None => return Err(Error::from(ErrorKind::NotFound).into()),
+            Some(VNode::Dir(_)) => err::item_is_not_file(),
+            None => Err(Error::from(ErrorKind::NotFound)),
         }
     }
     fn with_item_mut<T>(
@@ -383,8 +520,8 @@ impl VirtualFS {
             }
         }
         match current.entry(target.into()) {
-            Entry::Occupied(item) => return f(item),
-            Entry::Vacant(_) => return err::could_not_find_item(),
+            Entry::Occupied(item) => f(item),
+            Entry::Vacant(_) => err::could_not_find_item(),
         }
     }
     fn dir_delete(&mut self, fpath: &str, allow_if_non_empty: bool) -> IoResult<()> {
@@ -394,10 +531,18 @@ impl VirtualFS {
                     node.remove();
                     return Ok(());
                 }
-                return Err(Error::new(ErrorKind::InvalidInput, "directory is not empty").into());
+                Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "directory is not empty",
+                ))
             }
-            VNode::File(_) => return err::file_in_dir_path(),
-        })
+            VNode::File(_) => err::file_in_dir_path(),
+        })?;
+        // a directory that is created again starts out as never synced
+        let subdirs = format!("{fpath}/");
+        self.synced_dirs
+            .retain(|dir, _| &**dir != fpath && !dir.starts_with(&subdirs));
+        Ok(())
     }
 }
 
@@ -410,16 +555,25 @@ mod err {
         std::io::{Error, ErrorKind},
     };
     pub(super) fn item_is_not_file<T>() -> IoResult<T> {
-        Err(Error::new(ErrorKind::InvalidInput, "found directory, not a file").into())
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "found directory, not a file",
+        ))
     }
     pub(super) fn file_in_dir_path<T>() -> IoResult<T> {
-        Err(Error::new(ErrorKind::InvalidInput, "found file in directory path").into())
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "found file in directory path",
+        ))
     }
     pub(super) fn dir_missing_in_path<T>() -> IoResult<T> {
-        Err(Error::new(ErrorKind::InvalidInput, "could not find directory in path").into())
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "could not find directory in path",
+        ))
     }
     pub(super) fn could_not_find_item<T>() -> IoResult<T> {
-        Err(Error::new(ErrorKind::NotFound, "could not find item").into())
+        Err(Error::new(ErrorKind::NotFound, "could not find item"))
     }
 }
 
@@ -428,6 +582,13 @@ mod util {
         super::{err, ComponentIter, VDir, VNode},
         crate::IoResult,
     };
+    /// The path that identifies a directory (the root is `""` or `"."`)
+    pub(super) fn dir_key(dpath: &str) -> &str {
+        match dpath {
+            "." => "",
+            dpath => dpath,
+        }
+    }
     pub(super) fn split_parts(fpath: &str) -> Vec<&str> {
         fpath.split("/").collect()
     }
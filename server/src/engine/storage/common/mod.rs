@@ -31,7 +31,24 @@ pub mod static_meta;
 pub mod versions;
 
 pub mod paths_v1 {
-    use crate::engine::data::uuid::Uuid;
+    use {
+        crate::engine::data::uuid::Uuid,
+        parking_lot::{const_rwlock, RwLock},
+        std::collections::BTreeMap,
+    };
+    /// Spaces that were created with a custom `storage_path`. Every other space lives in the
+    /// default data directory
+    static SPACE_ROOTS: RwLock<BTreeMap<Uuid, Box<str>>> = const_rwlock(BTreeMap::new());
+    /// Set the root directory under which the given space's data is stored
+    pub fn set_space_root(space_uuid: Uuid, root: &str) {
+        let _ = SPACE_ROOTS
+            .write()
+            .insert(space_uuid, root.trim_end_matches('/').into());
+    }
+    /// Stop tracking a custom root for the given space
+    pub fn remove_space_root(space_uuid: Uuid) {
+        let _ = SPACE_ROOTS.write().remove(&space_uuid);
+    }
     pub fn model_path(
         space_name: &str,
         space_uuid: Uuid,
@@ -49,9 +66,28 @@ pub mod paths_v1 {
         model_name: &str,
         model_uuid: Uuid,
     ) -> String {
-        format!("data/{space_name}-{space_uuid}/mdl_{model_name}-{model_uuid}")
+        format!(
+            "{}/mdl_{model_name}-{model_uuid}",
+            self::space_dir(space_name, space_uuid)
+        )
+    }
+    /// The directory that holds the models dropped from the given space until they're purged
+    pub fn trash_dir(space_name: &str, space_uuid: Uuid) -> String {
+        format!("{}/trash", self::space_dir(space_name, space_uuid))
     }
     pub fn space_dir(space_name: &str, space_uuid: Uuid) -> String {
-        format!("data/{space_name}-{space_uuid}")
+        self::space_dir_in(
+            SPACE_ROOTS.read().get(&space_uuid).map(|root| &**root),
+            space_name,
+            space_uuid,
+        )
+    }
+    /// The directory of a space that is stored under `root` (or under the data directory, if it has no root of its
+    /// own), whether or not the root has been set for the space yet
+    pub fn space_dir_in(root: Option<&str>, space_name: &str, space_uuid: Uuid) -> String {
+        match root.map(|root| root.trim_end_matches('/')) {
+            Some(root) => format!("{root}/{space_name}-{space_uuid}"),
+            None => format!("data/{space_name}-{space_uuid}"),
+        }
     }
 }
@@ -382,6 +382,15 @@ impl<H: HeaderV1Spec> HeaderV1<H> {
     pub fn file_specifier_version(&self) -> FileSpecifierVersion {
         self.genesis_static_file_specifier_version
     }
+    /// Move the header to a new file specifier version, returning the offset (from the start of the file) and the
+    /// encoded bytes that have to be written to the file for it to have the new version
+    pub fn set_file_specifier_version(&mut self, v: FileSpecifierVersion) -> (u64, [u8; 2]) {
+        self.genesis_static_file_specifier_version = v;
+        (
+            Self::SEG2_REC1_FILE_SPECIFIER_VERSION.start as u64,
+            v.little_endian(),
+        )
+    }
     pub fn epoch_time(&self) -> u128 {
         self.genesis_runtime_epoch_time
     }
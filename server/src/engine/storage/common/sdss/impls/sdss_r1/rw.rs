@@ -32,8 +32,10 @@ use {
             mem::fixed_vec::FixedVec,
             storage::common::{
                 checksum::SCrc64,
-                interface::fs::{BufferedReader, File, FileExt, FileRead, FileWrite, FileWriteExt},
-                sdss::sdss_r1::FileSpecV1,
+                interface::fs::{
+                    BufferedReader, File, FileExt, FileRead, FileWrite, FileWriteExt, TempFile,
+                },
+                sdss::sdss_r1::{FileSpecV1, SimpleFileSpecV1},
             },
             RuntimeResult,
         },
@@ -41,7 +43,10 @@ use {
         IoResult,
     },
     core::fmt,
-    std::mem,
+    std::{
+        mem,
+        sync::atomic::{AtomicU64, Ordering},
+    },
 };
 
 /*
@@ -80,6 +85,18 @@ impl<S: FileSpecV1> SdssFile<S> {
         let md = S::write_metadata(&mut f, ())?;
         Ok(Self::new(f, md))
     }
+    /// Create a new SDSS based file that will replace the file at `path`. The data is written to a
+    /// temporary file, and once it has been fully written and synced, [`TempFile::commit`] atomically
+    /// moves it over `path`. If the returned [`TempFile`] is dropped without being committed, the
+    /// file at `path` is left untouched
+    pub fn create_temp(path: &str) -> RuntimeResult<(Self, TempFile)>
+    where
+        S: FileSpecV1<EncodeArgs = ()>,
+    {
+        let tmp = TempFile::new(path)?;
+        let f = Self::create(tmp.path())?;
+        Ok((f, tmp))
+    }
     pub fn into_buffered_reader(self) -> IoResult<SdssFile<S, BufferedReader>> {
         let Self { file, meta } = self;
         let r = file.into_buffered_reader();
@@ -90,6 +107,24 @@ impl<S: FileSpecV1> SdssFile<S> {
     }
 }
 
+impl<S: SimpleFileSpecV1> SdssFile<S> {
+    /// Rewrite the header with the current file specifier version, if the file was created with an older one. The
+    /// file cursor is left where it was
+    pub fn upgrade_file_specifier_version(&mut self) -> IoResult<()> {
+        if self.meta.file_specifier_version() == S::FILE_SPECFIER_VERSION {
+            return Ok(());
+        }
+        let cursor = self.file_cursor()?;
+        let (offset, version) = self
+            .meta
+            .set_file_specifier_version(S::FILE_SPECFIER_VERSION);
+        self.seek_from_start(offset)?;
+        self.file.fwrite_all(&version)?;
+        self.file.fsync_all()?;
+        self.seek_from_start(cursor)
+    }
+}
+
 impl<S: FileSpecV1, F: FileRead> SdssFile<S, F> {
     /// Attempt to fill the entire buffer from the file
     pub fn read_buffer(&mut self, buffer: &mut [u8]) -> IoResult<()> {
@@ -140,6 +175,10 @@ impl<S: FileSpecV1, F: FileWrite + FileWriteExt> SdssFile<S, F> {
         self.file.fwrite_all(data)?;
         self.file.fsync_all()
     }
+    /// Truncate (or extend) the file to the given size
+    pub fn truncate(&mut self, new_size: u64) -> IoResult<()> {
+        self.file.f_truncate(new_size)
+    }
 }
 
 /*
@@ -284,6 +323,19 @@ impl<S: FileSpecV1> TrackedReader<S> {
     tracked writer
 */
 
+/// The size of the extents in which space is reserved for tracked writers. A size of `0` disables
+/// preallocation
+static PREALLOC_CHUNK_SIZE: AtomicU64 = AtomicU64::new(0);
+
+/// Set the size of the extents used to preallocate space for journals and batch files
+pub fn set_prealloc_chunk_size(size: u64) {
+    PREALLOC_CHUNK_SIZE.store(size, Ordering::Release)
+}
+
+fn prealloc_chunk_size() -> u64 {
+    PREALLOC_CHUNK_SIZE.load(Ordering::Acquire)
+}
+
 /// A [`TrackedWriter`] is an advanced writer primitive that provides a robust abstraction over a writable
 /// interface. It tracks the cursor, automatically buffers writes and in case of buffer flush failure,
 /// provides methods to robustly handle errors, down to byte-level cursor tracking in case of failure.
@@ -298,6 +350,7 @@ pub struct TrackedWriter<
     t_cursor: u64,
     t_checksum: SCrc64,
     t_partial_checksum: SCrc64,
+    t_prealloc: u64,
     buf: FixedVec<u8, SIZE>,
 }
 
@@ -317,6 +370,7 @@ where
             .field("t_cursor", &self.t_cursor)
             .field("t_checksum", &self.t_checksum)
             .field("t_partial_checksum", &self.t_partial_checksum)
+            .field("t_prealloc", &self.t_prealloc)
             .field("buf", &self.buf)
             .finish()
     }
@@ -346,6 +400,21 @@ impl<
     pub fn __zero_buffer(&mut self) {
         self.buf.clear()
     }
+    /// Make sure that the disk blocks for the next `len` bytes have been reserved, preallocating another
+    /// extent if needed. The logical end of the file (our cursor) is tracked separately from the physical
+    /// size and the length of the file is left unchanged
+    fn reserve(&mut self, len: usize) -> IoResult<()> {
+        let chunk = prealloc_chunk_size();
+        let end = self.t_cursor + len as u64;
+        if chunk == 0 || end <= self.t_prealloc {
+            return Ok(());
+        }
+        let new_end = end.next_multiple_of(chunk);
+        self.f_d
+            .f_preallocate(self.t_cursor, new_end - self.t_cursor)?;
+        self.t_prealloc = new_end;
+        Ok(())
+    }
 }
 
 impl<
@@ -362,6 +431,7 @@ impl<
             t_cursor,
             t_checksum,
             t_partial_checksum: SCrc64::new(),
+            t_prealloc: 0,
             buf: FixedVec::allocate(),
         }
     }
@@ -379,6 +449,10 @@ impl<
     pub fn is_dirty(&self) -> bool {
         !self.buf.is_empty()
     }
+    /// Get the metadata (header) of the file
+    pub fn metadata(&self) -> &S::Metadata {
+        &self.f_md
+    }
 }
 
 impl<
@@ -411,6 +485,9 @@ impl<
     pub fn current_checksum(&self) -> u64 {
         self.t_checksum.clone().finish()
     }
+    pub fn checksum(&self) -> SCrc64 {
+        self.t_checksum.clone()
+    }
 }
 
 impl<
@@ -432,6 +509,7 @@ impl<
     /// - If errored, the checksum is updated to reflect the number of bytes written (unless otherwise configured)
     pub fn tracked_write_through_buffer(&mut self, buf: &[u8]) -> IoResult<()> {
         debug_assert!(self.buf.is_empty());
+        self.reserve(buf.len())?;
         match self.f_d.fwrite_all_count(buf) {
             (cnt, r) => {
                 self.t_cursor += cnt;
@@ -487,6 +565,7 @@ impl<
         self.flush_buf()?;
         // write whatever capacity exceeds the buffer size
         let to_write_cnt = buf.len().saturating_sub(SIZE);
+        self.reserve(to_write_cnt)?;
         match self.f_d.fwrite_all_count(&buf[..to_write_cnt]) {
             (cnt, r) => {
                 self.t_cursor += cnt;
@@ -506,6 +585,7 @@ impl<
     }
     /// Flush the buffer
     pub fn flush_buf(&mut self) -> IoResult<()> {
+        self.reserve(self.buf.len())?;
         match self.f_d.fwrite_all_count(&self.buf) {
             (written, r) => {
                 if written as usize == self.buf.len() {
@@ -548,6 +628,34 @@ impl<
     }
 }
 
+#[test]
+fn check_vfs_temp_replace() {
+    use crate::engine::storage::{
+        common::interface::fs::FileSystem,
+        v2::raw::spec::{Header, SystemDatabaseV1},
+    };
+    closure! {
+        let mut f = SdssFile::<SystemDatabaseV1>::create("myfile_replace")?;
+        f.fsynced_write(b"old")?;
+        // not committed; original file is untouched and the temporary file is removed
+        {
+            let (mut f, tmp) = SdssFile::<SystemDatabaseV1>::create_temp("myfile_replace")?;
+            f.fsynced_write(b"discarded")?;
+            drop(tmp);
+        }
+        assert_eq!(&FileSystem::read("myfile_replace")?[Header::SIZE..], b"old");
+        assert!(FileSystem::read("myfile_replace.tmp").is_err());
+        // committed; the original file is replaced
+        let (mut f, tmp) = SdssFile::<SystemDatabaseV1>::create_temp("myfile_replace")?;
+        f.fsynced_write(b"new")?;
+        tmp.commit()?;
+        assert_eq!(&FileSystem::read("myfile_replace")?[Header::SIZE..], b"new");
+        assert!(FileSystem::read("myfile_replace.tmp").is_err());
+        RuntimeResult::Ok(())
+    }
+    .unwrap()
+}
+
 #[test]
 fn check_vfs_buffering() {
     use crate::engine::storage::{
@@ -31,10 +31,16 @@ use {
             data::uuid::Uuid,
             error::{RuntimeResult, StorageError},
             mem::BufferedScanner,
-            storage::common_encoding::r1::{self, PersistObject},
-            txn::{gns::GNSTransaction, SpaceIDRef},
+            storage::{
+                common_encoding::r1::{self, PersistObject},
+                v2::{impls::gns_log::GNSEventLog, raw::journal::EventLogSpec},
+            },
+            txn::{
+                gns::{BatchEvents, BatchTxn, GNSTransaction, GNSTransactionCode},
+                SpaceIDRef,
+            },
         },
-        util::EndianQW,
+        util::{compiler::TaggedEnum, EndianQW},
     },
     std::marker::PhantomData,
 };
@@ -89,6 +95,33 @@ where
     }
     /// Update the global state from the restored event
     fn update_global_state(restore: Self::RestoreType, gns: &GNSData) -> RuntimeResult<()>;
+    /// Describe the restored event (without any secrets)
+    fn summarize(restore: &Self::RestoreType) -> EventSummary;
+    /// Decode the event and describe it, without applying it anywhere
+    fn decode_summarize(data: &[u8]) -> RuntimeResult<EventSummary> {
+        let mut scanner = BufferedScanner::new(data);
+        let restore = Self::decode(&mut scanner)?;
+        if scanner.eof() {
+            Ok(Self::summarize(&restore))
+        } else {
+            Err(StorageError::JournalLogEntryCorrupted.into())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A short description of an event in the GNS (see [`GNSEvent::summarize`])
+pub struct EventSummary {
+    /// the space, model (as `space.model`) or user that the event is about (if any)
+    pub entity: Option<String>,
+    /// what the event changed
+    pub summary: String,
+}
+
+impl EventSummary {
+    pub fn new(entity: Option<String>, summary: String) -> Self {
+        Self { entity, summary }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -141,3 +174,117 @@ impl<'a> PersistObject for SpaceID<'a> {
         })
     }
 }
+
+/*
+    batch
+    ---
+    [event count][payload length][events]
+    every event is [code][length][payload], where the payload is what the event would have been if it was committed on
+    its own. a batch is never nested in another batch
+*/
+
+impl BatchEvents {
+    /// Add an event to the batch
+    pub fn push<E: GNSEvent>(&mut self, event: E) {
+        let mut payload = vec![];
+        E::encode_event(event, &mut payload);
+        self.push_encoded(E::CODE, &payload);
+    }
+}
+
+pub struct BatchTxnMD {
+    event_c: u64,
+    payload_l: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BatchTxnRestorePL {
+    pub(super) events: Vec<(GNSTransactionCode, Vec<u8>)>,
+}
+
+impl<'a> PersistObject for BatchTxn<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64, 2);
+    type InputType = BatchTxn<'a>;
+    type OutputType = BatchTxnRestorePL;
+    type Metadata = BatchTxnMD;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        scanner.has_left(md.payload_l as usize)
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.count().u64_bytes_le());
+        buf.extend(data.events().len().u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(BatchTxnMD {
+            event_c: scanner.next_u64_le(),
+            payload_l: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.events());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let mut scanner = BufferedScanner::new(s.next_chunk_variable(md.payload_l as usize));
+        let mut events = vec![];
+        for _ in 0..md.event_c {
+            if !scanner.has_left(sizeof!(u64, 2)) {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+            let code = scanner.next_u64_le();
+            let payload_l = scanner.next_u64_le() as usize;
+            let code = match u8::try_from(code)
+                .ok()
+                .and_then(GNSTransactionCode::try_from_raw)
+            {
+                Some(code) if code != GNSTransactionCode::Batch => code,
+                _ => return Err(StorageError::InternalDecodeStructureIllegalData.into()),
+            };
+            if !scanner.has_left(payload_l) {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+            events.push((code, scanner.next_chunk_variable(payload_l).to_vec()));
+        }
+        if scanner.eof() {
+            Ok(BatchTxnRestorePL { events })
+        } else {
+            Err(StorageError::InternalDecodeStructureCorruptedPayload.into())
+        }
+    }
+}
+
+impl<'a> GNSEvent for BatchTxn<'a> {
+    type CommitType = BatchTxn<'a>;
+    type RestoreType = BatchTxnRestorePL;
+    fn update_global_state(
+        BatchTxnRestorePL { events }: Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        // every event was decoded (and checked) before any of them is applied
+        for (code, payload) in events {
+            <GNSEventLog as EventLogSpec>::DECODE_DISPATCH[code.dscr_u64() as usize](gns, payload)?;
+        }
+        Ok(())
+    }
+    fn summarize(BatchTxnRestorePL { events }: &Self::RestoreType) -> EventSummary {
+        let events: Vec<_> = events
+            .iter()
+            .map(
+                |(code, payload)| match GNSEventLog::summarize_event(*code, payload) {
+                    Ok(EventSummary {
+                        entity: Some(entity),
+                        ..
+                    }) => format!("{} {entity}", code.name()),
+                    Ok(_) => code.name().to_owned(),
+                    Err(_) => format!("{} (corrupted)", code.name()),
+                },
+            )
+            .collect();
+        EventSummary::new(
+            None,
+            format!("{} event(s): {}", events.len(), events.join("; ")),
+        )
+    }
+}
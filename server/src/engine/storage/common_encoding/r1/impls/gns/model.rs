@@ -25,7 +25,7 @@
 */
 
 use {
-    super::GNSEvent,
+    super::{EventSummary, GNSEvent},
     crate::{
         engine::{
             core::{
@@ -41,8 +41,8 @@ use {
             storage::common_encoding::r1::{self, map, obj, PersistObject},
             txn::{
                 gns::model::{
-                    AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateModelTxn,
-                    DropModelTxn,
+                    AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateIndexTxn,
+                    CreateModelTxn, DropIndexTxn, DropModelTxn,
                 },
                 ModelIDRef,
             },
@@ -77,6 +77,14 @@ impl ModelIDRes {
             model_version,
         }
     }
+    fn entity(&self) -> Option<String> {
+        Some(format!("{}.{}", self.space_id.name, self.model_name))
+    }
+}
+
+fn field_names(fields: &IndexSTSeqCns<Box<str>, Field>) -> String {
+    let fields: Vec<&str> = fields.stseq_ord_key().map(|k| k.as_ref()).collect();
+    fields.join(", ")
 }
 pub struct ModelIDMD {
     space_id: super::SpaceIDMD,
@@ -290,6 +298,16 @@ impl<'a> GNSEvent for CreateModelTxn<'a> {
         space.models_mut().insert(model_name);
         Ok(())
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            Some(format!("{}.{}", restore.space_id.name, restore.model_name)),
+            format!(
+                "{} (uuid {})",
+                restore.model.describe(),
+                restore.model.get_uuid()
+            ),
+        )
+    }
 }
 
 /*
@@ -371,6 +389,12 @@ impl<'a> GNSEvent for AlterModelAddTxn<'a> {
             Ok(())
         })
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            restore.model_id.entity(),
+            format!("add fields: {}", field_names(&restore.new_fields)),
+        )
+    }
 }
 
 /*
@@ -461,6 +485,12 @@ impl<'a> GNSEvent for AlterModelRemoveTxn<'a> {
             Ok(())
         })
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            restore.model_id.entity(),
+            format!("remove fields: {}", restore.removed_fields.join(", ")),
+        )
+    }
 }
 
 /*
@@ -542,6 +572,12 @@ impl<'a> GNSEvent for AlterModelUpdateTxn<'a> {
             Ok(())
         })
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            restore.model_id.entity(),
+            format!("update fields: {}", field_names(&restore.updated_fields)),
+        )
+    }
 }
 
 /*
@@ -606,4 +642,178 @@ impl<'a> GNSEvent for DropModelTxn<'a> {
             Ok(())
         })
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(restore.entity(), format!("uuid {}", restore.model_uuid))
+    }
+}
+
+/*
+    create index
+*/
+
+pub struct CreateIndexTxnMD {
+    model_id_md: ModelIDMD,
+    index_name_l: u64,
+    field_l: u64,
+}
+#[derive(Debug, PartialEq)]
+pub struct CreateIndexTxnRestorePL {
+    pub(super) model_id: ModelIDRes,
+    pub(super) index_name: Box<str>,
+    pub(super) field: Box<str>,
+}
+
+impl<'a> PersistObject for CreateIndexTxn<'a> {
+    const METADATA_SIZE: usize = <ModelID as PersistObject>::METADATA_SIZE + sizeof!(u64, 2);
+    type InputType = CreateIndexTxn<'a>;
+    type OutputType = CreateIndexTxnRestorePL;
+    type Metadata = CreateIndexTxnMD;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        scanner.has_left(
+            (md.model_id_md.space_id.space_name_l
+                + md.model_id_md.model_name_l
+                + md.index_name_l
+                + md.field_l) as usize,
+        )
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        <ModelID as PersistObject>::meta_enc(buf, data.model_id());
+        buf.extend(data.index_name().len().u64_bytes_le());
+        buf.extend(data.field().len().u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        let model_id_md = <ModelID as PersistObject>::meta_dec(scanner)?;
+        Ok(CreateIndexTxnMD {
+            model_id_md,
+            index_name_l: scanner.next_u64_le(),
+            field_l: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        <ModelID as PersistObject>::obj_enc(buf, data.model_id());
+        buf.extend(data.index_name().as_bytes());
+        buf.extend(data.field().as_bytes());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let model_id = <ModelID as PersistObject>::obj_dec(s, md.model_id_md)?;
+        let index_name =
+            r1::dec::utils::decode_string(s, md.index_name_l as usize)?.into_boxed_str();
+        let field = r1::dec::utils::decode_string(s, md.field_l as usize)?.into_boxed_str();
+        Ok(CreateIndexTxnRestorePL {
+            model_id,
+            index_name,
+            field,
+        })
+    }
+}
+
+impl<'a> GNSEvent for CreateIndexTxn<'a> {
+    type CommitType = CreateIndexTxn<'a>;
+    type RestoreType = CreateIndexTxnRestorePL;
+    fn update_global_state(
+        CreateIndexTxnRestorePL {
+            model_id,
+            index_name,
+            field,
+        }: Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        with_model_mut(gns, &model_id.space_id, &model_id, |model| {
+            // the index is empty for now; it is built once the model's rows are loaded
+            if model.__raw_create_index(index_name, field) {
+                Ok(())
+            } else {
+                Err(TransactionError::OnRestoreDataConflictMismatch.into())
+            }
+        })
+    }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            restore.model_id.entity(),
+            format!("index {} on {}", restore.index_name, restore.field),
+        )
+    }
+}
+
+/*
+    drop index
+*/
+
+pub struct DropIndexTxnMD {
+    model_id_md: ModelIDMD,
+    index_name_l: u64,
+}
+#[derive(Debug, PartialEq)]
+pub struct DropIndexTxnRestorePL {
+    pub(super) model_id: ModelIDRes,
+    pub(super) index_name: Box<str>,
+}
+
+impl<'a> PersistObject for DropIndexTxn<'a> {
+    const METADATA_SIZE: usize = <ModelID as PersistObject>::METADATA_SIZE + sizeof!(u64);
+    type InputType = DropIndexTxn<'a>;
+    type OutputType = DropIndexTxnRestorePL;
+    type Metadata = DropIndexTxnMD;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        scanner.has_left(
+            (md.model_id_md.space_id.space_name_l + md.model_id_md.model_name_l + md.index_name_l)
+                as usize,
+        )
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        <ModelID as PersistObject>::meta_enc(buf, data.model_id());
+        buf.extend(data.index_name().len().u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        let model_id_md = <ModelID as PersistObject>::meta_dec(scanner)?;
+        Ok(DropIndexTxnMD {
+            model_id_md,
+            index_name_l: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        <ModelID as PersistObject>::obj_enc(buf, data.model_id());
+        buf.extend(data.index_name().as_bytes());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let model_id = <ModelID as PersistObject>::obj_dec(s, md.model_id_md)?;
+        let index_name =
+            r1::dec::utils::decode_string(s, md.index_name_l as usize)?.into_boxed_str();
+        Ok(DropIndexTxnRestorePL {
+            model_id,
+            index_name,
+        })
+    }
+}
+
+impl<'a> GNSEvent for DropIndexTxn<'a> {
+    type CommitType = DropIndexTxn<'a>;
+    type RestoreType = DropIndexTxnRestorePL;
+    fn update_global_state(
+        DropIndexTxnRestorePL {
+            model_id,
+            index_name,
+        }: Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        with_model_mut(gns, &model_id.space_id, &model_id, |model| {
+            if model.__raw_drop_index(&index_name) {
+                Ok(())
+            } else {
+                Err(TransactionError::OnRestoreDataConflictMismatch.into())
+            }
+        })
+    }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            restore.model_id.entity(),
+            format!("index {}", restore.index_name),
+        )
+    }
 }
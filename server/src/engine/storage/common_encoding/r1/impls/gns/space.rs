@@ -25,7 +25,7 @@
 */
 
 use {
-    super::GNSEvent,
+    super::{EventSummary, GNSEvent},
     crate::{
         engine::{
             core::{space::Space, EntityIDRef, GNSData},
@@ -111,6 +111,12 @@ impl<'a> GNSEvent for CreateSpaceTxn<'a> {
             Err(TransactionError::OnRestoreDataConflictAlreadyExists.into())
         }
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            Some(restore.space_name.to_string()),
+            format!("uuid {}", restore.space.get_uuid()),
+        )
+    }
 }
 
 /*
@@ -193,6 +199,14 @@ impl<'a> GNSEvent for AlterSpaceTxn<'a> {
         }
         Ok(())
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        let mut props: Vec<&str> = restore.space_meta.keys().map(|k| k.as_ref()).collect();
+        props.sort();
+        EventSummary::new(
+            Some(restore.space_id.name.to_string()),
+            format!("set properties: {}", props.join(", ")),
+        )
+    }
 }
 
 /*
@@ -254,4 +268,10 @@ impl<'a> GNSEvent for DropSpaceTxn<'a> {
             }
         }
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            Some(restore.name.to_string()),
+            format!("uuid {}", restore.uuid),
+        )
+    }
 }
@@ -26,6 +26,7 @@
 
 use crate::engine::{
     core::{
+        index::PrimaryIndexKind,
         model::{Field, Layer, ModelData},
         space::Space,
     },
@@ -197,7 +198,8 @@ fn create_model() {
                             into_dict! {
                                 "username" => Field::new([Layer::str()].into(), false),
                                 "password" => Field::new([Layer::bin()].into(), false),
-                            }
+                            },
+                            PrimaryIndexKind::Hash,
                         )
                     );
                     Ok(())
@@ -207,6 +209,62 @@ fn create_model() {
     })
 }
 
+#[test]
+fn create_model_ttl() {
+    with_variable("create_model_ttl_test.global.db-tlog", |log_name| {
+        {
+            let global = TestGlobal::new_with_driver_id(log_name);
+            init_space(&global, "myspace", "{}");
+            let stmt = lex_insecure(
+                b"create model myspace.mymodel(username: string, password: binary) with { ttl: 3600 }",
+            )
+            .unwrap();
+            let stmt = parse_ast_node_full(&stmt[2..]).unwrap();
+            ModelData::transactional_exec_create(&global, stmt).unwrap();
+        }
+        multirun(|| {
+            let global = TestGlobal::new_with_driver_id(log_name);
+            global
+                .state()
+                .namespace()
+                .with_model(("myspace", "mymodel").into(), |model| {
+                    assert_eq!(model.ttl(), Some(3600));
+                    Ok(())
+                })
+                .unwrap();
+        })
+    })
+}
+
+#[test]
+fn create_model_unique() {
+    with_variable("create_model_unique_test.global.db-tlog", |log_name| {
+        {
+            let global = TestGlobal::new_with_driver_id(log_name);
+            init_space(&global, "myspace", "{}");
+            let stmt = lex_insecure(
+                b"create model myspace.mymodel(username: string, unique email: string, null phone: uint64)",
+            )
+            .unwrap();
+            let stmt = parse_ast_node_full(&stmt[2..]).unwrap();
+            ModelData::transactional_exec_create(&global, stmt).unwrap();
+        }
+        multirun(|| {
+            let global = TestGlobal::new_with_driver_id(log_name);
+            global
+                .state()
+                .namespace()
+                .with_model(("myspace", "mymodel").into(), |model| {
+                    let fields = model.fields();
+                    assert!(fields.st_get("email").unwrap().is_unique());
+                    assert!(!fields.st_get("phone").unwrap().is_unique());
+                    Ok(())
+                })
+                .unwrap();
+        })
+    })
+}
+
 #[test]
 fn alter_model_add() {
     with_variable("alter_model_add_test.global.db-tlog", |log_name| {
@@ -272,6 +330,40 @@ fn alter_model_remove() {
     })
 }
 
+#[test]
+fn alter_model_remove_indexed() {
+    with_variable("alter_model_rmidx_test.global.db-tlog", |log_name| {
+        {
+            let global = TestGlobal::new_with_driver_id(log_name);
+            init_space(&global, "myspace", "{}");
+            init_model(
+                &global,
+                "myspace",
+                "mymodel",
+                "username: string, password: binary, null city: string",
+            );
+            let stmt = lex_insecure(b"create index by_city on myspace.mymodel(city)").unwrap();
+            let stmt = parse_ast_node_full(&stmt[2..]).unwrap();
+            ModelData::transactional_exec_create_index(&global, stmt).unwrap();
+            let stmt = lex_insecure(b"alter model myspace.mymodel remove city").unwrap();
+            let stmt = parse_ast_node_full(&stmt[2..]).unwrap();
+            ModelData::transactional_exec_alter(&global, stmt).unwrap();
+        }
+        multirun(|| {
+            let global = TestGlobal::new_with_driver_id(log_name);
+            global
+                .state()
+                .namespace()
+                .with_model(("myspace", "mymodel").into(), |model| {
+                    assert!(model.fields().st_get("city").is_none());
+                    assert!(model.secondary_indexes().read().is_empty());
+                    Ok(())
+                })
+                .unwrap();
+        })
+    })
+}
+
 #[test]
 fn alter_model_update() {
     with_variable("alter_model_update_test.global.db-tlog", |log_name| {
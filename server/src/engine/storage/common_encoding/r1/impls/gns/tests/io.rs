@@ -27,7 +27,7 @@
 use {
     super::super::{
         model::{self, ModelIDRes},
-        space, SpaceIDRef, SpaceIDRes,
+        space, BatchTxnRestorePL, SpaceIDRef, SpaceIDRes,
     },
     crate::engine::{
         core::{model::ModelData, space::Space},
@@ -93,16 +93,23 @@ mod model_tests {
         super::{
             model::{
                 AlterModelAddTxnRestorePL, AlterModelRemoveTxnRestorePL,
-                AlterModelUpdateTxnRestorePL, CreateModelTxnRestorePL,
+                AlterModelUpdateTxnRestorePL, CreateIndexTxnRestorePL, CreateModelTxnRestorePL,
+                DropIndexTxnRestorePL,
             },
-            ModelData, Space,
+            BatchTxnRestorePL, ModelData, Space,
         },
         crate::engine::{
-            core::model::{Field, Layer},
+            core::{
+                index::PrimaryIndexKind,
+                model::{Field, Layer},
+            },
             data::{tag::TagSelector, uuid::Uuid},
-            txn::gns::model::{
-                AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateModelTxn,
-                DropModelTxn,
+            txn::gns::{
+                model::{
+                    AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateIndexTxn,
+                    CreateModelTxn, DropIndexTxn, DropModelTxn,
+                },
+                BatchEvents, BatchTxn, GNSTransactionCode,
             },
         },
     };
@@ -116,6 +123,7 @@ mod model_tests {
                 "password" => Field::new([Layer::bin()].into(), false),
                 "profile_pic" => Field::new([Layer::bin()].into(), true),
             ),
+            PrimaryIndexKind::Hash,
         );
         (space, model)
     }
@@ -250,4 +258,95 @@ mod model_tests {
             decoded
         );
     }
+    #[test]
+    fn create_index() {
+        let (space, model) = default_space_model();
+        let txn = CreateIndexTxn::new(
+            super::ModelIDRef::new(
+                super::SpaceIDRef::new("myspace", &space),
+                "mymodel",
+                model.get_uuid(),
+                model.delta_state().schema_current_version().value_u64(),
+            ),
+            "by_password",
+            "password",
+        );
+        let encoded = super::enc::full_self(txn);
+        let decoded = super::dec::full::<CreateIndexTxn>(&encoded).unwrap();
+        assert_eq!(
+            CreateIndexTxnRestorePL {
+                model_id: super::ModelIDRes::new(
+                    super::SpaceIDRes::new(space.get_uuid(), "myspace".into()),
+                    "mymodel".into(),
+                    model.get_uuid(),
+                    model.delta_state().schema_current_version().value_u64()
+                ),
+                index_name: "by_password".into(),
+                field: "password".into(),
+            },
+            decoded
+        );
+    }
+    #[test]
+    fn drop_index() {
+        let (space, model) = default_space_model();
+        let txn = DropIndexTxn::new(
+            super::ModelIDRef::new(
+                super::SpaceIDRef::new("myspace", &space),
+                "mymodel",
+                model.get_uuid(),
+                model.delta_state().schema_current_version().value_u64(),
+            ),
+            "by_password",
+        );
+        let encoded = super::enc::full_self(txn);
+        let decoded = super::dec::full::<DropIndexTxn>(&encoded).unwrap();
+        assert_eq!(
+            DropIndexTxnRestorePL {
+                model_id: super::ModelIDRes::new(
+                    super::SpaceIDRes::new(space.get_uuid(), "myspace".into()),
+                    "mymodel".into(),
+                    model.get_uuid(),
+                    model.delta_state().schema_current_version().value_u64()
+                ),
+                index_name: "by_password".into(),
+            },
+            decoded
+        );
+    }
+    #[test]
+    fn batch() {
+        let (space, model) = default_space_model();
+        let model_id = super::ModelIDRef::new(
+            super::SpaceIDRef::new("myspace", &space),
+            "mymodel",
+            model.get_uuid(),
+            model.delta_state().schema_current_version().value_u64(),
+        );
+        let removed_fields = ["password".into()];
+        let drop_index = DropIndexTxn::new(model_id, "by_password");
+        let remove = AlterModelRemoveTxn::new(model_id, &removed_fields);
+        let mut events = BatchEvents::new();
+        events.push(drop_index);
+        events.push(remove);
+        let encoded = super::enc::full_self(BatchTxn::new(&events));
+        let decoded = super::dec::full::<BatchTxn>(&encoded).unwrap();
+        assert_eq!(
+            BatchTxnRestorePL {
+                events: vec![
+                    (
+                        GNSTransactionCode::DropIndex,
+                        super::enc::full_self(drop_index)
+                    ),
+                    (
+                        GNSTransactionCode::AlterModelRemove,
+                        super::enc::full_self(remove)
+                    ),
+                ]
+            },
+            decoded
+        );
+        // a torn batch is rejected as a whole
+        assert!(super::dec::full::<BatchTxn>(&encoded[..encoded.len() - 1]).is_err());
+    }
 }
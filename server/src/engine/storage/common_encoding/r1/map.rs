@@ -326,7 +326,7 @@ impl<FM: FieldMapAny> MapStorageSpec for FieldMapSpec<FM> {
     }
     fn encode_entry_meta(buf: &mut VecU8, key: &Self::InMemoryKey, val: &Self::InMemoryVal) {
         buf.extend(key.len().u64_bytes_le());
-        buf.extend(0u64.to_le_bytes()); // TODO(@ohsayan): props
+        buf.extend(val.props().count().u64_bytes_le());
         buf.extend(val.layers().len().u64_bytes_le());
         buf.push(val.is_nullable() as u8);
     }
@@ -337,9 +337,7 @@ impl<FM: FieldMapAny> MapStorageSpec for FieldMapSpec<FM> {
         buf.extend(key.as_bytes());
     }
     fn encode_entry_val(buf: &mut VecU8, val: &Self::InMemoryVal) {
-        for layer in val.layers() {
-            super::obj::LayerRef::default_full_enc(buf, super::obj::LayerRef(layer))
-        }
+        super::obj::FieldRef::obj_enc(buf, val)
     }
     fn decode_pretest_for_entry_meta(scanner: &mut BufferedScanner) -> bool {
         scanner.has_left(sizeof!(u64, 3) + 1)
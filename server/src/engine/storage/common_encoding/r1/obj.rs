@@ -29,7 +29,8 @@ use {
     crate::{
         engine::{
             core::{
-                model::{Field, Layer, ModelData},
+                index::PrimaryIndexKind,
+                model::{ttl::TTL_MAX, Field, Layer, ListBounds, ModelData},
                 space::Space,
             },
             data::{
@@ -59,6 +60,7 @@ pub mod cell {
             },
             util::{compiler::TaggedEnum, EndianQW},
         },
+        std::collections::BTreeMap,
     };
     #[derive(
         Debug,
@@ -135,6 +137,16 @@ pub mod cell {
                         encode(buf, item);
                     }
                 }
+                Map => {
+                    // [len] then [keylen][key][tag][value] for every entry
+                    let map = dc.read_map().read();
+                    buf.extend(map.len().u64_bytes_le());
+                    for (key, value) in map.iter() {
+                        buf.extend(key.len().u64_bytes_le());
+                        buf.extend(key.as_bytes());
+                        encode(buf, value);
+                    }
+                }
             }
         }
     }
@@ -185,12 +197,9 @@ pub mod cell {
         DS::Error: From<EY::Error>,
         DS::Error: From<()>,
     {
-        if dscr == StorageCellTypeID::Dict {
-            if EY::CAN_YIELD_DICT {
-                return Ok(EY::yield_dict()?);
-            } else {
-                return Ok(EY::error()?);
-            }
+        // a map cell shares its tag with a dict, so it's only a dict if a dict can be yielded here
+        if (dscr == StorageCellTypeID::Dict) & EY::CAN_YIELD_DICT {
+            return Ok(EY::yield_dict()?);
         }
         if dscr == StorageCellTypeID::Null {
             return Ok(EY::yield_data(Datacell::null())?);
@@ -227,7 +236,6 @@ pub mod cell {
                     let Some(dscr) = StorageCellTypeID::try_from_raw(s.read_next_byte()?) else {
                         return Ok(EY::error()?);
                     };
-                    // FIXME(@ohsayan): right now, a list cannot contain a dict!
                     if !s.has_remaining(StorageCellTypeID::expect_atleast(dscr.value_u8())) {
                         return Ok(EY::error()?);
                     }
@@ -238,6 +246,34 @@ pub mod cell {
                 }
                 Datacell::new_list(l)
             }
+            TagClass::Map => {
+                let len = s.read_next_u64_le()? as usize;
+                let mut m = BTreeMap::new();
+                while (m.len() != len) & s.has_remaining(sizeof!(u64)) {
+                    let key_len = s.read_next_u64_le()? as usize;
+                    if !s.has_remaining(key_len.saturating_add(1)) {
+                        return Ok(EY::error()?);
+                    }
+                    let Ok(key) = String::from_utf8(s.read_next_variable_block(key_len)?) else {
+                        return Ok(EY::error()?);
+                    };
+                    let Some(dscr) = StorageCellTypeID::try_from_raw(s.read_next_byte()?) else {
+                        return Ok(EY::error()?);
+                    };
+                    if !s.has_remaining(StorageCellTypeID::expect_atleast(dscr.value_u8())) {
+                        return Ok(EY::error()?);
+                    }
+                    let value = self::decode_element::<Datacell, DS>(s, dscr)?;
+                    if m.insert(key.into_boxed_str(), value).is_some() {
+                        // duplicate key
+                        return Ok(EY::error()?);
+                    }
+                }
+                if m.len() != len {
+                    return Ok(EY::error()?);
+                }
+                Datacell::new_map(m)
+            }
         };
         Ok(EY::yield_data(d)?)
     }
@@ -245,6 +281,8 @@ pub mod cell {
 
 /*
     layer
+    ---
+    the layer props are the bounds of a list: [prop_c] `[prop id][value]` pairs after the metadata
 */
 
 #[derive(Debug)]
@@ -279,27 +317,47 @@ impl<'a> PersistObject for LayerRef<'a> {
     }
     fn meta_enc(buf: &mut VecU8, LayerRef(layer): Self::InputType) {
         buf.extend(layer.tag().tag_selector().value_qword().to_le_bytes());
-        buf.extend(0u64.to_le_bytes());
+        buf.extend(layer.bounds().props().count().u64_bytes_le());
     }
     unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
         Ok(LayerMD::new(scanner.next_u64_le(), scanner.next_u64_le()))
     }
-    fn obj_enc(_: &mut VecU8, _: Self::InputType) {}
+    fn obj_enc(buf: &mut VecU8, LayerRef(layer): Self::InputType) {
+        for (id, value) in layer.bounds().props() {
+            buf.extend(id.to_le_bytes());
+            buf.extend(value.to_le_bytes());
+        }
+    }
     unsafe fn obj_dec(
-        _: &mut BufferedScanner,
+        scanner: &mut BufferedScanner,
         md: Self::Metadata,
     ) -> RuntimeResult<Self::OutputType> {
-        if (md.type_selector > TagSelector::List.value_qword()) | (md.prop_set_arity != 0) {
+        // only lists have props, and there are only so many of them
+        let is_list = md.type_selector == TagSelector::List.value_qword();
+        let props_okay = (md.prop_set_arity == 0)
+            | (is_list & (md.prop_set_arity <= ListBounds::PROP_C_MAX)
+                && scanner.has_left(md.prop_set_arity as usize * sizeof!(u64, 2)));
+        if (md.type_selector > TagSelector::Map.value_qword()) | !props_okay {
             return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
         }
-        Ok(Layer::new_empty_props(
+        let mut bounds = ListBounds::unbounded();
+        for _ in 0..md.prop_set_arity {
+            let (id, value) = (scanner.next_u64_le(), scanner.next_u64_le());
+            if !bounds.set_prop(id, value) {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+        }
+        Ok(Layer::new_with_bounds(
             TagSelector::from_raw(md.type_selector as u8).into_full(),
+            bounds,
         ))
     }
 }
 
 /*
     field
+    ---
+    the field props (such as unique) are [prop_c] `[prop id][value]` pairs after the layers
 */
 
 pub struct FieldMD {
@@ -334,7 +392,7 @@ impl<'a> PersistObject for FieldRef<'a> {
     }
     fn meta_enc(buf: &mut VecU8, slf: Self::InputType) {
         // [prop_c][layer_c][null]
-        buf.extend(0u64.to_le_bytes());
+        buf.extend(slf.props().count().u64_bytes_le());
         buf.extend(slf.layers().len().u64_bytes_le());
         buf.push(slf.is_nullable() as u8);
     }
@@ -349,6 +407,10 @@ impl<'a> PersistObject for FieldRef<'a> {
         for layer in slf.layers() {
             LayerRef::default_full_enc(buf, LayerRef(layer));
         }
+        for (id, value) in slf.props() {
+            buf.extend(id.to_le_bytes());
+            buf.extend(value.to_le_bytes());
+        }
     }
     unsafe fn obj_dec(
         scanner: &mut BufferedScanner,
@@ -366,14 +428,91 @@ impl<'a> PersistObject for FieldRef<'a> {
                 LayerRef::meta_dec(scanner)?
             };
             let l = LayerRef::obj_dec(scanner, layer_md)?;
-            fin = l.tag().tag_class() != TagClass::List;
+            fin = !matches!(l.tag().tag_class(), TagClass::List | TagClass::Map);
             layers.push(l);
         }
-        let field = Field::new(layers, md.null == 1);
-        if (field.layers().len() as u64 == md.layer_c) & (md.null <= 1) & (md.prop_c == 0) & fin {
-            Ok(field)
-        } else {
-            Err(StorageError::InternalDecodeStructureCorrupted.into())
+        let mut field = Field::new(layers, md.null == 1);
+        if (field.layers().len() as u64 != md.layer_c)
+            | (md.null > 1)
+            | (md.prop_c > Field::PROP_C_MAX)
+            | !fin
+            || !scanner.has_left(md.prop_c as usize * sizeof!(u64, 2))
+        {
+            return Err(StorageError::InternalDecodeStructureCorrupted.into());
+        }
+        for _ in 0..md.prop_c {
+            let (id, value) = (scanner.next_u64_le(), scanner.next_u64_le());
+            if !field.set_prop(id, value) {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+        }
+        Ok(field)
+    }
+}
+
+/*
+    model options
+    ---
+    revisions 5 to 11 of the GNS log packed the model options into the qword of the primary key's tag: the index kind
+    in the low bits of the second byte, the append-only flag in its highest bit and the ttl in the remaining six bytes
+    (older revisions leave all of it unset). since revision 12, the options have a field of their own that follows the
+    layout's metadata:
+
+    [version: 8B][index kind: 8B][append only: 8B][ttl: 8B]
+
+    and the qword of the tag only holds the selector in its first byte and `MODEL_OPTIONS_IN_FIELD` in the rest. that
+    would be the index kind `0x7F` in the packed form, which doesn't exist, so no older revision could have written it
+*/
+
+/// The rest of the primary key tag's qword when the model options are in their own field
+const MODEL_OPTIONS_IN_FIELD: u64 = 0x7F << 8;
+/// The version of the model options field that we write
+const MODEL_OPTIONS_VERSION: u64 = 1;
+
+#[derive(Debug)]
+pub struct ModelOptionsMD {
+    index: u64,
+    append_only: u64,
+    ttl: u64,
+}
+
+impl ModelOptionsMD {
+    const SIZE: usize = sizeof!(u64, 4);
+    fn new(index: u64, append_only: u64, ttl: u64) -> Self {
+        Self {
+            index,
+            append_only,
+            ttl,
+        }
+    }
+    /// Unpack the options from the primary key tag's qword, as written by revisions 5 to 11
+    fn unpack_legacy(p_key_tag: u64) -> Self {
+        Self::new(
+            (p_key_tag >> 8) & 0x7F,
+            (p_key_tag >> 15) & 1,
+            p_key_tag >> 16,
+        )
+    }
+    fn enc(buf: &mut VecU8, model_def: &ModelData) {
+        buf.extend(MODEL_OPTIONS_VERSION.to_le_bytes());
+        buf.extend((model_def.primary_index().kind().value_u8() as u64).to_le_bytes());
+        buf.extend((model_def.is_append_only() as u64).to_le_bytes());
+        buf.extend(model_def.ttl().unwrap_or(0).to_le_bytes());
+    }
+    fn dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self> {
+        if !scanner.has_left(Self::SIZE) {
+            return Err(StorageError::InternalDecodeStructureCorrupted.into());
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): just checked the size
+            if scanner.next_u64_le() != MODEL_OPTIONS_VERSION {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+            Ok(Self::new(
+                scanner.next_u64_le(),
+                scanner.next_u64_le(),
+                scanner.next_u64_le(),
+            ))
         }
     }
 }
@@ -384,6 +523,7 @@ pub struct ModelLayoutMD {
     p_key_len: u64,
     p_key_tag: u64,
     field_c: u64,
+    options: ModelOptionsMD,
 }
 
 impl ModelLayoutMD {
@@ -392,12 +532,14 @@ impl ModelLayoutMD {
         p_key_len: u64,
         p_key_tag: u64,
         field_c: u64,
+        options: ModelOptionsMD,
     ) -> Self {
         Self {
             model_uuid,
             p_key_len,
             p_key_tag,
             field_c,
+            options,
         }
     }
     pub fn p_key_len(&self) -> u64 {
@@ -423,15 +565,28 @@ impl<'a> PersistObject for ModelLayoutRef<'a> {
     fn meta_enc(buf: &mut VecU8, ModelLayoutRef(model_def): Self::InputType) {
         buf.extend(model_def.get_uuid().to_le_bytes());
         buf.extend(model_def.p_key().len().u64_bytes_le());
-        buf.extend(model_def.p_tag().tag_selector().value_qword().to_le_bytes());
+        buf.extend(
+            (model_def.p_tag().tag_selector().value_qword() | MODEL_OPTIONS_IN_FIELD).to_le_bytes(),
+        );
         buf.extend(model_def.fields().len().u64_bytes_le());
+        ModelOptionsMD::enc(buf, model_def);
     }
     unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        let model_uuid = Uuid::from_bytes(scanner.next_chunk());
+        let p_key_len = scanner.next_u64_le();
+        let p_key_tag = scanner.next_u64_le();
+        let field_c = scanner.next_u64_le();
+        let options = if p_key_tag & !0xFF == MODEL_OPTIONS_IN_FIELD {
+            ModelOptionsMD::dec(scanner)?
+        } else {
+            ModelOptionsMD::unpack_legacy(p_key_tag)
+        };
         Ok(ModelLayoutMD::new(
-            Uuid::from_bytes(scanner.next_chunk()),
-            scanner.next_u64_le(),
-            scanner.next_u64_le(),
-            scanner.next_u64_le(),
+            model_uuid,
+            p_key_len,
+            p_key_tag & 0xFF,
+            field_c,
+            options,
         ))
     }
     fn obj_enc(buf: &mut VecU8, ModelLayoutRef(model_definition): Self::InputType) {
@@ -451,17 +606,34 @@ impl<'a> PersistObject for ModelLayoutRef<'a> {
         > as PersistObject>::obj_dec(
             scanner, super::map::MapIndexSizeMD(md.field_c as usize)
         )?;
+        let ModelOptionsMD {
+            index,
+            append_only,
+            ttl,
+        } = md.options;
         let ptag = if md.p_key_tag > TagSelector::MAX_DSCR as u64 {
             return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
         } else {
             TagSelector::from_raw(md.p_key_tag as u8)
         };
+        let Some(index) = u8::try_from(index)
+            .ok()
+            .and_then(PrimaryIndexKind::try_from_raw)
+        else {
+            return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+        };
+        if (append_only > 1) | (ttl > TTL_MAX) {
+            return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+        }
         Ok(ModelData::new_restore(
             md.model_uuid,
             key.into_boxed_str(),
             ptag.into_full(),
             fieldmap,
-        ))
+            index,
+        )
+        .with_ttl((ttl != 0).then_some(ttl))
+        .with_append_only(append_only == 1))
     }
 }
 
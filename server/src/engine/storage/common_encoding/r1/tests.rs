@@ -29,7 +29,8 @@ use {
     crate::{
         engine::{
             core::{
-                model::{Field, Layer, ModelData},
+                index::PrimaryIndexKind,
+                model::{Field, Layer, ListBounds, ModelData},
                 space::Space,
             },
             data::{
@@ -44,6 +45,7 @@ use {
         },
         util::compiler::TaggedEnum,
     },
+    std::collections::BTreeMap,
 };
 
 #[test]
@@ -69,12 +71,49 @@ fn layer() {
     assert_eq!(layer, dec);
 }
 
+#[test]
+fn layer_with_bounds() {
+    let mut bounds = ListBounds::unbounded();
+    assert!(bounds.set_prop(ListBounds::PROP_MAX_LEN, 100));
+    assert!(bounds.set_prop(ListBounds::PROP_ELEMENT_MAX, 256));
+    let layer = Layer::new_with_bounds(Layer::list().tag(), bounds);
+    let encoded = super::enc::full::<obj::LayerRef>(obj::LayerRef(&layer));
+    let dec = super::dec::full::<obj::LayerRef>(&encoded).unwrap();
+    assert_eq!(layer, dec);
+    let field = Field::new([layer, Layer::str()].into(), false);
+    let encoded = super::enc::full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+}
+
 #[test]
 fn field() {
     let field = Field::new([Layer::list(), Layer::uint64()].into(), true);
     let encoded = super::enc::full::<obj::FieldRef>((&field).into());
     let dec = super::dec::full::<obj::FieldRef>(&encoded).unwrap();
     assert_eq!(field, dec);
+    let field = Field::new([Layer::map(), Layer::list(), Layer::str()].into(), false);
+    let encoded = super::enc::full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+    let field = Field::new([Layer::str()].into(), true).with_unique(true);
+    let encoded = super::enc::full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_baseline_decode() {
+    // [prop c][layer c][null], then [selector][prop c] for the layer
+    let mut buf = vec![];
+    buf.extend(0u64.to_le_bytes());
+    buf.extend(1u64.to_le_bytes());
+    buf.push(1);
+    buf.extend(TagSelector::String.value_qword().to_le_bytes());
+    buf.extend(0u64.to_le_bytes());
+    let dec = super::dec::full::<obj::FieldRef>(&buf).unwrap();
+    assert!(!dec.is_unique());
+    assert_eq!(dec, Field::new([Layer::str()].into(), true));
 }
 
 #[test]
@@ -85,6 +124,10 @@ fn fieldmap() {
         "profile_pic".into(),
         Field::new([Layer::bin()].into(), true),
     );
+    fields.st_insert(
+        "email".into(),
+        Field::new([Layer::str()].into(), false).with_unique(true),
+    );
     let enc = super::enc::full_dict::<super::map::FieldMapSpec<_>>(&fields);
     let dec = super::dec::dict_full::<
         super::map::FieldMapSpec<crate::engine::idx::IndexSTSeqCns<Box<str>, _>>,
@@ -109,9 +152,142 @@ fn model() {
             "password" => Field::new([Layer::bin()].into(), false),
             "profile_pic" => Field::new([Layer::bin()].into(), true),
         },
+        PrimaryIndexKind::Hash,
+    );
+    let enc = super::enc::full::<obj::ModelLayoutRef>(obj::ModelLayoutRef(&model));
+    let dec = super::dec::full::<obj::ModelLayoutRef>(&enc).unwrap();
+    assert_eq!(model, dec);
+}
+
+#[test]
+fn model_radix_index() {
+    let model = ModelData::new_restore(
+        Uuid::new(),
+        "url".into(),
+        TagSelector::String.into_full(),
+        into_dict! {
+            "url" => Field::new([Layer::str()].into(), false),
+            "hits" => Field::new([Layer::uint64()].into(), false),
+        },
+        PrimaryIndexKind::Radix,
+    );
+    let enc = super::enc::full::<obj::ModelLayoutRef>(obj::ModelLayoutRef(&model));
+    let dec = super::dec::full::<obj::ModelLayoutRef>(&enc).unwrap();
+    assert_eq!(dec.primary_index().kind(), PrimaryIndexKind::Radix);
+    assert_eq!(model, dec);
+}
+
+/// A model layout with a single `password: binary` field, as written before the GNS log had model options (revision 5
+/// of `SystemDatabaseV1`), where the primary key tag's qword only holds the selector
+fn baseline_model_layout(uuid: Uuid) -> Vec<u8> {
+    legacy_model_layout(uuid, 0)
+}
+
+/// A model layout like [`baseline_model_layout`], with the model options packed into the rest of the primary key tag's
+/// qword (as written by revisions 5 to 11)
+fn legacy_model_layout(uuid: Uuid, packed_options: u64) -> Vec<u8> {
+    let mut buf = vec![];
+    // [uuid][pk len][pk tag][field count]
+    buf.extend(uuid.to_le_bytes());
+    buf.extend(8u64.to_le_bytes());
+    buf.extend((TagSelector::String.value_qword() | packed_options).to_le_bytes());
+    buf.extend(1u64.to_le_bytes());
+    buf.extend(b"username");
+    // [field id len][prop c][layer c][null][field id], then [selector][prop c] for the layer
+    buf.extend(8u64.to_le_bytes());
+    buf.extend(0u64.to_le_bytes());
+    buf.extend(1u64.to_le_bytes());
+    buf.push(0);
+    buf.extend(b"password");
+    buf.extend(TagSelector::Binary.value_qword().to_le_bytes());
+    buf.extend(0u64.to_le_bytes());
+    buf
+}
+
+#[test]
+fn model_baseline_decode() {
+    let uuid = Uuid::new();
+    let dec = super::dec::full::<obj::ModelLayoutRef>(&baseline_model_layout(uuid)).unwrap();
+    assert_eq!(dec.primary_index().kind(), PrimaryIndexKind::Hash);
+    assert_eq!(dec.ttl(), None);
+    assert!(!dec.is_append_only());
+    assert_eq!(
+        dec,
+        ModelData::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict! {
+                "password" => Field::new([Layer::bin()].into(), false),
+            },
+            PrimaryIndexKind::Hash,
+        )
+    );
+}
+
+#[test]
+fn model_legacy_options_decode() {
+    // radix index, append-only and a ttl of an hour
+    let packed = ((PrimaryIndexKind::Radix.value_u8() as u64) << 8) | (1 << 15) | (3600 << 16);
+    let dec =
+        super::dec::full::<obj::ModelLayoutRef>(&legacy_model_layout(Uuid::new(), packed)).unwrap();
+    assert_eq!(dec.primary_index().kind(), PrimaryIndexKind::Radix);
+    assert!(dec.is_append_only());
+    assert_eq!(dec.ttl(), Some(3600));
+    // and an index kind that doesn't exist
+    let packed = 0x7E << 8;
+    assert!(
+        super::dec::full::<obj::ModelLayoutRef>(&legacy_model_layout(Uuid::new(), packed)).is_err()
+    );
+}
+
+#[test]
+fn model_options_field() {
+    let model = ModelData::new_restore(
+        Uuid::new(),
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict! {
+            "password" => Field::new([Layer::bin()].into(), false),
+        },
+        PrimaryIndexKind::Hash,
+    )
+    .with_ttl(Some(3600));
+    let enc = super::enc::full::<obj::ModelLayoutRef>(obj::ModelLayoutRef(&model));
+    // the primary key tag's qword only holds the selector and the marker for the options field
+    let tag_qword = u64::from_le_bytes(enc[24..32].try_into().unwrap());
+    assert_eq!(tag_qword, TagSelector::String.value_qword() | (0x7F << 8));
+    // which comes right after the field count
+    let options: Vec<u64> = enc[40..72]
+        .chunks_exact(sizeof!(u64))
+        .map(|qw| u64::from_le_bytes(qw.try_into().unwrap()))
+        .collect();
+    assert_eq!(
+        options,
+        [1, PrimaryIndexKind::Hash.value_u8() as u64, 0, 3600]
     );
+    let dec = super::dec::full::<obj::ModelLayoutRef>(&enc).unwrap();
+    assert_eq!(dec.ttl(), Some(3600));
+    assert_eq!(model, dec);
+}
+
+#[test]
+fn model_append_only() {
+    let model = ModelData::new_restore(
+        Uuid::new(),
+        "event_id".into(),
+        TagSelector::String.into_full(),
+        into_dict! {
+            "event_id" => Field::new([Layer::str()].into(), false),
+            "payload" => Field::new([Layer::bin()].into(), false),
+        },
+        PrimaryIndexKind::Radix,
+    )
+    .with_append_only(true);
     let enc = super::enc::full::<obj::ModelLayoutRef>(obj::ModelLayoutRef(&model));
     let dec = super::dec::full::<obj::ModelLayoutRef>(&enc).unwrap();
+    assert!(dec.is_append_only());
+    assert_eq!(dec.primary_index().kind(), PrimaryIndexKind::Radix);
     assert_eq!(model, dec);
 }
 
@@ -230,6 +406,19 @@ fn dc_encode_decode() {
         Datacell::new_str("abcdefghijkl".to_owned().into_boxed_str()),
         // list
         Datacell::new_list(vec![]),
+        // map
+        Datacell::new_map(BTreeMap::new()),
+        Datacell::new_map(BTreeMap::from([
+            ("a".into(), Datacell::new_uint_default(1)),
+            (
+                "b".into(),
+                Datacell::new_list(vec![Datacell::new_str("c".into())]),
+            ),
+            (
+                "d".into(),
+                Datacell::new_map(BTreeMap::from([("e".into(), Datacell::new_bool(false))])),
+            ),
+        ])),
     ];
     for value in dc_tests {
         enc_dec(&value)
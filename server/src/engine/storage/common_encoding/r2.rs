@@ -29,13 +29,26 @@
 */
 
 use {
-    super::r1::{dec, impls::gns::GNSEvent, PersistObject},
+    super::r1::{
+        dec,
+        impls::gns::{EventSummary, GNSEvent},
+        PersistObject,
+    },
     crate::{
         engine::{
-            core::GNSData,
+            core::{
+                quota::UserWriteLimits,
+                task::{Schedule, TaskBody},
+                GNSData,
+            },
             error::{StorageError, TransactionError},
             mem::BufferedScanner,
-            txn::gns::sysctl::{AlterUserTxn, CreateUserTxn, DropUserTxn},
+            txn::gns::{
+                sysctl::{
+                    AlterUserLimitsTxn, AlterUserTxn, CreateUserTxn, DropUserTxn, SetReadOnlyTxn,
+                },
+                task::{AlterTaskTxn, CreateTaskTxn, DropTaskTxn},
+            },
             RuntimeResult,
         },
         util::EndianQW,
@@ -59,6 +72,10 @@ impl<'a> GNSEvent for CreateUserTxn<'a> {
             Err(TransactionError::OnRestoreDataConflictAlreadyExists.into())
         }
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        // never show the password hash
+        EventSummary::new(Some(restore.username.to_string()), String::new())
+    }
 }
 
 pub struct FullUserDefinition {
@@ -146,6 +163,9 @@ impl<'a> GNSEvent for AlterUserTxn<'a> {
             Err(TransactionError::OnRestoreDataConflictMismatch.into())
         }
     }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(Some(restore.username.to_string()), String::new())
+    }
 }
 
 impl<'a> PersistObject for AlterUserTxn<'a> {
@@ -189,6 +209,101 @@ impl<'a> PersistObject for AlterUserTxn<'a> {
     }
 }
 
+/*
+    alter user limits txn
+*/
+
+pub struct UserLimitsDefinition {
+    username: Box<str>,
+    limits: UserWriteLimits,
+}
+
+impl<'a> GNSEvent for AlterUserLimitsTxn<'a> {
+    type CommitType = Self;
+    type RestoreType = UserLimitsDefinition;
+    fn update_global_state(
+        UserLimitsDefinition { username, limits }: Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        if gns.sys_db().__raw_alter_user_limits(&username, limits) {
+            Ok(())
+        } else {
+            Err(TransactionError::OnRestoreDataConflictMismatch.into())
+        }
+    }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        let limit = |limit: Option<u64>| match limit {
+            Some(limit) => limit.to_string(),
+            None => "default".to_owned(),
+        };
+        EventSummary::new(
+            Some(restore.username.to_string()),
+            format!(
+                "write ops limit: {}, write bytes limit: {}",
+                limit(restore.limits.ops()),
+                limit(restore.limits.bytes())
+            ),
+        )
+    }
+}
+
+pub struct AlterUserLimitsMetadata {
+    uname_l: u64,
+    limits: UserWriteLimits,
+}
+
+impl<'a> AlterUserLimitsTxn<'a> {
+    const LIMIT_OPS: u64 = 1 << 0;
+    const LIMIT_BYTES: u64 = 1 << 1;
+}
+
+impl<'a> PersistObject for AlterUserLimitsTxn<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64, 4);
+    type InputType = Self;
+    type OutputType = UserLimitsDefinition;
+    type Metadata = AlterUserLimitsMetadata;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        scanner.has_left(md.uname_l as usize)
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        // [username length: 8B][limits that are set: 8B][ops limit: 8B][bytes limit: 8B]
+        let limits = data.limits();
+        let set = (limits.ops().is_some() as u64 * Self::LIMIT_OPS)
+            | (limits.bytes().is_some() as u64 * Self::LIMIT_BYTES);
+        buf.extend(data.username().len().u64_bytes_le());
+        buf.extend(set.u64_bytes_le());
+        buf.extend(limits.ops().unwrap_or(0).u64_bytes_le());
+        buf.extend(limits.bytes().unwrap_or(0).u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        let uname_l = scanner.next_u64_le();
+        let set = scanner.next_u64_le();
+        let ops = scanner.next_u64_le();
+        let bytes = scanner.next_u64_le();
+        if set & !(Self::LIMIT_OPS | Self::LIMIT_BYTES) != 0 {
+            return Err(StorageError::InternalDecodeStructureIllegalData.into());
+        }
+        let limits = UserWriteLimits::new(
+            (set & Self::LIMIT_OPS != 0).then_some(ops),
+            (set & Self::LIMIT_BYTES != 0).then_some(bytes),
+        );
+        Ok(AlterUserLimitsMetadata { uname_l, limits })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.username().as_bytes());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let username = dec::utils::decode_string(s, md.uname_l as usize)?;
+        Ok(UserLimitsDefinition {
+            username: username.into_boxed_str(),
+            limits: md.limits,
+        })
+    }
+}
+
 /*
     drop user txn
 */
@@ -208,6 +323,9 @@ impl<'a> GNSEvent for DropUserTxn<'a> {
             Err(TransactionError::OnRestoreDataConflictMismatch.into())
         }
     }
+    fn summarize(DropUserPayload(username): &Self::RestoreType) -> EventSummary {
+        EventSummary::new(Some(username.to_string()), String::new())
+    }
 }
 
 impl<'a> PersistObject for DropUserTxn<'a> {
@@ -235,3 +353,252 @@ impl<'a> PersistObject for DropUserTxn<'a> {
         Ok(DropUserPayload(username.into_boxed_str()))
     }
 }
+
+/*
+    create task txn
+*/
+
+pub struct FullTaskDefinition {
+    task_name: Box<str>,
+    schedule: Schedule,
+    body: TaskBody,
+}
+
+impl<'a> GNSEvent for CreateTaskTxn<'a> {
+    type CommitType = Self;
+    type RestoreType = FullTaskDefinition;
+    fn update_global_state(
+        FullTaskDefinition {
+            task_name,
+            schedule,
+            body,
+        }: Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        if gns.tasks().__raw_create_task(task_name, schedule, body) {
+            Ok(())
+        } else {
+            Err(TransactionError::OnRestoreDataConflictAlreadyExists.into())
+        }
+    }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            Some(restore.task_name.to_string()),
+            format!("schedule: {}", restore.schedule.src()),
+        )
+    }
+}
+
+pub struct CreateTaskMetadata {
+    name_l: u64,
+    schedule_l: u64,
+    payload_l: u64,
+    q_window: u64,
+    props_l: u64,
+}
+
+impl<'a> PersistObject for CreateTaskTxn<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64, 5);
+    type InputType = Self;
+    type OutputType = FullTaskDefinition;
+    type Metadata = CreateTaskMetadata;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        (md.q_window <= md.payload_l)
+            & scanner.has_left((md.name_l + md.schedule_l + md.payload_l) as usize)
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        // [name length: 8B][schedule length: 8B][payload length: 8B][query window: 8B][properties length: 8B]
+        buf.extend(data.task_name().len().u64_bytes_le());
+        buf.extend(data.schedule().len().u64_bytes_le());
+        buf.extend(data.payload().len().u64_bytes_le());
+        buf.extend(data.q_window().u64_bytes_le());
+        buf.extend(0u64.u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(CreateTaskMetadata {
+            name_l: scanner.next_u64_le(),
+            schedule_l: scanner.next_u64_le(),
+            payload_l: scanner.next_u64_le(),
+            q_window: scanner.next_u64_le(),
+            props_l: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.task_name().as_bytes());
+        buf.extend(data.schedule().as_bytes());
+        buf.extend(data.payload());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let task_name = dec::utils::decode_string(s, md.name_l as _)?;
+        let schedule = dec::utils::decode_string(s, md.schedule_l as _)?;
+        let payload = s.next_chunk_variable(md.payload_l as _);
+        match Schedule::parse(&schedule) {
+            Ok(schedule) if md.props_l == 0 => Ok(FullTaskDefinition {
+                task_name: task_name.into_boxed_str(),
+                schedule,
+                body: TaskBody::new(payload.to_vec().into_boxed_slice(), md.q_window as usize),
+            }),
+            _ => Err(StorageError::InternalDecodeStructureIllegalData.into()),
+        }
+    }
+}
+
+/*
+    alter task txn
+*/
+
+pub struct AlterTaskPayload {
+    task_name: Box<str>,
+    enabled: bool,
+}
+
+impl<'a> GNSEvent for AlterTaskTxn<'a> {
+    type CommitType = Self;
+    type RestoreType = AlterTaskPayload;
+    fn update_global_state(
+        AlterTaskPayload { task_name, enabled }: Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        if gns.tasks().__raw_alter_task(&task_name, enabled) {
+            Ok(())
+        } else {
+            Err(TransactionError::OnRestoreDataConflictMismatch.into())
+        }
+    }
+    fn summarize(restore: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(
+            Some(restore.task_name.to_string()),
+            format!("enabled: {}", restore.enabled),
+        )
+    }
+}
+
+impl<'a> PersistObject for AlterTaskTxn<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64, 2);
+    type InputType = Self;
+    type OutputType = AlterTaskPayload;
+    type Metadata = (u64, u64);
+    fn pretest_can_dec_object(scanner: &BufferedScanner, (name_l, _): &Self::Metadata) -> bool {
+        scanner.has_left(*name_l as usize)
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        // [name length: 8B][enabled: 8B]
+        buf.extend(data.task_name().len().u64_bytes_le());
+        buf.extend((data.enabled() as u64).u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok((scanner.next_u64_le(), scanner.next_u64_le()))
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.task_name().as_bytes());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        (name_l, enabled): Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let task_name = dec::utils::decode_string(s, name_l as usize)?.into_boxed_str();
+        match enabled {
+            0 | 1 => Ok(AlterTaskPayload {
+                task_name,
+                enabled: enabled == 1,
+            }),
+            _ => Err(StorageError::InternalDecodeStructureIllegalData.into()),
+        }
+    }
+}
+
+/*
+    drop task txn
+*/
+
+pub struct DropTaskPayload(Box<str>);
+
+impl<'a> GNSEvent for DropTaskTxn<'a> {
+    type CommitType = Self;
+    type RestoreType = DropTaskPayload;
+    fn update_global_state(
+        DropTaskPayload(task_name): Self::RestoreType,
+        gns: &GNSData,
+    ) -> RuntimeResult<()> {
+        if gns.tasks().__raw_drop_task(&task_name) {
+            Ok(())
+        } else {
+            Err(TransactionError::OnRestoreDataConflictMismatch.into())
+        }
+    }
+    fn summarize(DropTaskPayload(task_name): &Self::RestoreType) -> EventSummary {
+        EventSummary::new(Some(task_name.to_string()), String::new())
+    }
+}
+
+impl<'a> PersistObject for DropTaskTxn<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64);
+    type InputType = Self;
+    type OutputType = DropTaskPayload;
+    type Metadata = u64;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        scanner.has_left(*md as usize)
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.task_name().len().u64_bytes_le())
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(scanner.next_u64_le())
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.task_name().as_bytes());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let task_name = dec::utils::decode_string(s, md as usize)?;
+        Ok(DropTaskPayload(task_name.into_boxed_str()))
+    }
+}
+
+/*
+    set read only txn
+*/
+
+impl GNSEvent for SetReadOnlyTxn {
+    type CommitType = Self;
+    type RestoreType = bool;
+    fn update_global_state(read_only: Self::RestoreType, gns: &GNSData) -> RuntimeResult<()> {
+        gns.sys_db().__raw_set_read_only(read_only);
+        Ok(())
+    }
+    fn summarize(read_only: &Self::RestoreType) -> EventSummary {
+        EventSummary::new(None, format!("read only: {read_only}"))
+    }
+}
+
+impl PersistObject for SetReadOnlyTxn {
+    const METADATA_SIZE: usize = sizeof!(u64);
+    type InputType = Self;
+    type OutputType = bool;
+    type Metadata = u64;
+    fn pretest_can_dec_object(_: &BufferedScanner, _: &Self::Metadata) -> bool {
+        true
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend((data.read_only() as u64).u64_bytes_le())
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(scanner.next_u64_le())
+    }
+    fn obj_enc(_: &mut Vec<u8>, _: Self::InputType) {}
+    unsafe fn obj_dec(
+        _: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        match md {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StorageError::InternalDecodeStructureIllegalData.into()),
+        }
+    }
+}
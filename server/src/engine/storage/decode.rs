@@ -0,0 +1,185 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Logical decoding of the GNS journal (`skyd decode-gns`)
+
+use {
+    super::{
+        common_encoding::r1::impls::gns::EventSummary,
+        v2::{
+            impls::gns_log::GNSEventLog,
+            raw::journal::{self, DriverEventKind, EventLogAdapter, ScannedEvent},
+        },
+    },
+    crate::engine::RuntimeResult,
+    std::fmt::Write,
+};
+
+#[derive(Debug, PartialEq)]
+/// An event in the GNS journal, decoded into something that doesn't need any knowledge of SDSS
+pub struct DecodedEvent {
+    txn_id: u64,
+    event: &'static str,
+    entity: Option<String>,
+    summary: Option<String>,
+    checksum_ok: bool,
+    error: Option<String>,
+}
+
+impl DecodedEvent {
+    fn new(txn_id: u64, event: &'static str, checksum_ok: bool) -> Self {
+        Self {
+            txn_id,
+            event,
+            entity: None,
+            summary: None,
+            checksum_ok,
+            error: None,
+        }
+    }
+    fn with_error(mut self, error: impl ToString) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+    /// Returns the event as a JSON object (on a single line)
+    pub fn to_json(&self) -> String {
+        fn opt(v: &Option<String>) -> String {
+            match v {
+                Some(v) => format!("{v:?}"),
+                None => "null".to_owned(),
+            }
+        }
+        let mut ret = format!(
+            "{{\"txn_id\":{},\"event\":\"{}\",\"entity\":{},\"summary\":{},\"checksum_ok\":{}",
+            self.txn_id,
+            self.event,
+            opt(&self.entity),
+            opt(&self.summary),
+            self.checksum_ok
+        );
+        if let Some(ref error) = self.error {
+            let _ = write!(ret, ",\"error\":{error:?}");
+        }
+        ret.push('}');
+        ret
+    }
+}
+
+/// Decode every event in the GNS journal at `log_path` (in order) without loading it. An event that can't be decoded
+/// is still passed to `f` (with an error); this only fails if the journal can't be read any further
+pub fn decode_gns(log_path: &str, mut f: impl FnMut(DecodedEvent)) -> RuntimeResult<()> {
+    journal::scan_journal::<EventLogAdapter<GNSEventLog>, _>(log_path, |txn_id, event, file| {
+        let decoded = match event {
+            ScannedEvent::Server(code) => {
+                // the payload has to be read even if we don't know what it is
+                let (payload, checksum_ok) = journal::read_event_payload(file)?;
+                match code {
+                    Some(code) if checksum_ok => {
+                        let decoded = DecodedEvent::new(txn_id, code.name(), true);
+                        match GNSEventLog::summarize_event(code, &payload) {
+                            Ok(EventSummary { entity, summary }) => DecodedEvent {
+                                entity,
+                                summary: (!summary.is_empty()).then_some(summary),
+                                ..decoded
+                            },
+                            Err(e) => decoded.with_error(e),
+                        }
+                    }
+                    Some(code) => DecodedEvent::new(txn_id, code.name(), false)
+                        .with_error("payload checksum mismatch"),
+                    None => DecodedEvent::new(txn_id, "unknown", checksum_ok)
+                        .with_error("unknown event type"),
+                }
+            }
+            ScannedEvent::Driver(Some(DriverEventKind::Closed)) => {
+                DecodedEvent::new(txn_id, "driver_closed", true)
+            }
+            ScannedEvent::Driver(Some(DriverEventKind::Reopened)) => {
+                DecodedEvent::new(txn_id, "driver_reopened", true)
+            }
+            ScannedEvent::Driver(None) => DecodedEvent::new(txn_id, "driver_event", false)
+                .with_error("corrupted driver event"),
+        };
+        f(decoded);
+        Ok(())
+    })
+}
+
+#[test]
+fn decoded_event_json() {
+    let mut event = DecodedEvent::new(3, "create_space", true);
+    event.entity = Some("myspace".into());
+    event.summary = Some("uuid \"x\"".into());
+    assert_eq!(
+        event.to_json(),
+        r#"{"txn_id":3,"event":"create_space","entity":"myspace","summary":"uuid \"x\"","checksum_ok":true}"#
+    );
+    assert_eq!(
+        DecodedEvent::new(4, "unknown", false)
+            .with_error("unknown event type")
+            .to_json(),
+        r#"{"txn_id":4,"event":"unknown","entity":null,"summary":null,"checksum_ok":false,"error":"unknown event type"}"#
+    );
+}
+
+#[test]
+fn decode_gns_events() {
+    use crate::engine::{
+        core::{model::ModelData, space::Space},
+        fractal::test_utils::TestGlobal,
+        ql::{
+            ast,
+            ddl::crt::{CreateModel, CreateSpace},
+            tests::lex_insecure,
+        },
+    };
+    let log_name = "decode_gns_events.db-tlog";
+    {
+        let global = TestGlobal::new_with_driver_id(log_name);
+        let tokens = lex_insecure(b"create space myspace").unwrap();
+        let create_space: CreateSpace = ast::parse_ast_node_full(&tokens[2..]).unwrap();
+        Space::transactional_exec_create(&global, create_space).unwrap();
+        let tokens =
+            lex_insecure(b"create model myspace.mymodel(username: string, password: binary)")
+                .unwrap();
+        let create_model: CreateModel = ast::parse_ast_node_full(&tokens[2..]).unwrap();
+        ModelData::transactional_exec_create(&global, create_model).unwrap();
+    }
+    let mut events = vec![];
+    decode_gns(log_name, |event| events.push(event)).unwrap();
+    let events: Vec<_> = events
+        .iter()
+        .map(|ev| (ev.event, ev.entity.as_deref(), ev.checksum_ok))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            ("create_space", Some("myspace"), true),
+            ("create_model", Some("myspace.mymodel"), true),
+            ("driver_closed", None, true),
+        ]
+    );
+}
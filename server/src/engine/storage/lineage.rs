@@ -0,0 +1,307 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Server lineage
+//!
+//! The lineage file records every server version that has written to a data directory, oldest first. We use it at
+//! startup to refuse to run on a data directory that was last written by a newer (and incompatible) version since
+//! attempting to do so might silently corrupt data
+
+use {
+    super::{
+        common::{checksum::SCrc64, sdss::sdss_r1::rw::SdssFile, versions},
+        v2::raw::spec::ServerLineageV1,
+    },
+    crate::{
+        engine::{
+            error::{ErrorKind, StorageError},
+            RuntimeResult,
+        },
+        util::os,
+    },
+};
+
+pub const LINEAGE_PATH: &str = "lineage.db";
+
+/*
+    entry
+*/
+
+#[derive(Debug, PartialEq, Clone)]
+/// A single server version that wrote to the data directory
+pub struct LineageEntry {
+    version: Box<str>,
+    driver_version: u64,
+    epoch_time: u128,
+}
+
+impl LineageEntry {
+    fn new(version: &str, driver_version: u64, epoch_time: u128) -> Self {
+        Self {
+            version: version.into(),
+            driver_version,
+            epoch_time,
+        }
+    }
+    /// The entry for the currently running server
+    fn current() -> Self {
+        Self::new(
+            libsky::VERSION,
+            u64::from_le_bytes(versions::v2::V2_DRIVER_VERSION.little_endian()),
+            os::get_epoch_time(),
+        )
+    }
+    fn is_same_version(&self, other: &Self) -> bool {
+        (self.version == other.version) & (self.driver_version == other.driver_version)
+    }
+}
+
+/// Returns the `(major, minor, patch)` triple for a version string like `0.8.1` or `0.8.1-beta.1`
+fn parse_version(v: &str) -> Option<(u64, u64, u64)> {
+    let v = v.split(['-', '+']).next()?;
+    let mut parts = v.split('.').map(str::parse::<u64>);
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Some((major, minor, patch)),
+        _ => None,
+    }
+}
+
+/// Check if the currently running server (`current`) can safely use a data directory last written by `on_disk`.
+/// Downgrades across patch releases are fine, but anything else isn't
+fn check_compatible(on_disk: &LineageEntry, current: &LineageEntry) -> Result<(), String> {
+    let newer_driver = on_disk.driver_version > current.driver_version;
+    let newer_release = match (
+        parse_version(&on_disk.version),
+        parse_version(&current.version),
+    ) {
+        (Some((od_major, od_minor, _)), Some((c_major, c_minor, _))) => {
+            (od_major, od_minor) > (c_major, c_minor)
+        }
+        // we can't tell, so just go by the driver
+        _ => false,
+    };
+    if newer_driver | newer_release {
+        Err(format!(
+            "the data directory was last written by Skytable v{} (storage driver {}) but this is Skytable v{} (storage driver {}) which cannot safely read it. \
+            to migrate back, start Skytable v{} on this data directory, export your data and import it into a fresh data directory using this version. \
+            if you know what you're doing, you can skip this check with --force-downgrade-check-off",
+            on_disk.version, on_disk.driver_version, current.version, current.driver_version, on_disk.version,
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/*
+    lineage
+*/
+
+#[derive(Debug, PartialEq, Default)]
+/// The server version history for a data directory
+pub struct Lineage {
+    entries: Vec<LineageEntry>,
+}
+
+impl Lineage {
+    /// The most recent server version that wrote to the data directory
+    pub fn last(&self) -> Option<&LineageEntry> {
+        self.entries.last()
+    }
+    /*
+        encoding:
+        [entry count: 8B]
+        entry: [epoch time: 16B][driver version: 8B][version length: 8B][version]
+        [checksum: 8B]
+    */
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend((self.entries.len() as u64).to_le_bytes());
+        for entry in &self.entries {
+            buf.extend(entry.epoch_time.to_le_bytes());
+            buf.extend(entry.driver_version.to_le_bytes());
+            buf.extend((entry.version.len() as u64).to_le_bytes());
+            buf.extend(entry.version.as_bytes());
+        }
+        let mut checksum = SCrc64::new();
+        checksum.update(&buf);
+        buf.extend(checksum.finish().to_le_bytes());
+        buf
+    }
+    fn decode(buf: &[u8]) -> Option<Self> {
+        let (payload, checksum) = buf.split_at_checked(buf.len().checked_sub(sizeof!(u64))?)?;
+        let mut scrc = SCrc64::new();
+        scrc.update(payload);
+        if scrc.finish() != u64::from_le_bytes(checksum.try_into().ok()?) {
+            return None;
+        }
+        fn take<'a>(buf: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+            let (head, tail) = buf.split_at_checked(n)?;
+            *buf = tail;
+            Some(head)
+        }
+        fn take_u64(buf: &mut &[u8]) -> Option<u64> {
+            take(buf, sizeof!(u64)).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        }
+        let mut payload = payload;
+        let count = take_u64(&mut payload)?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let epoch_time =
+                u128::from_le_bytes(take(&mut payload, sizeof!(u128))?.try_into().ok()?);
+            let driver_version = take_u64(&mut payload)?;
+            let version_len = take_u64(&mut payload)? as usize;
+            let version = core::str::from_utf8(take(&mut payload, version_len)?).ok()?;
+            entries.push(LineageEntry::new(version, driver_version, epoch_time));
+        }
+        if payload.is_empty() {
+            Some(Self { entries })
+        } else {
+            None
+        }
+    }
+    /// Read the lineage at the given path, returning [`None`] if there isn't one
+    fn read(path: &str) -> RuntimeResult<Option<Self>> {
+        let mut f = match SdssFile::<ServerLineageV1>::open(path) {
+            Ok(f) => f,
+            Err(e) if matches!(e.kind(), ErrorKind::IoError(io) if io.kind() == std::io::ErrorKind::NotFound) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        };
+        match Self::decode(&f.read_full()?) {
+            Some(lineage) => Ok(Some(lineage)),
+            None => Err(StorageError::LineageCorrupted.into()),
+        }
+    }
+    /// Atomically replace the lineage at the given path
+    fn write(&self, path: &str) -> RuntimeResult<()> {
+        let (mut f, tmp) = SdssFile::<ServerLineageV1>::create_temp(path)?;
+        f.fsynced_write(&self.encode())?;
+        drop(f);
+        tmp.commit()?;
+        Ok(())
+    }
+    /// Add the currently running server to the lineage (unless it was also the last one to write to this data directory)
+    /// and persist it
+    pub fn record(mut self, path: &str) -> RuntimeResult<()> {
+        let current = LineageEntry::current();
+        if self
+            .last()
+            .is_some_and(|last| last.is_same_version(&current))
+        {
+            return Ok(());
+        }
+        self.entries.push(current);
+        self.write(path)
+    }
+}
+
+/// Load the lineage for the data directory and verify that it is safe for this server to use it. If `force` is set, then
+/// the check is skipped (and a corrupted lineage is discarded)
+pub fn verify(path: &str, force: bool) -> RuntimeResult<Lineage> {
+    let lineage = match Lineage::read(path) {
+        Ok(lineage) => lineage.unwrap_or_default(),
+        Err(e) if force => {
+            warn!(
+                "discarding unreadable server lineage since the downgrade check is disabled: {e}"
+            );
+            return Ok(Lineage::default());
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(last) = lineage.last() {
+        match check_compatible(last, &LineageEntry::current()) {
+            Ok(()) => {}
+            Err(e) if force => warn!("ignoring failed downgrade check: {e}"),
+            Err(e) => return Err(ErrorKind::Other(e).into()),
+        }
+    }
+    Ok(lineage)
+}
+
+#[test]
+fn lineage_version_compat() {
+    let current = LineageEntry::new("0.8.2", 1, 0);
+    for ok in [
+        "0.8.2",
+        "0.8.0",
+        "0.8.9",
+        "0.7.1",
+        "0.8.3-beta.1",
+        "nightly",
+    ] {
+        assert!(
+            check_compatible(&LineageEntry::new(ok, 1, 0), &current).is_ok(),
+            "{ok}"
+        );
+    }
+    for bad in ["0.9.0", "1.0.0", "0.9.0-alpha.1"] {
+        assert!(
+            check_compatible(&LineageEntry::new(bad, 1, 0), &current).is_err(),
+            "{bad}"
+        );
+    }
+    // newer driver, same release
+    assert!(check_compatible(&LineageEntry::new("0.8.2", 2, 0), &current).is_err());
+}
+
+#[test]
+fn lineage_read_write() {
+    const PATH: &str = "lineage_read_write.db";
+    assert_eq!(Lineage::read(PATH).unwrap(), None);
+    let lineage = verify(PATH, false).unwrap();
+    lineage.record(PATH).unwrap();
+    let lineage = Lineage::read(PATH).unwrap().unwrap();
+    assert_eq!(lineage.entries.len(), 1);
+    assert!(lineage
+        .last()
+        .unwrap()
+        .is_same_version(&LineageEntry::current()));
+    // restarting with the same version doesn't add a new entry
+    verify(PATH, false).unwrap().record(PATH).unwrap();
+    assert_eq!(Lineage::read(PATH).unwrap().unwrap(), lineage);
+    // a newer version wrote to the data directory
+    let mut newer = lineage;
+    newer.entries.push(LineageEntry::new("99.0.0", 1, 0));
+    newer.write(PATH).unwrap();
+    assert!(verify(PATH, false).is_err());
+    assert_eq!(verify(PATH, true).unwrap(), newer);
+}
+
+#[test]
+fn lineage_corrupted() {
+    let lineage = Lineage {
+        entries: vec![
+            LineageEntry::new("0.8.0", 1, 10),
+            LineageEntry::new("0.8.1", 1, 20),
+        ],
+    };
+    let mut encoded = lineage.encode();
+    assert_eq!(Lineage::decode(&encoded).unwrap(), lineage);
+    encoded[10] ^= 1;
+    assert_eq!(Lineage::decode(&encoded), None);
+    assert_eq!(Lineage::decode(&[]), None);
+}
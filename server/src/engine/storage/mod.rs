@@ -27,20 +27,33 @@
 //! Implementations of the Skytable Disk Storage Subsystem (SDSS)
 
 use {
+    self::common::interface::fs::FileSystem,
     super::{config::Configuration, core::GlobalNS, fractal::context, RuntimeResult},
-    std::path::Path,
 };
 
+pub mod check;
 mod common;
 mod common_encoding;
+pub mod decode;
+mod lineage;
+mod progress;
+pub mod replay;
+pub mod trash;
 // driver versions
 pub mod v1;
 pub mod v2;
 
 pub mod safe_interfaces {
+    #[cfg(any(test, feature = "fault-injection"))]
+    pub use super::common::interface::vfs::{Fault, VirtualFS};
     pub use super::{
-        common::{interface::fs::FileSystem, paths_v1},
-        v2::impls::mdl_journal::StdModelBatch,
+        common::{
+            checksum::SCrc64,
+            interface::fs::{FSContext, FSScope, FileSystem},
+            paths_v1,
+            sdss::sdss_r1::rw::set_prealloc_chunk_size,
+        },
+        v2::impls::mdl_journal::{StdModelBatch, TruncateModelBatch},
     };
 }
 
@@ -58,8 +71,18 @@ pub struct SELoaded {
 }
 
 pub fn load(cfg: &Configuration) -> RuntimeResult<SELoaded> {
+    // make sure that we aren't touching data written by a newer version
+    context::set_dmsg("verifying server lineage");
+    let lineage = lineage::verify(lineage::LINEAGE_PATH, cfg.system.force_downgrade_check_off)?;
+    let loaded = load_data(cfg)?;
+    context::set_dmsg("updating server lineage");
+    lineage.record(lineage::LINEAGE_PATH)?;
+    Ok(loaded)
+}
+
+fn load_data(cfg: &Configuration) -> RuntimeResult<SELoaded> {
     // first determine if this is a new install, an existing install or if it uses the old driver
-    if Path::new(v1::SYSDB_PATH).is_file() {
+    if FileSystem::is_file(v1::SYSDB_PATH) {
         warn!("older storage format detected");
         // this is an old install
         info!("loading data");
@@ -69,7 +92,7 @@ pub fn load(cfg: &Configuration) -> RuntimeResult<SELoaded> {
         context::set_dmsg("upgrading storage-v1 to storage-v2 format");
         return v2::recreate(gns);
     }
-    if !Path::new(v2::GNS_PATH).is_file() {
+    if !FileSystem::is_file(v2::GNS_PATH) {
         info!("initializing databases");
         context::set_dmsg("creating databases");
         // this is a new install
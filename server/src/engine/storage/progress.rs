@@ -0,0 +1,158 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Restore progress
+//!
+//! Restoring a large data directory can take a while, so we periodically log how far along we are: the model being
+//! restored, how many models are done, the number of events replayed so far and an estimate of the time left. The
+//! same summary is sent to the service manager (if any) as our status
+
+use {
+    crate::util::os,
+    std::time::{Duration, Instant},
+};
+
+/// How often we report progress
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub struct RestoreProgress {
+    models_total: usize,
+    models_done: usize,
+    /// the model being restored and how much of it (`[0, 1]`) has been restored
+    current: Option<(String, f64)>,
+    events: u64,
+    started: Instant,
+    last_report: Instant,
+}
+
+impl RestoreProgress {
+    pub fn new(models_total: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            models_total,
+            models_done: 0,
+            current: None,
+            events: 0,
+            started: now,
+            last_report: now,
+        }
+    }
+    /// Start restoring the given model
+    pub fn begin_model(&mut self, model: String) {
+        self.current = Some((model, 0.0));
+        self.report_if_due();
+    }
+    /// The current model has `consumed` of its `len` bytes restored, after replaying another `events` events
+    pub fn advance(&mut self, consumed: u64, len: u64, events: u64) {
+        self.events += events;
+        if let Some((_, done)) = self.current.as_mut() {
+            *done = if len == 0 {
+                1.0
+            } else {
+                (consumed as f64 / len as f64).min(1.0)
+            };
+        }
+        self.report_if_due();
+    }
+    /// The current model has been restored
+    pub fn finish_model(&mut self) {
+        self.current = None;
+        self.models_done += 1;
+    }
+    /// Everything has been restored
+    pub fn finish(self) {
+        if self.models_total != 0 {
+            info!(
+                "restored {} models ({} events replayed) in {:.1}s",
+                self.models_total,
+                self.events,
+                self.started.elapsed().as_secs_f64()
+            );
+        }
+    }
+    /// How much of the restore is done (`[0, 1]`)
+    fn fraction_done(&self) -> f64 {
+        if self.models_total == 0 {
+            return 1.0;
+        }
+        let current = self.current.as_ref().map_or(0.0, |(_, done)| *done);
+        (self.models_done as f64 + current) / self.models_total as f64
+    }
+    /// Estimate the time left, assuming that the rest goes as fast as what we've restored so far
+    fn eta(&self, elapsed: Duration) -> Option<Duration> {
+        let done = self.fraction_done();
+        if done == 0.0 {
+            return None;
+        }
+        Some(elapsed.mul_f64((1.0 - done) / done))
+    }
+    fn report_if_due(&mut self) {
+        if self.last_report.elapsed() < REPORT_INTERVAL {
+            return;
+        }
+        self.last_report = Instant::now();
+        let status = self.status(self.started.elapsed());
+        info!("{status}");
+        os::sd_notify(&format!("STATUS={status}"));
+    }
+    fn status(&self, elapsed: Duration) -> String {
+        let mut status = format!(
+            "restoring data: {}/{} models done ({:.1}%), {} events replayed",
+            self.models_done,
+            self.models_total,
+            self.fraction_done() * 100.0,
+            self.events
+        );
+        if let Some((model, _)) = self.current.as_ref() {
+            status.push_str(&format!(", now restoring {model}"));
+        }
+        match self.eta(elapsed) {
+            Some(eta) => status.push_str(&format!(", ETA {}s", eta.as_secs())),
+            None => status.push_str(", ETA unknown"),
+        }
+        status
+    }
+}
+
+#[test]
+fn restore_progress_status() {
+    let mut progress = RestoreProgress::new(4);
+    assert_eq!(
+        progress.status(Duration::from_secs(1)),
+        "restoring data: 0/4 models done (0.0%), 0 events replayed, ETA unknown"
+    );
+    progress.begin_model("myspace.a".into());
+    progress.advance(512, 1024, 100);
+    progress.finish_model();
+    progress.begin_model("myspace.b".into());
+    // 1.5 of 4 models in 30 seconds
+    progress.advance(256, 512, 50);
+    assert_eq!(
+        progress.status(Duration::from_secs(30)),
+        "restoring data: 1/4 models done (37.5%), 150 events replayed, now restoring myspace.b, ETA 50s"
+    );
+}
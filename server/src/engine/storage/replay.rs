@@ -0,0 +1,151 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Replay a data directory up to a given event into a scratch directory (`skyd replay`)
+//!
+//! The data directory is copied as is and the journals in the copy are then cut off right after the requested events.
+//! The copy is finally put in read-only mode, so that a server can be started over it to look at the data as it was at
+//! that point. Nothing in the original data directory is modified
+
+use {
+    super::{
+        common::paths_v1,
+        v1,
+        v2::{
+            self,
+            impls::{gns_log::GNSDriver, mdl_journal::ModelDriver},
+        },
+    },
+    crate::engine::{
+        core::{EntityIDRef, GNSData},
+        txn::gns::sysctl::SetReadOnlyTxn,
+    },
+    std::{fs, io, path::Path},
+};
+
+#[derive(Debug, Default, PartialEq)]
+/// The events at which the journals are cut off
+pub struct ReplayPoint {
+    /// the last GNS event to keep (the full GNS is kept if this is not set)
+    pub gns_until: Option<u64>,
+    /// the last data event (batch) to keep for each model (`space.model`)
+    pub models: Vec<(String, u64)>,
+}
+
+/// Copy the data directory in the current working directory into `scratch` (which must not exist yet) and replay it up
+/// to the given point. On success, the current working directory is changed to `scratch`
+pub fn replay_into(scratch: &str, point: &ReplayPoint) -> Result<(), String> {
+    if Path::new(v1::SYSDB_PATH).is_file() {
+        return Err(
+            "data directory uses the older storage format. start the server once to upgrade it"
+                .into(),
+        );
+    }
+    if !Path::new(v2::GNS_PATH).is_file() {
+        return Err("GNS is missing. not a data directory".into());
+    }
+    if Path::new(crate::SKY_PID_FILE).is_file() {
+        warn!("the server may be running. the copy may be inconsistent");
+    }
+    if Path::new(scratch).exists() {
+        return Err(format!("scratch directory `{scratch}` already exists"));
+    }
+    fs::create_dir_all(scratch)
+        .and_then(|_| fs::canonicalize(scratch))
+        .and_then(|scratch_abs| copy_dir(Path::new("."), &scratch_abs, &scratch_abs))
+        .map_err(|e| format!("failed to copy data directory into `{scratch}`: {e}"))?;
+    std::env::set_current_dir(scratch)
+        .map_err(|e| format!("failed to open scratch directory `{scratch}`: {e}"))?;
+    // GNS
+    let gns = GNSData::empty();
+    match point.gns_until {
+        Some(until) => {
+            let last = GNSDriver::truncate_gns(&gns, until).map_err(|e| e.to_string())?;
+            info!("replayed GNS up to event {last}");
+        }
+        None => GNSDriver::verify_gns(&gns).map_err(|e| e.to_string())?,
+    }
+    for (space_name, space) in gns.idx().read().iter() {
+        if space.storage_path().is_some() {
+            // the copy would still point to the original files
+            return Err(format!(
+                "space `{space_name}` is stored outside the data directory and can't be replayed"
+            ));
+        }
+    }
+    // data
+    for (model_name, until) in point.models.iter() {
+        let Some((space_name, entity_name)) = model_name.split_once('.') else {
+            return Err(format!(
+                "invalid model `{model_name}`. expected `space.model`"
+            ));
+        };
+        let models = gns.idx_models().read();
+        let (Some(model), Some(space)) = (
+            models.get(&EntityIDRef::new(space_name, entity_name)),
+            gns.idx()
+                .read()
+                .get(space_name)
+                .map(|space| space.get_uuid()),
+        ) else {
+            return Err(format!(
+                "model `{model_name}` doesn't exist at this point in the GNS"
+            ));
+        };
+        let model_path =
+            paths_v1::model_path(space_name, space, entity_name, model.data().get_uuid());
+        let last = ModelDriver::truncate_model_driver(model.data(), &model_path, *until)
+            .map_err(|e| format!("{model_path}: {e}"))?;
+        info!("replayed data for `{model_name}` up to event {last}");
+    }
+    // make sure nothing is written to the copy by accident
+    let mut gns_driver = GNSDriver::open_gns(&GNSData::empty()).map_err(|e| e.to_string())?;
+    gns_driver
+        .commit_event(SetReadOnlyTxn::new(true))
+        .and_then(|_| GNSDriver::close_driver(&mut gns_driver))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Recursively copy `src` into `dst`, skipping `skip` (the scratch directory may be inside the data directory) and the
+/// PID file
+fn copy_dir(src: &Path, dst: &Path, skip: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        if fs::canonicalize(&path)? == skip || entry.file_name() == crate::SKY_PID_FILE {
+            continue;
+        }
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir(&target)?;
+            copy_dir(&path, &target, skip)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
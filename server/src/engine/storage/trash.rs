@@ -0,0 +1,277 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Model trash
+//!
+//! If a trash retention is configured, a dropped model isn't deleted right away. Instead, a compacted copy of its rows
+//! (which is just a regular model data file) is written to the trash directory of its space along with a metadata file
+//! that holds the definition of the model and the time at which it was dropped. The copy only needs the definition that
+//! the model had when it was dropped, so restoring it doesn't depend on the model's schema history.
+//!
+//! Every entry is stored as `mdl_<model>-<uuid>.db-btlog` (data) and `mdl_<model>-<uuid>.meta` (metadata), with the
+//! UUID that the model had when it was dropped. The metadata file is written last, so an entry without one is ignored
+
+use {
+    super::{
+        common::{
+            checksum::SCrc64, interface::fs::FileSystem, paths_v1, sdss::sdss_r1::rw::SdssFile,
+        },
+        common_encoding::r1::{dec, enc, obj::ModelLayoutRef},
+        v2::{
+            impls::mdl_journal::{BatchStats, FullModel, ModelDriver},
+            raw::spec::TrashMetaV1,
+        },
+    },
+    crate::engine::{
+        core::model::ModelData, data::uuid::Uuid, error::StorageError, mem::BufferedScanner,
+        RuntimeResult,
+    },
+    std::io::ErrorKind as IoErrorKind,
+};
+
+const EXT_DATA: &str = ".db-btlog";
+const EXT_META: &str = ".meta";
+/// length of a (hyphenated) UUID in an entry's name
+const UUID_STR_LEN: usize = 36;
+
+#[derive(Debug, PartialEq)]
+/// A dropped model in the trash of a space
+pub struct TrashEntry {
+    /// path of the entry, without the extension
+    stem: String,
+    dropped_at: u64,
+}
+
+impl TrashEntry {
+    fn new(stem: String, dropped_at: u64) -> Self {
+        Self { stem, dropped_at }
+    }
+    /// Time (in seconds since the epoch) at which the model was dropped
+    pub fn dropped_at(&self) -> u64 {
+        self.dropped_at
+    }
+    fn data_path(&self) -> String {
+        format!("{}{EXT_DATA}", self.stem)
+    }
+    fn meta_path(&self) -> String {
+        format!("{}{EXT_META}", self.stem)
+    }
+    /*
+        encoding:
+        [dropped at: 8B][model layout]
+        [checksum: 8B]
+    */
+    fn encode_meta(dropped_at: u64, model: &ModelData) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend(dropped_at.to_le_bytes());
+        enc::full_into_buffer::<ModelLayoutRef>(&mut buf, ModelLayoutRef::from(model));
+        let mut checksum = SCrc64::new();
+        checksum.update(&buf);
+        buf.extend(checksum.finish().to_le_bytes());
+        buf
+    }
+    fn decode_meta(buf: &[u8]) -> RuntimeResult<(u64, ModelData)> {
+        if buf.len() < sizeof!(u64, 2) {
+            return Err(StorageError::TrashCorrupted.into());
+        }
+        let (payload, checksum) = buf.split_at(buf.len() - sizeof!(u64));
+        let mut scrc = SCrc64::new();
+        scrc.update(payload);
+        if scrc.finish() != u64::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(StorageError::TrashCorrupted.into());
+        }
+        let (dropped_at, layout) = payload.split_at(sizeof!(u64));
+        let mut scanner = BufferedScanner::new(layout);
+        let model = dec::full_from_scanner::<ModelLayoutRef>(&mut scanner)?;
+        if !scanner.eof() {
+            return Err(StorageError::TrashCorrupted.into());
+        }
+        Ok((u64::from_le_bytes(dropped_at.try_into().unwrap()), model))
+    }
+    fn read_meta(&self) -> RuntimeResult<(u64, ModelData)> {
+        let mut f = SdssFile::<TrashMetaV1>::open(&self.meta_path())?;
+        Self::decode_meta(&f.read_full()?)
+    }
+    /// Load the definition of the model (without any rows)
+    pub fn read_model(&self) -> RuntimeResult<ModelData> {
+        self.read_meta().map(|(_, model)| model)
+    }
+    /// Move the data file back into the directory of the restored `model` and load its rows. The entry stays in the
+    /// trash until [`Self::finish_restore`] is called (or [`Self::cancel_restore`] puts the data file back)
+    pub fn restore(
+        &self,
+        space_name: &str,
+        space_uuid: Uuid,
+        model_name: &str,
+        model: &ModelData,
+    ) -> RuntimeResult<ModelDriver> {
+        let model_uuid = model.get_uuid();
+        FileSystem::create_dir_all(&paths_v1::model_dir(
+            space_name, space_uuid, model_name, model_uuid,
+        ))?;
+        let model_path = paths_v1::model_path(space_name, space_uuid, model_name, model_uuid);
+        if let Err(e) = FileSystem::rename(&self.data_path(), &model_path) {
+            self.unrestore(space_name, space_uuid, model_name, model_uuid);
+            return Err(e.into());
+        }
+        ModelDriver::open_model_driver(model, &model_path).inspect_err(|_| {
+            self.unrestore(space_name, space_uuid, model_name, model_uuid);
+        })
+    }
+    /// Put the data file back into the trash if the restore couldn't be completed
+    pub fn cancel_restore(
+        &self,
+        driver: ModelDriver,
+        space_name: &str,
+        space_uuid: Uuid,
+        model_name: &str,
+        model_uuid: Uuid,
+    ) {
+        // release the data file before we move it
+        drop(driver);
+        self.unrestore(space_name, space_uuid, model_name, model_uuid)
+    }
+    fn unrestore(&self, space_name: &str, space_uuid: Uuid, model_name: &str, model_uuid: Uuid) {
+        let _ = FileSystem::rename(
+            &paths_v1::model_path(space_name, space_uuid, model_name, model_uuid),
+            &self.data_path(),
+        );
+        let _ = FileSystem::remove_dir_all(&paths_v1::model_dir(
+            space_name, space_uuid, model_name, model_uuid,
+        ));
+    }
+    /// Remove the (now empty) entry once the model has been restored
+    pub fn finish_restore(self) -> RuntimeResult<()> {
+        FileSystem::remove_file(&self.meta_path()).map_err(Into::into)
+    }
+    /// Delete the entry along with its data
+    pub fn discard(&self) -> RuntimeResult<()> {
+        // the metadata file goes first so that a half deleted entry is never listed
+        for path in [self.meta_path(), self.data_path()] {
+            match FileSystem::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == IoErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a copy of the model (as of now) to the trash of its space
+pub fn trash_model(
+    space_name: &str,
+    space_uuid: Uuid,
+    model_name: &str,
+    model: &ModelData,
+    dropped_at: u64,
+) -> RuntimeResult<TrashEntry> {
+    let trash_dir = paths_v1::trash_dir(space_name, space_uuid);
+    FileSystem::create_dir_all(&trash_dir)?;
+    let entry = TrashEntry::new(
+        format!("{trash_dir}/mdl_{model_name}-{}", model.get_uuid()),
+        dropped_at,
+    );
+    match write_entry(&entry, model) {
+        Ok(()) => Ok(entry),
+        Err(e) => {
+            let _ = entry.discard();
+            Err(e)
+        }
+    }
+}
+
+fn write_entry(entry: &TrashEntry, model: &ModelData) -> RuntimeResult<()> {
+    let mut drv = ModelDriver::create_model_driver(&entry.data_path())?;
+    {
+        // the row count is written ahead of the rows, so block inserts and deletes while we're at it
+        let _idx_latch = model.primary_index().acquire_exclusive();
+        drv.commit_with_ctx(FullModel::new(model), BatchStats::new())?;
+    }
+    ModelDriver::close_driver(&mut drv)?;
+    // release the data file before the entry is listed
+    drop(drv);
+    let (mut f, tmp) = SdssFile::<TrashMetaV1>::create_temp(&entry.meta_path())?;
+    f.fsynced_write(&TrashEntry::encode_meta(entry.dropped_at, model))?;
+    drop(f);
+    tmp.commit()?;
+    Ok(())
+}
+
+/// Returns the entries in the trash of the given space, oldest first. If a model name is given, only the entries
+/// for that model are returned. Entries that can't be read are skipped
+pub fn list(
+    space_name: &str,
+    space_uuid: Uuid,
+    model_name: Option<&str>,
+) -> RuntimeResult<Vec<TrashEntry>> {
+    let trash_dir = paths_v1::trash_dir(space_name, space_uuid);
+    let names = match FileSystem::read_dir(&trash_dir) {
+        Ok(names) => names,
+        Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+    let prefix = model_name.map(|model_name| format!("mdl_{model_name}-"));
+    let mut entries = vec![];
+    for name in names {
+        let Some(stem) = name.strip_suffix(EXT_META) else {
+            continue;
+        };
+        if let Some(prefix) = prefix.as_deref() {
+            // the rest is the UUID
+            if stem
+                .strip_prefix(prefix)
+                .is_none_or(|uuid| uuid.len() != UUID_STR_LEN)
+            {
+                continue;
+            }
+        }
+        let mut entry = TrashEntry::new(format!("{trash_dir}/{stem}"), 0);
+        match entry.read_meta() {
+            Ok((dropped_at, _)) => {
+                entry.dropped_at = dropped_at;
+                entries.push(entry);
+            }
+            Err(e) => warn!("skipping unreadable entry {trash_dir}/{name} in trash: {e}"),
+        }
+    }
+    entries.sort_by_key(TrashEntry::dropped_at);
+    Ok(entries)
+}
+
+/// Delete every entry in the trash of the given space that was dropped at or before `cutoff` (in seconds since the
+/// epoch). Returns the number of entries that were deleted
+pub fn purge(space_name: &str, space_uuid: Uuid, cutoff: u64) -> RuntimeResult<usize> {
+    let mut purged = 0;
+    for entry in list(space_name, space_uuid, None)? {
+        if entry.dropped_at() > cutoff {
+            break;
+        }
+        entry.discard()?;
+        purged += 1;
+    }
+    Ok(purged)
+}
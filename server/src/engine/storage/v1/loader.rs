@@ -31,6 +31,7 @@ use {
         fractal::{error::ErrorContext, ModelUniqueID},
         storage::{
             common::paths_v1,
+            progress::RestoreProgress,
             v1::raw::{
                 batch_jrnl,
                 journal::{raw as raw_journal, GNSAdapter},
@@ -46,11 +47,13 @@ pub fn load_gns() -> RuntimeResult<GNSData> {
     let gns_txn_driver =
         raw_journal::load_journal::<GNSAdapter, spec::GNSTransactionLogV1>(super::GNS_PATH, &gns)?;
     let mut model_drivers = HashMap::new();
+    let mut progress = RestoreProgress::new(gns.idx_models().read().len());
     let mut driver_guard = || {
         let mut models = gns.idx_models().write();
         // this is an existing instance, so read in all data
         for (space_name, space) in gns.idx().read().iter() {
             let space_uuid = space.get_uuid();
+            space.register_storage_path();
             for model_name in space.models().iter() {
                 let model = models
                     .get_mut(&EntityIDRef::new(&space_name, &model_name))
@@ -61,9 +64,12 @@ pub fn load_gns() -> RuntimeResult<GNSData> {
                     model_name,
                     model.data().get_uuid(),
                 );
-                let persist_driver = batch_jrnl::reinit(&path, model.data()).inherit_set_dmsg(
-                    format!("failed to restore model data from journal in `{path}`"),
-                )?;
+                progress.begin_model(format!("{space_name}.{model_name}"));
+                let persist_driver = batch_jrnl::reinit(&path, model.data(), &mut progress)
+                    .inherit_set_dmsg(format!(
+                        "failed to restore model data from journal in `{path}`"
+                    ))?;
+                progress.finish_model();
                 unsafe {
                     // UNSAFE(@ohsayan): all pieces of data are upgraded by now, so vacuum
                     model.data_mut().model_mutator().vacuum_stashed();
@@ -88,5 +94,6 @@ pub fn load_gns() -> RuntimeResult<GNSData> {
     for (_, driver) in model_drivers {
         driver.close().unwrap();
     }
+    progress.finish();
     Ok(gns)
 }
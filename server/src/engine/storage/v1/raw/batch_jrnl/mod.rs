@@ -42,14 +42,20 @@ pub use {persist::DataBatchPersistDriver, restore::DataBatchRestoreDriver};
 
 use {
     super::{rw::SDSSFileIO, spec},
-    crate::engine::{core::model::ModelData, error::RuntimeResult},
+    crate::engine::{
+        core::model::ModelData, error::RuntimeResult, storage::progress::RestoreProgress,
+    },
 };
 
 /// Re-initialize an existing batch journal and read all its data into model
-pub fn reinit(name: &str, model: &ModelData) -> RuntimeResult<DataBatchPersistDriver> {
+pub fn reinit(
+    name: &str,
+    model: &ModelData,
+    progress: &mut RestoreProgress,
+) -> RuntimeResult<DataBatchPersistDriver> {
     let (f, _header) = SDSSFileIO::open::<spec::DataBatchJournalV1>(name)?;
     // restore
     let mut restore_driver = DataBatchRestoreDriver::new(f)?;
-    restore_driver.read_data_batch_into_model(model)?;
+    restore_driver.read_data_batch_into_model(model, progress)?;
     DataBatchPersistDriver::new(restore_driver.into_file(), false)
 }
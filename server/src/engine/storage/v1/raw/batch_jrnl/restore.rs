@@ -37,13 +37,14 @@ use {
             },
             data::{cell::Datacell, tag::TagUnique},
             error::{RuntimeResult, StorageError},
-            idx::{MTIndex, STIndex, STIndexSeq},
+            idx::{STIndex, STIndexSeq},
             storage::{
                 common::interface::fs::File,
                 common_encoding::r1::{
                     obj::cell::{self, StorageCellTypeID},
                     DataSource,
                 },
+                progress::RestoreProgress,
                 v1::raw::rw::{SDSSFileIO, TrackedReader},
             },
         },
@@ -124,10 +125,14 @@ impl DataBatchRestoreDriver {
     pub(in crate::engine::storage::v1) fn read_data_batch_into_model(
         &mut self,
         model: &ModelData,
+        progress: &mut RestoreProgress,
     ) -> RuntimeResult<()> {
-        self.read_all_batches_and_for_each(|batch| {
+        self.read_all_batches_and_for_each(|batch, consumed, len| {
+            let events = batch.events.len() as u64;
             // apply the batch
-            Self::apply_batch(model, batch)
+            Self::apply_batch(model, batch)?;
+            progress.advance(consumed, len, events);
+            Ok(())
         })
     }
 }
@@ -135,7 +140,7 @@ impl DataBatchRestoreDriver {
 impl DataBatchRestoreDriver {
     fn read_all_batches_and_for_each(
         &mut self,
-        mut f: impl FnMut(NormalBatch) -> RuntimeResult<()>,
+        mut f: impl FnMut(NormalBatch, u64, u64) -> RuntimeResult<()>,
     ) -> RuntimeResult<()> {
         // begin
         let mut closed = false;
@@ -171,8 +176,8 @@ impl DataBatchRestoreDriver {
                 self.attempt_recover_data_batch()?;
                 continue;
             }
-            f(batch)?;
             // apply the batch
+            f(batch, self.f.cursor(), self.f.file_len())?;
         }
         if closed {
             if self.f.is_eof() {
@@ -248,6 +253,8 @@ impl DataBatchRestoreDriver {
                                 data,
                                 DeltaVersion::__new(schema_version),
                                 txn_id,
+                                // rows never expired back then
+                                0,
                             );
                             // resolve any deltas
                             let _ = row.resolve_schema_deltas_and_freeze(m.delta_state());
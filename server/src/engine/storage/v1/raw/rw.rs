@@ -80,6 +80,13 @@ impl TrackedReader {
     pub fn remaining(&self) -> u64 {
         self.len - self.cursor
     }
+    /// Returns the position of the cursor in the file
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+    pub fn file_len(&self) -> u64 {
+        self.len
+    }
     pub fn is_eof(&self) -> bool {
         self.len == self.cursor
     }
@@ -33,17 +33,20 @@ use {
         engine::{
             core::GNSData,
             storage::{
-                common_encoding::r1::impls::gns::GNSEvent,
+                common_encoding::r1::impls::gns::{EventSummary, GNSEvent},
                 v2::raw::journal::{self, EventLogDriver, JournalAdapterEvent},
             },
             txn::gns::{
                 model::{
-                    AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateModelTxn,
-                    DropModelTxn,
+                    AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateIndexTxn,
+                    CreateModelTxn, DropIndexTxn, DropModelTxn,
                 },
                 space::{AlterSpaceTxn, CreateSpaceTxn, DropSpaceTxn},
-                sysctl::{AlterUserTxn, CreateUserTxn, DropUserTxn},
-                GNSTransaction, GNSTransactionCode,
+                sysctl::{
+                    AlterUserLimitsTxn, AlterUserTxn, CreateUserTxn, DropUserTxn, SetReadOnlyTxn,
+                },
+                task::{AlterTaskTxn, CreateTaskTxn, DropTaskTxn},
+                BatchTxn, GNSTransaction, GNSTransactionCode,
             },
             RuntimeResult,
         },
@@ -61,12 +64,26 @@ pub struct GNSEventLog;
 
 impl GNSDriver {
     const FILE_PATH: &'static str = "gns.db-tlog";
+    /// Open the event log, moving it to the current revision once it has been read (an older revision only lacks
+    /// some of the events, so it is always safe to append to it)
     pub fn open_gns_with_name(name: &str, gs: &GNSData) -> RuntimeResult<Self> {
-        journal::open_journal(name, gs)
+        journal::open_and_upgrade_journal(name, gs)
     }
     pub fn open_gns(gs: &GNSData) -> RuntimeResult<Self> {
         Self::open_gns_with_name(Self::FILE_PATH, gs)
     }
+    /// Load the GNS into the given state and validate it, without opening it for writing
+    pub fn verify_gns(gs: &GNSData) -> RuntimeResult<()> {
+        journal::verify_journal::<EventLogAdapter<GNSEventLog>>(Self::FILE_PATH, gs).map(|_| ())
+    }
+    /// Load the GNS into the given state up to (and including) the event with ID `until` and cut off every event after
+    /// it. Returns the ID of the last event that was kept
+    pub fn truncate_gns(gs: &GNSData, until: u64) -> RuntimeResult<u64> {
+        Self::truncate_gns_with_name(Self::FILE_PATH, gs, until)
+    }
+    pub fn truncate_gns_with_name(name: &str, gs: &GNSData, until: u64) -> RuntimeResult<u64> {
+        journal::truncate_journal::<EventLogAdapter<GNSEventLog>>(name, gs, until)
+    }
     pub fn create_gns_with_name(name: &str) -> RuntimeResult<Self> {
         journal::create_journal(name)
     }
@@ -76,9 +93,48 @@ impl GNSDriver {
     }
 }
 
+/// Build a table of the given [`GNSEvent`] function for every event, indexed by [`GNSTransactionCode`]
 macro_rules! make_dispatch {
-    ($($obj:ty),* $(,)?) => {
-        [$(<$obj as crate::engine::storage::common_encoding::r1::impls::gns::GNSEvent>::decode_apply),*]
+    ($f:ident) => {
+        make_dispatch!(
+            @$f:
+            CreateSpaceTxn,
+            AlterSpaceTxn,
+            DropSpaceTxn,
+            CreateModelTxn,
+            AlterModelAddTxn,
+            AlterModelRemoveTxn,
+            AlterModelUpdateTxn,
+            DropModelTxn,
+            CreateUserTxn,
+            AlterUserTxn,
+            DropUserTxn,
+            CreateTaskTxn,
+            AlterTaskTxn,
+            DropTaskTxn,
+            SetReadOnlyTxn,
+            AlterUserLimitsTxn,
+            CreateIndexTxn,
+            DropIndexTxn,
+            BatchTxn,
+        )
+    };
+    (@$f:ident: $($obj:ty),* $(,)?) => {
+        [$(<$obj as GNSEvent>::$f),*]
+    };
+}
+
+type SummarizeDispatch =
+    [fn(&[u8]) -> RuntimeResult<EventSummary>; GNSTransactionCode::VARIANT_COUNT];
+
+impl GNSEventLog {
+    const SUMMARIZE_DISPATCH: SummarizeDispatch = make_dispatch!(decode_summarize);
+    /// Decode the payload of an event and describe it (see [`GNSEvent::summarize`])
+    pub fn summarize_event(
+        code: GNSTransactionCode,
+        payload: &[u8],
+    ) -> RuntimeResult<EventSummary> {
+        Self::SUMMARIZE_DISPATCH[code.dscr_u64() as usize](payload)
     }
 }
 
@@ -88,19 +144,7 @@ impl EventLogSpec for GNSEventLog {
     type EventMeta = GNSTransactionCode;
     type DecodeDispatch =
         [fn(&GNSData, Vec<u8>) -> RuntimeResult<()>; GNSTransactionCode::VARIANT_COUNT];
-    const DECODE_DISPATCH: Self::DecodeDispatch = make_dispatch![
-        CreateSpaceTxn,
-        AlterSpaceTxn,
-        DropSpaceTxn,
-        CreateModelTxn,
-        AlterModelAddTxn,
-        AlterModelRemoveTxn,
-        AlterModelUpdateTxn,
-        DropModelTxn,
-        CreateUserTxn,
-        AlterUserTxn,
-        DropUserTxn,
-    ];
+    const DECODE_DISPATCH: Self::DecodeDispatch = make_dispatch!(decode_apply);
 }
 
 impl<T: GNSEvent> JournalAdapterEvent<EventLogAdapter<GNSEventLog>> for T {
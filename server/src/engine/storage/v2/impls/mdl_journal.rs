@@ -39,9 +39,12 @@ use {
                 tag::{DataTag, TagUnique},
             },
             error::StorageError,
-            idx::{MTIndex, STIndex, STIndexSeq},
+            idx::{STIndex, STIndexSeq},
             storage::{
-                common::sdss::sdss_r1::rw::{TrackedReaderContext, TrackedWriter},
+                common::{
+                    interface::fs::TempFile,
+                    sdss::sdss_r1::rw::{TrackedReaderContext, TrackedWriter},
+                },
                 common_encoding::r1,
                 v2::raw::{
                     journal::{
@@ -53,7 +56,7 @@ use {
             },
             RuntimeResult,
         },
-        util::{compiler::TaggedEnum, EndianQW},
+        util::{compiler::TaggedEnum, os, EndianQW},
     },
     crossbeam_epoch::{pin, Guard},
     sky_macros::TaggedEnum,
@@ -61,18 +64,94 @@ use {
         cell::RefCell,
         collections::{hash_map::Entry as HMEntry, HashMap},
         rc::Rc,
+        thread,
     },
 };
 
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 pub type ModelDriver = BatchDriver<ModelDataAdapter>;
+/// Batches with at least twice these many deltas are encoded in parallel, with each thread encoding at least these
+/// many deltas
+const PARALLEL_ENCODE_MIN_DELTAS: usize = 1024;
+/// If set, the number of threads that every batch is encoded with (irrespective of its size and the number of CPUs)
+#[cfg(test)]
+static ENCODE_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Encode every batch with the given number of threads (`1` to always encode serially and `0` to go by the batch's size)
+#[cfg(test)]
+pub fn set_encode_workers(workers: usize) {
+    ENCODE_WORKERS.store(workers, Ordering::Release)
+}
+
+/// The number of threads to encode a batch of `expected` deltas with
+fn encode_workers(expected: usize) -> usize {
+    #[cfg(test)]
+    {
+        let workers = ENCODE_WORKERS.load(Ordering::Acquire);
+        if workers != 0 {
+            return workers.min(expected);
+        }
+    }
+    os::available_cpus().min(expected / PARALLEL_ENCODE_MIN_DELTAS)
+}
+
 impl ModelDriver {
     pub fn open_model_driver(mdl: &ModelData, model_data_file_path: &str) -> RuntimeResult<Self> {
         journal::open_journal(model_data_file_path, mdl)
     }
+    /// Load the model's data and validate every batch, without opening the journal for writing
+    pub fn verify_model_driver(mdl: &ModelData, model_data_file_path: &str) -> RuntimeResult<()> {
+        journal::verify_journal::<BatchAdapter<ModelDataAdapter>>(model_data_file_path, mdl)
+            .map(|_| ())
+    }
+    /// Load the model's data up to (and including) the batch with event ID `until` and cut off every batch after it.
+    /// Returns the ID of the last event that was kept
+    pub fn truncate_model_driver(
+        mdl: &ModelData,
+        model_data_file_path: &str,
+        until: u64,
+    ) -> RuntimeResult<u64> {
+        journal::truncate_journal::<BatchAdapter<ModelDataAdapter>>(
+            model_data_file_path,
+            mdl,
+            until,
+        )
+    }
     /// Create a new event log
     pub fn create_model_driver(model_data_file_path: &str) -> RuntimeResult<Self> {
         journal::create_journal(model_data_file_path)
     }
+    /// Rewrite the model's data file into a single batch that only holds the live rows, discarding any
+    /// dead (deleted or overwritten) entries. The driver is then swapped for one that appends to the
+    /// compacted file
+    ///
+    /// NB: the driver lock must be held throughout so that no batch is written to the old file meanwhile
+    pub fn compact_model_driver(
+        me: &mut Option<Self>,
+        mdl: &ModelData,
+        model_data_file_path: &str,
+    ) -> RuntimeResult<()> {
+        let tmp = TempFile::new(model_data_file_path)?;
+        let mut compacted = Self::create_model_driver(tmp.path())?;
+        {
+            // the row count is written ahead of the rows, so block inserts and deletes while we're at it
+            let _idx_latch = mdl.primary_index().acquire_exclusive();
+            compacted.commit_with_ctx(FullModel::new(mdl), BatchStats::new())?;
+        }
+        let compacted = compacted.detach()?;
+        let current = me.as_mut().unwrap();
+        Self::close_driver(current)?;
+        if let Err(e) = tmp.commit() {
+            Self::reopen_driver(current)?;
+            return Err(e.into());
+        }
+        // release the old file before we reopen the path (which now has the compacted data)
+        *me = None;
+        *me = Some(journal::reattach_journal(model_data_file_path, compacted)?);
+        Ok(())
+    }
 }
 
 /// The model data adapter (abstract journal adapter impl)
@@ -85,6 +164,8 @@ pub struct ModelDataAdapter;
 pub enum BatchType {
     /// a standard batch (with n <= m events; n = Δdata, m = cardinality)
     Standard = 0,
+    /// all rows were removed (this batch has no events)
+    Truncate = 1,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, TaggedEnum)]
@@ -97,6 +178,8 @@ pub enum EventType {
     Update = 2,
     /// owing to inconsistent reads, we exited early
     EarlyExit = 3,
+    /// an update that only has the fields that changed
+    PartialUpdate = 4,
 }
 
 /*
@@ -107,28 +190,48 @@ pub enum EventType {
     a little messy.
 */
 
-struct RowWriter<'b> {
-    f: &'b mut TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec>,
+/// Where encoded rows go: either straight into the data file, or into a buffer that is copied into it later
+trait RowSink {
+    fn put(&mut self, buf: &[u8]) -> RuntimeResult<()>;
+}
+
+impl RowSink for TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec> {
+    fn put(&mut self, buf: &[u8]) -> RuntimeResult<()> {
+        e!(self.dtrack_write(buf))
+    }
+}
+
+impl RowSink for Vec<u8> {
+    fn put(&mut self, buf: &[u8]) -> RuntimeResult<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+struct RowWriter<
+    'b,
+    W: RowSink = TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec>,
+> {
+    f: &'b mut W,
+    /// if false, updates are always written as full rows
+    partial_updates: bool,
 }
 
-impl<'b> RowWriter<'b> {
+impl<'b, W: RowSink> RowWriter<'b, W> {
     /// write global row information:
     /// - pk tag
     /// - schema version
     /// - column count
     fn write_row_global_metadata(&mut self, model: &ModelData) -> RuntimeResult<()> {
-        self.f
-            .dtrack_write(&[model.p_tag().tag_unique().value_u8()])?;
-        self.f.dtrack_write(
+        self.f.put(&[model.p_tag().tag_unique().value_u8()])?;
+        self.f.put(
             &model
                 .delta_state()
                 .schema_current_version()
                 .value_u64()
                 .u64_bytes_le(),
         )?;
-        e!(self
-            .f
-            .dtrack_write(&(model.fields().st_len() - 1).u64_bytes_le()))
+        self.f.put(&(model.fields().st_len() - 1).u64_bytes_le())
     }
     /// write row metadata:
     /// - change type
@@ -149,9 +252,17 @@ impl<'b> RowWriter<'b> {
             }
         }
         let change_type = [change.value_u8()];
-        self.f.dtrack_write(&change_type)?;
+        self.f.put(&change_type)?;
         let txn_id = txn_id.value_u64().u64_bytes_le();
-        self.f.dtrack_write(&txn_id)?;
+        self.f.put(&txn_id)?;
+        Ok(())
+    }
+    /// write row metadata for a partial update:
+    /// - change type
+    /// - txn id
+    fn write_partial_update_metadata(&mut self, txn_id: DeltaVersion) -> RuntimeResult<()> {
+        self.f.put(&[EventType::PartialUpdate.dscr()])?;
+        self.f.put(&txn_id.value_u64().u64_bytes_le())?;
         Ok(())
     }
     /// encode the primary key only. this means NO TAG is encoded.
@@ -163,7 +274,7 @@ impl<'b> RowWriter<'b> {
                     pk.read_uint()
                 }
                 .u64_bytes_le();
-                self.f.dtrack_write(&data)?;
+                self.f.put(&data)?;
             }
             TagUnique::Str | TagUnique::Bin => {
                 let slice = unsafe {
@@ -171,8 +282,8 @@ impl<'b> RowWriter<'b> {
                     pk.read_bin()
                 };
                 let slice_l = slice.len().u64_bytes_le();
-                self.f.dtrack_write(&slice_l)?;
-                self.f.dtrack_write(slice)?;
+                self.f.put(&slice_l)?;
+                self.f.put(slice)?;
             }
             TagUnique::Illegal => unsafe {
                 // UNSAFE(@ohsayan): a pk can't be constructed with illegal
@@ -181,11 +292,18 @@ impl<'b> RowWriter<'b> {
         }
         Ok(())
     }
+    /// encode when the row was last written, but only if the model's rows expire
+    fn write_row_written_at(&mut self, model: &ModelData, row_data: &RowData) -> RuntimeResult<()> {
+        if model.ttl().is_some() {
+            self.f.put(&row_data.written_at().u64_bytes_le())?;
+        }
+        Ok(())
+    }
     /// Encode a single cell
     fn write_cell(&mut self, value: &Datacell) -> RuntimeResult<()> {
         let mut buf = vec![];
         r1::obj::cell::encode(&mut buf, value);
-        self.f.dtrack_write(&buf)?;
+        self.f.put(&buf)?;
         Ok(())
     }
     /// Encode row data
@@ -196,11 +314,107 @@ impl<'b> RowWriter<'b> {
                     self.write_cell(cell)?;
                 }
                 None if field_name.as_str() == model.p_key() => {}
-                None => self.f.dtrack_write(&[0])?,
+                None => self.f.put(&[0])?,
+            }
+        }
+        Ok(())
+    }
+    /// Encode only the given fields of the row:
+    /// - field count
+    /// - (position, cell) for every field
+    fn write_row_partial_data(
+        &mut self,
+        model: &ModelData,
+        row_data: &RowData,
+        positions: &[usize],
+    ) -> RuntimeResult<()> {
+        self.f.put(&positions.len().u64_bytes_le())?;
+        let fields = model
+            .fields()
+            .stseq_ord_key()
+            .filter(|field_name| field_name.as_str() != model.p_key())
+            .enumerate()
+            .filter(|(position, _)| positions.binary_search(position).is_ok());
+        for (position, field_name) in fields {
+            self.f.put(&position.u64_bytes_le())?;
+            match row_data.fields().get(field_name) {
+                Some(cell) => self.write_cell(cell)?,
+                None => self.f.put(&[0])?,
             }
         }
         Ok(())
     }
+    /// Encode a single delta. Returns false if the delta was stale (and so nothing was written)
+    fn write_delta(&mut self, model: &ModelData, delta: &DataDelta) -> RuntimeResult<bool> {
+        if model.delta_state().is_truncated(delta) {
+            // the row was removed by a truncate and the truncate batch is already on disk
+            return Ok(false);
+        }
+        match delta.change() {
+            DataDeltaKind::Delete => {
+                self.write_row_metadata(delta.change(), delta.data_version())?;
+                self.write_row_pk(delta.row().d_key())?;
+            }
+            DataDeltaKind::Insert | DataDeltaKind::Update => {
+                // resolve deltas (this is yet another opportunity for us to reclaim memory from deleted items)
+                let row_data = delta
+                    .row()
+                    .resolve_schema_deltas_and_freeze_if(model.delta_state(), |row| {
+                        row.get_txn_revised() <= delta.data_version()
+                    });
+                if row_data.get_txn_revised() > delta.data_version() {
+                    /*
+                        inconsistent read. there should already be another revised delta somewhere and since rows track
+                        every field changed since they were last written out, that delta has our changes too
+                    */
+                    return Ok(false);
+                } else {
+                    match delta.changed_fields() {
+                        Some(changed)
+                            if self.partial_updates
+                                & (changed.schema_version()
+                                    == model.delta_state().schema_current_version()) =>
+                        {
+                            self.write_partial_update_metadata(delta.data_version())?;
+                            self.write_row_pk(delta.row().d_key())?;
+                            self.write_row_written_at(model, &row_data)?;
+                            self.write_row_partial_data(model, &row_data, changed.fields())?;
+                        }
+                        _ => {
+                            // either a full change, the schema changed since (so positions are no longer valid) or
+                            // the file predates partial updates
+                            self.write_row_metadata(delta.change(), delta.data_version())?;
+                            // encode data
+                            self.write_row_pk(delta.row().d_key())?;
+                            self.write_row_written_at(model, &row_data)?;
+                            self.write_row_data(model, &row_data)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns true if partial updates can be written to the data file (see
+/// [`ModelDataBatchAofV1::REVISION_PARTIAL_UPDATES`])
+fn can_write_partial_updates(
+    f: &TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec>,
+) -> bool {
+    f.metadata().file_specifier_version() >= ModelDataBatchAofV1::REVISION_PARTIAL_UPDATES
+}
+
+/// The delta was written out, so unless the row changed in the meantime, it has nothing left to write
+fn mark_written(model: &ModelData, delta: &DataDelta) {
+    model.delta_state().__record_written(delta.data_version());
+    if delta.change() != DataDeltaKind::Delete {
+        delta
+            .row()
+            .d_data()
+            .write()
+            .mark_clean(delta.data_version());
+    }
 }
 
 struct BatchWriter<'a, 'b> {
@@ -229,6 +443,10 @@ impl<'a, 'b> BatchWriter<'a, 'b> {
 
             -- @ohsayan
         */
+        let workers = encode_workers(expected);
+        if workers > 1 {
+            return Self::write_batch_parallel(model, g, expected, workers, f, batch_stat);
+        }
         let mut me = Self::new(model, g, f)?;
         let mut i = 0;
         while i < expected {
@@ -245,12 +463,74 @@ impl<'a, 'b> BatchWriter<'a, 'b> {
         }
         Ok(me.sync_count)
     }
+    /// Same as [`Self::write_batch`], but the deltas are encoded by `workers` threads (each into its own buffer) and
+    /// the buffers are then written out in order
+    fn write_batch_parallel(
+        model: &'a ModelData,
+        g: &'a Guard,
+        expected: usize,
+        workers: usize,
+        f: &'b mut TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec>,
+        batch_stat: &mut BatchStats,
+    ) -> RuntimeResult<usize> {
+        let partial_updates = can_write_partial_updates(f);
+        let mut deltas = Vec::with_capacity(expected);
+        for _ in 0..expected {
+            deltas.push(model.delta_state().__data_delta_dequeue(g).unwrap());
+        }
+        let encoded: Vec<RuntimeResult<EncodedDeltas>> = thread::scope(|s| {
+            let workers: Vec<_> = deltas
+                .chunks(expected.div_ceil(workers))
+                .map(|deltas| {
+                    let task = EncodeTask {
+                        model,
+                        deltas,
+                        partial_updates,
+                    };
+                    s.spawn(move || task.run())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        let (mut written, mut sync_count) = (0, 0);
+        let mut row_writer = RowWriter { f, partial_updates };
+        let r = row_writer.write_row_global_metadata(model).and_then(|_| {
+            for encoded in encoded {
+                let EncodedDeltas { buf, ends } = encoded?;
+                let mut start = 0;
+                for end in ends {
+                    if end != start {
+                        row_writer.f.dtrack_write(&buf[start..end])?;
+                        row_writer.f.flush_buf()?;
+                        mark_written(model, &deltas[written]);
+                        sync_count += 1;
+                    }
+                    start = end;
+                    written += 1;
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = r {
+            // push back everything that we didn't write; we have written and flushed all prior deltas
+            for delta in deltas.drain(written..) {
+                model.delta_state().append_new_data_delta(delta, g);
+            }
+            batch_stat.set_actual(written);
+            return Err(e);
+        }
+        Ok(sync_count)
+    }
     fn new(
         model: &'a ModelData,
         g: &'a Guard,
         f: &'b mut TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec>,
     ) -> RuntimeResult<Self> {
-        let mut row_writer = RowWriter { f };
+        let partial_updates = can_write_partial_updates(f);
+        let mut row_writer = RowWriter { f, partial_updates };
         row_writer.write_row_global_metadata(model)?;
         Ok(Self {
             model,
@@ -260,36 +540,50 @@ impl<'a, 'b> BatchWriter<'a, 'b> {
         })
     }
     fn step(&mut self, delta: &DataDelta) -> RuntimeResult<()> {
-        match delta.change() {
-            DataDeltaKind::Delete => {
-                self.row_writer
-                    .write_row_metadata(delta.change(), delta.data_version())?;
-                self.row_writer.write_row_pk(delta.row().d_key())?;
-            }
-            DataDeltaKind::Insert | DataDeltaKind::Update => {
-                // resolve deltas (this is yet another opportunity for us to reclaim memory from deleted items)
-                let row_data = delta
-                    .row()
-                    .resolve_schema_deltas_and_freeze_if(self.model.delta_state(), |row| {
-                        row.get_txn_revised() <= delta.data_version()
-                    });
-                if row_data.get_txn_revised() > delta.data_version() {
-                    // inconsistent read. there should already be another revised delta somewhere
-                    return Ok(());
-                }
-                self.row_writer
-                    .write_row_metadata(delta.change(), delta.data_version())?;
-                // encode data
-                self.row_writer.write_row_pk(delta.row().d_key())?;
-                self.row_writer.write_row_data(self.model, &row_data)?;
-            }
+        if self.row_writer.write_delta(self.model, delta)? {
+            self.row_writer.f.flush_buf()?;
+            mark_written(self.model, delta);
+            self.sync_count += 1;
         }
-        self.row_writer.f.flush_buf()?;
-        self.sync_count += 1;
         Ok(())
     }
 }
 
+/// Deltas encoded by a worker (see [`BatchWriter::write_batch_parallel`])
+struct EncodedDeltas {
+    buf: Vec<u8>,
+    /// where each delta ends in `buf` (a stale delta takes no space)
+    ends: Vec<usize>,
+}
+
+/// A run of deltas to be encoded on a worker thread
+struct EncodeTask<'a> {
+    model: &'a ModelData,
+    deltas: &'a [DataDelta],
+    partial_updates: bool,
+}
+
+// UNSAFE(@ohsayan): a delta's row is a raw pointer, which is why this isn't Send. the rows that the task borrows are
+// owned by `write_batch_parallel` and outlive the scoped encode threads. the workers only read them (and resolve schema
+// deltas under the row's lock) like concurrent queries do, and no two workers get the same delta
+unsafe impl<'a> Send for EncodeTask<'a> {}
+
+impl<'a> EncodeTask<'a> {
+    fn run(self) -> RuntimeResult<EncodedDeltas> {
+        let mut buf = vec![];
+        let mut ends = Vec::with_capacity(self.deltas.len());
+        let mut row_writer = RowWriter {
+            f: &mut buf,
+            partial_updates: self.partial_updates,
+        };
+        for delta in self.deltas {
+            row_writer.write_delta(self.model, delta)?;
+            ends.push(row_writer.f.len());
+        }
+        Ok(EncodedDeltas { buf, ends })
+    }
+}
+
 /// A standard model batch where atmost the given number of keys are flushed
 pub struct StdModelBatch<'a>(&'a ModelData, usize);
 
@@ -339,7 +633,10 @@ impl<'a> JournalAdapterEvent<BatchAdapter<ModelDataAdapter>> for FullModel<'a> {
         _: Rc<RefCell<BatchStats>>,
     ) -> RuntimeResult<()> {
         let g = pin();
-        let mut row_writer: RowWriter<'_> = RowWriter { f };
+        let mut row_writer: RowWriter<'_> = RowWriter {
+            f,
+            partial_updates: false,
+        };
         let index = self.0.primary_index().__raw_index();
         let current_row_count = index.mt_len();
         // expect commit == current row count
@@ -352,6 +649,7 @@ impl<'a> JournalAdapterEvent<BatchAdapter<ModelDataAdapter>> for FullModel<'a> {
             let row_data = row_data.read();
             row_writer.write_row_metadata(DataDeltaKind::Insert, row_data.get_txn_revised())?;
             row_writer.write_row_pk(key)?;
+            row_writer.write_row_written_at(self.0, &row_data)?;
             row_writer.write_row_data(self.0, &row_data)?;
         }
         // actual commit == current row count
@@ -362,6 +660,38 @@ impl<'a> JournalAdapterEvent<BatchAdapter<ModelDataAdapter>> for FullModel<'a> {
     }
 }
 
+/// A batch that removes all rows in the model
+pub struct TruncateModelBatch<'a>(&'a ModelData);
+
+impl<'a> TruncateModelBatch<'a> {
+    pub fn new(model: &'a ModelData) -> Self {
+        Self(model)
+    }
+}
+
+impl<'a> JournalAdapterEvent<BatchAdapter<ModelDataAdapter>> for TruncateModelBatch<'a> {
+    fn md(&self) -> u64 {
+        BatchType::Truncate.dscr_u64()
+    }
+    fn write_direct(
+        self,
+        f: &mut TrackedWriter<<BatchAdapter<ModelDataAdapter> as RawJournalAdapter>::Spec>,
+        _: Rc<RefCell<BatchStats>>,
+    ) -> RuntimeResult<()> {
+        let mut row_writer: RowWriter<'_> = RowWriter {
+            f,
+            partial_updates: false,
+        };
+        // expect commit == 0
+        row_writer.f.dtrack_write(&0u64.u64_bytes_le())?;
+        // [pk tag][schema version][column cnt]
+        row_writer.write_row_global_metadata(self.0)?;
+        // actual commit == 0
+        row_writer.f.dtrack_write(&0u64.u64_bytes_le())?;
+        Ok(())
+    }
+}
+
 /*
     restore implementation
     ---
@@ -374,6 +704,7 @@ impl<'a> JournalAdapterEvent<BatchAdapter<ModelDataAdapter>> for FullModel<'a> {
 
 /// Per-batch metadata
 pub struct BatchMetadata {
+    truncate: bool,
     pk_tag: TagUnique,
     schema_version: u64,
     column_count: u64,
@@ -381,8 +712,10 @@ pub struct BatchMetadata {
 
 enum DecodedBatchEventKind {
     Delete,
-    Insert(Vec<Datacell>),
-    Update(Vec<Datacell>),
+    /// the row and when it was written
+    Insert(Vec<Datacell>, u64),
+    Update(Vec<Datacell>, u64),
+    PartialUpdate(Vec<(usize, Datacell)>, u64),
 }
 
 /// State handling for any pending queries
@@ -445,21 +778,23 @@ impl BatchAdapterSpec for ModelDataAdapter {
         batch_type: Self::BatchType,
     ) -> RuntimeResult<Self::BatchMetadata> {
         // [pk tag][schema version][column cnt]
-        match batch_type {
-            BatchType::Standard => {}
-        }
+        let truncate = match batch_type {
+            BatchType::Standard => false,
+            BatchType::Truncate => true,
+        };
         let pk_tag = TagUnique::try_from_raw(f.read_block().map(|[b]| b)?)
             .ok_or(StorageError::RawJournalCorrupted)?;
         let schema_version = u64::from_le_bytes(f.read_block()?);
         let column_count = u64::from_le_bytes(f.read_block()?);
         Ok(BatchMetadata {
+            truncate,
             pk_tag,
             schema_version,
             column_count,
         })
     }
     fn update_state_for_new_event(
-        _: &Self::GlobalState,
+        gs: &Self::GlobalState,
         bs: &mut Self::BatchState,
         f: &mut TrackedReaderContext<Self::Spec>,
         batch_info: &Self::BatchMetadata,
@@ -480,21 +815,31 @@ impl BatchAdapterSpec for ModelDataAdapter {
             EventType::Insert | EventType::Update => {
                 // insert or update
                 // prepare row
+                let written_at = restore_impls::decode_written_at(gs, f)?;
                 let row = restore_impls::decode_row_data(batch_info, f)?;
                 if event_type == EventType::Insert {
                     bs.events.push(DecodedBatchEvent::new(
                         txn_id,
                         pk,
-                        DecodedBatchEventKind::Insert(row),
+                        DecodedBatchEventKind::Insert(row, written_at),
                     ));
                 } else {
                     bs.events.push(DecodedBatchEvent::new(
                         txn_id,
                         pk,
-                        DecodedBatchEventKind::Update(row),
+                        DecodedBatchEventKind::Update(row, written_at),
                     ));
                 }
             }
+            EventType::PartialUpdate => {
+                let written_at = restore_impls::decode_written_at(gs, f)?;
+                let changes = restore_impls::decode_partial_row_data(batch_info, f)?;
+                bs.events.push(DecodedBatchEvent::new(
+                    txn_id,
+                    pk,
+                    DecodedBatchEventKind::PartialUpdate(changes, written_at),
+                ));
+            }
             EventType::EarlyExit => unreachable!(),
         }
         Ok(())
@@ -507,14 +852,36 @@ impl BatchAdapterSpec for ModelDataAdapter {
         /*
             go over each change in this batch, resolve conflicts and then apply to global state
         */
-        let g = unsafe { crossbeam_epoch::unprotected() };
+        // a replaced row is read after it's removed from the index, so it must not be freed until we're done
+        let g = pin();
         let mut pending_delete = HashMap::new();
         let p_index = gs.primary_index().__raw_index();
+        if batch_md.truncate {
+            /*
+                every row written so far is gone. a truncate batch has no events and since any later batch only has
+                changes made after the truncate, we don't touch the delta version
+            */
+            if !batch_state.events.is_empty() {
+                return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+            }
+            // clearing might have to remove rows one by one, so pin since the removed nodes must outlive the walk
+            p_index.mt_clear(&pin());
+            return Ok(());
+        }
         let m = gs;
         let mut real_last_txn_id = DeltaVersion::genesis();
+        let non_pk_fields: Vec<_> = m
+            .fields()
+            .stseq_ord_key()
+            .filter(|key| key.as_str() != m.p_key())
+            .collect();
+        m.delta_state()
+            .__record_persisted_events(batch_state.events.len());
+        let restored_any = !batch_state.events.is_empty();
         for DecodedBatchEvent { txn_id, pk, kind } in batch_state.events {
             match kind {
-                DecodedBatchEventKind::Insert(new_row) | DecodedBatchEventKind::Update(new_row) => {
+                DecodedBatchEventKind::Insert(new_row, written_at)
+                | DecodedBatchEventKind::Update(new_row, written_at) => {
                     let popped_row = p_index.mt_delete_return(&pk, &g);
                     if let Some(row) = popped_row {
                         /*
@@ -522,9 +889,13 @@ impl BatchAdapterSpec for ModelDataAdapter {
                             version is never synced. this is how the diffing algorithm works to ensure consistency.
                             the delta diff algorithm statically guarantees this.
                         */
+                        /*
+                            a compacted data file starts with a snapshot of every live row and any delta that was
+                            pending at the time is written (again) after it, so we might see the same version twice
+                        */
                         let row_txn_revised = row.read().get_txn_revised();
                         assert!(
-                            row_txn_revised.value_u64() == 0 || row_txn_revised < txn_id,
+                            row_txn_revised.value_u64() == 0 || row_txn_revised <= txn_id,
                             "revised ID is {} but our row has version {}",
                             row.read().get_txn_revised().value_u64(),
                             txn_id.value_u64()
@@ -553,12 +924,44 @@ impl BatchAdapterSpec for ModelDataAdapter {
                         data,
                         DeltaVersion::__new(batch_md.schema_version),
                         txn_id,
+                        written_at,
                     );
                     // resolve any deltas
                     let _ = row.resolve_schema_deltas_and_freeze(m.delta_state());
                     // put it back in (lol); blame @ohsayan for this joke
                     p_index.mt_insert(row, &g);
                 }
+                DecodedBatchEventKind::PartialUpdate(changes, written_at) => {
+                    if txn_id > real_last_txn_id {
+                        real_last_txn_id = txn_id;
+                    }
+                    /*
+                        resolve the partial update against the row as we have it so far. if there's no row, then it was
+                        deleted (in an earlier batch) and there's nothing to apply this to
+                    */
+                    let Some(row) = p_index.mt_get(&pk, &g) else {
+                        continue;
+                    };
+                    let mut row = row.write();
+                    if row.get_txn_revised() > txn_id {
+                        // a newer version of this row was already synced
+                        continue;
+                    }
+                    for (position, new_data) in changes {
+                        let Some(field_name) = non_pk_fields.get(position) else {
+                            return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+                        };
+                        row.fields_mut().st_upsert(
+                            unsafe {
+                                // UNSAFE(@ohsayan): model in scope, we're good
+                                (*field_name).clone()
+                            },
+                            new_data,
+                        );
+                    }
+                    row.set_txn_revised(txn_id);
+                    row.set_written_at(written_at);
+                }
                 DecodedBatchEventKind::Delete => {
                     /*
                         due to the concurrent nature of the engine, deletes can "appear before" an insert or update and since
@@ -609,6 +1012,9 @@ impl BatchAdapterSpec for ModelDataAdapter {
         // +1 since it is a fetch add!
         m.delta_state()
             .__set_delta_version(DeltaVersion::__new(real_last_txn_id.value_u64() + 1));
+        if restored_any {
+            m.delta_state().__record_restored(real_last_txn_id);
+        }
         Ok(())
     }
 }
@@ -618,7 +1024,7 @@ mod restore_impls {
         super::BatchMetadata,
         crate::{
             engine::{
-                core::index::PrimaryIndexKey,
+                core::{index::PrimaryIndexKey, model::ModelData},
                 data::{cell::Datacell, tag::TagUnique},
                 error::StorageError,
                 storage::{
@@ -671,6 +1077,39 @@ mod restore_impls {
             },
         })
     }
+    /// Decode when the row was written, if the model's rows expire (zero otherwise)
+    pub fn decode_written_at<S: FileSpecV1>(
+        model: &ModelData,
+        f: &mut TrackedReaderContext<S>,
+    ) -> RuntimeResult<u64> {
+        match model.ttl() {
+            Some(_) => Ok(u64::from_le_bytes(f.read_block()?)),
+            None => Ok(0),
+        }
+    }
+    /// Decode the changed fields of a partial update: `[field count]([position][cell])*`
+    pub fn decode_partial_row_data(
+        batch_info: &BatchMetadata,
+        f: &mut TrackedReaderContext<ModelDataBatchAofV1>,
+    ) -> Result<Vec<(usize, Datacell)>, crate::engine::fractal::error::Error> {
+        let change_count = u64::from_le_bytes(f.read_block()?);
+        if change_count > batch_info.column_count {
+            return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+        }
+        let mut changes = Vec::with_capacity(change_count as usize);
+        for _ in 0..change_count {
+            let position = u64::from_le_bytes(f.read_block()?);
+            if position >= batch_info.column_count {
+                return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+            }
+            let Some(dscr) = StorageCellTypeID::try_from_raw(f.read_block().map(|[b]| b)?) else {
+                return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+            };
+            let cell = unsafe { cell::decode_element::<Datacell, _>(f, dscr) }.map_err(|e| e.0)?;
+            changes.push((position as usize, cell));
+        }
+        Ok(changes)
+    }
     pub fn decode_row_data(
         batch_info: &BatchMetadata,
         f: &mut TrackedReaderContext<ModelDataBatchAofV1>,
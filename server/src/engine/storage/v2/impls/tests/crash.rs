@@ -0,0 +1,259 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2024, Sayan Nandan <nandansayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    crash consistency tests for the drivers: we run a workload, crash the driver's journal with every fault and then
+    check that we either restore all of the data or refuse to open the journal, that the synced data can always be
+    restored with a repair and that the repaired journal can be written to again
+*/
+
+use {
+    super::model_driver::{create_model_and_space, run_insert},
+    crate::engine::{
+        core::{EntityIDRef, GNSData},
+        data::lit::Lit,
+        fault::{self, Fault},
+        fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+        storage::{safe_interfaces::paths_v1, GNSDriver, ModelDriver},
+        txn::gns::sysctl::CreateUserTxn,
+    },
+    crossbeam_epoch::pin,
+};
+
+/// The faults that we crash with when `pending_writes` writes (at least) weren't synced. Only
+/// `Fault::KeepWrites(usize::MAX)` is sure to leave a fully persisted journal behind
+fn faults(pending_writes: usize) -> Vec<Fault> {
+    let mut faults = vec![Fault::DropUnsynced, Fault::KeepWrites(usize::MAX)];
+    for n in 0..=pending_writes {
+        faults.push(Fault::KeepWrites(n));
+        faults.push(Fault::SkipWrite(n));
+        for bytes in [0, 1, 8, 16, 31, 64] {
+            faults.push(Fault::TornWrite { writes: n, bytes });
+        }
+    }
+    faults
+}
+
+/*
+    model driver
+*/
+
+const MODEL_DECL: &str = "create model crash.accounts(id: uint64, password: string)";
+const MODEL_SYNCED_ROWS: u64 = 4;
+const MODEL_ROWS: u64 = 8;
+
+fn model_id() -> EntityIDRef<'static> {
+    EntityIDRef::new("crash", "accounts")
+}
+
+fn insert_row(global: &TestGlobal, id: u64) {
+    run_insert(
+        global,
+        &format!("insert into crash.accounts({id}, 'password-{id}')"),
+    )
+    .unwrap()
+}
+
+/// Load the GNS (which we never crash) so that we can open the model driver by hand
+fn load_gns(log_name: &str) -> (GNSData, String) {
+    let gns = GNSData::empty();
+    let mut driver = GNSDriver::open_gns_with_name(log_name, &gns).unwrap();
+    GNSDriver::close_driver(&mut driver).unwrap();
+    let space_uuid = gns.idx().read().get("crash").unwrap().get_uuid();
+    let model_uuid = gns
+        .idx_models()
+        .read()
+        .get(&model_id())
+        .unwrap()
+        .data()
+        .get_uuid();
+    let path = paths_v1::model_path("crash", space_uuid, "accounts", model_uuid);
+    (gns, path)
+}
+
+fn assert_rows(gns: &GNSData, ids: &[u64], fault: Fault) {
+    gns.with_model(model_id(), |model| {
+        assert_eq!(model.primary_index().count(), ids.len(), "{fault:?}");
+        let g = pin();
+        for id in ids {
+            let row = model
+                .primary_index()
+                .select(Lit::new_uint(*id), &g)
+                .unwrap_or_else(|| panic!("{fault:?}: missing row {id}"))
+                .d_data()
+                .read();
+            assert_eq!(
+                row.fields().get("password").unwrap().str(),
+                format!("password-{id}"),
+                "{fault:?}"
+            );
+        }
+        Ok(())
+    })
+    .unwrap()
+}
+
+#[test]
+fn model_driver_crash_consistency() {
+    // a synced batch (rows 0..4), an unsynced batch (rows 4..8) and the (unsynced) close event
+    let all_rows: Vec<u64> = (0..MODEL_ROWS).collect();
+    let synced_rows = &all_rows[..MODEL_SYNCED_ROWS as usize];
+    for (i, fault) in faults(3).into_iter().enumerate() {
+        let log_name = format!("model_crash_consistency_{i}");
+        {
+            let mut global = TestGlobal::new_with_driver_id(&log_name);
+            global.set_max_data_pressure(MODEL_SYNCED_ROWS as usize);
+            create_model_and_space(&global, MODEL_DECL).unwrap();
+            for id in 0..MODEL_SYNCED_ROWS {
+                insert_row(&global, id);
+            }
+            global.set_max_data_pressure(usize::MAX);
+            for id in MODEL_SYNCED_ROWS..MODEL_ROWS {
+                insert_row(&global, id);
+            }
+            global.write_batch_deferred(model_id());
+        }
+        let (_, path) = load_gns(&log_name);
+        fault::crash_file(&path, fault).unwrap();
+        {
+            // we either read back everything or refuse to open the journal; never anything in between
+            let (gns, path) = load_gns(&log_name);
+            let models = gns.idx_models().read();
+            let model = models.get(&model_id()).unwrap();
+            match ModelDriver::open_model_driver(model.data(), &path) {
+                Ok(mut driver) => {
+                    drop(models);
+                    assert_rows(&gns, &all_rows, fault);
+                    ModelDriver::close_driver(&mut driver).unwrap();
+                }
+                Err(e) => assert_ne!(fault, Fault::KeepWrites(usize::MAX), "{e}"),
+            }
+        }
+        {
+            // the synced batch can always be restored
+            let (gns, path) = load_gns(&log_name);
+            let models = gns.idx_models().read();
+            let model = models.get(&model_id()).unwrap();
+            assert_eq!(
+                ModelDriver::truncate_model_driver(model.data(), &path, 0).unwrap(),
+                0,
+                "{fault:?}"
+            );
+            drop(models);
+            assert_rows(&gns, synced_rows, fault);
+        }
+        {
+            // and the driver picks up from there
+            let global = TestGlobal::new_with_driver_id(&log_name);
+            insert_row(&global, MODEL_ROWS);
+        }
+        {
+            let global = TestGlobal::new_with_driver_id(&log_name);
+            let mut rows = synced_rows.to_vec();
+            rows.push(MODEL_ROWS);
+            assert_rows(global.state().namespace(), &rows, fault);
+        }
+    }
+}
+
+/*
+    gns driver
+*/
+
+fn gns_users(gns: &GNSData) -> Vec<String> {
+    let mut users: Vec<String> = gns
+        .sys_db()
+        .users()
+        .read()
+        .keys()
+        .map(|user| user.to_string())
+        .collect();
+    users.sort();
+    users
+}
+
+#[test]
+fn gns_driver_crash_consistency() {
+    // 3 writes are pending at the time of the crash: user_c, user_d (both unsynced) and the close event
+    let all_users = ["user_a", "user_b", "user_c", "user_d"].map(String::from);
+    for (i, fault) in faults(3).into_iter().enumerate() {
+        let log_name = format!("gns_crash_consistency_{i}");
+        {
+            let mut driver = GNSDriver::create_gns_with_name(&log_name).unwrap();
+            driver
+                .commit_event(CreateUserTxn::new("user_a", b"hash_a"))
+                .unwrap(); // 0
+            driver
+                .commit_event(CreateUserTxn::new("user_b", b"hash_b"))
+                .unwrap(); // 1
+            driver
+                .commit_with_ctx_deferred_sync(CreateUserTxn::new("user_c", b"hash_c"), ())
+                .unwrap(); // 2
+            driver
+                .commit_with_ctx_deferred_sync(CreateUserTxn::new("user_d", b"hash_d"), ())
+                .unwrap(); // 3
+            GNSDriver::close_driver(&mut driver).unwrap(); // 4
+        }
+        fault::crash_file(&log_name, fault).unwrap();
+        {
+            // we either read back everything or refuse to open the journal; never anything in between
+            let gns = GNSData::empty();
+            match GNSDriver::open_gns_with_name(&log_name, &gns) {
+                Ok(mut driver) => {
+                    assert_eq!(gns_users(&gns), all_users, "{fault:?}");
+                    GNSDriver::close_driver(&mut driver).unwrap();
+                }
+                Err(e) => assert_ne!(fault, Fault::KeepWrites(usize::MAX), "{e}"),
+            }
+        }
+        {
+            // the synced events can always be restored
+            let gns = GNSData::empty();
+            assert_eq!(
+                GNSDriver::truncate_gns_with_name(&log_name, &gns, 1).unwrap(),
+                1,
+                "{fault:?}"
+            );
+            assert_eq!(gns_users(&gns), all_users[..2], "{fault:?}");
+        }
+        {
+            // and the driver picks up from there
+            let gns = GNSData::empty();
+            let mut driver = GNSDriver::open_gns_with_name(&log_name, &gns).unwrap();
+            assert_eq!(gns_users(&gns), all_users[..2], "{fault:?}");
+            driver
+                .commit_event(CreateUserTxn::new("user_e", b"hash_e"))
+                .unwrap();
+            GNSDriver::close_driver(&mut driver).unwrap();
+        }
+        {
+            let gns = GNSData::empty();
+            let mut driver = GNSDriver::open_gns_with_name(&log_name, &gns).unwrap();
+            assert_eq!(gns_users(&gns), ["user_a", "user_b", "user_e"], "{fault:?}");
+            GNSDriver::close_driver(&mut driver).unwrap();
+        }
+    }
+}
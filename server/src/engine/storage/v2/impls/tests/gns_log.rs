@@ -0,0 +1,70 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <nandansayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::GNSData,
+    storage::{
+        common::{
+            interface::fs::{File, FileExt, FileSystem, FileWrite},
+            sdss::sdss_r1::SimpleFileSpecV1,
+            versions::FileSpecifierVersion,
+        },
+        v2::{
+            impls::gns_log::GNSDriver,
+            raw::spec::{Header, SystemDatabaseV1},
+        },
+    },
+};
+
+fn read_header(name: &str) -> Header {
+    let data = FileSystem::read(name).unwrap();
+    Header::decode(data[..64].try_into().unwrap()).unwrap()
+}
+
+#[test]
+fn upgrade_older_revision() {
+    const NAME: &str = "gns_upgrade_older_revision.db-tlog";
+    let mut gns = GNSDriver::create_gns_with_name(NAME).unwrap();
+    GNSDriver::close_driver(&mut gns).unwrap();
+    // move the log back to the very first revision
+    let mut header = read_header(NAME);
+    let (offset, revision) = header.set_file_specifier_version(FileSpecifierVersion::__new(0));
+    let mut f = File::open(NAME).unwrap();
+    f.f_seek_start(offset).unwrap();
+    f.fwrite_all(&revision).unwrap();
+    drop(f);
+    assert_eq!(
+        read_header(NAME).file_specifier_version(),
+        FileSpecifierVersion::__new(0)
+    );
+    // opening it should move it to the current revision
+    let mut gns = GNSDriver::open_gns_with_name(NAME, &GNSData::empty()).unwrap();
+    GNSDriver::close_driver(&mut gns).unwrap();
+    assert_eq!(
+        read_header(NAME).file_specifier_version(),
+        SystemDatabaseV1::FILE_SPECFIER_VERSION
+    );
+}
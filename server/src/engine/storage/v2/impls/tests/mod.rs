@@ -24,4 +24,6 @@
  *
 */
 
+mod crash;
+mod gns_log;
 mod model_driver;
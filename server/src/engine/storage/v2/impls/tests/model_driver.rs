@@ -27,16 +27,26 @@
 use {
     crate::{
         engine::{
-            core::{dml, index::RowData, model::ModelData, space::Space, EntityID, EntityIDRef},
+            core::{
+                dml,
+                index::RowData,
+                model::{delta, ModelData},
+                space::Space,
+                EntityID, EntityIDRef,
+            },
             data::lit::Lit,
             error::QueryResult,
-            fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+            fractal::{test_utils::TestGlobal, GlobalInstanceLike, ModelUniqueIDRef},
             ql::{
                 ast,
-                ddl::crt::{CreateModel, CreateSpace},
-                dml::{ins::InsertStatement, upd::UpdateStatement},
+                ddl::{
+                    crt::{CreateModel, CreateSpace},
+                    drop::TruncateModel,
+                },
+                dml::{del::DeleteStatement, ins::InsertStatement, upd::UpdateStatement},
                 tests::lex_insecure,
             },
+            storage::v2::impls::mdl_journal,
         },
         util::test_utils,
     },
@@ -63,7 +73,10 @@ fn create_test_kv_int(change_count: usize) -> Vec<(u64, String)> {
         .collect()
 }
 
-fn create_model_and_space(global: &TestGlobal, create_model: &str) -> QueryResult<EntityID> {
+pub(super) fn create_model_and_space(
+    global: &TestGlobal,
+    create_model: &str,
+) -> QueryResult<EntityID> {
     let tokens = lex_insecure(create_model.as_bytes()).unwrap();
     let create_model: CreateModel = ast::parse_ast_node_full(&tokens[2..]).unwrap();
     let mdl_name = EntityID::new(
@@ -78,7 +91,7 @@ fn create_model_and_space(global: &TestGlobal, create_model: &str) -> QueryResul
     ModelData::transactional_exec_create(global, create_model).map(|_| mdl_name)
 }
 
-fn run_insert(global: &TestGlobal, insert: &str) -> QueryResult<()> {
+pub(super) fn run_insert(global: &TestGlobal, insert: &str) -> QueryResult<()> {
     let tokens = lex_insecure(insert.as_bytes()).unwrap();
     let insert: InsertStatement = ast::parse_ast_node_full(&tokens[1..]).unwrap();
     dml::insert(global, insert)
@@ -90,6 +103,18 @@ fn run_update(global: &TestGlobal, update: &str) -> QueryResult<()> {
     dml::update(global, insert)
 }
 
+fn run_delete(global: &TestGlobal, delete: &str) -> QueryResult<()> {
+    let tokens = lex_insecure(delete.as_bytes()).unwrap();
+    let delete: DeleteStatement = ast::parse_ast_node_full(&tokens[1..]).unwrap();
+    dml::delete(global, delete)
+}
+
+fn run_truncate(global: &TestGlobal, truncate: &str) -> QueryResult<()> {
+    let tokens = lex_insecure(truncate.as_bytes()).unwrap();
+    let truncate: TruncateModel = ast::parse_ast_node_full(&tokens[2..]).unwrap();
+    ModelData::transactional_exec_truncate(global, truncate)
+}
+
 fn auto_hook<T>(msg: &str, f: impl Fn() -> T) -> T {
     let hook = std::panic::take_hook();
     let decl_owned = msg.to_owned();
@@ -315,3 +340,361 @@ fn model_data_updates() {
         },
     );
 }
+
+#[test]
+fn model_data_ttl() {
+    // the rows of a model with a ttl also have the time they were written
+    run_sample_inserts(
+        "model_data_ttl_inserts",
+        "create model apps.social(user_name: string, password: string) with { ttl: 3600 }",
+        create_test_kv_strings(TEST_DATASET_SIZE),
+        |k, v| format!("insert into apps.social('{k}', '{v}')"),
+        |k| Lit::new_str(k),
+        |_, v, row| {
+            assert_eq!(row.fields().get("password").unwrap().str(), v);
+            assert_ne!(row.written_at(), 0);
+        },
+    );
+    run_sample_updates(
+        "model_data_ttl_updates",
+        "create model apps.social(user_name: string, password: string) with { ttl: 3600 }",
+        create_test_kv_strings(TEST_UPDATE_DATASET_SIZE),
+        |k, v| format!("insert into apps.social('{k}', '{v}')"),
+        |k, _| format!("update apps.social set password = '' where user_name = '{k}'"),
+        |k| Lit::new_str(k),
+        |_, _, row| {
+            assert!(row.fields().get("password").unwrap().str().is_empty());
+            assert_ne!(row.written_at(), 0);
+        },
+    );
+}
+
+#[test]
+fn model_data_compaction() {
+    const DECL: &str = "create model apps.social(user_id: uint64, password: string)";
+    auto_hook(DECL, || {
+        test_utils::with_variable("model_data_compaction", |log_name| {
+            let key_values = create_test_kv_int(TEST_DATASET_SIZE);
+            let mdl_name;
+            {
+                // insert and then update every row so that half the events are dead
+                let mut global = TestGlobal::new_with_driver_id(log_name);
+                global.set_max_data_pressure(TEST_DATASET_SIZE);
+                mdl_name = create_model_and_space(&global, DECL).unwrap();
+                for (k, v) in key_values.iter() {
+                    run_insert(&global, &format!("insert into apps.social({k}, '{v}')")).unwrap();
+                }
+                for (k, _) in key_values.iter() {
+                    run_update(
+                        &global,
+                        &format!("update apps.social set password = '' where user_id = {k}"),
+                    )
+                    .unwrap();
+                }
+            }
+            let mdl_id = EntityIDRef::new(mdl_name.space(), mdl_name.entity());
+            {
+                // compact and then update a few more rows
+                let global = TestGlobal::new_with_driver_id(log_name);
+                let models = global.state().namespace().idx_models().read();
+                let model = models.get(&mdl_id).unwrap();
+                assert_eq!(
+                    model.data().delta_state().persisted_events(),
+                    TEST_DATASET_SIZE * 2
+                );
+                let space_uuid = global
+                    .state()
+                    .namespace()
+                    .idx()
+                    .read()
+                    .get(mdl_name.space())
+                    .unwrap()
+                    .get_uuid();
+                model
+                    .driver()
+                    .compact(
+                        &global,
+                        ModelUniqueIDRef::new(
+                            mdl_name.space(),
+                            mdl_name.entity(),
+                            model.data().get_uuid(),
+                        ),
+                        space_uuid,
+                        model.data(),
+                    )
+                    .unwrap();
+                assert_eq!(
+                    model.data().delta_state().persisted_events(),
+                    TEST_DATASET_SIZE
+                );
+                drop(models);
+                for (k, _) in key_values.iter().take(TEST_DATASET_SIZE / 2) {
+                    run_update(
+                        &global,
+                        &format!("update apps.social set password = 'new' where user_id = {k}"),
+                    )
+                    .unwrap();
+                }
+            }
+            {
+                // the compacted data, with the updates that followed
+                let global = TestGlobal::new_with_driver_id(log_name);
+                global
+                    .state()
+                    .namespace()
+                    .with_model(mdl_id, |model| {
+                        assert_eq!(
+                            model.delta_state().persisted_events(),
+                            TEST_DATASET_SIZE + TEST_DATASET_SIZE / 2
+                        );
+                        let g = pin();
+                        for (i, (k, _)) in key_values.iter().enumerate() {
+                            let row = model
+                                .primary_index()
+                                .select(Lit::new_uint(*k), &g)
+                                .unwrap()
+                                .d_data()
+                                .read();
+                            let expected = if i < TEST_DATASET_SIZE / 2 { "new" } else { "" };
+                            assert_eq!(row.fields().get("password").unwrap().str(), expected);
+                        }
+                        Ok(())
+                    })
+                    .unwrap()
+            }
+        })
+    })
+}
+
+#[test]
+fn model_data_parallel_encode() {
+    const DECL: &str = "create model apps.social(user_id: uint64, password: string, email: string)";
+    const ROWS: u64 = TEST_DATASET_SIZE as u64;
+    // write the same deltas with a serial and a parallel encoder and check that both restore the same rows
+    let run = |log_name: &str, workers: usize| {
+        test_utils::with_variable(log_name, |log_name| {
+            let mdl_name;
+            {
+                let global = TestGlobal::new_with_driver_id(log_name);
+                mdl_name = create_model_and_space(&global, DECL).unwrap();
+                for k in 0..ROWS {
+                    run_insert(&global, &format!("insert into apps.social({k}, 'a', 'a')"))
+                        .unwrap();
+                }
+            }
+            mdl_journal::set_encode_workers(workers);
+            delta::set_delta_batches(true);
+            {
+                // all 3750 deltas are flushed in a single batch when the global is dropped
+                let global = TestGlobal::new_with_driver_id(log_name);
+                let update = |k: u64, field: &str| {
+                    run_update(
+                        &global,
+                        &format!("update apps.social set {field} = 'b' where user_id = {k}"),
+                    )
+                    .unwrap()
+                };
+                // partial updates, half of which go stale because the same row is updated again
+                for k in 0..ROWS {
+                    update(k, "password");
+                }
+                for k in 0..ROWS / 2 {
+                    update(k, "email");
+                }
+                // new rows whose inserts go stale
+                for k in ROWS..ROWS * 2 {
+                    run_insert(&global, &format!("insert into apps.social({k}, 'a', 'a')"))
+                        .unwrap();
+                    update(k, "email");
+                }
+                // and rows that are gone before their changes were ever written out
+                for k in ROWS / 2..ROWS / 2 + ROWS / 4 {
+                    run_delete(
+                        &global,
+                        &format!("delete from apps.social where user_id = {k}"),
+                    )
+                    .unwrap();
+                }
+            }
+            delta::set_delta_batches(false);
+            mdl_journal::set_encode_workers(0);
+            let global = TestGlobal::new_with_driver_id(log_name);
+            let rows: Vec<Option<(String, String, u64)>> = global
+                .state()
+                .namespace()
+                .with_model(
+                    EntityIDRef::new(mdl_name.space(), mdl_name.entity()),
+                    |model| {
+                        let g = pin();
+                        Ok((0..ROWS * 2)
+                            .map(|k| {
+                                model
+                                    .primary_index()
+                                    .select(Lit::new_uint(k), &g)
+                                    .map(|row| {
+                                        let row = row.d_data().read();
+                                        let field =
+                                            |name| row.fields().get(name).unwrap().str().to_owned();
+                                        (
+                                            field("password"),
+                                            field("email"),
+                                            row.get_txn_revised().value_u64(),
+                                        )
+                                    })
+                            })
+                            .collect())
+                    },
+                )
+                .unwrap();
+            rows
+        })
+    };
+    let serial = run("model_data_parallel_encode_serial", 1);
+    let parallel = run("model_data_parallel_encode_parallel", 4);
+    assert_eq!(
+        serial.iter().filter(|row| row.is_some()).count() as u64,
+        ROWS * 2 - ROWS / 4
+    );
+    assert_eq!(serial, parallel);
+}
+
+#[test]
+fn model_data_delta_batches() {
+    const DECL: &str = "create model apps.social(user_id: uint64, password: string, email: string)";
+    auto_hook(DECL, || {
+        test_utils::with_variable("model_data_delta_batches", |log_name| {
+            let key_values = create_test_kv_int(TEST_DATASET_SIZE);
+            let mdl_name;
+            {
+                // insert full rows
+                let mut global = TestGlobal::new_with_driver_id(log_name);
+                global.set_max_data_pressure(TEST_DATASET_SIZE);
+                mdl_name = create_model_and_space(&global, DECL).unwrap();
+                for (k, v) in key_values.iter() {
+                    run_insert(&global, &format!("insert into apps.social({k}, '{v}', '')"))
+                        .unwrap();
+                }
+            }
+            delta::set_delta_batches(true);
+            {
+                // partial updates to a different field each time
+                let mut global = TestGlobal::new_with_driver_id(log_name);
+                global.set_max_data_pressure(TEST_DATASET_SIZE);
+                for (k, _) in key_values.iter() {
+                    run_update(
+                        &global,
+                        &format!("update apps.social set password = '' where user_id = {k}"),
+                    )
+                    .unwrap();
+                }
+                for (k, _) in key_values.iter().take(TEST_DATASET_SIZE / 2) {
+                    run_update(
+                        &global,
+                        &format!("update apps.social set email = 'new' where user_id = {k}"),
+                    )
+                    .unwrap();
+                }
+                // new rows that are updated before they were ever written out
+                for k in TEST_DATASET_SIZE..TEST_DATASET_SIZE * 2 {
+                    run_insert(&global, &format!("insert into apps.social({k}, '', '')")).unwrap();
+                    run_update(
+                        &global,
+                        &format!("update apps.social set email = 'new' where user_id = {k}"),
+                    )
+                    .unwrap();
+                }
+            }
+            delta::set_delta_batches(false);
+            {
+                // the loader should have resolved the partial updates against the full rows
+                let global = TestGlobal::new_with_driver_id(log_name);
+                global
+                    .state()
+                    .namespace()
+                    .with_model(
+                        EntityIDRef::new(mdl_name.space(), mdl_name.entity()),
+                        |model| {
+                            let g = pin();
+                            for (i, (k, _)) in key_values.iter().enumerate() {
+                                let row = model
+                                    .primary_index()
+                                    .select(Lit::new_uint(*k), &g)
+                                    .unwrap()
+                                    .d_data()
+                                    .read();
+                                let expected_email =
+                                    if i < TEST_DATASET_SIZE / 2 { "new" } else { "" };
+                                assert_eq!(row.fields().get("password").unwrap().str(), "");
+                                assert_eq!(
+                                    row.fields().get("email").unwrap().str(),
+                                    expected_email
+                                );
+                            }
+                            for k in TEST_DATASET_SIZE..TEST_DATASET_SIZE * 2 {
+                                let row = model
+                                    .primary_index()
+                                    .select(Lit::new_uint(k as u64), &g)
+                                    .unwrap()
+                                    .d_data()
+                                    .read();
+                                assert_eq!(row.fields().get("password").unwrap().str(), "");
+                                assert_eq!(row.fields().get("email").unwrap().str(), "new");
+                            }
+                            Ok(())
+                        },
+                    )
+                    .unwrap()
+            }
+        })
+    })
+}
+
+#[test]
+fn model_data_truncate() {
+    const DECL: &str = "create model apps.social(user_id: uint64, password: string)";
+    auto_hook(DECL, || {
+        test_utils::with_variable("model_data_truncate", |log_name| {
+            let key_values = create_test_kv_int(TEST_DATASET_SIZE);
+            let mdl_name;
+            {
+                // some rows are written out and some are still pending when we truncate
+                let mut global = TestGlobal::new_with_driver_id(log_name);
+                global.set_max_data_pressure(TEST_DATASET_SIZE / 2);
+                mdl_name = create_model_and_space(&global, DECL).unwrap();
+                for (k, v) in key_values.iter().take(TEST_DATASET_SIZE / 2 + 10) {
+                    run_insert(&global, &format!("insert into apps.social({k}, '{v}')")).unwrap();
+                }
+                run_truncate(&global, "truncate model apps.social").unwrap();
+                // reuse some of the keys
+                for (k, _) in key_values.iter().take(10) {
+                    run_insert(&global, &format!("insert into apps.social({k}, 'new')")).unwrap();
+                }
+            }
+            {
+                // only the rows inserted after the truncate are restored
+                let global = TestGlobal::new_with_driver_id(log_name);
+                global
+                    .state()
+                    .namespace()
+                    .with_model(
+                        EntityIDRef::new(mdl_name.space(), mdl_name.entity()),
+                        |model| {
+                            assert_eq!(model.primary_index().count(), 10);
+                            let g = pin();
+                            for (k, _) in key_values.iter().take(10) {
+                                let row = model
+                                    .primary_index()
+                                    .select(Lit::new_uint(*k), &g)
+                                    .unwrap()
+                                    .d_data()
+                                    .read();
+                                assert_eq!(row.fields().get("password").unwrap().str(), "new");
+                            }
+                            Ok(())
+                        },
+                    )
+                    .unwrap()
+            }
+        })
+    })
+}
@@ -26,10 +26,11 @@
 
 use {
     self::impls::mdl_journal::{BatchStats, FullModel},
-    super::{common::interface::fs::FileSystem, v1, SELoaded},
+    super::{common::interface::fs::FileSystem, progress::RestoreProgress, v1, SELoaded},
     crate::engine::{
         config::Configuration,
         core::{
+            kdf,
             system_db::{SystemDatabase, VerifyUser},
             GNSData, GlobalNS,
         },
@@ -102,7 +103,7 @@ pub fn initialize_new(config: &Configuration) -> RuntimeResult<SELoaded> {
     FileSystem::create_dir_all(DATA_DIR)?;
     let mut gns_driver = impls::gns_log::GNSDriver::create_gns()?;
     let gns = GNSData::empty();
-    let password_hash = rcrypt::hash(&config.auth.root_key, rcrypt::DEFAULT_COST).unwrap();
+    let password_hash = kdf::hash(&config.auth.root_key);
     // now go ahead and initialize our root user
     gns_driver.commit_event(CreateUserTxn::new(
         SystemDatabase::ROOT_ACCOUNT,
@@ -110,7 +111,7 @@ pub fn initialize_new(config: &Configuration) -> RuntimeResult<SELoaded> {
     ))?;
     assert!(gns.sys_db().__raw_create_user(
         SystemDatabase::ROOT_ACCOUNT.to_owned().into_boxed_str(),
-        password_hash.into_boxed_slice(),
+        password_hash,
     ));
     Ok(SELoaded {
         gns: GlobalNS::new(gns, FractalGNSDriver::new(gns_driver)),
@@ -121,33 +122,55 @@ pub fn restore(cfg: &Configuration) -> RuntimeResult<SELoaded> {
     let gns = GNSData::empty();
     context::set_dmsg("loading gns");
     let mut gns_driver = impls::gns_log::GNSDriver::open_gns(&gns)?;
-    for (id, model) in gns.idx_models().write().iter_mut() {
+    for (_, space) in gns.idx().read().iter() {
+        space.register_storage_path();
+    }
+    let mut models = gns.idx_models().write();
+    let mut progress = RestoreProgress::new(models.len());
+    for (id, model) in models.iter_mut() {
         let model_data = model.data();
         let space_uuid = gns.idx().read().get(id.space()).unwrap().get_uuid();
         let model_data_file_path =
             paths_v1::model_path(id.space(), space_uuid, id.entity(), model_data.get_uuid());
         context::set_dmsg(format!("loading model driver in {model_data_file_path}"));
+        progress.begin_model(format!("{}.{}", id.space(), id.entity()));
         let model_driver =
             impls::mdl_journal::ModelDriver::open_model_driver(model_data, &model_data_file_path)?;
+        // we only know how far along we are once the whole journal has been replayed
+        progress.advance(1, 1, model_data.delta_state().persisted_events() as u64);
+        progress.finish_model();
         model.driver().initialize_model_driver(model_driver);
         unsafe {
             // UNSAFE(@ohsayan): all pieces of data are upgraded by now, so vacuum
             model.data_mut().model_mutator().vacuum_stashed();
         }
+        // only the declarations of the secondary indexes are in the GNS, so index the rows now that we have them
+        model.data_mut().secondary_indexes_rebuild();
     }
+    drop(models);
+    progress.finish();
     // check if password has changed
-    if gns
+    let root_password_changed = gns
         .sys_db()
         .verify_user(SystemDatabase::ROOT_ACCOUNT, cfg.auth.root_key.as_bytes())
-        == VerifyUser::IncorrectPassword
-    {
-        // the password was changed
-        warn!("root password changed via configuration");
+        == VerifyUser::IncorrectPassword;
+    // or if the KDF has changed
+    let root_needs_rehash = gns
+        .sys_db()
+        .users()
+        .read()
+        .get(SystemDatabase::ROOT_ACCOUNT)
+        .map(|root| kdf::needs_rehash(root.hash()))
+        .unwrap_or(false);
+    if root_password_changed | root_needs_rehash {
+        if root_password_changed {
+            warn!("root password changed via configuration");
+        }
         context::set_dmsg("updating password to system database from configuration");
-        let phash = rcrypt::hash(&cfg.auth.root_key, rcrypt::DEFAULT_COST).unwrap();
+        let phash = kdf::hash(&cfg.auth.root_key);
         gns_driver.commit_event(AlterUserTxn::new(SystemDatabase::ROOT_ACCOUNT, &phash))?;
         gns.sys_db()
-            .__raw_alter_user(SystemDatabase::ROOT_ACCOUNT, phash.into_boxed_slice());
+            .__raw_alter_user(SystemDatabase::ROOT_ACCOUNT, phash);
     }
     Ok(SELoaded {
         gns: GlobalNS::new(gns, FractalGNSDriver::new(gns_driver)),
@@ -47,7 +47,9 @@ mod raw;
 #[cfg(test)]
 mod tests;
 pub use raw::{
-    create_journal, open_journal, RawJournalAdapter, RawJournalAdapterEvent as JournalAdapterEvent,
+    create_journal, open_and_upgrade_journal, open_journal, reattach_journal, scan_journal,
+    truncate_journal, verify_journal, DriverEventKind, RawJournalAdapter,
+    RawJournalAdapterEvent as JournalAdapterEvent, ScannedEvent,
 };
 
 /*
@@ -128,14 +130,8 @@ impl<EL: EventLogSpec> RawJournalAdapter for EventLogAdapter<EL> {
         meta: Self::EventMeta,
         file: &mut TrackedReader<Self::Spec>,
     ) -> RuntimeResult<()> {
-        let expected_checksum = u64::from_le_bytes(file.read_block()?);
-        let plen = u64::from_le_bytes(file.read_block()?);
-        let mut pl = vec![0; plen as usize];
-        file.tracked_read(&mut pl)?;
-        let mut this_checksum = SCrc64::new();
-        this_checksum.update(&plen.to_le_bytes());
-        this_checksum.update(&pl);
-        if this_checksum.finish() != expected_checksum {
+        let (pl, checksum_okay) = read_event_payload(file)?;
+        if !checksum_okay {
             return Err(StorageError::RawJournalCorrupted.into());
         }
         <EL as EventLogSpec>::DECODE_DISPATCH
@@ -145,6 +141,24 @@ impl<EL: EventLogSpec> RawJournalAdapter for EventLogAdapter<EL> {
     }
 }
 
+/// Read the payload of an event log event (`[CK][PLEN][PL]`), returning it along with true if its checksum matched
+pub fn read_event_payload<S: FileSpecV1>(
+    file: &mut TrackedReader<S>,
+) -> RuntimeResult<(Vec<u8>, bool)> {
+    let expected_checksum = u64::from_le_bytes(file.read_block()?);
+    let plen = u64::from_le_bytes(file.read_block()?);
+    if !file.has_left(plen) {
+        // don't allocate for a corrupted length
+        return Err(std::io::Error::from(std::io::ErrorKind::InvalidInput).into());
+    }
+    let mut pl = vec![0; plen as usize];
+    file.tracked_read(&mut pl)?;
+    let mut this_checksum = SCrc64::new();
+    this_checksum.update(&plen.to_le_bytes());
+    this_checksum.update(&pl);
+    Ok((pl, this_checksum.finish() == expected_checksum))
+}
+
 /*
     implementation of a batch journal
 
@@ -36,7 +36,7 @@ use {
                 checksum::SCrc64,
                 sdss::sdss_r1::{
                     rw::{SdssFile, TrackedReader, TrackedWriter},
-                    FileSpecV1,
+                    FileSpecV1, SimpleFileSpecV1,
                 },
             },
             RuntimeResult,
@@ -76,6 +76,121 @@ where
     RawJournalWriter::new(initializer, file)
 }
 
+/// Open an existing journal and move it to the current revision of its file specifier. Only use this if every older
+/// revision can be appended to as if it were the current one
+pub fn open_and_upgrade_journal<J: RawJournalAdapter>(
+    log_path: &str,
+    gs: &J::GlobalState,
+) -> RuntimeResult<RawJournalWriter<J>>
+where
+    J::Spec: SimpleFileSpecV1 + FileSpecV1<DecodeArgs = ()>,
+{
+    let log = SdssFile::<J::Spec>::open(log_path)?;
+    let (initializer, mut file) = RawJournalReader::<J>::scroll(log, gs)?;
+    // only upgrade once the whole journal has been read without errors
+    file.upgrade_file_specifier_version()?;
+    RawJournalWriter::new(initializer, file)
+}
+
+/// Read and validate an existing journal, without opening it for writing
+pub fn verify_journal<J: RawJournalAdapter>(
+    log_path: &str,
+    gs: &J::GlobalState,
+) -> RuntimeResult<JournalInitializer>
+where
+    J::Spec: FileSpecV1<DecodeArgs = ()>,
+{
+    let log = SdssFile::<J::Spec>::open(log_path)?;
+    RawJournalReader::<J>::scroll(log, gs).map(|(initializer, _)| initializer)
+}
+
+#[derive(Debug, PartialEq)]
+/// An event found by [`scan_journal`]
+pub enum ScannedEvent<M> {
+    /// a server event, with its metadata (`None` if it is unknown)
+    Server(Option<M>),
+    /// a driver event (`None` if the event is corrupted)
+    Driver(Option<DriverEventKind>),
+}
+
+/// Read through every event in an existing journal without applying any of them. Every event is passed to `f` along
+/// with its ID and the reader, which (for a server event) is right before the event's payload so that `f` must read
+/// it. Unlike [`verify_journal`], this does not stop at a corrupted event unless the journal can't be read any further
+pub fn scan_journal<J, F>(log_path: &str, mut f: F) -> RuntimeResult<()>
+where
+    J: RawJournalAdapter,
+    J::Spec: FileSpecV1<DecodeArgs = ()>,
+    F: FnMut(u64, ScannedEvent<J::EventMeta>, &mut TrackedReader<J::Spec>) -> RuntimeResult<()>,
+{
+    let log = SdssFile::<J::Spec>::open(log_path)?;
+    let mut tr = TrackedReader::with_cursor(log, <J::Spec as FileSpecV1>::SIZE as u64)?;
+    while !tr.is_eof() {
+        let txn_id = u128::from_le_bytes(tr.read_block()?);
+        let meta = u64::from_le_bytes(tr.read_block()?);
+        if meta & SERVER_EV_MASK != 0 {
+            let meta = J::parse_event_meta(meta & !SERVER_EV_MASK);
+            f(txn_id as u64, ScannedEvent::Server(meta), &mut tr)?;
+            continue;
+        }
+        let mut block = [0u8; DriverEvent::FULL_EVENT_SIZE];
+        block[DriverEvent::OFFSET_0_TXN_ID].copy_from_slice(&txn_id.to_le_bytes());
+        block[DriverEvent::OFFSET_1_EVENT_KIND].copy_from_slice(&meta.to_le_bytes());
+        tr.tracked_read(&mut block[DriverEvent::OFFSET_2_CHECKSUM.start..])?;
+        let event = DriverEvent::decode(block).map(|ev| ev.event);
+        f(txn_id as u64, ScannedEvent::Driver(event), &mut tr)?;
+    }
+    Ok(())
+}
+
+/// Reopen a journal that was previously released using [`RawJournalWriter::detach`] (it may have been moved since)
+/// without reading through it again
+pub fn reattach_journal<J: RawJournalAdapter>(
+    log_path: &str,
+    initializer: JournalInitializer,
+) -> RuntimeResult<RawJournalWriter<J>>
+where
+    J::Spec: FileSpecV1<DecodeArgs = ()>,
+{
+    let mut log = SdssFile::<J::Spec>::open(log_path)?;
+    log.seek_from_start(initializer.cursor())?;
+    RawJournalWriter::new(initializer, log)
+}
+
+/// Apply the events in an existing journal up to (and including) the event with the ID `until` and cut the journal off
+/// right after it. The journal is closed again, so it can be opened like any other journal. Returns the ID of the last
+/// event that was kept
+pub fn truncate_journal<J: RawJournalAdapter>(
+    log_path: &str,
+    gs: &J::GlobalState,
+    until: u64,
+) -> RuntimeResult<u64>
+where
+    J::Spec: FileSpecV1<DecodeArgs = ()>,
+{
+    let log = SdssFile::<J::Spec>::open(log_path)?;
+    let (initializer, mut log, stopped_early) =
+        RawJournalReader::<J>::scroll_until(log, gs, until)?;
+    let last_txn_id = initializer.last_txn_id();
+    if stopped_early {
+        log.truncate(initializer.cursor())?;
+        log.seek_from_start(initializer.cursor())?;
+        // we stopped right after an event (and not at a close), so there's nothing to reopen
+        let mut writer = RawJournalWriter::<J> {
+            log_file: TrackedWriter::with_cursor_and_checksum(
+                log,
+                initializer.cursor(),
+                initializer.checksum(),
+            ),
+            known_txn_id: initializer.last_txn_id(),
+            known_txn_offset: initializer.last_offset(),
+            txn_id: initializer.txn_id(),
+            j: J::initialize(&initializer),
+        };
+        RawJournalWriter::close_driver(&mut writer)?;
+    }
+    Ok(last_txn_id)
+}
+
 #[derive(Debug)]
 pub struct JournalInitializer {
     cursor: u64,
@@ -452,7 +567,7 @@ impl DriverEvent {
 
 #[derive(Debug, PartialEq, Clone, Copy, sky_macros::EnumMethods, sky_macros::TaggedEnum)]
 #[repr(u8)]
-pub(super) enum DriverEventKind {
+pub enum DriverEventKind {
     Reopened = 0,
     Closed = 1,
 }
@@ -518,6 +633,39 @@ impl<J: RawJournalAdapter> RawJournalWriter<J> {
         &mut self,
         event: E,
         ctx: J::CommitContext,
+    ) -> RuntimeResult<()> {
+        self._commit_with_ctx(event, ctx, J::AUTO_SYNC_ON_EVENT_COMMIT)
+    }
+    /// Commit a new event to the journal, but only flush the buffer (irrespective of the adapter's sync preference).
+    ///
+    /// The event is **not durable** until [`Self::sync`] is called. This is used to group commits to several journals
+    /// into a single sync window
+    pub fn commit_with_ctx_deferred_sync<E: RawJournalAdapterEvent<J>>(
+        &mut self,
+        event: E,
+        ctx: J::CommitContext,
+    ) -> RuntimeResult<()> {
+        self._commit_with_ctx(event, ctx, false)?;
+        self.log_file.flush_buf()?;
+        // with direct commits the event was only buffered, so the cursor has just moved past it (and the next driver
+        // event has to point at its end)
+        self.known_txn_offset = self.log_file.cursor();
+        Ok(())
+    }
+    /// Sync (data and metadata) everything that has been written to the journal so far
+    pub fn sync(&mut self) -> RuntimeResult<()> {
+        self.log_file.flush_sync()?;
+        Ok(())
+    }
+    /// Returns the number of bytes that have been written to the journal so far
+    pub fn cursor(&self) -> u64 {
+        self.log_file.cursor()
+    }
+    fn _commit_with_ctx<E: RawJournalAdapterEvent<J>>(
+        &mut self,
+        event: E,
+        ctx: J::CommitContext,
+        sync: bool,
     ) -> RuntimeResult<()> {
         self.txn_context(|me, txn_id| {
             let ev_md = event.md();
@@ -548,7 +696,7 @@ impl<J: RawJournalAdapter> RawJournalWriter<J> {
                 }
             }
             jtrace_writer!(CommitServerEventAdapterCompleted);
-            if J::AUTO_SYNC_ON_EVENT_COMMIT {
+            if sync {
                 // should fsync after event
                 log_file.flush_sync()?;
                 jtrace_writer!(CommitCommitServerEventSyncCompleted);
@@ -631,6 +779,16 @@ impl<J: RawJournalAdapter> RawJournalWriter<J> {
         Self::_commit_driver_event(me, DriverEventKind::Reopened)?;
         Ok(())
     }
+    /// Close the driver and release the file, returning the state needed to [`reattach_journal`] it
+    pub fn detach(mut self) -> RuntimeResult<JournalInitializer> {
+        Self::close_driver(&mut self)?;
+        Ok(JournalInitializer::new(
+            self.log_file.cursor(),
+            self.log_file.checksum(),
+            self.txn_id,
+            self.known_txn_offset,
+        ))
+    }
 }
 
 pub struct RawJournalReader<J: RawJournalAdapter> {
@@ -662,6 +820,15 @@ impl<J: RawJournalAdapter> RawJournalReader<J> {
         file: SdssFile<<J as RawJournalAdapter>::Spec>,
         gs: &J::GlobalState,
     ) -> RuntimeResult<(JournalInitializer, SdssFile<J::Spec>)> {
+        Self::scroll_until(file, gs, u64::MAX).map(|(initializer, file, _)| (initializer, file))
+    }
+    /// Same as [`Self::scroll`], but stops at the first event after the event with ID `until`. Also returns true if
+    /// it stopped before reaching the end of the journal
+    pub fn scroll_until(
+        file: SdssFile<<J as RawJournalAdapter>::Spec>,
+        gs: &J::GlobalState,
+        until: u64,
+    ) -> RuntimeResult<(JournalInitializer, SdssFile<J::Spec>, bool)> {
         let reader = TrackedReader::with_cursor(
             file,
             <<J as RawJournalAdapter>::Spec as FileSpecV1>::SIZE as u64,
@@ -669,8 +836,11 @@ impl<J: RawJournalAdapter> RawJournalReader<J> {
         jtrace_reader!(Initialized);
         let mut me = Self::new(reader, 0, 0, 0, 0);
         loop {
-            if me._apply_next_event_and_stop(gs)? {
-                jtrace_reader!(Completed);
+            let stop_early = me.txn_id > until;
+            if stop_early || me._apply_next_event_and_stop(gs)? {
+                if !stop_early {
+                    jtrace_reader!(Completed);
+                }
                 let initializer = JournalInitializer::new(
                     me.tr.cursor(),
                     me.tr.checksum(),
@@ -679,7 +849,7 @@ impl<J: RawJournalAdapter> RawJournalReader<J> {
                     me.last_txn_offset,
                 );
                 let file = me.tr.into_inner();
-                return Ok((initializer, file));
+                return Ok((initializer, file, stop_early));
             }
         }
     }
@@ -26,14 +26,18 @@
 
 use {
     super::{
-        create_journal, open_journal, CommitPreference, DriverEvent, DriverEventKind,
-        JournalInitializer, RawJournalAdapter, RawJournalAdapterEvent, RawJournalWriter,
+        create_journal, open_journal, reattach_journal, truncate_journal, CommitPreference,
+        DriverEvent, DriverEventKind, JournalInitializer, RawJournalAdapter,
+        RawJournalAdapterEvent, RawJournalWriter,
     },
     crate::engine::{
         error::StorageError,
         fractal::error::ErrorContext,
         storage::{
-            common::sdss::sdss_r1::rw::TrackedReader,
+            common::{
+                interface::vfs::{Fault, VirtualFS},
+                sdss::sdss_r1::rw::TrackedReader,
+            },
             v2::raw::{
                 journal::raw::{JournalReaderTraceEvent, JournalWriterTraceEvent},
                 spec::SystemDatabaseV1,
@@ -466,3 +470,172 @@ fn multi_boot() {
         RawJournalWriter::close_driver(&mut j).unwrap();
     }
 }
+
+#[test]
+fn journal_detach_reattach() {
+    {
+        let mut j = create_journal::<SimpleDBJournal>("detach_reattach").unwrap();
+        let mut db = SimpleDB::new();
+        db.push(&mut j, "key_a").unwrap();
+        let initializer = j.detach().unwrap();
+        let mut j = reattach_journal::<SimpleDBJournal>("detach_reattach", initializer).unwrap();
+        db.push(&mut j, "key_b").unwrap();
+        RawJournalWriter::close_driver(&mut j).unwrap();
+    }
+    {
+        let db = SimpleDB::new();
+        let mut j = open_journal::<SimpleDBJournal>("detach_reattach", &db).unwrap();
+        assert_eq!(
+            db.data().as_ref(),
+            vec!["key_a".to_string(), "key_b".to_string()]
+        );
+        RawJournalWriter::close_driver(&mut j).unwrap();
+    }
+}
+
+#[test]
+fn journal_deferred_sync() {
+    {
+        let mut j = create_journal::<SimpleDBJournal>("deferred_sync").unwrap();
+        let start = j.cursor();
+        j.commit_with_ctx_deferred_sync(DbEventPush("key_a"), ())
+            .unwrap();
+        j.commit_with_ctx_deferred_sync(DbEventPush("key_b"), ())
+            .unwrap();
+        assert!(j.cursor() > start);
+        j.sync().unwrap();
+        RawJournalWriter::close_driver(&mut j).unwrap();
+    }
+    {
+        let db = SimpleDB::new();
+        let mut j = open_journal::<SimpleDBJournal>("deferred_sync", &db).unwrap();
+        assert_eq!(
+            db.data().as_ref(),
+            vec!["key_a".to_string(), "key_b".to_string()]
+        );
+        RawJournalWriter::close_driver(&mut j).unwrap();
+    }
+}
+
+#[test]
+fn journal_truncate() {
+    {
+        let mut j = create_journal::<SimpleDBJournal>("truncate").unwrap();
+        let mut db = SimpleDB::new();
+        db.push(&mut j, "key_a").unwrap(); // 0
+        RawJournalWriter::close_driver(&mut j).unwrap(); // 1
+    }
+    {
+        let mut db = SimpleDB::new();
+        let mut j = open_journal::<SimpleDBJournal>("truncate", &db).unwrap(); // 2
+        db.push(&mut j, "key_b").unwrap(); // 3
+        db.push(&mut j, "key_c").unwrap(); // 4
+        RawJournalWriter::close_driver(&mut j).unwrap(); // 5
+    }
+    {
+        let db = SimpleDB::new();
+        assert_eq!(
+            truncate_journal::<SimpleDBJournal>("truncate", &db, 3).unwrap(),
+            3
+        );
+        assert_eq!(
+            db.data().as_ref(),
+            vec!["key_a".to_string(), "key_b".to_string()]
+        );
+    }
+    {
+        // the truncated journal can be opened and written to like any other journal
+        let mut db = SimpleDB::new();
+        let mut j = open_journal::<SimpleDBJournal>("truncate", &db).unwrap();
+        assert_eq!(
+            db.data().as_ref(),
+            vec!["key_a".to_string(), "key_b".to_string()]
+        );
+        db.push(&mut j, "key_d").unwrap();
+        RawJournalWriter::close_driver(&mut j).unwrap();
+    }
+    {
+        // cutting past the end leaves the journal as is
+        let db = SimpleDB::new();
+        truncate_journal::<SimpleDBJournal>("truncate", &db, 100).unwrap();
+        assert_eq!(
+            db.data().as_ref(),
+            vec![
+                "key_a".to_string(),
+                "key_b".to_string(),
+                "key_d".to_string()
+            ]
+        );
+    }
+}
+
+/*
+    crash consistency tests
+*/
+
+#[test]
+fn journal_crash_consistency() {
+    // 3 writes are pending at the time of the crash: key_c, key_d (both unsynced) and the close event
+    const PENDING_WRITES: usize = 3;
+    let mut faults = vec![Fault::DropUnsynced];
+    for n in 0..=PENDING_WRITES {
+        faults.push(Fault::KeepWrites(n));
+        faults.push(Fault::SkipWrite(n));
+        for bytes in [0, 1, 8, 16, 24, 31] {
+            faults.push(Fault::TornWrite { writes: n, bytes });
+        }
+    }
+    let all_keys = ["key_a", "key_b", "key_c", "key_d"].map(String::from);
+    for (i, fault) in faults.into_iter().enumerate() {
+        let path = format!("crash_consistency_{i}");
+        {
+            let mut j = create_journal::<SimpleDBJournal>(&path).unwrap();
+            let mut db = SimpleDB::new();
+            db.push(&mut j, "key_a").unwrap(); // 0
+            db.push(&mut j, "key_b").unwrap(); // 1
+            j.commit_with_ctx_deferred_sync(DbEventPush("key_c"), ())
+                .unwrap(); // 2
+            j.commit_with_ctx_deferred_sync(DbEventPush("key_d"), ())
+                .unwrap(); // 3
+            RawJournalWriter::close_driver(&mut j).unwrap(); // 4
+        }
+        VirtualFS::instance()
+            .read()
+            .crash_file(&path, fault)
+            .unwrap();
+        let fully_persisted = match fault {
+            Fault::DropUnsynced => false,
+            Fault::KeepWrites(n) | Fault::SkipWrite(n) | Fault::TornWrite { writes: n, .. } => {
+                n >= PENDING_WRITES
+            }
+        };
+        {
+            // we either read back everything or refuse to open the journal; never anything in between
+            let db = SimpleDB::new();
+            match open_journal::<SimpleDBJournal>(&path, &db) {
+                Ok(mut j) => {
+                    assert_eq!(db.data().as_slice(), &all_keys[..], "{fault:?}");
+                    RawJournalWriter::close_driver(&mut j).unwrap();
+                }
+                Err(e) => assert!(!fully_persisted, "{fault:?}: {e}"),
+            }
+        }
+        {
+            // the synced events can always be restored
+            let db = SimpleDB::new();
+            assert_eq!(
+                truncate_journal::<SimpleDBJournal>(&path, &db, 1).unwrap(),
+                1,
+                "{fault:?}"
+            );
+            assert_eq!(db.data().as_slice(), &all_keys[..2], "{fault:?}");
+        }
+        {
+            let mut db = SimpleDB::new();
+            let mut j = open_journal::<SimpleDBJournal>(&path, &db).unwrap();
+            assert_eq!(db.data().as_slice(), &all_keys[..2], "{fault:?}");
+            db.push(&mut j, "key_e").unwrap();
+            RawJournalWriter::close_driver(&mut j).unwrap();
+        }
+    }
+}
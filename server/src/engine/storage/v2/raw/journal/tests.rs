@@ -218,6 +218,36 @@ fn test_this_data() {
     }
 }
 
+#[test]
+fn test_deferred_sync_then_close() {
+    // the driver events that follow a deferred commit must point at the end of it
+    {
+        let mut log = create_journal::<EventLogAdapter<TestDBAdapter>>("jrnl_deferred").unwrap();
+        log.commit_event(EventPush("acai berry")).unwrap();
+        log.commit_with_ctx_deferred_sync(EventPush("billberry"), ())
+            .unwrap();
+        RawJournalWriter::close_driver(&mut log).unwrap();
+    }
+    {
+        let db = TestDB::default();
+        let mut log = open_journal("jrnl_deferred", &db).unwrap();
+        assert_eq!(db._ref().as_slice(), ["acai berry", "billberry"]);
+        log.commit_with_ctx_deferred_sync(EventPush("cranberry"), ())
+            .unwrap();
+        log.sync().unwrap();
+        RawJournalWriter::close_driver(&mut log).unwrap();
+    }
+    {
+        let db = TestDB::default();
+        let mut log = open_journal::<EventLogAdapter<TestDBAdapter>>("jrnl_deferred", &db).unwrap();
+        assert_eq!(
+            db._ref().as_slice(),
+            ["acai berry", "billberry", "cranberry"]
+        );
+        RawJournalWriter::close_driver(&mut log).unwrap();
+    }
+}
+
 /*
     batch test
 */
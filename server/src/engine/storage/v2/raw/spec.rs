@@ -24,9 +24,12 @@
  *
 */
 
-use crate::engine::storage::common::{
-    sdss::{self, sdss_r1::HeaderV1},
-    versions::{self, DriverVersion, FileSpecifierVersion, ServerVersion},
+use crate::engine::{
+    error::{RuntimeResult, StorageError},
+    storage::common::{
+        sdss::{self, sdss_r1::HeaderV1},
+        versions::{self, DriverVersion, FileSpecifierVersion, ServerVersion},
+    },
 };
 
 #[allow(unused)]
@@ -48,6 +51,7 @@ pub type Header = HeaderV1<HeaderImplV2>;
 pub enum FileClass {
     EventLog = 0,
     Batch = 1,
+    Metadata = 2,
 }
 
 #[derive(
@@ -65,6 +69,8 @@ pub enum FileClass {
 pub enum FileSpecifier {
     GlobalNS = 0,
     ModelData = 1,
+    Lineage = 2,
+    TrashMeta = 3,
 }
 
 #[derive(Debug)]
@@ -81,13 +87,87 @@ impl sdss::sdss_r1::SimpleFileSpecV1 for SystemDatabaseV1 {
     type HeaderSpec = HeaderImplV2;
     const FILE_CLASS: FileClass = FileClass::EventLog;
     const FILE_SPECIFIER: FileSpecifier = FileSpecifier::GlobalNS;
-    const FILE_SPECFIER_VERSION: FileSpecifierVersion = FileSpecifierVersion::__new(0);
+    /// A revision only ever adds to the format (new events, or new data in existing ones), so a log of an older revision
+    /// is moved to the current one as soon as it is opened. That way an older server refuses to open it instead of
+    /// failing on something that it doesn't know. The revisions are:
+    /// - 1: scheduled tasks (`create_task`, `alter_task` and `drop_task`)
+    /// - 2: read-only mode (`set_read_only`)
+    /// - 3: per-user write quotas (`alter_user_limits`)
+    /// - 4: list bounds (`max_len` and `element_max` layer properties)
+    /// - 5: model options, like the primary index kind (in bytes of a model's layout that older revisions leave unset,
+    ///   so that their models decode to the defaults)
+    /// - 6: secondary indexes (`create_index` and `drop_index`)
+    /// - 7: batches of events that are committed as a single record (`batch`)
+    /// - 8: model ttls (in the model options)
+    /// - 9: map fields (a new layer tag)
+    /// - 10: append-only models (in the model options)
+    /// - 11: unique fields (in the field metadata)
+    /// - 12: model options in a field of their own, instead of the primary key tag's qword
+    const FILE_SPECFIER_VERSION: FileSpecifierVersion = FileSpecifierVersion::__new(12);
+    fn check_if_file_specifier_revision_is_compatible(
+        v: FileSpecifierVersion,
+    ) -> RuntimeResult<()> {
+        // every older revision is a subset of the current one
+        if v <= Self::FILE_SPECFIER_VERSION {
+            Ok(())
+        } else {
+            Err(StorageError::HeaderDecodeVersionMismatch.into())
+        }
+    }
 }
 
 pub struct ModelDataBatchAofV1;
+impl ModelDataBatchAofV1 {
+    /// The first revision of the data file that can have partial updates. Files of an older revision are still read,
+    /// but only full rows are written to them so that the servers that created them can still read them
+    pub const REVISION_PARTIAL_UPDATES: FileSpecifierVersion = FileSpecifierVersion::__new(1);
+}
 impl sdss::sdss_r1::SimpleFileSpecV1 for ModelDataBatchAofV1 {
     type HeaderSpec = HeaderImplV2;
     const FILE_CLASS: FileClass = FileClass::Batch;
     const FILE_SPECIFIER: FileSpecifier = FileSpecifier::ModelData;
+    const FILE_SPECFIER_VERSION: FileSpecifierVersion = Self::REVISION_PARTIAL_UPDATES;
+    fn check_if_file_specifier_revision_is_compatible(
+        v: FileSpecifierVersion,
+    ) -> RuntimeResult<()> {
+        // every older revision is a subset of the current one
+        if v <= Self::FILE_SPECFIER_VERSION {
+            Ok(())
+        } else {
+            Err(StorageError::HeaderDecodeVersionMismatch.into())
+        }
+    }
+}
+
+pub struct TrashMetaV1;
+impl sdss::sdss_r1::SimpleFileSpecV1 for TrashMetaV1 {
+    type HeaderSpec = HeaderImplV2;
+    const FILE_CLASS: FileClass = FileClass::Metadata;
+    const FILE_SPECIFIER: FileSpecifier = FileSpecifier::TrashMeta;
+    const FILE_SPECFIER_VERSION: FileSpecifierVersion = FileSpecifierVersion::__new(0);
+}
+
+/// The header for the server lineage file. Unlike all other files, any server and driver version is accepted at decode
+/// time because the whole point of this file is to find out which versions have touched the data directory
+#[derive(Debug)]
+pub struct HeaderImplLineage;
+impl sdss::sdss_r1::HeaderV1Spec for HeaderImplLineage {
+    type FileClass = FileClass;
+    type FileSpecifier = FileSpecifier;
+    const CURRENT_SERVER_VERSION: ServerVersion = versions::v2::V2_SERVER_VERSION;
+    const CURRENT_DRIVER_VERSION: DriverVersion = versions::v2::V2_DRIVER_VERSION;
+    fn check_if_server_version_compatible(_: ServerVersion) -> bool {
+        true
+    }
+    fn check_if_driver_version_compatible(_: DriverVersion) -> bool {
+        true
+    }
+}
+
+pub struct ServerLineageV1;
+impl sdss::sdss_r1::SimpleFileSpecV1 for ServerLineageV1 {
+    type HeaderSpec = HeaderImplLineage;
+    const FILE_CLASS: FileClass = FileClass::Metadata;
+    const FILE_SPECIFIER: FileSpecifier = FileSpecifier::Lineage;
     const FILE_SPECFIER_VERSION: FileSpecifierVersion = FileSpecifierVersion::__new(0);
 }
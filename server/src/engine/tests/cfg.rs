@@ -26,8 +26,10 @@
 
 use crate::{
     engine::config::{
-        self, AuthDriver, CLIConfigParseReturn, ConfigAuth, ConfigEndpoint, ConfigEndpointTcp,
-        ConfigEndpointTls, ConfigMode, ConfigReturn, ConfigSystem, Configuration, ParsedRawArgs,
+        self, AuthDriver, CLIConfigParseReturn, ConfigAuth, ConfigDdl, ConfigEndpoint,
+        ConfigEndpointAdditional, ConfigEndpointTcp, ConfigEndpointTls, ConfigEndpointUds,
+        ConfigKdf, ConfigLogging, ConfigMode, ConfigReturn, ConfigSystem, Configuration,
+        ParsedRawArgs, TlsFiles,
     },
     util::test_utils::with_files,
 };
@@ -99,6 +101,8 @@ fn parse_validate_cli_args() {
                 --endpoint tcp@127.0.0.1:2003 \
                 --endpoint tls@127.0.0.2:2004 \
                 --service-window=600 \
+                --tcp-keepalive=300 \
+                --journal-prealloc=8388608 \
                 --tlskey {pkey} \
                 --tlscert {cert} \
                 --tls-passphrase {pass} \
@@ -119,11 +123,14 @@ fn parse_validate_cli_args() {
                             ConfigEndpointTcp::new("127.0.0.2".into(), 2004),
                             "".into(),
                             "".into(),
-                            "".into()
+                            "".into(),
+                            TlsFiles::new(cert, pkey, pass)
                         )
                     ),
                     ConfigMode::Dev,
-                    ConfigSystem::new(600),
+                    ConfigSystem::new(600)
+                        .with_tcp_keepalive(300)
+                        .with_journal_prealloc(8388608),
                     ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
                 )
             )
@@ -131,6 +138,199 @@ fn parse_validate_cli_args() {
     );
 }
 #[test]
+fn parse_validate_cli_args_ipv6() {
+    let payload = "skyd --endpoint tcp@[::1]:2003 --auth-root-password password12345678";
+    let cfg = extract_cli_args(payload);
+    let ret = config::apply_and_validate::<config::CSCommandLine>(cfg)
+        .unwrap()
+        .into_config();
+    assert_eq!(
+        ret.endpoints,
+        ConfigEndpoint::Insecure(ConfigEndpointTcp::new("::1".into(), 2003))
+    );
+    // unbracketed addresses are ambiguous
+    let payload = "skyd --endpoint tcp@::1:2003 --auth-root-password password12345678";
+    let cfg = extract_cli_args(payload);
+    assert!(config::apply_and_validate::<config::CSCommandLine>(cfg).is_err());
+}
+#[test]
+fn parse_validate_cli_args_additional_endpoints() {
+    let payload = "skyd --endpoint tcp@127.0.0.1:2003 --endpoint tcp@[::1]:2003 \
+        --endpoint uds@/tmp/skyd.sock?auth=off --auth-root-password password12345678";
+    let cfg = extract_cli_args(payload);
+    let ret = config::apply_and_validate::<config::CSCommandLine>(cfg)
+        .unwrap()
+        .into_config();
+    assert_eq!(
+        ret.endpoints,
+        ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003))
+    );
+    assert_eq!(
+        ret.additional_endpoints,
+        vec![
+            ConfigEndpointAdditional::Tcp(ConfigEndpointTcp::new("::1".into(), 2003)),
+            ConfigEndpointAdditional::Uds(ConfigEndpointUds::new("/tmp/skyd.sock".into(), false)),
+        ]
+    );
+}
+#[test]
+fn parse_validate_cli_args_additional_endpoints_bad() {
+    for endpoints in [
+        // bad option
+        "--endpoint uds@/tmp/skyd.sock?auth=maybe",
+        // no path
+        "--endpoint uds@?auth=off",
+        // duplicates
+        "--endpoint tcp@127.0.0.1:2004",
+        "--endpoint uds@/tmp/skyd.sock --endpoint uds@/tmp/skyd.sock",
+    ] {
+        let payload = format!(
+            "skyd --endpoint tcp@127.0.0.1:2004 {endpoints} --auth-root-password password12345678"
+        );
+        let cfg = extract_cli_args(&payload);
+        assert!(
+            config::apply_and_validate::<config::CSCommandLine>(cfg).is_err(),
+            "{endpoints}"
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_journal_prealloc() {
+    for (prealloc, valid) in [("4096", true), ("0", false), ("5000", false)] {
+        let payload = format!(
+            "skyd --endpoint tcp@localhost:2003 --auth-root-password password12345678 --journal-prealloc={prealloc}"
+        );
+        let cfg = extract_cli_args(&payload);
+        assert_eq!(
+            config::apply_and_validate::<config::CSCommandLine>(cfg).is_ok(),
+            valid
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_query_memory_limit() {
+    for (args, expected) in [
+        ("", Some((None, None))),
+        ("--query-memory-limit 1048576", Some((Some(1048576), None))),
+        (
+            "--query-memory-limit 1048576 --query-memory-global-limit 67108864",
+            Some((Some(1048576), Some(67108864))),
+        ),
+        ("--query-memory-limit 0", None),
+        ("--query-memory-global-limit 0", None),
+        ("--query-memory-limit lots", None),
+    ] {
+        let payload = format!(
+            "skyd --endpoint tcp@localhost:2003 --auth-root-password password12345678 {args}"
+        );
+        let cfg = extract_cli_args(&payload);
+        let ret = config::apply_and_validate::<config::CSCommandLine>(cfg).ok();
+        assert_eq!(
+            ret.map(|cfg| {
+                let system = cfg.into_config().system;
+                (system.query_memory_limit, system.query_memory_global_limit)
+            }),
+            expected
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_user_write_limits() {
+    for (args, expected) in [
+        ("", Some((None, None))),
+        ("--user-write-ops-limit 100", Some((Some(100), None))),
+        (
+            "--user-write-ops-limit 100 --user-write-bytes-limit 1048576",
+            Some((Some(100), Some(1048576))),
+        ),
+        ("--user-write-ops-limit 0", None),
+        ("--user-write-bytes-limit 0", None),
+    ] {
+        let payload = format!(
+            "skyd --endpoint tcp@localhost:2003 --auth-root-password password12345678 {args}"
+        );
+        let cfg = extract_cli_args(&payload);
+        let ret = config::apply_and_validate::<config::CSCommandLine>(cfg).ok();
+        assert_eq!(
+            ret.map(|cfg| {
+                let system = cfg.into_config().system;
+                (system.user_write_ops_limit, system.user_write_bytes_limit)
+            }),
+            expected
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_size_limits() {
+    for (args, expected) in [
+        ("", Some((None, None))),
+        ("--row-size-limit 1048576", Some((Some(1048576), None))),
+        (
+            "--row-size-limit 1048576 --field-size-limit 65536",
+            Some((Some(1048576), Some(65536))),
+        ),
+        ("--row-size-limit 0", None),
+        ("--field-size-limit 0", None),
+        ("--field-size-limit 1k", None),
+    ] {
+        let payload = format!(
+            "skyd --endpoint tcp@localhost:2003 --auth-root-password password12345678 {args}"
+        );
+        let cfg = extract_cli_args(&payload);
+        let ret = config::apply_and_validate::<config::CSCommandLine>(cfg).ok();
+        assert_eq!(
+            ret.map(|cfg| {
+                let system = cfg.into_config().system;
+                (system.row_size_limit, system.field_size_limit)
+            }),
+            expected
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_otlp_endpoint() {
+    for (args, expected) in [
+        ("", Some(None)),
+        (
+            "--otlp-endpoint http://localhost:4318",
+            Some(Some("http://localhost:4318".to_owned())),
+        ),
+        ("--otlp-endpoint https://localhost:4318", None),
+        ("--otlp-endpoint localhost:4318", None),
+    ] {
+        let payload = format!(
+            "skyd --endpoint tcp@localhost:2003 --auth-root-password password12345678 {args}"
+        );
+        let cfg = extract_cli_args(&payload);
+        let ret = config::apply_and_validate::<config::CSCommandLine>(cfg).ok();
+        assert_eq!(
+            ret.map(|cfg| cfg.into_config().system.otlp_endpoint),
+            expected
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_force_downgrade_check_off() {
+    for (switch, expected) in [
+        ("", Some(false)),
+        ("--force-downgrade-check-off", Some(true)),
+        ("--force-downgrade-check-off=false", Some(false)),
+        ("--force-downgrade-check-off=maybe", None),
+    ] {
+        // the switch doesn't swallow the option that follows it
+        let payload = format!(
+            "skyd {switch} --endpoint tcp@localhost:2003 --auth-root-password password12345678"
+        );
+        let cfg = extract_cli_args(&payload);
+        let ret = config::apply_and_validate::<config::CSCommandLine>(cfg);
+        assert_eq!(
+            ret.ok()
+                .map(|cfg| cfg.into_config().system.force_downgrade_check_off),
+            expected
+        );
+    }
+}
+#[test]
 fn parse_validate_cli_args_help_and_version() {
     let pl1 = "skyd --help";
     let pl2 = "skyd --version";
@@ -236,7 +436,8 @@ fn parse_validate_env_args() {
                             ConfigEndpointTcp::new("localhost".into(), 8081),
                             "".into(),
                             "".into(),
-                            "".into()
+                            "".into(),
+                            TlsFiles::new(cert, key, pass)
                         )
                     ),
                     ConfigMode::Dev,
@@ -251,6 +452,8 @@ const CONFIG_FILE: &str = "\
 system:
   mode: dev
   rs_window: 600
+  tcp_keepalive: 120
+  journal_prealloc: 4194304
 
 auth:
   plugin: pwd
@@ -288,14 +491,316 @@ fn test_config_file() {
                             ConfigEndpointTcp::new("127.0.0.1".into(), 2004),
                             "".into(),
                             "".into(),
-                            "".into()
+                            "".into(),
+                            TlsFiles::new(
+                                "._test_sample_cert.pem",
+                                "._test_sample_private.key",
+                                "._test_sample_private.pass.txt",
+                            )
                         )
                     ),
                     ConfigMode::Dev,
-                    ConfigSystem::new(600),
+                    ConfigSystem::new(600)
+                        .with_tcp_keepalive(120)
+                        .with_journal_prealloc(4194304),
                     ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
                 )
             )
         },
     )
 }
+const CONFIG_FILE_PROXY: &str = "\
+auth:
+  plugin: pwd
+  root_pass: password12345678
+
+endpoints:
+  insecure:
+    host: 10.0.0.2
+    port: 2003
+    proxy_protocol: true
+    ";
+#[test]
+fn test_config_file_proxy_protocol() {
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg.endpoints,
+        ConfigEndpoint::Insecure(
+            ConfigEndpointTcp::new("10.0.0.2".into(), 2003).with_proxy_protocol()
+        )
+    );
+}
+const CONFIG_FILE_ADDITIONAL_ENDPOINTS: &str = "\
+auth:
+  plugin: pwd
+  root_pass: password12345678
+
+endpoints:
+  insecure:
+    host: 127.0.0.1
+    port: 2003
+  additional:
+    - tcp@0.0.0.0:2005
+    - uds@/run/skyd.sock
+    ";
+#[test]
+fn test_config_file_additional_endpoints() {
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_file_src(CONFIG_FILE_ADDITIONAL_ENDPOINTS);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg.additional_endpoints,
+        vec![
+            ConfigEndpointAdditional::Tcp(ConfigEndpointTcp::new("0.0.0.0".into(), 2005)),
+            ConfigEndpointAdditional::Uds(ConfigEndpointUds::new("/run/skyd.sock".into(), true)),
+        ]
+    );
+}
+#[test]
+fn test_config_file_additional_tls_without_main() {
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_file_src(&CONFIG_FILE_ADDITIONAL_ENDPOINTS.replace("tcp@0.0.0.0", "tls@0.0.0.0"));
+    // there's no TLS endpoint to take the certificate from
+    assert!(config::check_configuration().is_err());
+}
+const CONFIG_FILE_KDF: &str = "\
+auth:
+  plugin: pwd
+  root_pass: password12345678
+  kdf:
+    algorithm: argon2id
+    memory: 65536
+    ";
+#[test]
+fn test_config_file_kdf() {
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_file_src(CONFIG_FILE_KDF);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg.auth,
+        ConfigAuth::new_with_kdf(
+            AuthDriver::Pwd,
+            "password12345678".into(),
+            ConfigKdf::Argon2id {
+                memory: 65536,
+                iterations: 2,
+                parallelism: 1
+            }
+        )
+    );
+}
+
+/*
+    config key overrides
+*/
+
+#[test]
+fn test_config_file_overrides() {
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--config=config.yml".into(),
+        "--system.rs_window=300".into(),
+        "--endpoints.insecure.port=2005".into(),
+    ]);
+    config::set_env_src(vec![
+        "SKYDB_SYSTEM__RS_WINDOW=900".into(),
+        "SKYDB_SYSTEM__TCP_KEEPALIVE=60".into(),
+        "SKYDB_ENDPOINTS__INSECURE__PORT=2004".into(),
+    ]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    let cfg = config::check_configuration().unwrap().into_config();
+    // the CLI wins over env, which wins over the file
+    assert_eq!(cfg.system, ConfigSystem::new(300).with_tcp_keepalive(60));
+    assert_eq!(
+        cfg.endpoints,
+        ConfigEndpoint::Insecure(
+            ConfigEndpointTcp::new("10.0.0.2".into(), 2005).with_proxy_protocol()
+        )
+    );
+}
+
+#[test]
+fn test_config_overrides_without_file() {
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--auth.root_pass=password=12345678".into(),
+        "--auth.kdf.algorithm=bcrypt".into(),
+    ]);
+    config::set_env_src(vec![
+        "SKYDB_AUTH__PLUGIN=pwd".into(),
+        "SKYDB_SYSTEM__MODE=prod".into(),
+    ]);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(cfg.mode, ConfigMode::Prod);
+    assert_eq!(
+        cfg.auth,
+        ConfigAuth::new_with_kdf(
+            AuthDriver::Pwd,
+            "password=12345678".into(),
+            ConfigKdf::Bcrypt
+        )
+    );
+}
+
+#[test]
+fn test_config_overrides_bad() {
+    // unknown key
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--config=config.yml".into(),
+        "--system.mdoe=prod".into(),
+    ]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    assert!(config::check_configuration().is_err());
+    // bad value
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_env_src(vec!["SKYDB_SYSTEM__RS_WINDOW=soon".into()]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    assert!(config::check_configuration().is_err());
+    // can't mix with the older options
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--mode=dev".into(),
+        "--system.rs_window=300".into(),
+    ]);
+    assert!(config::check_configuration().is_err());
+}
+
+#[test]
+fn test_config_logging() {
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--config=config.yml".into(),
+        "--logging.max_size=1048576".into(),
+    ]);
+    config::set_env_src(vec![
+        "SKYDB_LOGGING__FILE=/var/log/skyd.log".into(),
+        "SKYDB_LOGGING__COMPRESS=false".into(),
+    ]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg.logging,
+        ConfigLogging {
+            file: Some("/var/log/skyd.log".into()),
+            max_size: Some(1048576),
+            rotate_interval: None,
+            compress: false,
+            retain: ConfigLogging::DEFAULT_RETAIN,
+        }
+    );
+    // rotation needs a file
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--config=config.yml".into(),
+        "--logging.rotate_interval=86400".into(),
+    ]);
+    config::set_env_src(vec![]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    assert!(config::check_configuration().is_err());
+}
+
+#[test]
+fn test_config_trash_retention() {
+    assert_eq!(config::parse_duration("90s"), Some(90));
+    assert_eq!(config::parse_duration("24h"), Some(86400));
+    assert_eq!(config::parse_duration("7d"), Some(604800));
+    assert_eq!(config::parse_duration("24"), None);
+    assert_eq!(config::parse_duration("h"), None);
+    config::set_cli_src(vec![
+        "skyd".into(),
+        "--config=config.yml".into(),
+        "--ddl.trash_retention=24h".into(),
+    ]);
+    config::set_env_src(vec![]);
+    config::set_file_src(CONFIG_FILE_PROXY);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg.ddl,
+        ConfigDdl {
+            trash_retention: Some(86400)
+        }
+    );
+    for bad in ["24", "0h", "1w"] {
+        config::set_cli_src(vec![
+            "skyd".into(),
+            "--config=config.yml".into(),
+            format!("--ddl.trash_retention={bad}"),
+        ]);
+        config::set_file_src(CONFIG_FILE_PROXY);
+        assert!(config::check_configuration().is_err(), "{bad}");
+    }
+}
+
+/*
+    config subcommands
+*/
+
+#[test]
+fn test_default_config_file() {
+    let default_cfg = config::default_config_file();
+    // the only thing missing is the root password
+    let issues = config::validate_config_file(&default_cfg);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].to_string().contains("auth.root_pass"));
+    let cfg_file = default_cfg.replace("  # root_pass:\n", "  root_pass: password12345678\n");
+    assert_eq!(config::validate_config_file(&cfg_file), vec![]);
+    // and it's the same as running with no configuration at all
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_file_src(&cfg_file);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(crate::engine::fractal::GENERAL_EXECUTOR_WINDOW),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+}
+
+#[test]
+fn test_validate_config_file() {
+    let issues: Vec<String> = config::validate_config_file(
+        "\
+system:
+  mode: staging
+  rs_window: 600
+  journal_prealloc: 5000
+  color: blue
+auth:
+  plugin: pwd
+  root_pass: short
+endpoints:
+  insecure:
+    port: 70000
+",
+    )
+    .into_iter()
+    .map(|issue| issue.to_string())
+    .collect();
+    assert_eq!(
+        issues,
+        [
+            "line 2: invalid value \"staging\" for `system.mode`. expected one of `dev`, `prod`",
+            "line 4: invalid value 5000 for `system.journal_prealloc`. expected an integer >= 4096 that is a multiple of 4096",
+            "line 5: unknown key `system.color`",
+            "line 8: invalid value \"short\" for `auth.root_pass`. expected a string with at least 16 characters",
+            "line 10: missing required key `endpoints.insecure.host` (the host for the TCP endpoint). expected a string",
+            "line 11: invalid value 70000 for `endpoints.insecure.port`. expected an integer between 1 and 65535",
+        ]
+    );
+    assert_eq!(
+        config::validate_config_file("system: [1, 2]\n")
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>(),
+        [
+            "missing required section `auth`",
+            "line 1: `system` must be a section but found a list",
+        ]
+    );
+}
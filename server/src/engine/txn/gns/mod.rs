@@ -24,6 +24,8 @@
  *
 */
 
+use crate::util::compiler::TaggedEnum;
+
 macro_rules! impl_gns_event {
     ($($item:ty = $variant:ident),* $(,)?) => {
         $(impl crate::engine::txn::gns::GNSTransaction for $item { const CODE: crate::engine::txn::gns::GNSTransactionCode = crate::engine::txn::gns::GNSTransactionCode::$variant;})*
@@ -33,6 +35,7 @@ macro_rules! impl_gns_event {
 pub mod model;
 pub mod space;
 pub mod sysctl;
+pub mod task;
 
 #[derive(Debug, PartialEq, Clone, Copy, sky_macros::TaggedEnum)]
 #[repr(u8)]
@@ -48,8 +51,86 @@ pub enum GNSTransactionCode {
     CreateUser = 8,
     AlterUser = 9,
     DropUser = 10,
+    CreateTask = 11,
+    AlterTask = 12,
+    DropTask = 13,
+    SetReadOnly = 14,
+    AlterUserLimits = 15,
+    CreateIndex = 16,
+    DropIndex = 17,
+    Batch = 18,
+}
+
+impl GNSTransactionCode {
+    /// The name of this event (as in `skyd decode-gns`)
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::CreateSpace => "create_space",
+            Self::AlterSpace => "alter_space",
+            Self::DropSpace => "drop_space",
+            Self::CreateModel => "create_model",
+            Self::AlterModelAdd => "alter_model_add",
+            Self::AlterModelRemove => "alter_model_remove",
+            Self::AlterModelUpdate => "alter_model_update",
+            Self::DropModel => "drop_model",
+            Self::CreateUser => "create_user",
+            Self::AlterUser => "alter_user",
+            Self::DropUser => "drop_user",
+            Self::CreateTask => "create_task",
+            Self::AlterTask => "alter_task",
+            Self::DropTask => "drop_task",
+            Self::SetReadOnly => "set_read_only",
+            Self::AlterUserLimits => "alter_user_limits",
+            Self::CreateIndex => "create_index",
+            Self::DropIndex => "drop_index",
+            Self::Batch => "batch",
+        }
+    }
 }
 
 pub trait GNSTransaction {
     const CODE: GNSTransactionCode;
 }
+
+impl_gns_event!(BatchTxn<'_> = Batch);
+
+#[derive(Debug, Default)]
+/// The events of a [`BatchTxn`]
+pub struct BatchEvents {
+    count: u64,
+    events: Vec<u8>,
+}
+
+impl BatchEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add an encoded event to the batch: `[code][payload length][payload]`. The events are restored in the order
+    /// that they were added
+    pub fn push_encoded(&mut self, code: GNSTransactionCode, payload: &[u8]) {
+        self.events.extend(code.dscr_u64().to_le_bytes());
+        self.events.extend((payload.len() as u64).to_le_bytes());
+        self.events.extend(payload);
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Transaction commit payload for a DDL query that has to change several structures at once (like removing indexed
+/// fields, which also drops their indexes). The events are committed as a single record, so a crash can never leave
+/// the GNS with only some of them applied
+pub struct BatchTxn<'a> {
+    events: &'a BatchEvents,
+}
+
+impl<'a> BatchTxn<'a> {
+    pub const fn new(events: &'a BatchEvents) -> Self {
+        Self { events }
+    }
+    pub fn count(&self) -> u64 {
+        self.events.count
+    }
+    pub fn events(&self) -> &[u8] {
+        &self.events.events
+    }
+}
@@ -24,6 +24,8 @@
  *
 */
 
+use crate::engine::core::quota::UserWriteLimits;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct CreateUserTxn<'a> {
     username: &'a str,
@@ -66,6 +68,24 @@ impl<'a> AlterUserTxn<'a> {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AlterUserLimitsTxn<'a> {
+    username: &'a str,
+    limits: UserWriteLimits,
+}
+
+impl<'a> AlterUserLimitsTxn<'a> {
+    pub fn new(username: &'a str, limits: UserWriteLimits) -> Self {
+        Self { username, limits }
+    }
+    pub fn username(&self) -> &str {
+        self.username
+    }
+    pub fn limits(&self) -> UserWriteLimits {
+        self.limits
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct DropUserTxn<'a> {
     username: &'a str,
@@ -80,4 +100,24 @@ impl<'a> DropUserTxn<'a> {
     }
 }
 
-impl_gns_event!(CreateUserTxn<'_> = CreateUser, AlterUserTxn<'_> = AlterUser, DropUserTxn<'_> = DropUser);
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SetReadOnlyTxn {
+    read_only: bool,
+}
+
+impl SetReadOnlyTxn {
+    pub fn new(read_only: bool) -> Self {
+        Self { read_only }
+    }
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+impl_gns_event!(
+    CreateUserTxn<'_> = CreateUser,
+    AlterUserTxn<'_> = AlterUser,
+    DropUserTxn<'_> = DropUser,
+    SetReadOnlyTxn = SetReadOnly,
+    AlterUserLimitsTxn<'_> = AlterUserLimits
+);
@@ -0,0 +1,96 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2024, Sayan Nandan <nandansayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CreateTaskTxn<'a> {
+    task_name: &'a str,
+    schedule: &'a str,
+    payload: &'a [u8],
+    q_window: usize,
+}
+
+impl<'a> CreateTaskTxn<'a> {
+    pub fn new(task_name: &'a str, schedule: &'a str, payload: &'a [u8], q_window: usize) -> Self {
+        Self {
+            task_name,
+            schedule,
+            payload,
+            q_window,
+        }
+    }
+    pub fn task_name(&self) -> &str {
+        self.task_name
+    }
+    pub fn schedule(&self) -> &str {
+        self.schedule
+    }
+    /// The statement's query text followed by its parameters
+    pub fn payload(&self) -> &[u8] {
+        self.payload
+    }
+    /// The length of the query text in [`Self::payload`]
+    pub fn q_window(&self) -> usize {
+        self.q_window
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AlterTaskTxn<'a> {
+    task_name: &'a str,
+    enabled: bool,
+}
+
+impl<'a> AlterTaskTxn<'a> {
+    pub fn new(task_name: &'a str, enabled: bool) -> Self {
+        Self { task_name, enabled }
+    }
+    pub fn task_name(&self) -> &str {
+        self.task_name
+    }
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DropTaskTxn<'a> {
+    task_name: &'a str,
+}
+
+impl<'a> DropTaskTxn<'a> {
+    pub fn new(task_name: &'a str) -> Self {
+        Self { task_name }
+    }
+    pub fn task_name(&self) -> &str {
+        self.task_name
+    }
+}
+
+impl_gns_event!(
+    CreateTaskTxn<'_> = CreateTask,
+    AlterTaskTxn<'_> = AlterTask,
+    DropTaskTxn<'_> = DropTask
+);
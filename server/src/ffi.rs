@@ -0,0 +1,505 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # C API
+//!
+//! A C ABI over the [embedded mode](crate::engine::embedded). The declarations (and docs) are in
+//! `include/skytable.h`.
+//!
+//! Every function returns one of the `SKY_*` status codes. If a query fails, [`sky_execute`] returns
+//! [`SKY_ERR_QUERY`] and the query error code (the same code that the server sends to clients) can be read with
+//! [`sky_last_error`]. A panic in the engine never unwinds into the caller: the function returns [`SKY_ERR_PANIC`]
+//! instead (or a zero value, if it doesn't return a status code)
+
+use {
+    crate::engine::{
+        embedded::{Database, Output, Value},
+        RuntimeResult,
+    },
+    std::{
+        ffi::{c_char, CStr},
+        panic::{self, AssertUnwindSafe},
+        ptr, slice,
+    },
+};
+
+/*
+    status codes
+*/
+
+pub const SKY_OK: i32 = 0;
+/// a null pointer, invalid UTF-8 or an invalid parameter was passed
+pub const SKY_ERR_ARGUMENT: i32 = 1;
+/// the database couldn't be opened (see the logs)
+pub const SKY_ERR_OPEN: i32 = 2;
+/// the query failed (see [`sky_last_error`])
+pub const SKY_ERR_QUERY: i32 = 3;
+/// the row, column or element doesn't exist
+pub const SKY_ERR_RANGE: i32 = 4;
+/// the engine panicked (see the logs); the database should be closed
+pub const SKY_ERR_PANIC: i32 = 5;
+
+/*
+    value types
+*/
+
+pub const SKY_TYPE_NULL: u32 = 0;
+pub const SKY_TYPE_BOOL: u32 = 1;
+pub const SKY_TYPE_UINT: u32 = 2;
+pub const SKY_TYPE_SINT: u32 = 3;
+pub const SKY_TYPE_FLOAT: u32 = 4;
+pub const SKY_TYPE_BINARY: u32 = 5;
+pub const SKY_TYPE_STRING: u32 = 6;
+pub const SKY_TYPE_LIST: u32 = 7;
+pub const SKY_TYPE_DICT: u32 = 8;
+
+#[repr(C)]
+/// A value. Only the fields for the value's type are set; binary and string values (which are not NUL terminated)
+/// are in `ptr` and `len`, and lists and dicts are read with [`sky_value_list_get`] and [`sky_value_dict_get`]
+pub struct SkyValue {
+    pub ty: u32,
+    pub b: bool,
+    pub u: u64,
+    pub i: i64,
+    pub f: f64,
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl SkyValue {
+    fn new(ty: u32) -> Self {
+        Self {
+            ty,
+            b: false,
+            u: 0,
+            i: 0,
+            f: 0.0,
+            ptr: ptr::null(),
+            len: 0,
+        }
+    }
+    fn with_bytes(ty: u32, bytes: &[u8]) -> Self {
+        Self {
+            ptr: bytes.as_ptr(),
+            len: bytes.len(),
+            ..Self::new(ty)
+        }
+    }
+    /// Borrow a result value. The returned value is only valid as long as the result is alive
+    fn borrow(v: &Value) -> Self {
+        match v {
+            Value::Null => Self::new(SKY_TYPE_NULL),
+            Value::Bool(b) => Self {
+                b: *b,
+                ..Self::new(SKY_TYPE_BOOL)
+            },
+            Value::UInt(u) => Self {
+                u: *u,
+                ..Self::new(SKY_TYPE_UINT)
+            },
+            Value::SInt(i) => Self {
+                i: *i,
+                ..Self::new(SKY_TYPE_SINT)
+            },
+            Value::Float(f) => Self {
+                f: *f,
+                ..Self::new(SKY_TYPE_FLOAT)
+            },
+            Value::Binary(b) => Self::with_bytes(SKY_TYPE_BINARY, b),
+            Value::String(s) => Self::with_bytes(SKY_TYPE_STRING, s.as_bytes()),
+            Value::List(l) => Self {
+                ptr: l as *const Vec<Value> as *const u8,
+                len: l.len(),
+                ..Self::new(SKY_TYPE_LIST)
+            },
+            Value::Dict(d) => Self {
+                ptr: d as *const Vec<(String, Value)> as *const u8,
+                len: d.len(),
+                ..Self::new(SKY_TYPE_DICT)
+            },
+        }
+    }
+    /// Copy a parameter. Lists and dicts can't be passed as parameters
+    unsafe fn to_param(&self) -> Option<Value> {
+        let bytes = || -> Option<&[u8]> {
+            match (self.ptr.is_null(), self.len) {
+                (_, 0) => Some(&[]),
+                (true, _) => None,
+                // UNSAFE(@ohsayan): the caller guarantees that ptr points to len bytes
+                (false, len) => Some(unsafe { slice::from_raw_parts(self.ptr, len) }),
+            }
+        };
+        Some(match self.ty {
+            SKY_TYPE_NULL => Value::Null,
+            SKY_TYPE_BOOL => Value::Bool(self.b),
+            SKY_TYPE_UINT => Value::UInt(self.u),
+            SKY_TYPE_SINT => Value::SInt(self.i),
+            SKY_TYPE_FLOAT => Value::Float(self.f),
+            SKY_TYPE_BINARY => Value::Binary(bytes()?.to_vec()),
+            SKY_TYPE_STRING => Value::String(core::str::from_utf8(bytes()?).ok()?.to_owned()),
+            _ => return None,
+        })
+    }
+}
+
+/// An open database
+pub struct SkyDb {
+    db: Database,
+    last_error: u16,
+}
+
+/// The rows returned by a query. A statement that returns a single value returns one row with one column, and one
+/// that returns nothing returns no rows
+pub struct SkyResult {
+    rows: Vec<Vec<Value>>,
+}
+
+/// Run `f`, returning `on_panic` if it panics (unwinding across the C ABI is undefined behavior)
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(ret) => ret,
+        Err(e) => {
+            let msg = e
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| e.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown");
+            error!("the engine panicked: {msg}");
+            on_panic
+        }
+    }
+}
+
+unsafe fn c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+/*
+    database
+*/
+
+#[no_mangle]
+/// Open the database in the directory at `path`. `options` are `options_len` server options (like
+/// `--auth-root-password=...`)
+///
+/// ## Safety
+/// `path` and each of the `options` must be valid C strings and `out` must be valid for writes
+pub unsafe extern "C" fn sky_open(
+    path: *const c_char,
+    options: *const *const c_char,
+    options_len: usize,
+    out: *mut *mut SkyDb,
+) -> i32 {
+    guard(SKY_ERR_PANIC, || {
+        let Some(path) = c_str(path) else {
+            return SKY_ERR_ARGUMENT;
+        };
+        open(|opts| Database::open(path, opts), options, options_len, out)
+    })
+}
+
+#[no_mangle]
+/// Open a database that is kept in memory instead of on disk (see [`Database::open_in_memory`]). `options` are
+/// `options_len` server options, like for [`sky_open`]
+///
+/// ## Safety
+/// Each of the `options` must be a valid C string and `out` must be valid for writes
+pub unsafe extern "C" fn sky_open_in_memory(
+    options: *const *const c_char,
+    options_len: usize,
+    out: *mut *mut SkyDb,
+) -> i32 {
+    guard(SKY_ERR_PANIC, || {
+        open(Database::open_in_memory, options, options_len, out)
+    })
+}
+
+unsafe fn open(
+    f: impl FnOnce(&[&str]) -> RuntimeResult<Database>,
+    options: *const *const c_char,
+    options_len: usize,
+    out: *mut *mut SkyDb,
+) -> i32 {
+    if out.is_null() || (options.is_null() && options_len != 0) {
+        return SKY_ERR_ARGUMENT;
+    }
+    let mut opts = Vec::with_capacity(options_len);
+    for i in 0..options_len {
+        match c_str(*options.add(i)) {
+            Some(opt) => opts.push(opt),
+            None => return SKY_ERR_ARGUMENT,
+        }
+    }
+    match f(&opts) {
+        Ok(db) => {
+            *out = Box::into_raw(Box::new(SkyDb { db, last_error: 0 }));
+            SKY_OK
+        }
+        Err(e) => {
+            error!("failed to open database: {e}");
+            SKY_ERR_OPEN
+        }
+    }
+}
+
+#[no_mangle]
+/// Run a query with `params_len` parameters. On success, the result is written to `out` and must be freed with
+/// [`sky_result_free`]
+///
+/// ## Safety
+/// `db` must have been returned by [`sky_open`], `query` must be a valid C string, `params` must point to
+/// `params_len` values and `out` must be valid for writes
+pub unsafe extern "C" fn sky_execute(
+    db: *mut SkyDb,
+    query: *const c_char,
+    params: *const SkyValue,
+    params_len: usize,
+    out: *mut *mut SkyResult,
+) -> i32 {
+    guard(SKY_ERR_PANIC, || {
+        if db.is_null() || out.is_null() || (params.is_null() && params_len != 0) {
+            return SKY_ERR_ARGUMENT;
+        }
+        let db = &mut *db;
+        let Some(query) = c_str(query) else {
+            return SKY_ERR_ARGUMENT;
+        };
+        let mut values = Vec::with_capacity(params_len);
+        for i in 0..params_len {
+            match (*params.add(i)).to_param() {
+                Some(v) => values.push(v),
+                None => return SKY_ERR_ARGUMENT,
+            }
+        }
+        match db.db.execute(query, &values) {
+            Ok(output) => {
+                db.last_error = 0;
+                let rows = match output {
+                    Output::Empty => vec![],
+                    Output::Value(v) => vec![vec![v]],
+                    Output::Row(row) => vec![row],
+                    Output::Rows(rows) => rows,
+                };
+                *out = Box::into_raw(Box::new(SkyResult { rows }));
+                SKY_OK
+            }
+            Err(e) => {
+                db.last_error = e.value_u8() as u16;
+                SKY_ERR_QUERY
+            }
+        }
+    })
+}
+
+#[no_mangle]
+/// Returns the error code of the last failed query (or 0 if the last query succeeded)
+///
+/// ## Safety
+/// `db` must have been returned by [`sky_open`]
+pub unsafe extern "C" fn sky_last_error(db: *const SkyDb) -> u16 {
+    guard(0, || match db.as_ref() {
+        Some(db) => db.last_error,
+        None => 0,
+    })
+}
+
+#[no_mangle]
+/// Flush all data and close the database
+///
+/// ## Safety
+/// `db` must have been returned by [`sky_open`] and must not be used again
+pub unsafe extern "C" fn sky_close(db: *mut SkyDb) {
+    guard((), || {
+        if !db.is_null() {
+            Box::from_raw(db).db.close()
+        }
+    })
+}
+
+/*
+    results
+*/
+
+#[no_mangle]
+/// Returns the number of rows
+///
+/// ## Safety
+/// `res` must have been returned by [`sky_execute`]
+pub unsafe extern "C" fn sky_result_rows(res: *const SkyResult) -> usize {
+    guard(0, || res.as_ref().map(|res| res.rows.len()).unwrap_or(0))
+}
+
+#[no_mangle]
+/// Returns the number of columns in the given row
+///
+/// ## Safety
+/// `res` must have been returned by [`sky_execute`]
+pub unsafe extern "C" fn sky_result_columns(res: *const SkyResult, row: usize) -> usize {
+    guard(0, || {
+        res.as_ref()
+            .and_then(|res| res.rows.get(row))
+            .map(Vec::len)
+            .unwrap_or(0)
+    })
+}
+
+#[no_mangle]
+/// Read a value. The value is only valid until the result is freed
+///
+/// ## Safety
+/// `res` must have been returned by [`sky_execute`] and `out` must be valid for writes
+pub unsafe extern "C" fn sky_result_get(
+    res: *const SkyResult,
+    row: usize,
+    column: usize,
+    out: *mut SkyValue,
+) -> i32 {
+    guard(SKY_ERR_PANIC, || {
+        let Some(res) = res.as_ref() else {
+            return SKY_ERR_ARGUMENT;
+        };
+        if out.is_null() {
+            return SKY_ERR_ARGUMENT;
+        }
+        match res.rows.get(row).and_then(|row| row.get(column)) {
+            Some(v) => {
+                *out = SkyValue::borrow(v);
+                SKY_OK
+            }
+            None => SKY_ERR_RANGE,
+        }
+    })
+}
+
+#[no_mangle]
+/// Read an element of a list value
+///
+/// ## Safety
+/// `list` must have been read from a result that is still alive and `out` must be valid for writes
+pub unsafe extern "C" fn sky_value_list_get(
+    list: *const SkyValue,
+    index: usize,
+    out: *mut SkyValue,
+) -> i32 {
+    guard(SKY_ERR_PANIC, || {
+        let Some(list) = list.as_ref() else {
+            return SKY_ERR_ARGUMENT;
+        };
+        if list.ty != SKY_TYPE_LIST || out.is_null() {
+            return SKY_ERR_ARGUMENT;
+        }
+        // UNSAFE(@ohsayan): a list value always points to the list in its result
+        let list = &*(list.ptr as *const Vec<Value>);
+        match list.get(index) {
+            Some(v) => {
+                *out = SkyValue::borrow(v);
+                SKY_OK
+            }
+            None => SKY_ERR_RANGE,
+        }
+    })
+}
+
+#[no_mangle]
+/// Read an entry of a dict value. The key is written to `key_out` as a string
+///
+/// ## Safety
+/// `dict` must have been read from a result that is still alive and `key_out` and `value_out` must be valid for
+/// writes
+pub unsafe extern "C" fn sky_value_dict_get(
+    dict: *const SkyValue,
+    index: usize,
+    key_out: *mut SkyValue,
+    value_out: *mut SkyValue,
+) -> i32 {
+    guard(SKY_ERR_PANIC, || {
+        let Some(dict) = dict.as_ref() else {
+            return SKY_ERR_ARGUMENT;
+        };
+        if dict.ty != SKY_TYPE_DICT || key_out.is_null() || value_out.is_null() {
+            return SKY_ERR_ARGUMENT;
+        }
+        // UNSAFE(@ohsayan): a dict value always points to the dict in its result
+        let dict = &*(dict.ptr as *const Vec<(String, Value)>);
+        match dict.get(index) {
+            Some((k, v)) => {
+                *key_out = SkyValue::with_bytes(SKY_TYPE_STRING, k.as_bytes());
+                *value_out = SkyValue::borrow(v);
+                SKY_OK
+            }
+            None => SKY_ERR_RANGE,
+        }
+    })
+}
+
+#[no_mangle]
+/// Free a result
+///
+/// ## Safety
+/// `res` must have been returned by [`sky_execute`] and must not be used again
+pub unsafe extern "C" fn sky_result_free(res: *mut SkyResult) {
+    guard((), || {
+        if !res.is_null() {
+            drop(Box::from_raw(res))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::engine::{embedded, fault},
+    };
+
+    #[test]
+    fn engine_panic_is_an_error_code() {
+        let _lock = embedded::TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        unsafe {
+            let options = [c"--auth-root-password=mypassword12345678".as_ptr()];
+            let mut db = ptr::null_mut();
+            assert_eq!(sky_open_in_memory(options.as_ptr(), 1, &mut db), SKY_OK);
+            let query = c"sysctl report status".as_ptr();
+            let mut res = ptr::null_mut();
+            fault::panic_on_next_query();
+            assert_eq!(
+                sky_execute(db, query, ptr::null(), 0, &mut res),
+                SKY_ERR_PANIC
+            );
+            assert!(res.is_null());
+            // the panic didn't take the database down with it
+            assert_eq!(sky_execute(db, query, ptr::null(), 0, &mut res), SKY_OK);
+            sky_result_free(res);
+            sky_close(db);
+        }
+    }
+}
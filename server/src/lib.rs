@@ -0,0 +1,156 @@
+/*
+ * Created on Thu Jul 02 2020
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2020, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#![forbid(unused_crate_dependencies)]
+#![deny(unused_imports, unused_must_use)]
+#![cfg_attr(feature = "nightly", feature(test))]
+
+//! # Skytable
+//!
+//! The `skyd` crate (or the `server` folder) is Skytable's database server and maybe
+//! is the most important part of the project. There are several modules within this crate; see
+//! the modules for their respective documentation.
+//!
+//! The library holds the whole server: the `skyd` binary just calls [`run`]. It is also built as a C library
+//! (`cdylib`) that runs the engine in [embedded mode](engine::embedded); see [`ffi`] for the C API and
+//! `include/skytable.h` for its declarations
+
+use std::env;
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+pub mod util;
+mod engine;
+pub mod ffi;
+
+#[cfg(feature = "fault-injection")]
+pub use engine::fault;
+
+use libsky::{URL, VERSION};
+
+// the allocator is only set by the binary, so that the C library uses the host's allocator
+#[cfg(all(not(target_env = "msvc"), not(miri)))]
+use jemallocator as _;
+
+/// The terminal art for `!noart` configurations
+const TEXT: &str = "
+███████ ██   ██ ██    ██ ████████  █████  ██████  ██      ███████
+██      ██  ██   ██  ██     ██    ██   ██ ██   ██ ██      ██
+███████ █████     ████      ██    ███████ ██████  ██      █████
+     ██ ██  ██     ██       ██    ██   ██ ██   ██ ██      ██
+███████ ██   ██    ██       ██    ██   ██ ██████  ███████ ███████
+";
+
+type IoResult<T> = std::io::Result<T>;
+const SKY_PID_FILE: &str = ".sky_pid";
+
+/// Run the server (this is the `skyd` binary's entrypoint)
+pub fn run() {
+    use crate::engine::config::ConfigReturn;
+    util::logger::init();
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("check") => exit!(engine::check_data_dir(&args[2..])),
+        Some("config") => exit!(engine::config_command(&args[2..])),
+        Some("decode-gns") => exit!(engine::decode_gns_command(&args[2..])),
+        Some("replay") => match engine::replay_command(&args[2..]) {
+            Ok(cfg) => return self::entrypoint(cfg),
+            Err(code) => exit!(code),
+        },
+        _ => {}
+    }
+    let config = match engine::config::check_configuration() {
+        Ok(cfg) => match cfg {
+            ConfigReturn::Config(cfg) => *cfg,
+            ConfigReturn::HelpMessage(msg) => {
+                exit!(eprintln!("{msg}"), 0x00)
+            }
+        },
+        Err(e) => exit_fatal!(error!("{e}")),
+    };
+    self::entrypoint(config)
+}
+
+fn entrypoint(config: engine::config::Configuration) {
+    println!("{TEXT}\nSkytable v{VERSION} | {URL}\n");
+    let run = || {
+        let f_rt_start = || {
+            engine::set_context_init("locking PID file");
+            let pid_file = util::os::FileLock::new(SKY_PID_FILE)?;
+            engine::set_context_init("initializing runtime");
+            let cpus = util::os::available_cpus();
+            match util::os::cgroup_memory() {
+                Some(mem) => info!(
+                    "detected {cpus} CPU(s) and a memory limit of {} MiB ({} MiB in use)",
+                    mem.limit / (1024 * 1024),
+                    mem.usage / (1024 * 1024)
+                ),
+                None => info!("detected {cpus} CPU(s)"),
+            }
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .thread_name("server")
+                .worker_threads(cpus)
+                .enable_all()
+                .build()?;
+            Ok((pid_file, runtime))
+        };
+        let (pid_file, runtime) = match f_rt_start() {
+            Ok((pf, rt)) => (pf, rt),
+            Err(e) => return (None, None, Err(e)),
+        };
+        let f_glob_init = runtime.block_on(async move {
+            engine::set_context_init("binding system signals");
+            let signal = util::os::TerminationSignal::init()?;
+            let (config, global) = tokio::task::spawn_blocking(|| engine::load_all(config))
+                .await
+                .unwrap()?;
+            engine::RuntimeResult::Ok((signal, config, global))
+        });
+        let (signal, config, global) = match f_glob_init {
+            Ok((sig, cfg, g)) => (sig, cfg, g),
+            Err(e) => return (Some(pid_file), None, Err(e)),
+        };
+        let g = global.global.clone();
+        let result_start =
+            runtime.block_on(async move { engine::start(signal, config, global).await });
+        (Some(pid_file), Some(g), result_start)
+    };
+    let (pid_file, global, result) = run();
+    if let Some(g) = global {
+        info!("cleaning up data");
+        engine::finish(g);
+    }
+    if pid_file.is_some() {
+        if let Err(e) = std::fs::remove_file(SKY_PID_FILE) {
+            error!("failed to remove PID file: {e}");
+        }
+    }
+    match result {
+        Ok(()) => println!("goodbye"),
+        Err(e) => exit_fatal!(error!("{e}")),
+    }
+}
@@ -24,106 +24,17 @@
  *
 */
 
-#![forbid(unused_crate_dependencies)]
 #![deny(unused_imports, unused_must_use)]
-#![cfg_attr(feature = "nightly", feature(test))]
 
 //! # Skytable
 //!
-//! The `skyd` crate (or the `server` folder) is Skytable's database server and maybe
-//! is the most important part of the project. There are several modules within this crate; see
-//! the modules for their respective documentation.
-
-use {env_logger::Builder, std::env};
-
-#[macro_use]
-extern crate log;
-#[macro_use]
-pub mod util;
-mod engine;
-
-use libsky::{URL, VERSION};
+//! The `skyd` binary. The server lives in the library target; see its documentation
 
 #[cfg(all(not(target_env = "msvc"), not(miri)))]
 #[global_allocator]
 /// Jemallocator - this is the default memory allocator for platforms other than msvc
 static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
-/// The terminal art for `!noart` configurations
-const TEXT: &str = "
-███████ ██   ██ ██    ██ ████████  █████  ██████  ██      ███████
-██      ██  ██   ██  ██     ██    ██   ██ ██   ██ ██      ██
-███████ █████     ████      ██    ███████ ██████  ██      █████
-     ██ ██  ██     ██       ██    ██   ██ ██   ██ ██      ██
-███████ ██   ██    ██       ██    ██   ██ ██████  ███████ ███████
-";
-
-type IoResult<T> = std::io::Result<T>;
-const SKY_PID_FILE: &str = ".sky_pid";
-
 fn main() {
-    use crate::engine::config::ConfigReturn;
-    Builder::new()
-        .parse_filters(&env::var("SKY_LOG").unwrap_or_else(|_| "info".to_owned()))
-        .init();
-    let config = match engine::config::check_configuration() {
-        Ok(cfg) => match cfg {
-            ConfigReturn::Config(cfg) => cfg,
-            ConfigReturn::HelpMessage(msg) => {
-                exit!(eprintln!("{msg}"), 0x00)
-            }
-        },
-        Err(e) => exit_fatal!(error!("{e}")),
-    };
-    self::entrypoint(config)
-}
-
-fn entrypoint(config: engine::config::Configuration) {
-    println!("{TEXT}\nSkytable v{VERSION} | {URL}\n");
-    let run = || {
-        let f_rt_start = || {
-            engine::set_context_init("locking PID file");
-            let pid_file = util::os::FileLock::new(SKY_PID_FILE)?;
-            engine::set_context_init("initializing runtime");
-            let runtime = tokio::runtime::Builder::new_multi_thread()
-                .thread_name("server")
-                .enable_all()
-                .build()?;
-            Ok((pid_file, runtime))
-        };
-        let (pid_file, runtime) = match f_rt_start() {
-            Ok((pf, rt)) => (pf, rt),
-            Err(e) => return (None, None, Err(e)),
-        };
-        let f_glob_init = runtime.block_on(async move {
-            engine::set_context_init("binding system signals");
-            let signal = util::os::TerminationSignal::init()?;
-            let (config, global) = tokio::task::spawn_blocking(|| engine::load_all(config))
-                .await
-                .unwrap()?;
-            engine::RuntimeResult::Ok((signal, config, global))
-        });
-        let (signal, config, global) = match f_glob_init {
-            Ok((sig, cfg, g)) => (sig, cfg, g),
-            Err(e) => return (Some(pid_file), None, Err(e)),
-        };
-        let g = global.global.clone();
-        let result_start =
-            runtime.block_on(async move { engine::start(signal, config, global).await });
-        (Some(pid_file), Some(g), result_start)
-    };
-    let (pid_file, global, result) = run();
-    if let Some(g) = global {
-        info!("cleaning up data");
-        engine::finish(g);
-    }
-    if let Some(_) = pid_file {
-        if let Err(e) = std::fs::remove_file(SKY_PID_FILE) {
-            error!("failed to remove PID file: {e}");
-        }
-    }
-    match result {
-        Ok(()) => println!("goodbye"),
-        Err(e) => exit_fatal!(error!("{e}")),
-    }
+    skyd::run()
 }
@@ -0,0 +1,296 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! The server's logger. Logs go to stderr (filtered with `SKY_LOG`) until a log file is configured (`logging.*`),
+//! after which they go to that file instead. The file is rotated once it grows too large or too old: it is renamed
+//! to `<file>.<timestamp>` (and gzipped in the background, if enabled) and rotated files beyond the retention limit
+//! are deleted
+
+use {
+    env_logger::Builder,
+    flate2::{write::GzEncoder, Compression},
+    log::{Log, Metadata, Record},
+    parking_lot::{const_mutex, Mutex},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{self, Write},
+        path::{Path, PathBuf},
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+static FILE: Mutex<Option<LogFile>> = const_mutex(None);
+
+/// Install the logger. Until [`log_to_file`] is called, everything goes to stderr
+pub fn init() {
+    let stderr = Builder::new()
+        .parse_filters(&std::env::var("SKY_LOG").unwrap_or_else(|_| "info".to_owned()))
+        .build();
+    log::set_max_level(stderr.filter());
+    log::set_boxed_logger(Box::new(Logger { stderr })).expect("logger was already set");
+}
+
+/// Send all logs to the file at `path` from now on, rotating it as configured
+pub fn log_to_file(path: &str, rotation: Rotation) -> io::Result<()> {
+    let file = LogFile::open(path.into(), rotation)?;
+    *FILE.lock() = Some(file);
+    Ok(())
+}
+
+struct Logger {
+    stderr: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.enabled(metadata)
+    }
+    fn log(&self, record: &Record) {
+        if !self.stderr.matches(record) {
+            return;
+        }
+        let mut file = FILE.lock();
+        match file.as_mut() {
+            Some(file) => {
+                let line = format!(
+                    "[{} {:<5} {}] {}\n",
+                    chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                );
+                if let Err(e) = file.write(line.as_bytes()) {
+                    // we can't log this, so stderr is the best we can do
+                    eprint!("failed to write to log file: {e}\n{line}");
+                }
+            }
+            None => {
+                drop(file);
+                self.stderr.log(record)
+            }
+        }
+    }
+    fn flush(&self) {
+        self.stderr.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// When and how a log file is rotated
+pub struct Rotation {
+    /// rotate once the file has grown past these many bytes
+    pub max_size: Option<u64>,
+    /// rotate once the file is this old
+    pub max_age: Option<Duration>,
+    /// gzip rotated files
+    pub compress: bool,
+    /// the number of rotated files to keep
+    pub retain: usize,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened: Instant,
+    rotation: Rotation,
+    /// compressing and pruning the last rotated file
+    pending: Option<JoinHandle<()>>,
+}
+
+impl LogFile {
+    fn open(path: PathBuf, rotation: Rotation) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            opened: Instant::now(),
+            rotation,
+            pending: None,
+        })
+    }
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        let due = self
+            .rotation
+            .max_size
+            .is_some_and(|max| self.size + line.len() as u64 > max)
+            | self
+                .rotation
+                .max_age
+                .is_some_and(|max| self.opened.elapsed() >= max);
+        // don't rotate an empty file, even if it's old
+        if due & (self.size != 0) {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+    /// Rotate the file. Compressing and pruning the rotated files happens in the background
+    fn rotate(&mut self) -> io::Result<()> {
+        // one at a time
+        if let Some(pending) = self.pending.take() {
+            let _ = pending.join();
+        }
+        let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut rotated = rotated_path(&self.path, &timestamp);
+        let mut n = 0;
+        while rotated.exists() || gz_path(&rotated).exists() {
+            n += 1;
+            rotated = rotated_path(&self.path, &format!("{timestamp}-{n}"));
+        }
+        fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.opened = Instant::now();
+        let (path, rotation) = (self.path.clone(), self.rotation);
+        let pending = thread::Builder::new()
+            .name("log-rotate".into())
+            .spawn(move || {
+                if rotation.compress {
+                    if let Err(e) = compress(&rotated) {
+                        eprintln!("failed to compress rotated log file {rotated:?}: {e}");
+                    }
+                }
+                if let Err(e) = prune(&path, rotation.retain) {
+                    eprintln!("failed to remove old log files: {e}");
+                }
+            })?;
+        self.pending = Some(pending);
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".");
+    rotated.push(suffix);
+    rotated.into()
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    rotated_path(path, "gz")
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let gz = gz_path(path);
+    let mut enc = GzEncoder::new(File::create(&gz)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut enc)?;
+    enc.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+/// Delete all but the `retain` most recent rotated files of the log file at `path`
+fn prune(path: &Path, retain: usize) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}.",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let mut rotated: Vec<(String, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            // a rotated file is `<file>.<timestamp>[-n][.gz]`
+            let suffix = name.strip_prefix(&prefix)?;
+            let timestamp = suffix.strip_suffix(".gz").unwrap_or(suffix);
+            if !timestamp.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            Some((timestamp.to_owned(), entry.path()))
+        })
+        .collect();
+    if rotated.len() <= retain {
+        return Ok(());
+    }
+    // the timestamps sort chronologically, except that `-n` comes after the first file in a second
+    rotated.sort_by(|(a, _), (b, _)| {
+        let key = |t: &str| {
+            let (ts, n) = t.split_at(t.len().min(15));
+            (
+                ts.to_owned(),
+                n.trim_start_matches('-').parse::<u64>().unwrap_or(0),
+            )
+        };
+        key(a).cmp(&key(b))
+    });
+    let remove = rotated.len() - retain;
+    for (_, path) in rotated.into_iter().take(remove) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn rotate_compress_prune() {
+    let dir = std::env::temp_dir().join(format!("skyd-logger-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    let path = dir.join("skyd.log");
+    let mut file = LogFile::open(
+        path.clone(),
+        Rotation {
+            max_size: Some(16),
+            max_age: None,
+            compress: true,
+            retain: 2,
+        },
+    )
+    .unwrap();
+    file.write(b"first line\n").unwrap();
+    for _ in 0..3 {
+        file.rotate().unwrap();
+        file.write(b"another line\n").unwrap();
+    }
+    // goes over the size limit
+    file.write(b"last line\n").unwrap();
+    file.pending.take().unwrap().join().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"last line\n");
+    let rotated: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name != "skyd.log")
+        .collect();
+    // the first file and one of the others are gone
+    assert_eq!(rotated.len(), 2);
+    for name in rotated {
+        assert!(name.ends_with(".gz"));
+        let mut dec = flate2::read::GzDecoder::new(File::open(dir.join(name)).unwrap());
+        let mut contents = String::new();
+        io::Read::read_to_string(&mut dec, &mut contents).unwrap();
+        assert_eq!(contents, "another line\n");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -27,6 +27,7 @@
 #[macro_use]
 mod macros;
 pub mod compiler;
+pub mod logger;
 pub mod os;
 #[cfg(test)]
 pub mod test_utils;
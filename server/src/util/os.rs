@@ -28,8 +28,10 @@
 pub use unix::*;
 #[cfg(windows)]
 pub use windows::*;
+mod cgroup;
 mod flock;
 mod free_memory;
+mod sd_notify;
 
 use {
     crate::IoResult,
@@ -40,7 +42,12 @@ use {
         time::{SystemTime, UNIX_EPOCH},
     },
 };
-pub use {flock::FileLock, free_memory::free_memory_in_bytes};
+pub use {
+    cgroup::{available_cpus, available_memory_in_bytes, cgroup_memory, CgroupMemory},
+    flock::FileLock,
+    free_memory::free_memory_in_bytes,
+    sd_notify::{sd_notify, sd_watchdog_interval},
+};
 
 #[derive(Debug)]
 #[repr(transparent)]
@@ -54,6 +61,18 @@ impl SysIOError {
     pub fn kind(&self) -> std::io::ErrorKind {
         self.0.kind()
     }
+    /// Returns true if the device is out of space or is failing
+    pub fn is_storage_failure(&self) -> bool {
+        use std::io::ErrorKind;
+        match self.kind() {
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded | ErrorKind::ReadOnlyFilesystem => {
+                true
+            }
+            #[cfg(unix)]
+            _ if self.0.raw_os_error() == Some(libc::EIO) => true,
+            _ => false,
+        }
+    }
 }
 
 impl From<std::io::Error> for SysIOError {
@@ -205,6 +224,37 @@ mod windows {
     }
 }
 
+/// Reserve disk blocks for `len` bytes starting at `offset` without changing the length of the file.
+/// This is a no-op on platforms (and file systems) that don't support it
+pub fn preallocate(file: &fs::File, offset: u64, len: u64) -> IoResult<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+        let ret = unsafe {
+            // UNSAFE(@ohsayan): the fd is valid for as long as we hold the file
+            libc::fallocate(
+                file.as_raw_fd(),
+                libc::FALLOC_FL_KEEP_SIZE,
+                offset as libc::off_t,
+                len as libc::off_t,
+            )
+        };
+        if ret != 0 {
+            let e = std::io::Error::last_os_error();
+            match e.raw_os_error() {
+                // the fs doesn't support it; nothing we can do about it
+                Some(libc::EOPNOTSUPP) | Some(libc::ENOSYS) => {}
+                _ => return Err(e),
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (file, offset, len);
+    }
+    Ok(())
+}
+
 /// Recursively copy files from the given `src` to the provided `dest`
 pub fn recursive_copy(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> IoResult<()> {
     fs::create_dir_all(&dst)?;
@@ -0,0 +1,150 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Container-aware resource detection. When running in a container (or any other cgroup with limits), the host's
+//! free memory and CPU count overstate what we can actually use and going by them will get us OOM killed
+
+use super::free_memory_in_bytes;
+
+/// The memory limit and current usage of our cgroup, in bytes (if we're in a cgroup with a memory limit)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CgroupMemory {
+    pub limit: u64,
+    pub usage: u64,
+}
+
+/// Returns the memory limit for our cgroup, if there is one
+pub fn cgroup_memory() -> Option<CgroupMemory> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::cgroup_memory()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Returns the memory that we can use, in bytes. This is the host's free memory, capped by whatever is left under our
+/// cgroup's memory limit (if any)
+pub fn available_memory_in_bytes() -> u64 {
+    let free = free_memory_in_bytes();
+    match cgroup_memory() {
+        Some(CgroupMemory { limit, usage }) => free.min(limit.saturating_sub(usage)),
+        None => free,
+    }
+}
+
+/// Returns the number of CPUs that we can use. This accounts for CPU affinity and cgroup CPU quotas
+pub fn available_cpus() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(unused))]
+/// Parse a cgroup v2 `memory.max` or a cgroup v1 `memory.limit_in_bytes`. Returns [`None`] if there is no limit
+fn parse_memory_limit(s: &str) -> Option<u64> {
+    // v1 uses a huge page-aligned value (close to i64::MAX) to say "no limit"
+    const V1_UNLIMITED: u64 = 1 << 62;
+    match s.trim() {
+        "max" => None,
+        limit => limit.parse().ok().filter(|limit| *limit < V1_UNLIMITED),
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(unused))]
+/// Find our cgroup v2 path (relative to the cgroup mount) in `/proc/self/cgroup`
+fn parse_v2_cgroup_path(proc_self_cgroup: &str) -> Option<&str> {
+    proc_self_cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::trim)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use {
+        super::{parse_memory_limit, parse_v2_cgroup_path, CgroupMemory},
+        std::{fs, path::Path},
+    };
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+    fn read_u64(path: &Path) -> Option<u64> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+    fn cgroup_v2_memory() -> Option<CgroupMemory> {
+        let proc_self_cgroup = fs::read_to_string("/proc/self/cgroup").ok()?;
+        let cgroup = parse_v2_cgroup_path(&proc_self_cgroup)?;
+        // the limit might be set on any ancestor, so we take the tightest one. also, with cgroup namespaces (most
+        // containers) our cgroup is the root of the mount
+        let mut dir = Path::new(CGROUP_ROOT).join(cgroup.trim_start_matches('/'));
+        if !dir.join("memory.max").is_file() {
+            dir = CGROUP_ROOT.into();
+        }
+        let usage = read_u64(&dir.join("memory.current"));
+        let mut limit = None;
+        let mut current = Some(dir.as_path());
+        while let Some(d) = current.filter(|d| d.starts_with(CGROUP_ROOT)) {
+            if let Some(l) = fs::read_to_string(d.join("memory.max"))
+                .ok()
+                .and_then(|l| parse_memory_limit(&l))
+            {
+                limit = Some(limit.map_or(l, |current: u64| current.min(l)));
+            }
+            current = d.parent();
+        }
+        Some(CgroupMemory {
+            limit: limit?,
+            usage: usage.unwrap_or(0),
+        })
+    }
+    fn cgroup_v1_memory() -> Option<CgroupMemory> {
+        let dir = Path::new(CGROUP_ROOT).join("memory");
+        let limit =
+            parse_memory_limit(&fs::read_to_string(dir.join("memory.limit_in_bytes")).ok()?)?;
+        Some(CgroupMemory {
+            limit,
+            usage: read_u64(&dir.join("memory.usage_in_bytes")).unwrap_or(0),
+        })
+    }
+    pub fn cgroup_memory() -> Option<CgroupMemory> {
+        cgroup_v2_memory().or_else(cgroup_v1_memory)
+    }
+}
+
+#[test]
+fn cgroup_parse() {
+    assert_eq!(parse_memory_limit("max\n"), None);
+    assert_eq!(parse_memory_limit("536870912\n"), Some(536870912));
+    assert_eq!(parse_memory_limit("9223372036854771712"), None);
+    assert_eq!(parse_memory_limit("garbage"), None);
+    assert_eq!(
+        parse_v2_cgroup_path("0::/system.slice/skyd.service\n"),
+        Some("/system.slice/skyd.service")
+    );
+    assert_eq!(
+        parse_v2_cgroup_path("12:memory:/docker/abc\n11:cpu:/docker/abc\n"),
+        None
+    );
+}
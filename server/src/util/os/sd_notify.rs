@@ -0,0 +1,103 @@
+/*
+ * Created on Fri Oct 16 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2023, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Support for the systemd service notification protocol (see `sd_notify(3)`). Everything here is a no-op unless
+//! we were started by a service manager that asked to be notified (that is, `NOTIFY_SOCKET` is set)
+
+use std::time::Duration;
+
+/// Send the given state (for example, `READY=1`) to the service manager. Returns true if a notification was sent
+pub fn sd_notify(state: &str) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::net::UnixDatagram;
+        let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+            return false;
+        };
+        let socket = socket.to_string_lossy();
+        let Ok(sock) = UnixDatagram::unbound() else {
+            return false;
+        };
+        let sent = match socket.strip_prefix('@') {
+            Some(abstract_name) => {
+                // abstract namespace socket
+                use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+                SocketAddr::from_abstract_name(abstract_name.as_bytes())
+                    .and_then(|addr| sock.send_to_addr(state.as_bytes(), &addr))
+            }
+            None => sock.send_to(state.as_bytes(), &*socket),
+        };
+        match sent {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("failed to notify service manager: {e}");
+                false
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = state;
+        false
+    }
+}
+
+/// If the service manager has enabled the watchdog for us, returns how often we should send `WATCHDOG=1` (half the
+/// watchdog timeout, as recommended)
+pub fn sd_watchdog_interval() -> Option<Duration> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    // if set, the watchdog is meant for a specific process
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    if usec == 0 {
+        return None;
+    }
+    Some(Duration::from_micros(usec / 2))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn sd_notify_datagram() {
+    use std::os::unix::net::UnixDatagram;
+    let path = std::env::temp_dir().join(format!("skyd-notify-test-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixDatagram::bind(&path).unwrap();
+    std::env::set_var("NOTIFY_SOCKET", &path);
+    assert!(sd_notify("READY=1"));
+    std::env::remove_var("NOTIFY_SOCKET");
+    let mut buf = [0u8; 16];
+    let n = listener.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"READY=1");
+    std::fs::remove_file(&path).unwrap();
+    // no socket, no notification
+    assert!(!sd_notify("READY=1"));
+}